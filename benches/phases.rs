@@ -0,0 +1,86 @@
+//! Benchmarks timing the parse, optimize, and execute phases of a program separately, such that
+//! changes to the optimizer and JIT can be measured.
+//!
+//! Run with `cargo bench`, or `cargo bench -- mandelbrot` to run only the benchmarks whose name
+//! contains `mandelbrot`. Build with `--features jit` to execute with the JIT.
+
+use std::cell::RefCell;
+use std::io::{self, Cursor};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use bfi::ioctx::{IoCtx, ReadWriteIoCtx};
+use bfi::{optimizer, Cell, ExecutionContext, ExecutionStatus, InterpreterConfig, Program};
+
+
+/// Minimum time spent repeating each benchmark, after running it once to warm up.
+const MEASUREMENT_TIME: Duration = Duration::from_secs(2);
+
+/// Minimum number of times each benchmark is repeated, however long it takes.
+const MIN_ITERATIONS: u32 = 5;
+
+/// Nested loops counting down from 255 at each level, around a multiplication loop that the
+/// optimizer replaces.
+static COUNTING: &str = "-[>-[>-[>-[>+<-]<-]<-]<-]";
+
+
+fn read_example(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples").join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {:?}: {}", path, e))
+}
+
+
+/// Time `f` over repeated calls, printing the mean and fastest time per call.
+fn bench(name: &str, filters: &[String], mut f: impl FnMut()) {
+    if !filters.is_empty() && !filters.iter().any(|filter| name.contains(filter.as_str())) {
+        return;
+    };
+    f();
+    let (mut iterations, mut total, mut fastest) = (0, Duration::default(), Duration::MAX);
+    while iterations < MIN_ITERATIONS || total < MEASUREMENT_TIME {
+        let start = Instant::now();
+        f();
+        let elapsed = start.elapsed();
+        iterations += 1;
+        total += elapsed;
+        fastest = fastest.min(elapsed);
+    }
+    println!(
+        "{:<24} {:>12.3?} per iteration (fastest {:.3?}, {} iterations)",
+        name,
+        total / iterations,
+        fastest,
+        iterations,
+    );
+}
+
+
+/// Execute an already optimized program to completion with no input, discarding its output.
+fn execute<C: Cell>(program: &Program) {
+    let streams = ReadWriteIoCtx::new(Cursor::new(Vec::new()), io::sink());
+    let ictx = RefCell::new(Box::new(streams) as Box<dyn IoCtx>);
+    let config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+    let status = ExecutionContext::<C>::with_program(ictx.borrow_mut(), program.clone())
+        .with_config(config)
+        .execute();
+    assert_eq!(status, ExecutionStatus::Terminated);
+}
+
+
+/// Benchmark each phase of the program `source` on a tape of cells of type `C`.
+fn bench_phases<C: Cell>(name: &str, source: &str, filters: &[String]) {
+    bench(&format!("{}/parse", name), filters, || { Program::parse(source); });
+    let program = Program::parse(source);
+    bench(&format!("{}/optimize", name), filters, || { optimizer::optimize(&program); });
+    let optimized = optimizer::optimize(&program);
+    bench(&format!("{}/execute", name), filters, || execute::<C>(&optimized));
+}
+
+
+fn main() {
+    // `cargo bench` passes `--bench`, while any other arguments select benchmarks to run
+    let filters: Vec<String> = std::env::args().skip(1).filter(|arg| !arg.starts_with('-')).collect();
+    bench_phases::<u16>("mandelbrot", &read_example("mandelbrot.b"), &filters);
+    bench_phases::<u8>("hanoi", &read_example("hanoi.b"), &filters);
+    bench_phases::<u8>("counting", COUNTING, &filters);
+}
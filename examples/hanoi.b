@@ -0,0 +1,222 @@
+Towers of Hanoi
+
+Prints the moves solving the Towers of Hanoi for ten disks from peg A to peg C
+as one line per move in the form Move disk 1 from A to C
+
++[>+[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-
+<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<<<<<<<<<[->>
+>>>>>>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<
+<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<<<<<<<<[-]>>>>
+>>>>>>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<<<<<<<<<<+<[-]>>>>>>>>>>>>>>>>>>>>>++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.++++++++
+++++++++++++++++++++++++++.+++++++.-----------------.---------------------------
+------------------------------------------.+++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++.+++++.++++++++++.--------.----------------------
+-----------------------------------------------------.+++++++++++++++++.--------
+---------.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+.++++++++++++.---.--.-----------------------------------------------------------
+------------------.[-]<<<<<<<<<<[->>>>>>>>>>+>>>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[
+-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++.[-]<<<<<<<<<<+[->>>>>>>>>>>>>>+>+>+<<<<<<<<<<<<<<<<]>>>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>[-<<
+<<+>>>>]<[[-]<->]<[<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>
+>]<[[-]<->]<[<<<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>>>>-]<<[-]>[-]<<<<<+++++++++++++++
++++++++++++++++++.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++.-----.---------------------------------------------------
+----------------------------.[-]<<<<<<<<<<[->>>>>>>>>>+>>>>+<<<<<<<<<<<<<<]>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<
+[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<
+<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>
+>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<
+<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>
+>>>>>>>-<]>[-<<<<<<<<<<<<<<<<<<<<<<+<<[-]>>>>>>>>>>>>>>>>>>>>>++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.++++++++++++++++++++
+++++++++++++++.+++++++.-----------------.---------------------------------------
+------------------------------.+++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++.+++++.++++++++++.--------.----------------------------------
+-----------------------------------------.++++++++++++++++++.------------------.
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++++++
++++.---.--.---------------------------------------------------------------------
+--------.[-]<<<<<<<<<[->>>>>>>>>+>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<
++>>>>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++.[-]<<<<<<<<<++[->>>>>>>>>>>>>+>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<
+<<<<<<<+>>>>>>>>>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<
+<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<<<<<<<<
+<<<<<[-]+>>>>>>>>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++++++++++++.+++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.--
+---.----------------------------------------------------------------------------
+---.[-]<<<<<<<<<[->>>>>>>>>+>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>
+>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.
+[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<
+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>
+>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<
+<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>]<<
+[[-]<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<<<<<<<<+<<<[
+-]>>>>>>>>>>>>>>>>>>>>>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++.++++++++++++++++++++++++++++++++++.+++++++.----------------
+-.---------------------------------------------------------------------.++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++.++++++++++.--
+------.-------------------------------------------------------------------------
+--.+++++++++++++++++++.-------------------.+++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++.++++++++++++.---.--.--------------------------
+---------------------------------------------------.[-]<<<<<<<<[->>>>>>>>+>>>>+<
+<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++.[-]<<<<<<<<+[->>>>>>>>>>>>+>+>+<<<<<<<
+<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<--->---->+<<[->>>+>+<<<<
+]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<
+<<+>>>]<[[-]<->]<[<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++
+++++++++++++++++++.+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++.-----.--------------------------------------------------
+-----------------------------.[-]<<<<<<<<[->>>>>>>>+>>>>+<<<<<<<<<<<<]>>>>>>>>>>
+>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>
+>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<
+<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>+
+>>+<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>
+>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<
+<<<<<<+<<<<[-]>>>>>>>>>>>>>>>>>>>>>+++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++.++++++++++++++++++++++++++++++++++.+++++++.----
+-------------.------------------------------------------------------------------
+---.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++.+
++++++++++.--------.-------------------------------------------------------------
+--------------.++++++++++++++++++++.--------------------.+++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++.++++++++++++.---.--.------------
+-----------------------------------------------------------------.[-]<<<<<<<[->>
+>>>>>+>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<<<<<<++[->>>>>>>>>>>+>+>+
+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<--->---->+<<[->>>+>+<
+<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<<<<<<<<<<<[-]>>>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-
+<<<+>>>]<[[-]<->]<[<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>-]<<[-]>[-]<<<<<+++++++++++++++
++++++++++++++++++.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++.-----.---------------------------------------------------
+----------------------------.[-]<<<<<<<[->>>>>>>+>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<
+<<<<<<<<<<+>>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>
+>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<
+<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>+>>+<<<<<<
+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>]<
+<[[-]<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<<<<<<+<<<<<[-]>
+>>>>>>>>>>>>>>>>>>>>++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++.++++++++++++++++++++++++++++++++++.+++++++.-----------------.-
+--------------------------------------------------------------------.+++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++.++++++++++.-----
+---.---------------------------------------------------------------------------.
++++++++++++++++++++++.---------------------.++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++.++++++++++++.---.--.-------------------------
+----------------------------------------------------.[-]<<<<<<[->>>>>>+>>>>+<<<<
+<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++.[-]<<<<<<+[->>>>>>>>>>+>+>+<<<<<<<<<<<<]>>>>>>>>>
+>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<
+->]<[<<<<<<<<<<<<[-]>>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<<<<<<<
+<<<[-]+>>>>>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++++++++++++.++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.-----.-
+------------------------------------------------------------------------------.[
+-]<<<<<<[->>>>>>+>>>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<+++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]++++++++++.[-]>>>]<<
+[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>
+>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<
+<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<
+<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>
+>-<]>[-<<<<<<<<<<<<<<<<<<+<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++.++++++++++++++++++++++++++
+++++++++.+++++++.-----------------.---------------------------------------------
+------------------------.+++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++.+++++.++++++++++.--------.----------------------------------------
+-----------------------------------.++++++++++++++++++++++.---------------------
+-.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++++
++++++.---.--.-------------------------------------------------------------------
+----------.[-]<<<<<[->>>>>+>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<+++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<<<<++[->>>>>
+>>>>+>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<--->---->+<<[->>>+>+
+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<<<<<<<<<[-]>>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<
++>>>]<[[-]<->]<[<<<<<<<<<<<[-]+>>>>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++
+++++++++++.+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++.-----.----------------------------------------------------------
+---------------------.[-]<<<<<[->>>>>+>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>
+>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]+++
++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<
+<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>
+>>>>>>]<[>>+<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>
+>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<<[-]>>>>>>>
+>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<<<<+<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>+++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++++++++++++++++
++++++++++++++++++.+++++++.-----------------.------------------------------------
+---------------------------------.++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++.+++++.++++++++++.--------.-------------------------------
+--------------------------------------------.+++++++++++++++++++++++.-----------
+------------.+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++.++++++++++++.---.--.--------------------------------------------------------
+---------------------.[-]<<<<[->>>>+>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<
+<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<<<+[->>
+>>>>>>+>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<--->---->+<<[->>>+>+<<
+<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<<<<<<<<[-]>>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>
+]<[[-]<->]<[<<<<<<<<<<[-]+>>>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++++++++
+++++.+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++.-----.----------------------------------------------------------------
+---------------.[-]<<<<[->>>>+>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]++++++++++.[-]>
+>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<
+<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+
+<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-<<<<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>-<]>[-<<<<
+<<<<<<<<<<<<+<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>+++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++.++++++++++++++++++++++++++++++++++.++
++++++.-----------------.--------------------------------------------------------
+-------------.++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++.+++++.++++++++++.--------.---------------------------------------------------
+------------------------.++++++++++++++++++++++++.------------------------.+++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.++++++++++++.-
+--.--.--------------------------------------------------------------------------
+---.[-]<<<[->>>+>>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++.[-]<<<++[->>>>>>>+>+>+<<<<<<<<<]>>>>
+>>>>>[-<<<<<<<<<+>>>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<
+[<<<<<<<<<[-]>>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<<<<<<<[-]+>>>>>>
+>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++++++++++++.++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++.-----.-----------------
+--------------------------------------------------------------.[-]<<<[->>>+>>>>+
+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>
+>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>>+<<<<
+<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<[[-]<<<<<<<<<
+<<<<<[-]>>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<<+<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.++++
+++++++++++++++++++++++++++++++.+++++++.-----------------.-----------------------
+----------------------------------------------.+++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++.+++++.++++++++++.--------.------------------
+---------------------------------------------------------.++++++++++++++++++++++
++++.-------------------------.++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++.++++++++++++.---.--.---------------------------------------
+--------------------------------------.[-]<<[->>+>>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>
+>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<+[
+->>>>>>+>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>
+[-<<<<+>>>>]<[[-]<->]<[<<<<<<<<[-]>>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<
+[<<<<<<<<[-]+>>>>>>>>-]<<[-]>[-]<<<<<++++++++++++++++++++++++++++++++.++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.-----
+.-------------------------------------------------------------------------------
+.[-]<<[->>+>>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<<<+++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<
+<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-
+<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>]<[>>+<<<<<<<<<<<<<<[->>>>>>>>>>>
+>>+>>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<[[-]<<<
+<<<<<<<<<<[-]>>>>>>>>>>>>>>-<]>[-<<<<<<<<<<<<<<+<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>
+>>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.
+++++++++++++++++++++++++++++++++++.+++++++.-----------------.-------------------
+--------------------------------------------------.+++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++.+++++.++++++++++.--------.--------------
+-------------------------------------------------------------.+++++++++++++++++.
+-.----------------.+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++.++++++++++++.---.--.--------------------------------------------------
+---------------------------.[-]<[->+>>>>+<<<<<]>>>>>[-<<<<<+>>>>>]<<<<++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<++[->>>>>+>+>+<<<<<<
+<]>>>>>>>[-<<<<<<<+>>>>>>>]<<--->---->+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<
+[<<<<<<<[-]>>>>>>>-]+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<<<<<[-]+>>>>>>>-]<<[
+-]>[-]<<<<<++++++++++++++++++++++++++++++++.++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++.-----.-------------------------
+------------------------------------------------------.[-]<[->+>>>>+<<<<<]>>>>>[
+-<<<<<+>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++.[-]++++++++++.[-]>>>]<<[-]]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+>+<
+<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>
+>>>>>>>>>>>>>>>]<[<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<
+<<<<<<<<<<<<<[-]<]
@@ -0,0 +1,5 @@
+Hello World
+
+Prints Hello World followed by an exclamation mark and a newline
+
+++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.
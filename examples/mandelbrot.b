@@ -0,0 +1,472 @@
+Mandelbrot
+
+Draws the Mandelbrot set as a grid of forty by twenty one characters using
+fixed point arithmetic with a scale of sixteen and thirteen iterations per point
+Requires cells of at least sixteen bits e g run with the cell size option set to 16
+
+>--------------------<+++++++++++++++++++++[->>>[-]-----------------------------
+---<[-]++++++++++++++++++++++++++++++++++++++++[->>[-]++++++++++++++++++++++++++
+++++++>[-]++++++++++++++++++++++++++++++++>>>[-]+>[-]+++++++++++++++++++++++++++
++++++<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>
+>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[
+-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<
+<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<
+<<<<<<<+>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-
+<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>
+>>>>]<]<<<<<<<<[->>>>>>>-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<
+<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<
+<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>+++++++++++++
++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+
+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<
+<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<
+<<<<<<-------------------------------->>>>>>>>>+++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<
+<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<
+<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<
+<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>
+>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]
+>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<
+<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<------------------------
+------------------------------------------------------------------------>>>>>[-]
+<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-
+]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>
+[-<<<<<<<<+>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]
+<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<
+[[-]<->]<[<<<++++++++++++++>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>
+[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>+
++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[
+->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]
+>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<+++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<
+[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<
+<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>----------------------
+---------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>
+>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>
+>>>]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+
+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-
+]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>
+>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<-------------------------------->>>>>>>>
+>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>
+>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<
+<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>
+>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>
+>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<
+<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>
+]<<<<<<<<<----------------------------------------------------------------------
+-------------------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<
+<<<+>>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-
+<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<
+<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[
+->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]
+]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<-->>>-]<[-]]<<<[->>>+>+<<<<]>>>>[
+-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>
+>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>
+>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[-
+>>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<+
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<
+<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>
+-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[-
+>>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>
+>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++++++>+<<<
+<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-
+<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>
+>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<------------------
+-------------->>>>>>>>>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<
+<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<
+<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+
+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<
+<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+
+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<
+<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<------------------------------------------------
+------------------------------------------------>>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<
+<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]
+<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<
+<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<
+<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<+++++++++++
++++>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<
+<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>
+>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>
+>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]
+>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>
+>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<
+<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------------------------->+<<<<<<<<]>>>
+>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>
+]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>+++
++++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>
+>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>
+>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>
+>>>>>]<<<<<<<<<<-------------------------------->>>>>>>>>+++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>
+>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>------------
+---->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<
+<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>
++>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+
+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<--------------
+--------------------------------------------------------------------------------
+-->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>
+]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<
+<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[
+-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<
+<<<+>>>>]<[[-]<->]<[<<<+>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]
+<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[
+->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>
+>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+
+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<[->
+>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<
+<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------------------
+------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>
+>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>
+]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>
+>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[
+-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>
+>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<-------------------------------->>>>>>>>>++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>
+>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<
+[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>-
+--------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++
+<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<
+<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<
+<<<<<<<-------------------------------------------------------------------------
+----------------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<
++>>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[-
+>>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<
+<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+
+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<
+<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<-------------->>>-]<[-]]<<<[->>>+>+<
+<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<
+<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<
+<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>
+>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>
+>>>]<<<<++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>
+>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<
+[->>>>>>>-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]
+<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<
+<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++
+++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>
++>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->
++>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<---------
+----------------------->>>>>>>>>++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+
+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>
+>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-
+<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>
+>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<
+<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>
+>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<---------------------------------------
+--------------------------------------------------------->>>>>[-]<<<<<<[->>>>>>>
+>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]+++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>
+>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>
+>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<
+<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<
+<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<++
+++++++++++++++>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>
+>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>
+[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<
+<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>
+]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+
+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>
+>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------------------------->+<
+<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<
+<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<
+[->>>>>>++++++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<
+<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<
+<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<
+<<<<+>>>>>>>>>>>]<<<<<<<<<<-------------------------------->>>>>>>>>++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<
+<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-
+--------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>-----------
+----->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>
+]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>
++>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<---
+--------------------------------------------------------------------------------
+------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>
+]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>
+>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>
+>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<
+<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<
+<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<------------------>>>-]<[-]]<<<[->>>+>+<<<<]>>
+>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>
+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+
+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<
++[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<
+<<++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<
+[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>
+>>>-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<
+<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<
+]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++++++>+
+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[
++[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>
+>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<---------------
+----------------->>>>>>>>>++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<
+<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<
+<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<
+<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<
+<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<
+<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<
+<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<---------------------------------------------
+--------------------------------------------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<
+<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]+++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+
+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>
+[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[
+-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<->>>-]<[
+-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>
+>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[
+-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<
+<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<
+<<<<<<<+>>>>>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-
+<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>
+>>>>]<]<<<<<<<<[->>>>>>>-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<
+<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<
+<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>+++++++++++++
++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+
+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<
+<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<
+<<<<<<-------------------------------->>>>>>>>>+++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<
+<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<
+<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<
+<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>
+>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]
+>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<
+<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<------------------------
+------------------------------------------------------------------------>>>>>[-]
+<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-
+]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>
+[-<<<<<<<<+>>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]
+<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<
+[[-]<->]<[<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<
+<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>
+]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>
+>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>
+[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>
+>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<
+<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------------------------->+<<<<<<<<]>>>>
+>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]
+<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>++++
+++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>
+>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>
+>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>
+>>>>]<<<<<<<<<<-------------------------------->>>>>>>>>++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>
+>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------
+--->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<
+<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+
+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<
+<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<---------------
+--------------------------------------------------------------------------------
+->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]+++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]
+<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<
+]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-
+]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<
+<<+>>>>]<[[-]<->]<[<<<----------------------------------------------------------
+------------------>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->
+>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+
+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+
+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]
+>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<<[->>>>>>>
+>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>
+>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>-------------------------------
+->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<
+<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<
+<<<<[->>>>>>++++++++++++++++++++++++++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<
+<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<
+<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<
+<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<-------------------------------->>>>>>>>>++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<
+[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>
+>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>-------
+--------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[
+>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[-
+>>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<
+<-------------------------------------------------------------------------------
+----------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>
+>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<
+<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>
+>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>
+>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>
++>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<++>>>-]<[-]]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>
+>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<
++[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<+>>>>>>>>>>>]
+<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<+[->>+<<]>>>
+++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<[-
+>>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------
+----------------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>>+>
++<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>+<<<<<<<<]>>>>>>>>[-<
+<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>++++++++++++++++++++++++++++++++>+<<<<<<<]>>>>
+>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->-[>+>>]>[+[-<+>]>+>>]
+<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[->+>>>>>>>>>>+<<<<<<
+<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<---------------------------
+----->>>>>>>>>++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>
+>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>
+>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]
+<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>>
+>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<+>>>>>>>>>
+>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+
+>>>>>>>>>>>>>]<<<<<<<<<---------------------------------------------------------
+--------------------------------------->>>>>[-]<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>
+>>>>>[-<<<<<<<<<+>>>>>>>>>]+++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+
+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]+++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[
+<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<[<
+<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<<+>>>-]<[-]]<<<[->>>+
+>+<<<<]>>>>[-<<<<+>>>>]<[<<<<<[-]>[-]<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<
+<<<<<<<+>>>>>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<
+<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>
+>>>>>>]<<+[->>+<<]>>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<+>>>>
+>>>>>>]<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>
+>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<
+<<<[->>>>>>>-------------------------------->+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>
+>>]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<[->>>>>>->>
++<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<]<<<<<<<[->>>>>>+++++++++++++++++++++++++
++++++++>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<[-]>>>>>>>>>[->+<]>>++++<[->
+-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<<
+[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<------
+-------------------------->>>>>>>>>+++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<<<<<<[->>>>>
+>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<<<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>
+>>>>>[-<<<<<<<<<+>>>>>>>>>]<]<<<<<<<<[->>>>>>>---------------->+<<<<<<<<]>>>>>>>
+>[-<<<<<<<<+>>>>>>>>]<<<<<<<[->>>>>>---------------->+<<<<<<<]>>>>>>>[-<<<<<<<+>
+>>>>>>]<<<<<<<<<[-]>>>>>>>>[->+<]>>++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>[-]>[-<
+<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>
+>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<------------------------------------
+------------------------------------------------------------>>>>>[-]<<<<<<[->>>>
+>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++[-<[->>+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<
+<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>
+>>>>>>>]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[-<[->>+
+>+<<<]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<[-]+>>>[-]]<[-]<<[->>+>
++<<<]>>>[-<<<+>>>]<[<<<<[-]>>>>[-]]+<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[[-]<->]<[<<
+<++++++++++++++++++++++++++>>>-]<[-]]<<.<<<<<<+<]>>>>>>>>>++++++++++.[-]<<<<<<<<
+<<++<]
@@ -26,7 +26,8 @@ class BfBindings(object):
         lib_stub = "%s.%s" % (self.LIBNAME, extension)
         lib_file = path.join(this_file_directory, "..", "..", "target", "release", lib_stub)
         if not path.exists(lib_file):
-            raise FileNotFoundError("missing %s, have you run `cargo build --release`?" % lib_stub)
+            raise FileNotFoundError(
+                "missing %s, have you run `cargo build --release --workspace`?" % lib_stub)
         self.lib = CDLL(lib_file)
         self._declare_funtypes()
         self._pool = ThreadPoolExecutor(max_workers=1, thread_name_prefix=self.LIBNAME)
@@ -0,0 +1,19 @@
+ROT13
+
+Reads text until the end of input and writes it back with every ASCII letter
+rotated by thirteen places in the alphabet while other bytes pass through as is
+Works whether the end of input leaves the cell unchanged or sets it to zero
+
+[-],[[->+>>>>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]+++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++[-<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<
++>>>>>>>>]<[<<<<<<<->>>>>>>[-]]<]<<<<<<[->+>>>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]++
+++++++++++++++++++++++++[-<<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<[<<<
+<<<->>>>>>[-]]<]<<<<<[->+>>>>+<<<<<]>>>>>[-<<<<<+>>>>>]++++++[-<<<<[->>>>>+>+<<<
+<<<]>>>>>>[-<<<<<<+>>>>>>]<[<<<<<->>>>>[-]]<]<<<<[->+>>>+<<<<]>>>>[-<<<<+>>>>]++
+++++++++++++++++++++++++[-<<<[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]<[<<<<->>>>[-]]<]
++<<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<[[-]<->]<[<<<<<<[->>>>+>+>>>+
+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<-]+<<<[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]<
+[[-]<->]<[<<<<[->>+>+>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<-]<[->+>+<<]>>[-<<+>>]<[
+<<<<<<<+++++++++++++>>>>>[->>>+>+<<<<]>>>>[-<<<<+>>>>]+++++++++++++[-<[->>+>+<<<
+]>>>[-<<<+>>>]<[<<->>[-]]<]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<----------------------
+---->>>>>>>>>[-]]<[-]<[-]]<<<<<<[-]>[-]>[-]>[-]>[-]>[-]<<<<<<.[-],]
@@ -0,0 +1,36 @@
+Self interpreter
+
+A BrainF*ck interpreter written in BrainF*ck
+Reads a program followed by an exclamation mark then runs the program with the
+rest of the input as its own input in the manner of the well known dbfi by
+Daniel B Cristofani although it is a separate and much less compact program
+Ends when the program does and expects the end of input to leave zero in the cell
+
+>>>>>>>><<<<<<+[>[-],>+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<[-]>>-]<-----------
+---------------------->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<[-]>>-]<----------
+>+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<+>>>-]<->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-
+]<->]<[<<<++++++++>>>-]<->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<++>>>-]<->+<[-
+>>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<+++++++>>>-]<-------------->+<[->>+>+<<<]>>>
+[-<<<+>>>]<[[-]<->]<[<<<+++>>>-]<-->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<++++
+>>>-]<----------------------------->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<++++
++>>>-]<-->+<[->>+>+<<<]>>>[-<<<+>>>]<[[-]<->]<[<<<++++++>>>-]<[-]<<[->>>+>+<<<<]
+>>>>[-<<<<+>>>>]<[[-]<<<<+>>[->>>>>>>>+<<<<<<<<]>>>>>>>>>>]<<]<<<<<<<<<<[<<<<<<<
+<]>>>>>>>>->[[->>+>+<<<]>>>[-<<<+>>>]+<[-[-[-[-[-[-[-[-[->-<[-]]>[-<<<<>>>>>>>>[
+>>>>>>>>]>>>>>>>>[>>>>>>>>]>,<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>>>]<]>[-<<<<
+>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>.<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>>>]
+<]>[-<<<<>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]
+<[<<<+>>>[-]]<<<[[-]<<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>+<<>>>>>>>>[>>>>>>>>
+]>>>>>>>>[>>>>>>>>]>>]<<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>[<<+>>[-<<<<<<<<+>
+>>>>>>>]<<<<<<<<<<->[->>+>>+<<<<]>>>>[-<<<<+>>>>]+<<[-[-[-[-[-[-[-[-[->>-<<[-]]>
+>[-]<<]>>[-]<<]>>[-<<<+>>>]<<]>>[-<<<->>>]<<]>>[-]<<]>>[-]<<]>>[-]<<]>>[-]<<]>>[
+-]<<<]>>]<]>[-<<<<>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>>>>>+<<<<[->>>>>+>+<<<<<<
+]>>>>>>[-<<<<<<+>>>>>>]<[[-]<->]<[<<<+>>>-]<<<[[-]<<<<<<<<<<[<<<<<<<<]<<<<<<<<[<
+<<<<<<<]>>+<<>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>>]<<<<<<<<<<[<<<<<<<<]<<<<<<<<
+[<<<<<<<<]>>[<<+>>[->>>>>>>>+<<<<<<<<]>>>>>>>><<->[->>+>>+<<<<]>>>>[-<<<<+>>>>]+
+<<[-[-[-[-[-[-[-[-[->>-<<[-]]>>[-]<<]>>[-]<<]>>[-<<<->>>]<<]>>[-<<<+>>>]<<]>>[-]
+<<]>>[-]<<]>>[-]<<]>>[-]<<]>>[-]<<<]>>]<]>[-<<<<>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>
+>>>]+>>>>>>>><<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>>>]<]>[-<<<<>>>>>>>>[>>>>>>>>
+]>>>>>>>>[>>>>>>>>]<<<<<<<<-<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>>>]<]>[-<<<<>>
+>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>-<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<<]>>>>]<]
+>[-<<<<>>>>>>>>[>>>>>>>>]>>>>>>>>[>>>>>>>>]>+<<<<<<<<<[<<<<<<<<]<<<<<<<<[<<<<<<<
+<]>>>>]<]>[-]<<<<+>>>>>>>>->]
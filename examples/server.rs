@@ -3,7 +3,7 @@ extern crate zmq;
 
 use std::io::Write;
 use std::cell::RefCell;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error};
 
 use bfi::{ioctx, interpreter};
 
@@ -26,7 +26,7 @@ impl ZmqRepServerIoCtx {
         let address: String = format!("{}://{}:{}", protocol, address, port);
         socket.bind(address.as_str()).unwrap();
         Self {
-            socket: socket,
+            socket,
             input_buffer: Vec::new(),
             status: SocketStatus::Readable,
         }
@@ -44,7 +44,7 @@ impl ioctx::IoCtx for ZmqRepServerIoCtx {
             },
             // dig into our store of previously received bytes that have yet to be consumed
             (_, SocketStatus::Writable) => {
-                buf.write(&self.input_buffer[..1])?;
+                buf.write_all(&self.input_buffer[..1])?;
                 self.input_buffer.remove(0);
                 Ok(1)
             },
@@ -52,19 +52,19 @@ impl ioctx::IoCtx for ZmqRepServerIoCtx {
                 match self.socket.recv_bytes(flags) {
                     // empty packet received, report zero bytes read and have faith that this is
                     // handled properly by the bf program (it is)
-                    Ok(v) if v.len() == 0 => {
+                    Ok(v) if v.is_empty() => {
                         self.status = SocketStatus::Writable;
                         Ok(0)
                     },
                     // write the first byte to the buffer, store the rest for later access
                     Ok(bytes_received) => {
-                        buf.write(&bytes_received[..1])?;
+                        buf.write_all(&bytes_received[..1])?;
                         self.input_buffer.extend_from_slice(&bytes_received[1..]);
                         self.status = SocketStatus::Writable;
                         Ok(1)
                     }
                     // bad news if we reach here
-                    Err(e) => Err(Error::new(ErrorKind::Other, format!("{:?}", e))),
+                    Err(e) => Err(Error::other(format!("{:?}", e))),
                 }
             },
         }
@@ -93,11 +93,11 @@ impl ioctx::IoCtx for ZmqRepServerIoCtx {
                         Ok(buf.len())
                     },
                     // fatal
-                    Err(e) => Err(io::Error::new(io::ErrorKind::Other, format!("{:?}", e))),
+                    Err(e) => Err(io::Error::other(format!("{:?}", e))),
                 }
             },
             // shouldn't occur due to the simplicity of the bf program and protections in read_input
-            SocketStatus::Readable => Err(Error::new(ErrorKind::Other, "unable to write")),
+            SocketStatus::Readable => Err(Error::other("unable to write")),
         }
     }
 
@@ -0,0 +1,7 @@
+//! The `libbfi` shared library exporting the C interface of `bfi::ffi`, declared for C in
+//! `include/bfi.h`.
+//!
+//! Kept apart from `bfi` itself, which is only built as a Rust library, as a shared library needs
+//! `std` for its allocator and panic handler while `bfi` also builds without it.
+
+pub use bfi_core::ffi::*;
@@ -0,0 +1,60 @@
+//! Fuzz target executing arbitrary programs with and without optimization, see
+//! `tests/properties.rs` for the properties checked.
+//!
+//! Run with `cargo +nightly fuzz run execute` from the root of the repository.
+
+#![no_main]
+
+use std::cell::RefCell;
+
+use libfuzzer_sys::fuzz_target;
+
+use bfi::ioctx::{InMemoryIoCtx, IoCtx};
+use bfi::{ExecutionContext, ExecutionStatus, InterpreterConfig, TapeMode};
+
+
+const MAX_MEMORY: usize = 1024;
+
+
+fn run(program: &str, input: &[u8], optimize: bool) -> (ExecutionStatus<bfi::BfError>, Vec<u8>) {
+    let config = InterpreterConfig {
+        tape_mode: TapeMode::Growable,
+        optimize,
+        max_steps: Some(100_000),
+        max_memory: Some(MAX_MEMORY),
+        ..InterpreterConfig::default()
+    };
+    let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    ictx.borrow_mut().write_input(input).unwrap();
+    let status = {
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), program).with_config(config);
+        let status = ectx.execute();
+        assert!(ectx.tape().len() <= MAX_MEMORY, "tape grew past the limit");
+        status
+    };
+    let mut output = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Ok(n) = ictx.borrow_mut().read_output(&mut buf) {
+        if n == 0 { break };
+        output.extend_from_slice(&buf[..n]);
+    }
+    (status, output)
+}
+
+
+// the program runs up to the first NUL byte, with the bytes after it as input
+fuzz_target!(|data: &[u8]| {
+    let (program, input) = match data.iter().position(|&b| b == 0) {
+        Some(i) => (&data[..i], &data[i + 1..]),
+        None => (data, &[][..]),
+    };
+    let program = String::from_utf8_lossy(program);
+    let (expected_status, expected_output) = run(&program, input, false);
+    let (status, output) = run(&program, input, true);
+    assert!(!matches!(expected_status, ExecutionStatus::InternalError(_)));
+    assert!(!matches!(status, ExecutionStatus::InternalError(_)));
+    let limited = |s: &ExecutionStatus<_>| matches!(s, ExecutionStatus::LimitExceeded(_));
+    if !limited(&expected_status) && !limited(&status) {
+        assert_eq!(expected_output, output);
+    };
+});
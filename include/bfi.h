@@ -0,0 +1,71 @@
+/*
+ * C interface to the bfi BrainF*ck interpreter, implemented in src/ffi.rs.
+ *
+ * Link against the libbfi shared library built by `cargo build --release --workspace` (or
+ * `-p bfi-ffi` for the library alone). Output is returned in memory owned by the library, to be
+ * handed back to `bf_free` once no longer needed.
+ */
+
+#ifndef BFI_H
+#define BFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Outcome of `bf_run` and `bf_step`, numbered like the exit status of the bfi executable. */
+typedef enum {
+    BF_OK = 0,
+    BF_PROGRAM_ERROR = 1,
+    BF_INVALID_ARGUMENT = 2,
+    BF_PARSE_ERROR = 3,
+    BF_LIMIT_EXCEEDED = 4,
+    BF_INTERNAL_ERROR = 5,
+    BF_RUNNING = 6,
+} BfStatus;
+
+/* Result of `bf_exec`, with `success` set to 1 if the program terminated without errors. */
+typedef struct {
+    uint8_t success;
+    uint8_t *output;
+    size_t output_length;
+} BfExecResult;
+
+/* A program being executed a few instructions at a time. */
+typedef struct BfInterpreter BfInterpreter;
+
+/* Run a NUL-terminated program to completion, returning its output only if it succeeded. */
+BfExecResult bf_exec(const char *program, const uint8_t *input, size_t input_length);
+
+/* Run a NUL-terminated program to completion, storing everything it wrote to `output`. */
+BfStatus bf_run(
+    const char *program,
+    const uint8_t *input,
+    size_t input_length,
+    uint8_t **output,
+    size_t *output_length);
+
+/* Create an interpreter for a NUL-terminated program, or return NULL if it is not valid UTF-8. */
+BfInterpreter *bf_new_interpreter(const char *program, const uint8_t *input, size_t input_length);
+
+/* Execute up to `steps` instructions, storing what they wrote to `output`. */
+BfStatus bf_step(
+    BfInterpreter *interpreter,
+    uint32_t steps,
+    uint8_t **output,
+    size_t *output_length);
+
+/* Deallocate output returned by any of the functions above. */
+void bf_free(uint8_t *to_free, size_t length);
+
+/* Deallocate an interpreter created by `bf_new_interpreter`. */
+void bf_free_interpreter(BfInterpreter *interpreter);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BFI_H */
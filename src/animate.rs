@@ -0,0 +1,207 @@
+//! Animation of a running program in the terminal, see `animate`.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cell::Cell;
+use crate::difftest;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+
+
+/// Number of cells shown in each frame.
+const WINDOW: usize = 16;
+
+/// Number of lines of each frame: the status, the tape as index, value, and ASCII rows, the data
+/// pointer, and the latest line of output.
+const LINES: usize = 6;
+
+const REVERSE: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+
+/// How fast `animate` runs a program and redraws it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Animation {
+    /// Instructions executed per second.
+    pub speed: f64,
+
+    /// Most frames drawn per second.
+    pub fps: f64,
+}
+
+impl Default for Animation {
+    fn default() -> Self { Animation { speed: 10.0, fps: 30.0 } }
+}
+
+
+/// Run the program of `ectx` to completion at the speed of `animation`, drawing a fixed window of
+/// the tape around the data pointer to `terminal` as it runs, each frame over the last. Returns
+/// the output of the program, which is shown a line at a time in the animation.
+///
+/// `ectx` must have been created with an `InMemoryIoCtx` holding all of the input of the program
+/// and `without_repl`, as the animation is drawn where the output would go.
+pub fn animate<C: Cell>(
+    ectx: &mut ExecutionContext<C>,
+    animation: Animation,
+    terminal: &mut dyn Write,
+) -> io::Result<Vec<u8>>
+{
+    ectx.start();
+    let frame_interval = Duration::from_secs_f64(1.0 / animation.fps);
+    let started = Instant::now();
+    let mut output = Vec::new();
+    let mut window_start = 0;
+    let mut steps = 0u64;
+    for frame in 0.. {
+        // the instructions that would have been executed by now at the speed of the animation
+        let due = (started.elapsed().as_secs_f64() * animation.speed) as u64 + 1;
+        while steps < due && ectx.status == ExecutionStatus::InProgress {
+            ectx.step();
+            steps += 1;
+        }
+        // breakpoints are not paused at
+        ectx.take_paused();
+        collect_output(ectx, &mut output)?;
+        window_start = follow(window_start, ectx.data_ptr());
+        if frame > 0 {
+            write!(terminal, "\x1b[{}A", LINES)?;
+        };
+        for line in render(ectx, steps, window_start, &output) {
+            write!(terminal, "\r{}\x1b[K\n", line)?;
+        }
+        terminal.flush()?;
+        if ectx.status != ExecutionStatus::InProgress {
+            break;
+        };
+        let next_step = started + Duration::from_secs_f64(steps as f64 / animation.speed);
+        let next_frame = Instant::now() + frame_interval;
+        thread::sleep(next_step.max(next_frame).saturating_duration_since(Instant::now()));
+    }
+    Ok(output)
+}
+
+
+/// Move the output written by the program since the last call into `output`.
+fn collect_output<C: Cell>(
+    ectx: &mut ExecutionContext<C>,
+    output: &mut Vec<u8>,
+) -> io::Result<()>
+{
+    if let Some(ctx) = ectx.io_context() {
+        let mut buf = [0u8; 1024];
+        loop {
+            match ctx.read_output(&mut buf)? {
+                0 => break,
+                n => output.extend_from_slice(&buf[..n]),
+            };
+        }
+    };
+    Ok(())
+}
+
+
+/// Index of the first cell shown, which stays put until the data pointer leaves the window shown
+/// and then moves by a whole window, such that the tape only scrolls as often as it must.
+fn follow(window_start: usize, data_ptr: usize) -> usize {
+    if (window_start..window_start + WINDOW).contains(&data_ptr) {
+        window_start
+    } else {
+        data_ptr - data_ptr % WINDOW
+    }
+}
+
+
+/// Render a frame as `LINES` lines, after `steps` instructions have executed.
+fn render<C: Cell>(
+    ectx: &ExecutionContext<C>,
+    steps: u64,
+    window_start: usize,
+    output: &[u8],
+) -> Vec<String>
+{
+    let status = match ectx.status {
+        ExecutionStatus::InProgress => "running".to_string(),
+        ref status => difftest::describe(status),
+    };
+    let tape = ectx.tape();
+    let cells: Vec<C> = (window_start..window_start + WINDOW)
+        .map(|i| tape.get(i).copied().unwrap_or_default())
+        .collect();
+    // wide enough for every index and value in the window
+    let width = (window_start..window_start + WINDOW)
+        .map(|i| i.to_string().len())
+        .chain(cells.iter().map(|cell| cell.to_string().len()))
+        .max()
+        .unwrap_or(0) + 1;
+    let mut rows = [String::from("index"), String::from("  dec"), String::from("ascii")];
+    let mut pointer = String::from("     ");
+    for (i, cell) in cells.iter().enumerate() {
+        let ascii = match cell.to_byte() {
+            b if b.is_ascii_graphic() => (b as char).to_string(),
+            _ => ".".to_string(),
+        };
+        let values = [(window_start + i).to_string(), cell.to_string(), ascii];
+        let current = window_start + i == ectx.data_ptr();
+        for (row, value) in rows.iter_mut().zip(values.iter()) {
+            let value = format!("{:>width$}", value, width = width);
+            if current {
+                row.push_str(&format!("{}{}{}", REVERSE, value, RESET));
+            } else {
+                row.push_str(&value);
+            };
+        }
+        pointer.push_str(&format!("{:>width$}", if current { "^" } else { "" }, width = width));
+    }
+    // the line being written, or the last one written if the output ends with a newline
+    let last_line = output.strip_suffix(b"\n").unwrap_or(output).rsplit(|&b| b == b'\n').next();
+    let last_line: String = last_line
+        .unwrap_or(&[])
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { ' ' })
+        .collect();
+    let [index, dec, ascii] = rows;
+    vec![
+        format!(" bfi | {} | {} steps", status, steps),
+        index,
+        dec,
+        ascii,
+        pointer.trim_end().to_string(),
+        format!("  out {}", last_line),
+    ]
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::ioctx::{InMemoryIoCtx, IoCtx};
+
+    #[test]
+    fn test_follow() {
+        assert_eq!(follow(0, 15), 0);
+        assert_eq!(follow(0, 16), 16);
+        assert_eq!(follow(16, 17), 16);
+        assert_eq!(follow(16, 3), 0);
+    }
+
+    #[test]
+    fn test_animate() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let program = "++++++++[>++++++++<-]>+.>++++++++++.";
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), program).without_repl();
+        let mut terminal = Vec::new();
+        let animation = Animation { speed: 1e6, fps: 1e3 };
+        let output = animate(&mut ectx, animation, &mut terminal).unwrap();
+        assert_eq!(output, b"A\n");
+        let terminal = String::from_utf8(terminal).unwrap();
+        let last_frame: Vec<&str> = terminal.rsplit("\x1b[6A").next().unwrap().lines().collect();
+        assert_eq!(last_frame.len(), LINES);
+        assert_eq!(last_frame[0], "\r bfi | terminated | 10 steps\x1b[K");
+        let dec = format!("\r  dec  0 65{} 10{}  0  0", REVERSE, RESET);
+        assert!(last_frame[2].starts_with(&dec));
+        assert_eq!(last_frame[4], "\r             ^\x1b[K");
+        assert_eq!(last_frame[5], "\r  out A\x1b[K");
+    }
+}
@@ -0,0 +1,400 @@
+//! Running programs on an async runtime without blocking its threads, see `AsyncInterpreter`.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::io;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::cell::Cell;
+use crate::config::{InterpreterConfig, IoMode};
+use crate::error::BfError;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::ioctx::IoCtx;
+use crate::program::{Instruction, Program};
+
+
+/// Number of instructions executed between giving other tasks of the runtime a turn.
+const YIELD_INTERVAL: u64 = 4096;
+
+
+/// Source of the bytes read by `,`, polled like `futures::io::AsyncRead`, such that a socket or
+/// pipe of any runtime can be adapted to it in a few lines.
+pub trait AsyncRead {
+    /// Read into `buf`, returning the number of bytes read, zero only at the end of input.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+/// Destination of the bytes written by `.`, polled like `futures::io::AsyncWrite`.
+pub trait AsyncWrite {
+    /// Write from `buf`, returning the number of bytes written.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+/// Always ready, reading the bytes of the slice in turn.
+impl AsyncRead for &[u8] {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>
+    {
+        Poll::Ready(io::Read::read(&mut *self, buf))
+    }
+}
+
+/// Always ready, appending every byte written.
+impl AsyncWrite for Vec<u8> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>
+    {
+        self.get_mut().extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+
+/// Input read ahead of the program and output it has yet to have written, shared between the
+/// `ExecutionContext` running the program and the `AsyncInterpreter` driving it.
+#[derive(Debug, Default)]
+struct Buffers {
+    input: VecDeque<u8>,
+    /// Whether the input has ended, such that no more will be added to `input`.
+    eof: bool,
+    output: Vec<u8>,
+}
+
+impl Buffers {
+    /// Whether enough input is buffered for `,` to read a whole value in `io_mode`, or the input
+    /// has ended, such that reading from the buffer gives the same result as reading the input.
+    fn is_ready(&self, io_mode: IoMode) -> bool {
+        let first = match self.input.front() {
+            Some(&first) => first,
+            None => return self.eof,
+        };
+        self.eof || match io_mode {
+            IoMode::Bytes => true,
+            IoMode::Utf8 => self.input.len() >= (first.leading_ones() as usize).clamp(1, 4),
+            // a number ends at the whitespace following it
+            IoMode::Numeric => self.input
+                .iter()
+                .skip_while(|b| b.is_ascii_whitespace())
+                .any(u8::is_ascii_whitespace),
+        }
+    }
+}
+
+/// `IoCtx` of a program run by an `AsyncInterpreter`, reading and writing `Buffers`.
+struct BufferedIoCtx {
+    buffers: Arc<Mutex<Buffers>>,
+}
+
+impl IoCtx for BufferedIoCtx {
+    fn read_input(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut self.buffers.lock().unwrap().input, buf)
+    }
+
+    fn write_output(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffers.lock().unwrap().output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush_output(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+
+/// Interpreter running a program as a future, which awaits an `AsyncRead` for the input of `,`
+/// and an `AsyncWrite` for the output of `.`, and yields to the runtime every few thousand
+/// instructions, such that a single thread can run many programs at once.
+///
+/// Output is written whenever the interpreter yields, before awaiting input, and once the program
+/// ends, such that a prompt is seen before the program waits for its answer. Programs are always
+/// interpreted rather than compiled with the JIT, and never open the REPL. The future is `Send`
+/// as long as the input and output are, such that a multi-threaded runtime can move it between
+/// its threads.
+pub struct AsyncInterpreter<C: Cell = u8> {
+    program: Program,
+    config: InterpreterConfig,
+    cells: PhantomData<C>,
+}
+
+impl AsyncInterpreter {
+    /// Create an interpreter of `program` with 8-bit cells.
+    pub fn new(program: &str) -> Self { Self::with_program(Program::parse(program)) }
+}
+
+impl<C: Cell> AsyncInterpreter<C> {
+    /// Create an interpreter of a program that has already been parsed, with cells of type `C`.
+    pub fn with_program(program: Program) -> Self {
+        AsyncInterpreter { program, config: InterpreterConfig::default(), cells: PhantomData }
+    }
+
+    /// Run programs with `config`, e.g. to limit their steps with `InterpreterConfig::max_steps`.
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Execute the program to completion, returning the final `ExecutionStatus`. May be called
+    /// any number of times, including concurrently, each run starting from a blank tape.
+    pub async fn run<R, W>(&self, input: &mut R, output: &mut W) -> ExecutionStatus<BfError>
+    where
+        C: Send,
+        R: AsyncRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let buffers = Arc::new(Mutex::new(Buffers::default()));
+        let ictx = BufferedIoCtx { buffers: Arc::clone(&buffers) };
+        let ictx = Confined(RefCell::new(Box::new(ictx) as Box<dyn IoCtx>));
+        let ectx = ExecutionContext::<C>::with_program(ictx.0.borrow_mut(), self.program.clone())
+            .with_config(self.config.clone())
+            .without_repl();
+        let mut ectx = Confined(ectx);
+        let mut steps: u64 = 0;
+        loop {
+            if ectx.0.next_instruction() == Some(Instruction::GetChar) {
+                let filled = match write_output(output, &buffers).await {
+                    Ok(()) => read_input(input, &buffers, self.config.io_mode).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = filled {
+                    return ExecutionStatus::InternalError(e.into());
+                };
+            };
+            match ectx.0.step() {
+                ExecutionStatus::NotStarted | ExecutionStatus::InProgress => {},
+                status => {
+                    return match write_output(output, &buffers).await {
+                        Ok(()) => status,
+                        Err(e) => ExecutionStatus::InternalError(e.into()),
+                    };
+                },
+            };
+            steps += 1;
+            if steps.is_multiple_of(YIELD_INTERVAL) {
+                if let Err(e) = write_output(output, &buffers).await {
+                    return ExecutionStatus::InternalError(e.into());
+                };
+                YieldNow::default().await;
+            };
+        }
+    }
+}
+
+
+/// Read from `input` until enough is buffered for the next `,`, see `Buffers::is_ready`.
+async fn read_input<R>(input: &mut R, buffers: &Mutex<Buffers>, io_mode: IoMode) -> io::Result<()>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let mut chunk = [0; 1024];
+    while !buffers.lock().unwrap().is_ready(io_mode) {
+        let n = future::poll_fn(|cx| Pin::new(&mut *input).poll_read(cx, &mut chunk)).await?;
+        let mut buffers = buffers.lock().unwrap();
+        match n {
+            0 => buffers.eof = true,
+            n => buffers.input.extend(&chunk[..n]),
+        };
+    }
+    Ok(())
+}
+
+/// Write and flush the output buffered so far to `output`.
+async fn write_output<W>(output: &mut W, buffers: &Mutex<Buffers>) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let pending = std::mem::take(&mut buffers.lock().unwrap().output);
+    if pending.is_empty() {
+        return Ok(());
+    };
+    let mut written = 0;
+    while written < pending.len() {
+        let buf = &pending[written..];
+        match future::poll_fn(|cx| Pin::new(&mut *output).poll_write(cx, buf)).await? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => written += n,
+        };
+    }
+    future::poll_fn(|cx| Pin::new(&mut *output).poll_flush(cx)).await
+}
+
+
+/// The `ExecutionContext` of a run of `AsyncInterpreter::run`, or the I/O context it borrows,
+/// neither of which is `Send` as they hold a `RefMut` and boxed trait objects.
+///
+/// Both are created by the run and kept in its future, with nothing outside the future able to
+/// reach them: the program and configuration are cloned, the I/O context only holds an `Arc` of
+/// `Buffers`, and there are no hooks, tracer, or cancel tokens. The borrow of the I/O context
+/// therefore never leaves the future, and moving the future to another thread moves both of them
+/// together.
+struct Confined<T>(T);
+
+// SAFETY: see `Confined`, which is only used by `AsyncInterpreter::run`
+unsafe impl<T> Send for Confined<T> {}
+
+
+/// Future that is pending once, giving the other tasks of the runtime a turn before it completes.
+#[derive(Default)]
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        };
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    use crate::config::EofBehavior;
+
+    /// Wakes the thread blocked on a future in `block_on`.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) { self.0.unpark(); }
+    }
+
+    /// Drive `future` to completion on the current thread, as the simplest of runtimes, returning
+    /// its output and the number of times it was pending.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut pending = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, pending),
+                Poll::Pending => {
+                    pending += 1;
+                    thread::park();
+                },
+            };
+        }
+    }
+
+    /// Input that is only ready every other poll, and then with a single byte.
+    struct Trickle {
+        input: Vec<u8>,
+        /// Whether the next poll is ready, having been woken by this one.
+        ready: bool,
+        polls: usize,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>>
+        {
+            self.polls += 1;
+            self.ready = !self.ready;
+            if self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            };
+            let n = self.input.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input.drain(..n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let config = InterpreterConfig { eof: EofBehavior::Zero, ..InterpreterConfig::default() };
+        let interpreter = AsyncInterpreter::new(",[.,]").with_config(config);
+        let mut input = Trickle { input: b"hello".to_vec(), ready: false, polls: 0 };
+        let mut output = Vec::new();
+        let (status, pending) = block_on(interpreter.run(&mut input, &mut output));
+        assert_eq!(status, ExecutionStatus::Terminated);
+        assert_eq!(output, b"hello");
+        // each byte was awaited, followed by the end of input
+        assert_eq!((input.polls, pending), (12, 6));
+    }
+
+    #[test]
+    fn test_io_modes() {
+        let config = InterpreterConfig { io_mode: IoMode::Numeric, ..InterpreterConfig::default() };
+        let interpreter = AsyncInterpreter::<u16>::with_program(Program::parse(",>,[<+>-]<."))
+            .with_config(config);
+        let mut input = Trickle { input: b"300 12\n".to_vec(), ready: false, polls: 0 };
+        let mut output = Vec::new();
+        let (status, _) = block_on(interpreter.run(&mut input, &mut output));
+        assert_eq!((status, output), (ExecutionStatus::Terminated, b"312\n".to_vec()));
+        let config = InterpreterConfig { io_mode: IoMode::Utf8, ..InterpreterConfig::default() };
+        let interpreter = AsyncInterpreter::<u32>::with_program(Program::parse(",."))
+            .with_config(config);
+        let mut input = Trickle { input: "é".as_bytes().to_vec(), ready: false, polls: 0 };
+        let mut output = Vec::new();
+        let (status, _) = block_on(interpreter.run(&mut input, &mut output));
+        assert_eq!((status, output), (ExecutionStatus::Terminated, "é".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_send() {
+        // polled first on this thread, then finished on another
+        let config = InterpreterConfig { eof: EofBehavior::Zero, ..InterpreterConfig::default() };
+        let interpreter = AsyncInterpreter::new(",[.,]").with_config(config);
+        let mut input = Trickle { input: b"moved".to_vec(), ready: false, polls: 0 };
+        let mut output = Vec::new();
+        let mut future = Box::pin(interpreter.run(&mut input, &mut output));
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        assert!(future.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+        let (status, _) = thread::scope(|s| s.spawn(move || block_on(future)).join().unwrap());
+        assert_eq!(status, ExecutionStatus::Terminated);
+        assert_eq!(output, b"moved");
+    }
+
+    #[test]
+    fn test_yield() {
+        // takes thousands of instructions with input and output that are always ready
+        let config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+        let interpreter = AsyncInterpreter::new("-[>+[-]+++++++++[-]<-]>.")
+            .with_config(config.clone());
+        let mut output = Vec::new();
+        let (status, pending) = block_on(interpreter.run(&mut &b""[..], &mut output));
+        assert_eq!((status, output), (ExecutionStatus::Terminated, vec![0]));
+        assert!(pending > 0);
+        let config = InterpreterConfig { max_steps: Some(10), ..config };
+        let interpreter = AsyncInterpreter::new("+[]").with_config(config);
+        let (status, _) = block_on(interpreter.run(&mut &b""[..], &mut Vec::new()));
+        assert_eq!(status, ExecutionStatus::LimitExceeded(BfError::StepLimit(10)));
+    }
+}
@@ -0,0 +1,279 @@
+//! Running a suite of programs, each on its own input and checked against its expected output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::cell::Cell;
+use crate::config::{CellSize, InterpreterConfig};
+use crate::difftest::{self, Outcome};
+use crate::error::BfError;
+use crate::interpreter::ExecutionStatus;
+use crate::program::Program;
+
+
+/// File extensions of the programs found in a directory by `discover`.
+const PROGRAM_EXTENSIONS: &[&str] = &["b", "bf"];
+
+/// Extension of the file holding the input of a program found in a directory by `discover`.
+const INPUT_EXTENSION: &str = "in";
+
+/// Extension of the file holding the expected output of a program found in a directory by
+/// `discover`.
+const EXPECTED_EXTENSION: &str = "out";
+
+
+/// The files making up a single case of a suite, see `discover`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// Name to report the case by: the program file as it was found.
+    pub name: String,
+
+    pub program: PathBuf,
+
+    /// File holding the input of the program, which is otherwise given no input.
+    pub input: Option<PathBuf>,
+
+    /// File holding the output expected of the program, which may otherwise write anything.
+    pub expected: Option<PathBuf>,
+}
+
+
+/// A case with its files read and its program parsed, ready to `run`.
+#[derive(Debug, Clone)]
+pub struct Test {
+    pub name: String,
+    pub program: Program,
+    pub input: Vec<u8>,
+    pub expected: Option<Vec<u8>>,
+}
+
+
+/// Whether a test passed, or why it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+}
+
+
+/// Find the cases of the suite at `path`, which is either a directory or a manifest file.
+///
+/// In a directory every `.b` or `.bf` file is a program, with its input read from the file of the
+/// same name ending in `.in` and its expected output from the one ending in `.out`, where they
+/// exist. A manifest lists a program per line, see `parse_manifest`.
+pub fn discover(path: &Path) -> Result<Vec<Case>, String> {
+    if !path.is_dir() {
+        let manifest = fs::read_to_string(path).map_err(|e| {
+            format!("manifest '{}' could not be read ({})", path.display(), e)
+        })?;
+        // unwrap is safe as a file that could be read has a parent directory
+        return parse_manifest(&manifest, path.parent().unwrap());
+    };
+    let entries = fs::read_dir(path).map_err(|e| {
+        format!("directory '{}' could not be read ({})", path.display(), e)
+    })?;
+    let mut programs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|program| {
+            program.is_file() && program.extension().is_some_and(|extension| {
+                PROGRAM_EXTENSIONS.iter().any(|e| extension == *e)
+            })
+        })
+        .collect();
+    programs.sort();
+    Ok(programs.into_iter().map(|program| {
+        let sibling = |extension| {
+            Some(program.with_extension(extension)).filter(|path| path.is_file())
+        };
+        Case {
+            name: program.display().to_string(),
+            input: sibling(INPUT_EXTENSION),
+            expected: sibling(EXPECTED_EXTENSION),
+            program,
+        }
+    }).collect())
+}
+
+
+/// Parse the cases listed in `manifest`, one per line as a program file followed optionally by
+/// its input file and its expected output file, with `-` for neither. Relative paths are relative
+/// to `base`, the directory of the manifest. Blank lines and lines starting with `#` are skipped.
+pub fn parse_manifest(manifest: &str, base: &Path) -> Result<Vec<Case>, String> {
+    let mut cases = Vec::new();
+    for (i, line) in manifest.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let path = |field: Option<&&str>| {
+            field.filter(|f| **f != "-").map(|f| base.join(f))
+        };
+        match fields.as_slice() {
+            [] => {},
+            [first, ..] if first.starts_with('#') => {},
+            [program, rest @ ..] if rest.len() <= 2 => cases.push(Case {
+                name: program.to_string(),
+                program: base.join(program),
+                input: path(rest.first()),
+                expected: path(rest.get(1)),
+            }),
+            _ => return Err(format!(
+                "line {} of the manifest has more than a program, input, and expected output",
+                i + 1,
+            )),
+        };
+    }
+    Ok(cases)
+}
+
+
+/// Run every test with the semantics and limits of `config` across up to `jobs` threads, returning
+/// their verdicts in the same order as `tests`.
+pub fn run(tests: &[Test], config: &InterpreterConfig, jobs: usize) -> Vec<Verdict> {
+    let next = AtomicUsize::new(0);
+    let verdicts = Mutex::new(vec![None; tests.len()]);
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, tests.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let test = match tests.get(i) {
+                    Some(test) => test,
+                    None => break,
+                };
+                let verdict = check(test, config);
+                // unwrap is safe as no thread panics while holding the lock
+                verdicts.lock().unwrap()[i] = Some(verdict);
+            });
+        }
+    });
+    // unwraps are safe as every test was taken by a thread that then recorded its verdict
+    verdicts.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+}
+
+
+/// Run a single test to completion and judge it.
+fn check(test: &Test, config: &InterpreterConfig) -> Verdict {
+    let (status, output) = match config.cell_size {
+        CellSize::U8 => outcome::<u8>(test, config),
+        CellSize::U16 => outcome::<u16>(test, config),
+        CellSize::U32 => outcome::<u32>(test, config),
+        CellSize::I32 => outcome::<i32>(test, config),
+    };
+    match (&status, &test.expected) {
+        (ExecutionStatus::Terminated, None) => Verdict::Pass,
+        (ExecutionStatus::Terminated, Some(expected)) if output == *expected => Verdict::Pass,
+        (ExecutionStatus::Terminated, Some(expected)) => Verdict::Fail(format!(
+            "output: {} bytes but {} expected, first differing at byte {}",
+            output.len(),
+            expected.len(),
+            difftest::first_difference(&output, expected),
+        )),
+        (status, _) => Verdict::Fail(difftest::describe(status)),
+    }
+}
+
+
+/// How the program of `test` ended and what it wrote, run with cells of type `C`.
+fn outcome<C: Cell>(
+    test: &Test,
+    config: &InterpreterConfig,
+) -> (ExecutionStatus<BfError>, Vec<u8>)
+{
+    let Outcome { status, output, .. } = difftest::run::<C>(
+        &test.program,
+        &test.input,
+        config.clone(),
+    );
+    (status, output)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::env;
+
+    use crate::config::TapeMode;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "# name input expected\n\nhello.bf - hello.out\n\n  cat.b cat.in\n";
+        let base = Path::new("suite");
+        assert_eq!(parse_manifest(manifest, base), Ok(vec![
+            Case {
+                name: "hello.bf".to_string(),
+                program: base.join("hello.bf"),
+                input: None,
+                expected: Some(base.join("hello.out")),
+            },
+            Case {
+                name: "cat.b".to_string(),
+                program: base.join("cat.b"),
+                input: Some(base.join("cat.in")),
+                expected: None,
+            },
+        ]));
+        assert!(parse_manifest("a.bf a.in a.out a.err", base).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no temporary directory")]
+    fn test_discover() {
+        let dir = env::temp_dir().join(format!("bfi-test-batch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in &[("b.bf", "+."), ("b.out", "\x01"), ("a.b", ",."), ("a.in", "x")] {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        let cases = discover(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(cases, Ok(vec![
+            Case {
+                name: dir.join("a.b").display().to_string(),
+                program: dir.join("a.b"),
+                input: Some(dir.join("a.in")),
+                expected: None,
+            },
+            Case {
+                name: dir.join("b.bf").display().to_string(),
+                program: dir.join("b.bf"),
+                input: None,
+                expected: Some(dir.join("b.out")),
+            },
+        ]));
+        assert!(discover(&dir).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no threads")]
+    fn test_run() {
+        let test = |program: &str, input: &[u8], expected: Option<&[u8]>| Test {
+            name: program.to_string(),
+            program: Program::parse(program),
+            input: input.to_vec(),
+            expected: expected.map(<[u8]>::to_vec),
+        };
+        let tests = vec![
+            test(",+.", b"a", Some(b"b")),
+            test(",.", b"a", Some(b"ab")),
+            test("<", b"", None),
+            test("+[]", b"", None),
+            test(",.,.", b"xy", None),
+        ];
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            max_steps: Some(100),
+            ..InterpreterConfig::default()
+        };
+        let verdicts = run(&tests, &config, 2);
+        assert_eq!(verdicts[0], Verdict::Pass);
+        assert_eq!(
+            verdicts[1],
+            Verdict::Fail("output: 1 bytes but 2 expected, first differing at byte 1".to_string()),
+        );
+        assert!(matches!(&verdicts[2], Verdict::Fail(e) if e.starts_with("failed (")));
+        assert!(matches!(&verdicts[3], Verdict::Fail(e) if e.starts_with("stopped (")));
+        assert_eq!(verdicts[4], Verdict::Pass);
+        assert!(run(&[], &config, 4).is_empty());
+    }
+}
@@ -1,95 +1,2467 @@
 extern crate clap;
 
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
+use std::rc::Rc;
+use std::sync::OnceLock;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+#[cfg(any(unix, target_os = "wasi"))]
+use std::os::fd::{FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::{App, Arg, ArgMatches};
+use clap::{App, Arg, ArgMatches, Shell, SubCommand};
+use log::{LevelFilter, Log, Metadata, Record};
 
-use bfi::ioctx::{IoCtx, StdIoCtx, UnbufferedStdIoCtx};
+use bfi::animate::{self, Animation};
+use bfi::batch::{self, Test, Verdict};
+use bfi::bytecode::Bytecode;
+#[cfg(unix)]
+use bfi::cancel::CancelToken;
+use bfi::cell::Cell;
+use bfi::compile::{self, Target};
+use bfi::config::{
+    BoundsPolicy, BreakpointAction, CellSize, EofBehavior, InterpreterConfig, IoMode,
+    OverflowPolicy, TapeBackend, TapeMode, DEFAULT_MAX_CALL_DEPTH, DEFAULT_TAPES,
+    DEFAULT_TAPE_SIZE,
+};
+use bfi::coverage::Coverage;
+use bfi::dap;
+use bfi::debugger::Breakpoint;
+use bfi::dialect::{self, Dialect, Extended, Extension, Strict, Substitution};
+use bfi::diff;
+use bfi::difftest;
+use bfi::equiv::{self, Inputs};
+use bfi::error::BfError;
+use bfi::format::{self, FormatOptions};
+use bfi::generate;
+use bfi::header::Header;
+use bfi::json::{self, Json};
+use bfi::lint;
+use bfi::lsp;
+use bfi::ioctx::{
+    InMemoryIoCtx, IoCtx, ReadWriteIoCtx, RecordingReader, StdIoCtx, UnbufferedStdIoCtx,
+};
+#[cfg(unix)]
+use bfi::ioctx::{RawStdin, TimeoutStdin};
 use bfi::interpreter::{ExecutionStatus, ExecutionContext};
+use bfi::optimizer;
+use bfi::preprocess::{self, SourceMap};
+use bfi::program::Program;
+use bfi::serve;
+use bfi::settings::Settings;
+use bfi::snapshot::Snapshot;
+use bfi::stats::ExecutionStats;
+use bfi::trace::Tracer;
 
 
 static PROGRAM_ARG: &str = "program";
+static EVAL_ARG: &str = "eval";
 static VERBOSE_ARG: &str = "verbose";
 static FILE_ARG: &str = "file";
 static UNBUFFERED_FLAG: &str = "unbuffered";
+static APPEND_FLAG: &str = "append";
+static TAPE_MODE_ARG: &str = "tape-mode";
+static TAPE_SIZE_ARG: &str = "tape-size";
+static BOUNDS_ARG: &str = "bounds";
+static CELL_SIZE_ARG: &str = "cell-size";
+static OVERFLOW_ARG: &str = "overflow";
+static EOF_ARG: &str = "eof";
+static IO_ARG: &str = "io";
+static BREAKPOINT_ACTION_ARG: &str = "breakpoint-action";
+static ON_INTERRUPT_ARG: &str = "on-interrupt";
+static NO_OPTIMIZE_FLAG: &str = "no-optimize";
+static DIALECT_ARG: &str = "dialect";
+static CHARSET_ARG: &str = "charset";
+static EXTENSIONS_ARG: &str = "extensions";
+static PREPROCESS_FLAG: &str = "preprocess";
+static STRICT_FLAG: &str = "strict";
+static SANDBOX_FLAG: &str = "sandbox";
+static SHARED_TAPE_FLAG: &str = "shared-tape";
+static NO_JIT_FLAG: &str = "no-jit";
+static DUMP_IR_FLAG: &str = "dump-ir";
+static INPUT_FILE_ARG: &str = "input-file";
+static INPUT_STRING_ARG: &str = "input-string";
+static RECORD_INPUT_ARG: &str = "record-input";
+static REPLAY_ARG: &str = "replay";
+static BREAK_ARG: &str = "break";
+static MAX_STEPS_ARG: &str = "max-steps";
+static TIMEOUT_ARG: &str = "timeout";
+static MAX_MEMORY_ARG: &str = "max-memory";
+static TRACE_FLAG: &str = "trace";
+static TRACE_FILE_ARG: &str = "trace-file";
+static TRACE_LAST_ARG: &str = "trace-last";
+static PROFILE_FLAG: &str = "profile";
+static STATS_FLAG: &str = "stats";
+static JSON_FLAG: &str = "json";
+static EXPECT_ARG: &str = "expect";
+static VISUALIZE_ARG: &str = "visualize";
+static SNAPSHOT_ON_EXIT_ARG: &str = "snapshot-on-exit";
+static RESTORE_ARG: &str = "restore";
+static TUI_FLAG: &str = "tui";
+static ANIMATE_FLAG: &str = "animate";
+static SPEED_ARG: &str = "speed";
+static FPS_ARG: &str = "fps";
+static DELAY_ARG: &str = "delay";
+static DELAY_OUTPUT_ONLY_FLAG: &str = "delay-output-only";
+static TAPES_ARG: &str = "tapes";
+static TAPE_BACKEND_ARG: &str = "tape-backend";
+static RAW_INPUT_FLAG: &str = "raw-input";
+static FD_IN_ARG: &str = "fd-in";
+static FD_OUT_ARG: &str = "fd-out";
+static INPUT_TIMEOUT_ARG: &str = "input-timeout";
+static HISTORY_ARG: &str = "history";
+static MAX_CALL_DEPTH_ARG: &str = "max-call-depth";
+
+static RUN_SUBCOMMAND: &str = "run";
+static PROFILE_SUBCOMMAND: &str = "profile";
+static ANNOTATE_FLAG: &str = "annotate";
+static COLOR_FLAG: &str = "color";
+static COV_SUBCOMMAND: &str = "cov";
+static LCOV_ARG: &str = "lcov";
+static COMPILE_SUBCOMMAND: &str = "compile";
+static TARGET_ARG: &str = "target";
+static OUTPUT_ARG: &str = "output";
+static DIFFTEST_SUBCOMMAND: &str = "difftest";
+static DISASM_SUBCOMMAND: &str = "disasm";
+static FMT_SUBCOMMAND: &str = "fmt";
+static WIDTH_ARG: &str = "width";
+static STRIP_COMMENTS_FLAG: &str = "strip-comments";
+static CHECK_FLAG: &str = "check";
+static MINIFY_SUBCOMMAND: &str = "minify";
+static SCORE_SUBCOMMAND: &str = "score";
+static OPTIMIZE_SUBCOMMAND: &str = "optimize";
+static MINIFY_FLAG: &str = "minify";
+static LINT_SUBCOMMAND: &str = "lint";
+static GENERATE_SUBCOMMAND: &str = "generate";
+static TEXT_ARG: &str = "text";
+static LSP_SUBCOMMAND: &str = "lsp";
+static DAP_SUBCOMMAND: &str = "dap";
+static SERVE_SUBCOMMAND: &str = "serve";
+static PORT_ARG: &str = "port";
+static BIND_ARG: &str = "bind";
+static COMPLETIONS_SUBCOMMAND: &str = "completions";
+static SHELL_ARG: &str = "shell";
+static BATCH_SUBCOMMAND: &str = "batch";
+static SUITE_ARG: &str = "suite";
+static JOBS_ARG: &str = "jobs";
+static EQUIV_SUBCOMMAND: &str = "equiv";
+static OTHER_FILE_ARG: &str = "other-file";
+static INPUTS_ARG: &str = "inputs";
+static SEED_ARG: &str = "seed";
+
+#[cfg(not(feature = "llvm"))]
+static TARGETS: &[&str] = &["c", "rust", "wasm", "bf", "bytecode"];
+#[cfg(feature = "llvm")]
+static TARGETS: &[&str] = &["c", "rust", "wasm", "bf", "bytecode", "llvm-ir", "object"];
+
+/// Filename used to indicate that the program should be read from stdin.
+static STDIN_FILENAME: &str = "-";
+
+static EXIT_STATUS_HELP: &str = "\
+EXIT STATUS:
+    0    The program terminated without errors
+    1    The program failed while running, e.g. by moving off of a fixed tape
+    2    Invalid usage, including files that could not be read
+    3    The program was rejected before running, e.g. for unbalanced brackets
+    4    Execution was stopped by --max-steps, --timeout, --max-memory, or --max-call-depth
+    5    Execution failed for a reason unrelated to the program, e.g. an I/O error
+    6    The output differed from --expect, difftest found the optimized program to behave
+         differently, equiv found the programs to differ, fmt --check found the program to be
+         unformatted, or batch found a program to fail
+    7    lint found likely mistakes in the program
+    130  Execution was interrupted with Ctrl-C";
+
+
+/// Exit status of `bfi`, distinguishing the ways in which it can fail. See `EXIT_STATUS_HELP`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ExitCode {
+    Success = 0,
+    ProgramError = 1,
+    UsageError = 2,
+    ParseError = 3,
+    LimitExceeded = 4,
+    InternalError = 5,
+    Mismatch = 6,
+    Warnings = 7,
+    Interrupted = 130,
+}
+
+impl<T> From<&ExecutionStatus<T>> for ExitCode {
+    fn from(status: &ExecutionStatus<T>) -> Self {
+        match status {
+            ExecutionStatus::Terminated => ExitCode::Success,
+            ExecutionStatus::ProgramError(_) => ExitCode::ProgramError,
+            ExecutionStatus::LimitExceeded(_) => ExitCode::LimitExceeded,
+            ExecutionStatus::Cancelled => ExitCode::Interrupted,
+            // execution never returns while it is still in progress
+            ExecutionStatus::InternalError(_)
+            | ExecutionStatus::NotStarted
+            | ExecutionStatus::InProgress => ExitCode::InternalError,
+        }
+    }
+}
+
+
+fn exit(code: ExitCode) -> ! { std::process::exit(code as i32) }
+
+
+/// How to present the profile of a program once it has executed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ProfileOutput {
+    /// Summarize the hottest loops, instruction counts, and most accessed cells.
+    Report,
+
+    /// Show the source annotated with execution counts, optionally as a color heat overlay.
+    Annotate { color: bool },
+
+    /// Show the source with the commands that were never executed marked, see `Coverage`.
+    Coverage,
+}
+
+
+/// Arguments controlling the semantics of a program, shared between execution and compilation.
+fn semantics_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name(TAPE_MODE_ARG)
+            .long("tape-mode")
+            .takes_value(true)
+            .value_name("MODE")
+            .possible_values(&["fixed", "growable", "circular"])
+            .default_value("growable")
+            .help("Tape behavior at its ends: fixed 30,000 cells, growable, or circular"),
+        Arg::with_name(TAPE_SIZE_ARG)
+            .long("tape-size")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| match s.parse::<usize>() {
+                Ok(0) => Err("the tape must have at least one cell".to_string()),
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            })
+            .help("Cells of a fixed or circular tape, or to allocate up front for a growable one"),
+        Arg::with_name(BOUNDS_ARG)
+            .long("bounds")
+            .takes_value(true)
+            .value_name("POLICY")
+            .possible_values(&["error", "wrap", "clamp"])
+            .default_value("error")
+            .help("Moving off a fixed tape: fail, wrap around to the other end, or stop at the end"),
+        Arg::with_name(CELL_SIZE_ARG)
+            .long("cell-size")
+            .takes_value(true)
+            .value_name("TYPE")
+            .possible_values(&["u8", "u16", "u32", "i32", "8", "16", "32"])
+            .default_value("u8")
+            .help("Integer type of each cell on the tape"),
+        Arg::with_name(OVERFLOW_ARG)
+            .long("overflow")
+            .takes_value(true)
+            .value_name("POLICY")
+            .possible_values(&["wrap", "saturate", "error"])
+            .default_value("wrap")
+            .help("Cells passing their bounds: wrap around, stop at the bound, or fail"),
+        Arg::with_name(EOF_ARG)
+            .long("eof")
+            .takes_value(true)
+            .value_name("VALUE")
+            .possible_values(&["unchanged", "0", "255"])
+            .default_value("unchanged")
+            .help("Value stored by ',' at the end of input: leave the cell unchanged, 0, or 255 (-1)"),
+        Arg::with_name(IO_ARG)
+            .long("io")
+            .takes_value(true)
+            .value_name("MODE")
+            .possible_values(&["bytes", "numeric", "utf8"])
+            .default_value("bytes")
+            .help("Whether '.' and ',' deal in bytes, whitespace-delimited integers, or UTF-8"),
+        Arg::with_name(BREAKPOINT_ACTION_ARG)
+            .long("breakpoint-action")
+            .takes_value(true)
+            .value_name("ACTION")
+            .possible_values(&["repl", "dump", "trace", "ignore"])
+            .default_value("repl")
+            .help("Effect of a '%' breakpoint: open the REPL, or dump or trace the state to \
+                stderr, or ignore it, continuing either way"),
+        Arg::with_name(NO_OPTIMIZE_FLAG)
+            .long("no-optimize")
+            .takes_value(false)
+            .help("Execute the program exactly as written, without optimization"),
+        Arg::with_name(DIALECT_ARG)
+            .long("dialect")
+            .takes_value(true)
+            .value_name("LANGUAGE")
+            .possible_values(dialect::DIALECTS)
+            .default_value("bf")
+            .help("Language the program is written in, e.g. ook for Ook!"),
+        Arg::with_name(CHARSET_ARG)
+            .long("charset")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with(DIALECT_ARG)
+            .help("Read the program in a dialect spelling each command as given in a TOML file"),
+        Arg::with_name(EXTENSIONS_ARG)
+            .long("extensions")
+            .takes_value(true)
+            .value_name("SET")
+            .use_delimiter(true)
+            .possible_values(dialect::EXTENSIONS)
+            .conflicts_with(CHARSET_ARG)
+            .help("Add commands to the dialect, e.g. ext1 for Extended Brainfuck Type I"),
+        Arg::with_name(STRICT_FLAG)
+            .long("strict")
+            .takes_value(false)
+            .help("Treat the '#' and '%' debugging commands as comments, as in standard brainfuck"),
+        Arg::with_name(PREPROCESS_FLAG)
+            .long("preprocess")
+            .takes_value(false)
+            .help("Expand #include, #define, and repetitions like '+*10' before parsing"),
+    ]
+}
+
+
+/// Arguments providing program input other than stdin, shared between execution and the
+/// `difftest` subcommand.
+fn input_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name(INPUT_FILE_ARG)
+            .long("input-file")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with(INPUT_STRING_ARG)
+            .help("Read program input from a file instead of stdin"),
+        Arg::with_name(INPUT_STRING_ARG)
+            .long("input-string")
+            .takes_value(true)
+            .value_name("INPUT")
+            .conflicts_with(INPUT_FILE_ARG)
+            .help("Use the provided string as program input instead of stdin"),
+    ]
+}
+
+
+/// Arguments limiting the resources used by a program, shared between execution and the
+/// `difftest` subcommand.
+fn limit_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name(MAX_STEPS_ARG)
+            .long("max-steps")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Stop execution with an error after executing N instructions"),
+        Arg::with_name(TIMEOUT_ARG)
+            .long("timeout")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .validator(|s| parse_timeout(&s).map(|_| ()))
+            .help("Stop execution with an error after running for the provided number of seconds"),
+        Arg::with_name(MAX_MEMORY_ARG)
+            .long("max-memory")
+            .takes_value(true)
+            .value_name("BYTES")
+            .validator(|s| parse_size(&s).map(|_| ()))
+            .help("Stop execution with an error if a growable tape grows past BYTES (e.g. 64M)"),
+        Arg::with_name(MAX_CALL_DEPTH_ARG)
+            .long("max-call-depth")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Stop execution with an error past N nested procedure calls (--dialect pbrain)"),
+    ]
+}
+
+
+/// Arguments controlling program execution, shared between the top level invocation and the `run`
+/// subcommand.
+fn execution_args() -> Vec<Arg<'static, 'static>> {
+    let mut args = vec![
+        Arg::with_name(VERBOSE_ARG)
+            .short("v")
+            .long("verbose")
+            .help("Toggle high verbosity"),
+        Arg::with_name(UNBUFFERED_FLAG)
+            .long("unbuffered")
+            .takes_value(false)
+            .help("Do not buffer output (note: may break output character encoding)"),
+        Arg::with_name(OUTPUT_ARG)
+            .short("o")
+            .long("output")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("File to write the program's output to instead of stdout"),
+        Arg::with_name(APPEND_FLAG)
+            .long("append")
+            .takes_value(false)
+            .requires(OUTPUT_ARG)
+            .help("Add to the end of the output file rather than replacing its contents"),
+        Arg::with_name(NO_JIT_FLAG)
+            .long("no-jit")
+            .takes_value(false)
+            .help("Always use the interpreter, even when built with the jit feature"),
+        Arg::with_name(DUMP_IR_FLAG)
+            .long("dump-ir")
+            .takes_value(false)
+            .help("Print the compiled program and the optimizations applied to stderr"),
+        Arg::with_name(SEED_ARG)
+            .long("seed")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Seed of the random bytes of '?' (--extensions random), 0 by default"),
+        Arg::with_name(TAPES_ARG)
+            .long("tapes")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| match s.parse::<usize>() {
+                Ok(0) => Err("there must be at least one tape".to_string()),
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            })
+            .help("Number of tapes switched between by '/' and '\\' (--extensions multitape)"),
+        Arg::with_name(TAPE_BACKEND_ARG)
+            .long("tape-backend")
+            .takes_value(true)
+            .value_name("STORAGE")
+            .possible_values(&["vec", "deque", "sparse"])
+            .help("Storage of the tape: vec (fastest), deque, or sparse (only nonzero cells)"),
+        Arg::with_name(RECORD_INPUT_ARG)
+            .long("record-input")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Record every byte of input read by the program to a file for use with --replay"),
+        Arg::with_name(REPLAY_ARG)
+            .long("replay")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[INPUT_FILE_ARG, INPUT_STRING_ARG])
+            .help("Feed the input recorded by --record-input back to the program instead of stdin"),
+        Arg::with_name(BREAK_ARG)
+            .long("break")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("CONDITION")
+            .validator(|s| s.parse::<Breakpoint>().map(|_| ()))
+            .help("Open the REPL when a condition like 'cell[5] == 42' or 'watch cell[5]' is hit"),
+        Arg::with_name(TRACE_FLAG)
+            .long("trace")
+            .takes_value(false)
+            .help("Log every instruction executed along with the data pointer and cell to stderr"),
+        Arg::with_name(TRACE_FILE_ARG)
+            .long("trace-file")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Write the trace to a file instead of stderr (implies --trace)"),
+        Arg::with_name(TRACE_LAST_ARG)
+            .long("trace-last")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Only trace the last N instructions, written once execution ends (implies --trace)"),
+        Arg::with_name(PROFILE_FLAG)
+            .long("profile")
+            .takes_value(false)
+            .help("Print the hottest loops, instruction counts, and most accessed cells to stderr"),
+        Arg::with_name(STATS_FLAG)
+            .long("stats")
+            .takes_value(false)
+            .help("Print the instructions executed, peak tape size, and time taken to stderr"),
+        Arg::with_name(JSON_FLAG)
+            .long("json")
+            .takes_value(false)
+            .conflicts_with(OUTPUT_ARG)
+            .help("Print the status, output, statistics, and any error as JSON once done"),
+        Arg::with_name(EXPECT_ARG)
+            .long("expect")
+            .takes_value(true)
+            .value_name("EXPECTED")
+            .conflicts_with_all(&[OUTPUT_ARG, JSON_FLAG])
+            .help("Fail with a diff unless the output is EXPECTED, or the contents of that file"),
+        Arg::with_name(VISUALIZE_ARG)
+            .long("visualize")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Draw the tape over time to an SVG file as a heatmap of the value of each cell"),
+        Arg::with_name(ANIMATE_FLAG)
+            .long("animate")
+            .takes_value(false)
+            .conflicts_with_all(&[TRACE_FLAG, OUTPUT_ARG, JSON_FLAG, EXPECT_ARG])
+            .help("Animate the tape in the terminal as the program runs, writing the output after"),
+        Arg::with_name(SPEED_ARG)
+            .long("speed")
+            .takes_value(true)
+            .value_name("N")
+            .requires(ANIMATE_FLAG)
+            .validator(|s| parse_rate(&s).map(|_| ()))
+            .help("Instructions executed per second of the animation [default: 10]"),
+        Arg::with_name(FPS_ARG)
+            .long("fps")
+            .takes_value(true)
+            .value_name("N")
+            .requires(ANIMATE_FLAG)
+            .validator(|s| parse_rate(&s).map(|_| ()))
+            .help("Most frames drawn per second of the animation [default: 30]"),
+        Arg::with_name(DELAY_ARG)
+            .long("delay")
+            .takes_value(true)
+            .value_name("MS")
+            .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Sleep for MS milliseconds after each instruction to slow the program down"),
+        Arg::with_name(DELAY_OUTPUT_ONLY_FLAG)
+            .long("delay-output-only")
+            .takes_value(false)
+            .requires(DELAY_ARG)
+            .help("Only sleep for --delay after instructions writing output"),
+        Arg::with_name(ON_INTERRUPT_ARG)
+            .long("on-interrupt")
+            .takes_value(true)
+            .value_name("ACTION")
+            .possible_values(&["repl", "stop"])
+            .help("Effect of Ctrl-C: open the REPL at the next instruction, or stop the program \
+                [default: repl if stdin is a terminal, stop otherwise]"),
+        Arg::with_name(SNAPSHOT_ON_EXIT_ARG)
+            .long("snapshot-on-exit")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Save the tape and position in the program to a file once execution stops"),
+        Arg::with_name(RESTORE_ARG)
+            .long("restore")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Resume execution from a file saved by --snapshot-on-exit"),
+        Arg::with_name(HISTORY_ARG)
+            .long("history")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Remember the last N instructions such that the REPL can step back over them"),
+        Arg::with_name(SANDBOX_FLAG)
+            .long("sandbox")
+            .takes_value(false)
+            .conflicts_with_all(&[PREPROCESS_FLAG, BREAK_ARG, HISTORY_ARG])
+            .help("Run an untrusted program: limit steps and memory unless --max-steps and \
+                --max-memory say otherwise, treat '#' and '%' as comments, never open the REPL, \
+                and never read files named by the program"),
+    ];
+    #[cfg(unix)]
+    args.push(
+        Arg::with_name(RAW_INPUT_FLAG)
+            .long("raw-input")
+            .takes_value(false)
+            .conflicts_with_all(&[INPUT_FILE_ARG, INPUT_STRING_ARG, REPLAY_ARG])
+            .help("Read each key as it is pressed, without waiting for Enter or echoing it"),
+    );
+    #[cfg(unix)]
+    args.push(
+        Arg::with_name(INPUT_TIMEOUT_ARG)
+            .long("input-timeout")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .validator(|s| parse_timeout(&s).map(|_| ()))
+            .conflicts_with_all(&[INPUT_FILE_ARG, INPUT_STRING_ARG, REPLAY_ARG])
+            .help("Have ',' act as at the end of input (see --eof) when none arrives in time"),
+    );
+    #[cfg(any(unix, target_os = "wasi"))]
+    args.push(
+        Arg::with_name(FD_IN_ARG)
+            .long("fd-in")
+            .takes_value(true)
+            .value_name("FD")
+            .validator(|s| s.parse::<RawFd>().map(|_| ()).map_err(|e| e.to_string()))
+            .conflicts_with_all(&[
+                INPUT_FILE_ARG, INPUT_STRING_ARG, REPLAY_ARG, RAW_INPUT_FLAG, INPUT_TIMEOUT_ARG,
+            ])
+            .help("Read program input from an open file descriptor instead of stdin"),
+    );
+    #[cfg(any(unix, target_os = "wasi"))]
+    args.push(
+        Arg::with_name(FD_OUT_ARG)
+            .long("fd-out")
+            .takes_value(true)
+            .value_name("FD")
+            .validator(|s| s.parse::<RawFd>().map(|_| ()).map_err(|e| e.to_string()))
+            .conflicts_with(OUTPUT_ARG)
+            .help("Write program output to an open file descriptor instead of stdout"),
+    );
+    #[cfg(feature = "tui")]
+    args.push(
+        Arg::with_name(TUI_FLAG)
+            .long("tui")
+            .takes_value(false)
+            .conflicts_with_all(&[TRACE_FLAG, OUTPUT_ARG, JSON_FLAG, EXPECT_ARG, ANIMATE_FLAG])
+            .help("Step through the program in a full-screen debugger showing the tape and output"),
+    );
+    args.extend(input_args());
+    args.extend(limit_args());
+    args.extend(semantics_args());
+    args
+}
 
 
 fn get_command_line_args() -> ArgMatches<'static> {
+    get_app()
+        .get_matches_safe()
+        .unwrap_or_else(|e| {
+            // help and version information are printed to stdout as a success
+            if !e.use_stderr() {
+                e.exit();
+            };
+            eprintln!("{}", e.message);
+            exit(ExitCode::UsageError)
+        })
+}
+
+
+/// Definitions of every subcommand and option, from which the command line is parsed and shell
+/// completions are generated.
+fn get_app() -> App<'static, 'static> {
     App::new("bfi")
         .version("0.1")
         .about("BrainF*ck language interpreter")
         .arg(Arg::with_name(PROGRAM_ARG)
-            .help("Program to execute, or launch interactive session if no prorgram is provided")
-            .conflicts_with(FILE_ARG)
+            .help("Program to execute, or launch interactive session if no prorgram is provided. \
+                A program naming a file or '-' is read from the file or stdin, as for a script. \
+                A program starting with '-' goes after '--', e.g. bfi -- -.")
+            .conflicts_with_all(&[FILE_ARG, EVAL_ARG])
+            .allow_hyphen_values(true)
             .index(1))
-        .arg(Arg::with_name(VERBOSE_ARG)
-            .short("v")
-            .long("verbose")
-            .help("Toggle high verbosity"))
+        .arg(Arg::with_name(EVAL_ARG)
+            .short("e")
+            .long("eval")
+            .takes_value(true)
+            .value_name("CODE")
+            .allow_hyphen_values(true)
+            .conflicts_with_all(&[PROGRAM_ARG, FILE_ARG])
+            .help("Inline program to execute"))
         .arg(Arg::with_name(FILE_ARG)
             .short("f")
             .long("file")
             .takes_value(true)
             .value_name("FILE")
-            .conflicts_with(PROGRAM_ARG)
-            .help("Program file to execute"))
-        .arg(Arg::with_name(UNBUFFERED_FLAG)
-            .long("unbuffered")
-            .takes_value(false)
-            .help("Do not buffer output (note: may break output character encoding)"))
-        .get_matches()
+            .conflicts_with_all(&[PROGRAM_ARG, EVAL_ARG])
+            .help("Program file to execute ('-' to read from stdin)"))
+        .args(&execution_args())
+        .subcommand(SubCommand::with_name(RUN_SUBCOMMAND)
+            .about("Execute a program file, or several one after another with --shared-tape")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to execute ('-' to read from stdin)")
+                .required(true)
+                .multiple(true)
+                .index(1))
+            .arg(Arg::with_name(SHARED_TAPE_FLAG)
+                .long("shared-tape")
+                .takes_value(false)
+                .help("Execute each program file in turn on the tape left by the one before"))
+            .args(&execution_args()))
+        .subcommand(SubCommand::with_name(PROFILE_SUBCOMMAND)
+            .about("Execute a program file and report where it spends its time")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to profile ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(ANNOTATE_FLAG)
+                .long("annotate")
+                .takes_value(false)
+                .help("Print the source with the execution count of each command instead"))
+            .arg(Arg::with_name(COLOR_FLAG)
+                .long("color")
+                .takes_value(false)
+                .requires(ANNOTATE_FLAG)
+                .help("Color the annotated source by execution count instead of marking counts"))
+            .args(&execution_args()))
+        .subcommand(SubCommand::with_name(COV_SUBCOMMAND)
+            .about("Execute a program file and report which of its commands were never executed")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to check the coverage of ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(LCOV_ARG)
+                .long("lcov")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Also write the coverage to FILE as an lcov tracefile"))
+            .args(&execution_args()))
+        .subcommand(SubCommand::with_name(COMPILE_SUBCOMMAND)
+            .about("Translate a program file into another language")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to compile ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(TARGET_ARG)
+                .long("target")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .possible_values(TARGETS)
+                .default_value("c")
+                .help("Language to compile to"))
+            .arg(Arg::with_name(OUTPUT_ARG)
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write the compiled program to instead of stdout"))
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(OPTIMIZE_SUBCOMMAND)
+            .about("Optimize a program file into plain BrainF*ck that runs faster on any \
+                interpreter, as with 'compile --target bf'")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to optimize ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(OUTPUT_ARG)
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write the optimized program to instead of stdout"))
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(DIFFTEST_SUBCOMMAND)
+            .about("Check that a program file behaves the same with and without optimization, \
+                reading all of its input before running it")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to test ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .args(&input_args())
+            .args(&limit_args())
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(EQUIV_SUBCOMMAND)
+            .about("Check that two program files write the same output on many inputs")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("First program file to compare ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(OTHER_FILE_ARG)
+                .help("Second program file to compare")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name(INPUTS_ARG)
+                .long("inputs")
+                .takes_value(true)
+                .value_name("SOURCE")
+                .default_value("fuzz:100")
+                .validator(|s| s.parse::<Inputs>().map(|_| ()))
+                .help("'fuzz:N' for N random inputs, or a file or directory of files to use as \
+                    inputs"))
+            .arg(Arg::with_name(SEED_ARG)
+                .long("seed")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("0")
+                .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Seed of the random inputs of 'fuzz:N' and of '?' (--extensions random)"))
+            .args(&limit_args())
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(BATCH_SUBCOMMAND)
+            .about("Run every program of a suite on its own input, checking that it terminates \
+                with its expected output")
+            .arg(Arg::with_name(SUITE_ARG)
+                .help("Directory of programs with '.in' input and '.out' expected output files \
+                    of the same name, or a manifest listing a program, input, and expected \
+                    output file per line")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(JOBS_ARG)
+                .short("j")
+                .long("jobs")
+                .takes_value(true)
+                .value_name("N")
+                .validator(|s| match s.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(format!("invalid number of jobs '{}'", s)),
+                })
+                .help("Run up to N programs at once [default: number of CPUs]"))
+            .args(&limit_args())
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(DISASM_SUBCOMMAND)
+            .about("List the instructions of a program compiled with '--target bytecode'")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Bytecode file to list ('-' to read from stdin)")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name(FMT_SUBCOMMAND)
+            .about("Reformat a program file, indenting loops and wrapping long lines")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to format ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(WIDTH_ARG)
+                .long("width")
+                .takes_value(true)
+                .value_name("COLUMNS")
+                .default_value("80")
+                .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Maximum length of a line"))
+            .arg(Arg::with_name(STRIP_COMMENTS_FLAG)
+                .long("strip-comments")
+                .takes_value(false)
+                .help("Remove comments and blank lines, leaving only commands"))
+            .arg(Arg::with_name(CHECK_FLAG)
+                .long("check")
+                .takes_value(false)
+                .conflicts_with(OUTPUT_ARG)
+                .help("Only check that the file is formatted, failing with exit status 6 if not"))
+            .arg(Arg::with_name(OUTPUT_ARG)
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write the formatted program to instead of stdout")))
+        .subcommand(SubCommand::with_name(MINIFY_SUBCOMMAND)
+            .about("Shorten a program file to its commands, removing those without effect")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to minify ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(OUTPUT_ARG)
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write the minified program to instead of stdout")))
+        .subcommand(SubCommand::with_name(SCORE_SUBCOMMAND)
+            .about("Count the commands, bytes, and other characters of a program file")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to score ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name(MINIFY_FLAG)
+                .long("minify")
+                .takes_value(false)
+                .help("Score the program as shortened by the minify subcommand instead")))
+        .subcommand(SubCommand::with_name(LINT_SUBCOMMAND)
+            .about("Check a program file for likely mistakes without running it")
+            .arg(Arg::with_name(FILE_ARG)
+                .help("Program file to check ('-' to read from stdin)")
+                .required(true)
+                .index(1))
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(GENERATE_SUBCOMMAND)
+            .about("Write a program printing the given text")
+            .arg(Arg::with_name(TEXT_ARG)
+                .long("text")
+                .takes_value(true)
+                .value_name("TEXT")
+                .required(true)
+                .help("Text for the program to print"))
+            .arg(Arg::with_name(OUTPUT_ARG)
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write the program to instead of stdout")))
+        .subcommand(SubCommand::with_name(LSP_SUBCOMMAND)
+            .about("Serve the Language Server Protocol on stdin and stdout for use by editors"))
+        .subcommand(SubCommand::with_name(DAP_SUBCOMMAND)
+            .about("Serve the Debug Adapter Protocol on stdin and stdout for use by editors"))
+        .subcommand(SubCommand::with_name(SERVE_SUBCOMMAND)
+            .about("Serve an HTTP API running the programs posted to /run as JSON")
+            .arg(Arg::with_name(PORT_ARG)
+                .short("p")
+                .long("port")
+                .takes_value(true)
+                .value_name("PORT")
+                .default_value("8080")
+                .validator(|s| s.parse::<u16>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Port to listen on, 0 for any free port"))
+            .arg(Arg::with_name(BIND_ARG)
+                .long("bind")
+                .takes_value(true)
+                .value_name("ADDRESS")
+                .default_value("127.0.0.1")
+                .help("Address to listen on, e.g. 0.0.0.0 for every interface"))
+            .arg(Arg::with_name(JOBS_ARG)
+                .short("j")
+                .long("jobs")
+                .takes_value(true)
+                .value_name("N")
+                .validator(|s| match s.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(format!("invalid number of jobs '{}'", s)),
+                })
+                .help("Handle up to N requests at once, answering 503 to the rest \
+                    [default: number of CPUs]"))
+            .args(&limit_args())
+            .args(&semantics_args()))
+        .subcommand(SubCommand::with_name(COMPLETIONS_SUBCOMMAND)
+            .about("Print a script completing the subcommands and options of bfi in a shell")
+            .arg(Arg::with_name(SHELL_ARG)
+                .help("Shell to complete in, e.g. sourcing `bfi completions bash` from ~/.bashrc")
+                .possible_values(&Shell::variants())
+                .required(true)
+                .index(1)))
+        .after_help(EXIT_STATUS_HELP)
+}
+
+
+/// Parse a timeout given as a non-negative number of seconds, e.g. `2.5`.
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| format!("invalid timeout '{}'", s))
+}
+
+
+/// Parse a rate given as a positive number per second, e.g. `10` or `0.5`.
+fn parse_rate(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| format!("invalid rate '{}'", s))
+}
+
+
+/// How to animate the program, if `--animate` was passed.
+fn get_animation(opts: &ArgMatches) -> Option<Animation> {
+    if !opts.is_present(ANIMATE_FLAG) {
+        return None;
+    };
+    // unwraps are safe as clap has already validated the rates
+    let rate = |arg: &str, default: f64| {
+        opts.value_of(arg).map_or(default, |s| parse_rate(s).unwrap())
+    };
+    let defaults = Animation::default();
+    Some(Animation { speed: rate(SPEED_ARG, defaults.speed), fps: rate(FPS_ARG, defaults.fps) })
+}
+
+
+/// Whether Ctrl-C opens the REPL, which by default it only does when there is a terminal to use
+/// the REPL from.
+fn pause_on_interrupt(opts: &ArgMatches) -> bool {
+    if opts.is_present(SANDBOX_FLAG) {
+        return false;
+    };
+    match opts.value_of(ON_INTERRUPT_ARG) {
+        Some(action) => action == "repl",
+        #[cfg(unix)]
+        None => unsafe { libc::isatty(libc::STDIN_FILENO) == 1 },
+        #[cfg(not(unix))]
+        None => false,
+    }
+}
+
+
+/// Parse a size in bytes with an optional binary suffix, e.g. `4096`, `64K`, `16M`, or `1G`.
+fn parse_size(s: &str) -> Result<usize, String> {
+    let (digits, multiplier) = match s.char_indices().last() {
+        Some((i, 'k')) | Some((i, 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'm')) | Some((i, 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'g')) | Some((i, 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size '{}'", s))
+}
+
+
+/// Build the I/O context, reading program input from the input file, replay, or string if one is
+/// provided and recording it if requested, and writing program output to the output file if one is
+/// provided. Fails with a description of the file that could not be opened.
+///
+/// With `--tui`, the input is read up front and the output kept in memory, as the debugger takes
+/// over the terminal, and the program gets no input unless one is provided. The same goes for
+/// `--animate`, except that the input may still come from stdin. With `captured`, the
+/// output is kept there instead, see `--json` and `--expect`.
+fn get_io_context(
+    opts: &ArgMatches,
+    captured: Option<CapturedOutput>,
+) -> Result<Box<dyn IoCtx>, String>
+{
+    let tui = opts.is_present(TUI_FLAG);
+    let animate = opts.is_present(ANIMATE_FLAG);
+    let open = |arg: &str, description: &str| -> Result<Option<std::fs::File>, String> {
+        opts.value_of(arg).map(|filename| {
+            std::fs::File::open(filename).map_err(|e| {
+                format!("{} '{}' could not be read ({})", description, filename, e)
+            })
+        }).transpose()
+    };
+    let input_file = open(INPUT_FILE_ARG, "input file")?
+        .or(open(REPLAY_ARG, "replay file")?)
+        .or(open_fd(opts, FD_IN_ARG)?);
+    let input: Box<dyn Read> = match (input_file, opts.value_of(INPUT_STRING_ARG)) {
+        (Some(file), _) => Box::new(io::BufReader::new(file)),
+        (None, Some(s)) => Box::new(io::Cursor::new(s.to_string().into_bytes())),
+        (None, None) if tui => Box::new(io::empty()),
+        (None, None) => stdin(opts)?,
+    };
+    let mut input: Box<dyn Read> = match opts.value_of(RECORD_INPUT_ARG) {
+        // the recording is unbuffered such that it is complete even if the program never ends
+        Some(filename) => match std::fs::File::create(filename) {
+            Ok(recording) => Box::new(RecordingReader::new(input, recording)),
+            Err(e) => {
+                return Err(format!("recording file '{}' could not be created ({})", filename, e));
+            },
+        },
+        None => input,
+    };
+    if tui || animate {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes).map_err(|e| format!("input could not be read ({})", e))?;
+        let mut ictx = InMemoryIoCtx::default();
+        ictx.write_input(&bytes).map_err(|e| format!("input could not be read ({})", e))?;
+        return Ok(Box::new(ictx));
+    };
+    if let Some(captured) = captured {
+        return Ok(Box::new(ReadWriteIoCtx::new(input, captured)));
+    };
+    let unbuffered = opts.is_present(UNBUFFERED_FLAG);
+    let output_file = match opts.value_of(OUTPUT_ARG) {
+        Some(filename) => Some(std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(opts.is_present(APPEND_FLAG))
+            .truncate(!opts.is_present(APPEND_FLAG))
+            .open(filename)
+            .map_err(|e| format!("output file '{}' could not be created ({})", filename, e))?),
+        None => open_fd(opts, FD_OUT_ARG)?,
+    };
+    if let Some(output) = output_file {
+        // the buffer is flushed when the context is dropped at the end of execution
+        return Ok(if unbuffered {
+            Box::new(ReadWriteIoCtx::new(input, output))
+        } else {
+            Box::new(ReadWriteIoCtx::new(input, io::BufWriter::new(output)))
+        });
+    };
+    Ok(if unbuffered {
+        Box::new(UnbufferedStdIoCtx::with_input(input))
+    } else {
+        Box::new(StdIoCtx::with_input(input))
+    })
+}
+
+
+/// The file descriptor given with `arg` as a file, e.g. one redirected into a WASI sandbox, failing
+/// if it is not open.
+#[cfg(any(unix, target_os = "wasi"))]
+fn open_fd(opts: &ArgMatches, arg: &str) -> Result<Option<std::fs::File>, String> {
+    opts.value_of(arg).map(|fd| {
+        // unwrap is safe as clap has already validated the value
+        let fd: RawFd = fd.parse().unwrap();
+        // the file takes ownership of the descriptor, which must be open for it to be sound
+        if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+            let e = io::Error::last_os_error();
+            return Err(format!("file descriptor {} could not be used ({})", fd, e));
+        };
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    }).transpose()
+}
+
+
+/// File descriptors cannot be given on other platforms.
+#[cfg(not(any(unix, target_os = "wasi")))]
+fn open_fd(_opts: &ArgMatches, _arg: &str) -> Result<Option<std::fs::File>, String> { Ok(None) }
+
+
+/// Program output kept in memory, shared such that it can be read once the `IoCtx` writing it is
+/// done. Written to stdout as well if `echo` is set, and otherwise only kept.
+#[derive(Clone, Default)]
+struct CapturedOutput {
+    output: Rc<RefCell<Vec<u8>>>,
+    echo: bool,
 }
 
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.echo {
+            io::stdout().write_all(buf)?;
+        };
+        self.output.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.echo {
+            io::stdout().flush()?;
+        };
+        Ok(())
+    }
+}
 
-fn get_io_context(unbuffered: bool) -> Box<dyn IoCtx> {
-    if unbuffered {
-        Box::new(UnbufferedStdIoCtx::default())
+
+/// The output expected with `--expect`: the contents of the file it names if there is one, and
+/// otherwise the value itself.
+fn read_expected(expected: &str) -> Result<Vec<u8>, String> {
+    if Path::new(expected).is_file() {
+        std::fs::read(expected).map_err(|e| {
+            format!("expected output file '{}' could not be read ({})", expected, e)
+        })
     } else {
-        Box::new(StdIoCtx::default())
+        Ok(expected.as_bytes().to_vec())
+    }
+}
+
+
+/// Stdin as program input, switched out of line-buffered mode with `--raw-input` and giving up on
+/// reads after `--input-timeout`.
+fn stdin(opts: &ArgMatches) -> Result<Box<dyn Read>, String> {
+    #[cfg(unix)]
+    {
+        // unwrap is safe as clap has already validated the timeout
+        let timeout = opts.value_of(INPUT_TIMEOUT_ARG).map(|s| parse_timeout(s).unwrap());
+        if opts.is_present(RAW_INPUT_FLAG) {
+            let stdin = RawStdin::new()
+                .map_err(|e| format!("terminal could not be switched to raw input ({})", e))?;
+            return Ok(match timeout {
+                Some(timeout) => Box::new(stdin.with_timeout(timeout)),
+                None => Box::new(stdin),
+            });
+        };
+        if let Some(timeout) = timeout {
+            return Ok(Box::new(TimeoutStdin::new(timeout)));
+        };
     }
+    Ok(Box::new(io::stdin()))
+}
+
+
+/// Build the tracer requested by the trace options, if any.
+fn get_tracer(opts: &ArgMatches) -> io::Result<Option<Tracer<'static>>> {
+    let tracing = opts.is_present(TRACE_FLAG)
+        || opts.is_present(TRACE_FILE_ARG)
+        || opts.is_present(TRACE_LAST_ARG);
+    if !tracing {
+        return Ok(None);
+    };
+    let tracer = match opts.value_of(TRACE_FILE_ARG) {
+        Some(filename) => Tracer::new(std::fs::File::create(filename)?),
+        None => Tracer::new(io::stderr()),
+    };
+    // unwrap is safe as clap has already validated the value
+    Ok(Some(match opts.value_of(TRACE_LAST_ARG) {
+        Some(n) => tracer.keep_last(n.parse().unwrap()),
+        None => tracer,
+    }))
+}
+
+
+/// Build the interpreter configuration from the provided options.
+fn get_interpreter_config(opts: &ArgMatches) -> InterpreterConfig {
+    get_program_config(opts, &Header::default())
+}
+
+
+/// Build the interpreter configuration of a program with `header` from the provided options,
+/// which take precedence over the header as it does over the configuration file. The header is
+/// applied before `--sandbox` such that a program cannot lift the limits of the sandbox.
+fn get_program_config(opts: &ArgMatches, header: &Header) -> InterpreterConfig {
+    let config = InterpreterConfig {
+        // unwrap is safe as clap has already validated the value against `possible_values`
+        tape_mode: opts.value_of(TAPE_MODE_ARG).unwrap().parse::<TapeMode>().unwrap(),
+        bounds: opts.value_of(BOUNDS_ARG).unwrap().parse::<BoundsPolicy>().unwrap(),
+        tape_size: opts.value_of(TAPE_SIZE_ARG).map_or(DEFAULT_TAPE_SIZE, |s| s.parse().unwrap()),
+        tape_capacity: opts.value_of(TAPE_SIZE_ARG).map_or(0, |s| s.parse().unwrap()),
+        cell_size: opts.value_of(CELL_SIZE_ARG).unwrap().parse::<CellSize>().unwrap(),
+        overflow: opts.value_of(OVERFLOW_ARG).unwrap().parse::<OverflowPolicy>().unwrap(),
+        eof: opts.value_of(EOF_ARG).unwrap().parse::<EofBehavior>().unwrap(),
+        io_mode: opts.value_of(IO_ARG).unwrap().parse::<IoMode>().unwrap(),
+        breakpoint_action: opts
+            .value_of(BREAKPOINT_ACTION_ARG)
+            .unwrap()
+            .parse::<BreakpointAction>()
+            .unwrap(),
+        optimize: !opts.is_present(NO_OPTIMIZE_FLAG),
+        jit: !opts.is_present(NO_JIT_FLAG),
+        // unwrap is safe as clap has already validated the limits
+        max_steps: opts.value_of(MAX_STEPS_ARG).map(|s| s.parse().unwrap()),
+        timeout: opts.value_of(TIMEOUT_ARG).map(|s| parse_timeout(s).unwrap()),
+        max_memory: opts.value_of(MAX_MEMORY_ARG).map(|s| parse_size(s).unwrap()),
+        history: opts.value_of(HISTORY_ARG).map_or(0, |s| s.parse().unwrap()),
+        max_call_depth: opts
+            .value_of(MAX_CALL_DEPTH_ARG)
+            .map_or(DEFAULT_MAX_CALL_DEPTH, |s| s.parse().unwrap()),
+        seed: opts.value_of(SEED_ARG).map_or(0, |s| s.parse().unwrap()),
+        delay: opts.value_of(DELAY_ARG).map(|s| Duration::from_millis(s.parse().unwrap())),
+        delay_output_only: opts.is_present(DELAY_OUTPUT_ONLY_FLAG),
+        tapes: opts.value_of(TAPES_ARG).map_or(DEFAULT_TAPES, |s| s.parse().unwrap()),
+        tape_backend: opts
+            .value_of(TAPE_BACKEND_ARG)
+            .map_or(TapeBackend::Vec, |s| s.parse().unwrap()),
+    };
+    let given = |name: &str| opts.occurrences_of(name) > 0;
+    let config = header.apply(settings().apply(config, given), given);
+    if opts.is_present(SANDBOX_FLAG) { config.sandboxed() } else { config }
+}
+
+
+/// Settings of the configuration file, see `load_settings`.
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+
+/// Read the configuration file named by `$BFI_CONFIG`, or else `bfi/config.toml` in
+/// `$XDG_CONFIG_HOME` or `~/.config`, which is not required to exist unless it is named. An empty
+/// `$BFI_CONFIG` reads no file at all.
+fn load_settings() -> Result<Settings, String> {
+    if let Some(path) = std::env::var_os("BFI_CONFIG") {
+        return if path.is_empty() { Ok(Settings::default()) } else { Settings::load(path) };
+    };
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    match dir.map(|dir| dir.join("bfi").join("config.toml")) {
+        Some(path) if path.exists() => Settings::load(path),
+        _ => Ok(Settings::default()),
+    }
+}
+
+
+/// The settings read by `main` from the configuration file.
+fn settings() -> &'static Settings { SETTINGS.get_or_init(Settings::default) }
+
+
+/// The dialect selected by the options, failing if the `--charset` file is unusable or the
+/// dialect cannot be extended as requested.
+fn get_dialect(opts: &ArgMatches) -> Result<Box<dyn Dialect>, String> {
+    let dialect: Box<dyn Dialect> = match opts.value_of(CHARSET_ARG) {
+        Some(filename) => Box::new(Substitution::load(filename)?),
+        None => {
+            // unwraps are safe as clap has already validated the values against `possible_values`
+            let name = opts.value_of(DIALECT_ARG).unwrap();
+            let dialect = dialect::from_name(name).unwrap();
+            let extensions: Vec<Extension> = opts
+                .values_of(EXTENSIONS_ARG)
+                .map_or_else(Vec::new, |values| values.map(|s| s.parse().unwrap()).collect());
+            if extensions.contains(&Extension::Ext1) && extensions.contains(&Extension::Grid) {
+                return Err("the ext1 and grid extensions both use '^'".to_string());
+            };
+            match (name, extensions.is_empty()) {
+                (_, true) => dialect,
+                // the characters of the extensions are part of the words of Ook!
+                ("ook", false) => return Err("the ook dialect cannot be extended".to_string()),
+                _ => Box::new(Extended::new(dialect, extensions)),
+            }
+        },
+    };
+    // the debugging commands of a sandboxed program could dump the state or open the REPL
+    let strict = opts.is_present(STRICT_FLAG) || opts.is_present(SANDBOX_FLAG);
+    Ok(if strict { Box::new(Strict::new(dialect)) } else { dialect })
+}
+
+
+/// Print the instruction listing of the compiled program to stderr, followed by a summary of the
+/// optimizations applied to it.
+fn dump_ir(program: &Program, config: &InterpreterConfig) {
+    if config.optimizes() {
+        let (optimized, report) = optimizer::optimize_with_report(program);
+        eprint!("{}", optimized);
+        eprintln!("bfi: optimizations: {}", report);
+    } else {
+        eprint!("{}", program);
+        eprintln!("bfi: optimizations: disabled");
+    };
+}
+
+
+/// Debugging and persistence options applied to an `ExecutionContext` by `execute_program`.
+struct ExecutionOptions<'a> {
+    breakpoints: Vec<Breakpoint>,
+    tracer: Option<Tracer<'static>>,
+    profile: Option<ProfileOutput>,
+    /// Collect `ExecutionStats` during execution.
+    stats: bool,
+    /// File to resume execution from, see `Snapshot`.
+    restore: Option<&'a str>,
+    /// File to save the state to once execution stops.
+    snapshot_on_exit: Option<&'a str>,
+    /// File to draw the tape over time to, see `Timeline`.
+    visualize: Option<&'a str>,
+    /// Run the program in the full-screen debugger, see `run_tui`.
+    tui: bool,
+    /// Animate the program as it runs, see `run_animation`.
+    animation: Option<Animation>,
+    /// Open the REPL on Ctrl-C rather than stopping the program, see `handle_interrupt`.
+    pause_on_interrupt: bool,
+    /// File to write the coverage to as an lcov tracefile, naming the source `filename`.
+    lcov: Option<&'a str>,
+    /// File the program was read from, if any.
+    filename: Option<&'a str>,
+    /// Prompt of the interactive session, see `ExecutionContext::with_prompt`.
+    prompt: Option<&'a str>,
+}
+
+
+/// Execute a program to completion on a tape of cells of type `C`, or start an interactive session
+/// if no program is provided, returning how execution ended along with its statistics if they
+/// were collected.
+fn execute_program<C: Cell>(
+    ictx: RefMut<Box<dyn IoCtx>>,
+    program: Option<Program>,
+    config: InterpreterConfig,
+    options: ExecutionOptions,
+) -> (ExecutionStatus<BfError>, Option<ExecutionStats>)
+{
+    let interactive = program.is_none();
+    let mut ectx = ExecutionContext::<C>::with_program(ictx, program.unwrap_or_default())
+        .with_config(config);
+    #[cfg(unix)]
+    {
+        if !interactive {
+            let token = handle_interrupt();
+            ectx = if options.pause_on_interrupt {
+                ectx.with_pause_token(token)
+            } else {
+                ectx.with_cancel_token(token)
+            };
+        };
+    }
+    for breakpoint in options.breakpoints {
+        ectx = ectx.with_breakpoint(breakpoint);
+    }
+    if let Some(tracer) = options.tracer {
+        ectx = ectx.with_tracer(tracer);
+    };
+    if options.profile.is_some() {
+        ectx = ectx.with_profiling();
+    };
+    if let Some(prompt) = options.prompt {
+        ectx = ectx.with_prompt(prompt);
+    };
+    if options.stats {
+        ectx = ectx.with_stats();
+    };
+    if options.visualize.is_some() {
+        ectx = ectx.with_timeline();
+    };
+    if let Some(filename) = options.restore {
+        match Snapshot::load(filename) {
+            Ok(snapshot) => ectx = ectx.with_snapshot(snapshot),
+            Err(e) => {
+                // nothing has been executed, so there is no output to flush before exiting
+                eprintln!("bfi: snapshot '{}' could not be restored ({})", filename, e);
+                exit(ExitCode::UsageError);
+            },
+        };
+    };
+    let mut status = if options.tui {
+        ectx = ectx.without_repl();
+        run_tui(&mut ectx)
+    } else if let Some(animation) = options.animation {
+        ectx = ectx.without_repl();
+        run_animation(&mut ectx, animation)
+    } else if interactive {
+        ectx.run_session()
+    } else {
+        ectx.execute()
+    };
+    if let Some(filename) = options.snapshot_on_exit {
+        if let Err(e) = ectx.snapshot().save(filename) {
+            eprintln!("bfi: snapshot could not be saved to '{}' ({})", filename, e);
+            if let ExecutionStatus::Terminated = status {
+                status = ExecutionStatus::InternalError(e);
+            };
+        };
+    };
+    if let (Some(filename), Some(timeline)) = (options.visualize, ectx.timeline()) {
+        if let Err(e) = std::fs::write(filename, timeline.to_svg()) {
+            eprintln!("bfi: visualization could not be saved to '{}' ({})", filename, e);
+            if let ExecutionStatus::Terminated = status {
+                status = ExecutionStatus::InternalError(BfError::from(e));
+            };
+        };
+    };
+    match (ectx.profile(), options.profile) {
+        (Some(profile), Some(ProfileOutput::Annotate { color })) => {
+            eprintln!("bfi: profile: {}", profile.annotate(ectx.program(), color));
+        },
+        (Some(profile), Some(ProfileOutput::Coverage)) => {
+            let coverage = Coverage::new(profile, ectx.program());
+            eprintln!("bfi: coverage: {}", coverage.listing());
+            if let Some(filename) = options.lcov {
+                let name = options.filename.unwrap_or(STDIN_FILENAME);
+                if let Err(e) = std::fs::write(filename, coverage.lcov(name)) {
+                    eprintln!("bfi: coverage could not be saved to '{}' ({})", filename, e);
+                    if let ExecutionStatus::Terminated = status {
+                        status = ExecutionStatus::InternalError(BfError::from(e));
+                    };
+                };
+            };
+        },
+        (Some(profile), _) => eprintln!("bfi: profile: {}", profile.report(ectx.program())),
+        (None, _) => {},
+    };
+    (status, ectx.stats().cloned())
+}
+
+
+/// Token cancelled by Ctrl-C, see `handle_interrupt`.
+#[cfg(unix)]
+static INTERRUPT: OnceLock<CancelToken> = OnceLock::new();
+
+/// Handler for Ctrl-C replaced by `handle_interrupt`, e.g. that of `RawStdin` restoring the
+/// terminal, or `SIG_DFL`.
+#[cfg(unix)]
+static PREVIOUS_INTERRUPT: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+
+
+/// Cancel the returned token with Ctrl-C, such that the program is paused in the REPL or stopped
+/// gracefully with its output flushed and its snapshot and statistics written as requested. A
+/// program waiting for input gives up on it as the read is interrupted. A second Ctrl-C before
+/// the first is acted on kills `bfi` as usual, through the handler replaced if there was one.
+#[cfg(unix)]
+fn handle_interrupt() -> CancelToken {
+    extern "C" fn on_interrupt(signal: libc::c_int) {
+        match (INTERRUPT.get(), PREVIOUS_INTERRUPT.load(Ordering::Relaxed)) {
+            (Some(token), _) if !token.is_cancelled() => token.cancel(),
+            (_, libc::SIG_DFL) | (_, libc::SIG_IGN) => unsafe {
+                // safe as both are async-signal-safe
+                libc::signal(signal, libc::SIG_DFL);
+                libc::raise(signal);
+            },
+            // safe as only handlers taking just the signal are saved, see below
+            (_, previous) => unsafe {
+                std::mem::transmute::<libc::sighandler_t, extern "C" fn(libc::c_int)>(previous)(
+                    signal,
+                )
+            },
+        };
+    }
+    let token = INTERRUPT.get_or_init(CancelToken::new).clone();
+    let handler = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // safe as the handler only sets an atomic flag or defers to the previous one, and the actions
+    // are fully initialized. Without `SA_RESTART`, a read waiting for input is interrupted.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        libc::sigaction(libc::SIGINT, &action, &mut previous);
+        if previous.sa_sigaction != handler && previous.sa_flags & libc::SA_SIGINFO == 0 {
+            PREVIOUS_INTERRUPT.store(previous.sa_sigaction, Ordering::Relaxed);
+        };
+    }
+    token
+}
+
+
+/// Run the program in the full-screen debugger, writing its output to stdout once the debugger is
+/// quit. Quitting before the program ends stops it without an error.
+#[cfg(feature = "tui")]
+fn run_tui<C: Cell>(ectx: &mut ExecutionContext<C>) -> ExecutionStatus<BfError> {
+    let written = bfi::tui::debug(ectx).and_then(|output| io::stdout().write_all(&output));
+    if let Err(e) = written {
+        return ExecutionStatus::InternalError(BfError::from(e));
+    };
+    // quitting is not an interruption, so the program is cancelled but ends without an error
+    if matches!(ectx.status, ExecutionStatus::NotStarted | ExecutionStatus::InProgress) {
+        ectx.cancel();
+        return ExecutionStatus::Terminated;
+    };
+    ectx.status.clone()
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui<C: Cell>(_: &mut ExecutionContext<C>) -> ExecutionStatus<BfError> {
+    unreachable!("--tui is only accepted when built with the tui feature")
+}
+
+
+/// Run the program to completion while animating it on stderr, writing its output to stdout once
+/// it ends.
+fn run_animation<C: Cell>(
+    ectx: &mut ExecutionContext<C>,
+    animation: Animation,
+) -> ExecutionStatus<BfError>
+{
+    let written = animate::animate(ectx, animation, &mut io::stderr())
+        .and_then(|output| io::stdout().write_all(&output));
+    if let Err(e) = written {
+        return ExecutionStatus::InternalError(BfError::from(e));
+    };
+    ectx.status.clone()
+}
+
+
+/// Describe how execution ended for `--json` as a single line of JSON: the status, exit code,
+/// output (as base64), the error if there was one, and the statistics if execution began.
+fn json_report(
+    status: &str,
+    retcode: ExitCode,
+    output: &[u8],
+    error: Option<&BfError>,
+    stats: Option<&ExecutionStats>,
+) -> Json
+{
+    Json::object(vec![
+        ("status", Json::from(status)),
+        ("exitCode", Json::from(retcode as i64)),
+        ("output", Json::from(json::base64(output))),
+        ("error", error.map_or(Json::Null, Json::from)),
+        ("stats", stats.map_or(Json::Null, Json::from)),
+    ])
+}
+
+
+/// Read a program from the provided file, or from stdin if the filename is `-`, as either source
+/// or bytecode.
+fn read_program_bytes(filename: &str) -> io::Result<Vec<u8>> {
+    if filename == STDIN_FILENAME {
+        let mut contents = Vec::new();
+        io::stdin().read_to_end(&mut contents)?;
+        Ok(contents)
+    } else {
+        std::fs::read(filename)
+    }
+}
+
+
+/// Read the source of a program from the provided file, or from stdin if the filename is `-`.
+fn read_program_file(filename: &str) -> io::Result<String> {
+    String::from_utf8(read_program_bytes(filename)?).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+    })
+}
+
+
+/// Parse `source` read from the provided file, or given inline if there is none, first expanding
+/// it with the preprocessor if `--preprocess` is set.
+///
+/// A `#!` line at the start of the source is skipped, such that programs can be made executable
+/// scripts, as is the header of settings below it, which is applied by the caller.
+fn parse_program(
+    opts: &ArgMatches,
+    source: &str,
+    filename: Option<&str>,
+    dialect: &dyn Dialect,
+) -> Result<Program, String> {
+    // the lines are left empty rather than removed to keep the locations of the rest the same
+    let source = &Header::strip(source);
+    let source = match source.strip_prefix("#!") {
+        Some(rest) => &rest[rest.find('\n').unwrap_or(rest.len())..],
+        None => source,
+    };
+    if !opts.is_present(PREPROCESS_FLAG) {
+        return Ok(Program::parse_dialect(source, dialect));
+    };
+    let (expanded, source_map) = match filename.filter(|&filename| filename != STDIN_FILENAME) {
+        Some(filename) => {
+            let dir = Path::new(filename).parent().unwrap_or_else(|| Path::new(""));
+            preprocess::preprocess(source, filename, dir)?
+        },
+        None => preprocess::preprocess(source, "<program>", Path::new(""))?,
+    };
+    Ok(Program::parse_dialect(&expanded, dialect).with_source_map(source_map))
+}
+
+
+/// Combine the program files run with `--shared-tape` into a single program executing each in
+/// turn, failing with the exit code and error if any of them cannot be read or is invalid.
+///
+/// Running the programs one after another on the same tape is the same as running the
+/// concatenation of their sources, as long as every loop starts and ends in the same program.
+fn link_programs(
+    opts: &ArgMatches,
+    filenames: &[&str],
+    dialect: &dyn Dialect,
+) -> Result<Program, (ExitCode, String)> {
+    let mut parts = Vec::new();
+    for &filename in filenames {
+        let source = read_program_file(filename).map_err(|e| {
+            (ExitCode::UsageError, format!("file '{}' could not be read ({})", filename, e))
+        })?;
+        let mut program = parse_program(opts, &source, Some(filename), dialect)
+            .map_err(|e| (ExitCode::ParseError, e))?;
+        if program.source_map().is_none() {
+            // errors name the file they are in, as it can be any of them
+            let source_map = SourceMap::plain(filename, program.source());
+            program = program.with_source_map(source_map);
+        };
+        program.verify().map_err(|e| (ExitCode::ParseError, e.to_string()))?;
+        let source_map = program.source_map().cloned().unwrap_or_default();
+        parts.push((program.source().to_string(), source_map));
+    }
+    let (source, source_map) = SourceMap::concatenate(parts);
+    Ok(Program::parse_dialect(&source, dialect).with_source_map(source_map))
+}
+
+
+/// Compile the program file named in the `compile` or `optimize` subcommand options for `target`,
+/// returning the exit code.
+fn compile_program(opts: &ArgMatches, target: Target) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let program = match parse_program(opts, &source, Some(filename), &*dialect) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::ParseError;
+        },
+    };
+    if let Err(e) = program.verify() {
+        eprintln!("bfi: {}", e);
+        return ExitCode::ParseError;
+    };
+    let config = get_interpreter_config(opts);
+    let compiled = match compile::compile(&program, target, &config) {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            eprintln!("bfi: compilation failed ({})", e);
+            return ExitCode::InternalError;
+        },
+    };
+    write_output(opts, &compiled, "compiled")
+}
+
+
+/// Run the program file named in the `difftest` subcommand options with and without optimization,
+/// printing the differences between the two and returning the exit code.
+fn difftest_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let program = match parse_program(opts, &source, Some(filename), &*dialect) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::ParseError;
+        },
+    };
+    if let Err(e) = program.verify() {
+        eprintln!("bfi: {}", e);
+        return ExitCode::ParseError;
+    };
+    // both runs are given the same input, so it is read in full up front
+    let input = match (opts.value_of(INPUT_FILE_ARG), opts.value_of(INPUT_STRING_ARG)) {
+        (Some(filename), _) => std::fs::read(filename).map_err(|e| {
+            format!("input file '{}' could not be read ({})", filename, e)
+        }),
+        (None, Some(s)) => Ok(s.as_bytes().to_vec()),
+        (None, None) => {
+            let mut input = Vec::new();
+            io::stdin().read_to_end(&mut input).map(|_| input).map_err(|e| {
+                format!("input could not be read ({})", e)
+            })
+        },
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let config = get_interpreter_config(opts);
+    let (reference, optimized, limited, differences) = match config.cell_size {
+        CellSize::U8 => report(difftest::difftest::<u8>(&program, &input, &config)),
+        CellSize::U16 => report(difftest::difftest::<u16>(&program, &input, &config)),
+        CellSize::U32 => report(difftest::difftest::<u32>(&program, &input, &config)),
+        CellSize::I32 => report(difftest::difftest::<i32>(&program, &input, &config)),
+    };
+    println!("reference: {}", reference);
+    println!("optimized: {}", optimized);
+    if limited {
+        println!("not compared, as execution was stopped by a limit");
+        ExitCode::LimitExceeded
+    } else if differences.is_empty() {
+        println!("no differences");
+        ExitCode::Success
+    } else {
+        for difference in differences {
+            println!("{}", difference);
+        }
+        ExitCode::Mismatch
+    }
+}
+
+
+/// Compare the two program files named in the `equiv` subcommand options on their inputs,
+/// printing the first input they differ on if any and returning the exit code.
+fn equiv_programs(opts: &ArgMatches) -> ExitCode {
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let load = |filename: &str| -> Result<Program, (ExitCode, String)> {
+        let source = read_program_file(filename).map_err(|e| {
+            (ExitCode::UsageError, format!("file '{}' could not be read ({})", filename, e))
+        })?;
+        let program = parse_program(opts, &source, Some(filename), &*dialect)
+            .map_err(|e| (ExitCode::ParseError, e))?;
+        program.verify().map_err(|e| (ExitCode::ParseError, e.to_string()))?;
+        Ok(program)
+    };
+    // unwraps are safe as both files are required, and INPUTS and SEED have defaults validated by
+    // clap
+    let programs = load(opts.value_of(FILE_ARG).unwrap())
+        .and_then(|a| Ok((a, load(opts.value_of(OTHER_FILE_ARG).unwrap())?)));
+    let (a, b) = match programs {
+        Ok(programs) => programs,
+        Err((retcode, e)) => {
+            eprintln!("bfi: {}", e);
+            return retcode;
+        },
+    };
+    let seed = opts.value_of(SEED_ARG).unwrap().parse().unwrap();
+    let inputs = opts.value_of(INPUTS_ARG).unwrap().parse::<Inputs>().unwrap();
+    let inputs = match inputs.load(seed) {
+        Ok(inputs) => inputs,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let mut config = get_interpreter_config(opts);
+    if config.max_steps.is_none() && config.timeout.is_none() {
+        config.max_steps = Some(equiv::DEFAULT_MAX_STEPS);
+    };
+    let report = match config.cell_size {
+        CellSize::U8 => equiv::compare::<u8>(&a, &b, &inputs, &config),
+        CellSize::U16 => equiv::compare::<u16>(&a, &b, &inputs, &config),
+        CellSize::U32 => equiv::compare::<u32>(&a, &b, &inputs, &config),
+        CellSize::I32 => equiv::compare::<i32>(&a, &b, &inputs, &config),
+    };
+    match report.divergence {
+        Some(divergence) => {
+            println!(
+                "programs differ on input {} \"{}\", {}",
+                divergence.index,
+                divergence.input.escape_ascii(),
+                divergence.difference,
+            );
+            ExitCode::Mismatch
+        },
+        None => {
+            println!("no differences on {} inputs", report.compared);
+            if report.inconclusive > 0 {
+                println!(
+                    "{} of them not compared in full, as execution was stopped by a limit",
+                    report.inconclusive,
+                );
+            };
+            ExitCode::Success
+        },
+    }
+}
+
+
+/// Run the suite of programs named in the `batch` subcommand options, printing the verdict of each
+/// and a summary, returning the exit code.
+fn batch_programs(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as SUITE is required
+    let cases = match batch::discover(Path::new(opts.value_of(SUITE_ARG).unwrap())) {
+        Ok(cases) => cases,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    // cases that cannot be read or parsed fail without running, the rest are run together
+    let loaded: Vec<(String, Result<Test, String>)> = cases.into_iter().map(|case| {
+        let read = |path: &Path| {
+            std::fs::read(path).map_err(|e| {
+                format!("file '{}' could not be read ({})", path.display(), e)
+            })
+        };
+        let load = || {
+            let filename = case.program.display().to_string();
+            let source = read_program_file(&filename).map_err(|e| {
+                format!("file '{}' could not be read ({})", filename, e)
+            })?;
+            let program = parse_program(opts, &source, Some(&filename), &*dialect)?;
+            program.verify().map_err(|e| e.to_string())?;
+            Ok(Test {
+                name: case.name.clone(),
+                program,
+                input: case.input.as_deref().map_or(Ok(Vec::new()), read)?,
+                expected: case.expected.as_deref().map(read).transpose()?,
+            })
+        };
+        (case.name.clone(), load())
+    }).collect();
+    let tests: Vec<Test> = loaded.iter().filter_map(|(_, test)| test.clone().ok()).collect();
+    // unwrap is safe as clap has already validated the value
+    let jobs = opts.value_of(JOBS_ARG).map_or_else(
+        || std::thread::available_parallelism().map_or(1, |n| n.get()),
+        |n| n.parse().unwrap(),
+    );
+    let mut verdicts = batch::run(&tests, &get_interpreter_config(opts), jobs).into_iter();
+    let mut failed = 0;
+    for (name, test) in &loaded {
+        // unwrap is safe as there is a verdict for each test that was loaded
+        let verdict = match test {
+            Ok(_) => verdicts.next().unwrap(),
+            Err(e) => Verdict::Fail(e.clone()),
+        };
+        match verdict {
+            Verdict::Pass => println!("pass {}", name),
+            Verdict::Fail(reason) => {
+                failed += 1;
+                println!("FAIL {}: {}", name, reason);
+            },
+        };
+    }
+    println!("{} passed, {} failed", loaded.len() - failed, failed);
+    if failed == 0 { ExitCode::Success } else { ExitCode::Mismatch }
+}
+
+
+/// Print the instruction listing of the bytecode file named in the `disasm` subcommand options,
+/// returning the exit code.
+fn disasm_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let bytes = match read_program_bytes(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    if !Bytecode::is_bytecode(&bytes) {
+        eprintln!("bfi: file '{}' is not bytecode, see 'bfi compile --target bytecode'", filename);
+        return ExitCode::UsageError;
+    };
+    match Bytecode::decode(&bytes) {
+        Ok(bytecode) => {
+            print!("{}", bytecode);
+            ExitCode::Success
+        },
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            ExitCode::ParseError
+        },
+    }
+}
+
+
+/// Format the program file named in the `fmt` subcommand options, or check that it is formatted
+/// with `--check`, returning the exit code.
+fn format_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required and WIDTH has a default validated by clap
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let options = FormatOptions {
+        width: opts.value_of(WIDTH_ARG).unwrap().parse().unwrap(),
+        strip_comments: opts.is_present(STRIP_COMMENTS_FLAG),
+    };
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    if let Err(e) = Program::parse(&source).verify() {
+        eprintln!("bfi: {}", e);
+        return ExitCode::ParseError;
+    };
+    let formatted = format::format(&source, &options);
+    if opts.is_present(CHECK_FLAG) {
+        return if formatted == source {
+            ExitCode::Success
+        } else {
+            eprintln!("bfi: file '{}' is not formatted", filename);
+            ExitCode::Mismatch
+        };
+    };
+    write_output(opts, formatted.as_bytes(), "formatted")
+}
+
+
+/// Minify the program file named in the `minify` subcommand options, returning the exit code.
+fn minify_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    if let Err(e) = Program::parse(&source).verify() {
+        eprintln!("bfi: {}", e);
+        return ExitCode::ParseError;
+    };
+    let minified = format::minify(&source) + "\n";
+    write_output(opts, minified.as_bytes(), "minified")
+}
+
+
+/// Print the score of the program file named in the `score` subcommand options, returning the
+/// exit code.
+fn score_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    let score = if opts.is_present(MINIFY_FLAG) {
+        if let Err(e) = Program::parse(&source).verify() {
+            eprintln!("bfi: {}", e);
+            return ExitCode::ParseError;
+        };
+        format::score(&format::minify(&source))
+    } else {
+        format::score(&source)
+    };
+    println!("commands: {}", score.commands);
+    println!("bytes: {}", score.bytes);
+    println!("non-command characters: {}", score.non_commands);
+    ExitCode::Success
+}
+
+
+/// Print the warnings found in the program file named in the `lint` subcommand options, returning
+/// the exit code.
+fn lint_program(opts: &ArgMatches) -> ExitCode {
+    // unwrap is safe as FILE is required
+    let filename = opts.value_of(FILE_ARG).unwrap();
+    let source = match read_program_file(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("bfi: file '{}' could not be read ({})", filename, e);
+            return ExitCode::UsageError;
+        },
+    };
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    let program = match parse_program(opts, &source, Some(filename), &*dialect) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::ParseError;
+        },
+    };
+    let warnings = lint::lint(&program, &get_interpreter_config(opts));
+    for warning in &warnings {
+        eprintln!("bfi: warning: {}", warning);
+    }
+    if warnings.is_empty() { ExitCode::Success } else { ExitCode::Warnings }
+}
+
+
+/// Serve the Language Server Protocol on stdin and stdout until the editor exits, returning the
+/// exit code.
+fn serve_lsp() -> ExitCode {
+    let stdin = io::stdin();
+    match lsp::serve(stdin.lock(), io::stdout()) {
+        Ok(true) => ExitCode::Success,
+        // the protocol asks for a failure when told to exit without being shut down first
+        Ok(false) => ExitCode::ProgramError,
+        Err(e) => {
+            eprintln!("bfi: lsp: {}", e);
+            ExitCode::InternalError
+        },
+    }
+}
+
+
+/// Serve the Debug Adapter Protocol on stdin and stdout until the editor disconnects, returning
+/// the exit code.
+fn serve_dap() -> ExitCode {
+    match dap::serve(io::stdin(), io::stdout()) {
+        Ok(()) => ExitCode::Success,
+        Err(e) => {
+            eprintln!("bfi: dap: {}", e);
+            ExitCode::InternalError
+        },
+    }
+}
+
+
+/// Serve the HTTP API of `serve::serve` on the address given by the options until killed,
+/// returning the exit code if the server could not start.
+fn serve_http(opts: &ArgMatches) -> ExitCode {
+    // programs are run as with `--sandbox`, see `serve::serve`
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => Strict::new(dialect),
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            return ExitCode::UsageError;
+        },
+    };
+    // unwraps are safe as both have defaults and the port has already been validated
+    let bind = opts.value_of(BIND_ARG).unwrap();
+    let port: u16 = opts.value_of(PORT_ARG).unwrap().parse().unwrap();
+    let listener = match TcpListener::bind((bind, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("bfi: serve: cannot listen on {}:{} ({})", bind, port, e);
+            return ExitCode::InternalError;
+        },
+    };
+    // the address listened on, as port 0 is any free port
+    if let Ok(address) = listener.local_addr() {
+        eprintln!("bfi: serving on http://{}", address);
+    };
+    let jobs = opts.value_of(JOBS_ARG).map_or_else(
+        || std::thread::available_parallelism().map_or(1, |n| n.get()),
+        |n| n.parse().unwrap(),
+    );
+    serve::serve(&listener, &get_interpreter_config(opts), &dialect, jobs)
+}
+
+
+/// Write the result of a subcommand to the file given with `--output`, or to stdout, returning the
+/// exit code. `what` describes the result in the error message if it could not be written.
+fn write_output(opts: &ArgMatches, contents: &[u8], what: &str) -> ExitCode {
+    let written = match opts.value_of(OUTPUT_ARG) {
+        Some(output) => std::fs::write(output, contents),
+        None => {
+            let mut stdout = io::stdout();
+            stdout.write_all(contents).and_then(|()| stdout.flush())
+        },
+    };
+    match written {
+        Ok(()) => ExitCode::Success,
+        Err(e) => {
+            eprintln!("bfi: {} program could not be written ({})", what, e);
+            ExitCode::InternalError
+        },
+    }
+}
+
+
+/// Summaries of the two runs of a `difftest::Comparison`, whether either was stopped by a limit,
+/// and the differences between them.
+fn report<C: Cell>(comparison: difftest::Comparison<C>) -> (String, String, bool, Vec<String>) {
+    let summary = |outcome: &difftest::Outcome<C>| {
+        format!("{}, {} bytes of output", difftest::describe(&outcome.status), outcome.output.len())
+    };
+    (
+        summary(&comparison.reference),
+        summary(&comparison.optimized),
+        comparison.is_limited(),
+        comparison.differences(),
+    )
+}
+
+
+/// Logger writing the diagnostics of the library to stderr, filtered by `RUST_LOG` as a
+/// comma-separated list of levels, each optionally for a module and the modules within it, e.g.
+/// `debug` or `info,bfi::interpreter=trace`. Nothing is logged without `RUST_LOG`.
+struct Logger {
+    /// Most verbose level of each module, `None` for every module not listed.
+    directives: Vec<(Option<String>, LevelFilter)>,
+}
+
+impl Logger {
+    fn from_env() -> Self {
+        let spec = std::env::var("RUST_LOG").unwrap_or_default();
+        let directives = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .filter_map(|directive| {
+                let (module, level) = match directive.split_once('=') {
+                    Some((module, level)) => (Some(module.to_string()), level),
+                    None => (None, directive),
+                };
+                match level.parse() {
+                    Ok(level) => Some((module, level)),
+                    Err(_) => {
+                        eprintln!("bfi: ignoring invalid RUST_LOG directive '{}'", directive);
+                        None
+                    },
+                }
+            })
+            .collect();
+        Logger { directives }
+    }
+
+    /// The level of the most specific directive covering `target`.
+    fn level(&self, target: &str) -> LevelFilter {
+        let covers = |module: &str| {
+            target
+                .strip_prefix(module)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        self.directives
+            .iter()
+            .filter(|(module, _)| module.as_deref().is_none_or(covers))
+            .max_by_key(|(module, _)| module.as_ref().map_or(0, String::len))
+            .map_or(LevelFilter::Off, |&(_, level)| level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives.iter().map(|&(_, level)| level).max().unwrap_or(LevelFilter::Off)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("bfi: {} {}: {}", record.level(), record.target(), record.args());
+        };
+    }
+
+    fn flush(&self) {}
 }
 
 
 fn main() {
-    let opts = get_command_line_args();
+    let logger = Logger::from_env();
+    log::set_max_level(logger.max_level());
+    // only fails if a logger was already set, which nothing else does
+    let _ = log::set_logger(Box::leak(Box::new(logger)));
+    match load_settings() {
+        Ok(settings) => SETTINGS.get_or_init(|| settings),
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            exit(ExitCode::UsageError);
+        },
+    };
+    let top_level_opts = get_command_line_args();
+    if let Some(opts) = top_level_opts.subcommand_matches(COMPILE_SUBCOMMAND) {
+        // unwraps are safe as TARGET has a default validated by clap
+        let target = opts.value_of(TARGET_ARG).unwrap().parse::<Target>().unwrap();
+        exit(compile_program(opts, target));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(OPTIMIZE_SUBCOMMAND) {
+        exit(compile_program(opts, Target::Brainfuck));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(DIFFTEST_SUBCOMMAND) {
+        exit(difftest_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(EQUIV_SUBCOMMAND) {
+        exit(equiv_programs(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(BATCH_SUBCOMMAND) {
+        exit(batch_programs(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(DISASM_SUBCOMMAND) {
+        exit(disasm_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(FMT_SUBCOMMAND) {
+        exit(format_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(MINIFY_SUBCOMMAND) {
+        exit(minify_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(SCORE_SUBCOMMAND) {
+        exit(score_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(LINT_SUBCOMMAND) {
+        exit(lint_program(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(GENERATE_SUBCOMMAND) {
+        // unwrap is safe as TEXT is required
+        let text = opts.value_of(TEXT_ARG).unwrap();
+        let program = generate::generate(text.as_bytes());
+        let program = format::format(&program, &FormatOptions::default());
+        exit(write_output(opts, program.as_bytes(), "generated"));
+    };
+    if top_level_opts.subcommand_matches(LSP_SUBCOMMAND).is_some() {
+        exit(serve_lsp());
+    };
+    if top_level_opts.subcommand_matches(DAP_SUBCOMMAND).is_some() {
+        exit(serve_dap());
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(SERVE_SUBCOMMAND) {
+        exit(serve_http(opts));
+    };
+    if let Some(opts) = top_level_opts.subcommand_matches(COMPLETIONS_SUBCOMMAND) {
+        // unwraps are safe as SHELL is required and validated by clap
+        let shell = opts.value_of(SHELL_ARG).unwrap().parse::<Shell>().unwrap();
+        get_app().gen_completions_to("bfi", shell, &mut io::stdout());
+        exit(ExitCode::Success);
+    };
+    // options for the `run`, `profile`, and `cov` subcommands extend those at the top level
+    let opts = top_level_opts
+        .subcommand_matches(RUN_SUBCOMMAND)
+        .or_else(|| top_level_opts.subcommand_matches(PROFILE_SUBCOMMAND))
+        .or_else(|| top_level_opts.subcommand_matches(COV_SUBCOMMAND))
+        .unwrap_or(&top_level_opts);
 
-    let program_string: String = match (opts.value_of(PROGRAM_ARG), opts.value_of(FILE_ARG)) {
-        (Some(s), None) => s.to_string(),
-        (None, Some(filename)) => match std::fs::read_to_string(filename) {
-            Ok(contents) => contents,
+    // a program naming a file is run from the file instead, such that scripts starting with
+    // `#!/usr/bin/env bfi` can be executed directly
+    let script = opts
+        .value_of(PROGRAM_ARG)
+        .filter(|&program| program == STDIN_FILENAME || Path::new(program).is_file());
+    let filename = opts.value_of(FILE_ARG).or(script);
+    let filenames: Vec<&str> = opts.values_of(FILE_ARG).map_or_else(Vec::new, Iterator::collect);
+    if filenames.len() > 1 && !opts.is_present(SHARED_TAPE_FLAG) {
+        eprintln!("bfi: running several program files requires --shared-tape");
+        exit(ExitCode::UsageError);
+    };
+    let inline_program = match script {
+        Some(_) => None,
+        None => opts.value_of(PROGRAM_ARG).or_else(|| opts.value_of(EVAL_ARG)),
+    };
+    let program_bytes: Option<Vec<u8>> = match (inline_program, filename) {
+        // several program files are read by `link_programs`
+        _ if filenames.len() > 1 => None,
+        (Some(s), None) => Some(s.as_bytes().to_vec()),
+        (None, Some(filename)) => match read_program_bytes(filename) {
+            Ok(contents) => Some(contents),
             Err(e) => {
                 eprintln!("bfi: file '{}' could not be read ({})", filename, e);
-                std::process::exit(1);
+                exit(ExitCode::UsageError);
             }
         },
+        (None, None) if opts.is_present(SANDBOX_FLAG) => {
+            eprintln!("bfi: --sandbox requires a program, as it never opens the REPL");
+            exit(ExitCode::UsageError);
+        },
         // default to REPL if no program provided
-        (None, None) => "!".to_string(),
+        (None, None) => None,
         // final arm should never be reached due to mutual `conflicts_with`
         _ => unreachable!(),
     };
+    let dialect = match get_dialect(opts) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("bfi: {}", e);
+            exit(ExitCode::UsageError);
+        },
+    };
+    // compiled programs are run as they are, with the cell size they were compiled for
+    let bytecode = match program_bytes.as_deref().filter(|bytes| Bytecode::is_bytecode(bytes)) {
+        Some(bytes) => match Bytecode::decode(bytes) {
+            Ok(bytecode) => Some(bytecode),
+            Err(e) => {
+                eprintln!("bfi: {}", e);
+                exit(ExitCode::ParseError);
+            },
+        },
+        None => None,
+    };
+    // settings carried by the program, over which options given explicitly take precedence
+    let header = match (&bytecode, program_bytes.as_deref()) {
+        (Some(bytecode), _) => Header { cell_size: Some(bytecode.cell_size), ..Header::default() },
+        (None, Some(bytes)) => match Header::parse(&String::from_utf8_lossy(bytes)) {
+            Ok(header) => header,
+            Err(e) => {
+                eprintln!("bfi: program header is invalid ({})", e);
+                exit(ExitCode::ParseError);
+            },
+        },
+        (None, None) => Header::default(),
+    };
+    let program = match (bytecode.as_ref(), program_bytes) {
+        _ if filenames.len() > 1 => match link_programs(opts, &filenames, &*dialect) {
+            Ok(program) => Some(program),
+            Err((retcode, e)) => {
+                eprintln!("bfi: {}", e);
+                exit(retcode);
+            },
+        },
+        (Some(bytecode), _) => Some(bytecode.program.clone()),
+        (None, Some(bytes)) => match String::from_utf8(bytes) {
+            Ok(source) => match parse_program(opts, &source, filename, &*dialect) {
+                Ok(program) => Some(program),
+                Err(e) => {
+                    eprintln!("bfi: {}", e);
+                    exit(ExitCode::ParseError);
+                },
+            },
+            Err(_) => {
+                // unwrap is safe as inline programs are always valid UTF-8
+                let filename = filename.unwrap();
+                eprintln!("bfi: file '{}' is neither source nor bytecode", filename);
+                exit(ExitCode::UsageError);
+            },
+        },
+        (None, None) => None,
+    };
+    // reject an invalid program up front to report it separately from errors while it runs
+    if let Some(Err(e)) = program.as_ref().map(Program::verify) {
+        if opts.is_present(JSON_FLAG) {
+            println!("{}", json_report("parse_error", ExitCode::ParseError, &[], Some(&e), None));
+        } else {
+            eprintln!("bfi: {}", e);
+        };
+        exit(ExitCode::ParseError);
+    };
 
     // Creating the io_context inside a block like this ensures that it is dropped before the call
     // to std::process::exit, necessary to flush output buffer for stdout
-    let retcode: i32 = {
-        let io_context = RefCell::new(get_io_context(opts.is_present(UNBUFFERED_FLAG)));
-
-        let execution_status: ExecutionStatus<String> =
-            ExecutionContext::new(io_context.borrow_mut(), program_string.as_str()).execute();
+    let retcode: ExitCode = {
+        let expected = match opts.value_of(EXPECT_ARG).map(read_expected).transpose() {
+            Ok(expected) => expected,
+            Err(e) => {
+                eprintln!("bfi: {}", e);
+                exit(ExitCode::UsageError);
+            },
+        };
+        let captured = (opts.is_present(JSON_FLAG) || expected.is_some()).then(|| {
+            CapturedOutput { echo: expected.is_some(), ..CapturedOutput::default() }
+        });
+        let io_context = match get_io_context(opts, captured.clone()) {
+            Ok(ictx) => RefCell::new(ictx),
+            Err(e) => {
+                eprintln!("bfi: {}", e);
+                exit(ExitCode::UsageError);
+            },
+        };
 
-        match execution_status {
-            ExecutionStatus::Terminated => {
-                if opts.is_present(VERBOSE_ARG) {
-                    eprintln!("bfi: terminated without errors");
-                };
-                0
+        let mut config = get_program_config(opts, &header);
+        if let Some(bytecode) = &bytecode {
+            // the header of bytecode only differs in cell size where `--cell-size` was given
+            if config.cell_size != bytecode.cell_size {
+                eprintln!("bfi: --cell-size differs from the cell size of the bytecode");
+                exit(ExitCode::UsageError);
+            };
+            // optimized when it was compiled, unless that was disabled
+            config.optimize = false;
+        };
+        if top_level_opts.subcommand_matches(COV_SUBCOMMAND).is_some() {
+            // loops like `[>]` would otherwise count as executed in full once they are reached
+            config.optimize = false;
+        };
+        if let (true, Some(program)) = (opts.is_present(DUMP_IR_FLAG), &program) {
+            dump_ir(program, &config);
+        };
+        let ictx = io_context.borrow_mut();
+        // unwrap is safe as clap has already validated each breakpoint
+        let breakpoints: Vec<Breakpoint> = opts
+            .values_of(BREAK_ARG)
+            .map_or(Vec::new(), |values| values.map(|s| s.parse().unwrap()).collect());
+        let tracer = match get_tracer(opts) {
+            Ok(tracer) => tracer,
+            Err(e) => {
+                // unwrap is safe as only the trace file can fail to open
+                let filename = opts.value_of(TRACE_FILE_ARG).unwrap();
+                eprintln!("bfi: trace file '{}' could not be created ({})", filename, e);
+                exit(ExitCode::UsageError);
             },
-            ExecutionStatus::ProgramError(err) | ExecutionStatus::InternalError(err) => {
-                eprintln!("bfi: exited with error: {}", err);
-                1
+        };
+
+        let profile = if top_level_opts.subcommand_matches(COV_SUBCOMMAND).is_some() {
+            Some(ProfileOutput::Coverage)
+        } else if opts.is_present(ANNOTATE_FLAG) {
+            Some(ProfileOutput::Annotate { color: opts.is_present(COLOR_FLAG) })
+        } else if opts.is_present(PROFILE_FLAG)
+            || top_level_opts.subcommand_matches(PROFILE_SUBCOMMAND).is_some()
+        {
+            Some(ProfileOutput::Report)
+        } else {
+            None
+        };
+
+        let options = ExecutionOptions {
+            breakpoints,
+            tracer,
+            profile,
+            stats: opts.is_present(STATS_FLAG) || opts.is_present(JSON_FLAG),
+            restore: opts.value_of(RESTORE_ARG),
+            snapshot_on_exit: opts.value_of(SNAPSHOT_ON_EXIT_ARG),
+            visualize: opts.value_of(VISUALIZE_ARG),
+            tui: opts.is_present(TUI_FLAG),
+            animation: get_animation(opts),
+            pause_on_interrupt: pause_on_interrupt(opts),
+            lcov: opts.value_of(LCOV_ARG),
+            filename,
+            prompt: settings().prompt.as_deref(),
+        };
+
+        let (execution_status, stats) = match config.cell_size {
+            CellSize::U8 => execute_program::<u8>(ictx, program, config, options),
+            CellSize::U16 => execute_program::<u16>(ictx, program, config, options),
+            CellSize::U32 => execute_program::<u32>(ictx, program, config, options),
+            CellSize::I32 => execute_program::<i32>(ictx, program, config, options),
+        };
+        let retcode = ExitCode::from(&execution_status);
+
+        if let (Some(captured), true) = (&captured, opts.is_present(JSON_FLAG)) {
+            let output = captured.output.borrow();
+            println!("{}", json_report(
+                execution_status.name(),
+                retcode,
+                &output,
+                execution_status.error(),
+                stats.as_ref(),
+            ));
+        } else {
+            if let (Some(stats), true) = (&stats, opts.is_present(STATS_FLAG)) {
+                eprintln!("bfi: stats: {}", stats.report());
+            };
+            match &execution_status {
+                ExecutionStatus::Terminated => {
+                    if opts.is_present(VERBOSE_ARG) {
+                        eprintln!("bfi: terminated without errors");
+                    };
+                },
+                ExecutionStatus::ProgramError(err)
+                | ExecutionStatus::InternalError(err)
+                | ExecutionStatus::LimitExceeded(err) => {
+                    eprintln!("bfi: exited with error: {}", err);
+                },
+                ExecutionStatus::Cancelled => eprintln!("bfi: interrupted"),
+                ExecutionStatus::NotStarted | ExecutionStatus::InProgress => {
+                    eprintln!("bfi: exited with error: execution stopped unexpectedly");
+                },
+            };
+        };
+
+        match (&captured, expected) {
+            // a program that failed has already been reported as such
+            (Some(captured), Some(expected)) if retcode == ExitCode::Success => {
+                let _ = io::stdout().flush();
+                let output = captured.output.borrow();
+                if *output == expected {
+                    retcode
+                } else {
+                    eprint!("bfi: output differs from the expected output\n{}", diff::unified(
+                        &String::from_utf8_lossy(&expected),
+                        &String::from_utf8_lossy(&output),
+                        "expected",
+                        "output",
+                    ));
+                    ExitCode::Mismatch
+                }
             },
-            _ => panic!("bfi: internal error"),
+            _ => retcode,
         }
     };
 
-    std::process::exit(retcode);
+    exit(retcode);
 }
@@ -0,0 +1,376 @@
+//! Compact binary encoding of compiled programs, such that large programs can be run repeatedly
+//! without parsing and optimizing them each time.
+
+use std::fmt;
+
+use crate::config::CellSize;
+use crate::error::BfError;
+use crate::program::{BitOp, Instruction, Program};
+
+
+/// First bytes of every bytecode file, identifying the format. The leading byte is not valid
+/// UTF-8, so no source file can be mistaken for bytecode.
+pub const MAGIC: &[u8; 4] = b"\xffBFC";
+
+/// Version of the format written by `Bytecode::encode`, incremented whenever it changes.
+pub const VERSION: u8 = 1;
+
+
+/// A program in its compiled form along with the cell size that it was compiled for, see
+/// `compile::Target::Bytecode`.
+///
+/// Bytecode is laid out as `MAGIC`, the `VERSION` byte, a byte for the cell size, and the number
+/// of instructions, followed by the instructions. Each instruction is a single opcode byte
+/// followed by its operands, if any. Numbers are encoded as
+/// [LEB128](https://en.wikipedia.org/wiki/LEB128) varints, with signed numbers zigzag encoded
+/// first such that small negative numbers stay small.
+///
+/// Jump targets are not stored, but linked again when the bytecode is decoded. Neither is the
+/// source of the program, so errors are reported against the position of the instruction in the
+/// compiled program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub program: Program,
+    pub cell_size: CellSize,
+}
+
+impl Bytecode {
+    /// Whether `bytes` start like bytecode, as opposed to source.
+    pub fn is_bytecode(bytes: &[u8]) -> bool { bytes.starts_with(MAGIC) }
+
+    /// Encode the program as bytecode.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.header();
+        for instruction in self.program.instructions() {
+            encode_instruction(&mut bytes, instruction);
+        }
+        bytes
+    }
+
+    /// Everything that precedes the instructions once encoded.
+    fn header(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(match self.cell_size {
+            CellSize::U8 => 0,
+            CellSize::U16 => 1,
+            CellSize::U32 => 2,
+            CellSize::I32 => 3,
+        });
+        write_unsigned(&mut bytes, self.program.len() as u64);
+        bytes
+    }
+
+    /// Decode bytecode written by `encode`, failing with `BfError::InvalidBytecode` if it is
+    /// malformed or was written by another version of `bfi`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, BfError> {
+        let invalid = |reason: &str| BfError::InvalidBytecode(reason.to_string());
+        if !Self::is_bytecode(bytes) {
+            return Err(invalid("missing header"));
+        };
+        let mut reader = Reader { bytes, offset: MAGIC.len() };
+        match reader.byte()? {
+            VERSION => {},
+            version => return Err(invalid(&format!("unsupported version {}", version))),
+        };
+        let cell_size = match reader.byte()? {
+            0 => CellSize::U8,
+            1 => CellSize::U16,
+            2 => CellSize::U32,
+            3 => CellSize::I32,
+            other => return Err(invalid(&format!("unknown cell size {}", other))),
+        };
+        let len = reader.unsigned()? as usize;
+        // every instruction takes at least a byte, which bounds the allocation for a corrupt length
+        let mut instructions = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            let offset = reader.offset;
+            instructions.push(match reader.byte()? {
+                0 => Instruction::Add(reader.signed_as()?),
+                1 => Instruction::Move(reader.signed_as()?),
+                2 => Instruction::SetZero,
+                3 => Instruction::Scan(reader.signed_as()?),
+                4 => Instruction::MulAdd(reader.signed_as()?, reader.signed_as()?),
+                5 => Instruction::PutChar,
+                6 => Instruction::GetChar,
+                7 => Instruction::LoopBeg(None),
+                8 => Instruction::LoopEnd(None),
+                9 => Instruction::DebugDump,
+                10 => Instruction::DebugBreakpoint,
+                11 => Instruction::ProcBeg(None),
+                12 => Instruction::ProcEnd(None),
+                13 => Instruction::ProcCall,
+                14 => Instruction::Fork,
+                15 => Instruction::End,
+                16 => Instruction::Store,
+                17 => Instruction::Load,
+                18 => Instruction::Bitwise(match reader.byte()? {
+                    0 => BitOp::ShiftRight,
+                    1 => BitOp::ShiftLeft,
+                    2 => BitOp::Not,
+                    3 => BitOp::Xor,
+                    4 => BitOp::And,
+                    5 => BitOp::Or,
+                    other => return Err(invalid(&format!("unknown bitwise operation {}", other))),
+                }),
+                19 => Instruction::Random,
+                20 => Instruction::Clock,
+                21 => Instruction::NextTape,
+                22 => Instruction::PrevTape,
+                23 => Instruction::RowUp,
+                24 => Instruction::RowDown,
+                other => {
+                    return Err(invalid(&format!("unknown opcode {} at byte {}", other, offset)));
+                },
+            });
+        }
+        if reader.offset != bytes.len() {
+            return Err(invalid(&format!("unexpected data at byte {}", reader.offset)));
+        };
+        let positions = (0..instructions.len()).collect();
+        Ok(Bytecode { program: Program::link(instructions, positions), cell_size })
+    }
+}
+
+
+impl fmt::Display for Bytecode {
+    /// Render the program as a listing of instructions, one per line, alongside the index of each
+    /// instruction and its offset in the encoded bytecode. The bodies of loops and procedures are
+    /// indented.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "; bytecode version {}, {} cells, {} instructions",
+            VERSION,
+            self.cell_size,
+            self.program.len(),
+        )?;
+        let mut offset = self.header().len();
+        let mut depth: usize = 0;
+        let mut encoded = Vec::new();
+        for (i, instruction) in self.program.instructions().iter().enumerate() {
+            if let Instruction::LoopEnd(_) | Instruction::ProcEnd(_) = instruction {
+                depth = depth.saturating_sub(1);
+            };
+            writeln!(f, "{:>6}  {:#06x}  {}{}", i, offset, "  ".repeat(depth), instruction)?;
+            if let Instruction::LoopBeg(_) | Instruction::ProcBeg(_) = instruction {
+                depth += 1;
+            };
+            encoded.clear();
+            encode_instruction(&mut encoded, instruction);
+            offset += encoded.len();
+        }
+        Ok(())
+    }
+}
+
+
+/// Append the opcode and operands of `instruction`.
+fn encode_instruction(bytes: &mut Vec<u8>, instruction: &Instruction) {
+    match *instruction {
+        Instruction::Add(n) => {
+            bytes.push(0);
+            write_signed(bytes, n as i64);
+        },
+        Instruction::Move(n) => {
+            bytes.push(1);
+            write_signed(bytes, n as i64);
+        },
+        Instruction::SetZero => bytes.push(2),
+        Instruction::Scan(n) => {
+            bytes.push(3);
+            write_signed(bytes, n as i64);
+        },
+        Instruction::MulAdd(offset, factor) => {
+            bytes.push(4);
+            write_signed(bytes, offset as i64);
+            write_signed(bytes, factor as i64);
+        },
+        Instruction::PutChar => bytes.push(5),
+        Instruction::GetChar => bytes.push(6),
+        Instruction::LoopBeg(_) => bytes.push(7),
+        Instruction::LoopEnd(_) => bytes.push(8),
+        Instruction::DebugDump => bytes.push(9),
+        Instruction::DebugBreakpoint => bytes.push(10),
+        Instruction::ProcBeg(_) => bytes.push(11),
+        Instruction::ProcEnd(_) => bytes.push(12),
+        Instruction::ProcCall => bytes.push(13),
+        Instruction::Fork => bytes.push(14),
+        Instruction::End => bytes.push(15),
+        Instruction::Store => bytes.push(16),
+        Instruction::Load => bytes.push(17),
+        Instruction::Bitwise(op) => {
+            bytes.push(18);
+            bytes.push(match op {
+                BitOp::ShiftRight => 0,
+                BitOp::ShiftLeft => 1,
+                BitOp::Not => 2,
+                BitOp::Xor => 3,
+                BitOp::And => 4,
+                BitOp::Or => 5,
+            });
+        },
+        Instruction::Random => bytes.push(19),
+        Instruction::Clock => bytes.push(20),
+        Instruction::NextTape => bytes.push(21),
+        Instruction::PrevTape => bytes.push(22),
+        Instruction::RowUp => bytes.push(23),
+        Instruction::RowDown => bytes.push(24),
+    }
+}
+
+
+/// Append `n` as an unsigned LEB128 varint.
+fn write_unsigned(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+
+/// Append `n` zigzag encoded as an unsigned LEB128 varint.
+fn write_signed(bytes: &mut Vec<u8>, n: i64) {
+    write_unsigned(bytes, ((n << 1) ^ (n >> 63)) as u64);
+}
+
+
+/// Cursor over bytecode being decoded.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, BfError> {
+        let byte = self.bytes.get(self.offset).copied().ok_or_else(|| {
+            BfError::InvalidBytecode(format!("unexpected end at byte {}", self.offset))
+        })?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn unsigned(&mut self) -> Result<u64, BfError> {
+        let mut n: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            };
+        }
+        Err(BfError::InvalidBytecode(format!("number too long at byte {}", self.offset)))
+    }
+
+    /// Read a signed number, failing if it does not fit in `T`.
+    fn signed_as<T: std::convert::TryFrom<i64>>(&mut self) -> Result<T, BfError> {
+        let offset = self.offset;
+        let n = self.unsigned()?;
+        let n = (n >> 1) as i64 ^ -((n & 1) as i64);
+        T::try_from(n).map_err(|_| {
+            BfError::InvalidBytecode(format!("number out of range at byte {}", offset))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::optimizer;
+
+    #[test]
+    fn test_round_trip() {
+        let program = optimizer::optimize(&Program::parse("+++[->>+++<<]>>[-]<<[>]-5000.,#%"));
+        let bytecode = Bytecode { program, cell_size: CellSize::U16 };
+        let bytes = bytecode.encode();
+        assert!(Bytecode::is_bytecode(&bytes));
+        let decoded = Bytecode::decode(&bytes).unwrap();
+        assert_eq!(decoded.cell_size, CellSize::U16);
+        assert_eq!(decoded.program.instructions(), bytecode.program.instructions());
+        let instructions = vec![
+            Instruction::ProcBeg(None),
+            Instruction::ProcCall,
+            Instruction::ProcEnd(None),
+            Instruction::Fork,
+            Instruction::End,
+            Instruction::Store,
+            Instruction::Load,
+            Instruction::Bitwise(BitOp::ShiftRight),
+            Instruction::Bitwise(BitOp::ShiftLeft),
+            Instruction::Bitwise(BitOp::Not),
+            Instruction::Bitwise(BitOp::Xor),
+            Instruction::Bitwise(BitOp::And),
+            Instruction::Bitwise(BitOp::Or),
+            Instruction::Random,
+            Instruction::Clock,
+            Instruction::NextTape,
+            Instruction::PrevTape,
+            Instruction::RowUp,
+            Instruction::RowDown,
+            Instruction::Move(isize::MIN),
+            Instruction::Add(i32::MAX),
+        ];
+        let program = Program::link(instructions, (0..21).collect());
+        let bytecode = Bytecode { program, cell_size: CellSize::I32 };
+        let decoded = Bytecode::decode(&bytecode.encode()).unwrap();
+        assert_eq!(decoded.program.instructions(), bytecode.program.instructions());
+    }
+
+    #[test]
+    fn test_varints() {
+        for n in [0, 1, -1, 63, -64, 64, 1 << 20, -(1 << 40), i64::MAX, i64::MIN] {
+            let mut bytes = Vec::new();
+            write_signed(&mut bytes, n);
+            let mut reader = Reader { bytes: &bytes, offset: 0 };
+            assert_eq!(reader.signed_as::<i64>(), Ok(n));
+            assert_eq!(reader.offset, bytes.len());
+        }
+        let mut bytes = Vec::new();
+        write_signed(&mut bytes, 1 << 40);
+        assert!(Reader { bytes: &bytes, offset: 0 }.signed_as::<i32>().is_err());
+    }
+
+    #[test]
+    fn test_listing() {
+        let program = optimizer::optimize(&Program::parse("++[>[-]<-]>>>."));
+        let bytecode = Bytecode { program, cell_size: CellSize::U8 };
+        assert_eq!(bytecode.to_string(), [
+            "; bytecode version 1, u8 cells, 9 instructions",
+            "     0  0x0007  +2",
+            "     1  0x0009  [ -> 6",
+            "     2  0x000a    >1",
+            "     3  0x000c    zero",
+            "     4  0x000d    <1",
+            "     5  0x000f    -1",
+            "     6  0x0011  ] -> 1",
+            "     7  0x0012  >3",
+            "     8  0x0014  .",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let valid = Bytecode { program: Program::parse("+[>]"), cell_size: CellSize::U8 }.encode();
+        assert!(Bytecode::decode(&valid).is_ok());
+        let mut invalid: Vec<Vec<u8>> = vec![
+            b"+[>]".to_vec(),
+            valid[..valid.len() - 1].to_vec(),
+            [&valid[..], &[0]].concat(),
+        ];
+        for (i, byte) in [(4, VERSION + 1), (5, 4), (7, 19)] {
+            let mut bytes = valid.clone();
+            bytes[i] = byte;
+            invalid.push(bytes);
+        }
+        for bytes in invalid {
+            assert!(
+                matches!(Bytecode::decode(&bytes), Err(BfError::InvalidBytecode(_))),
+                "{:?} was accepted",
+                bytes,
+            );
+        }
+    }
+}
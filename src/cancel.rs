@@ -0,0 +1,129 @@
+//! Stopping a running program from elsewhere, see `CancelToken`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+
+/// Flag shared with an `ExecutionContext` to stop its program from another thread or a signal
+/// handler, see `ExecutionContext::with_cancel_token`, or to pause it, see
+/// `ExecutionContext::with_pause_token`.
+///
+/// The interpreter checks the flag before each instruction and stops with
+/// `ExecutionStatus::Cancelled` once it is set, leaving the tape and pointers as they were for
+/// inspection. Programs compiled with the JIT check it at the end of each loop iteration, from
+/// where they return to the interpreter. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self { Self::default() }
+
+    /// Ask every execution holding a clone of this token to stop.
+    pub fn cancel(&self) { self.cancelled.store(true, Ordering::Relaxed); }
+
+    pub fn is_cancelled(&self) -> bool { self.cancelled.load(Ordering::Relaxed) }
+
+    /// Clear the flag, returning whether it was set, such that the token can be used again.
+    pub fn take(&self) -> bool { self.cancelled.swap(false, Ordering::Relaxed) }
+
+    /// The flag itself, read directly by the code compiled with the JIT.
+    #[cfg(feature = "jit")]
+    pub(crate) fn flag(&self) -> &AtomicBool { &self.cancelled }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::{self, Read};
+    use std::thread;
+
+    use crate::interpreter::{ExecutionContext, ExecutionStatus};
+    use crate::ioctx::{InMemoryIoCtx, IoCtx, ReadWriteIoCtx};
+
+    /// Input whose first read is interrupted by a signal, which sets `token` as the handler of
+    /// Ctrl-C does if there is one.
+    struct InterruptedInput {
+        token: Option<CancelToken>,
+        interrupted: bool,
+    }
+
+    impl Read for InterruptedInput {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+                self.token.iter().for_each(CancelToken::cancel);
+                return Err(io::ErrorKind::Interrupted.into());
+            };
+            (&b"a"[..]).read(buf)
+        }
+    }
+
+    fn interrupted_input(token: Option<CancelToken>) -> RefCell<Box<dyn IoCtx>> {
+        let input = InterruptedInput { token, interrupted: false };
+        RefCell::new(Box::new(ReadWriteIoCtx::new(input, io::sink())))
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no threads")]
+    fn test_cancel() {
+        let token = CancelToken::new();
+        let canceller = token.clone();
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+[>+<]")
+            .with_cancel_token(token);
+        thread::spawn(move || canceller.cancel());
+        assert_eq!(ectx.execute(), ExecutionStatus::Cancelled);
+        // the state is left as it was when the program was stopped
+        assert_eq!(ectx.tape()[0], 1);
+        assert_eq!(ectx.step(), ExecutionStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_pause() {
+        let token = CancelToken::new();
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+>+")
+            .with_pause_token(token.clone())
+            .without_repl();
+        ectx.step();
+        token.cancel();
+        assert_eq!(ectx.step(), ExecutionStatus::InProgress);
+        assert_eq!(ectx.take_paused(), vec!["interrupted".to_string()]);
+        assert!(!token.is_cancelled());
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert!(ectx.take_paused().is_empty());
+    }
+
+    #[test]
+    fn test_interrupted_read() {
+        // a signal setting neither token only delays the read
+        let ictx = interrupted_input(None);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), ",")
+            .with_cancel_token(CancelToken::new());
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(ectx.tape()[0], b'a');
+
+        // Ctrl-C while waiting for input stops the program without reading any
+        let token = CancelToken::new();
+        let ictx = interrupted_input(Some(token.clone()));
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+,").with_cancel_token(token);
+        assert_eq!(ectx.execute(), ExecutionStatus::Cancelled);
+        assert_eq!(ectx.tape()[0], 1);
+
+        // or pauses it, after which the read is made again
+        let token = CancelToken::new();
+        let ictx = interrupted_input(Some(token.clone()));
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), ",")
+            .with_pause_token(token)
+            .without_repl();
+        assert_eq!(ectx.step(), ExecutionStatus::InProgress);
+        assert_eq!(ectx.take_paused(), vec!["interrupted".to_string()]);
+        assert_eq!(ectx.tape()[0], 0);
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(ectx.tape()[0], b'a');
+    }
+}
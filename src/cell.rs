@@ -0,0 +1,142 @@
+//! Tape cell types supported by the interpreter.
+
+use core::convert::TryFrom;
+use core::fmt::{Debug, Display, LowerHex};
+use core::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+
+/// A single cell of the tape.
+///
+/// Arithmetic on cells wraps at the bounds of the underlying integer type unless configured
+/// otherwise, see `config::OverflowPolicy`, and bitwise operations act on its bits. Implemented
+/// for `u8` (the default), `u16`, `u32`, and `i32`.
+pub trait Cell:
+    Copy
+    + Default
+    + PartialEq
+    + Debug
+    + Display
+    + LowerHex
+    + Not<Output = Self>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+    + 'static
+{
+    /// Add one to the cell value, wrapping on overflow.
+    fn wrapping_inc(self) -> Self;
+
+    /// Subtract one from the cell value, wrapping on underflow.
+    fn wrapping_dec(self) -> Self;
+
+    /// Add a (possibly negative) amount to the cell value, wrapping at the bounds of the cell.
+    fn wrapping_offset(self, n: i32) -> Self;
+
+    /// Add `value * factor` to the cell value, wrapping at the bounds of the cell.
+    fn wrapping_mul_add(self, value: Self, factor: i32) -> Self;
+
+    /// Add a (possibly negative) amount to the cell value, or `None` if the result is past the
+    /// bounds of the cell.
+    fn checked_offset(self, n: i64) -> Option<Self>;
+
+    /// Add a (possibly negative) amount to the cell value, stopping at the bounds of the cell.
+    fn saturating_offset(self, n: i64) -> Self;
+
+    /// Create a cell holding the value of a byte read from program input.
+    fn from_byte(b: u8) -> Self;
+
+    /// Create a cell holding a (possibly negative) integer read from program input, wrapping it at
+    /// the bounds of the cell.
+    fn from_i64(n: i64) -> Self;
+
+    /// Truncate the cell value to the byte written to program output.
+    fn to_byte(self) -> u8;
+
+    /// The value of the cell as a (possibly negative) integer.
+    fn to_i64(self) -> i64;
+
+    /// Whether the cell holds zero, i.e. whether a loop should be skipped or exited.
+    fn is_zero(self) -> bool { self == Self::default() }
+}
+
+
+macro_rules! impl_cell {
+    ($($t:ty),*) => {$(
+        impl Cell for $t {
+            fn wrapping_inc(self) -> Self { self.wrapping_add(1) }
+            fn wrapping_dec(self) -> Self { self.wrapping_sub(1) }
+            // truncating (or sign-reinterpreting) `n` preserves its value modulo the cell width
+            fn wrapping_offset(self, n: i32) -> Self { self.wrapping_add(n as Self) }
+            fn wrapping_mul_add(self, value: Self, factor: i32) -> Self {
+                self.wrapping_add(value.wrapping_mul(factor as Self))
+            }
+            fn checked_offset(self, n: i64) -> Option<Self> {
+                Self::try_from((self as i64).checked_add(n)?).ok()
+            }
+            fn saturating_offset(self, n: i64) -> Self {
+                (self as i64).saturating_add(n).clamp(Self::MIN as i64, Self::MAX as i64) as Self
+            }
+            fn from_byte(b: u8) -> Self { Self::from(b) }
+            fn from_i64(n: i64) -> Self { n as Self }
+            fn to_byte(self) -> u8 { self as u8 }
+            fn to_i64(self) -> i64 { self as i64 }
+        }
+    )*};
+}
+
+impl_cell!(u8, u16, u32, i32);
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(0u8, 255u8.wrapping_inc());
+        assert_eq!(255u8, 0u8.wrapping_dec());
+        assert_eq!(256u16, 255u16.wrapping_inc());
+        assert_eq!(u16::MAX, 0u16.wrapping_dec());
+        assert_eq!(u32::MAX, 0u32.wrapping_dec());
+        assert_eq!(-1i32, 0i32.wrapping_dec());
+        assert_eq!(i32::MIN, i32::MAX.wrapping_inc());
+        assert_eq!(254u8, 1u8.wrapping_offset(-3));
+        assert_eq!(44u8, 0u8.wrapping_offset(300));
+        assert_eq!(300u16, 0u16.wrapping_offset(300));
+        assert_eq!(-3i32, 0i32.wrapping_offset(-3));
+        assert_eq!(7u8, 10u8.wrapping_mul_add(3, -1));
+        assert_eq!(4u8, 1u8.wrapping_mul_add(255, -3));
+        assert_eq!(-5i32, 1i32.wrapping_mul_add(3, -2));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Some(255u8), 250u8.checked_offset(5));
+        assert_eq!(None, 255u8.checked_offset(1));
+        assert_eq!(None, 1u8.checked_offset(-2));
+        assert_eq!(None, u32::MAX.checked_offset(1));
+        assert_eq!(Some(i32::MIN), (-1i32).checked_offset(i32::MIN as i64 + 1));
+        assert_eq!(None, i32::MIN.checked_offset(-1));
+        assert_eq!(255u8, 250u8.saturating_offset(300));
+        assert_eq!(0u16, 3u16.saturating_offset(-4));
+        assert_eq!(u32::MAX, 0u32.saturating_offset(i64::MAX));
+        assert_eq!(i32::MIN, 0i32.saturating_offset(i64::MIN));
+        assert_eq!(-1i32, 1i32.saturating_offset(-2));
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(0x34u8, 0x1234u16.to_byte());
+        assert_eq!(0xffu8, (-1i32).to_byte());
+        assert_eq!(200i32, i32::from_byte(200));
+        assert!(0u32.is_zero());
+        assert_eq!(-1, (-1i32).to_i64());
+        assert_eq!(4_294_967_295, u32::MAX.to_i64());
+        assert_eq!(255u8, u8::from_i64(-1));
+        assert_eq!(44u8, u8::from_i64(300));
+        assert_eq!(-7i32, i32::from_i64(-7));
+        assert!(!256u16.is_zero());
+    }
+}
@@ -0,0 +1,156 @@
+//! BrainF\*ck backend, see `compile::Target::Brainfuck`.
+//!
+//! Lowers the instructions of the optimizer back into the eight standard commands, such that the
+//! program runs faster on any interpreter: changes to a cell take the shorter way around the cell
+//! size, `[+]` becomes `[-]`, multiplication loops lose their redundant moves, and the commands
+//! `format::minify` finds to have no effect are dropped.
+
+use crate::config::{CellSize, InterpreterConfig};
+use crate::format;
+use crate::program::{Instruction, Program};
+
+
+/// Compile a program to plain BrainF\*ck on a single line, see `compile::compile`.
+///
+/// Fails if the program multiplies the current cell into others without clearing it after, which
+/// the optimizer never does and which cannot be written without a spare cell.
+pub fn compile(program: &Program, config: &InterpreterConfig) -> Result<String, String> {
+    let instructions = program.instructions();
+    let mut src = String::new();
+    let mut i = 0;
+    while i < instructions.len() {
+        match instructions[i] {
+            Instruction::Add(n) => add(&mut src, n, config.cell_size),
+            Instruction::Move(n) => move_ptr(&mut src, n),
+            Instruction::SetZero => src.push_str("[-]"),
+            Instruction::Scan(n) => {
+                src.push('[');
+                move_ptr(&mut src, n);
+                src.push(']');
+            },
+            Instruction::MulAdd(_, _) => {
+                // the loop the optimizer found, with the cells it changes in order
+                let end = i + instructions[i..]
+                    .iter()
+                    .position(|instruction| !matches!(instruction, Instruction::MulAdd(_, _)))
+                    .unwrap_or(instructions.len() - i);
+                if instructions.get(end) != Some(&Instruction::SetZero) {
+                    return Err(format!(
+                        "multiplication at program position {} does not clear the cell",
+                        program.position(i),
+                    ));
+                };
+                src.push_str("[-");
+                let mut offset = 0;
+                for instruction in &instructions[i..end] {
+                    if let Instruction::MulAdd(to, factor) = *instruction {
+                        move_ptr(&mut src, to - offset);
+                        add(&mut src, factor, config.cell_size);
+                        offset = to;
+                    };
+                }
+                move_ptr(&mut src, -offset);
+                src.push(']');
+                i = end;
+            },
+            Instruction::PutChar => src.push('.'),
+            Instruction::GetChar => src.push(','),
+            Instruction::LoopBeg(_) => src.push('['),
+            Instruction::LoopEnd(_) => src.push(']'),
+            Instruction::DebugDump | Instruction::DebugBreakpoint => {},
+            // programs with procedures, forks, or extensions are rejected by `compile::compile`
+            Instruction::ProcBeg(_)
+            | Instruction::ProcEnd(_)
+            | Instruction::ProcCall
+            | Instruction::Fork
+            | Instruction::End
+            | Instruction::Store
+            | Instruction::Load
+            | Instruction::Bitwise(_)
+        | Instruction::Random
+        | Instruction::Clock
+        | Instruction::NextTape
+        | Instruction::PrevTape
+        | Instruction::RowUp
+        | Instruction::RowDown => {},
+        };
+        i += 1;
+    }
+    Ok(format::minify(&src) + "\n")
+}
+
+
+/// Append the commands adding `n` to the current cell, going the shorter way around the values
+/// of a cell of `cell_size`.
+fn add(src: &mut String, n: i32, cell_size: CellSize) {
+    let bits = match cell_size {
+        CellSize::U8 => 8,
+        CellSize::U16 => 16,
+        CellSize::U32 | CellSize::I32 => 32,
+    };
+    let modulus = 1i64 << bits;
+    let n = (n as i64).rem_euclid(modulus);
+    if n <= modulus / 2 {
+        src.push_str(&"+".repeat(n as usize));
+    } else {
+        src.push_str(&"-".repeat((modulus - n) as usize));
+    };
+}
+
+
+fn move_ptr(src: &mut String, n: isize) {
+    let command = if n < 0 { "<" } else { ">" };
+    src.push_str(&command.repeat(n.unsigned_abs()));
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::EofBehavior;
+    use crate::difftest;
+    use crate::optimizer;
+
+    fn lower(source: &str, cell_size: CellSize) -> String {
+        let program = optimizer::optimize(&Program::parse(source));
+        let config = InterpreterConfig { cell_size, ..InterpreterConfig::default() };
+        compile(&program, &config).unwrap()
+    }
+
+    #[test]
+    fn test_instructions() {
+        assert_eq!(lower("+++++ comment -- >><<< [+] .", CellSize::U8), "+++<[-].\n");
+        assert_eq!(lower(&format!("{}.", "+".repeat(250)), CellSize::U8), "------.\n");
+        assert_eq!(lower(&format!("{}.", "+".repeat(250)), CellSize::U16), format!(
+            "{}.\n",
+            "+".repeat(250),
+        ));
+        assert_eq!(lower(",[>>>]<[>+<-]>.", CellSize::U8), ",[>>>]<[->+<]>.\n");
+        assert_eq!(lower(",[->>+++<<<-->]>>.<<<.", CellSize::U8), ",[-<-->>>+++<<]>>.<<<.\n");
+    }
+
+    #[test]
+    fn test_preserves_behavior() {
+        let config = InterpreterConfig { eof: EofBehavior::Zero, ..InterpreterConfig::default() };
+        for (source, input) in &[
+            (",[.,]", &b"echo"[..]),
+            ("++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.", b""),
+            (",>,<[->+<]>.[-]++++++[<++++++++>-]<.", b"\x03\x04"),
+            (">>>++[<<<+>>>-]<<<[>]+.", b""),
+        ] {
+            let program = Program::parse(source);
+            let lowered = lower(source, CellSize::U8);
+            let expected = difftest::run::<u8>(&program, input, config.clone());
+            let actual = difftest::run::<u8>(&Program::parse(&lowered), input, config.clone());
+            assert_eq!(actual.status, expected.status, "{}", lowered);
+            assert_eq!(actual.output, expected.output, "{}", lowered);
+        }
+    }
+
+    #[test]
+    fn test_multiplication_without_clear() {
+        let program = Program::link(vec![Instruction::MulAdd(1, 2)], vec![0]);
+        assert!(compile(&program, &InterpreterConfig::default()).is_err());
+    }
+}
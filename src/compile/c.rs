@@ -0,0 +1,301 @@
+//! C backend, see `compile::Target::C`.
+//!
+//! Cells are stored as unsigned integers of the configured width. Signed cells (`CellSize::I32`)
+//! behave identically to unsigned ones in every way that a program can observe, and unsigned
+//! arithmetic sidesteps the undefined behavior of signed overflow in C.
+
+use crate::compile::Source;
+use crate::config::{CellSize, EofBehavior, InterpreterConfig, TapeMode};
+use crate::program::{Instruction, Program};
+
+
+static PRELUDE: &str = "\
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+";
+
+
+/// Compile a program to C, see `compile::compile`.
+pub fn compile(program: &Program, config: &InterpreterConfig) -> String {
+    let mut src = Source::new();
+    src.line("/* generated by bfi */");
+    for line in PRELUDE.lines() {
+        src.line(line);
+    }
+    src.line("");
+    src.line(&format!("typedef {} cell;", cell_type(config.cell_size)));
+    src.line("");
+    tape(&mut src, config);
+    io(&mut src, config.eof);
+
+    src.open("int main(void) {");
+    if let TapeMode::Growable = config.tape_mode {
+        src.line("tape = calloc(1, sizeof(cell));");
+        src.line("if (tape == NULL) fail(\"out of memory\");");
+    };
+    for (i, instruction) in program.instructions().iter().enumerate() {
+        self::instruction(&mut src, *instruction, program.position(i));
+    }
+    src.line("return 0;");
+    src.close("}");
+    src.into_string()
+}
+
+
+fn cell_type(cell_size: CellSize) -> &'static str {
+    match cell_size {
+        CellSize::U8 => "uint8_t",
+        CellSize::U16 => "uint16_t",
+        CellSize::U32 | CellSize::I32 => "uint32_t",
+    }
+}
+
+
+/// Declare the tape along with `fail` and the `move_ptr` function implementing the tape mode.
+fn tape(src: &mut Source, config: &InterpreterConfig) {
+    let size = config.tape_size.max(1);
+    match config.tape_mode {
+        TapeMode::Growable => {
+            src.line("static cell *tape;");
+            src.line("static size_t len = 1;");
+        },
+        TapeMode::Fixed | TapeMode::Circular => {
+            src.line(&format!("static cell tape[{}];", size));
+        },
+    };
+    src.line("static size_t ptr = 0;");
+    src.line("");
+
+    src.open("static inline void fail(const char *message) {");
+    src.line("fflush(stdout);");
+    src.line("fprintf(stderr, \"bfi: exited with error: %s\\n\", message);");
+    src.line("exit(1);");
+    src.close("}");
+    src.line("");
+
+    src.open("static inline void move_ptr(ptrdiff_t n, unsigned long position) {");
+    match config.tape_mode {
+        TapeMode::Growable => {
+            src.line("(void)position;");
+            src.open("if (n < 0 && (size_t)-n > ptr) {");
+            src.line("size_t extra = (size_t)-n - ptr;");
+            src.line("tape = realloc(tape, (len + extra) * sizeof(cell));");
+            src.line("if (tape == NULL) fail(\"out of memory\");");
+            src.line("memmove(tape + extra, tape, len * sizeof(cell));");
+            src.line("memset(tape, 0, extra * sizeof(cell));");
+            src.line("len += extra;");
+            src.line("ptr = 0;");
+            src.line("return;");
+            src.close("}");
+            src.open("if (n > 0 && (size_t)n >= len - ptr) {");
+            src.line("size_t size = len * 2 > ptr + n + 1 ? len * 2 : ptr + n + 1;");
+            src.line("tape = realloc(tape, size * sizeof(cell));");
+            src.line("if (tape == NULL) fail(\"out of memory\");");
+            src.line("memset(tape + len, 0, (size - len) * sizeof(cell));");
+            src.line("len = size;");
+            src.close("}");
+            src.line("ptr += n;");
+        },
+        TapeMode::Fixed => {
+            src.line("const char *end = NULL;");
+            src.line("char message[128];");
+            src.line("if (n < 0 && (size_t)-n > ptr) end = \"start\";");
+            src.line(&format!("if (n > 0 && (size_t)n >= {} - ptr) end = \"end\";", size));
+            src.open("if (end != NULL) {");
+            src.line(concat!(
+                "sprintf(message, \"data pointer moved past the %s of the tape at program ",
+                "position %lu\", end, position);",
+            ));
+            src.line("fail(message);");
+            src.close("}");
+            src.line("ptr += n;");
+        },
+        TapeMode::Circular => {
+            src.line("(void)position;");
+            src.line(&format!("n %= {};", size));
+            src.line(&format!("ptr = (ptr + (size_t)(n < 0 ? n + {} : n)) % {};", size, size));
+        },
+    };
+    src.close("}");
+    src.line("");
+}
+
+
+/// Define `put_char` and `get_char`, the latter handling the end of input as configured.
+fn io(src: &mut Source, eof: EofBehavior) {
+    src.open("static inline void put_char(void) {");
+    src.line("putchar((unsigned char)tape[ptr]);");
+    src.close("}");
+    src.line("");
+    src.open("static inline void get_char(void) {");
+    src.line("int c = getchar();");
+    match eof {
+        EofBehavior::Unchanged => src.line("if (c != EOF) tape[ptr] = (cell)c;"),
+        EofBehavior::Zero => src.line("tape[ptr] = c == EOF ? 0 : (cell)c;"),
+        EofBehavior::MinusOne => src.line("tape[ptr] = c == EOF ? (cell)-1 : (cell)c;"),
+    };
+    src.close("}");
+    src.line("");
+}
+
+
+fn instruction(src: &mut Source, instruction: Instruction, position: usize) {
+    match instruction {
+        Instruction::Add(n) if n >= 0 => src.line(&format!("tape[ptr] += {};", n)),
+        Instruction::Add(n) => src.line(&format!("tape[ptr] -= {};", n.unsigned_abs())),
+        Instruction::Move(n) => src.line(&format!("move_ptr({}, {});", n, position)),
+        Instruction::SetZero => src.line("tape[ptr] = 0;"),
+        Instruction::Scan(n) => {
+            src.line(&format!("while (tape[ptr]) move_ptr({}, {});", n, position));
+        },
+        Instruction::MulAdd(offset, factor) => {
+            let op = if factor >= 0 { "+=" } else { "-=" };
+            src.open("if (tape[ptr]) {");
+            src.line("cell value = tape[ptr];");
+            src.line(&format!("move_ptr({}, {});", offset, position));
+            src.line(&format!("tape[ptr] {} value * {}u;", op, factor.unsigned_abs()));
+            src.line(&format!("move_ptr({}, {});", -offset, position));
+            src.close("}");
+        },
+        Instruction::PutChar => src.line("put_char();"),
+        Instruction::GetChar => src.line("get_char();"),
+        Instruction::LoopBeg(Some(_)) => src.open("while (tape[ptr]) {"),
+        Instruction::LoopEnd(Some(_)) => src.close("}"),
+        Instruction::LoopBeg(None) => src.line(&format!(
+            "fail(\"'[' at program position {} missing corresponding ']'\");", position)),
+        Instruction::LoopEnd(None) => src.line(&format!(
+            "fail(\"']' at program position {} missing corresponding '['\");", position)),
+        Instruction::DebugDump | Instruction::DebugBreakpoint => {},
+        // programs with procedures, forks, or extensions are rejected by `compile::compile`
+        Instruction::ProcBeg(_)
+        | Instruction::ProcEnd(_)
+        | Instruction::ProcCall
+        | Instruction::Fork
+        | Instruction::End
+        | Instruction::Store
+        | Instruction::Load
+        | Instruction::Bitwise(_)
+        | Instruction::Random
+        | Instruction::Clock
+        | Instruction::NextTape
+        | Instruction::PrevTape
+        | Instruction::RowUp
+        | Instruction::RowDown => {},
+    };
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use crate::compile::Target;
+    use crate::optimizer;
+    use std::process::{Command, Stdio};
+
+    /// Build the C source for `program` with the system C compiler and run it, returning its exit
+    /// code and output. Returns `None` if no C compiler is available.
+    fn build_and_run(
+        program: &str,
+        config: &InterpreterConfig,
+        input: &[u8],
+    ) -> Option<(i32, Vec<u8>, String)>
+    {
+        // WASI can neither write temporary files nor run other programs
+        if cfg!(target_os = "wasi") {
+            return None;
+        };
+        let dir = std::env::temp_dir().join(format!("bfi-c-{}-{:x}", std::process::id(),
+            program.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64))));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("program.c");
+        let binary = dir.join("program");
+        let compiled = crate::compile::compile(&Program::parse(program), Target::C, config)
+            .unwrap();
+        std::fs::write(&source, compiled).unwrap();
+        let status = Command::new("cc")
+            .args(["-std=c99", "-O1", "-o"])
+            .arg(&binary)
+            .arg(&source)
+            .status()
+            .ok()?;
+        assert!(status.success());
+        let mut child = Command::new(&binary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.as_mut().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        Some((output.status.code().unwrap(), output.stdout, stderr))
+    }
+
+    #[test]
+    fn test_instructions() {
+        let program = optimizer::optimize(&Program::parse("--[->+++<]<[<]"));
+        let src = compile(&program, &InterpreterConfig::default());
+        assert!(src.contains("tape[ptr] -= 2;"));
+        assert!(src.contains("tape[ptr] += value * 3u;"));
+        assert!(src.contains("while (tape[ptr]) move_ptr(-1, 11);"));
+    }
+
+    #[test]
+    fn test_programs() {
+        let config = InterpreterConfig::default();
+        let hello_world = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.\
+            >>.<-.<.+++.------.--------.>>+.>++.";
+        if let Some(result) = build_and_run(hello_world, &config, b"") {
+            assert_eq!(result, (0, b"Hello World!\n".to_vec(), String::new()));
+        };
+        if let Some(result) = build_and_run("<<,[.[-]<,]>>>.", &config, b"abc") {
+            assert_eq!(result, (0, b"abc\x00".to_vec(), String::new()));
+        };
+        let wide = InterpreterConfig { cell_size: CellSize::U16, ..config.clone() };
+        if let Some(result) = build_and_run("-[->+<]>[>+<+]>-.", &wide, b"") {
+            assert_eq!(result, (0, b"\x00".to_vec(), String::new()));
+        };
+    }
+
+    #[test]
+    fn test_eof() {
+        for (eof, expected) in [
+            (EofBehavior::Unchanged, b"aa"),
+            (EofBehavior::Zero, b"a\x00"),
+            (EofBehavior::MinusOne, b"a\xff"),
+        ] {
+            let config = InterpreterConfig { eof, cell_size: CellSize::U16, ..Default::default() };
+            if let Some(result) = build_and_run(",.,.", &config, b"a") {
+                assert_eq!(result, (0, expected.to_vec(), String::new()));
+            };
+        }
+    }
+
+    #[test]
+    fn test_tape_modes() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        if let Some(result) = build_and_run("+.>>>>", &fixed, b"") {
+            let error = "bfi: exited with error: data pointer moved past the end of the tape at \
+                program position 2\n";
+            assert_eq!(result, (1, b"\x01".to_vec(), error.to_string()));
+        };
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed };
+        if let Some(result) = build_and_run("<+++[->>+<<]>>.", &circular, b"") {
+            assert_eq!(result, (0, b"\x03".to_vec(), String::new()));
+        };
+        if let Some(result) = build_and_run("[]]", &InterpreterConfig::default(), b"") {
+            let error = "bfi: exited with error: ']' at program position 2 missing corresponding \
+                '['\n";
+            assert_eq!(result.2, error);
+        };
+    }
+}
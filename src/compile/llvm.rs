@@ -0,0 +1,540 @@
+//! LLVM backend, enabled by the `llvm` feature, see `compile::Target::LlvmIr`.
+//!
+//! Programs are lowered to textual LLVM IR, which `assemble` runs through the optimization passes
+//! of the system `opt` and compiles to an object file with `llc`. The object file defines `main`
+//! and only depends on the C standard library, so it can be linked with e.g. `cc program.o`.
+//!
+//! The IR uses typed pointers, as accepted by LLVM 14 and earlier releases.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::compile::Source;
+use crate::config::{CellSize, EofBehavior, InterpreterConfig, TapeMode};
+use crate::program::{Instruction, Program};
+
+
+/// Declarations and helpers shared by all tape modes, with `$cell` standing in for the integer
+/// type of the cells.
+static PRELUDE: &str = "\
+@tape = internal global $cell* null
+@len = internal global i64 1
+@ptr = internal global i64 0
+
+@error_format = private unnamed_addr constant [28 x i8] c\"bfi: exited with error: %s\\0A\\00\"
+@tape_format = private unnamed_addr constant [92 x i8] c\"bfi: exited with error: data \
+pointer moved past the %s of the tape at program position %lu\\0A\\00\"
+@start = private unnamed_addr constant [6 x i8] c\"start\\00\"
+@end = private unnamed_addr constant [4 x i8] c\"end\\00\"
+@out_of_memory = private unnamed_addr constant [14 x i8] c\"out of memory\\00\"
+
+declare i32 @putchar(i32)
+declare i32 @getchar()
+declare i32 @fflush(i8*)
+declare i32 @dprintf(i32, i8*, ...)
+declare i8* @calloc(i64, i64)
+declare i8* @realloc(i8*, i64)
+declare void @exit(i32)
+declare void @llvm.memmove.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+declare void @llvm.memset.p0i8.i64(i8*, i8, i64, i1)
+
+define internal void @fail(i8* %message) {
+    %flushed = call i32 @fflush(i8* null)
+    %format = getelementptr [28 x i8], [28 x i8]* @error_format, i64 0, i64 0
+    %printed = call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %format, i8* %message)
+    call void @exit(i32 1)
+    unreachable
+}
+
+define internal void @fail_tape(i8* %end, i64 %position) {
+    %flushed = call i32 @fflush(i8* null)
+    %format = getelementptr [92 x i8], [92 x i8]* @tape_format, i64 0, i64 0
+    %printed = call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %format, i8* %end, i64 %position)
+    call void @exit(i32 1)
+    unreachable
+}
+
+define internal void @fail_out_of_memory() {
+    %message = getelementptr [14 x i8], [14 x i8]* @out_of_memory, i64 0, i64 0
+    call void @fail(i8* %message)
+    unreachable
+}
+";
+
+
+/// `move_ptr` for `TapeMode::Growable`, with `$width` the size of a cell in bytes.
+static MOVE_GROWABLE: &str = "\
+define internal void @move_ptr(i64 %n, i64 %position) {
+entry:
+    %ptr = load i64, i64* @ptr
+    %len = load i64, i64* @len
+    %tape = load $cell*, $cell** @tape
+    %raw = bitcast $cell* %tape to i8*
+    %target = add i64 %ptr, %n
+    %before = icmp slt i64 %target, 0
+    br i1 %before, label %grow_start, label %within_start
+grow_start:
+    %extra = sub i64 0, %target
+    %start_len = add i64 %len, %extra
+    %start_bytes = mul i64 %start_len, $width
+    %start_tape = call i8* @realloc(i8* %raw, i64 %start_bytes)
+    %start_failed = icmp eq i8* %start_tape, null
+    br i1 %start_failed, label %out_of_memory, label %shift
+shift:
+    %extra_bytes = mul i64 %extra, $width
+    %len_bytes = mul i64 %len, $width
+    %shifted = getelementptr i8, i8* %start_tape, i64 %extra_bytes
+    call void @llvm.memmove.p0i8.p0i8.i64(i8* %shifted, i8* %start_tape, i64 %len_bytes, i1 false)
+    call void @llvm.memset.p0i8.i64(i8* %start_tape, i8 0, i64 %extra_bytes, i1 false)
+    %start_cells = bitcast i8* %start_tape to $cell*
+    store $cell* %start_cells, $cell** @tape
+    store i64 %start_len, i64* @len
+    store i64 0, i64* @ptr
+    ret void
+within_start:
+    store i64 %target, i64* @ptr
+    %past = icmp sge i64 %target, %len
+    br i1 %past, label %grow_end, label %done
+grow_end:
+    %doubled = mul i64 %len, 2
+    %needed = add i64 %target, 1
+    %double = icmp ugt i64 %doubled, %needed
+    %end_len = select i1 %double, i64 %doubled, i64 %needed
+    %end_bytes = mul i64 %end_len, $width
+    %end_tape = call i8* @realloc(i8* %raw, i64 %end_bytes)
+    %end_failed = icmp eq i8* %end_tape, null
+    br i1 %end_failed, label %out_of_memory, label %clear
+clear:
+    %old_bytes = mul i64 %len, $width
+    %new_bytes = sub i64 %end_bytes, %old_bytes
+    %fresh = getelementptr i8, i8* %end_tape, i64 %old_bytes
+    call void @llvm.memset.p0i8.i64(i8* %fresh, i8 0, i64 %new_bytes, i1 false)
+    %end_cells = bitcast i8* %end_tape to $cell*
+    store $cell* %end_cells, $cell** @tape
+    store i64 %end_len, i64* @len
+    ret void
+done:
+    ret void
+out_of_memory:
+    call void @fail_out_of_memory()
+    unreachable
+}
+";
+
+
+/// `move_ptr` for `TapeMode::Fixed`, with `$size` the number of cells on the tape.
+static MOVE_FIXED: &str = "\
+define internal void @move_ptr(i64 %n, i64 %position) {
+entry:
+    %ptr = load i64, i64* @ptr
+    %target = add i64 %ptr, %n
+    %before = icmp slt i64 %target, 0
+    br i1 %before, label %past_start, label %check_end
+check_end:
+    %past = icmp sge i64 %target, $size
+    br i1 %past, label %past_end, label %within
+within:
+    store i64 %target, i64* @ptr
+    ret void
+past_start:
+    %start = getelementptr [6 x i8], [6 x i8]* @start, i64 0, i64 0
+    call void @fail_tape(i8* %start, i64 %position)
+    unreachable
+past_end:
+    %end = getelementptr [4 x i8], [4 x i8]* @end, i64 0, i64 0
+    call void @fail_tape(i8* %end, i64 %position)
+    unreachable
+}
+";
+
+
+/// `move_ptr` for `TapeMode::Circular`, with `$size` the number of cells on the tape.
+static MOVE_CIRCULAR: &str = "\
+define internal void @move_ptr(i64 %n, i64 %position) {
+entry:
+    %ptr = load i64, i64* @ptr
+    %reduced = srem i64 %n, $size
+    %moved = add i64 %ptr, %reduced
+    %positive = add i64 %moved, $size
+    %target = urem i64 %positive, $size
+    store i64 %target, i64* @ptr
+    ret void
+}
+";
+
+
+/// Compile a program to LLVM IR, see `compile::compile`.
+pub fn compile(program: &Program, config: &InterpreterConfig) -> String {
+    let (cell, width) = match config.cell_size {
+        CellSize::U8 => ("i8", 1),
+        CellSize::U16 => ("i16", 2),
+        CellSize::U32 | CellSize::I32 => ("i32", 4),
+    };
+    let size = config.tape_size.max(1);
+    let move_ptr = match config.tape_mode {
+        TapeMode::Growable => MOVE_GROWABLE,
+        TapeMode::Fixed => MOVE_FIXED,
+        TapeMode::Circular => MOVE_CIRCULAR,
+    };
+    let mut src = Source::new();
+    src.line("; generated by bfi");
+    src.line("");
+    for template in &[PRELUDE, "", move_ptr] {
+        for line in template.lines() {
+            let line = line
+                .replace("$cell", cell)
+                .replace("$width", &width.to_string())
+                .replace("$size", &size.to_string());
+            src.line(&line);
+        }
+    }
+    src.line("");
+
+    let initial_len = match config.tape_mode {
+        TapeMode::Growable => 1,
+        TapeMode::Fixed | TapeMode::Circular => size,
+    };
+    let eof = match config.eof {
+        EofBehavior::Unchanged => None,
+        EofBehavior::Zero => Some(0),
+        EofBehavior::MinusOne => Some(-1),
+    };
+    let mut main = Function { src, cell, eof, strings: Vec::new(), next: 0 };
+    main.src.line("define i32 @main() {");
+    main.label("entry");
+    main.src.line(&format!("store i64 {}, i64* @len", initial_len));
+    main.src.line(&format!("%raw = call i8* @calloc(i64 {}, i64 {})", initial_len, width));
+    main.src.line("%failed = icmp eq i8* %raw, null");
+    main.src.line("br i1 %failed, label %out_of_memory, label %allocated");
+    main.label("out_of_memory");
+    main.src.line("call void @fail_out_of_memory()");
+    main.src.line("unreachable");
+    main.label("allocated");
+    main.src.line(&format!("%cells = bitcast i8* %raw to {}*", cell));
+    main.src.line(&format!("store {0}* %cells, {0}** @tape", cell));
+    for (i, instruction) in program.instructions().iter().enumerate() {
+        main.instruction(i, *instruction, program.position(i));
+    }
+    main.src.line("%flushed = call i32 @fflush(i8* null)");
+    main.src.line("ret i32 0");
+    main.src.close("}");
+
+    let mut src = main.src;
+    for (i, string) in main.strings.iter().enumerate() {
+        src.line(&format!(
+            "@message.{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"",
+            i, string.len() + 1, string));
+    }
+    src.into_string()
+}
+
+
+/// Run LLVM IR through `opt -O3` and compile it to an object file for the host with `llc`.
+pub fn assemble(ir: &[u8]) -> Result<Vec<u8>, String> {
+    let bitcode = pipe("opt", &["-O3", "-o", "-"], ir)?;
+    pipe("llc", &["-O3", "-filetype=obj", "-relocation-model=pic", "-o", "-"], &bitcode)
+}
+
+
+/// Run `program` with `input` on stdin, returning its stdout.
+fn pipe(program: &str, args: &[&str], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run '{}' ({})", program, e))?;
+    // write from another thread so that neither process blocks on a full pipe
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    writer.join().unwrap().map_err(|e| format!("could not write to '{}' ({})", program, e))?;
+    if !output.status.success() {
+        return Err(format!("'{}' failed: {}", program, String::from_utf8_lossy(&output.stderr)));
+    };
+    Ok(output.stdout)
+}
+
+
+/// Body of `main` under construction.
+struct Function<'a> {
+    src: Source,
+    cell: &'a str,
+    /// Value stored by `GetChar` at the end of input, if any.
+    eof: Option<i32>,
+    /// Error messages to define as global constants, referred to as `@message.{index}`.
+    strings: Vec<String>,
+    /// Counter used to create unique names for values and labels.
+    next: usize,
+}
+
+impl Function<'_> {
+    fn name(&mut self, prefix: &str) -> String {
+        self.next += 1;
+        format!("{}{}", prefix, self.next)
+    }
+
+    fn label(&mut self, label: &str) { self.src.reopen(&format!("{}:", label)); }
+
+    /// Emit a load of the current cell, returning the names of its address and value.
+    fn load_cell(&mut self) -> (String, String) {
+        let (tape, ptr, address, value) = (self.name("%t"), self.name("%p"), self.name("%a"),
+            self.name("%v"));
+        let cell = self.cell;
+        self.src.line(&format!("{} = load {1}*, {1}** @tape", tape, cell));
+        self.src.line(&format!("{} = load i64, i64* @ptr", ptr));
+        self.src.line(&format!("{} = getelementptr {1}, {1}* {2}, i64 {3}", address, cell, tape,
+            ptr));
+        self.src.line(&format!("{} = load {1}, {1}* {2}", value, cell, address));
+        (address, value)
+    }
+
+    /// Emit a branch to `nonzero` or `zero` depending on the value of the current cell.
+    fn branch_on_cell(&mut self, nonzero: &str, zero: &str) {
+        let (_, value) = self.load_cell();
+        let test = self.name("%z");
+        self.src.line(&format!("{} = icmp ne {} {}, 0", test, self.cell, value));
+        self.src.line(&format!("br i1 {}, label %{}, label %{}", test, nonzero, zero));
+    }
+
+    fn add(&mut self, n: String) {
+        let (address, value) = self.load_cell();
+        let sum = self.name("%s");
+        self.src.line(&format!("{} = add {} {}, {}", sum, self.cell, value, n));
+        self.src.line(&format!("store {0} {1}, {0}* {2}", self.cell, sum, address));
+    }
+
+    /// Literal of the cell type equal to `n` modulo the width of the cell.
+    fn literal(&self, n: i32) -> String {
+        match self.cell {
+            "i8" => (n as i8).to_string(),
+            "i16" => (n as i16).to_string(),
+            _ => n.to_string(),
+        }
+    }
+
+    fn move_ptr(&mut self, n: isize, position: usize) {
+        self.src.line(&format!("call void @move_ptr(i64 {}, i64 {})", n, position));
+    }
+
+    fn fail(&mut self, message: String) {
+        let string = self.strings.len();
+        let pointer = self.name("%m");
+        self.src.line(&format!(
+            "{} = getelementptr [{1} x i8], [{1} x i8]* @message.{2}, i64 0, i64 0",
+            pointer, message.len() + 1, string));
+        self.src.line(&format!("call void @fail(i8* {})", pointer));
+        self.src.line("unreachable");
+        self.strings.push(message);
+        // instructions following a failure are unreachable but still need a block
+        let dead = self.name("dead");
+        self.label(&dead);
+    }
+
+    fn instruction(&mut self, i: usize, instruction: Instruction, position: usize) {
+        match instruction {
+            Instruction::Add(n) => {
+                let n = self.literal(n);
+                self.add(n);
+            },
+            Instruction::Move(n) => self.move_ptr(n, position),
+            Instruction::SetZero => {
+                let (address, _) = self.load_cell();
+                self.src.line(&format!("store {0} 0, {0}* {1}", self.cell, address));
+            },
+            Instruction::Scan(n) => {
+                let (head, body, end) = (format!("scan{}", i), format!("scan{}_move", i),
+                    format!("scan{}_end", i));
+                self.src.line(&format!("br label %{}", head));
+                self.label(&head);
+                self.branch_on_cell(&body, &end);
+                self.label(&body);
+                self.move_ptr(n, position);
+                self.src.line(&format!("br label %{}", head));
+                self.label(&end);
+            },
+            Instruction::MulAdd(offset, factor) => {
+                let (body, end) = (format!("mul{}", i), format!("mul{}_end", i));
+                let (_, value) = self.load_cell();
+                let test = self.name("%z");
+                self.src.line(&format!("{} = icmp ne {} {}, 0", test, self.cell, value));
+                self.src.line(&format!("br i1 {}, label %{}, label %{}", test, body, end));
+                self.label(&body);
+                let product = self.name("%x");
+                let factor = self.literal(factor);
+                self.src.line(&format!("{} = mul {} {}, {}", product, self.cell, value, factor));
+                self.move_ptr(offset, position);
+                self.add(product);
+                self.move_ptr(-offset, position);
+                self.src.line(&format!("br label %{}", end));
+                self.label(&end);
+            },
+            Instruction::PutChar => {
+                let (_, value) = self.load_cell();
+                let byte = self.name("%c");
+                match self.cell {
+                    "i32" => self.src.line(&format!("{} = and i32 {}, 255", byte, value)),
+                    cell => self.src.line(&format!("{} = zext {} {} to i32", byte, cell, value)),
+                };
+                let written = self.name("%w");
+                self.src.line(&format!("{} = call i32 @putchar(i32 {})", written, byte));
+            },
+            Instruction::GetChar => {
+                let (store, end) = (format!("read{}", i), format!("read{}_end", i));
+                let (byte, eof) = (self.name("%c"), self.name("%e"));
+                self.src.line(&format!("{} = call i32 @getchar()", byte));
+                self.src.line(&format!("{} = icmp slt i32 {}, 0", eof, byte));
+                let byte = match self.eof {
+                    Some(value) => {
+                        let selected = self.name("%s");
+                        self.src.line(&format!("{} = select i1 {}, i32 {}, i32 {}", selected, eof,
+                            value, byte));
+                        self.src.line(&format!("br label %{}", store));
+                        selected
+                    },
+                    None => {
+                        self.src.line(&format!("br i1 {}, label %{}, label %{}", eof, end, store));
+                        byte
+                    },
+                };
+                self.label(&store);
+                let (address, _) = self.load_cell();
+                let value = match self.cell {
+                    "i32" => byte,
+                    cell => {
+                        let value = self.name("%v");
+                        self.src.line(&format!("{} = trunc i32 {} to {}", value, byte, cell));
+                        value
+                    },
+                };
+                self.src.line(&format!("store {0} {1}, {0}* {2}", self.cell, value, address));
+                self.src.line(&format!("br label %{}", end));
+                self.label(&end);
+            },
+            Instruction::LoopBeg(Some(_)) => {
+                self.src.line(&format!("br label %loop{}", i));
+                self.label(&format!("loop{}", i));
+                self.branch_on_cell(&format!("loop{}_body", i), &format!("loop{}_end", i));
+                self.label(&format!("loop{}_body", i));
+            },
+            Instruction::LoopEnd(Some(beg)) => {
+                self.src.line(&format!("br label %loop{}", beg));
+                self.label(&format!("loop{}_end", beg));
+            },
+            Instruction::LoopBeg(None) => self.fail(format!(
+                "'[' at program position {} missing corresponding ']'", position)),
+            Instruction::LoopEnd(None) => self.fail(format!(
+                "']' at program position {} missing corresponding '['", position)),
+            Instruction::DebugDump | Instruction::DebugBreakpoint => {},
+            // programs with procedures, forks, or extensions are rejected by `compile::compile`
+            Instruction::ProcBeg(_)
+            | Instruction::ProcEnd(_)
+            | Instruction::ProcCall
+            | Instruction::Fork
+            | Instruction::End
+            | Instruction::Store
+            | Instruction::Load
+            | Instruction::Bitwise(_)
+            | Instruction::Random
+            | Instruction::Clock
+            | Instruction::NextTape
+            | Instruction::PrevTape
+            | Instruction::RowUp
+            | Instruction::RowDown => {},
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compile::Target;
+
+    /// Run the IR compiled from `program` with `lli`, returning its exit code and output. Returns
+    /// `None` if `lli` is not available.
+    fn run(program: &str, config: &InterpreterConfig) -> Option<(i32, Vec<u8>, String)> {
+        // WASI can neither write temporary files nor run other programs
+        if cfg!(target_os = "wasi") {
+            return None;
+        };
+        let ir = crate::compile::compile(&Program::parse(program), Target::LlvmIr, config)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("bfi-llvm-{}-{:x}.ll", std::process::id(),
+            program.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64))));
+        std::fs::write(&path, ir).unwrap();
+        let output = Command::new("lli").arg(&path).stdin(Stdio::null()).output();
+        std::fs::remove_file(&path).unwrap();
+        let output = output.ok()?;
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        Some((output.status.code().unwrap(), output.stdout, stderr))
+    }
+
+    #[test]
+    fn test_instructions() {
+        let program = crate::optimizer::optimize(&Program::parse("--[->+++<]<[<]"));
+        let ir = compile(&program, &InterpreterConfig::default());
+        assert!(ir.contains("add i8 %v4, -2"));
+        assert!(ir.contains("call void @move_ptr(i64 1, i64 2)"));
+        assert!(ir.contains("scan4:"));
+    }
+
+    #[test]
+    fn test_programs() {
+        let config = InterpreterConfig::default();
+        let hello_world = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.\
+            >>.<-.<.+++.------.--------.>>+.>++.";
+        if let Some(result) = run(hello_world, &config) {
+            assert_eq!(result, (0, b"Hello World!\n".to_vec(), String::new()));
+        };
+        let wide = InterpreterConfig { cell_size: CellSize::U16, ..config.clone() };
+        if let Some(result) = run("<<-[->>+<<]>>[>+<+]>-.]", &wide) {
+            let error = "bfi: exited with error: ']' at program position 22 missing \
+                corresponding '['\n";
+            assert_eq!(result, (1, b"\x00".to_vec(), error.to_string()));
+        };
+    }
+
+    #[test]
+    fn test_eof() {
+        for (eof, expected) in [
+            (EofBehavior::Unchanged, b"\x01"),
+            (EofBehavior::Zero, b"\x00"),
+            (EofBehavior::MinusOne, b"\xff"),
+        ] {
+            let config = InterpreterConfig { eof, cell_size: CellSize::U16, ..Default::default() };
+            if let Some(result) = run("+,.", &config) {
+                assert_eq!(result, (0, expected.to_vec(), String::new()));
+            };
+        }
+    }
+
+    #[test]
+    fn test_tape_modes() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        if let Some(result) = run("+.<", &fixed) {
+            let error = "bfi: exited with error: data pointer moved past the start of the tape at \
+                program position 2\n";
+            assert_eq!(result, (1, b"\x01".to_vec(), error.to_string()));
+        };
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed };
+        if let Some(result) = run("<+++[->>+<<]>>.", &circular) {
+            assert_eq!(result, (0, b"\x03".to_vec(), String::new()));
+        };
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_assemble() {
+        let ir = compile(&Program::parse("+."), &InterpreterConfig::default());
+        if let Ok(object) = assemble(ir.as_bytes()) {
+            assert_eq!(&object[..4], b"\x7FELF");
+        };
+    }
+}
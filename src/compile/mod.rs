@@ -0,0 +1,228 @@
+//! Translation of programs into source code for other languages, or into bytecode for `bfi`.
+
+use std::str::FromStr;
+
+use crate::bytecode::Bytecode;
+use crate::config::{BoundsPolicy, InterpreterConfig, IoMode, OverflowPolicy, TapeMode};
+use crate::optimizer;
+use crate::program::Program;
+
+pub mod bf;
+pub mod c;
+#[cfg(feature = "llvm")]
+pub mod llvm;
+pub mod rust;
+pub mod wasm;
+
+
+/// Language to compile a program to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Target {
+    /// Standalone C99 source reading from stdin and writing to stdout.
+    C,
+
+    /// Standalone Rust source for a single `main.rs`, using only safe code.
+    Rust,
+
+    /// WebAssembly module exporting a `run` function, see `compile::wasm`.
+    Wasm,
+
+    /// Plain BrainF\*ck using only the eight standard commands, see `compile::bf`.
+    Brainfuck,
+
+    /// Bytecode for `bfi` itself to run without parsing or optimizing the program again, see
+    /// `bytecode::Bytecode`.
+    Bytecode,
+
+    /// Textual LLVM IR defining `main`, enabled by the `llvm` feature.
+    #[cfg(feature = "llvm")]
+    LlvmIr,
+
+    /// Object file for the host compiled from the LLVM IR with optimizations, enabled by the
+    /// `llvm` feature. Requires LLVM's `opt` and `llc` on the `PATH`.
+    #[cfg(feature = "llvm")]
+    Object,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "c" => Ok(Target::C),
+            "rust" => Ok(Target::Rust),
+            "wasm" => Ok(Target::Wasm),
+            "bf" => Ok(Target::Brainfuck),
+            "bytecode" => Ok(Target::Bytecode),
+            #[cfg(feature = "llvm")]
+            "llvm-ir" => Ok(Target::LlvmIr),
+            #[cfg(feature = "llvm")]
+            "object" => Ok(Target::Object),
+            other => Err(format!("unknown compilation target '{}'", other)),
+        }
+    }
+}
+
+
+/// Source code under construction, indented to the current nesting depth.
+pub(crate) struct Source {
+    text: String,
+    depth: usize,
+}
+
+impl Source {
+    pub(crate) fn new() -> Self { Source { text: String::new(), depth: 0 } }
+
+    /// Append a line at the current depth.
+    pub(crate) fn line(&mut self, line: &str) {
+        if !line.is_empty() {
+            self.text.push_str(&"    ".repeat(self.depth));
+            self.text.push_str(line);
+        };
+        self.text.push('\n');
+    }
+
+    /// Append a line and increase the depth of those following it.
+    pub(crate) fn open(&mut self, line: &str) {
+        self.line(line);
+        self.depth += 1;
+    }
+
+    /// Decrease the depth and append a line.
+    pub(crate) fn close(&mut self, line: &str) {
+        self.depth = self.depth.saturating_sub(1);
+        self.line(line);
+    }
+
+    /// Append a line at the depth of the enclosing block, e.g. `} else {`.
+    pub(crate) fn reopen(&mut self, line: &str) {
+        self.close(line);
+        self.depth += 1;
+    }
+
+    pub(crate) fn into_string(self) -> String { self.text }
+}
+
+
+/// Compile a program for `target`.
+///
+/// The tape mode, tape size, and cell size of the compiled program are taken from `config`, and
+/// the program is optimized first if `config.optimizes`. Debugging instructions are ignored.
+///
+/// Fails if the program uses procedures, forks, extensions, or an `IoMode` other than bytes, or if
+/// the configuration `clamps` or does not wrap cells on overflow, which no target other than
+/// bytecode supports, or if an external tool used to build the target could not be run.
+pub fn compile(
+    program: &Program,
+    target: Target,
+    config: &InterpreterConfig,
+) -> Result<Vec<u8>, String>
+{
+    if target != Target::Bytecode {
+        if program.has_procedures() {
+            return Err("procedures are not supported by compiled programs".to_string());
+        };
+        if program.has_forks() {
+            return Err("forks are not supported by compiled programs".to_string());
+        };
+        if program.has_extensions() {
+            return Err("extensions are not supported by compiled programs".to_string());
+        };
+        match config.io_mode {
+            IoMode::Bytes => {},
+            IoMode::Numeric => {
+                return Err("numeric I/O is not supported by compiled programs".to_string());
+            },
+            IoMode::Utf8 => {
+                return Err("UTF-8 I/O is not supported by compiled programs".to_string());
+            },
+        };
+        if config.clamps() {
+            return Err("clamping at the ends of the tape is not supported by compiled programs"
+                .to_string());
+        };
+        if config.overflow != OverflowPolicy::Wrap {
+            return Err("cells that do not wrap on overflow are not supported by compiled programs"
+                .to_string());
+        };
+    };
+    // a fixed tape that wraps at its ends is simply a circular tape
+    let wrapped;
+    let config = match (config.tape_mode, config.bounds) {
+        (TapeMode::Fixed, BoundsPolicy::Wrap) => {
+            wrapped = InterpreterConfig { tape_mode: TapeMode::Circular, ..config.clone() };
+            &wrapped
+        },
+        _ => config,
+    };
+    let program = if config.optimizes() {
+        optimizer::optimize(program)
+    } else {
+        program.clone()
+    };
+    Ok(match target {
+        Target::C => c::compile(&program, config).into_bytes(),
+        Target::Rust => rust::compile(&program, config).into_bytes(),
+        Target::Wasm => wasm::compile(&program, config),
+        Target::Brainfuck => bf::compile(&program, config)?.into_bytes(),
+        Target::Bytecode => Bytecode { program, cell_size: config.cell_size }.encode(),
+        #[cfg(feature = "llvm")]
+        Target::LlvmIr => llvm::compile(&program, config).into_bytes(),
+        #[cfg(feature = "llvm")]
+        Target::Object => llvm::assemble(llvm::compile(&program, config).as_bytes())?,
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_target_from_str() {
+        assert_eq!("c".parse::<Target>(), Ok(Target::C));
+        assert_eq!("rust".parse::<Target>(), Ok(Target::Rust));
+        assert_eq!("wasm".parse::<Target>(), Ok(Target::Wasm));
+        assert_eq!("bf".parse::<Target>(), Ok(Target::Brainfuck));
+        assert_eq!("bytecode".parse::<Target>(), Ok(Target::Bytecode));
+        assert!("cobol".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_compile_optimizes() {
+        let program = Program::parse("[-]");
+        let optimized = compile(&program, Target::C, &InterpreterConfig::default()).unwrap();
+        let unoptimized = compile(&program, Target::C, &InterpreterConfig {
+            optimize: false,
+            ..InterpreterConfig::default()
+        }).unwrap();
+        assert!(String::from_utf8(optimized).unwrap().contains("tape[ptr] = 0;"));
+        assert!(!String::from_utf8(unoptimized).unwrap().contains("tape[ptr] = 0;"));
+    }
+
+    #[test]
+    fn test_compile_bounds() {
+        let program = Program::parse("<+.");
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed.clone() };
+        let wrap = InterpreterConfig { bounds: BoundsPolicy::Wrap, ..fixed.clone() };
+        assert_eq!(compile(&program, Target::C, &wrap), compile(&program, Target::C, &circular));
+        let clamp = InterpreterConfig { bounds: BoundsPolicy::Clamp, ..fixed };
+        assert!(compile(&program, Target::C, &clamp).is_err());
+    }
+
+    #[test]
+    fn test_compile_overflow() {
+        let program = Program::parse("+.");
+        let config = InterpreterConfig {
+            overflow: OverflowPolicy::Saturate,
+            ..InterpreterConfig::default()
+        };
+        assert!(compile(&program, Target::C, &config).is_err());
+        assert!(compile(&program, Target::Bytecode, &config).is_ok());
+    }
+}
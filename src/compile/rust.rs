@@ -0,0 +1,329 @@
+//! Rust backend, see `compile::Target::Rust`.
+//!
+//! The compiled program is a single `main.rs` using only safe Rust and the standard library, so it
+//! can be built with nothing more than `rustc -O main.rs`.
+
+use crate::compile::Source;
+use crate::config::{CellSize, EofBehavior, InterpreterConfig, TapeMode};
+use crate::program::{Instruction, Program};
+
+
+/// Definition of the machine state shared by all tape modes. `move_ptr` is defined separately, as
+/// is the `EOF` value stored by `get_char` at the end of input.
+static MACHINE: &str = "\
+struct Machine {
+    tape: Vec<Cell>,
+    ptr: usize,
+    input: io::Bytes<io::BufReader<io::Stdin>>,
+    output: io::BufWriter<io::Stdout>,
+}
+
+impl Machine {
+    fn nonzero(&self) -> bool { self.tape[self.ptr] != 0 }
+
+    fn add(&mut self, n: Cell) { self.tape[self.ptr] = self.tape[self.ptr].wrapping_add(n); }
+
+    fn set_zero(&mut self) { self.tape[self.ptr] = 0; }
+
+    fn scan(&mut self, n: isize, position: usize) {
+        while self.nonzero() {
+            self.move_ptr(n, position);
+        }
+    }
+
+    fn mul_add(&mut self, offset: isize, factor: Cell, position: usize) {
+        if self.nonzero() {
+            let value = self.tape[self.ptr].wrapping_mul(factor);
+            self.move_ptr(offset, position);
+            self.add(value);
+            self.move_ptr(-offset, position);
+        }
+    }
+
+    fn put_char(&mut self) {
+        let byte = self.tape[self.ptr] as u8;
+        if let Err(e) = self.output.write_all(&[byte]) {
+            self.fail(&e.to_string());
+        }
+    }
+
+    fn get_char(&mut self) {
+        match self.input.next() {
+            Some(Ok(byte)) => self.tape[self.ptr] = Cell::from(byte),
+            Some(Err(e)) => self.fail(&e.to_string()),
+            None => if let Some(value) = EOF {
+                self.tape[self.ptr] = value;
+            },
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Err(e) = self.output.flush() {
+            self.fail(&e.to_string());
+        }
+    }
+
+    fn fail(&mut self, message: &str) -> ! {
+        let _ = self.output.flush();
+        eprintln!(\"bfi: exited with error: {}\", message);
+        process::exit(1)
+    }
+}
+";
+
+
+/// Compile a program to Rust, see `compile::compile`.
+pub fn compile(program: &Program, config: &InterpreterConfig) -> String {
+    let mut src = Source::new();
+    src.line("// generated by bfi");
+    src.line("#![allow(dead_code, unreachable_code)]");
+    src.line("");
+    src.line("use std::io::{self, Read, Write};");
+    src.line("use std::process;");
+    src.line("");
+    src.line(&format!("type Cell = {};", cell_type(config.cell_size)));
+    src.line(&format!("const EOF: Option<Cell> = {};", match config.eof {
+        EofBehavior::Unchanged => "None".to_string(),
+        EofBehavior::Zero => "Some(0)".to_string(),
+        EofBehavior::MinusOne => format!("Some({})", cell_literal(-1, config.cell_size)),
+    }));
+    src.line("");
+    for line in MACHINE.lines() {
+        src.line(line);
+    }
+    src.line("");
+    tape(&mut src, config);
+
+    src.open("fn main() {");
+    src.open("let mut m = Machine {");
+    match config.tape_mode {
+        TapeMode::Growable => src.line("tape: vec![0],"),
+        TapeMode::Fixed | TapeMode::Circular => {
+            src.line(&format!("tape: vec![0; {}],", config.tape_size.max(1)));
+        },
+    };
+    src.line("ptr: 0,");
+    src.line("input: io::BufReader::new(io::stdin()).bytes(),");
+    src.line("output: io::BufWriter::new(io::stdout()),");
+    src.close("};");
+    for (i, instruction) in program.instructions().iter().enumerate() {
+        self::instruction(&mut src, *instruction, program.position(i), config.cell_size);
+    }
+    src.line("m.finish();");
+    src.close("}");
+    src.into_string()
+}
+
+
+fn cell_type(cell_size: CellSize) -> &'static str {
+    match cell_size {
+        CellSize::U8 => "u8",
+        CellSize::U16 => "u16",
+        CellSize::U32 => "u32",
+        CellSize::I32 => "i32",
+    }
+}
+
+
+/// Literal of type `Cell` equal to `n` modulo the width of the cell.
+fn cell_literal(n: i32, cell_size: CellSize) -> String {
+    match cell_size {
+        CellSize::U8 => (n as u8).to_string(),
+        CellSize::U16 => (n as u16).to_string(),
+        CellSize::U32 => (n as u32).to_string(),
+        CellSize::I32 => n.to_string(),
+    }
+}
+
+
+/// Define `Machine::move_ptr` for the tape mode.
+fn tape(src: &mut Source, config: &InterpreterConfig) {
+    let size = config.tape_size.max(1);
+    src.open("impl Machine {");
+    match config.tape_mode {
+        TapeMode::Growable => {
+            src.open("fn move_ptr(&mut self, n: isize, _position: usize) {");
+            src.line("let target = self.ptr as isize + n;");
+            src.open("if target < 0 {");
+            src.line("self.tape.splice(0..0, std::iter::repeat(0).take(-target as usize));");
+            src.line("self.ptr = 0;");
+            src.reopen("} else {");
+            src.line("self.ptr = target as usize;");
+            src.open("if self.ptr >= self.tape.len() {");
+            src.line("self.tape.resize(self.ptr + 1, 0);");
+            src.close("}");
+            src.close("}");
+        },
+        TapeMode::Fixed => {
+            src.open("fn move_ptr(&mut self, n: isize, position: usize) {");
+            src.line("let target = self.ptr as isize + n;");
+            src.open("let end = if target < 0 {");
+            src.line("\"start\"");
+            src.reopen(&format!("}} else if target >= {} {{", size));
+            src.line("\"end\"");
+            src.reopen("} else {");
+            src.line("self.ptr = target as usize;");
+            src.line("return;");
+            src.close("};");
+            src.line(concat!(
+                "self.fail(&format!(\"data pointer moved past the {} of the tape at program ",
+                "position {}\", end, position));",
+            ));
+        },
+        TapeMode::Circular => {
+            src.open("fn move_ptr(&mut self, n: isize, _position: usize) {");
+            src.line(&format!("self.ptr = (self.ptr as isize + n).rem_euclid({}) as usize;", size));
+        },
+    };
+    src.close("}");
+    src.close("}");
+    src.line("");
+}
+
+
+fn instruction(src: &mut Source, instruction: Instruction, position: usize, cell_size: CellSize) {
+    match instruction {
+        Instruction::Add(n) => src.line(&format!("m.add({});", cell_literal(n, cell_size))),
+        Instruction::Move(n) => src.line(&format!("m.move_ptr({}, {});", n, position)),
+        Instruction::SetZero => src.line("m.set_zero();"),
+        Instruction::Scan(n) => src.line(&format!("m.scan({}, {});", n, position)),
+        Instruction::MulAdd(offset, factor) => src.line(&format!(
+            "m.mul_add({}, {}, {});", offset, cell_literal(factor, cell_size), position)),
+        Instruction::PutChar => src.line("m.put_char();"),
+        Instruction::GetChar => src.line("m.get_char();"),
+        Instruction::LoopBeg(Some(_)) => src.open("while m.nonzero() {"),
+        Instruction::LoopEnd(Some(_)) => src.close("}"),
+        Instruction::LoopBeg(None) => src.line(&format!(
+            "m.fail(\"'[' at program position {} missing corresponding ']'\");", position)),
+        Instruction::LoopEnd(None) => src.line(&format!(
+            "m.fail(\"']' at program position {} missing corresponding '['\");", position)),
+        Instruction::DebugDump | Instruction::DebugBreakpoint => {},
+        // programs with procedures, forks, or extensions are rejected by `compile::compile`
+        Instruction::ProcBeg(_)
+        | Instruction::ProcEnd(_)
+        | Instruction::ProcCall
+        | Instruction::Fork
+        | Instruction::End
+        | Instruction::Store
+        | Instruction::Load
+        | Instruction::Bitwise(_)
+        | Instruction::Random
+        | Instruction::Clock
+        | Instruction::NextTape
+        | Instruction::PrevTape
+        | Instruction::RowUp
+        | Instruction::RowDown => {},
+    };
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use crate::compile::Target;
+    use std::process::{Command, Stdio};
+
+    /// Build the Rust source for `program` with `rustc` and run it, returning its exit code and
+    /// output. Returns `None` if `rustc` is not available.
+    fn build_and_run(
+        program: &str,
+        config: &InterpreterConfig,
+        input: &[u8],
+    ) -> Option<(i32, Vec<u8>, String)>
+    {
+        // WASI can neither write temporary files nor run other programs
+        if cfg!(target_os = "wasi") {
+            return None;
+        };
+        let dir = std::env::temp_dir().join(format!("bfi-rust-{}-{:x}", std::process::id(),
+            program.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64))));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("main.rs");
+        let binary = dir.join("main");
+        let compiled = crate::compile::compile(&Program::parse(program), Target::Rust, config)
+            .unwrap();
+        std::fs::write(&source, compiled).unwrap();
+        let output = Command::new("rustc")
+            .arg("-o")
+            .arg(&binary)
+            .arg(&source)
+            .output()
+            .ok()?;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+        let mut child = Command::new(&binary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.as_mut().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        Some((output.status.code().unwrap(), output.stdout, stderr))
+    }
+
+    #[test]
+    fn test_cell_literal() {
+        assert_eq!(cell_literal(-1, CellSize::U8), "255");
+        assert_eq!(cell_literal(-1, CellSize::U16), "65535");
+        assert_eq!(cell_literal(-1, CellSize::I32), "-1");
+    }
+
+    #[test]
+    fn test_instructions() {
+        let program = crate::optimizer::optimize(&Program::parse("--[->+++<]<[<]"));
+        let src = compile(&program, &InterpreterConfig::default());
+        assert!(src.contains("m.add(254);"));
+        assert!(src.contains("m.mul_add(1, 3, 2);"));
+        assert!(src.contains("m.scan(-1, 11);"));
+    }
+
+    #[test]
+    fn test_programs() {
+        let config = InterpreterConfig::default();
+        if let Some(result) = build_and_run("<<,[.[-]<,]>>>.", &config, b"abc") {
+            assert_eq!(result, (0, b"abc\x00".to_vec(), String::new()));
+        };
+        let signed = InterpreterConfig { cell_size: CellSize::I32, ..config };
+        if let Some(result) = build_and_run("-[->-<]>[>+<-]>-.[]]", &signed, b"") {
+            let error = "bfi: exited with error: ']' at program position 19 missing \
+                corresponding '['\n";
+            assert_eq!(result, (1, b"\x00".to_vec(), error.to_string()));
+        };
+    }
+
+    #[test]
+    fn test_eof() {
+        for (eof, expected) in [
+            (EofBehavior::Unchanged, b"aa"),
+            (EofBehavior::Zero, b"a\x00"),
+            (EofBehavior::MinusOne, b"a\xff"),
+        ] {
+            let config = InterpreterConfig { eof, cell_size: CellSize::U16, ..Default::default() };
+            if let Some(result) = build_and_run(",.,.", &config, b"a") {
+                assert_eq!(result, (0, expected.to_vec(), String::new()));
+            };
+        }
+    }
+
+    #[test]
+    fn test_tape_modes() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        if let Some(result) = build_and_run("+.<", &fixed, b"") {
+            let error = "bfi: exited with error: data pointer moved past the start of the tape at \
+                program position 2\n";
+            assert_eq!(result, (1, b"\x01".to_vec(), error.to_string()));
+        };
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed };
+        if let Some(result) = build_and_run("<+++[->>+<<]>>.", &circular, b"") {
+            assert_eq!(result, (0, b"\x03".to_vec(), String::new()));
+        };
+    }
+}
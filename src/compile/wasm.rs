@@ -0,0 +1,643 @@
+//! WebAssembly backend, see `compile::Target::Wasm`.
+//!
+//! The compiled module imports `env.putchar: [i32] -> []` and `env.getchar: [] -> [i32]`, the
+//! latter returning a negative value at the end of input, and exports its `memory` along with
+//! `run: [] -> [i32]`. `run` returns zero when the program terminates without errors, and one more
+//! than the program position of the offending instruction when moving past the end of a fixed tape
+//! or reaching an unmatched bracket.
+//!
+//! The tape starts at address zero of the exported memory, which is grown as needed by the
+//! growable tape. Bulk memory instructions are used to make room at the start of the tape.
+
+use crate::config::{CellSize, EofBehavior, InterpreterConfig, TapeMode};
+use crate::program::{Instruction, Program};
+
+
+const PAGE_SIZE: usize = 65536;
+
+// function indices, imports first
+const PUTCHAR: u32 = 0;
+const GETCHAR: u32 = 1;
+const RUN: u32 = 2;
+const MOVE: u32 = 3;
+const ENSURE: u32 = 4;
+
+// global indices
+const PTR: u32 = 0;
+const LEN: u32 = 1;
+
+// type indices
+const TYPE_I32_TO_VOID: u32 = 0;
+const TYPE_VOID_TO_I32: u32 = 1;
+const TYPE_I32_TO_I32: u32 = 2;
+
+const I32: u8 = 0x7F;
+const VOID: u8 = 0x40;
+
+mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0B;
+    pub const BR: u8 = 0x0C;
+    pub const BR_IF: u8 = 0x0D;
+    pub const RETURN: u8 = 0x0F;
+    pub const CALL: u8 = 0x10;
+    pub const SELECT: u8 = 0x1B;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const LOCAL_TEE: u8 = 0x22;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const MEMORY_SIZE: u8 = 0x3F;
+    pub const MEMORY_GROW: u8 = 0x40;
+    pub const I32_CONST: u8 = 0x41;
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_LT_S: u8 = 0x48;
+    pub const I32_GT_U: u8 = 0x4B;
+    pub const I32_GE_S: u8 = 0x4E;
+    pub const I32_ADD: u8 = 0x6A;
+    pub const I32_SUB: u8 = 0x6B;
+    pub const I32_MUL: u8 = 0x6C;
+    pub const I32_REM_S: u8 = 0x6F;
+    pub const I32_REM_U: u8 = 0x70;
+    pub const I32_AND: u8 = 0x71;
+    pub const I32_OR: u8 = 0x72;
+    pub const I32_SHL: u8 = 0x74;
+    pub const I32_SHR_U: u8 = 0x76;
+    pub const PREFIX_FC: u8 = 0xFC;
+    pub const MEMORY_COPY: u32 = 10;
+    pub const MEMORY_FILL: u32 = 11;
+}
+
+
+/// Compile a program to a WebAssembly module, see `compile::compile`.
+pub fn compile(program: &Program, config: &InterpreterConfig) -> Vec<u8> {
+    let size = config.tape_size.max(1);
+    let scale = match config.cell_size {
+        CellSize::U8 => 0,
+        CellSize::U16 => 1,
+        CellSize::U32 | CellSize::I32 => 2,
+    };
+    let (pages, len) = match config.tape_mode {
+        TapeMode::Growable => (1, 1),
+        TapeMode::Fixed | TapeMode::Circular => ((size << scale).div_ceil(PAGE_SIZE), size),
+    };
+
+    let mut module = b"\0asm".to_vec();
+    module.extend_from_slice(&1u32.to_le_bytes());
+
+    section(&mut module, 1, vector(vec![
+        function_type(&[I32], &[]),
+        function_type(&[], &[I32]),
+        function_type(&[I32], &[I32]),
+    ]));
+    section(&mut module, 2, vector(vec![
+        import("putchar", TYPE_I32_TO_VOID),
+        import("getchar", TYPE_VOID_TO_I32),
+    ]));
+    section(&mut module, 3, vector(vec![
+        uleb(TYPE_VOID_TO_I32),
+        uleb(TYPE_I32_TO_I32),
+        uleb(TYPE_I32_TO_VOID),
+    ]));
+    section(&mut module, 5, vector(vec![[vec![0x00], uleb(pages as u32)].concat()]));
+    section(&mut module, 6, vector(vec![global(0), global(len as i32)]));
+    section(&mut module, 7, vector(vec![
+        [name("run"), vec![0x00], uleb(RUN)].concat(),
+        [name("memory"), vec![0x02], uleb(0)].concat(),
+    ]));
+
+    let eof = match config.eof {
+        EofBehavior::Unchanged => None,
+        EofBehavior::Zero => Some(0),
+        EofBehavior::MinusOne => Some(-1),
+    };
+    let mut run = Code { eof, ..Code::new(scale) };
+    for (i, instruction) in program.instructions().iter().enumerate() {
+        run.instruction(*instruction, program.position(i));
+    }
+    run.i32_const(0);
+    section(&mut module, 10, vector(vec![
+        run.function(3),
+        move_function(config.tape_mode, scale, size as i32),
+        ensure_function(),
+    ]));
+    module
+}
+
+
+fn uleb(mut n: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            return bytes;
+        };
+        bytes.push(byte | 0x80);
+    }
+}
+
+
+fn sleb(mut n: i32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0) {
+            bytes.push(byte);
+            return bytes;
+        };
+        bytes.push(byte | 0x80);
+    }
+}
+
+
+fn section(module: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+    module.push(id);
+    module.extend(uleb(contents.len() as u32));
+    module.extend(contents);
+}
+
+
+fn vector(items: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut bytes = uleb(items.len() as u32);
+    for item in items {
+        bytes.extend(item);
+    }
+    bytes
+}
+
+
+fn name(s: &str) -> Vec<u8> { [uleb(s.len() as u32), s.as_bytes().to_vec()].concat() }
+
+
+fn function_type(params: &[u8], results: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x60];
+    bytes.extend(vector(params.iter().map(|t| vec![*t]).collect()));
+    bytes.extend(vector(results.iter().map(|t| vec![*t]).collect()));
+    bytes
+}
+
+
+fn import(function: &str, type_index: u32) -> Vec<u8> {
+    [name("env"), name(function), vec![0x00], uleb(type_index)].concat()
+}
+
+
+/// Mutable `i32` global with an initial value.
+fn global(init: i32) -> Vec<u8> {
+    [vec![I32, 0x01, op::I32_CONST], sleb(init), vec![op::END]].concat()
+}
+
+
+/// Body of a function under construction, operating on cells `1 << scale` bytes wide.
+struct Code {
+    bytes: Vec<u8>,
+    scale: u8,
+    /// Value stored by `GetChar` at the end of input, if any.
+    eof: Option<i32>,
+}
+
+impl Code {
+    fn new(scale: u8) -> Self { Code { bytes: Vec::new(), scale, eof: None } }
+
+    /// Finish the function, declaring `locals` additional `i32` locals.
+    fn function(mut self, locals: u32) -> Vec<u8> {
+        self.op(op::END);
+        let body = [vector(vec![[uleb(locals), vec![I32]].concat()]), self.bytes].concat();
+        [uleb(body.len() as u32), body].concat()
+    }
+
+    fn op(&mut self, opcode: u8) { self.bytes.push(opcode); }
+
+    fn op_index(&mut self, opcode: u8, index: u32) {
+        self.bytes.push(opcode);
+        self.bytes.extend(uleb(index));
+    }
+
+    fn i32_const(&mut self, n: i32) {
+        self.bytes.push(op::I32_CONST);
+        self.bytes.extend(sleb(n));
+    }
+
+    fn block(&mut self, opcode: u8) { self.bytes.extend(&[opcode, VOID]); }
+
+    /// Convert the cell index on top of the stack to a byte address.
+    fn scale_index(&mut self) {
+        if self.scale > 0 {
+            self.i32_const(self.scale as i32);
+            self.op(op::I32_SHL);
+        };
+    }
+
+    /// Push the address of the current cell.
+    fn cell_address(&mut self) {
+        self.op_index(op::GLOBAL_GET, PTR);
+        self.scale_index();
+    }
+
+    /// Replace the address on top of the stack with the value of the cell at that address.
+    fn load(&mut self) {
+        let opcode = [0x2D, 0x2F, 0x28][self.scale as usize]; // i32.load8_u, load16_u, load
+        self.bytes.extend(&[opcode, self.scale, 0x00]);
+    }
+
+    /// Store the value on top of the stack at the address below it.
+    fn store(&mut self) {
+        let opcode = [0x3A, 0x3B, 0x36][self.scale as usize]; // i32.store8, store16, store
+        self.bytes.extend(&[opcode, self.scale, 0x00]);
+    }
+
+    fn load_cell(&mut self) {
+        self.cell_address();
+        self.load();
+    }
+
+    /// Return `position + 1` from `run`.
+    fn fail(&mut self, position: usize) {
+        self.i32_const(position as i32 + 1);
+        self.op(op::RETURN);
+    }
+
+    fn move_ptr(&mut self, n: isize, position: usize) {
+        self.i32_const(n as i32);
+        self.op_index(op::CALL, MOVE);
+        self.block(op::IF);
+        self.fail(position);
+        self.op(op::END);
+    }
+
+    /// Add the value on top of the stack to the current cell.
+    fn add_to_cell(&mut self) {
+        self.op_index(op::LOCAL_SET, 1);
+        self.cell_address();
+        self.op_index(op::LOCAL_TEE, 0);
+        self.op_index(op::LOCAL_GET, 0);
+        self.load();
+        self.op_index(op::LOCAL_GET, 1);
+        self.op(op::I32_ADD);
+        self.store();
+    }
+
+    /// Emit the code for one instruction of `run`, which has the locals `0: address`,
+    /// `1: value`, and `2: source`, the last holding the input or the value of a `MulAdd` loop
+    /// counter.
+    fn instruction(&mut self, instruction: Instruction, position: usize) {
+        match instruction {
+            Instruction::Add(n) => {
+                self.i32_const(n);
+                self.add_to_cell();
+            },
+            Instruction::Move(n) => self.move_ptr(n, position),
+            Instruction::SetZero => {
+                self.cell_address();
+                self.i32_const(0);
+                self.store();
+            },
+            Instruction::Scan(n) => {
+                self.block(op::BLOCK);
+                self.block(op::LOOP);
+                self.load_cell();
+                self.op(op::I32_EQZ);
+                self.op_index(op::BR_IF, 1);
+                self.move_ptr(n, position);
+                self.op_index(op::BR, 0);
+                self.op(op::END);
+                self.op(op::END);
+            },
+            Instruction::MulAdd(offset, factor) => {
+                self.load_cell();
+                self.op_index(op::LOCAL_TEE, 2);
+                self.block(op::IF);
+                self.move_ptr(offset, position);
+                self.op_index(op::LOCAL_GET, 2);
+                self.i32_const(factor);
+                self.op(op::I32_MUL);
+                self.add_to_cell();
+                self.move_ptr(-offset, position);
+                self.op(op::END);
+            },
+            Instruction::PutChar => {
+                self.load_cell();
+                self.i32_const(0xFF);
+                self.op(op::I32_AND);
+                self.op_index(op::CALL, PUTCHAR);
+            },
+            Instruction::GetChar => match self.eof {
+                // store the input byte, or the configured value if the input was exhausted
+                Some(eof) => {
+                    self.cell_address();
+                    self.op_index(op::CALL, GETCHAR);
+                    self.op_index(op::LOCAL_TEE, 2);
+                    self.i32_const(eof);
+                    self.op_index(op::LOCAL_GET, 2);
+                    self.i32_const(0);
+                    self.op(op::I32_GE_S);
+                    self.op(op::SELECT);
+                    self.store();
+                },
+                None => {
+                    self.op_index(op::CALL, GETCHAR);
+                    self.op_index(op::LOCAL_TEE, 2);
+                    self.i32_const(0);
+                    self.op(op::I32_GE_S);
+                    self.block(op::IF);
+                    self.cell_address();
+                    self.op_index(op::LOCAL_GET, 2);
+                    self.store();
+                    self.op(op::END);
+                },
+            },
+            Instruction::LoopBeg(Some(_)) => {
+                self.block(op::BLOCK);
+                self.block(op::LOOP);
+                self.load_cell();
+                self.op(op::I32_EQZ);
+                self.op_index(op::BR_IF, 1);
+            },
+            Instruction::LoopEnd(Some(_)) => {
+                self.op_index(op::BR, 0);
+                self.op(op::END);
+                self.op(op::END);
+            },
+            Instruction::LoopBeg(None) | Instruction::LoopEnd(None) => self.fail(position),
+            Instruction::DebugDump | Instruction::DebugBreakpoint => {},
+            // programs with procedures, forks, or extensions are rejected by `compile::compile`
+            Instruction::ProcBeg(_)
+            | Instruction::ProcEnd(_)
+            | Instruction::ProcCall
+            | Instruction::Fork
+            | Instruction::End
+            | Instruction::Store
+            | Instruction::Load
+            | Instruction::Bitwise(_)
+            | Instruction::Random
+            | Instruction::Clock
+            | Instruction::NextTape
+            | Instruction::PrevTape
+            | Instruction::RowUp
+            | Instruction::RowDown => {},
+        };
+    }
+}
+
+
+/// `move: [n] -> [error]`, moving the data pointer `n` cells and returning nonzero if it moved off
+/// of a fixed tape. Uses the locals `1: target` and `2: extra`.
+fn move_function(tape_mode: TapeMode, scale: u8, size: i32) -> Vec<u8> {
+    let mut code = Code::new(scale);
+    match tape_mode {
+        TapeMode::Fixed => {
+            code.op_index(op::GLOBAL_GET, PTR);
+            code.op_index(op::LOCAL_GET, 0);
+            code.op(op::I32_ADD);
+            code.op_index(op::LOCAL_TEE, 1);
+            code.i32_const(0);
+            code.op(op::I32_LT_S);
+            code.op_index(op::LOCAL_GET, 1);
+            code.i32_const(size);
+            code.op(op::I32_GE_S);
+            code.op(op::I32_OR);
+            code.block(op::IF);
+            code.i32_const(1);
+            code.op(op::RETURN);
+            code.op(op::END);
+            code.op_index(op::LOCAL_GET, 1);
+            code.op_index(op::GLOBAL_SET, PTR);
+        },
+        TapeMode::Circular => {
+            // (ptr + n % size + size) % size, which cannot overflow as both ptr, |n % size| < size
+            code.op_index(op::GLOBAL_GET, PTR);
+            code.op_index(op::LOCAL_GET, 0);
+            code.i32_const(size);
+            code.op(op::I32_REM_S);
+            code.op(op::I32_ADD);
+            code.i32_const(size);
+            code.op(op::I32_ADD);
+            code.i32_const(size);
+            code.op(op::I32_REM_U);
+            code.op_index(op::GLOBAL_SET, PTR);
+        },
+        TapeMode::Growable => {
+            code.op_index(op::GLOBAL_GET, PTR);
+            code.op_index(op::LOCAL_GET, 0);
+            code.op(op::I32_ADD);
+            code.op_index(op::LOCAL_TEE, 1);
+            code.i32_const(0);
+            code.op(op::I32_LT_S);
+            code.block(op::IF);
+            // shift the tape right to make room for `extra` cells at the start
+            code.i32_const(0);
+            code.op_index(op::LOCAL_GET, 1);
+            code.op(op::I32_SUB);
+            code.op_index(op::LOCAL_SET, 2);
+            code.op_index(op::GLOBAL_GET, LEN);
+            code.op_index(op::LOCAL_GET, 2);
+            code.op(op::I32_ADD);
+            code.scale_index();
+            code.op_index(op::CALL, ENSURE);
+            code.op_index(op::LOCAL_GET, 2);
+            code.scale_index();
+            code.i32_const(0);
+            code.op_index(op::GLOBAL_GET, LEN);
+            code.scale_index();
+            code.op(op::PREFIX_FC);
+            code.bytes.extend(uleb(op::MEMORY_COPY));
+            code.bytes.extend(&[0x00, 0x00]);
+            code.i32_const(0);
+            code.i32_const(0);
+            code.op_index(op::LOCAL_GET, 2);
+            code.scale_index();
+            code.op(op::PREFIX_FC);
+            code.bytes.extend(uleb(op::MEMORY_FILL));
+            code.bytes.push(0x00);
+            code.op_index(op::GLOBAL_GET, LEN);
+            code.op_index(op::LOCAL_GET, 2);
+            code.op(op::I32_ADD);
+            code.op_index(op::GLOBAL_SET, LEN);
+            code.i32_const(0);
+            code.op_index(op::GLOBAL_SET, PTR);
+            code.op(op::ELSE);
+            // extend the tape to the right, memory past its end is always zero
+            code.op_index(op::LOCAL_GET, 1);
+            code.op_index(op::GLOBAL_SET, PTR);
+            code.op_index(op::LOCAL_GET, 1);
+            code.op_index(op::GLOBAL_GET, LEN);
+            code.op(op::I32_GE_S);
+            code.block(op::IF);
+            code.op_index(op::LOCAL_GET, 1);
+            code.i32_const(1);
+            code.op(op::I32_ADD);
+            code.op_index(op::GLOBAL_SET, LEN);
+            code.op_index(op::GLOBAL_GET, LEN);
+            code.scale_index();
+            code.op_index(op::CALL, ENSURE);
+            code.op(op::END);
+            code.op(op::END);
+        },
+    };
+    code.i32_const(0);
+    code.function(2)
+}
+
+
+/// `ensure: [bytes] -> []`, growing the memory to at least `bytes` bytes or trapping if it cannot
+/// be grown. Uses the local `1: pages`.
+fn ensure_function() -> Vec<u8> {
+    let mut code = Code::new(0);
+    code.op_index(op::LOCAL_GET, 0);
+    code.i32_const(PAGE_SIZE as i32 - 1);
+    code.op(op::I32_ADD);
+    code.i32_const(16);
+    code.op(op::I32_SHR_U);
+    code.op_index(op::LOCAL_TEE, 1);
+    code.bytes.extend(&[op::MEMORY_SIZE, 0x00]);
+    code.op(op::I32_GT_U);
+    code.block(op::IF);
+    code.op_index(op::LOCAL_GET, 1);
+    code.bytes.extend(&[op::MEMORY_SIZE, 0x00]);
+    code.op(op::I32_SUB);
+    code.bytes.extend(&[op::MEMORY_GROW, 0x00]);
+    code.i32_const(-1);
+    code.op(op::I32_EQ);
+    code.block(op::IF);
+    code.op(op::UNREACHABLE);
+    code.op(op::END);
+    code.op(op::END);
+    code.function(1)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use crate::compile::Target;
+
+    /// Node.js script instantiating the module named by its argument with stdin as input, writing
+    /// the output of the module to stdout and exiting with the return value of `run`.
+    static RUNNER: &str = "
+        const input = require('fs').readFileSync(0);
+        const bytes = require('fs').readFileSync(process.argv[1]);
+        let read = 0;
+        const output = [];
+        const env = {
+            putchar: (c) => output.push(c),
+            getchar: () => read < input.length ? input[read++] : -1,
+        };
+        WebAssembly.instantiate(bytes, { env }).then(({ instance }) => {
+            const status = instance.exports.run();
+            process.stdout.write(Buffer.from(output));
+            process.exitCode = status;
+        });
+    ";
+
+    /// Run the module compiled from `program` with Node.js, returning the return value of `run`
+    /// and the output. Returns `None` if Node.js is not available.
+    fn run(program: &str, config: &InterpreterConfig, input: &[u8]) -> Option<(i32, Vec<u8>)> {
+        // WASI can neither write temporary files nor run other programs
+        if cfg!(target_os = "wasi") {
+            return None;
+        };
+        let path = std::env::temp_dir().join(format!("bfi-wasm-{}-{:x}.wasm", std::process::id(),
+            program.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64))));
+        let compiled = crate::compile::compile(&Program::parse(program), Target::Wasm, config)
+            .unwrap();
+        std::fs::write(&path, compiled).unwrap();
+        let child = Command::new("node")
+            .arg("-e")
+            .arg(RUNNER)
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => {
+                std::fs::remove_file(&path).unwrap();
+                return None;
+            },
+        };
+        child.stdin.as_mut().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+        Some((output.status.code().unwrap(), output.stdout))
+    }
+
+    #[test]
+    fn test_leb128() {
+        assert_eq!(uleb(0), vec![0x00]);
+        assert_eq!(uleb(624_485), vec![0xE5, 0x8E, 0x26]);
+        assert_eq!(sleb(63), vec![0x3F]);
+        assert_eq!(sleb(64), vec![0xC0, 0x00]);
+        assert_eq!(sleb(-1), vec![0x7F]);
+        assert_eq!(sleb(-123_456), vec![0xC0, 0xBB, 0x78]);
+    }
+
+    #[test]
+    fn test_header() {
+        let module = compile(&Program::parse(""), &InterpreterConfig::default());
+        assert_eq!(&module[..8], b"\0asm\x01\0\0\0");
+    }
+
+    #[test]
+    fn test_programs() {
+        let config = InterpreterConfig::default();
+        let hello_world = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.\
+            >>.<-.<.+++.------.--------.>>+.>++.";
+        if let Some(result) = run(hello_world, &config, b"") {
+            assert_eq!(result, (0, b"Hello World!\n".to_vec()));
+        };
+        if let Some(result) = run("<<,[.[-]<,]>>>.", &config, b"abc") {
+            assert_eq!(result, (0, b"abc\x00".to_vec()));
+        };
+        let wide = InterpreterConfig { cell_size: CellSize::U16, ..config.clone() };
+        if let Some(result) = run("-[->+<]>[>+<+]>-.]", &wide, b"") {
+            assert_eq!(result, (18, b"\x00".to_vec()));
+        };
+        // grow the memory past its initial page in both directions
+        let far = format!("{}+.{}.", ">".repeat(70_000), "<".repeat(140_000));
+        if let Some(result) = run(&far, &wide, b"") {
+            assert_eq!(result, (0, b"\x01\x00".to_vec()));
+        };
+    }
+
+    #[test]
+    fn test_eof() {
+        for (eof, expected) in [
+            (EofBehavior::Unchanged, b"aa"),
+            (EofBehavior::Zero, b"a\x00"),
+            (EofBehavior::MinusOne, b"a\xff"),
+        ] {
+            let config = InterpreterConfig { eof, cell_size: CellSize::U16, ..Default::default() };
+            if let Some(result) = run(",.,.", &config, b"a") {
+                assert_eq!(result, (0, expected.to_vec()));
+            };
+        }
+    }
+
+    #[test]
+    fn test_tape_modes() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        if let Some(result) = run("+.>>>>", &fixed, b"") {
+            assert_eq!(result, (3, b"\x01".to_vec()));
+        };
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed };
+        if let Some(result) = run("<+++[->>+<<]>>.", &circular, b"") {
+            assert_eq!(result, (0, b"\x03".to_vec()));
+        };
+    }
+}
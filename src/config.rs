@@ -0,0 +1,596 @@
+//! Configuration options controlling the semantics of the interpreter.
+
+use alloc::format;
+use alloc::string::String;
+use core::default::Default;
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+use crate::cell::Cell;
+use crate::error::TapeEnd;
+
+
+/// Number of cells on the tape of the original BrainF\*ck implementation.
+pub const DEFAULT_TAPE_SIZE: usize = 30_000;
+
+/// Default limit on nested procedure calls, see `InterpreterConfig::max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+/// Default number of tapes of the multitape extension, see `InterpreterConfig::tapes`.
+pub const DEFAULT_TAPES: usize = 2;
+
+/// Step limit of a sandboxed configuration without one, see `InterpreterConfig::sandboxed`.
+pub const SANDBOX_MAX_STEPS: u64 = 10_000_000;
+
+/// Memory limit in bytes of a sandboxed configuration without one.
+pub const SANDBOX_MAX_MEMORY: usize = 16 << 20;
+
+
+/// Behavior of the tape when the data pointer is moved past either of its ends.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TapeMode {
+    /// Classic tape of `tape_size` cells. Moving off of either end is a program error.
+    Fixed,
+
+    /// Tape that grows in both directions as needed.
+    Growable,
+
+    /// Tape of `tape_size` cells with the two ends joined, such that moving off of one end places
+    /// the data pointer at the other.
+    Circular,
+}
+
+impl FromStr for TapeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(TapeMode::Fixed),
+            "growable" => Ok(TapeMode::Growable),
+            "circular" => Ok(TapeMode::Circular),
+            other => Err(format!("unknown tape mode '{}'", other)),
+        }
+    }
+}
+
+
+/// What happens when the data pointer is moved past either end of a `TapeMode::Fixed` tape.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BoundsPolicy {
+    /// Fail with `error::BfError::TapeOverflow`.
+    Error,
+
+    /// Continue from the other end, as if the tape were `TapeMode::Circular`.
+    Wrap,
+
+    /// Stop the data pointer at the end it moved past.
+    Clamp,
+}
+
+impl FromStr for BoundsPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(BoundsPolicy::Error),
+            "wrap" => Ok(BoundsPolicy::Wrap),
+            "clamp" => Ok(BoundsPolicy::Clamp),
+            other => Err(format!("unknown bounds policy '{}'", other)),
+        }
+    }
+}
+
+
+/// What happens when `+` or `-`, or a number read by `,` with `IoMode::Numeric`, takes a cell past
+/// the bounds of its `CellSize`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OverflowPolicy {
+    /// Continue from the other bound, e.g. `+` on 255 gives 0 with `CellSize::U8`.
+    Wrap,
+
+    /// Stay at the bound, e.g. `+` on 255 gives 255 with `CellSize::U8`.
+    Saturate,
+
+    /// Fail with `error::BfError::CellOverflow`.
+    Error,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(OverflowPolicy::Wrap),
+            "saturate" => Ok(OverflowPolicy::Saturate),
+            "error" => Ok(OverflowPolicy::Error),
+            other => Err(format!("unknown overflow policy '{}'", other)),
+        }
+    }
+}
+
+impl OverflowPolicy {
+    /// Add `n` to `cell` as the policy dictates, or `None` if the result is past the bounds of the
+    /// cell with `OverflowPolicy::Error`.
+    pub fn add<C: Cell>(self, cell: C, n: i64) -> Option<C> {
+        match self {
+            // truncating `n` preserves its value modulo the cell width
+            OverflowPolicy::Wrap => Some(cell.wrapping_offset(n as i32)),
+            OverflowPolicy::Saturate => Some(cell.saturating_offset(n)),
+            OverflowPolicy::Error => cell.checked_offset(n),
+        }
+    }
+
+    /// Add `value * factor` to `cell` as in `add`.
+    pub fn mul_add<C: Cell>(self, cell: C, value: C, factor: i32) -> Option<C> {
+        match self {
+            OverflowPolicy::Wrap => Some(cell.wrapping_mul_add(value, factor)),
+            _ => self.add(cell, value.to_i64().saturating_mul(factor as i64)),
+        }
+    }
+
+    /// The cell holding `n` as the policy dictates, or `None` if `n` is past the bounds of the cell
+    /// with `OverflowPolicy::Error`.
+    pub fn convert<C: Cell>(self, n: i64) -> Option<C> {
+        self.add(C::default(), n)
+    }
+}
+
+
+/// How the cells of the tape are stored, see `tape::Tape`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TapeBackend {
+    /// A single array, the fastest to access and the only storage the JIT runs programs on.
+    Vec,
+
+    /// A ring buffer, which grows to the left as cheaply as to the right.
+    Deque,
+
+    /// Only the cells that are not zero, for programs that range far across the tape, see
+    /// `tape::SparseTape`.
+    Sparse,
+}
+
+impl FromStr for TapeBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vec" => Ok(TapeBackend::Vec),
+            "deque" => Ok(TapeBackend::Deque),
+            "sparse" => Ok(TapeBackend::Sparse),
+            other => Err(format!("unknown tape backend '{}'", other)),
+        }
+    }
+}
+
+
+/// Width of each cell on the tape.
+///
+/// Selects the `cell::Cell` implementation used by frontends like the `bfi` executable. Note that
+/// the cells of an `ExecutionContext` are determined by its type parameter.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CellSize {
+    U8,
+    U16,
+    U32,
+    I32,
+}
+
+impl CellSize {
+    /// Number of bytes taken by each cell on the tape.
+    pub fn bytes(self) -> usize {
+        match self {
+            CellSize::U8 => 1,
+            CellSize::U16 => 2,
+            CellSize::U32 | CellSize::I32 => 4,
+        }
+    }
+}
+
+impl FromStr for CellSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" | "u8" => Ok(CellSize::U8),
+            "16" | "u16" => Ok(CellSize::U16),
+            "32" | "u32" => Ok(CellSize::U32),
+            "i32" => Ok(CellSize::I32),
+            other => Err(format!("unknown cell size '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for CellSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellSize::U8 => write!(f, "u8"),
+            CellSize::U16 => write!(f, "u16"),
+            CellSize::U32 => write!(f, "u32"),
+            CellSize::I32 => write!(f, "i32"),
+        }
+    }
+}
+
+
+/// Effect of reading input (`,`) once the input stream is exhausted.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EofBehavior {
+    /// Leave the current cell as it is.
+    Unchanged,
+
+    /// Set the current cell to zero.
+    Zero,
+
+    /// Set the current cell to -1, i.e. 255 for `u8` cells and the maximum value of other
+    /// unsigned cells.
+    MinusOne,
+}
+
+impl FromStr for EofBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unchanged" => Ok(EofBehavior::Unchanged),
+            "0" | "zero" => Ok(EofBehavior::Zero),
+            "255" | "-1" => Ok(EofBehavior::MinusOne),
+            other => Err(format!("unknown EOF behavior '{}'", other)),
+        }
+    }
+}
+
+impl EofBehavior {
+    /// The value of a cell holding `cell` after `,` finds no more input.
+    pub fn apply<C: Cell>(self, cell: C) -> C {
+        match self {
+            EofBehavior::Unchanged => cell,
+            EofBehavior::Zero => C::default(),
+            EofBehavior::MinusOne => C::default().wrapping_dec(),
+        }
+    }
+}
+
+
+/// How `.` and `,` turn cells into program output and program input into cells.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IoMode {
+    /// Write and read a single byte, i.e. a character of ASCII text.
+    Bytes,
+
+    /// Write the value of the cell in decimal followed by a newline, and read a (possibly
+    /// negative) decimal integer delimited by whitespace, wrapping it at the bounds of the cell.
+    Numeric,
+
+    /// Write and read UTF-8 text, with cells wider than a byte holding a whole code point and
+    /// byte cells the bytes of a character one at a time, see `utf8`.
+    Utf8,
+}
+
+impl FromStr for IoMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytes" => Ok(IoMode::Bytes),
+            "numeric" => Ok(IoMode::Numeric),
+            "utf8" | "utf-8" => Ok(IoMode::Utf8),
+            other => Err(format!("unknown I/O mode '{}'", other)),
+        }
+    }
+}
+
+
+/// Effect of a `%` breakpoint in the program.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BreakpointAction {
+    /// Open the REPL, or pause execution if the REPL is disabled, see
+    /// `ExecutionContext::without_repl`.
+    Repl,
+
+    /// Write the cells around the data pointer to stderr and continue.
+    Dump,
+
+    /// Write the location of the breakpoint, the data pointer, and the value of the current cell
+    /// to stderr as a single line and continue.
+    Trace,
+
+    /// Continue as if the breakpoint was not there.
+    Ignore,
+}
+
+impl FromStr for BreakpointAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repl" => Ok(BreakpointAction::Repl),
+            "dump" => Ok(BreakpointAction::Dump),
+            "trace" => Ok(BreakpointAction::Trace),
+            "ignore" => Ok(BreakpointAction::Ignore),
+            other => Err(format!("unknown breakpoint action '{}'", other)),
+        }
+    }
+}
+
+
+/// Settings for an `ExecutionContext`, see `ExecutionContext::with_config`.
+///
+/// The `Default` configuration matches the behavior of `bfi` when no options are specified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpreterConfig {
+    /// What to do when the data pointer reaches the end of the tape.
+    pub tape_mode: TapeMode,
+
+    /// Number of cells on the tape in `TapeMode::Fixed` and `TapeMode::Circular`. Unused by
+    /// `TapeMode::Growable`.
+    pub tape_size: usize,
+
+    /// What moving the data pointer past either end of the tape does in `TapeMode::Fixed`.
+    pub bounds: BoundsPolicy,
+
+    /// Number of cells a growable tape allocates memory for up front, such that a program using
+    /// that many cells runs without reallocating the tape. Zero by default.
+    pub tape_capacity: usize,
+
+    /// How the cells of the tape are stored.
+    pub tape_backend: TapeBackend,
+
+    /// Width of the cells on the tape.
+    pub cell_size: CellSize,
+
+    /// What `+` and `-` do at the bounds of a cell.
+    pub overflow: OverflowPolicy,
+
+    /// What `,` does at the end of input.
+    pub eof: EofBehavior,
+
+    /// Whether `.` and `,` deal in bytes, decimal numbers, or UTF-8 text.
+    pub io_mode: IoMode,
+
+    /// What a `%` breakpoint does, e.g. nothing such that programs containing breakpoints can
+    /// run unattended.
+    pub breakpoint_action: BreakpointAction,
+
+    /// Whether to run the program through `optimizer::optimize` before execution, which is only
+    /// done when the rest of the configuration allows it, see `optimizes`.
+    pub optimize: bool,
+
+    /// Whether to compile the program to native code with `jit::Jit` when running it to
+    /// completion. Has no effect unless `bfi` is built with the `jit` feature, and ignored when
+    /// `max_steps` or `timeout` is set.
+    pub jit: bool,
+
+    /// Maximum number of instructions to execute before stopping with
+    /// `ExecutionStatus::LimitExceeded`. Instructions are counted after optimization, with each
+    /// cell moved past by a scan counted as a further instruction.
+    pub max_steps: Option<u64>,
+
+    /// Maximum time to spend executing before stopping with `ExecutionStatus::LimitExceeded`. Time
+    /// spent blocked on input or in the REPL counts towards the timeout, but it is only checked
+    /// between instructions.
+    pub timeout: Option<Duration>,
+
+    /// Maximum size in bytes of a `TapeMode::Growable` tape, which the rows, tapes, and threads of
+    /// the grid and multitape extensions and Brainfork count against together. Growing the tape
+    /// any further, or creating one more, stops execution with `ExecutionStatus::LimitExceeded`.
+    /// Fixed and circular tapes are allocated in full before execution and are only limited in
+    /// how many more of them are created.
+    pub max_memory: Option<usize>,
+
+    /// Number of executed instructions to remember such that the REPL can step backwards over
+    /// them with `rs` and `undo`, see `journal::Journal`. Zero, the default, remembers none.
+    /// Like breakpoints, remembering instructions means that the program is always interpreted.
+    pub history: usize,
+
+    /// Maximum number of procedure calls of a pbrain program that can be in progress at once,
+    /// beyond which execution stops with `ExecutionStatus::LimitExceeded`. Guards against
+    /// unbounded recursion, see `dialect::PBrain`.
+    pub max_call_depth: usize,
+
+    /// Seed of the pseudo-random bytes of `dialect::Extension::Random`, such that a program using
+    /// them behaves the same on every run with the same seed.
+    pub seed: u64,
+
+    /// Time to sleep after each instruction, e.g. to follow a program as it runs. Like `timeout`,
+    /// a delay means that the program is always interpreted.
+    pub delay: Option<Duration>,
+
+    /// Only sleep for `delay` after `.`, such that output appears at a readable pace while the
+    /// rest of the program runs at full speed.
+    pub delay_output_only: bool,
+
+    /// Number of tapes switched between by `dialect::Extension::Multitape`, each of which is
+    /// configured like the first, including the memory limit.
+    pub tapes: usize,
+}
+
+impl InterpreterConfig {
+    /// Whether execution is limited by `max_steps` or `timeout`.
+    pub fn is_limited(&self) -> bool { self.max_steps.is_some() || self.timeout.is_some() }
+
+    /// Whether the data pointer stops at the ends of the tape, such that moves cannot be undone
+    /// by moving back.
+    pub fn clamps(&self) -> bool {
+        self.tape_mode == TapeMode::Fixed && self.bounds == BoundsPolicy::Clamp
+    }
+
+    /// Where the data pointer lands when moved to `target`, past either end of a fixed or circular
+    /// tape of `len` cells, or the end it moved past if that fails with `BoundsPolicy::Error`.
+    pub fn bounded_target(&self, target: isize, len: usize) -> Result<usize, TapeEnd> {
+        let len = len as isize;
+        match (self.tape_mode, self.bounds) {
+            (TapeMode::Fixed, BoundsPolicy::Error) if target < 0 => Err(TapeEnd::Start),
+            (TapeMode::Fixed, BoundsPolicy::Error) => Err(TapeEnd::End),
+            (TapeMode::Fixed, BoundsPolicy::Clamp) => Ok(target.clamp(0, len - 1) as usize),
+            _ => Ok(target.rem_euclid(len) as usize),
+        }
+    }
+
+    /// Whether programs are run through `optimizer::optimize`, which assumes that moves can be
+    /// undone by moving back and that cells wrap, e.g. to replace `[+]` with setting the cell to
+    /// zero.
+    pub fn optimizes(&self) -> bool {
+        self.optimize && !self.clamps() && self.overflow == OverflowPolicy::Wrap
+    }
+
+    /// The configuration for running untrusted programs, e.g. those of an online judge: limited
+    /// to `SANDBOX_MAX_STEPS` and `SANDBOX_MAX_MEMORY` unless it has limits of its own, and with
+    /// breakpoints ignored such that no program can open the REPL.
+    ///
+    /// The memory limit otherwise only stops a growable tape from growing, so the tape size and
+    /// capacity, which are allocated up front, are cut down to the cells that fit within it.
+    pub fn sandboxed(self) -> Self {
+        let max_memory = self.max_memory.unwrap_or(SANDBOX_MAX_MEMORY);
+        let max_cells = max_memory / self.cell_size.bytes();
+        InterpreterConfig {
+            max_steps: Some(self.max_steps.unwrap_or(SANDBOX_MAX_STEPS)),
+            max_memory: Some(max_memory),
+            tape_size: self.tape_size.min(max_cells),
+            tape_capacity: self.tape_capacity.min(max_cells),
+            breakpoint_action: BreakpointAction::Ignore,
+            ..self
+        }
+    }
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        Self {
+            tape_mode: TapeMode::Growable,
+            tape_size: DEFAULT_TAPE_SIZE,
+            bounds: BoundsPolicy::Error,
+            tape_capacity: 0,
+            tape_backend: TapeBackend::Vec,
+            cell_size: CellSize::U8,
+            overflow: OverflowPolicy::Wrap,
+            eof: EofBehavior::Unchanged,
+            io_mode: IoMode::Bytes,
+            breakpoint_action: BreakpointAction::Repl,
+            optimize: true,
+            jit: true,
+            max_steps: None,
+            timeout: None,
+            max_memory: None,
+            history: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            seed: 0,
+            delay: None,
+            delay_output_only: false,
+            tapes: DEFAULT_TAPES,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tape_mode_from_str() {
+        assert_eq!(Ok(TapeMode::Fixed), "fixed".parse());
+        assert_eq!(Ok(TapeMode::Growable), "growable".parse());
+        assert_eq!(Ok(TapeMode::Circular), "circular".parse());
+        assert!("infinite".parse::<TapeMode>().is_err());
+    }
+
+    #[test]
+    fn test_bounds_policy_from_str() {
+        assert_eq!(Ok(BoundsPolicy::Error), "error".parse());
+        assert_eq!(Ok(BoundsPolicy::Wrap), "wrap".parse());
+        assert_eq!(Ok(BoundsPolicy::Clamp), "clamp".parse());
+        assert!("bounce".parse::<BoundsPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_overflow_policy_from_str() {
+        assert_eq!(Ok(OverflowPolicy::Wrap), "wrap".parse());
+        assert_eq!(Ok(OverflowPolicy::Saturate), "saturate".parse());
+        assert_eq!(Ok(OverflowPolicy::Error), "error".parse());
+        assert!("clamp".parse::<OverflowPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_overflow_policy_add() {
+        assert_eq!(OverflowPolicy::Wrap.add(255u8, 2), Some(1));
+        assert_eq!(OverflowPolicy::Saturate.add(255u8, 2), Some(255));
+        assert_eq!(OverflowPolicy::Error.add(255u8, 2), None);
+        assert_eq!(OverflowPolicy::Wrap.mul_add(1u8, 100, 3), Some(45));
+        assert_eq!(OverflowPolicy::Saturate.mul_add(1u8, 100, -3), Some(0));
+        assert_eq!(OverflowPolicy::Error.mul_add(1u8, 100, 2), Some(201));
+        assert_eq!(OverflowPolicy::Wrap.convert::<u8>(-1), Some(255));
+        assert_eq!(OverflowPolicy::Saturate.convert::<u8>(-1), Some(0));
+        assert_eq!(OverflowPolicy::Saturate.convert::<i32>(i64::MAX), Some(i32::MAX));
+        assert_eq!(OverflowPolicy::Error.convert::<u16>(65536), None);
+    }
+
+    #[test]
+    fn test_cell_size_from_str() {
+        assert_eq!(Ok(CellSize::U8), "u8".parse());
+        assert_eq!(Ok(CellSize::U16), "16".parse());
+        assert_eq!(Ok(CellSize::I32), "i32".parse());
+        assert!("64".parse::<CellSize>().is_err());
+        assert_eq!(Ok(CellSize::U16), CellSize::U16.to_string().parse());
+    }
+
+    #[test]
+    fn test_eof_behavior_from_str() {
+        assert_eq!(Ok(EofBehavior::Unchanged), "unchanged".parse());
+        assert_eq!(Ok(EofBehavior::Zero), "0".parse());
+        assert_eq!(Ok(EofBehavior::MinusOne), "255".parse());
+        assert_eq!(Ok(EofBehavior::MinusOne), "-1".parse());
+        assert!("eof".parse::<EofBehavior>().is_err());
+    }
+
+    #[test]
+    fn test_io_mode_from_str() {
+        assert_eq!(Ok(IoMode::Bytes), "bytes".parse());
+        assert_eq!(Ok(IoMode::Numeric), "numeric".parse());
+        assert_eq!(Ok(IoMode::Utf8), "utf8".parse());
+        assert!("decimal".parse::<IoMode>().is_err());
+    }
+
+    #[test]
+    fn test_breakpoint_action_from_str() {
+        assert_eq!(Ok(BreakpointAction::Repl), "repl".parse());
+        assert_eq!(Ok(BreakpointAction::Dump), "dump".parse());
+        assert_eq!(Ok(BreakpointAction::Trace), "trace".parse());
+        assert_eq!(Ok(BreakpointAction::Ignore), "ignore".parse());
+        assert!("log".parse::<BreakpointAction>().is_err());
+    }
+
+    #[test]
+    fn test_sandboxed() {
+        let config = InterpreterConfig::default().sandboxed();
+        assert_eq!(config.max_steps, Some(SANDBOX_MAX_STEPS));
+        assert_eq!(config.max_memory, Some(SANDBOX_MAX_MEMORY));
+        assert_eq!(config.breakpoint_action, BreakpointAction::Ignore);
+        let config = InterpreterConfig { max_steps: Some(5), ..InterpreterConfig::default() };
+        assert_eq!(config.sandboxed().max_steps, Some(5));
+        let config = InterpreterConfig {
+            tape_size: usize::MAX,
+            tape_capacity: usize::MAX,
+            cell_size: CellSize::U16,
+            max_memory: Some(100),
+            ..InterpreterConfig::default()
+        };
+        let config = config.sandboxed();
+        assert_eq!((config.tape_size, config.tape_capacity), (50, 50));
+    }
+
+    #[test]
+    fn test_bounded_target() {
+        let default = InterpreterConfig::default();
+        let config = InterpreterConfig { tape_mode: TapeMode::Fixed, ..default };
+        assert_eq!(config.bounded_target(-1, 3), Err(TapeEnd::Start));
+        assert_eq!(config.bounded_target(3, 3), Err(TapeEnd::End));
+        let clamp = InterpreterConfig { bounds: BoundsPolicy::Clamp, ..config };
+        assert_eq!(clamp.bounded_target(5, 3), Ok(2));
+        let wrap = InterpreterConfig { bounds: BoundsPolicy::Wrap, ..config };
+        assert_eq!(wrap.bounded_target(-1, 3), Ok(2));
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..config };
+        assert_eq!(circular.bounded_target(4, 3), Ok(1));
+    }
+}
@@ -0,0 +1,156 @@
+//! Which commands of a program were executed, see `Coverage`.
+
+use crate::profile::Profile;
+use crate::program::Program;
+
+
+/// The commands of a source file and whether each was executed.
+#[derive(Debug, Clone, PartialEq)]
+struct FileCoverage {
+    /// Name of the file, `None` for a program that was not preprocessed.
+    name: Option<String>,
+
+    /// Text of each line, with the execution count of each character of it, `None` for characters
+    /// that are not commands.
+    lines: Vec<(String, Vec<Option<u64>>)>,
+}
+
+
+/// The commands of a program that were executed in a run, to find dead code with.
+///
+/// Built from the `Profile` of the run, such that a command merged into an instruction by the
+/// optimizer counts as executed whenever the instruction is, see `Profile::token_counts`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    files: Vec<FileCoverage>,
+}
+
+impl Coverage {
+    pub fn new(profile: &Profile, program: &Program) -> Self {
+        let files = profile.file_counts(program).into_iter().map(|file| FileCoverage {
+            name: file.name.map(str::to_string),
+            lines: file.source.lines().map(str::to_string).zip(file.lines).collect(),
+        }).collect();
+        Coverage { files }
+    }
+
+    /// Number of commands in the source.
+    pub fn commands(&self) -> usize { self.counts().count() }
+
+    /// Number of commands in the source that were executed at least once.
+    pub fn executed(&self) -> usize { self.counts().filter(|&count| count > 0).count() }
+
+    fn counts(&self) -> impl Iterator<Item = u64> + '_ {
+        self.files.iter().flat_map(|file| file.lines.iter().flat_map(|(_, counts)| counts.iter()))
+            .flatten()
+            .copied()
+    }
+
+    /// Render the source with the number of times each line was executed, i.e. the most times
+    /// any command on it was, marking every command that was never executed with `^`.
+    ///
+    /// A preprocessed program is shown as the files it was preprocessed from, each headed by its
+    /// name.
+    pub fn listing(&self) -> String {
+        let commands = self.commands();
+        let executed = self.executed();
+        let percent = if commands == 0 { 100.0 } else { 100.0 * executed as f64 / commands as f64 };
+        let mut lines = vec![
+            format!("{} of {} commands executed ({:.1}%)", executed, commands, percent),
+            String::new(),
+            "  line      count".to_string(),
+        ];
+        for file in &self.files {
+            if let Some(name) = &file.name {
+                lines.push(format!("==> {} <==", name));
+            };
+            for (i, (text, counts)) in file.lines.iter().enumerate() {
+                let count = counts.iter().flatten().max();
+                lines.push(format!(
+                    "{:>6} {:>10} | {}",
+                    i + 1,
+                    count.map_or(String::new(), u64::to_string),
+                    text,
+                ).trim_end().to_string());
+                if !counts.contains(&Some(0)) {
+                    continue;
+                };
+                // keep tabs such that the marks line up regardless of tab width
+                let marks: String = text.chars().zip(counts.iter()).map(|(c, count)| match count {
+                    Some(0) => '^',
+                    _ if c == '\t' => '\t',
+                    _ => ' ',
+                }).collect();
+                lines.push(format!("{:>17} | {}", "", marks).trim_end().to_string());
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Describe the coverage of each line in the tracefile format of `lcov`, naming a program
+    /// that was not preprocessed as `name`.
+    pub fn lcov(&self, name: &str) -> String {
+        let mut report = String::new();
+        for file in &self.files {
+            report.push_str(&format!("TN:\nSF:{}\n", file.name.as_deref().unwrap_or(name)));
+            let (mut found, mut hit) = (0, 0);
+            for (i, (_, counts)) in file.lines.iter().enumerate() {
+                if let Some(&count) = counts.iter().flatten().max() {
+                    report.push_str(&format!("DA:{},{}\n", i + 1, count));
+                    found += 1;
+                    if count > 0 {
+                        hit += 1;
+                    };
+                };
+            }
+            report.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", found, hit));
+        }
+        report
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn coverage(source: &str, executed: &[usize]) -> Coverage {
+        let program = Program::parse(source);
+        let mut profile = Profile::default();
+        for &i in executed {
+            profile.record(i, 0);
+        }
+        Coverage::new(&profile, &program)
+    }
+
+    #[test]
+    fn test_listing() {
+        // the loop body is skipped, as is everything after the '.'
+        let coverage = coverage("+ skip\n[>+<-]\n\t.,\n", &[0, 1, 6, 7]);
+        assert_eq!((coverage.executed(), coverage.commands()), (4, 9));
+        assert_eq!(coverage.listing().lines().collect::<Vec<&str>>(), vec![
+            "4 of 9 commands executed (44.4%)",
+            "",
+            "  line      count",
+            "     1          1 | + skip",
+            "     2          1 | [>+<-]",
+            "                  |  ^^^^",
+            "     3          1 | \t.,",
+            "                  | \t ^",
+        ]);
+    }
+
+    #[test]
+    fn test_lcov() {
+        let coverage = coverage("+\n\n>\n", &[0]);
+        assert_eq!(coverage.lcov("prog.bf"), concat!(
+            "TN:\n",
+            "SF:prog.bf\n",
+            "DA:1,1\n",
+            "DA:3,0\n",
+            "LF:2\n",
+            "LH:1\n",
+            "end_of_record\n",
+        ));
+    }
+}
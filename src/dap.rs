@@ -0,0 +1,702 @@
+//! A minimal [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/) server
+//! for BrainF\*ck programs, see `serve`.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use crate::config::InterpreterConfig;
+use crate::difftest;
+use crate::ffi::BfStatus;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::ioctx::{InMemoryIoCtx, IoCtx};
+use crate::json::Json;
+use crate::lsp::{read_message, write_message};
+use crate::program::{Instruction, Program};
+
+
+/// Number of instructions executed between checks for new requests while the program runs, e.g.
+/// for a request to pause it.
+const STEPS_PER_POLL: usize = 10_000;
+
+/// The only thread of a program, as the protocol has no notion of a program without threads.
+const THREAD_ID: usize = 1;
+
+/// References to the variables of the scopes shown while execution is stopped.
+const POINTER_SCOPE: u64 = 1;
+const TAPE_SCOPE: u64 = 2;
+
+
+/// Serve the Debug Adapter Protocol over `input` and `output` until the client disconnects or the
+/// input ends.
+///
+/// The client launches a program by the path of its file, with the input to give it as a string,
+/// as the input of the server is taken by the protocol, and optionally the `tapeMode` and `eof`
+/// behavior named like the options of the `bfi` executable. Breakpoints can be set on any line, and
+/// execution stops at the first command of the line, as well as at every `%` in the program.
+/// While stopped, the client can step one instruction at a time, over a loop, or out of the loop
+/// it is in, and inspect the data pointer and the cells of the tape. Lines and columns are counted
+/// from one, and messages are framed like those of the Language Server Protocol, see
+/// `lsp::read_message`.
+pub fn serve(input: impl Read + Send + 'static, mut output: impl Write) -> io::Result<()> {
+    // requests are read on another thread such that they can be answered while the program runs
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = BufReader::new(input);
+        loop {
+            match read_message(&mut input) {
+                Ok(Some(message)) => {
+                    if sender.send(Ok(message)).is_err() {
+                        break;
+                    };
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    // the server stops at the error once it has been received
+                    let _ = sender.send(Err(e));
+                    break;
+                },
+            };
+        }
+    });
+    let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    let mut session = Session::new(&ictx);
+    let mut seq = 0;
+    loop {
+        if session.running.is_some() {
+            session.run(STEPS_PER_POLL);
+            send_events(&mut output, &mut seq, &mut session)?;
+        };
+        let request = if session.running.is_some() {
+            match receiver.try_recv() {
+                Ok(request) => request?,
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(request) => request?,
+                Err(_) => break,
+            }
+        };
+        let command = request.get("command").and_then(Json::as_str).unwrap_or("");
+        let arguments = request.get("arguments").unwrap_or(&Json::Null);
+        let mut response = vec![
+            ("type", Json::from("response")),
+            ("request_seq", request.get("seq").cloned().unwrap_or(Json::Null)),
+            ("command", Json::from(command)),
+        ];
+        match session.request(command, arguments) {
+            Ok(body) => {
+                response.push(("success", Json::from(true)));
+                response.push(("body", body));
+            },
+            Err(message) => {
+                response.push(("success", Json::from(false)));
+                response.push(("message", Json::from(message)));
+            },
+        };
+        send(&mut output, &mut seq, response)?;
+        send_events(&mut output, &mut seq, &mut session)?;
+        if command == "disconnect" {
+            break;
+        };
+    }
+    Ok(())
+}
+
+
+/// Write a message with `fields` following its sequence number, the next of `seq`.
+fn send(output: &mut impl Write, seq: &mut usize, fields: Vec<(&str, Json)>) -> io::Result<()> {
+    *seq += 1;
+    let mut message = vec![("seq", Json::from(*seq))];
+    message.extend(fields);
+    write_message(output, &Json::object(message))
+}
+
+
+/// Write the events queued by `session` since the last call.
+fn send_events(output: &mut impl Write, seq: &mut usize, session: &mut Session) -> io::Result<()> {
+    for (event, body) in session.events.drain(..) {
+        send(output, seq, vec![
+            ("type", Json::from("event")),
+            ("event", Json::from(event)),
+            ("body", body),
+        ])?;
+    }
+    Ok(())
+}
+
+
+/// How far execution proceeds once resumed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Resume {
+    /// Until a breakpoint or the end of the program.
+    Continue,
+
+    /// A single instruction.
+    Step,
+
+    /// Until the instruction with the index given is next, or a breakpoint.
+    To(usize),
+}
+
+
+/// Why execution stopped.
+#[derive(Debug, Clone, PartialEq)]
+enum Stop {
+    Entry,
+    Step,
+    Pause,
+
+    /// A breakpoint was hit, either one set by the client or a `%` with the description given.
+    Breakpoint(Option<String>),
+
+    /// The program terminated or failed.
+    Ended,
+}
+
+
+/// State of the server between requests.
+struct Session<'a> {
+    ictx: &'a RefCell<Box<dyn IoCtx>>,
+    /// The program being debugged, `None` until launched.
+    ectx: Option<ExecutionContext<'a, u8>>,
+    /// Path to the file of the program.
+    path: String,
+    stop_on_entry: bool,
+    /// Whether breakpoints are ignored, as asked for by running without debugging.
+    no_debug: bool,
+    /// Indices of the instructions execution stops before.
+    breakpoints: BTreeSet<usize>,
+    /// How far execution proceeds before stopping, `None` while stopped.
+    running: Option<Resume>,
+    ended: bool,
+    /// Events to send once the response to the current request has been sent.
+    events: Vec<(&'static str, Json)>,
+}
+
+impl<'a> Session<'a> {
+    fn new(ictx: &'a RefCell<Box<dyn IoCtx>>) -> Self {
+        Session {
+            ictx,
+            ectx: None,
+            path: String::new(),
+            stop_on_entry: false,
+            no_debug: false,
+            breakpoints: BTreeSet::new(),
+            running: None,
+            ended: false,
+            events: Vec::new(),
+        }
+    }
+
+    /// Respond to the request for `command`, returning the body of the response or the reason it
+    /// failed.
+    fn request(&mut self, command: &str, arguments: &Json) -> Result<Json, String> {
+        match command {
+            "initialize" => Ok(Json::object(vec![
+                ("supportsConfigurationDoneRequest", Json::from(true)),
+                ("supportsTerminateRequest", Json::from(true)),
+            ])),
+            "launch" => {
+                self.launch(arguments)?;
+                // breakpoints are only set once the program they are in is known
+                self.events.push(("initialized", Json::object(Vec::new())));
+                Ok(Json::Null)
+            },
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "configurationDone" => {
+                self.ectx()?;
+                if self.stop_on_entry && !self.no_debug {
+                    self.stop(Stop::Entry);
+                } else {
+                    self.resume(Resume::Continue);
+                };
+                Ok(Json::Null)
+            },
+            "threads" => Ok(Json::object(vec![
+                ("threads", Json::from(vec![Json::object(vec![
+                    ("id", Json::from(THREAD_ID)),
+                    ("name", Json::from("main")),
+                ])])),
+            ])),
+            "stackTrace" => {
+                let ectx = self.ectx()?;
+                let program = ectx.program();
+                // the position past the end of a program is shown at its last instruction
+                let location = program.location(ectx.program_ptr().min(program.len().max(1) - 1));
+                let name = Path::new(&self.path)
+                    .file_name()
+                    .map_or_else(|| self.path.clone(), |name| name.to_string_lossy().into_owned());
+                Ok(Json::object(vec![
+                    ("stackFrames", Json::from(vec![Json::object(vec![
+                        ("id", Json::from(1usize)),
+                        ("name", Json::from("main")),
+                        ("source", Json::object(vec![
+                            ("name", Json::from(name)),
+                            ("path", Json::from(self.path.as_str())),
+                        ])),
+                        ("line", Json::from(location.line)),
+                        ("column", Json::from(location.column)),
+                    ])])),
+                    ("totalFrames", Json::from(1usize)),
+                ]))
+            },
+            "scopes" => {
+                let tape = self.ectx()?.tape().len();
+                Ok(Json::object(vec![
+                    ("scopes", Json::from(vec![
+                        Json::object(vec![
+                            ("name", Json::from("Pointer")),
+                            ("variablesReference", Json::from(POINTER_SCOPE as usize)),
+                            ("expensive", Json::from(false)),
+                        ]),
+                        Json::object(vec![
+                            ("name", Json::from("Tape")),
+                            ("variablesReference", Json::from(TAPE_SCOPE as usize)),
+                            // the client asks for large tapes a page at a time
+                            ("indexedVariables", Json::from(tape)),
+                            ("expensive", Json::from(false)),
+                        ]),
+                    ])),
+                ]))
+            },
+            "variables" => self.variables(arguments),
+            "continue" => {
+                self.resume(Resume::Continue);
+                Ok(Json::object(vec![("allThreadsContinued", Json::from(true))]))
+            },
+            "next" => {
+                let ectx = self.ectx()?;
+                let resume = match ectx.next_instruction() {
+                    Some(Instruction::LoopBeg(Some(end))) => Resume::To(end + 1),
+                    _ => Resume::Step,
+                };
+                self.resume(resume);
+                Ok(Json::Null)
+            },
+            "stepIn" => {
+                self.ectx()?;
+                self.resume(Resume::Step);
+                Ok(Json::Null)
+            },
+            "stepOut" => {
+                let ectx = self.ectx()?;
+                let program_ptr = ectx.program_ptr();
+                // the innermost loop around the next instruction is the last to start before it
+                let resume = ectx.program().instructions()[..program_ptr.min(ectx.program().len())]
+                    .iter()
+                    .rev()
+                    .find_map(|instruction| match instruction {
+                        Instruction::LoopBeg(Some(end)) if *end >= program_ptr => Some(end + 1),
+                        _ => None,
+                    })
+                    .map_or(Resume::Continue, Resume::To);
+                self.resume(resume);
+                Ok(Json::Null)
+            },
+            "pause" => {
+                if self.running.is_some() {
+                    self.stop(Stop::Pause);
+                };
+                Ok(Json::Null)
+            },
+            "terminate" => {
+                self.end();
+                Ok(Json::Null)
+            },
+            "disconnect" => Ok(Json::Null),
+            _ => Err(format!("request '{}' is not supported", command)),
+        }
+    }
+
+    /// The program being debugged, failing if none has been launched yet.
+    fn ectx(&self) -> Result<&ExecutionContext<'a, u8>, String> {
+        self.ectx.as_ref().ok_or_else(|| "no program has been launched".to_string())
+    }
+
+    /// Load the program named in `arguments`, failing if it cannot be read or is invalid.
+    fn launch(&mut self, arguments: &Json) -> Result<(), String> {
+        if self.ectx.is_some() {
+            return Err("a program has already been launched".to_string());
+        };
+        let path = arguments
+            .get("program")
+            .and_then(Json::as_str)
+            .ok_or_else(|| "missing path to the program".to_string())?;
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("program '{}' could not be read ({})", path, e))?;
+        let input = arguments.get("input").and_then(Json::as_str).unwrap_or("");
+        self.ictx.borrow_mut().write_input(input.as_bytes()).map_err(|e| e.to_string())?;
+        // every command of the source is its own instruction, such that each can be stepped to
+        let mut config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+        if let Some(tape_mode) = arguments.get("tapeMode").and_then(Json::as_str) {
+            config.tape_mode = tape_mode.parse()?;
+        };
+        if let Some(eof) = arguments.get("eof").and_then(Json::as_str) {
+            config.eof = eof.parse()?;
+        };
+        let program = Program::parse(&source);
+        let mut ectx = ExecutionContext::with_program(self.ictx.borrow_mut(), program)
+            .with_config(config)
+            .without_repl();
+        ectx.start();
+        if let ExecutionStatus::ProgramError(e) = &ectx.status {
+            return Err(e.to_string());
+        };
+        self.ectx = Some(ectx);
+        self.path = path.to_string();
+        self.stop_on_entry = arguments.get("stopOnEntry").and_then(Json::as_bool) == Some(true);
+        self.no_debug = arguments.get("noDebug").and_then(Json::as_bool) == Some(true);
+        Ok(())
+    }
+
+    /// Replace the breakpoints with those in `arguments`, each on the first command at or after
+    /// the line and column given.
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let ectx = self.ectx.as_ref().ok_or_else(|| "no program has been launched".to_string())?;
+        let path = arguments.at(&["source", "path"]).and_then(Json::as_str).unwrap_or("");
+        let same_file = std::fs::canonicalize(path)
+            .is_ok_and(|path| std::fs::canonicalize(&self.path).is_ok_and(|own| own == path));
+        let program = ectx.program();
+        let mut breakpoints = BTreeSet::new();
+        let requested = arguments.get("breakpoints").and_then(Json::as_array).unwrap_or(&[]);
+        let mut verified = Vec::new();
+        for breakpoint in requested {
+            let line = breakpoint.get("line").and_then(Json::as_u64).unwrap_or(0) as usize;
+            let column = breakpoint.get("column").and_then(Json::as_u64).unwrap_or(1) as usize;
+            let found = (0..program.len()).find(|&i| {
+                let location = program.location(i);
+                location.line == line && location.column >= column
+            });
+            verified.push(match found.filter(|_| same_file) {
+                Some(i) => {
+                    breakpoints.insert(i);
+                    let location = program.location(i);
+                    Json::object(vec![
+                        ("verified", Json::from(true)),
+                        ("line", Json::from(location.line)),
+                        ("column", Json::from(location.column)),
+                    ])
+                },
+                None => Json::object(vec![
+                    ("verified", Json::from(false)),
+                    ("line", Json::from(line)),
+                    ("message", Json::from("no command on this line")),
+                ]),
+            });
+        }
+        if same_file {
+            self.breakpoints = breakpoints;
+        };
+        Ok(Json::object(vec![("breakpoints", Json::from(verified))]))
+    }
+
+    /// Cells of the scope named in `arguments`, limited to the page asked for.
+    fn variables(&self, arguments: &Json) -> Result<Json, String> {
+        let ectx = self.ectx()?;
+        let variable = |name: String, value: String| {
+            Json::object(vec![
+                ("name", Json::from(name)),
+                ("value", Json::from(value)),
+                ("variablesReference", Json::from(0usize)),
+            ])
+        };
+        let tape = ectx.tape();
+        let variables = match arguments.get("variablesReference").and_then(Json::as_u64) {
+            Some(POINTER_SCOPE) => vec![
+                variable("data pointer".to_string(), ectx.data_ptr().to_string()),
+                variable("cell".to_string(), tape[ectx.data_ptr()].to_string()),
+            ],
+            Some(TAPE_SCOPE) => {
+                let start = arguments.get("start").and_then(Json::as_u64).unwrap_or(0) as usize;
+                let count = arguments.get("count").and_then(Json::as_u64).map(|n| n as usize);
+                let end = count.map_or(tape.len(), |count| start.saturating_add(count));
+                (start.min(tape.len())..end.min(tape.len()))
+                    .map(|i| variable(format!("[{}]", i), tape[i].to_string()))
+                    .collect()
+            },
+            _ => return Err("unknown variables reference".to_string()),
+        };
+        Ok(Json::object(vec![("variables", Json::from(variables))]))
+    }
+
+    /// Continue execution as far as `resume` says, or end the session if the program has ended.
+    fn resume(&mut self, resume: Resume) {
+        match &self.ectx {
+            Some(ectx) if ectx.status == ExecutionStatus::InProgress => {
+                self.running = Some(resume);
+            },
+            Some(_) => self.end(),
+            None => {},
+        };
+    }
+
+    /// Execute up to `steps` instructions of the running program, stopping early where it is
+    /// told to.
+    fn run(&mut self, steps: usize) {
+        let stop = match (&mut self.ectx, self.running) {
+            (Some(ectx), Some(resume)) => {
+                let breakpoints = if self.no_debug { None } else { Some(&self.breakpoints) };
+                advance(ectx, resume, breakpoints, steps)
+            },
+            _ => return,
+        };
+        self.collect_output();
+        if let Some(stop) = stop {
+            self.stop(stop);
+        };
+    }
+
+    /// Stop execution, telling the client why, or end the session if the program terminated.
+    fn stop(&mut self, stop: Stop) {
+        self.running = None;
+        let status = match &self.ectx {
+            Some(ectx) => ectx.status.clone(),
+            None => return,
+        };
+        let (reason, description) = match stop {
+            Stop::Entry => ("entry", None),
+            Stop::Step => ("step", None),
+            Stop::Pause => ("pause", None),
+            Stop::Breakpoint(description) => ("breakpoint", description),
+            // a failed program is stopped where it failed, such that its state can be inspected
+            Stop::Ended => match status {
+                ExecutionStatus::Terminated => return self.end(),
+                status => ("exception", Some(difftest::describe(&status))),
+            },
+        };
+        let mut body = vec![
+            ("reason", Json::from(reason)),
+            ("threadId", Json::from(THREAD_ID)),
+            ("allThreadsStopped", Json::from(true)),
+        ];
+        if let Some(description) = description {
+            body.push(("text", Json::from(description)));
+        };
+        self.events.push(("stopped", Json::object(body)));
+    }
+
+    /// Tell the client that the program has exited, with an exit code like that of the `bfi`
+    /// executable.
+    fn end(&mut self) {
+        self.running = None;
+        if self.ended {
+            return;
+        };
+        self.ended = true;
+        self.collect_output();
+        let exit_code = self.ectx.as_ref().map_or(0, |ectx| match ectx.status {
+            // terminated early by the client
+            ExecutionStatus::InProgress => 0,
+            ref status => BfStatus::from(status) as i64,
+        });
+        self.events.push(("exited", Json::object(vec![("exitCode", Json::from(exit_code))])));
+        self.events.push(("terminated", Json::object(Vec::new())));
+    }
+
+    /// Queue the output written by the program since the last call as an event.
+    fn collect_output(&mut self) {
+        let mut output = Vec::new();
+        if let Some(ctx) = self.ectx.as_mut().and_then(ExecutionContext::io_context) {
+            let mut buf = [0u8; 1024];
+            while let Ok(n) = ctx.read_output(&mut buf) {
+                if n == 0 { break };
+                output.extend_from_slice(&buf[..n]);
+            }
+        };
+        if !output.is_empty() {
+            self.events.push(("output", Json::object(vec![
+                ("category", Json::from("stdout")),
+                ("output", Json::from(String::from_utf8_lossy(&output).into_owned())),
+            ])));
+        };
+    }
+}
+
+
+/// Execute up to `steps` instructions, returning why execution should stop, if it should.
+fn advance(
+    ectx: &mut ExecutionContext<u8>,
+    resume: Resume,
+    breakpoints: Option<&BTreeSet<usize>>,
+    steps: usize,
+) -> Option<Stop> {
+    for _ in 0..steps {
+        if ectx.step() != ExecutionStatus::InProgress {
+            return Some(Stop::Ended);
+        };
+        let paused = ectx.take_paused();
+        let program_ptr = ectx.program_ptr();
+        if let Some(breakpoints) = breakpoints {
+            if !paused.is_empty() {
+                return Some(Stop::Breakpoint(Some(paused.join(", "))));
+            };
+            if breakpoints.contains(&program_ptr) {
+                return Some(Stop::Breakpoint(None));
+            };
+        };
+        match resume {
+            Resume::Step => return Some(Stop::Step),
+            Resume::To(target) if target == program_ptr => return Some(Stop::Step),
+            _ => {},
+        };
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Run the server on `requests` for the program `source`, saved to a file named after `test`,
+    /// returning what it sent.
+    fn serve_requests(test: &str, source: &str, requests: &[(&str, &str)]) -> Vec<Json> {
+        let name = format!("bfi-test-dap-{}-{}.bf", test, std::process::id());
+        let file = std::env::temp_dir().join(name);
+        std::fs::write(&file, source).unwrap();
+        let path = Json::from(file.to_string_lossy().into_owned());
+        let input: Vec<u8> = requests
+            .iter()
+            .enumerate()
+            .map(|(i, (command, arguments))| {
+                let arguments = arguments.replace("$PATH", &path.to_string());
+                format!(
+                    r#"{{"seq":{},"type":"request","command":"{}","arguments":{}}}"#,
+                    i + 1, command, arguments,
+                )
+            })
+            .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+            .flat_map(String::into_bytes)
+            .collect();
+        let mut output = Vec::new();
+        serve(std::io::Cursor::new(input), &mut output).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let mut sent = Vec::new();
+        let mut output = &output[..];
+        while let Some(message) = read_message(&mut output).unwrap() {
+            sent.push(message);
+        }
+        sent
+    }
+
+    /// Summary of each message: the command of a response, or the event and its reason.
+    fn summarize(sent: &[Json]) -> Vec<String> {
+        sent.iter()
+            .map(|message| {
+                let field = |path: &[&str]| message.at(path).and_then(Json::as_str).unwrap_or("");
+                match field(&["type"]) {
+                    "response" if message.get("success") == Some(&Json::Bool(false)) => {
+                        format!("{} failed", field(&["command"]))
+                    },
+                    "response" => field(&["command"]).to_string(),
+                    _ => format!("{} {}", field(&["event"]), field(&["body", "reason"])),
+                }
+            })
+            .map(|summary| summary.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no threads")]
+    fn test_session() {
+        let sent = serve_requests("session", "++\n[>+<-]\n>.%+.", &[
+            ("initialize", r#"{"adapterID":"bfi"}"#),
+            ("launch", r#"{"program":$PATH,"stopOnEntry":true}"#),
+            ("setBreakpoints", r#"{
+                "source":{"path":$PATH},"breakpoints":[{"line":3},{"line":5}]
+            }"#),
+            ("configurationDone", "{}"),
+            ("continue", r#"{"threadId":1}"#),
+            ("variables", r#"{"variablesReference":2}"#),
+            ("stepIn", r#"{"threadId":1}"#),
+            ("continue", r#"{"threadId":1}"#),
+            ("stackTrace", r#"{"threadId":1}"#),
+            ("evaluate", r#"{"expression":"1"}"#),
+            ("continue", r#"{"threadId":1}"#),
+            ("disconnect", "{}"),
+        ]);
+        assert_eq!(summarize(&sent), vec![
+            "initialize",
+            "launch",
+            "initialized",
+            "setBreakpoints",
+            "configurationDone",
+            "stopped entry",
+            "continue",
+            "stopped breakpoint",
+            "variables",
+            "stepIn",
+            "stopped step",
+            "continue",
+            "output",
+            "stopped breakpoint",
+            "stackTrace",
+            "evaluate failed",
+            "continue",
+            "output",
+            "exited",
+            "terminated",
+            "disconnect",
+        ]);
+        let seqs: Vec<u64> = sent.iter().filter_map(|m| m.get("seq")?.as_u64()).collect();
+        assert_eq!(seqs, (1..=sent.len() as u64).collect::<Vec<u64>>());
+        assert_eq!(
+            sent[3].at(&["body", "breakpoints"]).unwrap().to_string(),
+            concat!(
+                r#"[{"verified":true,"line":3,"column":1},"#,
+                r#"{"verified":false,"line":5,"message":"no command on this line"}]"#,
+            ),
+        );
+        assert_eq!(
+            sent[8].at(&["body", "variables"]).unwrap().to_string(),
+            concat!(
+                r#"[{"name":"[0]","value":"0","variablesReference":0},"#,
+                r#"{"name":"[1]","value":"2","variablesReference":0}]"#,
+            ),
+        );
+        assert_eq!(sent[12].at(&["body", "output"]), Some(&Json::from("\u{2}")));
+        assert_eq!(
+            sent[13].at(&["body", "text"]).and_then(Json::as_str),
+            Some("breakpoint at line 3, col 3"),
+        );
+        let frame = &sent[14].at(&["body", "stackFrames"]).and_then(Json::as_array).unwrap()[0];
+        assert_eq!((frame.get("line"), frame.get("column")), (
+            Some(&Json::from(3usize)),
+            Some(&Json::from(4usize)),
+        ));
+        assert_eq!(sent[18].at(&["body", "exitCode"]), Some(&Json::from(0usize)));
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no threads")]
+    fn test_failure() {
+        let sent = serve_requests("failure", "<", &[
+            ("initialize", "{}"),
+            ("launch", r#"{"program":$PATH,"tapeMode":"fixed"}"#),
+            ("configurationDone", "{}"),
+            ("scopes", r#"{"frameId":1}"#),
+            ("continue", r#"{"threadId":1}"#),
+        ]);
+        assert_eq!(summarize(&sent), vec![
+            "initialize",
+            "launch",
+            "initialized",
+            "configurationDone",
+            "stopped exception",
+            "scopes",
+            "continue",
+            "exited",
+            "terminated",
+        ]);
+        assert_eq!(sent[7].at(&["body", "exitCode"]), Some(&Json::from(1usize)));
+
+        let sent = serve_requests("failure", "+[", &[("launch", r#"{"program":$PATH}"#)]);
+        assert_eq!(summarize(&sent), vec!["launch failed"]);
+    }
+}
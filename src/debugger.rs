@@ -0,0 +1,404 @@
+//! Conditional breakpoints and watchpoints that pause execution in the REPL.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cell::Cell;
+
+
+/// Part of the interpreter state observed by a breakpoint.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operand {
+    /// The cell at an index of the tape, written `cell[<index>]`, counting from the cell the data
+    /// pointer started on such that cells to its left have negative indices.
+    Cell(isize),
+
+    /// The current cell, written `cell`.
+    CurrentCell,
+
+    /// The data pointer, written `ptr`, counting from where it started like `Cell`.
+    DataPtr,
+}
+
+impl Operand {
+    /// Read the value of the operand from `tape`, the cell the data pointer started on being at
+    /// index `origin`. Cells off either end of the tape read as zero.
+    fn read<C: Cell>(self, tape: &[C], data_ptr: usize, origin: usize) -> i64 {
+        match self {
+            Operand::Cell(i) => usize::try_from(origin as isize + i)
+                .ok()
+                .and_then(|i| tape.get(i))
+                .map_or(0, |c| c.to_i64()),
+            Operand::CurrentCell => tape.get(data_ptr).map_or(0, |c| c.to_i64()),
+            Operand::DataPtr => data_ptr as i64 - origin as i64,
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "cell" => Ok(Operand::CurrentCell),
+            "ptr" => Ok(Operand::DataPtr),
+            other => other
+                .strip_prefix("cell[")
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|index| index.trim().parse::<isize>().ok())
+                .map(Operand::Cell)
+                .ok_or_else(|| format!("unknown operand '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Cell(i) => write!(f, "cell[{}]", i),
+            Operand::CurrentCell => write!(f, "cell"),
+            Operand::DataPtr => write!(f, "ptr"),
+        }
+    }
+}
+
+
+/// Comparison between an `Operand` and a value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Operators in the order they are searched for when parsing, longest first.
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    fn holds(self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // unwrap is safe as every comparison has an operator
+        let (operator, _) = Comparison::OPERATORS.iter().find(|(_, c)| c == self).unwrap();
+        write!(f, "{}", operator)
+    }
+}
+
+
+/// Condition under which to pause execution.
+///
+/// Breakpoints are parsed from strings like `cell[5] == 42` (optionally prefixed with `when`) or
+/// `watch cell[3]`, as accepted by the `--break` option and the REPL `break` command.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// Pause when the comparison becomes true, i.e. when it holds after an instruction but did not
+    /// hold before it.
+    When(Operand, Comparison, i64),
+
+    /// Pause whenever the value of the operand changes.
+    Watch(Operand),
+}
+
+impl Breakpoint {
+    fn triggered(self, before: i64, after: i64) -> bool {
+        match self {
+            Breakpoint::When(_, comparison, value) => {
+                !comparison.holds(before, value) && comparison.holds(after, value)
+            },
+            Breakpoint::Watch(_) => before != after,
+        }
+    }
+
+    fn operand(self) -> Operand {
+        match self {
+            Breakpoint::When(operand, _, _) | Breakpoint::Watch(operand) => operand,
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(operand) = s.strip_prefix("watch ") {
+            return Ok(Breakpoint::Watch(operand.parse()?));
+        };
+        let condition = s.strip_prefix("when ").unwrap_or(s);
+        for (operator, comparison) in Comparison::OPERATORS.iter() {
+            if let Some(i) = condition.find(operator) {
+                let operand = condition[..i].parse()?;
+                let value = condition[i + operator.len()..].trim();
+                let value = value
+                    .parse::<i64>()
+                    .map_err(|_| format!("invalid value '{}' in breakpoint", value))?;
+                return Ok(Breakpoint::When(operand, *comparison, value));
+            };
+        }
+        Err(format!("invalid breakpoint '{}'", s))
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::When(operand, comparison, value) => {
+                write!(f, "{} {} {}", operand, comparison, value)
+            },
+            Breakpoint::Watch(operand) => write!(f, "watch {}", operand),
+        }
+    }
+}
+
+
+/// What pauses execution at a breakpoint of a `Debugger`.
+#[derive(Debug, Clone, PartialEq)]
+enum Stop {
+    /// A `%` in the program at a position in the source (counted in tokens), alongside a
+    /// description of where it is, e.g. `line 2, col 3`.
+    Source(usize, String),
+
+    /// A condition checked after every instruction, alongside the value of its operand when it
+    /// was last checked, `None` if it has not been checked since it was added or enabled.
+    Condition(Breakpoint, Option<i64>),
+}
+
+
+/// Whether a breakpoint of a `Debugger` pauses execution.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    Enabled,
+    Disabled,
+    Deleted,
+}
+
+
+/// Set of breakpoints checked by the interpreter, numbered from 1 in the order they were added
+/// such that they can be listed, disabled, and deleted from the REPL by number.
+///
+/// Conditions are checked after every instruction by `check`, while each `%` in the program is
+/// looked up with `source` when it is reached.
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    /// Every breakpoint ever added, deleted ones included such that numbers are never reused.
+    breakpoints: Vec<(Stop, State)>,
+}
+
+impl Debugger {
+    /// Add a breakpoint, returning its number. It can first be triggered by the state following
+    /// the next `check`.
+    pub fn add(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push((Stop::Condition(breakpoint, None), State::Enabled));
+        self.breakpoints.len()
+    }
+
+    /// Number the `%` at `position` in the source of the program (counted in tokens), described
+    /// by where it is in the source, returning its number.
+    pub fn add_source(&mut self, position: usize, description: String) -> usize {
+        self.breakpoints.push((Stop::Source(position, description), State::Enabled));
+        self.breakpoints.len()
+    }
+
+    /// Whether any condition is enabled, without which there is nothing to `check`.
+    pub fn has_conditions(&self) -> bool {
+        self.breakpoints.iter().any(|(stop, state)| {
+            matches!((stop, state), (Stop::Condition(..), State::Enabled))
+        })
+    }
+
+    /// The number of the `%` at `position` in the source of the program and whether it still
+    /// pauses execution, i.e. has been neither disabled nor deleted, or `None` if it was not
+    /// numbered with `add_source`.
+    pub fn source(&self, position: usize) -> Option<(usize, bool)> {
+        self.breakpoints.iter().enumerate().find_map(|(i, (stop, state))| match stop {
+            Stop::Source(p, _) if *p == position => Some((i + 1, *state == State::Enabled)),
+            _ => None,
+        })
+    }
+
+    /// Enable or disable the breakpoint numbered `number`. A condition that is enabled again can
+    /// first be triggered by the state following the next `check`, as when it was added.
+    pub fn enable(&mut self, number: usize, enabled: bool) -> Result<(), String> {
+        let (stop, state) = self.get_mut(number)?;
+        if let Stop::Condition(_, last) = stop {
+            *last = None;
+        };
+        *state = if enabled { State::Enabled } else { State::Disabled };
+        Ok(())
+    }
+
+    /// Delete the breakpoint numbered `number`, such that a `%` no longer pauses execution.
+    pub fn delete(&mut self, number: usize) -> Result<(), String> {
+        let (_, state) = self.get_mut(number)?;
+        *state = State::Deleted;
+        Ok(())
+    }
+
+    fn get_mut(&mut self, number: usize) -> Result<&mut (Stop, State), String> {
+        let breakpoints = &mut self.breakpoints;
+        number
+            .checked_sub(1)
+            .and_then(move |i| breakpoints.get_mut(i))
+            .filter(|(_, state)| *state != State::Deleted)
+            .ok_or_else(|| format!("no breakpoint {}", number))
+    }
+
+    /// Update the enabled conditions with the current state of the interpreter, returning a
+    /// description of each that was triggered since the previous check. The cell the data pointer
+    /// started on is at index `origin` of `tape`.
+    pub fn check<C: Cell>(&mut self, tape: &[C], data_ptr: usize, origin: usize) -> Vec<String> {
+        let mut triggered = Vec::new();
+        for (i, (stop, state)) in self.breakpoints.iter_mut().enumerate() {
+            let (breakpoint, last) = match (stop, *state) {
+                (Stop::Condition(breakpoint, last), State::Enabled) => (*breakpoint, last),
+                _ => continue,
+            };
+            let value = breakpoint.operand().read(tape, data_ptr, origin);
+            match (breakpoint, *last) {
+                (Breakpoint::When(..), Some(before)) if breakpoint.triggered(before, value) => {
+                    triggered.push(format!("breakpoint {}: {}", i + 1, breakpoint));
+                },
+                (Breakpoint::Watch(operand), Some(before)) if breakpoint.triggered(before, value) => {
+                    let change = format!("changed from {} to {}", before, value);
+                    triggered.push(format!("watchpoint {}: {} {}", i + 1, operand, change));
+                },
+                _ => {},
+            };
+            *last = Some(value);
+        }
+        triggered
+    }
+}
+
+impl fmt::Display for Debugger {
+    /// List every breakpoint that has not been deleted alongside its number and whether it is
+    /// enabled, one per line, as shown by `info breakpoints` in the REPL.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut listed = self.breakpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, state))| *state != State::Deleted)
+            .peekable();
+        if listed.peek().is_none() {
+            return write!(f, "no breakpoints");
+        };
+        write!(f, "{:<4} {:<8} breakpoint", "num", "enabled")?;
+        for (i, (stop, state)) in listed {
+            let enabled = if *state == State::Enabled { "yes" } else { "no" };
+            write!(f, "\n{:<4} {:<8} ", i + 1, enabled)?;
+            match stop {
+                Stop::Source(_, description) => write!(f, "% at {}", description)?,
+                Stop::Condition(breakpoint, _) => write!(f, "{}", breakpoint)?,
+            };
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_breakpoint_from_str() {
+        assert_eq!(
+            "cell[5] == 42".parse(),
+            Ok(Breakpoint::When(Operand::Cell(5), Comparison::Eq, 42)),
+        );
+        assert_eq!(
+            "when ptr>=3".parse(),
+            Ok(Breakpoint::When(Operand::DataPtr, Comparison::Ge, 3)),
+        );
+        assert_eq!(
+            "cell != -1".parse(),
+            Ok(Breakpoint::When(Operand::CurrentCell, Comparison::Ne, -1)),
+        );
+        assert_eq!("watch cell[ 3 ]".parse(), Ok(Breakpoint::Watch(Operand::Cell(3))));
+        assert_eq!("watch cell[-2]".parse(), Ok(Breakpoint::Watch(Operand::Cell(-2))));
+        assert!("cell[x] == 1".parse::<Breakpoint>().is_err());
+        assert!("cell[1] == y".parse::<Breakpoint>().is_err());
+        assert!("cell[1]".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn test_breakpoint_display() {
+        for s in &["cell[5] == 42", "ptr < 3", "cell >= 0", "watch cell[1]"] {
+            assert_eq!(&s.parse::<Breakpoint>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut debugger = Debugger::default();
+        debugger.add("cell[1] == 2".parse().unwrap());
+        debugger.add("watch cell[0]".parse().unwrap());
+        assert!(debugger.check(&[0u8, 2], 0, 0).is_empty());
+        assert!(debugger.check(&[0u8, 1], 0, 0).is_empty());
+        assert_eq!(debugger.check(&[0u8, 2], 1, 0), vec!["breakpoint 1: cell[1] == 2"]);
+        assert!(debugger.check(&[0u8, 2], 1, 0).is_empty());
+        assert_eq!(debugger.check(&[255u8], 0, 0), vec![
+            "watchpoint 2: cell[0] changed from 0 to 255",
+        ]);
+        // indices count from the cell the data pointer started on once the tape grows left
+        let mut debugger = Debugger::default();
+        debugger.add("cell[-1] == 3".parse().unwrap());
+        debugger.add("ptr < 0".parse().unwrap());
+        assert!(debugger.check(&[0u8], 0, 0).is_empty());
+        assert_eq!(debugger.check(&[0u8, 3, 0], 0, 2), vec![
+            "breakpoint 1: cell[-1] == 3",
+            "breakpoint 2: ptr < 0",
+        ]);
+    }
+
+    #[test]
+    fn test_manage() {
+        let mut debugger = Debugger::default();
+        assert_eq!(debugger.to_string(), "no breakpoints");
+        assert_eq!(debugger.add_source(4, "line 1, col 5".to_string()), 1);
+        assert_eq!(debugger.add("cell[0] == 2".parse().unwrap()), 2);
+        assert_eq!(debugger.add("watch ptr".parse().unwrap()), 3);
+        assert_eq!(debugger.source(4), Some((1, true)));
+        assert_eq!(debugger.source(5), None);
+        debugger.enable(1, false).unwrap();
+        debugger.delete(3).unwrap();
+        assert_eq!(debugger.source(4), Some((1, false)));
+        assert_eq!(debugger.to_string(), concat!(
+            "num  enabled  breakpoint\n",
+            "1    no       % at line 1, col 5\n",
+            "2    yes      cell[0] == 2",
+        ));
+        assert_eq!(debugger.delete(3), Err("no breakpoint 3".to_string()));
+        assert_eq!(debugger.enable(0, true), Err("no breakpoint 0".to_string()));
+        // a disabled condition is neither checked nor triggered by changes while it was disabled
+        debugger.enable(2, false).unwrap();
+        assert!(!debugger.has_conditions());
+        assert!(debugger.check(&[0u8], 0, 0).is_empty());
+        debugger.enable(2, true).unwrap();
+        assert!(debugger.check(&[2u8], 0, 0).is_empty());
+        assert!(debugger.check(&[0u8], 0, 0).is_empty());
+        assert_eq!(debugger.check(&[2u8], 0, 0), vec!["breakpoint 2: cell[0] == 2"]);
+    }
+}
@@ -0,0 +1,459 @@
+//! Front ends translating the source of BrainF\*ck dialects into the same stream of `Token`s.
+//!
+//! Support for another dialect is added by implementing `Dialect` for it and listing it in
+//! `DIALECTS` and `from_name`. Dialects that only spell the commands differently need no code at
+//! all, see `Substitution`.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::token::{Location, Token};
+
+
+/// Names of the dialects accepted by `from_name`.
+pub static DIALECTS: &[&str] = &["bf", "brainfork", "ook", "pbrain"];
+
+/// Names of the extensions accepted by `Extension::from_str`.
+pub static EXTENSIONS: &[&str] = &["ext1", "grid", "multitape", "random", "time"];
+
+
+/// A language that is parsed into BrainF\*ck tokens.
+///
+/// Dialects are shared between threads, e.g. by `serve` parsing the programs of requests in
+/// parallel.
+pub trait Dialect: Send + Sync {
+    /// Translate `source` into tokens, alongside the location in the source that each token was
+    /// translated from. Anything that is not part of a command is a comment and ignored.
+    fn locate(&self, source: &str) -> Vec<(Token, Location)>;
+
+    /// Like `locate`, without the locations.
+    fn parse(&self, source: &str) -> Vec<Token> {
+        self.locate(source).into_iter().map(|(token, _)| token).collect()
+    }
+}
+
+
+/// Look up a dialect by one of the names listed in `DIALECTS`.
+pub fn from_name(name: &str) -> Result<Box<dyn Dialect>, String> {
+    match name {
+        "bf" => Ok(Box::new(BrainFuck)),
+        "brainfork" => Ok(Box::new(BrainFork)),
+        "ook" => Ok(Box::new(Ook)),
+        "pbrain" => Ok(Box::new(PBrain)),
+        other => Err(format!("unknown dialect '{}'", other)),
+    }
+}
+
+
+/// A set of commands that can be added to a dialect, see `Extended`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Extension {
+    /// [Extended Type I](https://esolangs.org/wiki/Extended_Brainfuck#Extended_Type_I): `@` ends
+    /// the program, `$` copies the current cell into a separate storage cell and `!` copies it
+    /// back, `}` and `{` shift the current cell right and left by one bit, and `~`, `^`, `&`, and
+    /// `|` replace it with its bitwise not, and its xor, and, and or with the storage cell.
+    Ext1,
+
+    /// `?` sets the current cell to a pseudo-random byte, the same for each run with the same
+    /// `InterpreterConfig::seed`.
+    Random,
+
+    /// `;` sets the current cell to the time since execution began in hundredths of a second,
+    /// wrapping like any other value of the cell, e.g. every 2.56 seconds with 8-bit cells, such
+    /// that programs can wait for it to change.
+    Time,
+
+    /// `/` and `\` switch to the next and previous of `InterpreterConfig::tapes` independent
+    /// tapes, wrapping around, each with its own data pointer.
+    Multitape,
+
+    /// `^` and `_` move the data pointer up and down a row of a grid of cells that grows in every
+    /// direction, keeping its column, such that `<` and `>` move along the row. Each row is a tape
+    /// configured like the first, including the memory limit. Cannot be combined with `Ext1`,
+    /// which uses `^` for xor.
+    Grid,
+}
+
+impl Extension {
+    /// The command written as `c` in the extension, if any.
+    pub fn decode(self, c: char) -> Option<Token> {
+        match (self, c) {
+            (Extension::Ext1, '@') => Some(Token::End),
+            (Extension::Ext1, '$') => Some(Token::Store),
+            (Extension::Ext1, '!') => Some(Token::Load),
+            (Extension::Ext1, '}') => Some(Token::ShiftRight),
+            (Extension::Ext1, '{') => Some(Token::ShiftLeft),
+            (Extension::Ext1, '~') => Some(Token::Not),
+            (Extension::Ext1, '^') => Some(Token::Xor),
+            (Extension::Ext1, '&') => Some(Token::And),
+            (Extension::Ext1, '|') => Some(Token::Or),
+            (Extension::Random, '?') => Some(Token::Random),
+            (Extension::Time, ';') => Some(Token::Clock),
+            (Extension::Multitape, '/') => Some(Token::NextTape),
+            (Extension::Multitape, '\\') => Some(Token::PrevTape),
+            (Extension::Grid, '^') => Some(Token::RowUp),
+            (Extension::Grid, '_') => Some(Token::RowDown),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Extension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ext1" => Ok(Extension::Ext1),
+            "random" => Ok(Extension::Random),
+            "time" => Ok(Extension::Time),
+            "multitape" => Ok(Extension::Multitape),
+            "grid" => Ok(Extension::Grid),
+            other => Err(format!("unknown extension '{}'", other)),
+        }
+    }
+}
+
+
+/// A dialect with the commands of `Extension`s added, each written as a single character anywhere
+/// in the source.
+///
+/// Only dialects that do not use the characters of the extensions in their own commands can be
+/// extended, e.g. not Ook!, which uses `!`.
+pub struct Extended {
+    dialect: Box<dyn Dialect>,
+    extensions: Vec<Extension>,
+}
+
+impl Extended {
+    pub fn new(dialect: Box<dyn Dialect>, extensions: Vec<Extension>) -> Self {
+        Extended { dialect, extensions }
+    }
+}
+
+impl Dialect for Extended {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        let mut tokens = self.dialect.locate(source);
+        tokens.extend(Token::locate_with(source, |c| {
+            self.extensions.iter().find_map(|extension| extension.decode(c)).ok_or(c)
+        }));
+        tokens.sort_by_key(|(_, location)| (location.line, location.column));
+        tokens
+    }
+}
+
+
+/// A dialect without the `#` and `%` debugging commands of `bfi`, which are comments in standard
+/// BrainF\*ck, e.g. for programs that use them in their comments.
+pub struct Strict {
+    dialect: Box<dyn Dialect>,
+}
+
+impl Strict {
+    pub fn new(dialect: Box<dyn Dialect>) -> Self { Strict { dialect } }
+}
+
+impl Dialect for Strict {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        let mut tokens = self.dialect.locate(source);
+        tokens.retain(|(token, _)| !matches!(token, Token::DebugDump | Token::DebugBreakpoint));
+        tokens
+    }
+}
+
+
+/// Plain BrainF\*ck, with one character per command.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BrainFuck;
+
+impl Dialect for BrainFuck {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> { Token::locate_str(source) }
+}
+
+
+/// [Brainfork](https://esolangs.org/wiki/Brainfork), extending BrainF\*ck with threads.
+///
+/// `Y` forks the running thread: the current cell of the original thread is set to zero, while the
+/// new thread continues with a copy of the tape, one cell to the right, with that cell set to one.
+/// Threads take turns executing one instruction each, and share the input and output of the
+/// program, see `scheduler::Scheduler`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BrainFork;
+
+impl Dialect for BrainFork {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        Token::locate_with(source, |c| match c {
+            'Y' => Ok(Token::Fork),
+            other => Token::decode(other),
+        })
+    }
+}
+
+
+/// [pbrain](https://esolangs.org/wiki/Pbrain), extending BrainF\*ck with procedures.
+///
+/// `(` starts the definition of a procedure identified by the value of the current cell, which
+/// lasts until the matching `)`, and `:` calls the procedure identified by the value of the
+/// current cell.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PBrain;
+
+impl Dialect for PBrain {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        Token::locate_with(source, |c| match c {
+            '(' => Ok(Token::ProcBeg),
+            ')' => Ok(Token::ProcEnd),
+            ':' => Ok(Token::ProcCall),
+            other => Token::decode(other),
+        })
+    }
+}
+
+
+/// A dialect spelling each command as an arbitrary string, e.g. `Blub.` or an obfuscation.
+///
+/// Mappings are written in a subset of TOML, with one line per command giving the string it is
+/// spelled as:
+///
+/// ```text
+/// # Blub
+/// ">" = "Blub. Blub?"
+/// "<" = "Blub? Blub."
+/// ```
+///
+/// Keys are the usual characters of the commands and values are basic strings, in which `\"`,
+/// `\\`, `\n`, and `\t` are escaped. Commands left out of the mapping are unavailable. Where
+/// several strings match at the same point of the source, the longest is the command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Substitution {
+    /// The string spelling each command, longest first.
+    words: Vec<(String, Token)>,
+}
+
+impl Substitution {
+    /// Read a mapping from the file at `path`.
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("charset '{}' could not be read ({})", path.display(), e))?
+            .parse()
+            .map_err(|e| format!("charset '{}' is invalid ({})", path.display(), e))
+    }
+
+    /// Parse a TOML basic string from the start of `s`, returning it with the rest of `s`.
+    pub(crate) fn parse_string(s: &str) -> Option<(String, &str)> {
+        let mut chars = s.strip_prefix('"')?.char_indices();
+        let mut string = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((string, &s[i + 2..])),
+                '\\' => string.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    escaped @ ('"' | '\\') => escaped,
+                    _ => return None,
+                }),
+                c => string.push(c),
+            };
+        }
+        None
+    }
+}
+
+impl FromStr for Substitution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<(String, Token)> = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            };
+            let invalid = |reason: &str| format!("line {}: {}", i + 1, reason);
+            let (key, rest) = Substitution::parse_string(line)
+                .ok_or_else(|| invalid("expected a quoted command"))?;
+            let token = match key.chars().collect::<Vec<char>>()[..] {
+                [c] => Token::decode(c).ok(),
+                _ => None,
+            }.ok_or_else(|| invalid(&format!("'{}' is not a command", key)))?;
+            let rest = rest.trim_start().strip_prefix('=').ok_or_else(|| invalid("expected '='"))?;
+            let (word, rest) = Substitution::parse_string(rest.trim_start())
+                .ok_or_else(|| invalid("expected a quoted string"))?;
+            if !(rest.trim().is_empty() || rest.trim().starts_with('#')) {
+                return Err(invalid(&format!("unexpected '{}'", rest.trim())));
+            };
+            if word.is_empty() {
+                return Err(invalid(&format!("'{}' is spelled as an empty string", key)));
+            };
+            if words.iter().any(|&(_, existing)| existing == token) {
+                return Err(invalid(&format!("'{}' is spelled more than once", key)));
+            };
+            words.push((word, token));
+        }
+        words.sort_by_key(|(word, _)| core::cmp::Reverse(word.len()));
+        Ok(Substitution { words })
+    }
+}
+
+impl Dialect for Substitution {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        let mut location = Location { line: 1, column: 1 };
+        let mut tokens = Vec::new();
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            let len = match self.words.iter().find(|(word, _)| rest.starts_with(word.as_str())) {
+                Some((word, token)) => {
+                    tokens.push((*token, location));
+                    word.len()
+                },
+                None => c.len_utf8(),
+            };
+            for c in rest[..len].chars() {
+                location = match c {
+                    '\n' => Location { line: location.line + 1, column: 1 },
+                    _ => Location { column: location.column + 1, ..location },
+                };
+            }
+            rest = &rest[len..];
+        }
+        tokens
+    }
+}
+
+
+/// [Ook!](https://esolangs.org/wiki/Ook!), in which each command is a pair of the words `Ook.`,
+/// `Ook?`, and `Ook!`, e.g. `Ook. Ook?` for `>`.
+///
+/// Commands are located at their first word. The unassigned pair `Ook? Ook?` is ignored, as is a
+/// final unpaired word.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Ook;
+
+impl Ook {
+    /// The token written as the pair of words ending in `first` and `second`.
+    fn decode(first: char, second: char) -> Option<Token> {
+        match (first, second) {
+            ('.', '?') => Some(Token::PtrInc),
+            ('?', '.') => Some(Token::PtrDec),
+            ('.', '.') => Some(Token::ValInc),
+            ('!', '!') => Some(Token::ValDec),
+            ('!', '.') => Some(Token::PutChar),
+            ('.', '!') => Some(Token::GetChar),
+            ('!', '?') => Some(Token::LoopBeg),
+            ('?', '!') => Some(Token::LoopEnd),
+            _ => None,
+        }
+    }
+}
+
+impl Dialect for Ook {
+    fn locate(&self, source: &str) -> Vec<(Token, Location)> {
+        // the punctuation and location of every word, in order
+        let mut words: Vec<(char, Location)> = Vec::new();
+        for (line, text) in source.lines().enumerate() {
+            let chars: Vec<char> = text.chars().collect();
+            for (column, window) in chars.windows(4).enumerate() {
+                if let ['O', 'o', 'k', punctuation @ ('.' | '?' | '!')] = *window {
+                    words.push((punctuation, Location { line: line + 1, column: column + 1 }));
+                };
+            }
+        }
+        words
+            .chunks_exact(2)
+            .filter_map(|pair| Ook::decode(pair[0].0, pair[1].0).map(|token| (token, pair[0].1)))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ook() {
+        let source = "Ook. Ook? Ook. Ook.\nOok! Ook?Ook! Ook! Ook? Ook! comment Ook! Ook. Ook? Ook?";
+        assert_eq!(Ook.parse(source), Token::parse_str(">+[-]."));
+        let located = Ook.locate(source);
+        assert_eq!(located[2], (Token::LoopBeg, Location { line: 2, column: 1 }));
+        assert_eq!(located[3], (Token::ValDec, Location { line: 2, column: 10 }));
+        assert_eq!(Ook.parse("Ook. Ook. Ook."), vec![Token::ValInc]);
+    }
+
+    #[test]
+    fn test_pbrain() {
+        let tokens = vec![Token::ProcBeg, Token::ValInc, Token::ProcEnd, Token::ProcCall];
+        assert_eq!(PBrain.parse("(+): comment"), tokens);
+        assert_eq!(BrainFuck.parse("(+): comment"), vec![Token::ValInc]);
+    }
+
+    #[test]
+    fn test_brainfork() {
+        assert_eq!(BrainFork.parse("+Y. why"), vec![Token::ValInc, Token::Fork, Token::PutChar]);
+        assert_eq!(BrainFuck.parse("+Y"), vec![Token::ValInc]);
+    }
+
+    #[test]
+    fn test_substitution() {
+        let blub: Substitution = concat!(
+            "# longest match wins\n",
+            "\"+\" = \"ab\"\n",
+            "  \"-\"=\"abc\"  # trailing comment\n",
+            "\".\" = \"\\\"\\n\"\n",
+        ).parse().unwrap();
+        let located = blub.locate("ab abc\"\nxab");
+        let tokens: Vec<Token> = located.iter().map(|&(token, _)| token).collect();
+        assert_eq!(tokens, Token::parse_str("+-.+"));
+        assert_eq!(located[3].1, Location { line: 2, column: 2 });
+        for invalid in [
+            r#"+ = "a""#,
+            r#""x" = "a""#,
+            r#""+" "a""#,
+            r#""+" = """#,
+            r#""+" = "a" b"#,
+            "\"+\" = \"a\"\n\"+\" = \"b\"",
+        ] {
+            assert!(invalid.parse::<Substitution>().is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn test_extended() {
+        let extended = Extended::new(Box::new(PBrain), vec![Extension::Ext1]);
+        let tokens = vec![Token::Store, Token::ValInc, Token::ProcCall, Token::Xor, Token::End];
+        assert_eq!(extended.parse("$+:^@ comment"), tokens);
+        assert_eq!(extended.locate("\n+$")[1], (Token::Store, Location { line: 2, column: 2 }));
+        assert_eq!("ext1".parse(), Ok(Extension::Ext1));
+        let extended = Extended::new(Box::new(BrainFuck), vec![Extension::Ext1, Extension::Random]);
+        assert_eq!(extended.parse("?@ why?"), vec![Token::Random, Token::End, Token::Random]);
+        let extended = Extended::new(Box::new(BrainFuck), vec![Extension::Time]);
+        assert_eq!(extended.parse("+; ?"), vec![Token::ValInc, Token::Clock]);
+        let extended = Extended::new(Box::new(BrainFuck), vec![Extension::Multitape]);
+        assert_eq!(extended.parse("/+\\"), vec![Token::NextTape, Token::ValInc, Token::PrevTape]);
+        let extended = Extended::new(Box::new(BrainFuck), vec![Extension::Grid]);
+        assert_eq!(extended.parse("^>_"), vec![Token::RowUp, Token::PtrInc, Token::RowDown]);
+        assert!("ext2".parse::<Extension>().is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        let strict = Strict::new(Box::new(BrainFuck));
+        assert_eq!(strict.parse("+# 100% done\n."), vec![Token::ValInc, Token::PutChar]);
+        assert_eq!(strict.locate("#\n+")[0], (Token::ValInc, Location { line: 2, column: 1 }));
+    }
+
+    #[test]
+    fn test_from_name() {
+        for name in DIALECTS {
+            assert!(from_name(name).is_ok());
+        }
+        assert_eq!(from_name("bf").unwrap().parse("+x-"), vec![Token::ValInc, Token::ValDec]);
+        assert!(from_name("cobol").is_err());
+    }
+}
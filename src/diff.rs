@@ -0,0 +1,213 @@
+//! Line-by-line differences between two texts, see `unified`.
+
+use std::cmp;
+
+
+/// Number of unchanged lines shown around each change by `unified`.
+const CONTEXT_LINES: usize = 3;
+
+/// Largest number of pairs of lines compared to find the longest common subsequence, beyond which
+/// every line between the common start and end of the texts is shown as changed.
+const MAX_COMPARISONS: usize = 10_000_000;
+
+
+/// A line of one text or both, as found by `lines`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+
+/// Describe how `new` differs from `old` in the unified format of `diff -u`, with `---` and `+++`
+/// headers naming them as `old_name` and `new_name`. Empty if the texts are the same.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    if old == new {
+        return String::new();
+    };
+    let lines = lines(old, new);
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    // lines of either text passed so far, and the index in `lines` to look for changes from
+    let (mut old_line, mut new_line) = (0, 0);
+    let mut i = 0;
+    while i < lines.len() {
+        let changed = match lines[i..].iter().position(|line| !matches!(line, Line::Same(_))) {
+            Some(offset) => i + offset,
+            None => break,
+        };
+        let start = changed.saturating_sub(CONTEXT_LINES).max(i);
+        for line in &lines[i..start] {
+            advance(line, &mut old_line, &mut new_line);
+        }
+        // extend the hunk over every change closer than twice the context to the last
+        let mut end = changed;
+        let mut same = 0;
+        for (j, line) in lines.iter().enumerate().skip(changed) {
+            if let Line::Same(_) = line {
+                same += 1;
+                if same > 2 * CONTEXT_LINES {
+                    break;
+                };
+            } else {
+                same = 0;
+                end = j + 1;
+            };
+        }
+        let end = cmp::min(end + CONTEXT_LINES, lines.len());
+        let hunk = &lines[start..end];
+        let count = |f: fn(&Line) -> bool| hunk.iter().filter(|line| f(line)).count();
+        let old_count = count(|line| !matches!(line, Line::Added(_)));
+        let new_count = count(|line| !matches!(line, Line::Removed(_)));
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_line, old_count),
+            range(new_line, new_count),
+        ));
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Same(text) => (' ', text),
+                Line::Removed(text) => ('-', text),
+                Line::Added(text) => ('+', text),
+            };
+            diff.push(prefix);
+            match text.strip_suffix('\n') {
+                Some(text) => diff.push_str(&format!("{}\n", text)),
+                None => diff.push_str(&format!("{}\n\\ No newline at end of file\n", text)),
+            };
+            advance(line, &mut old_line, &mut new_line);
+        }
+        i = end;
+    }
+    diff
+}
+
+
+/// Lines of `old` and `new`, each keeping its newline, as a shortest sequence of removals from
+/// `old` and additions of `new` turning one into the other.
+fn lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|line| Line::Same(line)).collect();
+    if a.len().saturating_mul(b.len()) > MAX_COMPARISONS {
+        lines.extend(a.iter().map(|line| Line::Removed(line)));
+        lines.extend(b.iter().map(|line| Line::Added(line)));
+    } else {
+        // lengths of the longest common subsequences of every pair of suffixes of `a` and `b`
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                lines.push(Line::Same(a[i]));
+                i += 1;
+                j += 1;
+            } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                lines.push(Line::Removed(a[i]));
+                i += 1;
+            } else {
+                lines.push(Line::Added(b[j]));
+                j += 1;
+            };
+        }
+    };
+    lines.extend(old[old.len() - suffix..].iter().map(|line| Line::Same(line)));
+    lines
+}
+
+
+/// Count the lines of either text passed by `line`.
+fn advance(line: &Line, old_line: &mut usize, new_line: &mut usize) {
+    match line {
+        Line::Same(_) => {
+            *old_line += 1;
+            *new_line += 1;
+        },
+        Line::Removed(_) => *old_line += 1,
+        Line::Added(_) => *new_line += 1,
+    };
+}
+
+
+/// The range of `count` lines after the first `start` in a hunk header, e.g. `3,2`, numbered from
+/// one. An empty range is given as the line before it.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unified() {
+        assert_eq!(unified("a\nb\n", "a\nb\n", "old", "new"), "");
+        assert_eq!(unified("a\nb\nc\n", "a\nB\nc\n", "old", "new"), concat!(
+            "--- old\n",
+            "+++ new\n",
+            "@@ -1,3 +1,3 @@\n",
+            " a\n",
+            "-b\n",
+            "+B\n",
+            " c\n",
+        ));
+        assert_eq!(unified("x\n", "x", "expected", "output"), concat!(
+            "--- expected\n",
+            "+++ output\n",
+            "@@ -1 +1 @@\n",
+            "-x\n",
+            "+x\n",
+            "\\ No newline at end of file\n",
+        ));
+        assert_eq!(unified("", "a\n", "old", "new"), "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+    }
+
+    #[test]
+    fn test_unified_hunks() {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20).filter_map(|n| match n {
+            2 => Some("two\n".to_string()),
+            18 => None,
+            19 => Some("xix\n".to_string()),
+            n => Some(format!("{}\n", n)),
+        }).collect();
+        assert_eq!(unified(&old, &new, "old", "new"), concat!(
+            "--- old\n",
+            "+++ new\n",
+            "@@ -1,5 +1,5 @@\n",
+            " 1\n",
+            "-2\n",
+            "+two\n",
+            " 3\n",
+            " 4\n",
+            " 5\n",
+            "@@ -15,6 +15,5 @@\n",
+            " 15\n",
+            " 16\n",
+            " 17\n",
+            "-18\n",
+            "-19\n",
+            "+xix\n",
+            " 20\n",
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+//! Differential testing of the optimizer and JIT against the unoptimized interpreter.
+
+use std::cell::RefCell;
+use std::mem;
+
+use crate::cell::Cell;
+use crate::config::InterpreterConfig;
+use crate::error::BfError;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::ioctx::{InMemoryIoCtx, IoCtx};
+use crate::program::Program;
+
+
+/// How a program ended, see `run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome<C: Cell> {
+    pub status: ExecutionStatus<BfError>,
+    pub output: Vec<u8>,
+
+    /// The tape without the zero cells at either end, as the optimized program may grow the tape
+    /// less than the program as written.
+    pub tape: Vec<C>,
+
+    /// Position of the data pointer relative to the start of `tape`.
+    pub data_ptr: isize,
+}
+
+
+/// Execute `program` to completion on `input`, returning how it ended.
+pub fn run<C: Cell>(program: &Program, input: &[u8], config: InterpreterConfig) -> Outcome<C> {
+    let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    let written = ictx.borrow_mut().write_input(input);
+    let (status, tape, data_ptr) = match written {
+        Ok(_) => {
+            let mut ectx = ExecutionContext::<C>::with_program(ictx.borrow_mut(), program.clone())
+                .with_config(config);
+            let status = ectx.execute();
+            (status, ectx.tape().to_vec(), ectx.data_ptr())
+        },
+        Err(e) => (ExecutionStatus::InternalError(e.into()), Vec::new(), 0),
+    };
+    let mut output = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Ok(n) = ictx.borrow_mut().read_output(&mut buf) {
+        if n == 0 { break };
+        output.extend_from_slice(&buf[..n]);
+    }
+    // an empty tape is positioned at the data pointer such that its offset is always zero
+    let start = tape.iter().position(|&c| c != C::default()).unwrap_or(data_ptr);
+    let end = tape.iter().rposition(|&c| c != C::default()).map_or(start, |i| i + 1);
+    Outcome {
+        status,
+        output,
+        tape: tape[start..end].to_vec(),
+        data_ptr: data_ptr as isize - start as isize,
+    }
+}
+
+
+/// A program run by the unoptimized interpreter and by the optimizer and JIT, see `difftest`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison<C: Cell> {
+    pub reference: Outcome<C>,
+    pub optimized: Outcome<C>,
+}
+
+impl<C: Cell> Comparison<C> {
+    /// Whether either run was stopped by a limit of the configuration, in which case the two are
+    /// not comparable: the optimized program executes fewer, different instructions.
+    pub fn is_limited(&self) -> bool {
+        let limited = |outcome: &Outcome<C>| {
+            matches!(outcome.status, ExecutionStatus::LimitExceeded(_))
+        };
+        limited(&self.reference) || limited(&self.optimized)
+    }
+
+    /// Describe each way in which the optimized run differs from the reference, if any. Always
+    /// empty for runs stopped by a limit, see `is_limited`.
+    ///
+    /// Errors are compared by kind only, as they may refer to different instructions once the
+    /// program is optimized.
+    pub fn differences(&self) -> Vec<String> {
+        let (reference, optimized) = (&self.reference, &self.optimized);
+        let mut differences = Vec::new();
+        if self.is_limited() {
+            return differences;
+        };
+        if mem::discriminant(&reference.status) != mem::discriminant(&optimized.status) {
+            differences.push(format!(
+                "status: {} but {} once optimized",
+                describe(&reference.status),
+                describe(&optimized.status),
+            ));
+        };
+        if reference.output != optimized.output {
+            let i = first_difference(&reference.output, &optimized.output);
+            differences.push(format!(
+                "output: {} bytes but {} once optimized, first differing at byte {}",
+                reference.output.len(),
+                optimized.output.len(),
+                i,
+            ));
+        };
+        if reference.data_ptr != optimized.data_ptr {
+            differences.push(format!(
+                "data pointer: {} but {} once optimized, relative to the first nonzero cell",
+                reference.data_ptr,
+                optimized.data_ptr,
+            ));
+        } else if reference.tape != optimized.tape {
+            let i = first_difference(&reference.tape, &optimized.tape);
+            let cell = |tape: &[C]| tape.get(i).copied().unwrap_or_default();
+            differences.push(format!(
+                "tape: cell {} from the data pointer is {} but {} once optimized",
+                i as isize - reference.data_ptr,
+                cell(&reference.tape),
+                cell(&optimized.tape),
+            ));
+        };
+        differences
+    }
+}
+
+
+/// Run `program` on `input` both with the unoptimized interpreter and with the optimizer and JIT,
+/// with the semantics and limits of `config`.
+///
+/// The JIT is only used if `bfi` is built with the `jit` feature and `config` sets no limits, see
+/// `InterpreterConfig::jit`.
+pub fn difftest<C: Cell>(
+    program: &Program,
+    input: &[u8],
+    config: &InterpreterConfig,
+) -> Comparison<C>
+{
+    let reference = InterpreterConfig { optimize: false, jit: false, ..config.clone() };
+    let optimized = InterpreterConfig { optimize: true, jit: true, ..config.clone() };
+    Comparison {
+        reference: run(program, input, reference),
+        optimized: run(program, input, optimized),
+    }
+}
+
+
+/// Short description of how execution ended.
+pub fn describe(status: &ExecutionStatus<BfError>) -> String {
+    match status {
+        ExecutionStatus::Terminated => "terminated".to_string(),
+        ExecutionStatus::ProgramError(e) => format!("failed ({})", e),
+        ExecutionStatus::LimitExceeded(e) => format!("stopped ({})", e),
+        ExecutionStatus::InternalError(e) => format!("failed internally ({})", e),
+        ExecutionStatus::Cancelled => "cancelled".to_string(),
+        ExecutionStatus::NotStarted | ExecutionStatus::InProgress => "unfinished".to_string(),
+    }
+}
+
+
+/// Index of the first element at which `a` and `b` differ, where `a` and `b` are not equal.
+pub(crate) fn first_difference<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).position(|(x, y)| x != y).unwrap_or_else(|| a.len().min(b.len()))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_behavior() {
+        let program = Program::parse("<<,[->>+<<]>>[-<+>]<+.>>>><<<<");
+        let comparison = difftest::<u8>(&program, b"A", &InterpreterConfig::default());
+        assert_eq!(comparison.reference.output, b"B");
+        assert_eq!(comparison.reference.tape, vec![66]);
+        assert_eq!(comparison.reference.data_ptr, 0);
+        assert_eq!(comparison.reference, comparison.optimized);
+        assert!(comparison.differences().is_empty());
+    }
+
+    #[test]
+    fn test_differences() {
+        let reference = Outcome {
+            status: ExecutionStatus::Terminated,
+            output: b"ab".to_vec(),
+            tape: vec![1u8, 2],
+            data_ptr: 1,
+        };
+        let optimized = Outcome {
+            status: ExecutionStatus::InternalError(BfError::ReplError("closed".to_string())),
+            output: b"ac".to_vec(),
+            tape: vec![1, 3],
+            ..reference.clone()
+        };
+        let mut comparison = Comparison { reference, optimized };
+        assert_eq!(comparison.differences(), vec![
+            "status: terminated but failed internally (closed) once optimized",
+            "output: 2 bytes but 2 once optimized, first differing at byte 1",
+            "tape: cell 0 from the data pointer is 2 but 3 once optimized",
+        ]);
+        comparison.optimized.status = ExecutionStatus::LimitExceeded(BfError::StepLimit(10));
+        assert!(comparison.is_limited() && comparison.differences().is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+//! The step loop of the interpreter for targets without `std`, see `Engine`.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::cell::Cell;
+use crate::config::{InterpreterConfig, TapeMode};
+use crate::error::BfError;
+use crate::optimizer;
+use crate::program::{Instruction, Program};
+use crate::token::Token;
+
+
+/// Source of the bytes read by `,`, e.g. a serial port on an embedded target.
+pub trait Input {
+    /// The next byte of input, or `None` at the end of input.
+    fn read_byte(&mut self) -> Option<u8>;
+}
+
+/// Destination of the bytes written by `.`.
+pub trait Output {
+    fn write_byte(&mut self, byte: u8);
+}
+
+/// Reads the bytes of the slice in turn, advancing past each.
+impl Input for &[u8] {
+    fn read_byte(&mut self) -> Option<u8> {
+        let (&byte, rest) = self.split_first()?;
+        *self = rest;
+        Some(byte)
+    }
+}
+
+impl Output for Vec<u8> {
+    fn write_byte(&mut self, byte: u8) { self.push(byte); }
+}
+
+
+/// Interpreter of a `Program` for targets without `std`, e.g. microcontrollers or WebAssembly
+/// hosts without WASI, with `,` and `.` going through the `Input` and `Output` traits.
+///
+/// Programs are parsed, optimized, and executed with the same semantics as by
+/// `interpreter::ExecutionContext`, following the tape, overflow, EOF, and limit settings of
+/// `config::InterpreterConfig`. Without a clock, threads, or a terminal, there are no timeouts,
+/// delays, breakpoints, or history, `.` and `,` always deal in bytes, and programs using
+/// procedures, forks, or the random, time, multitape, and grid extensions fail with
+/// `BfError::UnsupportedCommand` before executing anything.
+///
+/// Cells are of type `C`, which should match `InterpreterConfig::cell_size`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Engine<C: Cell = u8> {
+    program: Program,
+    config: InterpreterConfig,
+    tape: Vec<C>,
+    data_ptr: usize,
+    program_ptr: usize,
+    /// The storage cell of `dialect::Extension::Ext1`.
+    storage: C,
+    steps: u64,
+    prepared: bool,
+    /// The error that stopped execution, returned again by any further step.
+    error: Option<BfError>,
+}
+
+impl<C: Cell> Engine<C> {
+    /// Create an `Engine` executing `program` with the default configuration, e.g.
+    /// `Engine::<u8>::new(Program::parse(source))`.
+    pub fn new(program: Program) -> Self {
+        Engine {
+            program,
+            config: InterpreterConfig::default(),
+            tape: Vec::new(),
+            data_ptr: 0,
+            program_ptr: 0,
+            storage: C::default(),
+            steps: 0,
+            prepared: false,
+            error: None,
+        }
+        .with_config(InterpreterConfig::default())
+    }
+
+    /// Apply the provided configuration, resetting the tape to match.
+    ///
+    /// Must be called before execution begins.
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        let len = match config.tape_mode {
+            TapeMode::Growable => 1,
+            TapeMode::Fixed | TapeMode::Circular => config.tape_size.max(1),
+        };
+        self.tape = alloc::vec![C::default(); len];
+        self.data_ptr = 0;
+        self.config = config;
+        self
+    }
+
+    /// Execute every remaining instruction of the program.
+    pub fn run(
+        &mut self,
+        input: &mut impl Input,
+        output: &mut impl Output,
+    ) -> Result<(), BfError>
+    {
+        while self.step(input, output)? {}
+        Ok(())
+    }
+
+    /// Execute the next instruction of the program, returning whether any remain.
+    ///
+    /// The program is checked for unmatched brackets and unsupported commands, then optimized if
+    /// the configuration allows it, before the first instruction is executed. Stepping such that
+    /// the program can be put aside now and then allows it to share a thread, e.g. with a
+    /// watchdog or an event loop. A failed step can be retried, failing again.
+    pub fn step(
+        &mut self,
+        input: &mut impl Input,
+        output: &mut impl Output,
+    ) -> Result<bool, BfError>
+    {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        };
+        if let Err(e) = self.prepare().and_then(|()| self.execute(input, output)) {
+            self.error = Some(e.clone());
+            return Err(e);
+        };
+        Ok(!self.is_finished())
+    }
+
+    pub fn tape(&self) -> &[C] { &self.tape }
+
+    pub fn data_ptr(&self) -> usize { self.data_ptr }
+
+    /// Number of instructions executed so far, counted as for `InterpreterConfig::max_steps`.
+    pub fn steps(&self) -> u64 { self.steps }
+
+    /// Whether every instruction of the program has been executed.
+    pub fn is_finished(&self) -> bool {
+        self.prepared && self.error.is_none() && self.program_ptr >= self.program.len()
+    }
+
+    /// Check and optimize the program if execution has not yet begun.
+    fn prepare(&mut self) -> Result<(), BfError> {
+        if self.prepared {
+            return Ok(());
+        };
+        self.program.verify()?;
+        let mut instructions = self.program.instructions().iter().enumerate();
+        let unsupported = instructions.find_map(|(i, instruction)| {
+            let token = match instruction {
+                Instruction::ProcBeg(_) => Token::ProcBeg,
+                Instruction::ProcEnd(_) => Token::ProcEnd,
+                Instruction::ProcCall => Token::ProcCall,
+                Instruction::Fork => Token::Fork,
+                Instruction::Random => Token::Random,
+                Instruction::Clock => Token::Clock,
+                Instruction::NextTape => Token::NextTape,
+                Instruction::PrevTape => Token::PrevTape,
+                Instruction::RowUp => Token::RowUp,
+                Instruction::RowDown => Token::RowDown,
+                _ => return None,
+            };
+            Some((i, token))
+        });
+        if let Some((i, token)) = unsupported {
+            return Err(BfError::UnsupportedCommand(token, self.program.context(i)));
+        };
+        if self.config.optimizes() {
+            self.program = optimizer::optimize(&self.program);
+        };
+        self.prepared = true;
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        input: &mut impl Input,
+        output: &mut impl Output,
+    ) -> Result<(), BfError>
+    {
+        let instruction = match self.program.get(self.program_ptr) {
+            Some(&instruction) => instruction,
+            None => return Ok(()),
+        };
+        self.count_step()?;
+        let cell = self.tape[self.data_ptr];
+        match instruction {
+            Instruction::Add(n) => {
+                self.set_cell_or_overflow(self.config.overflow.add(cell, n as i64))?;
+            },
+            Instruction::Move(n) => self.pointer_move(n)?,
+            Instruction::SetZero => self.tape[self.data_ptr] = C::default(),
+            Instruction::Scan(n) => {
+                while !self.tape[self.data_ptr].is_zero() {
+                    self.pointer_move(n)?;
+                    // a scan can loop forever on a circular tape, so each move counts as a step
+                    self.count_step()?;
+                }
+            },
+            Instruction::MulAdd(offset, factor) if !cell.is_zero() => {
+                // travel to the target and back such that the tape is extended or wrapped as
+                // necessary
+                self.pointer_move(offset)?;
+                let target = self.tape[self.data_ptr];
+                self.set_cell_or_overflow(self.config.overflow.mul_add(target, cell, factor))?;
+                self.pointer_move(-offset)?;
+            },
+            Instruction::MulAdd(_, _) => {},
+            Instruction::PutChar => output.write_byte(cell.to_byte()),
+            Instruction::GetChar => {
+                self.tape[self.data_ptr] = match input.read_byte() {
+                    Some(byte) => C::from_byte(byte),
+                    None => self.config.eof.apply(cell),
+                };
+            },
+            Instruction::LoopBeg(Some(end)) if cell.is_zero() => self.program_ptr = end,
+            Instruction::LoopEnd(Some(beg)) if !cell.is_zero() => self.program_ptr = beg,
+            Instruction::End => self.program_ptr = self.program.len(),
+            Instruction::Store => self.storage = cell,
+            Instruction::Load => self.tape[self.data_ptr] = self.storage,
+            Instruction::Bitwise(op) => self.tape[self.data_ptr] = op.apply(cell, self.storage),
+            // rejected by `prepare`, or with nothing to do without a terminal
+            _ => {},
+        };
+        self.program_ptr += 1;
+        Ok(())
+    }
+
+    /// Count an executed instruction, failing past the step limit of the configuration.
+    fn count_step(&mut self) -> Result<(), BfError> {
+        self.steps += 1;
+        match self.config.max_steps {
+            Some(max_steps) if self.steps > max_steps => Err(BfError::StepLimit(max_steps)),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_cell_or_overflow(&mut self, value: Option<C>) -> Result<(), BfError> {
+        match value {
+            Some(value) => {
+                self.tape[self.data_ptr] = value;
+                Ok(())
+            },
+            None => Err(BfError::CellOverflow(self.program.context(self.program_ptr))),
+        }
+    }
+
+    fn pointer_move(&mut self, n: isize) -> Result<(), BfError> {
+        let target = self.data_ptr as isize + n;
+        let len = self.tape.len();
+        if 0 <= target && target < len as isize {
+            self.data_ptr = target as usize;
+            return Ok(());
+        };
+        if self.config.tape_mode != TapeMode::Growable {
+            self.data_ptr = self.config.bounded_target(target, len).map_err(|end| {
+                BfError::TapeOverflow(end, self.program.context(self.program_ptr))
+            })?;
+            return Ok(());
+        };
+        let max_len = self.config.max_memory.map_or(usize::MAX, |max| max / mem::size_of::<C>());
+        let memory_limit = || BfError::MemoryLimit(self.config.max_memory.unwrap_or(usize::MAX));
+        if target < 0 {
+            // at least double the tape where the memory limit allows, such that a program
+            // travelling left moves each cell a logarithmic rather than linear number of times
+            let grown = target.unsigned_abs().max(len.min(max_len.saturating_sub(len)));
+            if len + grown > max_len {
+                return Err(memory_limit());
+            };
+            self.tape.splice(0..0, core::iter::repeat_n(C::default(), grown));
+            self.data_ptr = (target + grown as isize) as usize;
+        } else {
+            if target as usize + 1 > max_len {
+                return Err(memory_limit());
+            };
+            self.tape.resize(target as usize + 1, C::default());
+            self.data_ptr = target as usize;
+        };
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{BoundsPolicy, EofBehavior, OverflowPolicy};
+    use crate::dialect::{BrainFuck, Extended, Extension, PBrain};
+    use crate::error::TapeEnd;
+    use crate::token::Location;
+
+    fn run(program: Program, config: InterpreterConfig) -> Result<Vec<u8>, BfError> {
+        let mut engine: Engine = Engine::new(program).with_config(config);
+        engine.run(&mut &[][..], &mut Vec::new()).map(|()| engine.tape().to_vec())
+    }
+
+    #[test]
+    fn test_run() {
+        let mut engine: Engine = Engine::new(Program::parse(",>,<[->+<]>."));
+        let mut output = Vec::new();
+        engine.run(&mut &[3, 4][..], &mut output).unwrap();
+        assert_eq!(output, vec![7]);
+        assert_eq!((engine.tape(), engine.data_ptr()), (&[0, 7][..], 1));
+        assert!(engine.is_finished());
+    }
+
+    #[test]
+    fn test_step() {
+        let config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+        let mut engine = Engine::<u16>::new(Program::parse("++ + [>+++<-] -")).with_config(config);
+        let (mut input, mut output) = (&[][..], Vec::new());
+        assert_eq!(engine.step(&mut input, &mut output), Ok(true));
+        assert_eq!(engine.tape(), &[1]);
+        engine.run(&mut input, &mut output).unwrap();
+        assert_eq!(engine.tape(), &[u16::MAX, 9]);
+        assert_eq!(engine.steps(), 26);
+        assert_eq!(engine.step(&mut input, &mut output), Ok(false));
+    }
+
+    #[test]
+    fn test_config() {
+        let parse = Program::parse;
+        let default = InterpreterConfig::default;
+        let fixed = InterpreterConfig { tape_mode: TapeMode::Fixed, tape_size: 2, ..default() };
+        assert_eq!(run(parse("<+"), default()), Ok(vec![1, 0]));
+        let wrap = InterpreterConfig { bounds: BoundsPolicy::Wrap, ..fixed };
+        assert_eq!(run(parse("<+"), wrap), Ok(vec![0, 1]));
+        let saturate = InterpreterConfig { overflow: OverflowPolicy::Saturate, ..fixed };
+        assert_eq!(run(parse("->+,"), saturate), Ok(vec![0, 1]));
+        let zero = InterpreterConfig { eof: EofBehavior::Zero, ..fixed };
+        assert_eq!(run(parse("+,>+"), zero), Ok(vec![0, 1]));
+        let error = InterpreterConfig { overflow: OverflowPolicy::Error, ..fixed };
+        assert!(matches!(run(parse("+[+]"), error), Err(BfError::CellOverflow(_))));
+        let limited = InterpreterConfig { max_steps: Some(10), ..default() };
+        assert_eq!(run(parse("+[]"), limited), Err(BfError::StepLimit(10)));
+        let limited = InterpreterConfig { max_memory: Some(4), ..default() };
+        assert_eq!(run(parse(">>>>"), limited), Err(BfError::MemoryLimit(4)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let location = |line, column| Location { line, column };
+        let error = |program: Program, config: InterpreterConfig| {
+            let mut engine: Engine = Engine::new(program).with_config(config);
+            let error = engine.run(&mut &[][..], &mut Vec::new()).unwrap_err();
+            assert_eq!(engine.step(&mut &[][..], &mut Vec::new()), Err(error.clone()));
+            error
+        };
+        let default = InterpreterConfig::default;
+        let e = error(Program::parse("+[\n[]"), default());
+        assert!(matches!(e, BfError::UnmatchedOpenBracket(_)));
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 2,
+            optimize: false,
+            ..default()
+        };
+        match error(Program::parse("><<"), fixed) {
+            BfError::TapeOverflow(TapeEnd::Start, context) => {
+                assert_eq!(context.location, location(1, 3));
+            },
+            other => panic!("unexpected error {:?}", other),
+        };
+        let e = error(Program::parse_dialect("+(-)", &PBrain), default());
+        assert_eq!(
+            e.to_string(),
+            "'(' at line 1, col 2 is not supported by the engine\n1 | +(-)\n  |  ^",
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        let dialect = Extended::new(Box::new(BrainFuck), vec![Extension::Ext1]);
+        let program = Program::parse_dialect("+++$+++!@+", &dialect);
+        assert_eq!(run(program, InterpreterConfig::default()), Ok(vec![3]));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let depth = 100_000;
+        let source = format!("+{}-{}+", "[".repeat(depth), "]".repeat(depth));
+        assert_eq!(run(Program::parse(&source), InterpreterConfig::default()), Ok(vec![1]));
+    }
+}
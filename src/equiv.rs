@@ -0,0 +1,244 @@
+//! Checking that two programs behave the same on many inputs, see `compare`.
+
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::cell::Cell;
+use crate::config::InterpreterConfig;
+use crate::difftest::{self, Outcome};
+use crate::interpreter::ExecutionStatus;
+use crate::program::Program;
+use crate::random::Xorshift;
+
+
+/// Step limit of each run when none is configured, such that programs that never halt on some
+/// input are still compared on the rest.
+pub const DEFAULT_MAX_STEPS: u64 = 1_000_000;
+
+/// Longest input generated by `Inputs::Fuzz`.
+const MAX_FUZZ_LENGTH: usize = 16;
+
+
+/// Where the inputs to compare programs on come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inputs {
+    /// The provided number of random inputs, starting with the empty input.
+    Fuzz(usize),
+
+    /// A file holding a single input, or a directory of files holding one each.
+    Path(PathBuf),
+}
+
+impl FromStr for Inputs {
+    type Err = String;
+
+    /// Parse `fuzz:N` for `N` random inputs, or anything else as a path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("fuzz:") {
+            Some(count) => count
+                .parse()
+                .map(Inputs::Fuzz)
+                .map_err(|_| format!("invalid number of inputs '{}'", count)),
+            None => Ok(Inputs::Path(PathBuf::from(s))),
+        }
+    }
+}
+
+impl Inputs {
+    /// Generate or read the inputs, generating random ones from `seed`.
+    pub fn load(&self, seed: u64) -> Result<Vec<Vec<u8>>, String> {
+        let read = |path: &PathBuf| {
+            fs::read(path).map_err(|e| {
+                format!("input file '{}' could not be read ({})", path.display(), e)
+            })
+        };
+        match self {
+            Inputs::Fuzz(count) => Ok(fuzz(*count, seed)),
+            Inputs::Path(path) if path.is_dir() => {
+                let entries = fs::read_dir(path).map_err(|e| {
+                    format!("directory '{}' could not be read ({})", path.display(), e)
+                })?;
+                let mut files: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|file| file.is_file())
+                    .collect();
+                files.sort();
+                files.iter().map(read).collect()
+            },
+            Inputs::Path(path) => Ok(vec![read(path)?]),
+        }
+    }
+}
+
+
+/// `count` random inputs of up to `MAX_FUZZ_LENGTH` bytes generated from `seed`, starting with
+/// the empty input. Bytes are mostly printable, as programs commonly parse text.
+pub fn fuzz(count: usize, seed: u64) -> Vec<Vec<u8>> {
+    let mut rng = Xorshift::new(seed);
+    let mut next = move |n: u64| rng.below(n);
+    (0..count).map(|i| {
+        let length = if i == 0 { 0 } else { 1 + next(MAX_FUZZ_LENGTH as u64) as usize };
+        (0..length).map(|_| match next(8) {
+            0 => next(256) as u8,
+            1 => b'0' + next(10) as u8,
+            2 => b'\n',
+            _ => b' ' + next(95) as u8,
+        }).collect()
+    }).collect()
+}
+
+
+/// The first input the programs were found to behave differently on, see `compare`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Index of the input among those compared.
+    pub index: usize,
+    pub input: Vec<u8>,
+
+    /// Description of how the second program differs from the first.
+    pub difference: String,
+}
+
+
+/// Result of comparing two programs, see `compare`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Number of inputs both programs were run on.
+    pub compared: usize,
+
+    /// Number of those inputs on which a run was stopped by a limit before the programs could be
+    /// told apart, such that they may still differ.
+    pub inconclusive: usize,
+
+    pub divergence: Option<Divergence>,
+}
+
+
+/// Run programs `a` and `b` on each input in turn, with the semantics and limits of `config`,
+/// until they behave differently: they write different output, or end in a different way, e.g.
+/// one terminates while the other fails. Errors are compared by kind only.
+///
+/// As the two programs are likely to execute a different number of steps, a run stopped by a
+/// limit only tells them apart if its output so far is not the start of the output of the other.
+pub fn compare<C: Cell>(
+    a: &Program,
+    b: &Program,
+    inputs: &[Vec<u8>],
+    config: &InterpreterConfig,
+) -> Report
+{
+    let mut report = Report::default();
+    for (index, input) in inputs.iter().enumerate() {
+        let first = difftest::run::<C>(a, input, config.clone());
+        let second = difftest::run::<C>(b, input, config.clone());
+        report.compared += 1;
+        match difference(&first, &second) {
+            Ok(true) => {},
+            Ok(false) => report.inconclusive += 1,
+            Err(difference) => {
+                report.divergence = Some(Divergence { index, input: input.clone(), difference });
+                break;
+            },
+        };
+    }
+    report
+}
+
+
+/// Whether two runs behave the same, `Ok(false)` if that cannot be known as one was stopped by a
+/// limit, or a description of how the second differs.
+fn difference<C: Cell>(first: &Outcome<C>, second: &Outcome<C>) -> Result<bool, String> {
+    let limited = |outcome: &Outcome<C>| {
+        matches!(outcome.status, ExecutionStatus::LimitExceeded(_))
+    };
+    let (a, b) = (&first.output, &second.output);
+    let i = difftest::first_difference(a, b);
+    let output = || format!(
+        "output: {} bytes but {} for the second program, first differing at byte {}",
+        a.len(),
+        b.len(),
+        i,
+    );
+    if limited(first) || limited(second) {
+        // the output of a stopped run may yet have grown into the other
+        let prefix = (limited(first) && i == a.len()) || (limited(second) && i == b.len());
+        return if a == b || prefix { Ok(false) } else { Err(output()) };
+    };
+    if mem::discriminant(&first.status) != mem::discriminant(&second.status) {
+        return Err(format!(
+            "status: {} but {} for the second program",
+            difftest::describe(&first.status),
+            difftest::describe(&second.status),
+        ));
+    };
+    if a != b {
+        return Err(output());
+    };
+    Ok(true)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::{EofBehavior, TapeMode};
+
+    #[test]
+    fn test_inputs_from_str() {
+        assert_eq!("fuzz:10".parse(), Ok(Inputs::Fuzz(10)));
+        assert!("fuzz:many".parse::<Inputs>().is_err());
+        assert_eq!("inputs/".parse(), Ok(Inputs::Path(PathBuf::from("inputs/"))));
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let inputs = fuzz(50, 1);
+        assert_eq!(inputs.len(), 50);
+        assert!(inputs[0].is_empty());
+        assert!(inputs[1..].iter().all(|input| !input.is_empty() && input.len() <= 16));
+        assert_eq!(inputs, fuzz(50, 1));
+        assert_ne!(inputs, fuzz(50, 2));
+    }
+
+    #[test]
+    fn test_compare() {
+        let config = InterpreterConfig {
+            eof: EofBehavior::Zero,
+            max_steps: Some(1000),
+            ..InterpreterConfig::default()
+        };
+        let inputs = fuzz(20, 0);
+        let check = |a: &str, b: &str, config: &InterpreterConfig| {
+            compare::<u8>(&Program::parse(a), &Program::parse(b), &inputs, config)
+        };
+        let report = check(",[.,]", "comment ,[.,]+-", &config);
+        assert_eq!(report, Report { compared: 20, inconclusive: 0, divergence: None });
+
+        // reading again at the end of input changes nothing that is written
+        let report = check(",[.,]", ",[.,],", &config);
+        assert_eq!(report.divergence, None);
+        let report = check(",.", ",+.", &config);
+        assert_eq!(report.compared, 1);
+        assert_eq!(report.divergence, Some(Divergence {
+            index: 0,
+            input: Vec::new(),
+            difference: "output: 1 bytes but 1 for the second program, first differing at byte 0"
+                .to_string(),
+        }));
+
+        // neither ever halts, with the same output for as long as they run
+        let report = check("+[.]", "+[..]", &config);
+        assert_eq!(report, Report { compared: 20, inconclusive: 20, divergence: None });
+        let report = check("+[.]", "++[.]", &config);
+        assert!(report.divergence.is_some());
+
+        let report = check("<", "", &config);
+        assert!(report.divergence.is_none());
+        let config = InterpreterConfig { tape_mode: TapeMode::Fixed, ..config.clone() };
+        let report = check("<", "", &config);
+        assert_eq!(report.divergence.unwrap().difference.split(':').next(), Some("status"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Causes of failed execution, carried by `ExecutionStatus`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::json::Json;
+use crate::token::{Location, Token};
+
+
+/// Where in the source of a program an error occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceContext {
+    /// Name of the file containing the error if the program was preprocessed from files, see
+    /// `preprocess::SourceMap`.
+    pub file: Option<String>,
+
+    pub location: Location,
+
+    /// The line of source containing the error with a caret under the offending command, `None`
+    /// if the program was not parsed from source. See `Program::excerpt`.
+    pub excerpt: Option<String>,
+}
+
+
+impl fmt::Display for SourceContext {
+    /// Describe where the error occurred without the excerpt, e.g. `line 2, col 3 in lib.bf`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{} in {}", self.location, file),
+            None => write!(f, "{}", self.location),
+        }
+    }
+}
+
+
+/// End of the tape that the data pointer moved past.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TapeEnd {
+    Start,
+    End,
+}
+
+impl fmt::Display for TapeEnd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TapeEnd::Start => write!(f, "start"),
+            TapeEnd::End => write!(f, "end"),
+        }
+    }
+}
+
+
+/// Reason that a program failed to execute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BfError {
+    /// A `[` without a matching `]`.
+    UnmatchedOpenBracket(SourceContext),
+
+    /// A `]` without a matching `[`.
+    UnmatchedCloseBracket(SourceContext),
+
+    /// A `(` without a matching `)`.
+    UnmatchedOpenParen(SourceContext),
+
+    /// A `)` without a matching `(`.
+    UnmatchedCloseParen(SourceContext),
+
+    /// A procedure was called that has not been defined, identified by the value of the cell.
+    UndefinedProcedure(i64, SourceContext),
+
+    /// Input read by `,` in `IoMode::Numeric` was not an integer.
+    InvalidNumber(String, SourceContext),
+
+    /// Procedure calls were nested deeper than `InterpreterConfig::max_call_depth`.
+    CallDepth(usize),
+
+    /// Several errors found together, e.g. every unmatched bracket of a program.
+    Multiple(Vec<BfError>),
+
+    /// The data pointer moved past an end of a fixed tape.
+    TapeOverflow(TapeEnd, SourceContext),
+
+    /// `+` or `-` took a cell past its bounds with `config::OverflowPolicy::Error`.
+    CellOverflow(SourceContext),
+
+    /// Execution reached `InterpreterConfig::max_steps` instructions.
+    StepLimit(u64),
+
+    /// Execution ran for longer than `InterpreterConfig::timeout`.
+    Timeout(Duration),
+
+    /// The tape would have grown past `InterpreterConfig::max_memory` bytes.
+    MemoryLimit(usize),
+
+    /// Reading program input or writing program output failed.
+    #[cfg(feature = "std")]
+    IoError(io::ErrorKind, String),
+
+    /// A snapshot could not be parsed or does not fit the program being resumed, see
+    /// `snapshot::Snapshot`.
+    InvalidSnapshot(String),
+
+    /// A compiled program could not be decoded, see `bytecode::Bytecode`.
+    InvalidBytecode(String),
+
+    /// The REPL could not read a command, e.g. because the terminal was closed.
+    ReplError(String),
+
+    /// A command that `engine::Engine` cannot run without `std`, e.g. a procedure call.
+    UnsupportedCommand(Token, SourceContext),
+}
+
+impl BfError {
+    /// Where in the source the error occurred, if it was caused by a particular command.
+    pub fn context(&self) -> Option<&SourceContext> {
+        match self {
+            BfError::UnmatchedOpenBracket(context)
+            | BfError::UnmatchedCloseBracket(context)
+            | BfError::UnmatchedOpenParen(context)
+            | BfError::UnmatchedCloseParen(context)
+            | BfError::UndefinedProcedure(_, context)
+            | BfError::InvalidNumber(_, context)
+            | BfError::TapeOverflow(_, context)
+            | BfError::UnsupportedCommand(_, context)
+            | BfError::CellOverflow(context) => Some(context),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BfError {
+    /// Describe the error on one line, followed by an excerpt of the source on the lines below if
+    /// the error has one.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BfError::UnmatchedOpenBracket(context) => {
+                write!(f, "'[' at {} has no matching ']'", context)?;
+            },
+            BfError::UnmatchedCloseBracket(context) => {
+                write!(f, "']' at {} has no matching '['", context)?;
+            },
+            BfError::UnmatchedOpenParen(context) => {
+                write!(f, "'(' at {} has no matching ')'", context)?;
+            },
+            BfError::UnmatchedCloseParen(context) => {
+                write!(f, "')' at {} has no matching '('", context)?;
+            },
+            BfError::UndefinedProcedure(id, context) => {
+                write!(f, "procedure {} called at {} is not defined", id, context)?;
+            },
+            BfError::InvalidNumber(input, context) => {
+                write!(f, "',' at {} read '{}', which is not an integer", context, input)?;
+            },
+            BfError::CallDepth(max_call_depth) => {
+                write!(f, "exceeded the limit of {} nested procedure calls", max_call_depth)?;
+            },
+            BfError::Multiple(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("\n"))?;
+            },
+            BfError::TapeOverflow(end, context) => write!(
+                f, "data pointer moved past the {} of the tape at {}", end, context)?,
+            BfError::CellOverflow(context) => {
+                write!(f, "cell overflowed its bounds at {}", context)?;
+            },
+            BfError::StepLimit(max_steps) => write!(f, "exceeded the limit of {} steps", max_steps)?,
+            BfError::Timeout(timeout) => {
+                write!(f, "exceeded the timeout of {}s", timeout.as_secs_f64())?;
+            },
+            BfError::MemoryLimit(max_memory) => {
+                write!(f, "tape exceeded the memory limit of {} bytes", max_memory)?;
+            },
+            BfError::InvalidSnapshot(reason) => write!(f, "invalid snapshot: {}", reason)?,
+            BfError::InvalidBytecode(reason) => write!(f, "invalid bytecode: {}", reason)?,
+            BfError::UnsupportedCommand(token, context) => {
+                write!(f, "'{}' at {} is not supported by the engine", token, context)?;
+            },
+            #[cfg(feature = "std")]
+            BfError::IoError(_, message) => write!(f, "{}", message)?,
+            BfError::ReplError(message) => write!(f, "{}", message)?,
+        };
+        match self.context().and_then(|context| context.excerpt.as_ref()) {
+            Some(excerpt) => write!(f, "\n{}", excerpt),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for BfError {}
+
+/// The message of the error alongside its location in the source, if it has one, as reported by
+/// `bfi --json` and `serve`.
+#[cfg(feature = "std")]
+impl From<&BfError> for Json {
+    fn from(e: &BfError) -> Self {
+        let location = e.context().map_or(Json::Null, |context| Json::object(vec![
+            ("line", Json::from(context.location.line)),
+            ("column", Json::from(context.location.column)),
+            ("file", context.file.as_deref().map_or(Json::Null, Json::from)),
+        ]));
+        Json::object(vec![("message", Json::from(e.to_string())), ("location", location)])
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for BfError {
+    fn from(e: io::Error) -> Self { BfError::IoError(e.kind(), e.to_string()) }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let context = SourceContext {
+            file: None,
+            location: Location { line: 2, column: 3 },
+            excerpt: Some("2 | +[\n  |  ^".to_string()),
+        };
+        assert_eq!(
+            BfError::UnmatchedOpenBracket(context.clone()).to_string(),
+            "'[' at line 2, col 3 has no matching ']'\n2 | +[\n  |  ^",
+        );
+        let errors = BfError::Multiple(vec![
+            BfError::TapeOverflow(TapeEnd::Start, SourceContext { excerpt: None, ..context }),
+            BfError::StepLimit(10),
+        ]);
+        assert_eq!(
+            errors.to_string(),
+            "data pointer moved past the start of the tape at line 2, col 3\n\
+                exceeded the limit of 10 steps",
+        );
+        let context = SourceContext { file: Some("lib.bf".to_string()), ..context };
+        assert_eq!(
+            BfError::UndefinedProcedure(3, context).to_string(),
+            "procedure 3 called at line 2, col 3 in lib.bf is not defined\n2 | +[\n  |  ^",
+        );
+    }
+}
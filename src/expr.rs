@@ -0,0 +1,357 @@
+//! Arithmetic over the tape asked from the REPL with `?`, e.g. `? cell[3] + cell[4]`, see `Expr`.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cell::Cell;
+
+
+/// Arithmetic operator of an `Expr`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    /// Apply the operator, failing on overflow and division by zero.
+    fn apply(self, lhs: i64, rhs: i64) -> Result<i64, String> {
+        if rhs == 0 && matches!(self, Op::Div | Op::Rem) {
+            return Err("division by zero".to_string());
+        };
+        match self {
+            Op::Add => lhs.checked_add(rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => lhs.checked_mul(rhs),
+            Op::Div => lhs.checked_div(rhs),
+            Op::Rem => lhs.checked_rem(rhs),
+        }.ok_or_else(|| format!("{} {} {} overflowed", lhs, self, rhs))
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Sub => write!(f, "-"),
+            Op::Mul => write!(f, "*"),
+            Op::Div => write!(f, "/"),
+            Op::Rem => write!(f, "%"),
+        }
+    }
+}
+
+
+/// Function of the cells in a range of the tape, written e.g. `sum(cell[0..10])`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Function {
+    Sum,
+    Min,
+    Max,
+}
+
+
+/// Expression over the tape, parsed from strings like `cell[3] + cell[4]`, `ptr`, or
+/// `sum(cell[0..10])`.
+///
+/// Cells are indexed as by breakpoints, see `debugger::Operand`: `cell` is the current cell, and
+/// `cell[i]` counts from the cell the data pointer started on, with any expression as the index.
+/// Ranges `cell[a..b]` include `a` but not `b`. Arithmetic is on 64-bit integers with `+`, `-`,
+/// `*`, `/`, `%`, and parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(i64),
+    /// The data pointer, counting from where it started.
+    Ptr,
+    CurrentCell,
+    Cell(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+    /// A function of the cells from the first index up to but excluding the second.
+    Range(Function, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluate the expression against `tape`, on which the data pointer is at `data_ptr` and
+    /// started at `origin`. Cells off either end of the tape read as zero.
+    pub fn eval<C: Cell>(
+        &self,
+        tape: &[C],
+        data_ptr: usize,
+        origin: usize,
+    ) -> Result<i64, String> {
+        let eval = |expr: &Expr| expr.eval(tape, data_ptr, origin);
+        let read = |index: i64| {
+            (origin as i64)
+                .checked_add(index)
+                .and_then(|i| usize::try_from(i).ok())
+                .and_then(|i| tape.get(i))
+                .map_or(0, |c| c.to_i64())
+        };
+        match self {
+            Expr::Number(n) => Ok(*n),
+            Expr::Ptr => Ok(data_ptr as i64 - origin as i64),
+            Expr::CurrentCell => Ok(tape.get(data_ptr).map_or(0, |c| c.to_i64())),
+            Expr::Cell(index) => Ok(read(eval(index)?)),
+            Expr::Neg(expr) => {
+                let value = eval(expr)?;
+                value.checked_neg().ok_or_else(|| format!("-{} overflowed", value))
+            },
+            Expr::Binary(lhs, op, rhs) => op.apply(eval(lhs)?, eval(rhs)?),
+            Expr::Range(function, start, end) => {
+                let (start, end) = (eval(start)?, eval(end)?);
+                if start >= end {
+                    return match function {
+                        Function::Sum => Ok(0),
+                        _ => Err(format!("range {}..{} is empty", start, end)),
+                    };
+                };
+                // only the cells on the tape are visited, the rest reading as zero
+                let first = start.max(-(origin as i64));
+                let last = end.min(tape.len() as i64 - origin as i64);
+                let on_tape = (first..last).map(read);
+                let off_tape = last.saturating_sub(first) < end.saturating_sub(start);
+                let mut values = on_tape.chain(off_tape.then_some(0));
+                match function {
+                    Function::Sum => values.try_fold(0, |sum, value| Op::Add.apply(sum, value)),
+                    // unwraps are safe as the range is not empty
+                    Function::Min => Ok(values.min().unwrap()),
+                    Function::Max => Ok(values.max().unwrap()),
+                }
+            },
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { tokens: tokenize(s)?, next: 0 };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}'", token)),
+        }
+    }
+}
+
+
+/// Token of the source of an `Expr`.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Symbol(char),
+    /// `..`, separating the ends of a range.
+    To,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(c) => write!(f, "{}", c),
+            Token::To => write!(f, ".."),
+        }
+    }
+}
+
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+            let n = digits.parse().map_err(|_| format!("number {} is too large", digits))?;
+            tokens.push(Token::Number(n));
+        } else if c.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if c == '.' {
+            chars.next();
+            match chars.next() {
+                Some('.') => tokens.push(Token::To),
+                _ => return Err("expected '..'".to_string()),
+            };
+        } else if "+-*/%()[]".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        };
+    }
+    Ok(tokens)
+}
+
+
+/// Recursive descent parser of an `Expr`, with one method per level of precedence.
+struct Parser {
+    tokens: Vec<Token>,
+    /// Index of the next token to consume.
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> { self.tokens.get(self.next) }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    /// Consume the next token, failing unless it is `expected`.
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected '{}' but found '{}'", expected, token)),
+            None => Err(format!("expected '{}'", expected)),
+        }
+    }
+
+    /// Sums and differences of terms.
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol('+')) => Op::Add,
+                Some(Token::Symbol('-')) => Op::Sub,
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.term()?));
+        }
+    }
+
+    /// Products, quotients, and remainders of factors.
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut expr = self.factor()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol('*')) => Op::Mul,
+                Some(Token::Symbol('/')) => Op::Div,
+                Some(Token::Symbol('%')) => Op::Rem,
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Symbol('-')) => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Some(Token::Symbol('(')) => {
+                let expr = self.expr()?;
+                self.expect(Token::Symbol(')'))?;
+                Ok(expr)
+            },
+            Some(Token::Name(name)) => match name.as_str() {
+                "ptr" => Ok(Expr::Ptr),
+                "cell" if self.peek() == Some(&Token::Symbol('[')) => {
+                    self.next += 1;
+                    let index = self.expr()?;
+                    self.expect(Token::Symbol(']'))?;
+                    Ok(Expr::Cell(Box::new(index)))
+                },
+                "cell" => Ok(Expr::CurrentCell),
+                "sum" | "min" | "max" => {
+                    let function = match name.as_str() {
+                        "sum" => Function::Sum,
+                        "min" => Function::Min,
+                        _ => Function::Max,
+                    };
+                    self.expect(Token::Symbol('('))?;
+                    self.expect(Token::Name("cell".to_string()))?;
+                    self.expect(Token::Symbol('['))?;
+                    let start = self.expr()?;
+                    self.expect(Token::To)?;
+                    let end = self.expr()?;
+                    self.expect(Token::Symbol(']'))?;
+                    self.expect(Token::Symbol(')'))?;
+                    Ok(Expr::Range(function, Box::new(start), Box::new(end)))
+                },
+                _ => Err(format!("unknown name '{}'", name)),
+            },
+            Some(token) => Err(format!("unexpected '{}'", token)),
+            None => Err("expression ended early".to_string()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(s: &str, tape: &[u8], data_ptr: usize, origin: usize) -> Result<i64, String> {
+        s.parse::<Expr>()?.eval(tape, data_ptr, origin)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("ptr".parse(), Ok(Expr::Ptr));
+        assert_eq!("cell".parse(), Ok(Expr::CurrentCell));
+        assert_eq!(
+            "cell[3] + 2 * -cell".parse(),
+            Ok(Expr::Binary(
+                Box::new(Expr::Cell(Box::new(Expr::Number(3)))),
+                Op::Add,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Number(2)),
+                    Op::Mul,
+                    Box::new(Expr::Neg(Box::new(Expr::CurrentCell))),
+                )),
+            )),
+        );
+        assert_eq!(
+            "sum(cell[0..10])".parse(),
+            Ok(Expr::Range(Function::Sum, Box::new(Expr::Number(0)), Box::new(Expr::Number(10)))),
+        );
+        assert_eq!("cell[1".parse::<Expr>(), Err("expected ']'".to_string()));
+        assert_eq!("1 2".parse::<Expr>(), Err("unexpected '2'".to_string()));
+        assert_eq!("tape".parse::<Expr>(), Err("unknown name 'tape'".to_string()));
+        assert!("sum(cell[0])".parse::<Expr>().is_err());
+        assert!("cell[0.5]".parse::<Expr>().is_err());
+        assert!("".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn test_eval() {
+        let tape = [0u8, 3, 4, 200];
+        assert_eq!(eval("cell[1] + cell[2]", &tape, 0, 0), Ok(7));
+        assert_eq!(eval("(cell[1] + cell[2]) * 2 - 1", &tape, 0, 0), Ok(13));
+        assert_eq!(eval("cell[ptr + 1] % 3", &tape, 2, 0), Ok(2));
+        assert_eq!(eval("ptr", &tape, 1, 2), Ok(-1));
+        assert_eq!(eval("cell", &tape, 3, 0), Ok(200));
+        assert_eq!(eval("sum(cell[0..4])", &tape, 0, 0), Ok(207));
+        assert_eq!(eval("sum(cell[-1..1])", &tape, 0, 1), Ok(3));
+        assert_eq!(eval("max(cell[1..3])", &tape, 0, 0), Ok(4));
+        // cells off the tape read as zero
+        assert_eq!(eval("cell[10]", &tape, 0, 0), Ok(0));
+        assert_eq!(eval("min(cell[1..100])", &tape, 0, 0), Ok(0));
+        assert_eq!(eval("sum(cell[-1000000000..1000000000])", &tape, 0, 0), Ok(207));
+        assert_eq!(eval("sum(cell[3..1])", &tape, 0, 0), Ok(0));
+        assert_eq!(eval("max(cell[3..1])", &tape, 0, 0), Err("range 3..1 is empty".to_string()));
+        assert_eq!(eval("1 / (cell[0])", &tape, 0, 0), Err("division by zero".to_string()));
+        assert!(eval("9223372036854775807 + 1", &tape, 0, 0).is_err());
+    }
+}
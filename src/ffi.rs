@@ -0,0 +1,418 @@
+//! C interface for embedding the interpreter in programs written in other languages, declared for
+//! C in `include/bfi.h`.
+//!
+//! Programs are run to completion with `bf_exec` or `bf_run`, or a few instructions at a time
+//! with an interpreter created by `bf_new_interpreter` and advanced by `bf_step`. Output is
+//! returned in memory owned by Rust, which the caller hands back to `bf_free` once done with it.
+
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use libc::{c_char, c_uchar, size_t};
+
+use crate::config::InterpreterConfig;
+use crate::difftest;
+use crate::error::BfError;
+use crate::execute;
+use crate::interpreter::ExecutionStatus;
+use crate::playground::Playground;
+use crate::program::Program;
+
+
+/// Outcome of `bf_run` and `bf_step`, numbered like the exit status of the `bfi` executable.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BfStatus {
+    /// The program terminated without errors.
+    Ok = 0,
+
+    /// The program failed while running, e.g. by moving off of a fixed tape.
+    ProgramError = 1,
+
+    /// A pointer passed in was null, or the program was not valid UTF-8.
+    InvalidArgument = 2,
+
+    /// The program was rejected before running, e.g. for unbalanced brackets.
+    ParseError = 3,
+
+    /// Execution was stopped by a limit of the configuration.
+    LimitExceeded = 4,
+
+    /// Execution failed for a reason unrelated to the program, e.g. an I/O error.
+    InternalError = 5,
+
+    /// The program has yet to end, see `bf_step`.
+    Running = 6,
+}
+
+impl From<&ExecutionStatus<BfError>> for BfStatus {
+    fn from(status: &ExecutionStatus<BfError>) -> Self {
+        match status {
+            ExecutionStatus::NotStarted | ExecutionStatus::InProgress => BfStatus::Running,
+            ExecutionStatus::Terminated => BfStatus::Ok,
+            // only found by `Program::verify` before anything is executed
+            ExecutionStatus::ProgramError(BfError::UnmatchedOpenBracket(_))
+            | ExecutionStatus::ProgramError(BfError::UnmatchedCloseBracket(_))
+            | ExecutionStatus::ProgramError(BfError::UnmatchedOpenParen(_))
+            | ExecutionStatus::ProgramError(BfError::UnmatchedCloseParen(_))
+            | ExecutionStatus::ProgramError(BfError::Multiple(_)) => BfStatus::ParseError,
+            ExecutionStatus::ProgramError(_) => BfStatus::ProgramError,
+            // foreign code has no `CancelToken` to stop execution with, so this is unreachable
+            ExecutionStatus::LimitExceeded(_) | ExecutionStatus::Cancelled => {
+                BfStatus::LimitExceeded
+            },
+            ExecutionStatus::InternalError(_) => BfStatus::InternalError,
+        }
+    }
+}
+
+
+/// A program being executed a few instructions at a time, see `bf_new_interpreter`. Opaque to
+/// foreign code, which only handles pointers to it.
+pub struct BfInterpreter {
+    playground: Playground,
+    /// Number of bytes of output already returned by `bf_step`.
+    returned: usize,
+}
+
+
+/// Result structure communicating the status of a call to `bf_exec` in a foreign-friendly way.
+#[repr(C)]
+pub struct BfExecResult {
+    /// Impoverished boolean (`match success { 1 => true, 0 => false }`) indicating  the final
+    /// status of the program producing this `BfExecResult`.
+    pub success: c_uchar, // u8
+
+    /// Raw pointer to the start of the memory section containing the output of the program.
+    pub output: *mut c_uchar, // u8
+
+    /// Length of the program output, required as many valid programs will output `\x00` bytes,
+    /// preventing the usage of a NUL-terminated string for program output.
+    pub output_length: size_t, // usize
+}
+
+impl BfExecResult {
+    fn default_failure() -> Self {
+        Self {
+            success: 0,
+            output: std::ptr::null_mut() as *mut c_uchar,
+            output_length: 0,
+        }
+    }
+}
+
+
+/// Interface to `bfi::execute` a program from foreign code.
+///
+/// # Safety
+///
+/// This function dereferences the raw pointers provided as inputs. A fatal memory error will occur
+/// if either of these are invalid addresses or the specified `input_length` of `input` is not
+/// correct.
+#[no_mangle]
+#[deny(improper_ctypes)]  // TODO: this deny currently does not work
+pub unsafe extern "C" fn bf_exec(
+    program: *const c_char,
+    input: *const c_uchar,
+    input_length: size_t,
+) -> BfExecResult
+{
+    let program_str: &str = match CStr::from_ptr(program).to_str() { // unsafe
+        Ok(s) => s,
+        // return failure if the program provided is not valid unicode
+        Err(_) => return BfExecResult::default_failure(),
+    };
+
+    let input_slice: &[u8] = slice::from_raw_parts(input, input_length); //unsafe
+
+    match execute(program_str, input_slice) {
+        Ok(mut v) => {
+            // ensure v.len() == v.capacity() such that the capacity of the vector does not need to
+            // be shared with the foreign caller in order for the subsequent call to `bf_free` to
+            // not leak -- both len and capacity are required when calling `Vec::from_raw_parts`
+            v.shrink_to_fit();
+            let l = v.len();
+            let ptr = v.as_mut_ptr();
+            // instruct rust to forget about this section of memory -- it will only be
+            // deallocated if the vector is reassembled and dropped (see `bf_free`)
+            mem::forget(v);
+            BfExecResult {
+                success: 1,
+                output: ptr,
+                output_length: l,
+            }
+        },
+        // point to garbage -- will certainly crash the program if this location is returned to
+        // `bf_free`, so it is up to the foreign caller to be responsible here (as always)
+        Err(_) => BfExecResult::default_failure(),
+    }
+}
+
+
+/// Deallocate the memory containing the output of a previous call to `bf_exec`.
+///
+/// The output returned from `bf_exec` represents a vector in memory that has been forgotten by Rust
+/// and will thus not be automatically deallocated. In order to prevent this leakage, the caller o
+/// `bf_exec` should return this pointer back to Rust here such that it can be consumed and dropped.
+///
+/// # Safety
+///
+/// This function dereferences the raw pointer provided as input. In order to not crash with a
+/// message like `free(): invalid pointer`, the provided pointer and length must match the location
+/// of a vector in memory that Rust has been told to forget about -- e.g., one returned as `output`
+/// and `output_length` from `bf_exec`.
+///
+/// See the
+/// [Safety section](https://doc.rust-lang.org/std/vec/struct.Vec.html#method.from_raw_parts) of
+/// `Vec::from_raw_parts` for more information.
+#[no_mangle]
+pub unsafe extern "C" fn bf_free(
+    to_free: *mut c_uchar,
+    length: size_t,
+) {
+    Vec::from_raw_parts(to_free, length, length);
+}
+
+
+/// Run a program to completion, like `bf_exec` but reporting how execution ended and returning
+/// the output written before any error.
+///
+/// The output is stored to `output` and its length to `output_length`, to be passed to `bf_free`
+/// once no longer needed. Both are left unchanged if the status is `BfStatus::InvalidArgument`.
+///
+/// # Safety
+///
+/// `program` must point to a NUL-terminated string, `input` to `input_length` bytes (or be null if
+/// `input_length` is zero), and `output` and `output_length` to memory they can be written to.
+#[no_mangle]
+pub unsafe extern "C" fn bf_run(
+    program: *const c_char,
+    input: *const c_uchar,
+    input_length: size_t,
+    output: *mut *mut c_uchar,
+    output_length: *mut size_t,
+) -> BfStatus
+{
+    let program = match to_str(program) {
+        Some(program) => Program::parse(program),
+        None => return BfStatus::InvalidArgument,
+    };
+    if output.is_null() || output_length.is_null() {
+        return BfStatus::InvalidArgument;
+    };
+    let input = to_slice(input, input_length);
+    let outcome = difftest::run::<u8>(&program, input, InterpreterConfig::default());
+    give(outcome.output, output, output_length);
+    BfStatus::from(&outcome.status)
+}
+
+
+/// Create an interpreter for a program reading from the provided input, to be executed with
+/// `bf_step` and passed to `bf_free_interpreter` once done with. Returns null if `program` is null
+/// or not valid UTF-8.
+///
+/// # Safety
+///
+/// `program` must point to a NUL-terminated string, and `input` to `input_length` bytes (or be
+/// null if `input_length` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn bf_new_interpreter(
+    program: *const c_char,
+    input: *const c_uchar,
+    input_length: size_t,
+) -> *mut BfInterpreter
+{
+    let program = match to_str(program) {
+        Some(program) => program,
+        None => return ptr::null_mut(),
+    };
+    let playground = Playground::new(program, to_slice(input, input_length));
+    Box::into_raw(Box::new(BfInterpreter { playground, returned: 0 }))
+}
+
+
+/// Execute up to `steps` instructions, returning `BfStatus::Running` if the program can continue.
+///
+/// The output written by these instructions is stored to `output` and its length to
+/// `output_length`, to be passed to `bf_free` once no longer needed.
+///
+/// # Safety
+///
+/// `interpreter` must have been returned by `bf_new_interpreter` and not yet freed, and `output`
+/// and `output_length` must point to memory they can be written to.
+#[no_mangle]
+pub unsafe extern "C" fn bf_step(
+    interpreter: *mut BfInterpreter,
+    steps: u32,
+    output: *mut *mut c_uchar,
+    output_length: *mut size_t,
+) -> BfStatus
+{
+    let interpreter = match interpreter.as_mut() {
+        Some(interpreter) => interpreter,
+        None => return BfStatus::InvalidArgument,
+    };
+    if output.is_null() || output_length.is_null() {
+        return BfStatus::InvalidArgument;
+    };
+    interpreter.playground.step(steps);
+    let written = interpreter.playground.output_bytes()[interpreter.returned..].to_vec();
+    interpreter.returned += written.len();
+    give(written, output, output_length);
+    BfStatus::from(interpreter.playground.execution_status())
+}
+
+
+/// Deallocate an interpreter created by `bf_new_interpreter`. Does nothing if it is null.
+///
+/// # Safety
+///
+/// `interpreter` must have been returned by `bf_new_interpreter` and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn bf_free_interpreter(interpreter: *mut BfInterpreter) {
+    if !interpreter.is_null() {
+        drop(Box::from_raw(interpreter));
+    };
+}
+
+
+/// The string at `s`, if it is not null and is valid UTF-8.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    };
+    CStr::from_ptr(s).to_str().ok()
+}
+
+
+/// The `length` bytes at `bytes`, which may be null if `length` is zero.
+unsafe fn to_slice<'a>(bytes: *const c_uchar, length: size_t) -> &'a [u8] {
+    if bytes.is_null() { &[] } else { slice::from_raw_parts(bytes, length) }
+}
+
+
+/// Hand ownership of `bytes` to foreign code through `ptr` and `length`, to be returned with
+/// `bf_free`.
+unsafe fn give(bytes: Vec<u8>, ptr: *mut *mut c_uchar, length: *mut size_t) {
+    // a boxed slice has no spare capacity, so it is freed by `bf_free` knowing only its length
+    let bytes = bytes.into_boxed_slice();
+    *length = bytes.len();
+    *ptr = Box::into_raw(bytes) as *mut c_uchar;
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CString;
+
+    static ADD_PROGRAM: &str = ",>,<[->+<]>.";
+
+    /// Every function exported by this module.
+    fn exports() -> Vec<&'static str> {
+        include_str!("ffi.rs")
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub unsafe extern \"C\" fn "))
+            .filter_map(|line| line.split('(').next())
+            .collect()
+    }
+
+    #[test]
+    fn test_foreign_addition() {
+        let program: *const c_char = CString::new(ADD_PROGRAM).unwrap().into_raw();
+        for a in 0..=255 {
+            for b in 0..=255 {
+                let result: BfExecResult = unsafe { bf_exec(program, [a, b].as_ptr(), 2) };
+                assert_eq!(result.success, 1u8);
+                let expected_output: u8 = a.wrapping_add(b);
+                let actual_output: &[u8] = unsafe {
+                    slice::from_raw_parts(result.output, result.output_length)
+                };
+                if actual_output.len() != 1 {
+                    panic!("expected output of length 1, got {} ({} + {})",
+                        actual_output.len(), a, b);
+                };
+                assert_eq!(actual_output[0] as u8, expected_output);
+                unsafe { bf_free(result.output, result.output_length) };
+            };
+        };
+    }
+
+    #[test]
+    fn test_foreign_program_invalid_unicode() {
+        let program = b"\x81";
+        let program_ptr = program.as_ptr() as *const c_char;
+        let result = unsafe { bf_exec(program_ptr, [].as_ptr(), 0) };
+        assert_eq!(result.success, 0u8);
+    }
+
+    #[test]
+    fn test_foreign_program_error() {
+        let program = b"[";
+        let program_ptr = program.as_ptr() as *const c_char;
+        let result = unsafe { bf_exec(program_ptr, [].as_ptr(), 0) };
+        assert_eq!(result.success, 0u8);
+    }
+
+    #[test]
+    fn test_run() {
+        let program = CString::new(",.,.").unwrap();
+        let (mut output, mut output_length) = (ptr::null_mut(), 0);
+        let status = unsafe {
+            bf_run(program.as_ptr(), b"hi".as_ptr(), 2, &mut output, &mut output_length)
+        };
+        assert_eq!(status, BfStatus::Ok);
+        assert_eq!(unsafe { slice::from_raw_parts(output, output_length) }, b"hi");
+        unsafe { bf_free(output, output_length) };
+    }
+
+    #[test]
+    fn test_run_errors() {
+        let (mut output, mut output_length) = (ptr::null_mut(), 0);
+        let program = CString::new("+[").unwrap();
+        let status = unsafe {
+            bf_run(program.as_ptr(), ptr::null(), 0, &mut output, &mut output_length)
+        };
+        assert_eq!(status, BfStatus::ParseError);
+        assert_eq!(output_length, 0);
+        unsafe { bf_free(output, output_length) };
+        let status =
+            unsafe { bf_run(ptr::null(), ptr::null(), 0, &mut output, &mut output_length) };
+        assert_eq!(status, BfStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_interpreter() {
+        let program = CString::new(ADD_PROGRAM).unwrap();
+        let interpreter = unsafe { bf_new_interpreter(program.as_ptr(), [3, 4].as_ptr(), 2) };
+        assert!(!interpreter.is_null());
+        let (mut written, mut steps) = (Vec::new(), 0);
+        let status = loop {
+            let (mut output, mut output_length) = (ptr::null_mut(), 0);
+            let status = unsafe { bf_step(interpreter, 1, &mut output, &mut output_length) };
+            written.extend_from_slice(unsafe { slice::from_raw_parts(output, output_length) });
+            unsafe { bf_free(output, output_length) };
+            steps += 1;
+            if status != BfStatus::Running {
+                break status;
+            };
+        };
+        unsafe { bf_free_interpreter(interpreter) };
+        assert_eq!(status, BfStatus::Ok);
+        assert_eq!(written, vec![7]);
+        // the loop is executed one instruction at a time rather than optimized into an addition
+        assert!(steps > ADD_PROGRAM.len());
+    }
+
+    #[test]
+    fn test_header() {
+        let header = include_str!("../include/bfi.h");
+        let exports = exports();
+        assert_eq!(exports.len(), 6);
+        for export in exports {
+            assert!(header.contains(&format!("{}(", export)), "{} is not declared", export);
+        }
+    }
+}
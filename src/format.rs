@@ -0,0 +1,408 @@
+//! Formatting of BrainF\*ck source, see `format` and `minify`, and measuring it, see `score`.
+
+use crate::token::Token;
+
+
+/// Indentation added for each level of loop nesting.
+const INDENT: &str = "    ";
+
+
+/// Options controlling the layout produced by `format`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /// Maximum length of a line including its indentation, exceeded only by comment words that
+    /// are longer on their own.
+    pub width: usize,
+
+    /// Remove comments and blank lines, leaving only commands.
+    pub strip_comments: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions { width: 80, strip_comments: false }
+    }
+}
+
+
+/// Part of the source being formatted.
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Command(char),
+    /// A word of comment on a line with commands.
+    Word(String),
+    /// A line of comment without commands, trimmed of surrounding whitespace.
+    Line(String),
+    Blank,
+}
+
+/// Reformat `source`, indenting the body of each loop by its depth and wrapping lines at
+/// `options.width`.
+///
+/// Loops that contain no other loops are kept on one line if they fit, while the body of any other
+/// loop starts on the line after its `[` and is followed by its `]` on a line of its own. Comments
+/// on their own line are kept on their own line, other comment words alongside the commands around
+/// them, and consecutive blank lines are collapsed into one. Only the commands of BrainF\*ck and
+/// `bfi` itself are recognized, see `Token::decode`, so commands of other dialects are formatted
+/// as comments.
+///
+/// The formatted program has the same commands in the same order as `source`, and formatting it
+/// again leaves it unchanged.
+pub fn format(source: &str, options: &FormatOptions) -> String {
+    let pieces = lex(source, options.strip_comments);
+    let mut writer = Writer { options, lines: Vec::new(), line: String::new(), depth: 0 };
+    let mut i = 0;
+    while i < pieces.len() {
+        match &pieces[i] {
+            Piece::Command('[') => match inline_loop(&pieces, i, &writer) {
+                Some(end) => {
+                    for piece in &pieces[i..=end] {
+                        writer.push(piece);
+                    }
+                    i = end;
+                },
+                None => {
+                    writer.push(&pieces[i]);
+                    writer.flush();
+                    writer.depth += 1;
+                },
+            },
+            Piece::Command(']') => {
+                writer.flush();
+                writer.depth = writer.depth.saturating_sub(1);
+                writer.push(&pieces[i]);
+            },
+            Piece::Line(line) => {
+                writer.flush();
+                for word in line.split_whitespace() {
+                    writer.push(&Piece::Word(word.to_string()));
+                }
+                writer.flush();
+            },
+            Piece::Blank => {
+                writer.flush();
+                if writer.lines.last().is_some_and(|line| !line.is_empty()) {
+                    writer.lines.push(String::new());
+                };
+            },
+            piece => writer.push(piece),
+        };
+        i += 1;
+    }
+    writer.flush();
+    // a trailing blank line would be collapsed into the final newline when formatted again
+    while writer.lines.last().is_some_and(String::is_empty) {
+        writer.lines.pop();
+    }
+    writer.lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+
+/// Reduce `source` to the shortest equivalent sequence of commands this can find, without any
+/// comments or whitespace.
+///
+/// Besides comments, this removes adjacent commands that cancel out, like `+-` and `<>`, and loops
+/// that can never run because the current cell is always zero where they start: at the start of
+/// the program, and following another loop. Commands changing the tape after the last command
+/// that could observe it are removed as well. The minified program reads and writes the same as
+/// `source`, though it may not fail the same way on a fixed tape, as moves off the end of the tape
+/// can be removed with the commands that cancel them out.
+pub fn minify(source: &str) -> String {
+    let tokens = Token::parse_str(source);
+    let mut minified: Vec<Token> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if inverse(token).is_some_and(|inverse| minified.last() == Some(&inverse)) {
+            minified.pop();
+        } else if token == Token::LoopBeg && is_zero(&minified) {
+            match matching_end(&tokens, i) {
+                Some(end) => i = end,
+                None => minified.push(token),
+            };
+        } else {
+            minified.push(token);
+        };
+        i += 1;
+    }
+    // only moves and changes to cells have an inverse
+    while minified.last().and_then(|&token| inverse(token)).is_some() {
+        minified.pop();
+    }
+    minified.into_iter().map(Token::encode).collect()
+}
+
+
+/// Size of a program as counted by code golfers, see `score`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Score {
+    /// Number of commands, the usual measure of the length of a program.
+    pub commands: usize,
+
+    /// Length of the source in bytes, including everything that is not a command.
+    pub bytes: usize,
+
+    /// Number of characters that are not commands, i.e. comments and whitespace.
+    pub non_commands: usize,
+}
+
+
+/// Measure the size of `source`, see `Score`.
+pub fn score(source: &str) -> Score {
+    let commands = source.chars().filter(|&c| Token::decode(c).is_ok()).count();
+    Score {
+        commands,
+        bytes: source.len(),
+        non_commands: source.chars().count() - commands,
+    }
+}
+
+
+/// The command undoing `token`, if any.
+fn inverse(token: Token) -> Option<Token> {
+    match token {
+        Token::ValInc => Some(Token::ValDec),
+        Token::ValDec => Some(Token::ValInc),
+        Token::PtrInc => Some(Token::PtrDec),
+        Token::PtrDec => Some(Token::PtrInc),
+        _ => None,
+    }
+}
+
+
+/// Whether the current cell is known to be zero after `tokens`: either nothing has changed the
+/// tape, or a loop has just ended, with nothing but output since.
+fn is_zero(tokens: &[Token]) -> bool {
+    match tokens.iter().rev().find(|&&token| token != Token::PutChar) {
+        None | Some(Token::LoopEnd) => true,
+        Some(_) => false,
+    }
+}
+
+
+/// Index of the `]` matching the `[` at `start`, if any.
+fn matching_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LoopBeg => depth += 1,
+            Token::LoopEnd if depth == 1 => return Some(i),
+            Token::LoopEnd => depth -= 1,
+            _ => {},
+        };
+    }
+    None
+}
+
+
+/// Split `source` into pieces, dropping comments if `strip_comments` is set.
+fn lex(source: &str, strip_comments: bool) -> Vec<Piece> {
+    let is_command = |c: char| Token::decode(c).is_ok();
+    let mut pieces = Vec::new();
+    for line in source.lines() {
+        if !line.chars().any(is_command) {
+            if !strip_comments {
+                let line = line.trim();
+                let piece = if line.is_empty() { Piece::Blank } else { Piece::Line(line.to_string()) };
+                pieces.push(piece);
+            };
+            continue;
+        };
+        let mut word = String::new();
+        for c in line.chars() {
+            if is_command(c) || c.is_whitespace() {
+                if !word.is_empty() && !strip_comments {
+                    pieces.push(Piece::Word(word.clone()));
+                };
+                word.clear();
+            } else {
+                word.push(c);
+            };
+            if is_command(c) {
+                pieces.push(Piece::Command(c));
+            };
+        }
+        if !word.is_empty() && !strip_comments {
+            pieces.push(Piece::Word(word));
+        };
+    }
+    pieces
+}
+
+
+/// Index of the `]` closing the loop opened at `start` if the loop should be kept on the current
+/// line: it contains no other loops or comment lines and fits within the width.
+fn inline_loop(pieces: &[Piece], start: usize, writer: &Writer) -> Option<usize> {
+    let mut end = start + 1;
+    loop {
+        match pieces.get(end)? {
+            Piece::Command(']') => break,
+            Piece::Command('[') | Piece::Line(_) | Piece::Blank => return None,
+            _ => end += 1,
+        };
+    }
+    let mut line = writer.line.clone();
+    for piece in &pieces[start..=end] {
+        append(&mut line, piece);
+    }
+    if writer.indent().len() + line.len() <= writer.options.width { Some(end) } else { None }
+}
+
+
+/// Append `piece` to `line`, separated by a space if either is a comment.
+fn append(line: &mut String, piece: &Piece) {
+    let last_is_command = line.chars().last().is_none_or(|c| Token::decode(c).is_ok());
+    match piece {
+        Piece::Command(c) => {
+            if !last_is_command {
+                line.push(' ');
+            };
+            line.push(*c);
+        },
+        Piece::Word(word) | Piece::Line(word) => {
+            if !line.is_empty() {
+                line.push(' ');
+            };
+            line.push_str(word);
+        },
+        Piece::Blank => {},
+    };
+}
+
+
+/// Formatted lines under construction.
+struct Writer<'a> {
+    options: &'a FormatOptions,
+    lines: Vec<String>,
+    /// The line being written, without its indentation.
+    line: String,
+    depth: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn indent(&self) -> String { INDENT.repeat(self.depth) }
+
+    /// Append `piece` to the current line, first starting a new line if it would not fit.
+    fn push(&mut self, piece: &Piece) {
+        let mut line = self.line.clone();
+        append(&mut line, piece);
+        if !self.line.is_empty() && self.indent().len() + line.len() > self.options.width {
+            self.flush();
+            line.clear();
+            append(&mut line, piece);
+        };
+        self.line = line;
+    }
+
+    /// End the current line, if it has anything on it.
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.lines.push(format!("{}{}", self.indent(), line));
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn commands(source: &str) -> String {
+        source.chars().filter(|&c| Token::decode(c).is_ok()).collect()
+    }
+
+    #[test]
+    fn test_format() {
+        let source = "\
+            hello world\n\
+            \n\n\
+            ++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]   set up cells\n\
+            >>.>---.+++++++..+++.\n";
+        assert_eq!(format(source, &FormatOptions::default()), "\
+            hello world\n\
+            \n\
+            ++++++++[\n    >++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-\n\
+            ] set up cells >>.>---.+++++++..+++.\n");
+    }
+
+    #[test]
+    fn test_wrap() {
+        let options = FormatOptions { width: 10, ..FormatOptions::default() };
+        assert_eq!(
+            format("+[++++++++++>>>>>>] a comment", &options),
+            "+[\n    ++++++\n    ++++>>\n    >>>>\n] a\ncomment\n",
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let options = FormatOptions { strip_comments: true, ..FormatOptions::default() };
+        assert_eq!(format("cat:\n\n,[.,] the end", &options), ",[.,]\n");
+    }
+
+    #[test]
+    fn test_minify() {
+        assert_eq!(minify("a comment, + - >< and [-] all [ removed ]"), ",[-]");
+        assert_eq!(minify("[comment loop.]+[->+<]+-[>.<]>."), "+[->+<]>.");
+        assert_eq!(minify("+[-].[.]+"), "+[-].");
+        assert_eq!(minify(">>+<-<<>>>.>+"), ">>+<->.");
+        // an unmatched bracket is kept as it is
+        assert_eq!(minify("+[-][+."), "+[-][+.");
+        assert_eq!(minify(""), "");
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score("+[->+<]. done\n"), Score { commands: 8, bytes: 14, non_commands: 6 });
+        assert_eq!(score("é+"), Score { commands: 1, bytes: 3, non_commands: 1 });
+        assert_eq!(score(""), Score::default());
+    }
+
+    #[test]
+    fn test_minify_preserves_behavior() {
+        use crate::config::{EofBehavior, InterpreterConfig};
+        use crate::difftest;
+        use crate::program::Program;
+
+        let config = InterpreterConfig { eof: EofBehavior::Zero, ..InterpreterConfig::default() };
+        let examples = [
+            (include_str!("../examples/hello.b"), ""),
+            (include_str!("../examples/rot13.b"), "Uryyb, jbeyq!"),
+            (include_str!("../examples/hanoi.b"), ""),
+            (include_str!("../examples/selfinterpreter.b"), ",[.,]!echo"),
+        ];
+        for (source, input) in &examples {
+            let minified = minify(source);
+            assert!(minified.len() < source.len());
+            assert_eq!(minify(&minified), minified);
+            let run = |source: &str| {
+                let program = Program::parse(source);
+                let outcome = difftest::run::<u8>(&program, input.as_bytes(), config.clone());
+                (outcome.status, outcome.output)
+            };
+            assert_eq!(run(&minified), run(source));
+        }
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let sources = [
+            include_str!("../examples/hello.b"),
+            include_str!("../examples/rot13.b"),
+            include_str!("../examples/hanoi.b"),
+            "+[ [x]y ]] z [[ ",
+            "a+b-c[d>e<f]g.h,\n\n\ni",
+        ];
+        for width in [1, 10, 40, 80] {
+            for strip_comments in [false, true] {
+                let options = FormatOptions { width, strip_comments };
+                for source in &sources {
+                    let formatted = format(source, &options);
+                    assert_eq!(commands(&formatted), commands(source));
+                    assert_eq!(format(&formatted, &options), formatted, "{:?}", options);
+                }
+            }
+        }
+    }
+}
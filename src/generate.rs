@@ -0,0 +1,151 @@
+//! Generation of programs printing a given text, see `generate`.
+
+/// Number of cells holding characters, each kept near the last character printed from it such that
+/// e.g. lowercase letters, uppercase letters, and punctuation can each be printed from their own.
+const CELLS: usize = 4;
+
+/// Largest factor tried for multiplication loops, see `Change`.
+const MAX_FACTOR: i32 = 16;
+
+
+/// Generate a short program printing `text`.
+///
+/// The program keeps a scratch cell at the start of the tape and `CELLS` cells to its right, and
+/// prints each byte from whichever cell is cheapest to change to it: changing a cell by up to a
+/// few dozen is done directly with `+` or `-`, while larger changes multiply by counting down the
+/// scratch cell in a loop. Cells are assumed to wrap, but as only the low byte of a cell is printed
+/// the program prints the same with cells of any size.
+pub fn generate(text: &[u8]) -> String {
+    let changes = Changes::new();
+    let mut program = String::new();
+    let mut cells = [0u8; CELLS];
+    // the data pointer starts on the scratch cell
+    let mut ptr = 0;
+    for &byte in text {
+        let (cell, change) = (0..CELLS)
+            .map(|cell| (cell, changes.best(cells[cell], byte)))
+            .min_by_key(|&(cell, change)| change.len(ptr, cell + 1))
+            .unwrap();
+        change.write(&mut program, ptr, cell + 1);
+        program.push('.');
+        cells[cell] = byte;
+        ptr = cell + 1;
+    }
+    program
+}
+
+
+/// A change to a cell by `factor * times + rest`, computed by counting down the scratch cell from
+/// `times` if it is not zero, while adding `factor` to the cell on each iteration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Change {
+    times: i32,
+    factor: i32,
+    rest: i32,
+}
+
+impl Change {
+    /// Length of the commands applying the change to the cell at `cell` when the data pointer is
+    /// at `ptr`, leaving it at `cell`.
+    fn len(&self, ptr: usize, cell: usize) -> usize {
+        let (times, factor, rest) =
+            (self.times.unsigned_abs(), self.factor.unsigned_abs(), self.rest.unsigned_abs());
+        if self.times == 0 {
+            ptr.abs_diff(cell) + rest as usize
+        } else {
+            // to the scratch cell, `[`, over to the cell and back, `-]`, and to the cell again
+            ptr + (times + factor + rest) as usize + 3 * cell + 3
+        }
+    }
+
+    /// Append the commands applying the change to the cell at `cell` when the data pointer is at
+    /// `ptr`.
+    fn write(&self, program: &mut String, ptr: usize, cell: usize) {
+        let steps = |n: i32| if n < 0 { "-" } else { "+" }.repeat(n.unsigned_abs() as usize);
+        if self.times != 0 {
+            program.push_str(&"<".repeat(ptr));
+            program.push_str(&steps(self.times));
+            program.push('[');
+            program.push_str(&">".repeat(cell));
+            program.push_str(&steps(self.factor));
+            program.push_str(&"<".repeat(cell));
+            program.push_str("-]");
+            program.push_str(&">".repeat(cell));
+        } else if ptr < cell {
+            program.push_str(&">".repeat(cell - ptr));
+        } else {
+            program.push_str(&"<".repeat(ptr - cell));
+        };
+        program.push_str(&steps(self.rest));
+    }
+}
+
+
+/// The shortest way to change a cell by each amount from -256 to 255, ignoring the moves to get to
+/// it.
+struct Changes {
+    by_delta: Vec<Change>,
+}
+
+impl Changes {
+    fn new() -> Self {
+        let mut by_delta = Vec::new();
+        for delta in -256..256 {
+            let mut best = Change { times: 0, factor: 0, rest: delta };
+            for times in 1..=MAX_FACTOR {
+                for factor in (-MAX_FACTOR..=MAX_FACTOR).filter(|&factor| factor != 0) {
+                    let change = Change { times, factor, rest: delta - times * factor };
+                    if change.len(0, 1) < best.len(0, 1) {
+                        best = change;
+                    };
+                }
+            }
+            by_delta.push(best);
+        }
+        Changes { by_delta }
+    }
+
+    /// The shortest change from `from` to `to`, wrapping in either direction.
+    fn best(&self, from: u8, to: u8) -> Change {
+        let up = to.wrapping_sub(from) as usize;
+        // indexed from -256, such that `up` is at index `up + 256` and `up - 256` at `up`
+        let (up, down) = (self.by_delta[up + 256], self.by_delta[up]);
+        if down.len(0, 1) < up.len(0, 1) { down } else { up }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::InterpreterConfig;
+    use crate::difftest;
+    use crate::interpreter::ExecutionStatus;
+    use crate::program::Program;
+
+    fn run<C: crate::cell::Cell>(program: &str) -> Vec<u8> {
+        let program = Program::parse(program);
+        let outcome = difftest::run::<C>(&program, b"", InterpreterConfig::default());
+        assert_eq!(outcome.status, ExecutionStatus::Terminated);
+        outcome.output
+    }
+
+    #[test]
+    fn test_generate() {
+        let all_bytes: Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+        let texts: [&[u8]; 4] = [b"", b"Hello, World!\n", b"zzzz AAAA", &all_bytes];
+        for text in &texts {
+            let program = generate(text);
+            assert_eq!(run::<u8>(&program), *text);
+            assert_eq!(run::<u16>(&program), *text);
+            assert_eq!(run::<i32>(&program), *text);
+        }
+    }
+
+    #[test]
+    fn test_shorter_than_naive() {
+        let text = b"Hello, World!\n";
+        let naive: usize = text.iter().map(|&b| b as usize + 4).sum();
+        assert!(generate(text).len() < naive / 4);
+    }
+}
@@ -0,0 +1,177 @@
+//! Settings carried by a program at the top of its source, see `Header`.
+
+use std::str::FromStr;
+
+use crate::config::{
+    BoundsPolicy, CellSize, EofBehavior, InterpreterConfig, IoMode, OverflowPolicy, TapeMode,
+};
+
+
+/// Start of each line of a header.
+const HEADER_PREFIX: &str = ";!";
+
+
+/// Settings of the interpreter given by the lines starting with `;!` at the top of a program, e.g.
+/// `;! cell-size=16 eof=zero tape=30000`, such that a program carries the semantics it was written
+/// for with it.
+///
+/// Each setting is named like the option of the `bfi` executable that it stands in for, with
+/// `tape` short for `tape-size`, and takes the same values. The header may follow a `#!` line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Header {
+    pub tape_mode: Option<TapeMode>,
+    pub tape_size: Option<usize>,
+    pub bounds: Option<BoundsPolicy>,
+    pub cell_size: Option<CellSize>,
+    pub overflow: Option<OverflowPolicy>,
+    pub eof: Option<EofBehavior>,
+    pub io_mode: Option<IoMode>,
+}
+
+impl Header {
+    /// Read the header of `source`, which is empty if the source has none, failing on an unknown
+    /// setting or value.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut header = Header::default();
+        for line in header_lines(source) {
+            for setting in line[HEADER_PREFIX.len()..].split_whitespace() {
+                let (name, value) = setting
+                    .split_once('=')
+                    .ok_or_else(|| format!("setting '{}' has no value", setting))?;
+                header.set(name, value)?;
+            }
+        }
+        Ok(header)
+    }
+
+    /// Set the setting `name` to `value`, both as written in a header, e.g. as read from the
+    /// configuration file by `settings::Settings`.
+    pub(crate) fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "tape-mode" => self.tape_mode = Some(parse_value(name, value)?),
+            "tape" | "tape-size" => self.tape_size = Some(parse_value(name, value)?),
+            "bounds" => self.bounds = Some(parse_value(name, value)?),
+            "cell-size" => self.cell_size = Some(parse_value(name, value)?),
+            "overflow" => self.overflow = Some(parse_value(name, value)?),
+            "eof" => self.eof = Some(parse_value(name, value)?),
+            "io" => self.io_mode = Some(parse_value(name, value)?),
+            _ => return Err(format!("unknown setting '{}'", name)),
+        };
+        Ok(())
+    }
+
+    /// `source` with the lines of its header left empty, such that settings are not read as
+    /// commands (e.g. the `-` of `cell-size`) while every command keeps its location.
+    pub fn strip(source: &str) -> String {
+        let lines = header_lines(source).count();
+        if lines == 0 {
+            return source.to_string();
+        };
+        let mut stripped = String::with_capacity(source.len());
+        for (i, line) in source.split_inclusive('\n').enumerate() {
+            let is_header = line.starts_with(HEADER_PREFIX) && i <= lines;
+            if !is_header {
+                stripped.push_str(line);
+            } else if line.ends_with('\n') {
+                stripped.push('\n');
+            };
+        }
+        stripped
+    }
+
+    /// Apply every setting to `config` except those for which `given` is true of the name of the
+    /// option, such that options given explicitly take precedence over the header.
+    pub fn apply(
+        &self,
+        mut config: InterpreterConfig,
+        given: impl Fn(&str) -> bool,
+    ) -> InterpreterConfig {
+        if let Some(tape_mode) = self.tape_mode.filter(|_| !given("tape-mode")) {
+            config.tape_mode = tape_mode;
+        };
+        if let Some(tape_size) = self.tape_size.filter(|_| !given("tape-size")) {
+            // as with `--tape-size`, a growable tape starts with room for that many cells
+            config.tape_size = tape_size;
+            config.tape_capacity = tape_size;
+        };
+        if let Some(bounds) = self.bounds.filter(|_| !given("bounds")) {
+            config.bounds = bounds;
+        };
+        if let Some(cell_size) = self.cell_size.filter(|_| !given("cell-size")) {
+            config.cell_size = cell_size;
+        };
+        if let Some(overflow) = self.overflow.filter(|_| !given("overflow")) {
+            config.overflow = overflow;
+        };
+        if let Some(eof) = self.eof.filter(|_| !given("eof")) {
+            config.eof = eof;
+        };
+        if let Some(io_mode) = self.io_mode.filter(|_| !given("io")) {
+            config.io_mode = io_mode;
+        };
+        config
+    }
+}
+
+
+/// The lines of the header of `source`, see `Header`.
+fn header_lines(source: &str) -> impl Iterator<Item = &str> {
+    source
+        .lines()
+        .enumerate()
+        .skip_while(|(i, line)| *i == 0 && line.starts_with("#!"))
+        .map(|(_, line)| line)
+        .take_while(|line| line.starts_with(HEADER_PREFIX))
+}
+
+
+/// Parse the `value` of the setting `name`.
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value '{}' of setting '{}'", value, name))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = ";! cell-size=16 eof=zero\n;! tape=300\n+.\n;! io=numeric";
+        let header = Header::parse(source).unwrap();
+        assert_eq!(header, Header {
+            cell_size: Some(CellSize::U16),
+            eof: Some(EofBehavior::Zero),
+            tape_size: Some(300),
+            ..Header::default()
+        });
+        let header = Header::parse("#!/usr/bin/env bfi\n;! tape-mode=fixed\n").unwrap();
+        assert_eq!(header.tape_mode, Some(TapeMode::Fixed));
+        assert_eq!(Header::parse("+ ;! eof=zero"), Ok(Header::default()));
+        assert_eq!(
+            Header::parse(";! cells=16"),
+            Err("unknown setting 'cells'".to_string()),
+        );
+        assert_eq!(
+            Header::parse(";! eof=never"),
+            Err("invalid value 'never' of setting 'eof'".to_string()),
+        );
+        assert!(Header::parse(";! eof").is_err());
+    }
+
+    #[test]
+    fn test_strip() {
+        assert_eq!(Header::strip(";! cell-size=16\n;! eof=0\n+-\n;! x\n"), "\n\n+-\n;! x\n");
+        assert_eq!(Header::strip("#!bfi\n;! cell-size=16\n-"), "#!bfi\n\n-");
+        assert_eq!(Header::strip("+-"), "+-");
+    }
+
+    #[test]
+    fn test_apply() {
+        let header = Header::parse(";! cell-size=16 eof=zero tape=30").unwrap();
+        let config = header.apply(InterpreterConfig::default(), |name| name == "eof");
+        assert_eq!(config.cell_size, CellSize::U16);
+        assert_eq!(config.eof, InterpreterConfig::default().eof);
+        assert_eq!((config.tape_size, config.tape_capacity), (30, 30));
+    }
+}
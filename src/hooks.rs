@@ -0,0 +1,98 @@
+//! Callbacks from within the interpreter loop, see `InterpreterHooks`.
+
+use crate::cell::Cell;
+use crate::program::Instruction;
+
+
+/// Callbacks made by an `ExecutionContext` as it executes a program, to build tracers,
+/// visualizers, or coverage tools on, see `ExecutionContext::with_hooks`.
+///
+/// Every callback does nothing by default, such that an implementation only provides those it
+/// needs. Loops replaced by the optimizer, e.g. `[-]`, are executed as single instructions, so
+/// disable `InterpreterConfig::optimize` to be called for every loop of the source.
+pub trait InterpreterHooks<C: Cell> {
+    /// Called before each instruction is executed, with its index in the program and the tape as
+    /// it stands.
+    fn on_instruction(
+        &mut self,
+        _program_ptr: usize,
+        _instruction: &Instruction,
+        _tape: &[C],
+        _data_ptr: usize,
+    ) {}
+
+    /// Called with each value written by `.`, before it is encoded according to the
+    /// `config::IoMode`.
+    fn on_output(&mut self, _value: C) {}
+
+    /// Called with each value read by `,`, or with `None` at the end of input.
+    fn on_input(&mut self, _value: Option<C>) {}
+
+    /// Called when the `[` at `program_ptr` enters its loop, as the current cell is not zero.
+    /// Further iterations of the loop, which jump back from its `]`, are not reported.
+    fn on_loop_enter(&mut self, _program_ptr: usize) {}
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::config::InterpreterConfig;
+    use crate::interpreter::{ExecutionContext, ExecutionStatus};
+    use crate::ioctx::{InMemoryIoCtx, IoCtx};
+
+    /// Every callback made, shared with the test once the hooks are handed to the interpreter.
+    #[derive(Default)]
+    struct Recorder {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl InterpreterHooks<u8> for Recorder {
+        fn on_instruction(&mut self, program_ptr: usize, _: &Instruction, tape: &[u8], ptr: usize) {
+            self.events.borrow_mut().push(format!("{}: cell {}", program_ptr, tape[ptr]));
+        }
+
+        fn on_output(&mut self, value: u8) {
+            self.events.borrow_mut().push(format!("output {}", value));
+        }
+
+        fn on_input(&mut self, value: Option<u8>) {
+            self.events.borrow_mut().push(format!("input {:?}", value));
+        }
+
+        fn on_loop_enter(&mut self, program_ptr: usize) {
+            self.events.borrow_mut().push(format!("loop {}", program_ptr));
+        }
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut ictx = InMemoryIoCtx::default();
+        ictx.write_input(b"\x02").unwrap();
+        let ictx = RefCell::new(Box::new(ictx) as Box<dyn IoCtx>);
+        let recorder = Recorder::default();
+        let events = Rc::clone(&recorder.events);
+        let config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), ",[-].,")
+            .with_config(config)
+            .with_hooks(Box::new(recorder));
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(*events.borrow(), vec![
+            "0: cell 0",
+            "input Some(2)",
+            "1: cell 2",
+            "loop 1",
+            "2: cell 2",
+            "3: cell 1",
+            "2: cell 1",
+            "3: cell 0",
+            "4: cell 0",
+            "output 0",
+            "5: cell 0",
+            "input None",
+        ]);
+    }
+}
@@ -1,13 +1,46 @@
 //! The interpreter resposible for executing programs.
 
+use std::borrow::Cow;
 use std::cell::RefMut;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::default::Default;
 use std::fmt::{self, Debug};
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
+use std::mem;
+#[cfg(feature = "jit")]
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Instant;
 
+use crate::cancel::CancelToken;
+use crate::cell::Cell;
+use crate::config::{
+    BoundsPolicy, BreakpointAction, InterpreterConfig, IoMode, TapeBackend, TapeMode,
+};
+#[cfg(feature = "jit")]
+use crate::config::OverflowPolicy;
+use crate::debugger::{Breakpoint, Debugger};
+use crate::difftest;
+use crate::error::{BfError, SourceContext};
+use crate::expr::Expr;
+use crate::hooks::InterpreterHooks;
 use crate::ioctx::IoCtx;
+#[cfg(feature = "jit")]
+use crate::jit;
+use crate::journal::Journal;
+use crate::optimizer;
+use crate::profile::Profile;
+use crate::program::{Instruction, Program};
+use crate::random::Xorshift;
 use crate::repl;
-use crate::token::Token;
+use crate::scheduler::{Scheduler, Thread};
+use crate::snapshot::Snapshot;
+use crate::stats::ExecutionStats;
+use crate::tape::{self, Tape};
+use crate::trace::Tracer;
+use crate::utf8::{self, Utf8Input, Utf8Output};
+use crate::visualize::Timeline;
 
 
 /// Current status of the interpreter.
@@ -27,271 +60,2395 @@ pub enum ExecutionStatus<T> {
 
     /// Indicates termination with a 'me' problem (e.g. HTTP 500s).
     InternalError(T),
+
+    /// Indicates that execution was stopped upon reaching the step limit or timeout of the
+    /// configuration, see `InterpreterConfig::max_steps` and `InterpreterConfig::timeout`.
+    LimitExceeded(T),
+
+    /// Indicates that execution was stopped through a `CancelToken`, see
+    /// `ExecutionContext::with_cancel_token`, or with `ExecutionContext::cancel`.
+    Cancelled,
+}
+
+impl<T> ExecutionStatus<T> {
+    /// Name of the status as reported in JSON, e.g. `"limit_exceeded"`, with `"unfinished"` for
+    /// both `NotStarted` and `InProgress`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutionStatus::NotStarted | ExecutionStatus::InProgress => "unfinished",
+            ExecutionStatus::Terminated => "terminated",
+            ExecutionStatus::ProgramError(_) => "program_error",
+            ExecutionStatus::InternalError(_) => "internal_error",
+            ExecutionStatus::LimitExceeded(_) => "limit_exceeded",
+            ExecutionStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// The cause of the failure, if execution failed.
+    pub fn error(&self) -> Option<&T> {
+        match self {
+            ExecutionStatus::ProgramError(e)
+            | ExecutionStatus::InternalError(e)
+            | ExecutionStatus::LimitExceeded(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 
+/// Number of instructions executed between checks of the timeout, as reading the clock is slow
+/// relative to executing an instruction.
+const TIMEOUT_CHECK_INTERVAL: u64 = 1024;
+
+
+/// A tape of the multitape extension that is not in use, with its data pointer and origin.
+type SpareTape<C> = (Box<dyn Tape<C>>, usize, usize);
+
+
+/// Number of cells `tape` has room for, counted against the memory limit of the program while
+/// another tape is in use.
+fn spare_cells<C: Cell>(tape: &dyn Tape<C>) -> usize { tape.len().max(tape.capacity()) }
+
+
 /// The internal state of a BrainF\*ck program.
 ///
-/// Note that only the `status` is visible. If you are interested in inspecting the state during
-/// execution, use the REPL by putting a debug breakpoint (`%`) in your program!
-pub struct ExecutionContext<'a> {
-    pub status: ExecutionStatus<String>,
+/// The state can be inspected between calls to `step` with accessors like `tape` and `data_ptr`.
+/// To inspect it interactively instead, put a debug breakpoint (`%`) in your program, or pause on
+/// a condition with `with_breakpoint`!
+///
+/// Cells on the tape are of type `C`, 8-bit unsigned integers unless otherwise specified.
+pub struct ExecutionContext<'a, C: Cell = u8> {
+    pub status: ExecutionStatus<BfError>,
+    config: InterpreterConfig,
     ctx: Option<RefMut<'a, Box<dyn IoCtx>>>,
-    data: Vec<u8>,
+    data: Box<dyn Tape<C>>,
     data_ptr: usize,
-    program: Vec<Token>,
+    program: Program,
     program_ptr: usize,
-    loop_stack: Vec<usize>,
+    debugger: Debugger,
+    /// Number of instructions executed so far, counted against `config.max_steps`.
+    steps: u64,
+    /// Time at which execution is stopped, if `config.timeout` is set.
+    deadline: Option<Instant>,
+    /// Time at which execution began, read by the time extension, see `dialect::Extension::Time`.
+    started: Option<Instant>,
+    cancel_token: Option<CancelToken>,
+    pause_token: Option<CancelToken>,
+    tracer: Option<Tracer<'a>>,
+    profile: Option<Profile>,
+    stats: Option<ExecutionStats>,
+    timeline: Option<Timeline>,
+    hooks: Option<Box<dyn InterpreterHooks<C> + 'a>>,
+    /// Snapshot to resume from once execution begins, see `with_snapshot`.
+    restore: Option<Snapshot<C>>,
+    journal: Journal<C>,
+    /// Index of the `ProcBeg` of each procedure defined so far, keyed by the value identifying it.
+    procedures: HashMap<i64, usize>,
+    /// Index of the instruction to return to from each procedure call in progress, innermost last.
+    call_stack: Vec<usize>,
+    /// Threads forked by the program waiting for their turn to run.
+    scheduler: Scheduler<C>,
+    /// Storage cell of the Extended Type I extension, see `dialect::Extension::Ext1`.
+    storage: C,
+    /// Source of the bytes of the random extension, see `dialect::Extension::Random`.
+    rng: Xorshift,
+    /// Every tape of the multitape extension with its pointers, `None` for the one in use as
+    /// it is `data`, see `dialect::Extension::Multitape`. Created as they are first switched to.
+    tapes: Vec<Option<SpareTape<C>>>,
+    /// Index in `tapes` of the tape in use.
+    tape_index: usize,
+    /// Rows of the grid extension other than the one in use, keyed by row, each with the value of
+    /// `origin` when it was last in use, see `dialect::Extension::Grid`.
+    rows: HashMap<i64, (Box<dyn Tape<C>>, usize)>,
+    /// Row of the grid in use as `data`.
+    row: i64,
+    /// Cells held by every tape other than `data`, i.e. the other `tapes` and `rows` and the tapes
+    /// of the threads in `scheduler`, which `data` shares `config.max_memory` with.
+    spare_cells: usize,
+    /// Index into `data` of the cell the data pointer started on, from which indices are counted
+    /// when shown to the user. Moves right as a growable tape grows to the left, by which rows of
+    /// the grid not in use are shifted when next used to keep its columns lined up.
+    origin: usize,
+    /// Whether breakpoints open the REPL, see `without_repl`.
+    repl: bool,
+    /// Whether a program entered in the REPL is running in place of the program, see
+    /// `run_subprogram`.
+    subprogram: bool,
+    /// Prompt of `run_session`, see `with_prompt`.
+    prompt: Option<String>,
+    /// Descriptions of the breakpoints hit since `take_paused` was last called.
+    paused: Vec<String>,
+    /// Bytes of UTF-8 text passing through byte cells, see `config::IoMode::Utf8`.
+    utf8_input: Utf8Input,
+    utf8_output: Utf8Output,
+    /// Whether the last `,` gave up waiting for input as the cancel or pause token was set, such
+    /// that it runs again if execution continues, see `abandon_read`.
+    read_abandoned: bool,
 }
 
 
-impl<'a> Debug for ExecutionContext<'a> {
+/// Alias for `ExecutionContext` under the name used when embedding the interpreter as a library.
+pub type Interpreter<'a, C = u8> = ExecutionContext<'a, C>;
+
+
+/// Execute `program` to completion using the provided I/O context.
+///
+/// Shorthand for `ExecutionContext::new(ictx, program).run_to_completion()`.
+pub fn run(ictx: RefMut<Box<dyn IoCtx>>, program: &str) -> ExecutionStatus<BfError> {
+    ExecutionContext::new(ictx, program).run_to_completion()
+}
+
+
+impl<'a, C: Cell> Debug for ExecutionContext<'a, C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "data: {:?}\ndata_ptr: {:?}\nprogram_ptr: {:?}\nloop_stack: {:?}\nstatus: {:?}",
-            self.data, self.data_ptr, self.program_ptr, self.loop_stack, self.status,
+            "data: {:?}\norigin: {:?}\ndata_ptr: {:?}\nprogram_ptr: {:?}\nstatus: {:?}",
+            self.data.cells(), self.origin, self.logical_ptr(), self.program_ptr, self.status,
         )
     }
 }
 
 
-impl<'a> Default for ExecutionContext<'a> {
+impl<'a, C: Cell> Default for ExecutionContext<'a, C> {
     fn default() -> Self {
         ExecutionContext {
             status: ExecutionStatus::NotStarted,
+            config: InterpreterConfig::default(),
             ctx: None,
-            data: vec![0],
+            data: tape::new_tape(TapeBackend::Vec, vec![C::default()]),
             data_ptr: 0,
-            program: vec![],
+            program: Program::default(),
             program_ptr: 0,
-            loop_stack: vec![],
+            debugger: Debugger::default(),
+            steps: 0,
+            deadline: None,
+            started: None,
+            cancel_token: None,
+            pause_token: None,
+            tracer: None,
+            profile: None,
+            stats: None,
+            timeline: None,
+            hooks: None,
+            restore: None,
+            journal: Journal::default(),
+            procedures: HashMap::new(),
+            call_stack: Vec::new(),
+            scheduler: Scheduler::default(),
+            storage: C::default(),
+            rng: Xorshift::default(),
+            tapes: Vec::new(),
+            tape_index: 0,
+            rows: HashMap::new(),
+            row: 0,
+            spare_cells: 0,
+            origin: 0,
+            repl: true,
+            subprogram: false,
+            prompt: None,
+            paused: Vec::new(),
+            utf8_input: Utf8Input::default(),
+            utf8_output: Utf8Output::default(),
+            read_abandoned: false,
+        }
+    }
+}
+
+
+/// Executes the program, yielding each instruction executed along with its index in the program,
+/// such that a trace of execution can be consumed lazily, e.g. by analysis tools.
+///
+/// The iterator ends with execution, after which the final status and state are available from
+/// `into_inner`. Instructions are executed with `ExecutionContext::step`, so the program is never
+/// compiled with the JIT, and loops replaced by the optimizer are yielded as single instructions.
+impl<'a, C: Cell> IntoIterator for ExecutionContext<'a, C> {
+    type Item = (usize, Instruction);
+    type IntoIter = ExecutedInstructions<'a, C>;
+
+    fn into_iter(self) -> Self::IntoIter { ExecutedInstructions { ectx: self } }
+}
+
+
+/// Iterator over the instructions executed by an `ExecutionContext`, see its `into_iter`.
+pub struct ExecutedInstructions<'a, C: Cell = u8> {
+    ectx: ExecutionContext<'a, C>,
+}
+
+impl<'a, C: Cell> ExecutedInstructions<'a, C> {
+    /// The context executing the program, e.g. to inspect the tape between instructions.
+    pub fn context(&self) -> &ExecutionContext<'a, C> { &self.ectx }
+
+    pub fn into_inner(self) -> ExecutionContext<'a, C> { self.ectx }
+}
+
+impl<'a, C: Cell> Iterator for ExecutedInstructions<'a, C> {
+    type Item = (usize, Instruction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(executed) = self.ectx.step_instruction() {
+                return Some(executed);
+            };
+            if self.ectx.status != ExecutionStatus::InProgress {
+                return None;
+            };
         }
     }
 }
 
 
 impl<'a> ExecutionContext<'a> {
-    /// Create a new `ExecutionContext` with the provided I/O context and program. Typically called
-    /// immediately before `execute`.
+    /// Create a new `ExecutionContext` with 8-bit cells and the provided I/O context and program.
+    /// Typically called immediately before `execute`.
     pub fn new(ictx: RefMut<'a, Box<dyn IoCtx>>, program: &str) -> Self {
+        Self::with_cells(ictx, program)
+    }
+}
+
+
+impl<'a, C: Cell> ExecutionContext<'a, C> {
+    /// Create a new `ExecutionContext` with cells of type `C`, e.g.
+    /// `ExecutionContext::<u16>::with_cells(ictx, program)`.
+    pub fn with_cells(ictx: RefMut<'a, Box<dyn IoCtx>>, program: &str) -> Self {
+        Self::with_program(ictx, Program::parse(program))
+    }
+
+    /// Like `with_cells`, executing a program that has already been parsed, e.g. from another
+    /// dialect with `Program::parse_dialect`.
+    ///
+    /// Each `%` in the program is numbered in the order it appears in the source, ahead of any
+    /// breakpoint added with `with_breakpoint`, such that it can be disabled from the REPL.
+    pub fn with_program(ictx: RefMut<'a, Box<dyn IoCtx>>, program: Program) -> Self {
+        let mut debugger = Debugger::default();
+        for (i, instruction) in program.instructions().iter().enumerate() {
+            if let Instruction::DebugBreakpoint = instruction {
+                debugger.add_source(program.position(i), program.context(i).to_string());
+            };
+        }
         ExecutionContext {
             ctx: Some(ictx),
-            program: Token::parse_str(program),
+            program,
+            debugger,
             ..ExecutionContext::default()
         }
     }
 
+    /// Apply the provided configuration to this context, resetting the tape to match.
+    ///
+    /// Must be called before execution begins.
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        self.journal = Journal::with_capacity(config.history);
+        self.rng = Xorshift::new(config.seed);
+        self.config = config;
+        self.reset_tape();
+        self
+    }
+
+    /// Open the REPL when `breakpoint` is hit during execution, see `debugger::Breakpoint`.
+    ///
+    /// Breakpoints are checked after every instruction, so programs with breakpoints are always
+    /// interpreted rather than compiled with the JIT.
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.debugger.add(breakpoint);
+        self
+    }
+
+    /// Pause at breakpoints by returning from `step` rather than opening the REPL, such that the
+    /// caller can present the state of execution itself. The breakpoints hit are available from
+    /// `take_paused`.
+    pub fn without_repl(mut self) -> Self {
+        self.repl = false;
+        self
+    }
+
+    /// Prompt for each program of `run_session` with `prompt` rather than
+    /// `repl::DEFAULT_PROMPT`.
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// Record every instruction executed with `tracer`, see `trace::Tracer`.
+    ///
+    /// Like programs with breakpoints, traced programs are always interpreted rather than compiled
+    /// with the JIT.
+    pub fn with_tracer(mut self, tracer: Tracer<'a>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Count the instructions executed and cells accessed, available from `profile` during and
+    /// after execution. See `profile::Profile`.
+    ///
+    /// Like programs with breakpoints, profiled programs are always interpreted rather than
+    /// compiled with the JIT.
+    pub fn with_profiling(mut self) -> Self {
+        self.profile = Some(Profile::default());
+        self
+    }
+
+    /// Collect the totals of `stats::ExecutionStats`, available from `stats` once execution ends.
+    ///
+    /// Instructions are counted as they are interpreted, so programs are then never compiled with
+    /// the JIT.
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(ExecutionStats::default());
+        self
+    }
+
+    /// Sample the tape as the program executes, available from `timeline` during and after
+    /// execution. See `visualize::Timeline`.
+    ///
+    /// The tape is sampled as instructions are interpreted, so programs are then never compiled
+    /// with the JIT.
+    pub fn with_timeline(mut self) -> Self {
+        self.timeline = Some(Timeline::default());
+        self
+    }
+
+    /// Stop execution with `ExecutionStatus::Cancelled` once `token` is cancelled, see
+    /// `cancel::CancelToken`.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Pause execution as at a breakpoint whenever `token` is cancelled, opening the REPL at the
+    /// next instruction, or returning from `step` if the REPL is disabled with `without_repl`.
+    /// The token is cleared each time, such that it can pause execution again.
+    pub fn with_pause_token(mut self, token: CancelToken) -> Self {
+        self.pause_token = Some(token);
+        self
+    }
+
+    /// Call back into `hooks` as the program executes, see `hooks::InterpreterHooks`.
+    ///
+    /// The callbacks are made as instructions are interpreted, so programs are then never compiled
+    /// with the JIT.
+    pub fn with_hooks(mut self, hooks: Box<dyn InterpreterHooks<C> + 'a>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Resume execution from `snapshot` rather than from the start of the program, see
+    /// `snapshot::Snapshot`.
+    ///
+    /// The snapshot must have been taken of the same program with the same configuration,
+    /// otherwise execution fails without executing anything.
+    pub fn with_snapshot(mut self, snapshot: Snapshot<C>) -> Self {
+        self.restore = Some(snapshot);
+        self
+    }
+
     /// Execute the program and return the resulting `ExecutionStatus`.
     ///
     /// The output of the program itself is obtained in other ways, see `ioctx::IoCtx`.
-    pub fn execute(&mut self) -> ExecutionStatus<String> {
-        self.run();
+    pub fn execute(&mut self) -> ExecutionStatus<BfError> { self.run_to_completion() }
+
+    /// Execute a single instruction of the program and return the resulting `ExecutionStatus`.
+    ///
+    /// Calling `step` on a context that has already terminated is a no-op that returns the final
+    /// status again, meaning that a caller can drive execution with a loop like:
+    ///
+    /// ```rust
+    /// # use std::cell::RefCell;
+    /// # use bfi::ioctx::{IoCtx, InMemoryIoCtx};
+    /// # use bfi::interpreter::{ExecutionContext, ExecutionStatus};
+    /// let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    /// let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+++[-]");
+    /// while let ExecutionStatus::InProgress = ectx.step() {}
+    /// assert_eq!(ectx.status, ExecutionStatus::Terminated);
+    /// ```
+    pub fn step(&mut self) -> ExecutionStatus<BfError> {
+        self.step_instruction();
         self.status.clone()
     }
 
-    fn run(&mut self) {
-        loop {
-            match self.status {
-                ExecutionStatus::Terminated => return self.cleanup(),
-                ExecutionStatus::ProgramError(_) | ExecutionStatus::InternalError(_) => return,
-                ExecutionStatus::NotStarted => self.status = ExecutionStatus::InProgress,
-                ExecutionStatus::InProgress => {
-                    match self.program.get(self.program_ptr) {
-                        Some(&cmd) => self.run_command(cmd),
-                        None => self.status = ExecutionStatus::Terminated,
+    /// Like `step`, returning the instruction executed and its index in the program, if one was
+    /// rather than e.g. switching threads at the end of the program.
+    fn step_instruction(&mut self) -> Option<(usize, Instruction)> {
+        self.start();
+        let mut executed = None;
+        if let ExecutionStatus::InProgress = self.status {
+            match self.program.get(self.program_ptr) {
+                Some(&instruction) => {
+                    self.count_step();
+                    self.trace();
+                    log::trace!(
+                        "instruction {} '{}' with the data pointer at {}",
+                        self.program_ptr,
+                        instruction,
+                        self.data_ptr,
+                    );
+                    if self.status == ExecutionStatus::InProgress {
+                        if let Some(profile) = &mut self.profile {
+                            profile.record(self.program_ptr, self.data_ptr);
+                        };
+                        if let Some(stats) = &mut self.stats {
+                            stats.record(&instruction, self.data.len());
+                        };
+                        if let Some(timeline) = &mut self.timeline {
+                            timeline.record(&self.data.cells(), self.data_ptr);
+                        };
+                        if let Some(hooks) = &mut self.hooks {
+                            hooks.on_instruction(
+                                self.program_ptr,
+                                &instruction,
+                                &self.data.cells(),
+                                self.data_ptr,
+                            );
+                        };
+                        self.record_history(instruction);
+                        executed = Some((self.program_ptr, instruction));
+                        self.run_instruction(instruction);
+                        self.delay(instruction);
+                        self.schedule();
                     };
                 },
+                // the program only terminates once every thread has reached its end
+                None => match self.scheduler.take_next() {
+                    Some(thread) => {
+                        self.spare_cells -= thread.cells();
+                        self.switch_thread(thread);
+                    },
+                    None => self.status = ExecutionStatus::Terminated,
+                },
+            };
+            if self.status != ExecutionStatus::InProgress {
+                self.finish_trace();
+                self.finish_output();
+                log::debug!(
+                    "execution {} after {} steps",
+                    difftest::describe(&self.status),
+                    self.steps,
+                );
+                if let Some(stats) = &mut self.stats {
+                    stats.finish(self.data.len());
+                };
+                if let Some(timeline) = &mut self.timeline {
+                    timeline.finish(&self.data.cells(), self.data_ptr);
+                };
+            };
+        };
+        if self.status == ExecutionStatus::InProgress
+            && self.pause_token.as_ref().is_some_and(CancelToken::take)
+        {
+            if !self.repl {
+                self.paused.push("interrupted".to_string());
+            } else {
+                println!("interrupted");
+                self.print_next_instruction();
+                self.run_interactive();
+            };
+        };
+        if self.status == ExecutionStatus::InProgress && self.debugger.has_conditions() {
+            let triggered = self.debugger.check(&self.data.cells(), self.data_ptr, self.origin);
+            if !self.repl {
+                self.paused.extend(triggered);
+            } else if !triggered.is_empty() {
+                for description in triggered.iter() {
+                    println!("{}", description);
+                }
+                self.print_next_instruction();
+                self.run_interactive();
+            };
+        };
+        executed
+    }
+
+    /// Execute every remaining instruction of the program, returning the final `ExecutionStatus`.
+    ///
+    /// May be called after any number of calls to `step` to finish execution.
+    pub fn run_to_completion(&mut self) -> ExecutionStatus<BfError> {
+        #[cfg(feature = "jit")]
+        {
+            if let Some(status) = self.run_jit() {
+                return status;
+            };
+        }
+        loop {
+            match self.step() {
+                ExecutionStatus::NotStarted | ExecutionStatus::InProgress => {},
+                status => return status,
             };
         }
     }
 
-    fn run_command(&mut self, command: Token) {
-        match command {
-            Token::PtrInc => self.pointer_increment(),
-            Token::PtrDec => self.pointer_decrement(),
-            Token::ValInc => self.value_increment(),
-            Token::ValDec => self.value_decrement(),
-            Token::PutChar => self.put_character(),
-            Token::GetChar => self.get_character(),
-            Token::LoopBeg => self.loop_enter(),
-            Token::LoopEnd => self.loop_exit(),
-            Token::DebugDump => eprintln!("{:?}", self),
-            Token::DebugBreakpoint => self.run_interactive(),
-        };
-        match command {
-            Token::LoopEnd => {} // special case that sets the program pointer itself
-            _ => self.program_ptr += 1,
+    /// Stop execution of the program with `ExecutionStatus::Cancelled`, as a cancelled
+    /// `CancelToken` would, after which `step` and `run_to_completion` do nothing.
+    ///
+    /// Has no effect on a program that has already finished.
+    pub fn cancel(&mut self) {
+        if matches!(self.status, ExecutionStatus::NotStarted | ExecutionStatus::InProgress) {
+            self.status = ExecutionStatus::Cancelled;
         };
     }
 
-    fn run_interactive(&mut self) {
-        let program_ptr_before = self.program_ptr;
-        for cmd in repl::ReplInstance::default() {
-            match cmd {
-                repl::ReplResult::Command(cmd) => self.run_command(cmd),
-                repl::ReplResult::Quit => {
-                    self.status = ExecutionStatus::Terminated;
-                    return
+    /// The cells of the tape, starting from its leftmost cell, borrowed unless the
+    /// `config::TapeBackend` stores them out of order. A tape growing to the left grows by blocks,
+    /// such that it may start with cells that the data pointer has not yet reached.
+    pub fn tape(&self) -> Cow<'_, [C]> { self.data.cells() }
+
+    /// Index into `tape` of the current cell.
+    pub fn data_ptr(&self) -> usize { self.data_ptr }
+
+    /// Index into `tape` of the cell the data pointer started on, i.e. the number of cells the tape
+    /// has grown to the left.
+    pub fn origin(&self) -> usize { self.origin }
+
+    /// Index of the current cell counted from `origin`, negative to its left, as shown to the user.
+    pub fn logical_ptr(&self) -> isize { self.data_ptr as isize - self.origin as isize }
+
+    /// The program being executed. Once execution begins this is the optimized program, if
+    /// optimization is enabled.
+    pub fn program(&self) -> &Program { &self.program }
+
+    /// Execution counts of the program so far, if profiling was enabled with `with_profiling`.
+    pub fn profile(&self) -> Option<&Profile> { self.profile.as_ref() }
+
+    /// Statistics of the execution, if they were enabled with `with_stats`.
+    pub fn stats(&self) -> Option<&ExecutionStats> { self.stats.as_ref() }
+
+    /// Samples of the tape, if they were enabled with `with_timeline`.
+    pub fn timeline(&self) -> Option<&Timeline> { self.timeline.as_ref() }
+
+    /// Capture the tape and pointers such that execution can be resumed later with
+    /// `with_snapshot`. Taken once execution has begun, as the program may be optimized then.
+    pub fn snapshot(&self) -> Snapshot<C> {
+        Snapshot {
+            tape: self.data.cells().into_owned(),
+            data_ptr: self.data_ptr,
+            program_ptr: self.program_ptr,
+            program_len: self.program.len(),
+        }
+    }
+
+    /// Index into `program` of the next instruction to execute.
+    pub fn program_ptr(&self) -> usize { self.program_ptr }
+
+    /// Descriptions of the breakpoints hit since the last call, which are only collected if the
+    /// REPL is disabled with `without_repl`.
+    pub fn take_paused(&mut self) -> Vec<String> { std::mem::take(&mut self.paused) }
+
+    /// The I/O context of the program, e.g. to read the output of an `InMemoryIoCtx` while the
+    /// program runs.
+    pub fn io_context(&mut self) -> Option<&mut Box<dyn IoCtx>> { self.ctx.as_deref_mut() }
+
+    /// The next instruction to execute, or `None` if the end of the program has been reached.
+    pub fn next_instruction(&self) -> Option<Instruction> {
+        self.program.get(self.program_ptr).copied()
+    }
+
+    /// Run an interactive session, executing each program entered against the tape of this context
+    /// until the session is exited. The tape and data pointer persist between programs, and a
+    /// program error is reported without ending the session.
+    ///
+    /// The program of this context is not executed.
+    pub fn run_session(&mut self) -> ExecutionStatus<BfError> {
+        self.start();
+        let session = match &self.prompt {
+            Some(prompt) => repl::Session::with_prompt(prompt),
+            None => repl::Session::default(),
+        };
+        for command in session {
+            match command {
+                repl::SessionCommand::Program(program) => self.run_session_program(program),
+                repl::SessionCommand::Reset => self.reset_tape(),
+                repl::SessionCommand::Dump => {
+                    println!("{}", self.render_tape());
                 },
-                repl::ReplResult::Error(e) => {
-                    self.status = ExecutionStatus::InternalError(e);
-                    return
+                repl::SessionCommand::Load(filename) => match std::fs::read_to_string(&filename) {
+                    Ok(source) => self.run_session_program(Program::parse(&source)),
+                    Err(e) => println!("file '{}' could not be read ({})", filename, e),
+                },
+                repl::SessionCommand::Save(filename) => {
+                    if let Err(e) = self.snapshot().save(&filename) {
+                        println!("snapshot could not be saved to '{}' ({})", filename, e);
+                    };
+                },
+                // only the tape is restored, as each program entered runs from its beginning
+                repl::SessionCommand::Restore(filename) => match Snapshot::load(&filename) {
+                    Ok(snapshot) => {
+                        self.data = tape::new_tape(self.config.tape_backend, snapshot.tape);
+                        self.data_ptr = snapshot.data_ptr;
+                    },
+                    Err(e) => println!("snapshot '{}' could not be restored ({})", filename, e),
+                },
+                repl::SessionCommand::Query(expr) => self.print_query(&expr),
+                repl::SessionCommand::Error(e) => {
+                    self.status = ExecutionStatus::InternalError(BfError::ReplError(e));
                 },
             };
+            if self.status != ExecutionStatus::InProgress {
+                return self.status.clone();
+            };
         }
-        self.program_ptr = program_ptr_before;
+        self.status = ExecutionStatus::Terminated;
+        self.status.clone()
     }
 
-    fn cleanup(&mut self) {
-        // Assert that all open loops have been terminated
-        if !self.loop_stack.is_empty() {
-            let e = format!("unmatched '[' at program position(s): {:?}", self.loop_stack);
-            self.status = ExecutionStatus::ProgramError(e.to_string());
+    fn run_session_program(&mut self, program: Program) {
+        let program = if self.config.optimizes() {
+            optimizer::optimize(&program)
+        } else {
+            program
+        };
+        // each program entered is given the full step limit and timeout
+        self.reset_limits();
+        self.run_subprogram(program);
+        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
+            if let Err(e) = ctx_inner.flush_output() {
+                self.status = ExecutionStatus::InternalError(e.into());
+            };
         };
+        if let ExecutionStatus::ProgramError(e) | ExecutionStatus::LimitExceeded(e) = &self.status {
+            println!("error: {}", e);
+            self.status = ExecutionStatus::InProgress;
+        };
+    }
+
+    /// Replace the tape with a blank one as specified by the configuration.
+    fn reset_tape(&mut self) {
+        self.tapes.clear();
+        self.tape_index = 0;
+        self.rows.clear();
+        self.row = 0;
+        self.spare_cells = self.scheduler.cells();
+        self.data = self.blank_tape();
+        self.data_ptr = 0;
+        self.origin = 0;
     }
 
-    fn pointer_increment(&mut self) {
-        self.data_ptr += 1;
-        if self.data.get(self.data_ptr).is_none() {
-            self.data.push(0);
+    fn blank_tape(&self) -> Box<dyn Tape<C>> {
+        let mut data = tape::new_tape(self.config.tape_backend, Vec::new());
+        match self.config.tape_mode {
+            TapeMode::Growable => {
+                let max_len = self.max_tape_len().unwrap_or(usize::MAX);
+                data.reserve(self.config.tape_capacity.min(max_len), max_len);
+                data.grow_right(1);
+            },
+            TapeMode::Fixed | TapeMode::Circular => data.grow_right(self.config.tape_size.max(1)),
         };
+        data
     }
 
-    fn pointer_decrement(&mut self) {
-        match self.data_ptr {
-            0 => self.data.insert(0, 0),
-            _ => self.data_ptr -= 1,
+    /// The value of the current cell.
+    fn cell(&self) -> C { self.data.get(self.data_ptr) }
+
+    /// The cells around the current cell as shown by the REPL, see `repl::render_tape`.
+    fn render_tape(&self) -> String {
+        repl::render_tape(&self.data.cells(), self.data_ptr, self.origin)
+    }
+
+    fn set_cell(&mut self, value: C) { self.data.set(self.data_ptr, value); }
+
+    /// Prepare the program for execution if execution has not yet begun, failing without executing
+    /// anything if its brackets are unbalanced.
+    ///
+    /// Called by `step` and the other ways of executing the program, so only needed to inspect
+    /// the program as it will be executed, i.e. optimized, before executing anything.
+    pub fn start(&mut self) {
+        if let ExecutionStatus::NotStarted = self.status {
+            if let Some(stats) = &mut self.stats {
+                stats.start();
+            };
+            if let Err(e) = self.program.verify() {
+                self.status = ExecutionStatus::ProgramError(e);
+                return;
+            };
+            if self.config.optimizes() {
+                self.program = optimizer::optimize(&self.program);
+            };
+            if let Some(snapshot) = self.restore.take() {
+                if let Err(e) = self.restore(snapshot) {
+                    self.status = ExecutionStatus::ProgramError(e);
+                    return;
+                };
+            };
+            // record the initial state such that breakpoints only trigger once it changes
+            self.debugger.check(&self.data.cells(), self.data_ptr, self.origin);
+            self.reset_limits();
+            log::debug!("executing {} instructions", self.program.len());
+            self.started = Some(Instant::now());
+            self.status = ExecutionStatus::InProgress;
         };
     }
 
-    fn value_increment(&mut self) {
-        self.data[self.data_ptr] = self.data[self.data_ptr].wrapping_add(1);
+    /// Replace the tape and pointers with those of `snapshot`, failing if it was taken of a
+    /// program of a different length.
+    fn restore(&mut self, snapshot: Snapshot<C>) -> Result<(), BfError> {
+        if snapshot.program_len != self.program.len() {
+            return Err(BfError::InvalidSnapshot(format!(
+                "taken of a program of {} instructions rather than {}",
+                snapshot.program_len, self.program.len(),
+            )));
+        };
+        self.data = tape::new_tape(self.config.tape_backend, snapshot.tape);
+        self.data_ptr = snapshot.data_ptr;
+        self.program_ptr = snapshot.program_ptr;
+        Ok(())
     }
 
-    fn value_decrement(&mut self) {
-        self.data[self.data_ptr] = self.data[self.data_ptr].wrapping_sub(1);
+    /// Restart the step count and timeout of the configuration from now.
+    fn reset_limits(&mut self) {
+        self.steps = 0;
+        self.deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
     }
 
-    fn put_character(&mut self) {
-        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
-            (*ctx_inner).write_all(&self.data[self.data_ptr..=self.data_ptr]).unwrap();
+    /// Count an instruction about to be executed, stopping execution if it would exceed the step
+    /// limit or the timeout has passed.
+    fn count_step(&mut self) {
+        self.steps += 1;
+        if self.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            self.status = ExecutionStatus::Cancelled;
+            return;
+        };
+        if let Some(max_steps) = self.config.max_steps {
+            if self.steps > max_steps {
+                self.status = ExecutionStatus::LimitExceeded(BfError::StepLimit(max_steps));
+            };
+        };
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.config.timeout) {
+            if self.steps.is_multiple_of(TIMEOUT_CHECK_INTERVAL) && Instant::now() >= deadline {
+                self.status = ExecutionStatus::LimitExceeded(BfError::Timeout(timeout));
+            };
         };
     }
 
-    fn get_character(&mut self) {
-        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
-            let mut buffer: [u8; 1] = [0; 1];
-            match (*ctx_inner).read(&mut buffer[..]) {
-                Ok(n) if n == 1 => self.data[self.data_ptr] = buffer[0],
-                // do nothing if we got nothing -- note that this decision is an important
-                // contributor towards program behavior
-                Ok(_) => {},
-                Err(e) => self.status = ExecutionStatus::InternalError(format!("{}", e).to_string()),
+    /// Record the instruction about to be executed with the tracer, if there is one.
+    fn trace(&mut self) {
+        if let (Some(tracer), ExecutionStatus::InProgress) = (&mut self.tracer, &self.status) {
+            let cell = self.data.get(self.data_ptr);
+            if let Err(e) = tracer.record(&self.program, self.program_ptr, self.data_ptr, cell) {
+                self.status = ExecutionStatus::InternalError(e.into());
             };
         };
     }
 
-    fn find_loop_end(ptr: usize, program: &[Token]) -> Result<usize, ()> {
-        match program.get(ptr) {
-            Some(Token::LoopEnd) => Ok(ptr),
-            Some(Token::LoopBeg) => ExecutionContext::find_loop_end(ptr + 1, program)
-                .and_then(|i| ExecutionContext::find_loop_end(i + 1, program)),
-            Some(_) => ExecutionContext::find_loop_end(ptr + 1, program),
-            None => Err(()),
-        }
+    /// Write out the rest of the trace once execution has ended.
+    fn finish_trace(&mut self) {
+        if let Some(Err(e)) = self.tracer.as_mut().map(|tracer| tracer.finish()) {
+            if let ExecutionStatus::Terminated = self.status {
+                self.status = ExecutionStatus::InternalError(e.into());
+            };
+        };
     }
 
-    fn loop_enter(&mut self) {
-        match self.data[self.data_ptr] {
-            0 => match ExecutionContext::find_loop_end(self.program_ptr + 1, &self.program) {
-                Ok(i) => self.program_ptr = i,
-                Err(_) => {
-                    let e = format!(
-                        "'[' at program position {} missing corresponding ']'", self.program_ptr
-                    );
-                    self.status = ExecutionStatus::ProgramError(e.to_string());
+    /// Write out a character left incomplete by the bytes written in `IoMode::Utf8` once execution
+    /// has ended.
+    fn finish_output(&mut self) {
+        let text = self.utf8_output.finish();
+        if text.is_empty() {
+            return;
+        };
+        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
+            if let Err(e) = (*ctx_inner).write_all(text.as_bytes()) {
+                if let ExecutionStatus::Terminated = self.status {
+                    self.status = ExecutionStatus::InternalError(e.into());
+                };
+            };
+        };
+    }
+
+    /// Run the program to completion with the JIT, returning `None` without executing anything if
+    /// the program or cell type cannot be compiled.
+    ///
+    /// Instructions that the compiled code hands back are executed with `step`, so a `%` breakpoint
+    /// opens the REPL as usual, as does the `]` handed back once the pause token is set.
+    #[cfg(feature = "jit")]
+    fn run_jit(&mut self) -> Option<ExecutionStatus<BfError>> {
+        if !self.config.jit
+            || self.debugger.has_conditions()
+            || self.config.is_limited()
+            || self.config.delay.is_some()
+            || self.tracer.is_some()
+            || self.profile.is_some()
+            || self.stats.is_some()
+            || self.timeline.is_some()
+            || self.hooks.is_some()
+            || self.journal.is_enabled()
+            || self.program.has_forks()
+            || self.data.as_mut_slice().is_none()
+            || self.config.overflow != OverflowPolicy::Wrap
+            || log::log_enabled!(log::Level::Trace)
+        {
+            return None;
+        };
+        self.start();
+        let compiled = jit::Jit::compile(&self.program, jit::cell_width::<C>()?)?;
+        let unset = AtomicBool::new(false);
+        while let ExecutionStatus::InProgress = self.status {
+            // the tape may have been reallocated by the previous step
+            let tape = match self.data.as_mut_slice() {
+                Some(tape) => tape,
+                None => break,
+            };
+            let (len, tape) = (tape.len(), tape.as_mut_ptr() as *mut u8);
+            let interrupts = [&self.cancel_token, &self.pause_token]
+                .map(|token| token.as_ref().map_or(&unset, CancelToken::flag));
+            let exit = unsafe {
+                compiled.resume(self.program_ptr, tape, len, self.data_ptr, interrupts)
+            };
+            self.data_ptr = match (exit.advanced, self.program.get(exit.instruction)) {
+                // rewind to the last position on the tape, the interpreter completes the move
+                (true, Some(Instruction::Move(n))) | (true, Some(Instruction::Scan(n))) => {
+                    (exit.data_ptr as isize - n) as usize
                 },
-            },
-            _ => self.loop_stack.push(self.program_ptr),
+                _ => exit.data_ptr,
+            };
+            self.program_ptr = exit.instruction;
+            self.step();
         }
+        Some(self.status.clone())
     }
 
-    fn loop_exit(&mut self) {
-        match (self.loop_stack.pop(), self.data[self.data_ptr]) {
-            (Some(_), 0) => self.program_ptr += 1,
-            (Some(ptr_loc), _) => self.program_ptr = ptr_loc,
-            (None, _) => {
-                let e = format!(
-                    "']' at program position {} missing corresponding '['", self.program_ptr
-                );
-                self.status = ExecutionStatus::ProgramError(e.to_string());
+    fn run_instruction(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::Add(n) => self.value_add(n),
+            Instruction::Move(n) => self.pointer_move(n),
+            Instruction::SetZero => self.set_cell(C::default()),
+            Instruction::Scan(n) => self.pointer_scan(n),
+            Instruction::MulAdd(offset, factor) => self.value_mul_add(offset, factor),
+            Instruction::PutChar => self.put_character(),
+            Instruction::GetChar => self.get_character(),
+            Instruction::LoopBeg(end) => self.loop_enter(end),
+            Instruction::LoopEnd(beg) => self.loop_exit(beg),
+            Instruction::ProcBeg(end) => self.procedure_define(end),
+            Instruction::ProcEnd(_) => self.procedure_return(),
+            Instruction::ProcCall => self.procedure_call(),
+            Instruction::Fork => self.fork(),
+            // ends every thread, not just the one running
+            Instruction::End => self.status = ExecutionStatus::Terminated,
+            Instruction::Store => self.storage = self.cell(),
+            Instruction::Load => self.set_cell(self.storage),
+            Instruction::Bitwise(op) => {
+                self.set_cell(op.apply(self.cell(), self.storage));
             },
-        }
+            Instruction::Random => {
+                let value = C::from_byte(self.rng.next_byte());
+                self.set_cell(value);
+            },
+            Instruction::NextTape => self.switch_tape(1),
+            Instruction::PrevTape => self.switch_tape(-1),
+            Instruction::RowUp => self.move_row(-1),
+            Instruction::RowDown => self.move_row(1),
+            Instruction::Clock => {
+                let elapsed = self.started.map_or(0, |started| started.elapsed().as_millis() / 10);
+                self.set_cell(C::from_i64(elapsed as i64));
+            },
+            Instruction::DebugDump => eprintln!("{:?}", self),
+            Instruction::DebugBreakpoint => {
+                let context = self.program.context(self.program_ptr);
+                // only the breakpoints of the program are numbered, see `with_program`
+                let source = match self.subprogram {
+                    false => self.debugger.source(self.program.position(self.program_ptr)),
+                    true => None,
+                };
+                // execution resumes from the instruction following the breakpoint
+                self.program_ptr += 1;
+                match self.config.breakpoint_action {
+                    // disabled or deleted from the REPL
+                    _ if source.is_some_and(|(_, enabled)| !enabled) => {},
+                    BreakpointAction::Repl if self.repl => {
+                        match source {
+                            Some((number, _)) => println!("breakpoint {} at {}", number, context),
+                            None => println!("breakpoint at {}", context),
+                        };
+                        self.run_interactive();
+                    },
+                    BreakpointAction::Repl => {
+                        self.paused.push(format!("breakpoint at {}", context));
+                    },
+                    BreakpointAction::Dump => eprintln!(
+                        "breakpoint at {}\n{}",
+                        context, self.render_tape(),
+                    ),
+                    BreakpointAction::Trace => eprintln!(
+                        "breakpoint at {}: data pointer {}, cell {}",
+                        context, self.logical_ptr(), self.cell(),
+                    ),
+                    BreakpointAction::Ignore => {},
+                };
+            },
+        };
+        match instruction {
+            // special cases that set the program pointer themselves
+            Instruction::LoopBeg(_)
+            | Instruction::LoopEnd(_)
+            | Instruction::ProcBeg(_)
+            | Instruction::ProcEnd(_)
+            | Instruction::ProcCall
+            | Instruction::Fork
+            | Instruction::DebugBreakpoint => {},
+            Instruction::GetChar if mem::take(&mut self.read_abandoned) => {},
+            _ => self.program_ptr += 1,
+        };
     }
-}
-
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use std::cell::RefCell;
-    use crate::ioctx::{InMemoryIoCtx, IoCtx};
 
-    #[test]
-    fn test_pointer_increment() {
-        let mut ectx = ExecutionContext::default();
-        ectx.pointer_increment();
-        assert_eq!(1, ectx.data_ptr);
-        assert_eq!(vec![0, 0], ectx.data);
+    /// Execute a standalone program (e.g. a line entered in the REPL) against the current tape,
+    /// restoring the original program once it completes.
+    fn run_subprogram(&mut self, program: Program) {
+        if let Err(e) = program.verify() {
+            self.status = ExecutionStatus::ProgramError(e);
+            return;
+        };
+        let program_before = mem::replace(&mut self.program, program);
+        let program_ptr_before = mem::replace(&mut self.program_ptr, 0);
+        let subprogram_before = mem::replace(&mut self.subprogram, true);
+        // procedures are defined by index into the program, so they are local to the subprogram
+        let procedures_before = mem::take(&mut self.procedures);
+        let call_stack_before = mem::take(&mut self.call_stack);
+        let scheduler_before = mem::take(&mut self.scheduler);
+        while let ExecutionStatus::InProgress = self.status {
+            match self.program.get(self.program_ptr) {
+                Some(&instruction) => {
+                    self.count_step();
+                    if self.status == ExecutionStatus::InProgress {
+                        self.run_instruction(instruction);
+                    };
+                },
+                None => break,
+            };
+        }
+        self.program = program_before;
+        self.program_ptr = program_ptr_before;
+        self.subprogram = subprogram_before;
+        self.procedures = procedures_before;
+        self.call_stack = call_stack_before;
+        // threads forked by the subprogram end along with it
+        self.spare_cells -= self.scheduler.cells();
+        self.scheduler = scheduler_before;
     }
 
-    #[test]
-    fn test_pointer_decrement() {
-        let mut ectx = ExecutionContext::default();
-        ectx.pointer_decrement();
-        assert_eq!(0, ectx.data_ptr);
-        assert_eq!(vec![0, 0], ectx.data);
+    /// Execute up to `n` instructions of the program from the REPL, stopping early at the end of
+    /// the program or if execution fails.
+    fn run_steps(&mut self, n: usize) {
+        for _ in 0..n {
+            match self.program.get(self.program_ptr) {
+                Some(&instruction) if self.status == ExecutionStatus::InProgress => {
+                    self.record_history(instruction);
+                    self.run_instruction(instruction);
+                },
+                _ => break,
+            };
+        }
     }
 
-    #[test]
-    fn test_find_loop_end() {
-        let program = vec![Token::PtrInc, Token::LoopEnd];
-        assert_eq!(Ok(1), ExecutionContext::find_loop_end(0, &program));
+    /// Undo up to `n` of the most recently executed instructions from the REPL, returning the
+    /// number undone, which is fewer than `n` if the history runs out.
+    fn undo_steps(&mut self, n: usize) -> usize {
+        for undone in 0..n {
+            match self.journal.undo(&mut *self.data) {
+                Some((program_ptr, data_ptr, grown_left)) => {
+                    self.program_ptr = program_ptr;
+                    self.data_ptr = data_ptr;
+                    self.origin -= grown_left;
+                },
+                None => return undone,
+            };
+        }
+        n
     }
 
-    #[test]
-    fn test_input_output() {
-        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
-        let mut ictx_ref = ictx.borrow_mut();
-        let val = b"value";
-        ictx_ref.write_input(val).unwrap();
-        let status = ExecutionContext::new(ictx_ref, ",[.[-],]").execute();
-        let mut buf = [0u8; 5];
-        let output = ictx.borrow_mut().read_output(&mut buf);
-        assert_eq!(output.unwrap(), 5usize);
-        assert_eq!(val, &buf);
-        assert_eq!(status, ExecutionStatus::<String>::Terminated);
+    /// Remember the state that `instruction` is about to change, if history is enabled.
+    fn record_history(&mut self, instruction: Instruction) {
+        if !self.journal.is_enabled() {
+            return;
+        };
+        let cell = match instruction {
+            Instruction::Add(_)
+            | Instruction::SetZero
+            | Instruction::GetChar
+            | Instruction::Load
+            | Instruction::Bitwise(_) => Some(self.data_ptr),
+            Instruction::MulAdd(offset, _) => {
+                let target = self.data_ptr as isize + offset;
+                let len = self.data.len() as isize;
+                let target = match (self.config.tape_mode, self.config.bounds) {
+                    (TapeMode::Circular, _) | (TapeMode::Fixed, BoundsPolicy::Wrap) => {
+                        target.rem_euclid(len)
+                    },
+                    (TapeMode::Fixed, BoundsPolicy::Clamp) => target.clamp(0, len - 1),
+                    // cells added by growing the tape are removed by undoing the growth instead
+                    (TapeMode::Fixed, BoundsPolicy::Error) | (TapeMode::Growable, _) => target,
+                };
+                usize::try_from(target).ok()
+            },
+            // breakpoints and dumps change nothing
+            Instruction::DebugDump | Instruction::DebugBreakpoint => return,
+            // the procedure table, call stack, threads, storage cell, random state, and other
+            // tapes and rows are not recorded, so history ends here
+            Instruction::ProcBeg(_)
+            | Instruction::ProcEnd(_)
+            | Instruction::ProcCall
+            | Instruction::Fork
+            | Instruction::Store
+            | Instruction::Random
+            | Instruction::NextTape
+            | Instruction::PrevTape
+            | Instruction::RowUp
+            | Instruction::RowDown => {
+                self.journal.clear();
+                return;
+            },
+            _ => None,
+        };
+        self.journal.record(self.program_ptr, self.data_ptr, &*self.data, cell);
     }
 
-    #[test]
-    fn test_missing_close_bracket() {
-        for token in vec![Token::LoopBeg, Token::LoopEnd] {
-            let mut ectx = ExecutionContext::default();
-            ectx.program = vec![token];
-            let status = ectx.execute();
-            match status {
-                ExecutionStatus::ProgramError(_) => {},
-                _ => panic!(),
-            };
+    /// Index into `data` of the cell at `index` counted from `origin`, growing a growable tape to
+    /// reach it if necessary and allowed by the memory limit. Returns `None` if the cell is not on
+    /// the tape.
+    fn cell_at(&mut self, index: isize) -> Option<usize> {
+        let growable = self.config.tape_mode == TapeMode::Growable;
+        let past_start = self.origin as isize + index;
+        if growable && past_start < 0 {
+            self.grow_left(past_start.unsigned_abs());
+        };
+        let physical = usize::try_from(self.origin as isize + index).ok()?;
+        let len = self.data.len();
+        if growable && physical >= len && self.reserve_tape(physical + 1) {
+            self.data.grow_right(physical + 1 - len);
         };
+        Some(physical).filter(|&i| i < self.data.len())
     }
 
-    #[test]
-    fn test_debug_fmt() {
-        let mut ectx = ExecutionContext::default();
-        ectx.program = vec![Token::DebugDump];
+    /// Open the REPL, from which execution continues at the instruction at `program_ptr`.
+    fn run_interactive(&mut self) {
+        for cmd in repl::ReplInstance::default() {
+            match cmd {
+                repl::ReplResult::Program(program) => {
+                    self.journal.clear();
+                    self.run_subprogram(program);
+                },
+                repl::ReplResult::Print => {
+                    println!("{}", self.render_tape());
+                },
+                repl::ReplResult::Break(breakpoint) => {
+                    println!("added breakpoint {}", self.debugger.add(breakpoint));
+                },
+                repl::ReplResult::ListBreakpoints => println!("{}", self.debugger),
+                repl::ReplResult::Query(expr) => self.print_query(&expr),
+                repl::ReplResult::EnableBreakpoint(number, enabled) => {
+                    if let Err(e) = self.debugger.enable(number, enabled) {
+                        println!("{}", e);
+                    };
+                },
+                repl::ReplResult::DeleteBreakpoint(number) => {
+                    if let Err(e) = self.debugger.delete(number) {
+                        println!("{}", e);
+                    };
+                },
+                // changes made from the REPL cannot be undone, so they end the history
+                repl::ReplResult::SetCell(index, value) => match self.cell_at(index) {
+                    // truncating `value` preserves it modulo the width of the cell
+                    Some(i) => {
+                        self.data.set(i, C::default().wrapping_offset(value as i32));
+                        self.journal.clear();
+                    },
+                    None => println!("cell {} is off the end of the tape", index),
+                },
+                repl::ReplResult::SetPointer(index) => match self.cell_at(index) {
+                    Some(i) => {
+                        self.data_ptr = i;
+                        self.journal.clear();
+                    },
+                    None => println!("cell {} is off the end of the tape", index),
+                },
+                repl::ReplResult::Step(n) => {
+                    self.run_steps(n);
+                    if self.status != ExecutionStatus::InProgress {
+                        return;
+                    };
+                    self.print_next_instruction();
+                },
+                repl::ReplResult::Undo(n) => {
+                    let undone = self.undo_steps(n);
+                    if !self.journal.is_enabled() {
+                        println!("no history to undo, see --history");
+                    } else if undone < n {
+                        println!("undid {} of {} instructions", undone, n);
+                    };
+                    self.print_next_instruction();
+                },
+                repl::ReplResult::Save(filename) => {
+                    if let Err(e) = self.snapshot().save(&filename) {
+                        println!("snapshot could not be saved to '{}' ({})", filename, e);
+                    };
+                },
+                repl::ReplResult::Load(filename) => {
+                    match Snapshot::load(&filename).and_then(|s| self.restore(s)) {
+                        Ok(()) => self.journal.clear(),
+                        Err(e) => println!("snapshot '{}' could not be loaded ({})", filename, e),
+                    };
+                },
+                repl::ReplResult::Quit => {
+                    self.status = ExecutionStatus::Terminated;
+                    return
+                },
+                repl::ReplResult::Error(e) => {
+                    self.status = ExecutionStatus::InternalError(BfError::ReplError(e));
+                    return
+                },
+            };
+        }
+        // changes made from the REPL do not trigger breakpoints
+        self.debugger.check(&self.data.cells(), self.data_ptr, self.origin);
+    }
+
+    /// Print the value of `expr` over the tape, or why it has none.
+    fn print_query(&self, expr: &Expr) {
+        match expr.eval(&self.data.cells(), self.data_ptr, self.origin) {
+            Ok(value) => println!("{}", value),
+            Err(e) => println!("{}", e),
+        };
+    }
+
+    /// Print the next instruction to execute from the REPL, alongside its index, position, and
+    /// location in the source.
+    fn print_next_instruction(&self) {
+        match self.next_instruction() {
+            Some(instruction) => println!(
+                "{:>6} {:>6}  {} at {}",
+                self.program_ptr,
+                self.program.position(self.program_ptr),
+                instruction,
+                self.program.context(self.program_ptr),
+            ),
+            None => println!("end of program"),
+        };
+    }
+
+    fn pointer_move(&mut self, n: isize) {
+        let target = self.data_ptr as isize + n;
+        let len = self.data.len() as isize;
+        if 0 <= target && target < len {
+            self.data_ptr = target as usize;
+            return;
+        };
+        match (self.config.tape_mode, target < 0) {
+            (TapeMode::Growable, true) if self.grow_left(target.unsigned_abs()) => {
+                // the data pointer moved right along with its cell as the tape grew
+                self.data_ptr = (self.data_ptr as isize + n) as usize;
+            },
+            (TapeMode::Growable, false) if self.reserve_tape(target as usize + 1) => {
+                self.data.grow_right(target as usize + 1 - self.data.len());
+                self.data_ptr = target as usize;
+            },
+            (TapeMode::Growable, _) => {
+                // unwrap is safe as the tape can only fail to grow with a memory limit
+                let e = BfError::MemoryLimit(self.config.max_memory.unwrap());
+                self.status = ExecutionStatus::LimitExceeded(e);
+            },
+            (TapeMode::Circular, _) | (TapeMode::Fixed, _) => {
+                match self.config.bounded_target(target, len as usize) {
+                    Ok(data_ptr) => self.data_ptr = data_ptr,
+                    Err(end) => self.program_error(|context| BfError::TapeOverflow(end, context)),
+                };
+            },
+        };
+    }
+
+    /// Add at least `n` cells to the start of the tape, returning false without growing it if that
+    /// would exceed the memory limit of the configuration. The data pointer and origin move right
+    /// along with the cells they point at.
+    fn grow_left(&mut self, n: usize) -> bool {
+        let len = self.data.len();
+        let max_len = self.max_tape_len().unwrap_or(usize::MAX);
+        // at least double the tape where the memory limit allows, such that a program travelling
+        // left moves each cell a logarithmic rather than linear number of times
+        let grown = n.max(len.min(max_len.saturating_sub(len)));
+        if !self.reserve_tape(len + grown) {
+            return false;
+        };
+        self.data.grow_left(grown);
+        if let Some(profile) = &mut self.profile {
+            profile.grow_left(grown);
+        };
+        if let Some(timeline) = &mut self.timeline {
+            timeline.grow_left(grown);
+        };
+        self.journal.grow_left(grown);
+        self.data_ptr += grown;
+        self.origin += grown;
+        true
+    }
+
+    /// Make room for the tape to grow to `len` cells, returning false without growing it if that
+    /// would exceed the memory limit of the configuration.
+    fn reserve_tape(&mut self, len: usize) -> bool {
+        let max_len = match self.max_tape_len() {
+            Some(max_len) => max_len,
+            None => return true,
+        };
+        if len > max_len {
+            return false;
+        };
+        self.data.reserve(len, max_len);
+        true
+    }
+
+    /// Number of cells the tape can grow to within the memory limit of the configuration, if any,
+    /// which it shares with the tapes not in use, see `spare_cells`.
+    fn max_tape_len(&self) -> Option<usize> {
+        self.config.max_memory
+            .map(|max_memory| (max_memory / mem::size_of::<C>()).saturating_sub(self.spare_cells))
+    }
+
+    /// Stop execution if the tape in use no longer fits within the memory limit of the
+    /// configuration alongside the tapes not in use, e.g. as another is created or copied.
+    fn check_memory_limit(&mut self) {
+        if self.max_tape_len().is_some_and(|max_len| self.data.len() > max_len) {
+            // unwrap is safe as there is only a maximum length with a memory limit
+            let e = BfError::MemoryLimit(self.config.max_memory.unwrap());
+            self.status = ExecutionStatus::LimitExceeded(e);
+        };
+    }
+
+    /// Free the memory the tape in use allocated ahead to grow into as it is about to be set
+    /// aside, if there is a memory limit for that memory to count against.
+    fn release_data(&mut self) {
+        if self.config.max_memory.is_some() {
+            self.data.shrink_to_fit();
+        };
+    }
+
+    fn pointer_scan(&mut self, n: isize) {
+        while !self.cell().is_zero() {
+            self.pointer_move(n);
+            if self.status != ExecutionStatus::InProgress {
+                return;
+            };
+            // a scan can loop forever on a circular tape, so each move counts as a step
+            self.count_step();
+        }
+    }
+
+    /// Fail with an error caused by the current instruction, built from its context in the source.
+    fn program_error(&mut self, error: impl FnOnce(SourceContext) -> BfError) {
+        let e = error(self.program.context(self.program_ptr));
+        self.status = ExecutionStatus::ProgramError(e);
+    }
+
+    fn value_add(&mut self, n: i32) {
+        self.set_cell_or_overflow(self.config.overflow.add(self.cell(), n as i64));
+    }
+
+    /// Store the result of arithmetic on the current cell, failing if it overflowed as
+    /// configured by `InterpreterConfig::overflow`.
+    fn set_cell_or_overflow(&mut self, value: Option<C>) {
+        match value {
+            Some(value) => self.set_cell(value),
+            None => self.program_error(BfError::CellOverflow),
+        };
+    }
+
+    fn value_mul_add(&mut self, offset: isize, factor: i32) {
+        let value = self.cell();
+        if value.is_zero() {
+            return;
+        };
+        // travel to the target and back such that the tape is extended or wrapped as necessary
+        self.pointer_move(offset);
+        if self.status != ExecutionStatus::InProgress {
+            return;
+        };
+        self.set_cell_or_overflow(self.config.overflow.mul_add(self.cell(), value, factor));
+        if self.status != ExecutionStatus::InProgress {
+            return;
+        };
+        self.pointer_move(-offset);
+    }
+
+    /// Sleep after `instruction` as configured by `InterpreterConfig::delay`, flushing output
+    /// first such that it is seen before the pause.
+    fn delay(&mut self, instruction: Instruction) {
+        let delayed = !self.config.delay_output_only || instruction == Instruction::PutChar;
+        let delay = match self.config.delay {
+            Some(delay) if delayed && self.status == ExecutionStatus::InProgress => delay,
+            _ => return,
+        };
+        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
+            if let Err(e) = (*ctx_inner).flush_output() {
+                self.status = ExecutionStatus::InternalError(e.into());
+            };
+        };
+        thread::sleep(delay);
+    }
+
+    fn put_character(&mut self) {
+        let value = self.cell();
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_output(value);
+        };
+        if let Some(ctx_inner) = self.ctx.iter_mut().next() {
+            let result = match self.config.io_mode {
+                IoMode::Bytes => (*ctx_inner).write_all(&[value.to_byte()]),
+                IoMode::Numeric => writeln!(*ctx_inner, "{}", value),
+                IoMode::Utf8 if mem::size_of::<C>() == 1 => {
+                    (*ctx_inner).write_all(self.utf8_output.push(value.to_byte()).as_bytes())
+                },
+                IoMode::Utf8 => write!(*ctx_inner, "{}", utf8::char_from_i64(value.to_i64())),
+            };
+            if let Err(e) = result {
+                self.status = ExecutionStatus::InternalError(e.into());
+            };
+        };
+    }
+
+    fn get_character(&mut self) {
+        if self.config.io_mode == IoMode::Numeric {
+            return self.get_number();
+        };
+        loop {
+            let ctx_inner = match self.ctx.iter_mut().next() {
+                Some(ctx_inner) => ctx_inner,
+                None => return,
+            };
+            let mut buffer: [u8; 1] = [0; 1];
+            let result = match self.config.io_mode {
+                IoMode::Utf8 if mem::size_of::<C>() == 1 => {
+                    self.utf8_input.next_byte(&mut **ctx_inner).map(|b| b.map(C::from_byte))
+                },
+                IoMode::Utf8 => utf8::read_char(&mut **ctx_inner)
+                    .map(|c| c.map(|c| C::from_i64(i64::from(u32::from(c))))),
+                _ => (*ctx_inner)
+                    .read(&mut buffer[..])
+                    .map(|n| (n == 1).then(|| C::from_byte(buffer[0]))),
+            };
+            match result {
+                Ok(Some(value)) => self.read_value(value),
+                Ok(None) => self.end_of_input(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    if !self.abandon_read() {
+                        continue;
+                    };
+                },
+                Err(e) => self.status = ExecutionStatus::InternalError(e.into()),
+            };
+            return;
+        }
+    }
+
+    /// Give up on a read by `,` interrupted by a signal if the signal set the cancel or pause
+    /// token, e.g. Ctrl-C while the program waits for input, returning whether it did. The `,` then
+    /// runs again once execution continues.
+    fn abandon_read(&mut self) -> bool {
+        self.read_abandoned = [&self.cancel_token, &self.pause_token]
+            .iter()
+            .any(|token| token.as_ref().is_some_and(CancelToken::is_cancelled));
+        self.read_abandoned
+    }
+
+    /// Read the next whitespace-delimited word of input into the current cell as an integer,
+    /// consuming the whitespace character after it.
+    fn get_number(&mut self) {
+        let mut word = Vec::new();
+        let mut buffer: [u8; 1] = [0; 1];
+        loop {
+            let read = match self.ctx.iter_mut().next() {
+                Some(ctx_inner) => (*ctx_inner).read(&mut buffer[..]),
+                None => return,
+            };
+            match read {
+                Ok(1) if buffer[0].is_ascii_whitespace() && word.is_empty() => {},
+                Ok(1) if !buffer[0].is_ascii_whitespace() => word.push(buffer[0]),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    if self.abandon_read() {
+                        return;
+                    };
+                },
+                Err(e) => {
+                    self.status = ExecutionStatus::InternalError(e.into());
+                    return;
+                },
+            };
+        }
+        if word.is_empty() {
+            return self.end_of_input();
+        };
+        let word = String::from_utf8_lossy(&word).into_owned();
+        match word.parse::<i64>() {
+            Ok(n) => match self.config.overflow.convert(n) {
+                Some(value) => self.read_value(value),
+                None => self.program_error(BfError::CellOverflow),
+            },
+            Err(_) => self.program_error(|context| BfError::InvalidNumber(word, context)),
+        };
+    }
+
+    /// Store a value read by `,` in the current cell.
+    fn read_value(&mut self, value: C) {
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_input(Some(value));
+        };
+        self.set_cell(value);
+    }
+
+    /// Update the current cell as configured once `,` finds no more input.
+    fn end_of_input(&mut self) {
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_input(None);
+        };
+        // by default the cell is left alone -- note that this decision is an important
+        // contributor towards program behavior
+        self.set_cell(self.config.eof.apply(self.cell()));
+    }
+
+    fn loop_enter(&mut self, end: Option<usize>) {
+        match (end, self.cell().is_zero()) {
+            (Some(i), true) => self.program_ptr = i + 1,
+            (Some(_), false) => {
+                if let Some(hooks) = &mut self.hooks {
+                    hooks.on_loop_enter(self.program_ptr);
+                };
+                self.program_ptr += 1;
+            },
+            (None, _) => self.program_error(BfError::UnmatchedOpenBracket),
+        }
+    }
+
+    fn loop_exit(&mut self, beg: Option<usize>) {
+        match (beg, self.cell().is_zero()) {
+            (Some(_), true) => self.program_ptr += 1,
+            (Some(i), false) => self.program_ptr = i + 1,
+            (None, _) => self.program_error(BfError::UnmatchedCloseBracket),
+        }
+    }
+
+    /// Define the procedure identified by the current cell, replacing any earlier definition, and
+    /// skip over its body.
+    fn procedure_define(&mut self, end: Option<usize>) {
+        match end {
+            Some(i) => {
+                self.procedures.insert(self.cell().to_i64(), self.program_ptr);
+                self.program_ptr = i + 1;
+            },
+            None => self.program_error(BfError::UnmatchedOpenParen),
+        }
+    }
+
+    /// Return to the instruction following the innermost procedure call. Reached without a call in
+    /// progress, execution simply continues.
+    fn procedure_return(&mut self) {
+        self.program_ptr = match self.call_stack.pop() {
+            Some(i) => i,
+            None => self.program_ptr + 1,
+        };
+    }
+
+    /// Call the procedure identified by the current cell, failing if it has not been defined or
+    /// the call would exceed the call depth limit of the configuration.
+    fn procedure_call(&mut self) {
+        let id = self.cell().to_i64();
+        match self.procedures.get(&id) {
+            Some(_) if self.call_stack.len() >= self.config.max_call_depth => {
+                let e = BfError::CallDepth(self.config.max_call_depth);
+                self.status = ExecutionStatus::LimitExceeded(e);
+            },
+            Some(&beg) => {
+                self.call_stack.push(self.program_ptr + 1);
+                self.program_ptr = beg + 1;
+            },
+            None => self.program_error(|context| BfError::UndefinedProcedure(id, context)),
+        }
+    }
+
+    /// Switch to the tape `offset` tapes after the one in use, wrapping around, creating it if it
+    /// has not been used yet.
+    fn switch_tape(&mut self, offset: isize) {
+        let count = self.config.tapes.max(1);
+        if self.tapes.len() < count {
+            self.tapes.resize_with(count, || None);
+        };
+        let target = (self.tape_index as isize + offset).rem_euclid(count as isize) as usize;
+        if target == self.tape_index {
+            return;
+        };
+        self.release_data();
+        self.spare_cells += spare_cells(&*self.data);
+        let (data, data_ptr, origin) = match self.tapes[target].take() {
+            Some(tape) => {
+                self.spare_cells -= spare_cells(&*tape.0);
+                tape
+            },
+            None => (self.blank_tape(), 0, 0),
+        };
+        self.tapes[self.tape_index] = Some((
+            mem::replace(&mut self.data, data),
+            mem::replace(&mut self.data_ptr, data_ptr),
+            mem::replace(&mut self.origin, origin),
+        ));
+        self.tape_index = target;
+        self.check_memory_limit();
+    }
+
+    /// Move the data pointer `offset` rows down the grid, keeping its column, growing the row moved
+    /// to if it does not yet reach the column.
+    fn move_row(&mut self, offset: i64) {
+        let target = self.row + offset;
+        self.release_data();
+        self.spare_cells += spare_cells(&*self.data);
+        let (mut data, origin) = match self.rows.remove(&target) {
+            Some(row) => {
+                self.spare_cells -= spare_cells(&*row.0);
+                row
+            },
+            None => (self.blank_tape(), self.origin),
+        };
+        // a row saved while another tape of the multitape extension was in use may be ahead
+        data.grow_left(self.origin.saturating_sub(origin));
+        let previous = mem::replace(&mut self.data, data);
+        self.rows.insert(self.row, (previous, self.origin));
+        self.row = target;
+        self.check_memory_limit();
+        if self.status != ExecutionStatus::InProgress {
+            return;
+        };
+        if self.cell_at(self.logical_ptr()).is_none() {
+            // unwrap is safe as a row can only fail to grow with a memory limit
+            let e = BfError::MemoryLimit(self.config.max_memory.unwrap());
+            self.status = ExecutionStatus::LimitExceeded(e);
+        };
+    }
+
+    /// Fork a new thread one cell to the right on a copy of the tape, setting its cell to one and
+    /// the current cell of the running thread to zero. The new thread runs once the threads
+    /// already waiting have each had a turn.
+    fn fork(&mut self) {
+        let mut parent = Thread {
+            tape: self.data.cells().into_owned(),
+            data_ptr: self.data_ptr,
+            program_ptr: self.program_ptr + 1,
+            call_stack: self.call_stack.clone(),
+        };
+        parent.tape[parent.data_ptr] = C::default();
+        // the running thread becomes the new thread, then switches back to the original
+        self.pointer_move(1);
+        if self.status != ExecutionStatus::InProgress {
+            return;
+        };
+        self.set_cell(C::default().wrapping_inc());
+        self.program_ptr += 1;
+        self.release_data();
+        let child = self.switch_thread(parent);
+        self.spare_cells += child.cells();
+        self.scheduler.spawn(child);
+        self.check_memory_limit();
+    }
+
+    /// Give the next waiting thread its turn, if any thread is waiting.
+    fn schedule(&mut self) {
+        if self.status != ExecutionStatus::InProgress || self.scheduler.is_empty() {
+            return;
+        };
+        self.release_data();
+        let current = self.switch_thread(Thread::default());
+        self.spare_cells += current.cells();
+        let next = self.scheduler.rotate(current);
+        self.spare_cells -= next.cells();
+        self.switch_thread(next);
+    }
+
+    /// Make `thread` the running thread, returning the thread that was running before.
+    fn switch_thread(&mut self, thread: Thread<C>) -> Thread<C> {
+        // the history belongs to the thread that was running
+        self.journal.clear();
+        let tape = tape::new_tape(self.config.tape_backend, thread.tape);
+        Thread {
+            tape: mem::replace(&mut self.data, tape).into_cells(),
+            data_ptr: mem::replace(&mut self.data_ptr, thread.data_ptr),
+            program_ptr: mem::replace(&mut self.program_ptr, thread.program_ptr),
+            call_stack: mem::replace(&mut self.call_stack, thread.call_stack),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::time::Duration;
+    use crate::config::{EofBehavior, OverflowPolicy};
+    use crate::ioctx::{InMemoryIoCtx, IoCtx, ReadWriteIoCtx};
+    use crate::token::{Location, Token};
+
+    #[test]
+    fn test_pointer_increment() {
+        let mut ectx: ExecutionContext = ExecutionContext::default();
+        ectx.pointer_move(1);
+        assert_eq!(1, ectx.data_ptr);
+        assert_eq!(vec![0, 0], *ectx.data.cells());
+    }
+
+    #[test]
+    fn test_pointer_decrement() {
+        let mut ectx: ExecutionContext = ExecutionContext::default();
+        ectx.pointer_move(-1);
+        assert_eq!(0, ectx.data_ptr);
+        assert_eq!(vec![0, 0], *ectx.data.cells());
+    }
+
+    #[test]
+    fn test_fixed_tape() {
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 2,
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config.clone());
+        ectx.pointer_move(1);
+        assert_eq!(1, ectx.data_ptr);
+        ectx.pointer_move(1);
+        match ectx.status {
+            ExecutionStatus::ProgramError(_) => {},
+            _ => panic!(),
+        };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config);
+        ectx.pointer_move(-1);
+        match ectx.status {
+            ExecutionStatus::ProgramError(_) => {},
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn test_bounds_policy() {
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 3,
+            bounds: BoundsPolicy::Wrap,
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config.clone());
+        ectx.pointer_move(-1);
+        assert_eq!(2, ectx.data_ptr);
+        ectx.pointer_move(4);
+        assert_eq!(0, ectx.data_ptr);
+        let config = InterpreterConfig { bounds: BoundsPolicy::Clamp, ..config };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config.clone());
+        ectx.pointer_move(-5);
+        assert_eq!(0, ectx.data_ptr);
+        ectx.pointer_move(10);
+        assert_eq!(2, ectx.data_ptr);
+        assert_eq!(ectx.status, ExecutionStatus::NotStarted);
+        // the loop runs once, as moving back does not undo a clamped move
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+[-<<+>>]"),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!((&*ectx.tape(), ectx.data_ptr()), (&[1, 0, 0][..], 2));
+    }
+
+    #[test]
+    fn test_circular_tape() {
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Circular,
+            tape_size: 3,
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config);
+        ectx.pointer_move(-1);
+        assert_eq!(2, ectx.data_ptr);
+        ectx.pointer_move(1);
+        assert_eq!(0, ectx.data_ptr);
+        assert_eq!(vec![0, 0, 0], *ectx.data.cells());
+        ectx.pointer_move(-4);
+        assert_eq!(2, ectx.data_ptr);
+    }
+
+    #[test]
+    fn test_pointer_move_growable() {
+        let mut ectx: ExecutionContext = ExecutionContext::default();
+        ectx.pointer_move(-3);
+        assert_eq!(0, ectx.data_ptr);
+        assert_eq!(vec![0, 0, 0, 0], *ectx.data.cells());
+        ectx.pointer_move(5);
+        assert_eq!(5, ectx.data_ptr);
+        assert_eq!(6, ectx.data.len());
+    }
+
+    #[test]
+    fn test_tape_capacity() {
+        let config = InterpreterConfig { tape_capacity: 1000, ..InterpreterConfig::default() };
+        let ectx: ExecutionContext = ExecutionContext::default().with_config(config.clone());
+        assert_eq!((ectx.data.len(), ectx.data.capacity() >= 1000), (1, true));
+        // the memory limit caps the allocation
+        let config = InterpreterConfig { max_memory: Some(8), ..config };
+        let ectx: ExecutionContext = ExecutionContext::default().with_config(config);
+        assert_eq!((ectx.data.len(), ectx.data.capacity()), (1, 8));
+    }
+
+    #[test]
+    fn test_optimized_idioms() {
+        // copies the first cell two cells to the left, then scans right to the end of the data
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+++>+>+<<[-<<+>>]>[>]"),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(vec![0, 3, 0, 0, 1, 1, 0], *ectx.data.cells());
+        // the tape doubled as it grew to the left, leaving a cell to spare
+        assert_eq!((6, 3), (ectx.data_ptr, ectx.origin));
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("++[>+<-]"),
+            ..ExecutionContext::default()
+        }.with_config(InterpreterConfig { optimize: false, ..InterpreterConfig::default() });
+        let mut executed = ectx.into_iter();
+        let loops: Vec<usize> = executed
+            .by_ref()
+            .filter(|(_, instruction)| matches!(instruction, Instruction::LoopEnd(_)))
+            .map(|(program_ptr, _)| program_ptr)
+            .collect();
+        assert_eq!(loops, vec![7, 7]);
+        assert_eq!(executed.next(), None);
+        let ectx = executed.into_inner();
+        assert_eq!((&ectx.status, &*ectx.tape()), (&ExecutionStatus::Terminated, &[0, 2][..]));
+        // optimized loops are executed as a single instruction
+        let ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("++[-]"),
+            ..ExecutionContext::default()
+        };
+        let executed: Vec<(usize, Instruction)> = ectx.into_iter().collect();
+        assert_eq!(executed, vec![(0, Instruction::Add(2)), (1, Instruction::SetZero)]);
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let depth = 100_000;
+        let source = format!("+{}-{}+", "[".repeat(depth), "]".repeat(depth));
+        for &optimize in [true, false].iter() {
+            let mut ectx: ExecutionContext = ExecutionContext {
+                program: Program::parse(&source),
+                ..ExecutionContext::default()
+            }.with_config(InterpreterConfig { optimize, ..InterpreterConfig::default() });
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated, "{}", optimize);
+            assert_eq!(ectx.tape()[0], 1, "{}", optimize);
+        }
+    }
+
+    #[test]
+    fn test_tape_backends() {
+        for &backend in [TapeBackend::Vec, TapeBackend::Deque, TapeBackend::Sparse].iter() {
+            let config = InterpreterConfig {
+                tape_backend: backend,
+                ..InterpreterConfig::default()
+            };
+            let mut ectx: ExecutionContext = ExecutionContext {
+                program: Program::parse("<+<++>>>>+++[-<<<+>>>]"),
+                ..ExecutionContext::default()
+            }.with_config(config);
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+            assert_eq!(&*ectx.tape(), &[0, 2, 4, 0, 0, 0], "{:?}", backend);
+            assert_eq!((ectx.data_ptr(), ectx.logical_ptr()), (5, 2), "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn test_wide_cells() {
+        let mut ectx: ExecutionContext<u16> = ExecutionContext {
+            program: Program::parse("-"),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(vec![u16::MAX], *ectx.data.cells());
+    }
+
+    #[test]
+    fn test_input_output() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ictx_ref = ictx.borrow_mut();
+        let val = b"value";
+        ictx_ref.write_input(val).unwrap();
+        let status = ExecutionContext::new(ictx_ref, ",[.[-],]").execute();
+        let mut buf = [0u8; 5];
+        let output = ictx.borrow_mut().read_output(&mut buf);
+        assert_eq!(output.unwrap(), 5usize);
+        assert_eq!(val, &buf);
+        assert_eq!(status, ExecutionStatus::Terminated);
+    }
+
+    #[test]
+    fn test_eof_behavior() {
+        for (eof, expected) in [
+            (EofBehavior::Unchanged, 7u16),
+            (EofBehavior::Zero, 0),
+            (EofBehavior::MinusOne, u16::MAX),
+        ] {
+            let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+            let config = InterpreterConfig { eof, ..InterpreterConfig::default() };
+            let mut ectx = ExecutionContext::<u16>::with_cells(ictx.borrow_mut(), "+++++++,")
+                .with_config(config);
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+            assert_eq!(vec![expected], *ectx.data.cells());
+        }
+    }
+
+    #[test]
+    fn test_numeric_io() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        ictx.borrow_mut().write_input(b"  12\n-1 300").unwrap();
+        let config = InterpreterConfig { io_mode: IoMode::Numeric, ..InterpreterConfig::default() };
+        let status = ExecutionContext::<i32>::with_cells(ictx.borrow_mut(), ",+.,.,.,.")
+            .with_config(config.clone())
+            .execute();
+        assert_eq!(status, ExecutionStatus::Terminated);
+        let mut buf = [0u8; 16];
+        let n = ictx.borrow_mut().read_output(&mut buf).unwrap();
+        // the cell is left unchanged at the end of input
+        assert_eq!(&buf[..n], b"13\n-1\n300\n300\n");
+
+        ictx.borrow_mut().write_input(b"300 4x").unwrap();
+        let mut ectx = ExecutionContext::<u8>::with_cells(ictx.borrow_mut(), ",>,")
+            .with_config(config.clone());
+        match ectx.execute() {
+            ExecutionStatus::ProgramError(BfError::InvalidNumber(word, _)) => {
+                assert_eq!(word, "4x");
+            },
+            status => panic!("{:?}", status),
+        };
+        assert_eq!(*ectx.data.cells(), vec![44, 0]);
+
+        // a number past the bounds of the cell overflows like arithmetic does
+        for (overflow, expected) in [(OverflowPolicy::Saturate, 255), (OverflowPolicy::Error, 0)] {
+            let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+            ictx.borrow_mut().write_input(b"7 300 ").unwrap();
+            let mut ectx = ExecutionContext::<u8>::with_cells(ictx.borrow_mut(), ",>,")
+                .with_config(InterpreterConfig { overflow, ..config.clone() });
+            let status = ectx.execute();
+            assert_eq!(*ectx.data.cells(), vec![7, expected]);
+            match status {
+                ExecutionStatus::Terminated if overflow == OverflowPolicy::Saturate => {},
+                ExecutionStatus::ProgramError(BfError::CellOverflow(context)) => {
+                    assert_eq!(overflow, OverflowPolicy::Error);
+                    assert_eq!(context.location.column, 3);
+                },
+                status => panic!("{:?}", status),
+            };
+        }
+    }
+
+    #[test]
+    fn test_run_steps() {
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+>++<-"),
+            ..ExecutionContext::default()
+        };
+        ectx.start();
+        ectx.run_steps(3);
+        assert_eq!(ectx.next_instruction(), Some(Instruction::Move(-1)));
+        assert_eq!(&*ectx.tape(), &[1, 2]);
+        ectx.run_steps(10);
+        assert_eq!(ectx.next_instruction(), None);
+        assert_eq!(ectx.status, ExecutionStatus::InProgress);
+        assert_eq!(&*ectx.tape(), &[0, 2]);
+    }
+
+    #[test]
+    fn test_cell_at() {
+        let mut ectx: ExecutionContext = ExecutionContext::default();
+        assert_eq!(ectx.cell_at(3), Some(3));
+        assert_eq!(ectx.tape().len(), 4);
+        // cells to the left of the start have negative indices
+        assert_eq!(ectx.cell_at(-2), Some(2));
+        assert_eq!((ectx.origin(), ectx.data_ptr(), ectx.logical_ptr()), (4, 4, 0));
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 2,
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext::default().with_config(config);
+        assert_eq!(ectx.cell_at(1), Some(1));
+        assert_eq!(ectx.cell_at(2), None);
+        assert_eq!(ectx.cell_at(-1), None);
+    }
+
+    #[test]
+    fn test_read_write_streams() {
+        struct Closed;
+        impl std::io::Write for Closed {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+        }
+        let streams = ReadWriteIoCtx::new(&b"ab"[..], Closed);
+        let ictx = RefCell::new(Box::new(streams) as Box<dyn IoCtx>);
+        let status = ExecutionContext::new(ictx.borrow_mut(), ",>,.").execute();
+        let e = BfError::IoError(std::io::ErrorKind::BrokenPipe, "closed".to_string());
+        assert_eq!(status, ExecutionStatus::InternalError(e));
+        let streams = ReadWriteIoCtx::new(&b"abc"[..], Vec::new());
+        assert_eq!(streams.get_ref(), (&&b"abc"[..], &Vec::new()));
+        assert_eq!(streams.into_inner().0, b"abc");
+    }
+
+    #[test]
+    fn test_missing_close_bracket() {
+        for token in [Token::LoopBeg, Token::LoopEnd] {
+            let mut ectx: ExecutionContext = ExecutionContext {
+                program: Program::from_tokens(&[token]),
+                ..ExecutionContext::default()
+            };
+            let status = ectx.execute();
+            match status {
+                ExecutionStatus::ProgramError(_) => {},
+                _ => panic!(),
+            };
+        };
+    }
+
+    #[test]
+    fn test_unbalanced_program_not_executed() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+.[");
+        let expected = BfError::UnmatchedOpenBracket(SourceContext {
+            file: None,
+            location: Location { line: 1, column: 3 },
+            excerpt: Some("1 | +.[\n  |   ^".to_string()),
+        });
+        assert_eq!(ectx.execute(), ExecutionStatus::ProgramError(expected));
+        assert_eq!(&*ectx.tape(), &[0]);
+        drop(ectx);
+        assert_eq!(ictx.borrow_mut().read_output(&mut [0; 1]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_limits() {
+        let config = InterpreterConfig { max_steps: Some(10), ..InterpreterConfig::default() };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+++++[-]"),
+            ..ExecutionContext::default()
+        }.with_config(config.clone());
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+[]"),
+            ..ExecutionContext::default()
+        }.with_config(config.clone());
+        let expected = BfError::StepLimit(10);
+        assert_eq!(ectx.execute(), ExecutionStatus::LimitExceeded(expected.clone()));
+        // a scan that never finds a zero cell
+        let circular = InterpreterConfig {
+            tape_mode: TapeMode::Circular,
+            tape_size: 2,
+            ..config
+        };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+>+[>]"),
+            ..ExecutionContext::default()
+        }.with_config(circular);
+        assert_eq!(ectx.execute(), ExecutionStatus::LimitExceeded(expected));
+        let timeout = InterpreterConfig {
+            timeout: Some(std::time::Duration::from_millis(10)),
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+[]"),
+            ..ExecutionContext::default()
+        }.with_config(timeout);
+        let expected = BfError::Timeout(std::time::Duration::from_millis(10));
+        assert_eq!(ectx.execute(), ExecutionStatus::LimitExceeded(expected));
+    }
+
+    #[test]
+    fn test_procedures() {
+        let parse = |s| Program::parse_dialect(s, &crate::dialect::PBrain);
+        let mut ectx: ExecutionContext =
+            ExecutionContext { program: parse("+(>++<)::"), ..ExecutionContext::default() };
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(&*ectx.tape(), &[1, 4]);
+        let config = InterpreterConfig { max_call_depth: 5, ..InterpreterConfig::default() };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: parse("+(:):"),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        assert_eq!(ectx.execute(), ExecutionStatus::LimitExceeded(BfError::CallDepth(5)));
+        let mut ectx: ExecutionContext =
+            ExecutionContext { program: parse("+:"), ..ExecutionContext::default() };
+        match ectx.execute() {
+            ExecutionStatus::ProgramError(BfError::UndefinedProcedure(1, _)) => {},
+            status => panic!("{:?}", status),
+        };
+    }
+
+    #[test]
+    fn test_fork() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let program = Program::parse_dialect("+Y.>.", &crate::dialect::BrainFork);
+        let status = ExecutionContext::<u8>::with_program(ictx.borrow_mut(), program).execute();
+        assert_eq!(status, ExecutionStatus::Terminated);
+        // the threads take turns, the new thread first
+        let mut buf = [0u8; 5];
+        assert_eq!(ictx.borrow_mut().read_output(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], &[1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ext1() {
+        let extended = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Ext1],
+        );
+        // 6 is stored, then shifted left, and, or, and xor with 3, not, and shifted right
+        let source = "++++++$>!{>+++&>+++|>+++^>!~>!}@+";
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse_dialect(source, &extended),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(&*ectx.tape(), &[6, 12, 2, 7, 5, 249, 3]);
+    }
+
+    #[test]
+    fn test_random() {
+        let extended = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Random],
+        );
+        let tape = |seed| {
+            let mut ectx: ExecutionContext = ExecutionContext {
+                program: Program::parse_dialect("?>?>?>?", &extended),
+                ..ExecutionContext::default()
+            }.with_config(InterpreterConfig { seed, ..InterpreterConfig::default() });
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+            ectx.tape().to_vec()
+        };
+        assert_eq!(tape(1), tape(1));
+        assert_ne!(tape(1), tape(2));
+    }
+
+    #[test]
+    fn test_multitape() {
+        let extended = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Multitape],
+        );
+        // each tape keeps its own cells and data pointer, and switching wraps around
+        let config = InterpreterConfig { tapes: 3, ..InterpreterConfig::default() };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse_dialect("+>++/+++\\\\>>+//-", &extended),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!((&*ectx.tape(), ectx.data_ptr()), (&[2][..], 0));
+        let spare = |i: usize| ectx.tapes[i].as_ref().map(|(tape, ptr, _)| (tape.cells(), *ptr));
+        assert_eq!(spare(0), Some((vec![1, 2].into(), 1)));
+        assert_eq!(spare(2), Some((vec![0, 0, 1].into(), 2)));
+    }
+
+    #[test]
+    fn test_grid() {
+        let extended = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Grid],
+        );
+        // the first row grows to the left while the second is not in use, keeping the columns of
+        // the grid lined up
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse_dialect(">+_++^<<+_", &extended),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(ectx.row, 1);
+        assert_eq!((&*ectx.tape(), ectx.logical_ptr()), (&[0, 0, 0, 2][..], -1));
+        assert_eq!((&*ectx.rows[&0].0.cells(), ectx.rows[&0].1), (&[0, 1, 0, 1][..], 2));
+    }
+
+    #[test]
+    fn test_clock() {
+        let extended = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Ext1, crate::dialect::Extension::Time],
+        );
+        // store the time, then loop until the time xor the stored time is no longer zero
+        let source = ";$>+[>;^[[-]<->]<]";
+        let config = InterpreterConfig {
+            timeout: Some(Duration::from_secs(5)),
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse_dialect(source, &extended),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        let started = Instant::now();
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert!(started.elapsed().as_millis() < 1000);
+        assert_eq!(&ectx.tape()[1..], &[0, 0]);
+    }
+
+    #[test]
+    fn test_delay() {
+        let run = |delay_output_only| {
+            let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+            let config = InterpreterConfig {
+                delay: Some(Duration::from_millis(20)),
+                delay_output_only,
+                ..InterpreterConfig::default()
+            };
+            let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+>+>+.")
+                .with_config(config);
+            let started = Instant::now();
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+            started.elapsed()
+        };
+        assert!(run(false) >= Duration::from_millis(120));
+        let output_only = run(true);
+        assert!(output_only >= Duration::from_millis(20));
+        assert!(output_only < Duration::from_millis(120));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let config = InterpreterConfig {
+            max_memory: Some(9),
+            optimize: false,
+            ..InterpreterConfig::default()
+        };
+        let mut ectx: ExecutionContext<u16> = ExecutionContext {
+            program: Program::parse(">><<<"),
+            ..ExecutionContext::default()
+        }.with_config(config.clone());
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        assert_eq!(&*ectx.tape(), &[0, 0, 0, 0]);
+        assert!(ectx.data.capacity() <= 4);
+        let mut ectx: ExecutionContext<u16> = ExecutionContext {
+            program: Program::parse("+[>+]"),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        let expected = BfError::MemoryLimit(9);
+        assert_eq!(ectx.execute(), ExecutionStatus::LimitExceeded(expected));
+        assert_eq!(&*ectx.tape(), &[1, 1, 1, 1]);
+        assert_eq!(ectx.cell_at(4), None);
+    }
+
+    #[test]
+    fn test_shared_memory_limit() {
+        // every row, tape, and thread takes up memory from the same limit rather than its own
+        let grid = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Grid],
+        );
+        let multitape = crate::dialect::Extended::new(
+            Box::new(crate::dialect::BrainFuck),
+            vec![crate::dialect::Extension::Multitape],
+        );
+        let config = InterpreterConfig {
+            max_memory: Some(16),
+            tapes: 3,
+            ..InterpreterConfig::default()
+        };
+        let run = |source, dialect: &dyn crate::dialect::Dialect| {
+            let mut ectx: ExecutionContext = ExecutionContext {
+                program: Program::parse_dialect(source, dialect),
+                ..ExecutionContext::default()
+            }.with_config(config.clone());
+            ectx.execute()
+        };
+        let expected = ExecutionStatus::LimitExceeded(BfError::MemoryLimit(16));
+        assert_eq!(run("+[_+]", &grid), expected);
+        assert_eq!(run(">>>>>>/>>>>>>/>>>>>>", &multitape), expected);
+        assert_eq!(run("+[Y]", &crate::dialect::BrainFork), expected);
+        // rows within the limit between them are fine
+        assert_eq!(run(">>>>>>_+^+", &grid), ExecutionStatus::Terminated);
+    }
+
+    #[test]
+    fn test_profiling() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+++[<+>-]<").with_profiling();
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        let profile = ectx.profile().unwrap();
+        // the loop is optimized to a multiplication and zeroing of the cell
+        assert_eq!(profile.instruction_counts(), &[1, 1, 1, 1]);
+        // the tape grows to the left during the multiplication, moving the counts along with it
+        assert_eq!(profile.cell_accesses(), &[0, 4]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+++[<+>-]<>>>").with_stats();
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        let stats = ectx.stats().unwrap();
+        // the loop is optimized to a multiplication and zeroing of the cell, and the moves after
+        // it are combined
+        assert_eq!(stats.instructions, 4);
+        assert_eq!(stats.counts, vec![("add", 1), ("muladd", 1), ("zero", 1), ("move", 1)]);
+        // the tape grows to the left during the multiplication, then twice to the right
+        assert_eq!(stats.peak_tape_size, 4);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+++[<+>-]<").with_timeline();
+        assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+        // a row before each of the four instructions left by the optimizer, and one once
+        // execution ends
+        let timeline = ectx.timeline().unwrap();
+        assert_eq!(timeline.len(), 5);
+        assert!(timeline.to_svg().contains("cell -1"));
+    }
+
+    #[test]
+    fn test_undo_steps() {
+        let config = InterpreterConfig { history: 4, ..InterpreterConfig::default() };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+++[<+>-]<,"),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        ectx.start();
+        ectx.run_steps(6);
+        assert_eq!((&*ectx.tape(), ectx.data_ptr(), ectx.program_ptr()), (&[3, 0][..], 0, 5));
+        assert_eq!(ectx.undo_steps(3), 3);
+        assert_eq!((&*ectx.tape(), ectx.data_ptr(), ectx.program_ptr()), (&[3, 3][..], 1, 2));
+        // the first instruction has been forgotten, and undoing the multiplication also takes
+        // back the cell it added to the tape
+        assert_eq!(ectx.undo_steps(5), 1);
+        assert_eq!((&*ectx.tape(), ectx.data_ptr(), ectx.program_ptr()), (&[3][..], 0, 1));
+        ectx.run_steps(6);
+        assert_eq!(&*ectx.tape(), &[3, 0]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let config = InterpreterConfig { max_steps: Some(4), ..InterpreterConfig::default() };
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+++[>++<-]>+"),
+            ..ExecutionContext::default()
+        }.with_config(config);
+        assert!(matches!(ectx.execute(), ExecutionStatus::LimitExceeded(_)));
+        let snapshot = ectx.snapshot();
+        assert_eq!(snapshot.program_ptr, 4);
+        let mut resumed: ExecutionContext = ExecutionContext {
+            program: Program::parse("+++[>++<-]>+"),
+            ..ExecutionContext::default()
+        }.with_snapshot(snapshot.clone());
+        assert_eq!(resumed.execute(), ExecutionStatus::Terminated);
+        assert_eq!((&*resumed.tape(), resumed.data_ptr()), (&[0, 7][..], 1));
+        let mut different: ExecutionContext = ExecutionContext {
+            program: Program::parse("+"),
+            ..ExecutionContext::default()
+        }.with_snapshot(snapshot);
+        assert!(matches!(
+            different.execute(),
+            ExecutionStatus::ProgramError(BfError::InvalidSnapshot(_)),
+        ));
+        assert_eq!(&*different.tape(), &[0]);
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+\n  +]"),
+            ..ExecutionContext::default()
+        };
+        let expected = "']' at line 2, col 4 has no matching '['\n2 |   +]\n  |    ^";
+        match ectx.execute() {
+            ExecutionStatus::ProgramError(e @ BfError::UnmatchedCloseBracket(_)) => {
+                assert_eq!(e.to_string(), expected);
+            },
+            status => panic!("unexpected status {:?}", status),
+        };
+    }
+
+    #[test]
+    fn test_step() {
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("+>++"),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.step(), ExecutionStatus::InProgress);
+        assert_eq!(vec![1], *ectx.data.cells());
+        assert_eq!(ectx.step(), ExecutionStatus::InProgress);
+        assert_eq!(1, ectx.data_ptr);
+        assert_eq!(ectx.run_to_completion(), ExecutionStatus::Terminated);
+        assert_eq!(vec![1, 2], *ectx.data.cells());
+        assert_eq!(ectx.step(), ExecutionStatus::Terminated);
+    }
+
+    #[test]
+    fn test_inspect_and_cancel() {
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::parse("++>+[]"),
+            ..ExecutionContext::default()
+        };
+        assert_eq!(ectx.next_instruction(), Some(Instruction::Add(1)));
+        ectx.step();
+        // the program is optimized when execution begins
+        assert_eq!(ectx.program().len(), 5);
+        assert_eq!(ectx.next_instruction(), Some(Instruction::Move(1)));
+        assert_eq!(&*ectx.tape(), &[2]);
+        while ectx.program_ptr() < 3 {
+            ectx.step();
+        }
+        assert_eq!((&*ectx.tape(), ectx.data_ptr()), (&[2, 1][..], 1));
+        assert_eq!(ectx.next_instruction(), Some(Instruction::LoopBeg(Some(4))));
+        ectx.cancel();
+        assert_eq!(ectx.run_to_completion(), ExecutionStatus::Cancelled);
+        assert_eq!(ectx.program_ptr(), 3);
+    }
+
+    #[test]
+    fn test_without_repl() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+%+.")
+            .with_breakpoint("cell[0] == 2".parse().unwrap())
+            .without_repl();
+        ectx.start();
+        assert_eq!(ectx.program().len(), 4);
+        ectx.step();
+        assert!(ectx.take_paused().is_empty());
+        assert_eq!(ectx.step(), ExecutionStatus::InProgress);
+        assert_eq!(ectx.take_paused(), vec!["breakpoint at line 1, col 2".to_string()]);
+        ectx.step();
+        assert_eq!(ectx.take_paused(), vec!["breakpoint 2: cell[0] == 2".to_string()]);
+        assert_eq!(ectx.run_to_completion(), ExecutionStatus::Terminated);
+        let mut buf = [0u8; 2];
+        assert_eq!(ectx.io_context().unwrap().read_output(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 2);
+    }
+
+    #[test]
+    fn test_breakpoint_action() {
+        let actions = [BreakpointAction::Dump, BreakpointAction::Trace, BreakpointAction::Ignore];
+        for &action in &actions {
+            let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+            let config = InterpreterConfig { breakpoint_action: action, ..Default::default() };
+            // the REPL is left enabled, so this would block on stdin if it was opened
+            let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+%+.").with_config(config);
+            assert_eq!(ectx.execute(), ExecutionStatus::Terminated);
+            assert!(ectx.take_paused().is_empty());
+            let mut buf = [0u8; 2];
+            assert_eq!(ectx.io_context().unwrap().read_output(&mut buf).unwrap(), 1);
+            assert_eq!(buf[0], 2);
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let status = run(ictx.borrow_mut(), "++++++++[>++++++++<-]>+.");
+        assert_eq!(status, ExecutionStatus::Terminated);
+        let mut buf = [0u8; 1];
+        ictx.borrow_mut().read_output(&mut buf).unwrap();
+        assert_eq!(b"A", &buf);
+    }
+
+    #[test]
+    fn test_debug_fmt() {
+        let mut ectx: ExecutionContext = ExecutionContext {
+            program: Program::from_tokens(&[Token::DebugDump]),
+            ..ExecutionContext::default()
+        };
         let status = ectx.execute();
-        assert_eq!(status, ExecutionStatus::<String>::Terminated);
+        assert_eq!(status, ExecutionStatus::Terminated);
     }
 }
@@ -2,6 +2,8 @@
 
 use std::io::{self, Read, Write};
 use std::default::Default;
+#[cfg(unix)]
+use std::time::Duration;
 
 
 /// Trait to read and write inputs and outputs to a BF program.
@@ -66,19 +68,25 @@ impl Write for dyn IoCtx {
 
 /// Basic context using stdin and stdout implementations for `Read`, `Write`.
 pub struct StdIoCtx {
-    input: io::Stdin,
+    input: Box<dyn Read>,
     output: io::Stdout,  // bf does not support stderr
 }
 
-impl Default for StdIoCtx {
-    fn default() -> Self {
+impl StdIoCtx {
+    /// Context writing to stdout but reading program input from `input` rather than stdin, e.g.
+    /// when input is predetermined by a file.
+    pub fn with_input(input: Box<dyn Read>) -> Self {
         Self {
-            input: io::stdin(),
+            input,
             output: io::stdout(),
         }
     }
 }
 
+impl Default for StdIoCtx {
+    fn default() -> Self { Self::with_input(Box::new(io::stdin())) }
+}
+
 /// # Panics
 ///
 /// Note that `write_input` and `read_output` are not implemented here, meaning the default
@@ -91,9 +99,12 @@ impl IoCtx for StdIoCtx {
 
 
 /// `StdIoCtx` that flushes the output stream on every call to `write_output`.
+#[derive(Default)]
 pub struct UnbufferedStdIoCtx { ctx: StdIoCtx }
-impl Default for UnbufferedStdIoCtx {
-    fn default() -> Self { Self { ctx: StdIoCtx::default() } }
+
+impl UnbufferedStdIoCtx {
+    /// See `StdIoCtx::with_input`.
+    pub fn with_input(input: Box<dyn Read>) -> Self { Self { ctx: StdIoCtx::with_input(input) } }
 }
 
 /// # Panics
@@ -112,14 +123,11 @@ impl IoCtx for UnbufferedStdIoCtx {
 
 
 /// Struct wrapper for u8 vector implementing `Read`, `Write` traits
+#[derive(Default)]
 struct ByteBuf {
     buf: Vec<u8>,
 }
 
-impl Default for ByteBuf {
-    fn default() -> Self { Self { buf: Vec::new() } }
-}
-
 impl Read for ByteBuf {
     fn read(&mut self, input_buf: &mut [u8]) -> io::Result<usize> {
         // slice of input buffer for which Read is implemented
@@ -140,20 +148,12 @@ impl Write for ByteBuf {
 /// individually support both `Read`, `Write` for use when program output is intended to be
 /// consumed by the process executing it, rather than a separate program or a human, both of which
 /// are better served by the `StdIoCtx`.
+#[derive(Default)]
 pub struct InMemoryIoCtx {
     input: ByteBuf,
     output: ByteBuf,
 }
 
-impl Default for InMemoryIoCtx {
-    fn default() -> Self {
-        Self {
-            input: ByteBuf::default(),
-            output: ByteBuf::default(),
-        }
-    }
-}
-
 impl IoCtx for InMemoryIoCtx {
     fn read_input(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.input.read(buf) }
     fn write_input(&mut self, buf: &[u8]) -> io::Result<usize> { self.input.write(buf) }
@@ -161,3 +161,225 @@ impl IoCtx for InMemoryIoCtx {
     fn read_output(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.output.read(buf) }
     fn flush_output(&mut self) -> io::Result<()> { self.output.flush() }
 }
+
+
+/// `IoCtx` reading program input from any `Read` and writing program output to any `Write`, e.g.
+/// files, sockets, or mock streams.
+///
+/// ```
+/// # use std::cell::RefCell;
+/// # use std::io::Cursor;
+/// # use bfi::ioctx::{IoCtx, ReadWriteIoCtx};
+/// # use bfi::interpreter;
+/// let ictx = ReadWriteIoCtx::new(Cursor::new(b"hi".to_vec()), Vec::new());
+/// let ictx = RefCell::new(Box::new(ictx) as Box<dyn IoCtx>);
+/// interpreter::run(ictx.borrow_mut(), ",.,.");
+/// ```
+#[derive(Default)]
+pub struct ReadWriteIoCtx<R: Read, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: Read, W: Write> ReadWriteIoCtx<R, W> {
+    pub fn new(input: R, output: W) -> Self { Self { input, output } }
+
+    /// Get references to the underlying input and output streams.
+    pub fn get_ref(&self) -> (&R, &W) { (&self.input, &self.output) }
+
+    /// Unwrap this `ReadWriteIoCtx`, returning the underlying input and output streams.
+    pub fn into_inner(self) -> (R, W) { (self.input, self.output) }
+}
+
+/// # Panics
+///
+/// The default implementations for `write_input` and `read_output` are used here which panic
+/// unconditionally.
+impl<R: Read, W: Write> IoCtx for ReadWriteIoCtx<R, W> {
+    fn read_input(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.input.read(buf) }
+    fn write_output(&mut self, buf: &[u8]) -> io::Result<usize> { self.output.write(buf) }
+    fn flush_output(&mut self) -> io::Result<()> { self.output.flush() }
+}
+
+
+/// `Read` adapter copying every byte read from `input` to `recording`, e.g. to record the input
+/// consumed by a program such that it can be replayed exactly later.
+///
+/// Bytes are written to `recording` as soon as they are read, so an unbuffered recording (like a
+/// `File`) holds every byte consumed even if the program never finishes.
+///
+/// ```
+/// # use std::io::Read;
+/// # use bfi::ioctx::RecordingReader;
+/// let mut reader = RecordingReader::new(&b"abc"[..], Vec::new());
+/// let mut buf = [0u8; 2];
+/// reader.read_exact(&mut buf).unwrap();
+/// assert_eq!(reader.into_inner().1, b"ab");
+/// ```
+pub struct RecordingReader<R: Read, W: Write> {
+    input: R,
+    recording: W,
+}
+
+impl<R: Read, W: Write> RecordingReader<R, W> {
+    pub fn new(input: R, recording: W) -> Self { Self { input, recording } }
+
+    /// Unwrap this `RecordingReader`, returning the underlying input and recording.
+    pub fn into_inner(self) -> (R, W) { (self.input, self.recording) }
+}
+
+impl<R: Read, W: Write> Read for RecordingReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n_read = self.input.read(buf)?;
+        self.recording.write_all(&buf[..n_read])?;
+        Ok(n_read)
+    }
+}
+
+
+/// Terminal settings to restore should the program be interrupted while `RawStdin` is in use.
+#[cfg(unix)]
+static ORIGINAL_TERMIOS: std::sync::OnceLock<libc::termios> = std::sync::OnceLock::new();
+
+
+/// Stdin with the terminal switched out of line-buffered mode, such that every key pressed is
+/// read as soon as it is pressed rather than once Enter is, and without being echoed. Programs
+/// like games can then react to each key on its own.
+///
+/// The terminal is restored when the `RawStdin` is dropped, or if the process is killed by Ctrl-C
+/// before then. A handler for `SIGINT` installed later, e.g. to cancel the program gracefully,
+/// should call the handler it replaces before killing the process to keep the latter. Stdin is
+/// read as it is when it is not a terminal, e.g. when piped.
+#[cfg(unix)]
+pub struct RawStdin {
+    stdin: io::Stdin,
+    original: Option<libc::termios>,
+    /// Action for `SIGINT` before `RawStdin` replaced it with `restore_and_interrupt`.
+    previous: Option<libc::sigaction>,
+    timeout: Option<Duration>,
+}
+
+#[cfg(unix)]
+impl RawStdin {
+    pub fn new() -> io::Result<Self> {
+        let stdin = io::stdin();
+        // safe as tcgetattr fills in the termios or fails without touching it
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Ok(Self { stdin, original: None, previous: None, timeout: None });
+        };
+        let mut raw = original;
+        // unlike the raw mode of `cfmakeraw`, signals and output processing are left alone such
+        // that Ctrl-C still interrupts the program and newlines still return the cursor
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        let _ = ORIGINAL_TERMIOS.set(original);
+        // safe as the actions are fully initialized, and sigaction fills in the previous one
+        let previous = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction =
+                restore_and_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            libc::sigaction(libc::SIGINT, &action, &mut previous);
+            previous
+        };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        };
+        Ok(Self { stdin, original: Some(original), previous: Some(previous), timeout: None })
+    }
+
+    /// Give up on a read once no key has been pressed for `timeout`, see `TimeoutStdin`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[cfg(unix)]
+impl Read for RawStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.timeout {
+            Some(timeout) => read_stdin_with_timeout(buf, timeout),
+            None => self.stdin.read(buf),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawStdin {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original) };
+        };
+        // the handler is put back only if still in place, as one installed since may chain to it
+        if let Some(previous) = &self.previous {
+            unsafe {
+                let mut current: libc::sigaction = std::mem::zeroed();
+                libc::sigaction(libc::SIGINT, std::ptr::null(), &mut current);
+                let handler = restore_and_interrupt as extern "C" fn(libc::c_int);
+                if current.sa_sigaction == handler as libc::sighandler_t {
+                    libc::sigaction(libc::SIGINT, previous, std::ptr::null_mut());
+                };
+            };
+        };
+    }
+}
+
+
+/// Stdin that gives up on a read once nothing has arrived for `timeout`, reading no bytes as if
+/// input had ended. Later reads wait for input again, such that a program can poll for input
+/// between doing other work, e.g. in an event loop.
+///
+/// Bytes are read from stdin directly rather than through the buffer of `io::Stdin`, as input
+/// sitting in that buffer would not be seen by the wait.
+#[cfg(unix)]
+pub struct TimeoutStdin {
+    timeout: Duration,
+}
+
+#[cfg(unix)]
+impl TimeoutStdin {
+    pub fn new(timeout: Duration) -> Self { Self { timeout } }
+}
+
+#[cfg(unix)]
+impl Read for TimeoutStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        read_stdin_with_timeout(buf, self.timeout)
+    }
+}
+
+
+/// Wait up to `timeout` for stdin to become readable, then read from it, returning zero bytes
+/// read if it did not.
+#[cfg(unix)]
+fn read_stdin_with_timeout(buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+    let mut fd = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+    match unsafe { libc::poll(&mut fd, 1, timeout) } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => return Ok(0),
+        _ => {},
+    };
+    match unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) } {
+        -1 => Err(io::Error::last_os_error()),
+        n => Ok(n as usize),
+    }
+}
+
+
+/// Handler for `SIGINT` while `RawStdin` is in use, restoring the terminal before the process is
+/// interrupted as it would have been without the handler.
+#[cfg(unix)]
+extern "C" fn restore_and_interrupt(signal: libc::c_int) {
+    if let Some(original) = ORIGINAL_TERMIOS.get() {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original) };
+    };
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    };
+}
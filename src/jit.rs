@@ -0,0 +1,559 @@
+//! Just-in-time compilation of programs to native machine code, enabled by the `jit` feature.
+//!
+//! Only the arithmetic, pointer movement, and looping instructions are compiled. Whenever the
+//! compiled code reaches an instruction it cannot handle itself (I/O, debugging, unmatched
+//! brackets, or moving the data pointer off of the current tape) it returns the index of that
+//! instruction to the caller, which executes it with the interpreter and resumes the compiled code
+//! at the next instruction. The semantics of the tape are thus exactly those of the interpreter.
+//! Each loop also hands its `]` back once the program is cancelled or paused, see
+//! `cancel::CancelToken`, such that a program that never does I/O can still be interrupted.
+//!
+//! Code generation is currently only implemented for x86-64 on Unix-like systems. On other
+//! platforms `Jit::compile` returns `None` and the interpreter is used for the entire program.
+
+use std::any::TypeId;
+use std::sync::atomic::AtomicBool;
+
+use crate::program::Program;
+
+
+/// Width in bytes of the cells of type `C`, if the JIT supports them.
+pub fn cell_width<C: 'static>() -> Option<usize> {
+    let id = TypeId::of::<C>();
+    if id == TypeId::of::<u8>() {
+        Some(1)
+    } else if id == TypeId::of::<u16>() {
+        Some(2)
+    } else if id == TypeId::of::<u32>() || id == TypeId::of::<i32>() {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+
+/// The reason that compiled code returned control to the caller.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Exit {
+    /// Index of the instruction the interpreter should execute next.
+    pub instruction: usize,
+
+    /// Position of the data pointer. When `advanced` is set, the pointer has already been moved by
+    /// the `Move` or `Scan` at `instruction` and is out of the bounds of the tape.
+    pub data_ptr: usize,
+
+    /// Whether the instruction at `instruction` was partially executed, see `data_ptr`.
+    pub advanced: bool,
+}
+
+
+#[cfg(all(target_arch = "x86_64", unix))]
+pub use self::x86_64::Jit;
+
+#[cfg(not(all(target_arch = "x86_64", unix)))]
+pub use self::unsupported::Jit;
+
+
+#[cfg(not(all(target_arch = "x86_64", unix)))]
+mod unsupported {
+    use super::*;
+
+    /// Placeholder for platforms without a code generator.
+    pub struct Jit {}
+
+    impl Jit {
+        pub fn compile(_: &Program, _: usize) -> Option<Self> { None }
+
+        /// # Safety
+        ///
+        /// Never callable as `compile` never succeeds.
+        pub unsafe fn resume(
+            &self,
+            _: usize,
+            _: *mut u8,
+            _: usize,
+            _: usize,
+            _: [&AtomicBool; 2],
+        ) -> Exit
+        {
+            unreachable!()
+        }
+    }
+}
+
+
+#[cfg(all(target_arch = "x86_64", unix))]
+mod x86_64 {
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::mem;
+    use std::ptr;
+
+    use super::*;
+    use crate::program::Instruction;
+
+    /// Machine state shared with the compiled code, which reads the first three fields on entry
+    /// and writes back `data_ptr` before returning. The flags are read at the end of each loop
+    /// iteration.
+    #[repr(C)]
+    struct State {
+        tape: *mut u8,
+        data_ptr: usize,
+        len: usize,
+        interrupts: [*const AtomicBool; 2],
+    }
+
+    type EntryFn = unsafe extern "C" fn(*mut State, *const u8) -> u64;
+
+    /// Jump destinations that may not have been emitted yet when the jump is.
+    #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+    enum Target {
+        Instruction(usize),
+        /// Out of line exit at an instruction, and whether the data pointer has been advanced.
+        Exit(usize, bool),
+        Epilogue,
+    }
+
+    /// A program compiled to native code.
+    ///
+    /// Registers during execution: `rbx` holds the address of the `State`, `r12` the address of
+    /// the tape, `r13` the data pointer (in cells), and `r14` the length of the tape (in cells).
+    pub struct Jit {
+        code: ExecutableBuffer,
+        labels: Vec<usize>,
+    }
+
+    impl Jit {
+        /// Compile a program operating on cells `cell_width` bytes wide (1, 2, or 4).
+        ///
+        /// Returns `None` if the code cannot be compiled or made executable.
+        pub fn compile(program: &Program, cell_width: usize) -> Option<Self> {
+            let scale: u8 = match cell_width {
+                1 => 0,
+                2 => 1,
+                4 => 2,
+                _ => return None,
+            };
+            let mut asm = Assembler {
+                code: Vec::new(),
+                width: cell_width,
+                scale,
+                patches: Vec::new(),
+            };
+            asm.prologue();
+
+            let mut labels: Vec<usize> = Vec::with_capacity(program.len() + 1);
+            for (i, instruction) in program.instructions().iter().enumerate() {
+                labels.push(asm.code.len());
+                asm.instruction(i, *instruction);
+            }
+            labels.push(asm.code.len());
+            asm.mov_rax(Assembler::exit_code(program.len(), false));
+
+            let mut targets: HashMap<Target, usize> = HashMap::new();
+            targets.insert(Target::Epilogue, asm.code.len());
+            asm.epilogue();
+            let exits: Vec<Target> = asm.patches.iter()
+                .map(|&(_, target)| target)
+                .filter(|target| matches!(target, Target::Exit(_, _)))
+                .collect();
+            for exit in exits {
+                if let (false, Target::Exit(i, advanced)) = (targets.contains_key(&exit), exit) {
+                    targets.insert(exit, asm.code.len());
+                    asm.mov_rax(Assembler::exit_code(i, advanced));
+                    asm.jump(0xE9, Target::Epilogue);
+                };
+            }
+            for (i, &label) in labels.iter().enumerate() {
+                targets.insert(Target::Instruction(i), label);
+            }
+            asm.patch(&targets)?;
+
+            Some(Self { code: ExecutableBuffer::new(&asm.code)?, labels })
+        }
+
+        /// Run the compiled code starting from instruction `start` until it returns control, which
+        /// it does at the end of a loop iteration once either of `interrupts` is set.
+        ///
+        /// # Safety
+        ///
+        /// `tape` must point to `len` cells of the width the program was compiled for, `data_ptr`
+        /// must be less than `len`, and `start` must be at most the length of the program.
+        pub unsafe fn resume(
+            &self,
+            start: usize,
+            tape: *mut u8,
+            len: usize,
+            data_ptr: usize,
+            interrupts: [&AtomicBool; 2],
+        ) -> Exit
+        {
+            let interrupts = interrupts.map(|flag| flag as *const AtomicBool);
+            let mut state = State { tape, data_ptr, len, interrupts };
+            let entry = self.code.ptr.add(self.labels[start]);
+            let f: EntryFn = mem::transmute(self.code.ptr);
+            let code = f(&mut state, entry);
+            Exit {
+                instruction: (code >> 1) as usize,
+                data_ptr: state.data_ptr,
+                advanced: code & 1 == 1,
+            }
+        }
+    }
+
+    struct Assembler {
+        code: Vec<u8>,
+        width: usize,
+        scale: u8,
+        /// Locations of rel32 jump displacements to fill in once all targets are known.
+        patches: Vec<(usize, Target)>,
+    }
+
+    impl Assembler {
+        fn exit_code(instruction: usize, advanced: bool) -> u64 {
+            ((instruction as u64) << 1) | (advanced as u64)
+        }
+
+        fn emit(&mut self, bytes: &[u8]) { self.code.extend_from_slice(bytes); }
+
+        /// Emit a jump with a rel32 displacement to be patched: `E9` for `jmp`, or the second
+        /// opcode byte of a two byte conditional jump (e.g. `84` for `je`).
+        fn jump(&mut self, opcode: u8, target: Target) {
+            match opcode {
+                0xE9 => self.emit(&[0xE9]),
+                cc => self.emit(&[0x0F, cc]),
+            };
+            self.patches.push((self.code.len(), target));
+            self.emit(&[0; 4]);
+        }
+
+        fn patch(&mut self, targets: &HashMap<Target, usize>) -> Option<()> {
+            for &(at, target) in &self.patches {
+                let rel = i32::try_from(*targets.get(&target)? as i64 - (at as i64 + 4)).ok()?;
+                self.code[at..at + 4].copy_from_slice(&rel.to_le_bytes());
+            }
+            Some(())
+        }
+
+        fn prologue(&mut self) {
+            self.emit(&[0x53]); // push rbx
+            self.emit(&[0x41, 0x54]); // push r12
+            self.emit(&[0x41, 0x55]); // push r13
+            self.emit(&[0x41, 0x56]); // push r14
+            self.emit(&[0x48, 0x89, 0xFB]); // mov rbx, rdi
+            self.emit(&[0x4C, 0x8B, 0x23]); // mov r12, [rbx]
+            self.emit(&[0x4C, 0x8B, 0x6B, 0x08]); // mov r13, [rbx + 8]
+            self.emit(&[0x4C, 0x8B, 0x73, 0x10]); // mov r14, [rbx + 16]
+            self.emit(&[0xFF, 0xE6]); // jmp rsi
+        }
+
+        fn epilogue(&mut self) {
+            self.emit(&[0x4C, 0x89, 0x6B, 0x08]); // mov [rbx + 8], r13
+            self.emit(&[0x41, 0x5E]); // pop r14
+            self.emit(&[0x41, 0x5D]); // pop r13
+            self.emit(&[0x41, 0x5C]); // pop r12
+            self.emit(&[0x5B]); // pop rbx
+            self.emit(&[0xC3]); // ret
+        }
+
+        fn mov_rax(&mut self, value: u64) {
+            self.emit(&[0x48, 0xB8]);
+            self.emit(&value.to_le_bytes());
+        }
+
+        /// Return to the caller, asking it to interpret instruction `i`.
+        fn exit_inline(&mut self, i: usize) {
+            self.mov_rax(Self::exit_code(i, false));
+            self.jump(0xE9, Target::Epilogue);
+        }
+
+        /// Operand size prefix for 16-bit cells.
+        fn cell_prefix(&mut self) {
+            if self.width == 2 {
+                self.emit(&[0x66]);
+            };
+        }
+
+        /// SIB byte addressing the current cell, `[r12 + r13 * width]`.
+        fn cell_sib(&self) -> u8 { (self.scale << 6) | 0x2C }
+
+        fn cmp_cell_zero(&mut self) {
+            let sib = self.cell_sib();
+            self.cell_prefix();
+            match self.width {
+                1 => self.emit(&[0x43, 0x80, 0x3C, sib, 0x00]), // cmp byte [cell], 0
+                _ => self.emit(&[0x43, 0x83, 0x3C, sib, 0x00]), // cmp (d)word [cell], 0
+            };
+        }
+
+        fn add_cell(&mut self, n: i32) {
+            let sib = self.cell_sib();
+            self.cell_prefix();
+            match self.width {
+                1 => self.emit(&[0x43, 0x80, 0x04, sib, n as u8]),
+                2 => {
+                    self.emit(&[0x43, 0x81, 0x04, sib]);
+                    self.emit(&(n as u16).to_le_bytes());
+                },
+                _ => {
+                    self.emit(&[0x43, 0x81, 0x04, sib]);
+                    self.emit(&n.to_le_bytes());
+                },
+            };
+        }
+
+        fn zero_cell(&mut self) {
+            let sib = self.cell_sib();
+            self.cell_prefix();
+            match self.width {
+                1 => self.emit(&[0x43, 0xC6, 0x04, sib, 0x00]),
+                2 => self.emit(&[0x43, 0xC7, 0x04, sib, 0x00, 0x00]),
+                _ => self.emit(&[0x43, 0xC7, 0x04, sib, 0x00, 0x00, 0x00, 0x00]),
+            };
+        }
+
+        /// Move the data pointer, leaving it in place and exiting if it leaves the tape.
+        fn move_checked(&mut self, i: usize, n: i32) {
+            self.emit(&[0x49, 0x81, 0xC5]); // add r13, imm32
+            self.emit(&n.to_le_bytes());
+            self.emit(&[0x4D, 0x39, 0xF5]); // cmp r13, r14
+            self.jump(0x83, Target::Exit(i, true)); // jae
+        }
+
+        fn instruction(&mut self, i: usize, instruction: Instruction) {
+            match instruction {
+                Instruction::Add(n) => self.add_cell(n),
+                Instruction::SetZero => self.zero_cell(),
+                Instruction::Move(n) => match i32::try_from(n) {
+                    Ok(n) => self.move_checked(i, n),
+                    Err(_) => self.exit_inline(i),
+                },
+                Instruction::Scan(n) => match i32::try_from(n) {
+                    Ok(n) => {
+                        let top = self.code.len();
+                        self.cmp_cell_zero();
+                        self.jump(0x84, Target::Instruction(i + 1)); // je
+                        self.move_checked(i, n);
+                        self.emit(&[0xE9]); // jmp top
+                        let rel = top as i64 - (self.code.len() as i64 + 4);
+                        self.emit(&(rel as i32).to_le_bytes());
+                    },
+                    Err(_) => self.exit_inline(i),
+                },
+                Instruction::MulAdd(offset, factor) => match i32::try_from(offset) {
+                    Ok(offset) => {
+                        self.cmp_cell_zero();
+                        self.jump(0x84, Target::Instruction(i + 1)); // je
+                        self.emit(&[0x49, 0x8D, 0x85]); // lea rax, [r13 + disp32]
+                        self.emit(&offset.to_le_bytes());
+                        self.emit(&[0x4C, 0x39, 0xF0]); // cmp rax, r14
+                        // the pointer has not moved, so exit as if the instruction never began
+                        self.jump(0x83, Target::Exit(i, false)); // jae
+                        let sib = self.cell_sib();
+                        let sib_rax = (self.scale << 6) | 0x04;
+                        match self.width {
+                            1 => self.emit(&[0x43, 0x0F, 0xB6, 0x0C, sib]), // movzx ecx, byte
+                            2 => self.emit(&[0x43, 0x0F, 0xB7, 0x0C, sib]), // movzx ecx, word
+                            _ => self.emit(&[0x43, 0x8B, 0x0C, sib]), // mov ecx, dword
+                        };
+                        self.emit(&[0x69, 0xC9]); // imul ecx, ecx, imm32
+                        self.emit(&factor.to_le_bytes());
+                        match self.width {
+                            1 => self.emit(&[0x41, 0x00, 0x0C, sib_rax]), // add [r12 + rax], cl
+                            2 => self.emit(&[0x66, 0x41, 0x01, 0x0C, sib_rax]), // ..., cx
+                            _ => self.emit(&[0x41, 0x01, 0x0C, sib_rax]), // ..., ecx
+                        };
+                    },
+                    Err(_) => self.exit_inline(i),
+                },
+                Instruction::LoopBeg(Some(end)) => {
+                    self.cmp_cell_zero();
+                    self.jump(0x84, Target::Instruction(end + 1)); // je
+                },
+                Instruction::LoopEnd(Some(beg)) => {
+                    self.cmp_cell_zero();
+                    self.jump(0x84, Target::Instruction(i + 1)); // je
+                    // the interpreter repeats the loop once it has acted on the interruption
+                    for offset in [0x18, 0x20] {
+                        self.emit(&[0x48, 0x8B, 0x43, offset]); // mov rax, [rbx + offset]
+                        self.emit(&[0x80, 0x38, 0x00]); // cmp byte [rax], 0
+                        self.jump(0x85, Target::Exit(i, false)); // jne
+                    }
+                    self.jump(0xE9, Target::Instruction(beg + 1));
+                },
+                Instruction::PutChar
+                | Instruction::GetChar
+                | Instruction::LoopBeg(None)
+                | Instruction::LoopEnd(None)
+                | Instruction::DebugDump
+                | Instruction::DebugBreakpoint
+                | Instruction::ProcBeg(_)
+                | Instruction::ProcEnd(_)
+                | Instruction::ProcCall
+                | Instruction::Fork
+                | Instruction::End
+                | Instruction::Store
+                | Instruction::Load
+                | Instruction::Bitwise(_)
+                | Instruction::Random
+                | Instruction::Clock
+                | Instruction::NextTape
+                | Instruction::PrevTape
+                | Instruction::RowUp
+                | Instruction::RowDown => self.exit_inline(i),
+            };
+        }
+    }
+
+    /// Memory mapped region holding executable code, unmapped on drop.
+    struct ExecutableBuffer {
+        ptr: *mut u8,
+        len: usize,
+    }
+
+    impl ExecutableBuffer {
+        fn new(code: &[u8]) -> Option<Self> {
+            let len = code.len();
+            unsafe {
+                let ptr = libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                );
+                if ptr == libc::MAP_FAILED {
+                    return None;
+                };
+                ptr::copy_nonoverlapping(code.as_ptr(), ptr as *mut u8, len);
+                if libc::mprotect(ptr, len, libc::PROT_READ | libc::PROT_EXEC) != 0 {
+                    libc::munmap(ptr, len);
+                    return None;
+                };
+                Some(Self { ptr: ptr as *mut u8, len })
+            }
+        }
+    }
+
+    impl Drop for ExecutableBuffer {
+        fn drop(&mut self) {
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+        }
+    }
+}
+
+
+#[cfg(all(test, target_arch = "x86_64", unix))]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::config::{BoundsPolicy, InterpreterConfig, TapeMode};
+    use crate::interpreter::{ExecutionContext, ExecutionStatus};
+    use crate::ioctx::{InMemoryIoCtx, IoCtx};
+    use crate::optimizer;
+
+    static UNSET: AtomicBool = AtomicBool::new(false);
+
+    /// Run `program` with and without the JIT, asserting that both produce the same status and
+    /// output, and return the output.
+    fn run_both<C: crate::Cell>(program: &str, config: InterpreterConfig, input: &[u8]) -> Vec<u8> {
+        let mut results = Vec::new();
+        for &jit in &[false, true] {
+            let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+            ictx.borrow_mut().write_input(input).unwrap();
+            let config = InterpreterConfig { jit, ..config.clone() };
+            let status = ExecutionContext::<C>::with_cells(ictx.borrow_mut(), program)
+                .with_config(config)
+                .execute();
+            let mut output = Vec::new();
+            let mut buf = [0u8; 256];
+            while let Ok(n @ 1..=256) = ictx.borrow_mut().read_output(&mut buf) {
+                output.extend_from_slice(&buf[..n]);
+            }
+            results.push((status, output));
+        }
+        assert_eq!(results[0], results[1]);
+        results.pop().unwrap().1
+    }
+
+    #[test]
+    fn test_cell_width() {
+        assert_eq!(cell_width::<u8>(), Some(1));
+        assert_eq!(cell_width::<u16>(), Some(2));
+        assert_eq!(cell_width::<i32>(), Some(4));
+        assert_eq!(cell_width::<u64>(), None);
+    }
+
+    #[test]
+    fn test_resume() {
+        let program = optimizer::optimize(&Program::parse("+++[>++<-]>[>+>+<<-]>>>+++<<<"));
+        let jit = Jit::compile(&program, 1).unwrap();
+        let mut tape = vec![0u8; 5];
+        let exit = unsafe { jit.resume(0, tape.as_mut_ptr(), tape.len(), 0, [&UNSET, &UNSET]) };
+        assert_eq!(exit, Exit { instruction: program.len(), data_ptr: 1, advanced: false });
+        assert_eq!(tape, vec![0, 0, 6, 6, 3]);
+    }
+
+    #[test]
+    fn test_resume_off_tape() {
+        let program = Program::parse(">>.");
+        let jit = Jit::compile(&program, 2).unwrap();
+        let mut tape = vec![0u16; 2];
+        let (ptr, len) = (tape.as_mut_ptr() as *mut u8, tape.len());
+        let exit = unsafe { jit.resume(0, ptr, len, 0, [&UNSET, &UNSET]) };
+        assert_eq!(exit, Exit { instruction: 1, data_ptr: 2, advanced: true });
+        let exit = unsafe { jit.resume(2, ptr, len, 1, [&UNSET, &UNSET]) };
+        assert_eq!(exit, Exit { instruction: 2, data_ptr: 1, advanced: false });
+    }
+
+    #[test]
+    fn test_resume_interrupted() {
+        let program = Program::parse("+[>+<]");
+        let jit = Jit::compile(&program, 1).unwrap();
+        let mut tape = vec![0u8; 2];
+        let set = AtomicBool::new(true);
+        // the loop runs once before handing back its ']'
+        let exit = unsafe { jit.resume(0, tape.as_mut_ptr(), tape.len(), 0, [&UNSET, &set]) };
+        assert_eq!(exit, Exit { instruction: 5, data_ptr: 0, advanced: false });
+        assert_eq!(tape, vec![1, 1]);
+    }
+
+    #[test]
+    fn test_programs() {
+        let hello_world = concat!(
+            "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.",
+            "------.--------.>>+.>++.",
+        );
+        let config = InterpreterConfig::default();
+        assert_eq!(run_both::<u8>(hello_world, config.clone(), b""), b"Hello World!\n");
+        assert_eq!(run_both::<u8>(",[.[-],]", config.clone(), b"echo"), b"echo");
+        assert_eq!(run_both::<u8>("<<+[>>+<<-]>>.", config.clone(), b""), b"\x01");
+        assert_eq!(run_both::<u16>("-[->-<]>+[>+<+]>.", config.clone(), b""), b"\xFE");
+        assert_eq!(run_both::<i32>("-[>+<-]>[>+<+]>.", config, b""), b"\x01");
+    }
+
+    #[test]
+    fn test_tape_modes() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        run_both::<u8>("+[>+]", fixed.clone(), b"");
+        run_both::<u8>("+[<[-]>-]+[>>[-]<<]", fixed.clone(), b"");
+        let wrap = InterpreterConfig { bounds: BoundsPolicy::Wrap, ..fixed.clone() };
+        assert_eq!(run_both::<u8>("<+++[->>+<<]>>.", wrap, b""), b"\x03");
+        let clamp = InterpreterConfig { bounds: BoundsPolicy::Clamp, ..fixed.clone() };
+        assert_eq!(run_both::<u8>("<<+>>>>>+.<<<<<<.", clamp, b""), b"\x01\x01");
+        let circular = InterpreterConfig { tape_mode: TapeMode::Circular, ..fixed };
+        assert_eq!(run_both::<u8>("<+++[->>+<<]>>.", circular, b""), b"\x03");
+    }
+
+    #[test]
+    fn test_execute() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let status = ExecutionContext::new(ictx.borrow_mut(), "[").execute();
+        assert!(matches!(status, ExecutionStatus::ProgramError(_)));
+    }
+}
@@ -0,0 +1,132 @@
+//! Bounded history of executed instructions, from which the REPL can step backwards.
+
+use std::collections::VecDeque;
+
+use crate::cell::Cell;
+use crate::tape::Tape;
+
+
+/// State overwritten by a single instruction, enough to undo it.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry<C: Cell> {
+    program_ptr: usize,
+    data_ptr: usize,
+    tape_len: usize,
+
+    /// Number of cells added to the start of the tape by the instruction.
+    grown_left: usize,
+
+    /// Index and previous value of the cell modified by the instruction, if any.
+    cell: Option<(usize, C)>,
+}
+
+
+/// Undo log of the instructions executed by an `ExecutionContext`, see
+/// `InterpreterConfig::history`.
+///
+/// Only the tape and pointers are restored by `undo`: output that has been written and input that
+/// has been read are not taken back. Once `capacity` entries are held, the oldest is discarded for
+/// each new entry.
+#[derive(Debug, Clone, Default)]
+pub struct Journal<C: Cell> {
+    capacity: usize,
+    entries: VecDeque<Entry<C>>,
+}
+
+impl<C: Cell> Journal<C> {
+    /// Create a journal remembering up to `capacity` instructions, or none at all if zero.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Journal { capacity, entries: VecDeque::new() }
+    }
+
+    /// Whether instructions are recorded at all.
+    pub fn is_enabled(&self) -> bool { self.capacity > 0 }
+
+    /// Number of instructions that can currently be undone.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Forget every instruction recorded so far, e.g. once the state is changed by other means.
+    pub fn clear(&mut self) { self.entries.clear(); }
+
+    /// Record the state before the instruction at `program_ptr` executes. `cell` is the index of
+    /// the cell of `tape` that the instruction may modify, if any.
+    pub fn record(
+        &mut self,
+        program_ptr: usize,
+        data_ptr: usize,
+        tape: &dyn Tape<C>,
+        cell: Option<usize>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        };
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        };
+        self.entries.push_back(Entry {
+            program_ptr,
+            data_ptr,
+            tape_len: tape.len(),
+            grown_left: 0,
+            cell: cell.filter(|&i| i < tape.len()).map(|i| (i, tape.get(i))),
+        });
+    }
+
+    /// Account for `n` cells added to the start of the tape by the most recent instruction.
+    pub fn grow_left(&mut self, n: usize) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.grown_left += n;
+        };
+    }
+
+    /// Undo the most recent instruction by restoring `tape` to its state before the instruction,
+    /// returning the program and data pointers from before it and the number of cells removed from
+    /// the start of the tape. Returns `None` if there is nothing left to undo.
+    pub fn undo(&mut self, tape: &mut dyn Tape<C>) -> Option<(usize, usize, usize)> {
+        let entry = self.entries.pop_back()?;
+        tape.shrink_left(entry.grown_left);
+        tape.truncate(entry.tape_len);
+        if let Some((i, value)) = entry.cell {
+            tape.set(i, value);
+        };
+        Some((entry.program_ptr, entry.data_ptr, entry.grown_left))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_undo() {
+        let mut journal = Journal::with_capacity(2);
+        let mut tape = vec![1u8, 2];
+        journal.record(0, 1, &tape, Some(1));
+        tape[1] = 5;
+        journal.record(1, 1, &tape, None);
+        tape.insert(0, 0);
+        journal.grow_left(1);
+        tape.push(0);
+        assert_eq!(journal.undo(&mut tape), Some((1, 1, 1)));
+        assert_eq!(tape, vec![1, 5]);
+        assert_eq!(journal.undo(&mut tape), Some((0, 1, 0)));
+        assert_eq!(tape, vec![1, 2]);
+        assert_eq!(journal.undo(&mut tape), None);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut journal = Journal::with_capacity(2);
+        for i in 0..3 {
+            journal.record(i, 0, &vec![0u8], None);
+        }
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal.undo(&mut vec![0]), Some((2, 0, 0)));
+        let mut disabled = Journal::with_capacity(0);
+        disabled.record(0, 0, &vec![0u8], Some(0));
+        assert!(!disabled.is_enabled() && disabled.is_empty());
+    }
+}
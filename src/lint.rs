@@ -0,0 +1,291 @@
+//! Static analysis finding likely mistakes in a program without running it, see `lint`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::{BoundsPolicy, EofBehavior, InterpreterConfig, TapeMode};
+use crate::error::{SourceContext, TapeEnd};
+use crate::program::{Instruction, Program};
+use crate::token::Location;
+
+
+/// Kind of mistake found by `lint`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    /// A `[` without a matching `]`.
+    UnmatchedOpenBracket,
+
+    /// A `]` without a matching `[`.
+    UnmatchedCloseBracket,
+
+    /// A loop that can be entered but whose body changes neither the current cell nor the data
+    /// pointer, e.g. `+[]`, so it never ends once entered.
+    InfiniteLoop,
+
+    /// A change to a cell that is replaced by the `,` at the provided location before anything
+    /// reads it. Only found if `,` stores a value at the end of input, see `EofBehavior`.
+    Overwritten(Location),
+
+    /// A change to a cell that nothing reads before the program ends.
+    NeverRead,
+
+    /// A move that takes the data pointer past the provided end of a fixed tape.
+    OutOfBounds(TapeEnd),
+}
+
+
+/// A likely mistake in a program, found by `lint`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub context: SourceContext,
+}
+
+impl fmt::Display for Warning {
+    /// Describe the warning on one line, followed by an excerpt of the source on the lines below if
+    /// there is one.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = self.context.location;
+        match &self.kind {
+            WarningKind::UnmatchedOpenBracket => {
+                write!(f, "'[' at {} has no matching ']'", location)?;
+            },
+            WarningKind::UnmatchedCloseBracket => {
+                write!(f, "']' at {} has no matching '['", location)?;
+            },
+            WarningKind::InfiniteLoop => write!(
+                f,
+                "loop at {} never ends once entered, as it changes neither the current cell nor \
+                    the data pointer",
+                location,
+            )?,
+            WarningKind::Overwritten(by) => write!(
+                f,
+                "value written at {} is overwritten by ',' at {} before it is read",
+                location,
+                by,
+            )?,
+            WarningKind::NeverRead => write!(f, "value written at {} is never read", location)?,
+            WarningKind::OutOfBounds(end) => {
+                write!(f, "move at {} goes past the {} of the tape", location, end)?;
+            },
+        };
+        match &self.context.excerpt {
+            Some(excerpt) => write!(f, "\n{}", excerpt),
+            None => Ok(()),
+        }
+    }
+}
+
+
+/// Find likely mistakes in `program`, which should not have been optimized, under the semantics
+/// of `config`. Warnings are ordered by the position of the instruction they refer to.
+///
+/// Loops that can never run, like those at the start of the program that are commonly used for
+/// comments, are not analyzed.
+pub fn lint(program: &Program, config: &InterpreterConfig) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (i, instruction) in program.instructions().iter().enumerate() {
+        match instruction {
+            Instruction::LoopBeg(None) => warnings.push((i, WarningKind::UnmatchedOpenBracket)),
+            Instruction::LoopEnd(None) => warnings.push((i, WarningKind::UnmatchedCloseBracket)),
+            Instruction::LoopBeg(Some(end)) if is_infinite(program, i, *end) => {
+                warnings.push((i, WarningKind::InfiniteLoop));
+            },
+            _ => {},
+        };
+    }
+    unread_writes(program, config, &mut warnings);
+    if config.tape_mode == TapeMode::Fixed && config.bounds == BoundsPolicy::Error {
+        out_of_bounds(program, config.tape_size, &mut warnings);
+    };
+    warnings.sort_by_key(|&(i, _)| i);
+    warnings
+        .into_iter()
+        .map(|(i, kind)| Warning { kind, context: program.context(i) })
+        .collect()
+}
+
+
+/// Whether the loop opened at `start` never runs, as the current cell is zero where it starts:
+/// at the start of the program, or after another loop with nothing but output in between.
+fn is_dead(program: &Program, start: usize) -> bool {
+    let before = &program.instructions()[..start];
+    match before.iter().rev().find(|&&instruction| instruction != Instruction::PutChar) {
+        None | Some(Instruction::LoopEnd(_)) => true,
+        Some(_) => false,
+    }
+}
+
+
+/// Whether the loop from `start` to `end` can run but never ends once it does.
+fn is_infinite(program: &Program, start: usize, end: usize) -> bool {
+    let (mut offset, mut change) = (0, 0i64);
+    for instruction in &program.instructions()[start + 1..end] {
+        match instruction {
+            Instruction::Add(n) if offset == 0 => change += *n as i64,
+            Instruction::Move(n) => offset += n,
+            Instruction::Add(_) | Instruction::PutChar | Instruction::DebugDump => {},
+            _ => return false,
+        };
+    }
+    offset == 0 && change == 0 && !is_dead(program, start)
+}
+
+
+/// Warn about changes to cells that are overwritten or never read. Only straight-line code is
+/// analyzed, with every loop taken to read all cells.
+fn unread_writes(
+    program: &Program,
+    config: &InterpreterConfig,
+    warnings: &mut Vec<(usize, WarningKind)>,
+) {
+    // first unread write to each cell, by offset from the data pointer at the last loop
+    let mut pending: HashMap<isize, usize> = HashMap::new();
+    let mut offset = 0;
+    let mut i = 0;
+    while i < program.len() {
+        match program.instructions()[i] {
+            Instruction::Add(_) => {
+                pending.entry(offset).or_insert(i);
+            },
+            Instruction::Move(n) => offset += n,
+            Instruction::PutChar => {
+                pending.remove(&offset);
+            },
+            // with the cell left unchanged at the end of input, the earlier write may be read
+            Instruction::GetChar if config.eof != EofBehavior::Unchanged => {
+                if let Some(write) = pending.remove(&offset) {
+                    let location = program.context(i).location;
+                    warnings.push((write, WarningKind::Overwritten(location)));
+                };
+            },
+            Instruction::LoopBeg(Some(end)) if is_dead(program, i) => i = end,
+            _ => {
+                pending.clear();
+                offset = 0;
+            },
+        };
+        i += 1;
+    }
+    warnings.extend(pending.into_values().map(|write| (write, WarningKind::NeverRead)));
+}
+
+
+/// Warn about the first move past either end of a tape of `tape_size` cells, following the data
+/// pointer for as long as its position is known.
+fn out_of_bounds(program: &Program, tape_size: usize, warnings: &mut Vec<(usize, WarningKind)>) {
+    let mut ptr: isize = 0;
+    // whether the data pointer is known once each open loop ends
+    let mut balanced: Vec<bool> = Vec::new();
+    let mut i = 0;
+    while i < program.len() {
+        match program.instructions()[i] {
+            Instruction::Move(n) => {
+                ptr += n;
+                if ptr < 0 || ptr >= tape_size as isize {
+                    let end = if ptr < 0 { TapeEnd::Start } else { TapeEnd::End };
+                    warnings.push((i, WarningKind::OutOfBounds(end)));
+                    return;
+                };
+            },
+            Instruction::LoopBeg(Some(end)) if is_dead(program, i) => i = end,
+            Instruction::LoopBeg(Some(end)) => {
+                balanced.push(net_move(&program.instructions()[i + 1..end]) == Some(0));
+            },
+            Instruction::LoopEnd(Some(_)) if balanced.pop() == Some(true) => {},
+            Instruction::LoopBeg(None) | Instruction::LoopEnd(_) => return,
+            _ => {},
+        };
+        i += 1;
+    }
+}
+
+
+/// Total distance moved by `instructions`, or `None` if it is not fixed because of a loop that
+/// moves the data pointer.
+fn net_move(instructions: &[Instruction]) -> Option<isize> {
+    // position of the data pointer at the start of each open loop, to which it must return
+    let mut starts: Vec<isize> = Vec::new();
+    let mut position = 0;
+    for instruction in instructions {
+        match instruction {
+            Instruction::Move(n) => position += n,
+            Instruction::LoopBeg(_) => starts.push(position),
+            Instruction::LoopEnd(_) if starts.pop() != Some(position) => return None,
+            _ => {},
+        };
+    }
+    Some(position)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(source: &str, config: &InterpreterConfig) -> Vec<(WarningKind, usize)> {
+        lint(&Program::parse(source), config)
+            .into_iter()
+            .map(|warning| (warning.kind, warning.context.location.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_brackets() {
+        let config = InterpreterConfig::default();
+        assert_eq!(kinds("+[-]]", &config), vec![(WarningKind::UnmatchedCloseBracket, 5)]);
+        assert_eq!(kinds("+[[-]", &config), vec![(WarningKind::UnmatchedOpenBracket, 2)]);
+    }
+
+    #[test]
+    fn test_infinite_loop() {
+        let config = InterpreterConfig::default();
+        assert_eq!(kinds("+[]", &config), vec![(WarningKind::InfiniteLoop, 2)]);
+        assert_eq!(kinds(",[>+<+-.]", &config), vec![(WarningKind::InfiniteLoop, 2)]);
+        // comment loops and loops changing the cell or pointer are fine
+        assert!(kinds("[comment]+[-]+[>]+[,]", &config).is_empty());
+    }
+
+    #[test]
+    fn test_unread_writes() {
+        let config = InterpreterConfig::default();
+        assert_eq!(kinds("+.>++", &config), vec![(WarningKind::NeverRead, 4)]);
+        assert!(kinds("+,.", &config).is_empty());
+        let config = InterpreterConfig { eof: EofBehavior::Zero, ..config };
+        assert_eq!(
+            kinds("+>+<,.>.", &config),
+            vec![(WarningKind::Overwritten(Location { line: 1, column: 5 }), 1)],
+        );
+        assert!(kinds("+[->+<]>.", &config).is_empty());
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let fixed = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: 4,
+            ..InterpreterConfig::default()
+        };
+        assert_eq!(kinds("+[>+<-]>.<<", &fixed), vec![
+            (WarningKind::OutOfBounds(TapeEnd::Start), 11),
+        ]);
+        assert_eq!(kinds("+[>>+<<-]>>>>.", &fixed), vec![
+            (WarningKind::OutOfBounds(TapeEnd::End), 13),
+        ]);
+        // the data pointer is unknown after a loop that moves it
+        assert!(kinds("+[>]<<<<<.", &fixed).is_empty());
+        assert!(kinds("<.", &InterpreterConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let warnings = lint(&Program::parse("+\n+[]"), &InterpreterConfig::default());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "loop at line 2, col 2 never ends once entered, as it changes neither the current \
+                cell nor the data pointer\n2 | +[]\n  |  ^",
+        );
+    }
+}
@@ -0,0 +1,477 @@
+//! A minimal [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! server for BrainF\*ck source, see `serve`.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::config::InterpreterConfig;
+use crate::format::{self, FormatOptions};
+use crate::json::Json;
+use crate::lint::{self, WarningKind};
+use crate::program::Program;
+
+
+/// Error code of a response to a request for a method the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Error code of a response to a request that cannot be read.
+const INVALID_PARAMS: i64 = -32602;
+
+/// Severities of a diagnostic.
+const ERROR: usize = 1;
+const WARNING: usize = 2;
+
+
+/// Read a message framed by a `Content-Length` header from `input`, or `None` at the end of input.
+///
+/// Messages of the Language Server Protocol are JSON, each preceded by headers giving its length
+/// in bytes and separated from it by an empty line.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        };
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        };
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            };
+        };
+    }
+    let length = length.ok_or_else(|| invalid_data("message without a Content-Length"))?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    let content = String::from_utf8(content).map_err(|_| invalid_data("message is not UTF-8"))?;
+    Json::parse(&content).map(Some).map_err(invalid_data)
+}
+
+
+/// Write `message` to `output` preceded by its `Content-Length` header, see `read_message`.
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    output.flush()
+}
+
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+
+/// Serve the Language Server Protocol over `input` and `output` until told to exit or the input
+/// ends, returning whether a shutdown was requested first.
+///
+/// The server publishes the warnings of `lint` as diagnostics whenever a document is opened or
+/// changed, formats documents with `format::format`, jumps between matching brackets with "go to
+/// definition", highlights the bracket matching the one under the cursor, and shows the loop
+/// depth of any character on hover. Positions are counted in characters rather than the UTF-16
+/// code units of the protocol, which only differ after characters outside of the basic
+/// multilingual plane, and those can only appear in comments.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let mut server = Server { documents: HashMap::new(), shutdown: false };
+    while let Some(message) = read_message(&mut input)? {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        if method == "exit" {
+            break;
+        };
+        let params = message.get("params").unwrap_or(&Json::Null);
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.request(method, params) {
+                    Ok(result) => Json::object(vec![
+                        ("jsonrpc", Json::from("2.0")),
+                        ("id", id.clone()),
+                        ("result", result),
+                    ]),
+                    Err((code, error)) => Json::object(vec![
+                        ("jsonrpc", Json::from("2.0")),
+                        ("id", id.clone()),
+                        ("error", Json::object(vec![
+                            ("code", Json::from(code)),
+                            ("message", Json::from(error)),
+                        ])),
+                    ]),
+                };
+                write_message(&mut output, &response)?;
+            },
+            None => {
+                for notification in server.notify(method, params) {
+                    write_message(&mut output, &notification)?;
+                }
+            },
+        };
+    }
+    Ok(server.shutdown)
+}
+
+
+/// State of the server between messages.
+struct Server {
+    /// Text of each open document, by URI.
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Respond to the request for `method`, failing with an error code and message.
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        match method {
+            "initialize" => Ok(Json::object(vec![
+                ("capabilities", Json::object(vec![
+                    // the full text of a document is sent on every change
+                    ("textDocumentSync", Json::from(1usize)),
+                    ("hoverProvider", Json::from(true)),
+                    ("definitionProvider", Json::from(true)),
+                    ("documentHighlightProvider", Json::from(true)),
+                    ("documentFormattingProvider", Json::from(true)),
+                ])),
+                ("serverInfo", Json::object(vec![("name", Json::from("bfi"))])),
+            ])),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            },
+            "textDocument/hover" => {
+                let (_, text, offset) = self.position(params)?;
+                let depth = depth_at(text, offset);
+                Ok(Json::object(vec![
+                    ("contents", Json::object(vec![
+                        ("kind", Json::from("plaintext")),
+                        ("value", Json::from(format!("loop depth {}", depth))),
+                    ])),
+                ]))
+            },
+            "textDocument/definition" => {
+                let (uri, text, offset) = self.position(params)?;
+                Ok(match matching_bracket(text, offset) {
+                    Some(other) => Json::object(vec![
+                        ("uri", Json::from(uri)),
+                        ("range", range(text, other)),
+                    ]),
+                    None => Json::Null,
+                })
+            },
+            "textDocument/documentHighlight" => {
+                let (_, text, offset) = self.position(params)?;
+                Ok(match matching_bracket(text, offset) {
+                    Some(other) => Json::from(vec![
+                        Json::object(vec![("range", range(text, offset))]),
+                        Json::object(vec![("range", range(text, other))]),
+                    ]),
+                    None => Json::Null,
+                })
+            },
+            "textDocument/formatting" => {
+                let (_, text) = self.document(params)?;
+                let formatted = format::format(text, &FormatOptions::default());
+                if formatted == text {
+                    return Ok(Json::from(Vec::new()));
+                };
+                let lines = text.split('\n').count();
+                Ok(Json::from(vec![Json::object(vec![
+                    ("range", Json::object(vec![
+                        ("start", position(0, 0)),
+                        // past the end of the document, so the edit replaces all of it
+                        ("end", position(lines, 0)),
+                    ])),
+                    ("newText", Json::from(formatted)),
+                ])]))
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("method '{}' is not supported", method))),
+        }
+    }
+
+    /// Handle the notification of `method`, returning the notifications to send in reply.
+    fn notify(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str).unwrap_or("");
+        let text = match method {
+            "textDocument/didOpen" => params.at(&["textDocument", "text"]).and_then(Json::as_str),
+            "textDocument/didChange" => params
+                .get("contentChanges")
+                .and_then(Json::as_array)
+                .and_then(<[Json]>::last)
+                .and_then(|change| change.get("text"))
+                .and_then(Json::as_str),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                // clear the diagnostics of the closed document
+                return vec![diagnostics(uri, "")];
+            },
+            _ => None,
+        };
+        match text {
+            Some(text) => {
+                self.documents.insert(uri.to_string(), text.to_string());
+                vec![diagnostics(uri, text)]
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// URI and text of the open document named in `params`.
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a str), (i64, String)> {
+        let uri = params
+            .at(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .ok_or_else(|| (INVALID_PARAMS, "missing document URI".to_string()))?;
+        match self.documents.get(uri) {
+            Some(text) => Ok((uri, text)),
+            None => Err((INVALID_PARAMS, format!("document '{}' is not open", uri))),
+        }
+    }
+
+    /// URI, text, and offset in characters into the text of the position named in `params`.
+    fn position<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a str, usize), (i64, String)> {
+        let (uri, text) = self.document(params)?;
+        let line = params.at(&["position", "line"]).and_then(Json::as_u64);
+        let character = params.at(&["position", "character"]).and_then(Json::as_u64);
+        match (line, character) {
+            (Some(line), Some(character)) => {
+                Ok((uri, text, offset(text, line as usize, character as usize)))
+            },
+            _ => Err((INVALID_PARAMS, "missing position".to_string())),
+        }
+    }
+}
+
+
+/// Notification publishing the warnings of `lint` for `text` as diagnostics.
+fn diagnostics(uri: &str, text: &str) -> Json {
+    let warnings = lint::lint(&Program::parse(text), &InterpreterConfig::default());
+    let diagnostics = warnings
+        .into_iter()
+        .map(|warning| {
+            let severity = match warning.kind {
+                WarningKind::UnmatchedOpenBracket | WarningKind::UnmatchedCloseBracket => ERROR,
+                _ => WARNING,
+            };
+            let location = warning.context.location;
+            let start = position(location.line - 1, location.column - 1);
+            let end = position(location.line - 1, location.column);
+            // only the first line, as the excerpt below it is shown by the editor itself
+            let message = warning.to_string().lines().next().unwrap_or("").to_string();
+            Json::object(vec![
+                ("range", Json::object(vec![("start", start), ("end", end)])),
+                ("severity", Json::from(severity)),
+                ("source", Json::from("bfi")),
+                ("message", Json::from(message)),
+            ])
+        })
+        .collect::<Vec<Json>>();
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("method", Json::from("textDocument/publishDiagnostics")),
+        ("params", Json::object(vec![
+            ("uri", Json::from(uri)),
+            ("diagnostics", Json::from(diagnostics)),
+        ])),
+    ])
+}
+
+
+fn position(line: usize, character: usize) -> Json {
+    Json::object(vec![("line", Json::from(line)), ("character", Json::from(character))])
+}
+
+
+/// Range of the character at `offset` in `text`.
+fn range(text: &str, offset: usize) -> Json {
+    let (line, character) = line_and_character(text, offset);
+    Json::object(vec![
+        ("start", position(line, character)),
+        ("end", position(line, character + 1)),
+    ])
+}
+
+
+/// Offset in characters into `text` of a line and character counted from zero, clamped to the
+/// end of the line.
+fn offset(text: &str, line: usize, character: usize) -> usize {
+    let mut offset = 0;
+    for (i, content) in text.split('\n').enumerate() {
+        let length = content.chars().count();
+        if i == line {
+            return offset + character.min(length);
+        };
+        offset += length + 1;
+    }
+    offset.saturating_sub(1)
+}
+
+
+/// Line and character counted from zero of the character at `offset` in `text`.
+fn line_and_character(text: &str, offset: usize) -> (usize, usize) {
+    let before: Vec<char> = text.chars().take(offset).collect();
+    let line = before.iter().filter(|&&c| c == '\n').count();
+    let character = before.iter().rev().take_while(|&&c| c != '\n').count();
+    (line, character)
+}
+
+
+/// Number of loops around the character at `offset` in `text`, with brackets counted as part of
+/// the loop they open or close.
+fn depth_at(text: &str, offset: usize) -> usize {
+    let mut depth: usize = 0;
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '[' => depth += 1,
+            ']' if i == offset => return depth,
+            ']' => depth = depth.saturating_sub(1),
+            _ => {},
+        };
+        if i == offset {
+            return depth;
+        };
+    }
+    depth
+}
+
+
+/// Offset of the bracket matching the one at `offset` in `text`, if there is a bracket there and
+/// it is matched.
+fn matching_bracket(text: &str, offset: usize) -> Option<usize> {
+    let mut open = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '[' => open.push(i),
+            ']' => match open.pop() {
+                Some(start) if start == offset => return Some(i),
+                Some(start) if i == offset => return Some(start),
+                _ => {},
+            },
+            _ => {},
+        };
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Frame each of `messages` as it is sent by a client.
+    fn frame(messages: &[&str]) -> Vec<u8> {
+        messages
+            .iter()
+            .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+            .flat_map(String::into_bytes)
+            .collect()
+    }
+
+    /// Run the server on `messages`, returning whether it was shut down and what it sent.
+    fn serve_messages(messages: &[&str]) -> (bool, Vec<Json>) {
+        let input = frame(messages);
+        let mut output = Vec::new();
+        let shutdown = serve(&input[..], &mut output).unwrap();
+        let mut sent = Vec::new();
+        let mut output = &output[..];
+        while let Some(message) = read_message(&mut output).unwrap() {
+            sent.push(message);
+        }
+        (shutdown, sent)
+    }
+
+    const OPEN: &str = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{
+        "textDocument":{"uri":"file:///a.b","languageId":"bf","version":1,"text":"+[>[-]\n<]]"}
+    }}"#;
+
+    #[test]
+    fn test_lifecycle() {
+        let (shutdown, sent) = serve_messages(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"workspace/symbol","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+        ]);
+        assert!(shutdown);
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].get("id"), Some(&Json::from(1usize)));
+        let capabilities = sent[0].at(&["result", "capabilities"]).unwrap();
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+        assert_eq!(sent[1].at(&["error", "code"]), Some(&Json::from(METHOD_NOT_FOUND)));
+        assert_eq!(sent[2].get("result"), Some(&Json::Null));
+
+        let (shutdown, _) = serve_messages(&[r#"{"jsonrpc":"2.0","method":"exit"}"#]);
+        assert!(!shutdown);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let (_, sent) = serve_messages(&[OPEN]);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            sent[0].get("method").and_then(Json::as_str),
+            Some("textDocument/publishDiagnostics"),
+        );
+        let diagnostics = sent[0].at(&["params", "diagnostics"]).and_then(Json::as_array).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].to_string(), concat!(
+            r#"{"range":{"start":{"line":1,"character":2},"end":{"line":1,"character":3}},"#,
+            r#""severity":1,"source":"bfi","message":"']' at line 2, col 3 has no matching '['"}"#,
+        ));
+    }
+
+    #[test]
+    fn test_brackets() {
+        let request = |id: usize, method: &str, line: usize, character: usize| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"textDocument":
+                    {{"uri":"file:///a.b"}},"position":{{"line":{},"character":{}}}}}}}"#,
+                id, method, line, character,
+            )
+        };
+        let requests = [
+            request(1, "textDocument/definition", 1, 1),
+            request(2, "textDocument/definition", 0, 0),
+            request(3, "textDocument/hover", 0, 4),
+            request(4, "textDocument/hover", 1, 1),
+            request(5, "textDocument/documentHighlight", 0, 3),
+        ];
+        let mut messages = vec![OPEN];
+        messages.extend(requests.iter().map(String::as_str));
+        let (_, sent) = serve_messages(&messages);
+        let results: Vec<String> = sent[1..]
+            .iter()
+            .map(|response| response.get("result").unwrap().to_string())
+            .collect();
+        assert_eq!(results, vec![
+            concat!(
+                r#"{"uri":"file:///a.b","range":"#,
+                r#"{"start":{"line":0,"character":1},"end":{"line":0,"character":2}}}"#,
+            ),
+            "null",
+            r#"{"contents":{"kind":"plaintext","value":"loop depth 2"}}"#,
+            r#"{"contents":{"kind":"plaintext","value":"loop depth 1"}}"#,
+            concat!(
+                r#"[{"range":{"start":{"line":0,"character":3},"end":{"line":0,"character":4}}},"#,
+                r#"{"range":{"start":{"line":0,"character":5},"end":{"line":0,"character":6}}}]"#,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_formatting() {
+        let (_, sent) = serve_messages(&[
+            OPEN,
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/formatting","params":{
+                "textDocument":{"uri":"file:///a.b"},"options":{"tabSize":4,"insertSpaces":true}
+            }}"#,
+        ]);
+        let edits = sent[1].get("result").and_then(Json::as_array).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].at(&["range", "end", "line"]), Some(&Json::from(2usize)));
+        assert_eq!(edits[0].get("newText"), Some(&Json::from("+[\n    >[-]<\n]\n]\n")));
+    }
+}
@@ -0,0 +1,267 @@
+//! Optimization passes transforming a `Program` into an equivalent one that runs faster.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::program::{Instruction, Program};
+
+
+/// Tally of the optimizations applied by `optimize_with_report`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Number of runs of `+`/`-` or `>`/`<` collapsed into a single instruction.
+    pub collapsed_runs: usize,
+
+    /// Number of `[-]` and `[+]` loops replaced with `SetZero`.
+    pub set_zero: usize,
+
+    /// Number of `[>]` and `[<]` style loops replaced with `Scan`.
+    pub scans: usize,
+
+    /// Number of copy and multiplication loops replaced with `MulAdd` instructions.
+    pub mul_add_loops: usize,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "collapsed runs: {}, set zero: {}, scans: {}, multiplication loops: {}",
+            self.collapsed_runs, self.set_zero, self.scans, self.mul_add_loops,
+        )
+    }
+}
+
+
+/// Optimize a program, returning an equivalent program that executes fewer instructions.
+///
+/// Runs of `+`/`-` are collapsed into a single `Add` and runs of `>`/`<` into a single `Move`.
+/// Runs that cancel out entirely are removed. Note that this means that a fixed tape may no longer
+/// report an error for a run like `><` that briefly leaves the tape before returning.
+///
+/// Common loop idioms are then replaced with dedicated instructions:
+///
+/// - `[-]` and `[+]` become `SetZero`
+/// - `[>]`, `[<<]`, etc. become `Scan`
+/// - loops like `[->+>++<<]` that add multiples of the current cell to other cells and leave the
+///   current cell at zero become a series of `MulAdd` followed by `SetZero`
+pub fn optimize(program: &Program) -> Program { optimize_with_report(program).0 }
+
+
+/// Optimize a program as in `optimize`, also reporting which optimizations were applied.
+pub fn optimize_with_report(program: &Program) -> (Program, Report) {
+    #[cfg(feature = "std")]
+    let started = Instant::now();
+    let mut report = Report::default();
+    let (instructions, positions) = collapse_runs(program, &mut report);
+    let (instructions, positions) = replace_idioms(&instructions, &positions, &mut report);
+    let optimized = Program::link(instructions, positions).with_source_of(program);
+    #[cfg(feature = "std")]
+    log::debug!(
+        "optimized {} instructions into {} in {:?}",
+        program.len(),
+        optimized.len(),
+        started.elapsed(),
+    );
+    // there is no clock to time the passes with
+    #[cfg(not(feature = "std"))]
+    log::debug!("optimized {} instructions into {}", program.len(), optimized.len());
+    (optimized, report)
+}
+
+
+fn collapse_runs(program: &Program, report: &mut Report) -> (Vec<Instruction>, Vec<usize>) {
+    let mut instructions: Vec<Instruction> = Vec::with_capacity(program.len());
+    let mut positions: Vec<usize> = Vec::with_capacity(program.len());
+    let mut run_length = 0;
+    for (i, &instruction) in program.instructions().iter().enumerate() {
+        let merged = match (instructions.last(), instruction) {
+            (Some(&Instruction::Add(a)), Instruction::Add(b)) => {
+                Some(Instruction::Add(a.wrapping_add(b)))
+            },
+            (Some(&Instruction::Move(a)), Instruction::Move(b)) => Some(Instruction::Move(a + b)),
+            _ => None,
+        };
+        if merged.is_none() {
+            run_length = 0;
+        };
+        match merged {
+            Some(Instruction::Add(0)) | Some(Instruction::Move(0)) => {
+                instructions.pop();
+                positions.pop();
+            },
+            Some(m) => {
+                *instructions.last_mut().unwrap() = m;
+                run_length += 1;
+                if run_length == 1 {
+                    report.collapsed_runs += 1;
+                };
+            },
+            None => {
+                instructions.push(instruction);
+                positions.push(program.position(i));
+            },
+        };
+    }
+    (instructions, positions)
+}
+
+
+fn replace_idioms(
+    instructions: &[Instruction],
+    positions: &[usize],
+    report: &mut Report,
+) -> (Vec<Instruction>, Vec<usize>)
+{
+    let mut out_instructions: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    let mut out_positions: Vec<usize> = Vec::with_capacity(positions.len());
+    let mut i = 0;
+    while i < instructions.len() {
+        if let Some(end) = simple_loop_end(instructions, i) {
+            if let Some(replacement) = match_idiom(&instructions[i + 1..end], report) {
+                out_positions.extend(replacement.iter().map(|_| positions[i]));
+                out_instructions.extend(replacement);
+                i = end + 1;
+                continue;
+            };
+        };
+        out_instructions.push(instructions[i]);
+        out_positions.push(positions[i]);
+        i += 1;
+    }
+    (out_instructions, out_positions)
+}
+
+
+/// If the instruction at `beg` opens a non-empty loop whose body consists only of `Add` and `Move`
+/// instructions, return the index of the closing bracket.
+fn simple_loop_end(instructions: &[Instruction], beg: usize) -> Option<usize> {
+    match instructions.get(beg) {
+        Some(Instruction::LoopBeg(_)) => {},
+        _ => return None,
+    };
+    for (i, instruction) in instructions.iter().enumerate().skip(beg + 1) {
+        match instruction {
+            Instruction::Add(_) | Instruction::Move(_) => {},
+            Instruction::LoopEnd(_) if i > beg + 1 => return Some(i),
+            _ => return None,
+        };
+    }
+    None
+}
+
+
+/// Find the instructions equivalent to a loop with the provided body, if any.
+fn match_idiom(body: &[Instruction], report: &mut Report) -> Option<Vec<Instruction>> {
+    match body {
+        [Instruction::Add(1)] | [Instruction::Add(-1)] => {
+            report.set_zero += 1;
+            return Some(vec![Instruction::SetZero]);
+        },
+        [Instruction::Move(n)] => {
+            report.scans += 1;
+            return Some(vec![Instruction::Scan(*n)]);
+        },
+        _ => {},
+    };
+
+    // tally the net change to each cell relative to the current cell over one iteration
+    let mut offset: isize = 0;
+    let mut deltas: BTreeMap<isize, i32> = BTreeMap::new();
+    for instruction in body {
+        match instruction {
+            Instruction::Add(n) => {
+                let delta = deltas.entry(offset).or_insert(0);
+                *delta = delta.wrapping_add(*n);
+            },
+            Instruction::Move(n) => offset += n,
+            _ => return None,
+        };
+    }
+    // the loop must return to the current cell and decrement it exactly once per iteration
+    if offset != 0 || deltas.remove(&0) != Some(-1) {
+        return None;
+    };
+
+    report.mul_add_loops += 1;
+    let mut replacement: Vec<Instruction> = deltas
+        .into_iter()
+        .filter(|&(_, delta)| delta != 0)
+        .map(|(offset, delta)| Instruction::MulAdd(offset, delta))
+        .collect();
+    replacement.push(Instruction::SetZero);
+    Some(replacement)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_collapse_runs() {
+        let program = optimize(&Program::parse("+++>>-<<<[-]."));
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::Add(3),
+                Instruction::Move(2),
+                Instruction::Add(-1),
+                Instruction::Move(-3),
+                Instruction::SetZero,
+                Instruction::PutChar,
+            ],
+        );
+        assert_eq!(0, program.position(0));
+        assert_eq!(3, program.position(1));
+        assert_eq!(12, program.position(5));
+    }
+
+    #[test]
+    fn test_cancelling_runs() {
+        let program = optimize(&Program::parse(">+-<.+-+"));
+        assert_eq!(program.instructions(), &[Instruction::PutChar, Instruction::Add(1)]);
+        assert_eq!(4, program.position(0));
+        assert_eq!(7, program.position(1));
+    }
+
+    #[test]
+    fn test_idioms() {
+        let (program, report) = optimize_with_report(&Program::parse("[+]>[<<]+[->+>---<<]"));
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::SetZero,
+                Instruction::Move(1),
+                Instruction::Scan(-2),
+                Instruction::Add(1),
+                Instruction::MulAdd(1, 1),
+                Instruction::MulAdd(2, -3),
+                Instruction::SetZero,
+            ],
+        );
+        assert_eq!(1, report.set_zero);
+        assert_eq!(1, report.scans);
+        assert_eq!(1, report.mul_add_loops);
+    }
+
+    #[test]
+    fn test_non_idioms() {
+        // unbalanced, non-unit decrement, empty, and I/O loops are left alone
+        for source in &["[->+]", "[-->+<]", "[]", "[-.]"] {
+            let optimized = optimize(&Program::parse(source));
+            assert!(
+                !optimized.instructions().iter().any(|i| matches!(
+                    i,
+                    Instruction::SetZero | Instruction::Scan(_) | Instruction::MulAdd(_, _)
+                )),
+                "{}",
+                source,
+            );
+        };
+    }
+}
@@ -0,0 +1,162 @@
+//! A program executed a few instructions at a time, see `Playground`.
+
+use std::cell::RefCell;
+
+use crate::config::InterpreterConfig;
+use crate::difftest;
+use crate::error::BfError;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::ioctx::{InMemoryIoCtx, IoCtx};
+use crate::program::Program;
+use crate::snapshot::Snapshot;
+
+
+/// A program executed a few instructions at a time, such that its state can be shown between calls
+/// to `step` and whatever runs it stays responsive, e.g. a page in a browser.
+///
+/// Each instruction of the source is executed on its own, as the program is not optimized.
+#[derive(Debug, Clone)]
+pub struct Playground {
+    program: Program,
+    /// Input not yet read by the program.
+    input: Vec<u8>,
+    output: Vec<u8>,
+    /// State of the program to resume from, `None` until it has started.
+    snapshot: Option<Snapshot<u8>>,
+    status: ExecutionStatus<BfError>,
+}
+
+impl Playground {
+    /// Prepare `source` for execution with `input`.
+    pub fn new(source: &str, input: &[u8]) -> Self {
+        Playground {
+            program: Program::parse(source),
+            input: input.to_vec(),
+            output: Vec::new(),
+            snapshot: None,
+            status: ExecutionStatus::NotStarted,
+        }
+    }
+
+    /// Execute up to `steps` instructions, returning whether the program can continue.
+    pub fn step(&mut self, steps: u32) -> bool {
+        if !self.is_running() {
+            return false;
+        };
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        if let Err(e) = ictx.borrow_mut().write_input(&self.input) {
+            self.status = ExecutionStatus::InternalError(e.into());
+            return false;
+        };
+        {
+            let config = InterpreterConfig { optimize: false, ..InterpreterConfig::default() };
+            let program = self.program.clone();
+            let mut ectx = ExecutionContext::<u8>::with_program(ictx.borrow_mut(), program)
+                .with_config(config);
+            if let Some(snapshot) = self.snapshot.take() {
+                ectx = ectx.with_snapshot(snapshot);
+            };
+            for _ in 0..steps {
+                if !matches!(ectx.step(), ExecutionStatus::InProgress) {
+                    break;
+                };
+            }
+            self.snapshot = Some(ectx.snapshot());
+            self.status = ectx.status.clone();
+        }
+        // what remains of the input is read back such that the next call resumes where this ended
+        let mut ictx = ictx.borrow_mut();
+        self.input = drain(|buf| ictx.read_input(buf));
+        self.output.extend(drain(|buf| ictx.read_output(buf)));
+        self.is_running()
+    }
+
+    /// Whether the program has yet to end, either by terminating or by failing.
+    pub fn is_running(&self) -> bool {
+        matches!(self.status, ExecutionStatus::NotStarted | ExecutionStatus::InProgress)
+    }
+
+    /// Short description of the state of the program, e.g. `terminated` or the error it failed
+    /// with.
+    pub fn status(&self) -> String {
+        match self.status {
+            ExecutionStatus::NotStarted => "not started".to_string(),
+            ExecutionStatus::InProgress => "running".to_string(),
+            _ => difftest::describe(&self.status),
+        }
+    }
+
+    /// Status of the program, from which `status` is described.
+    pub fn execution_status(&self) -> &ExecutionStatus<BfError> { &self.status }
+
+    /// Everything written by the program so far, with invalid UTF-8 replaced.
+    pub fn output(&self) -> String { String::from_utf8_lossy(&self.output).into_owned() }
+
+    /// Everything written by the program so far, as it was written.
+    pub fn output_bytes(&self) -> &[u8] { &self.output }
+
+    /// The cells of the tape, starting from its leftmost cell.
+    pub fn tape(&self) -> Vec<u8> {
+        self.snapshot.as_ref().map_or_else(|| vec![0], |snapshot| snapshot.tape.clone())
+    }
+
+    /// Index into `tape` of the current cell.
+    pub fn data_ptr(&self) -> usize {
+        self.snapshot.as_ref().map_or(0, |snapshot| snapshot.data_ptr)
+    }
+
+    /// Offset in characters into the source of the next instruction to execute, e.g. to
+    /// highlight it, or `None` once the program has ended.
+    pub fn position(&self) -> Option<usize> {
+        let program_ptr = self.snapshot.as_ref().map_or(0, |snapshot| snapshot.program_ptr);
+        if !self.is_running() || program_ptr >= self.program.len() {
+            return None;
+        };
+        let location = self.program.location(program_ptr);
+        let before: usize = self
+            .program
+            .source()
+            .split('\n')
+            .take(location.line - 1)
+            .map(|line| line.chars().count() + 1)
+            .sum();
+        Some(before + location.column - 1)
+    }
+}
+
+
+/// Read from `read` until it returns nothing more or fails.
+fn drain(mut read: impl FnMut(&mut [u8]) -> std::io::Result<usize>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Ok(n) = read(&mut buf) {
+        if n == 0 { break };
+        bytes.extend_from_slice(&buf[..n]);
+    }
+    bytes
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_playground() {
+        let mut playground = Playground::new("a,+.\n  ,+.", b"ab");
+        assert_eq!(playground.status(), "not started");
+        assert_eq!(playground.position(), Some(1));
+        assert!(playground.step(3));
+        assert_eq!(playground.output(), "b");
+        assert_eq!(playground.tape(), vec![b'b']);
+        assert_eq!(playground.position(), Some(7));
+        // the rest of the input is read on the next call
+        assert!(playground.step(2));
+        assert_eq!(playground.status(), "running");
+        assert!(!playground.step(10));
+        assert_eq!(playground.output(), "bc");
+        assert_eq!(playground.status(), "terminated");
+        assert_eq!(playground.position(), None);
+        assert!(!playground.step(1));
+    }
+}
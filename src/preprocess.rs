@@ -0,0 +1,515 @@
+//! Expansion of includes, macros, and repetitions into plain source, see `preprocess`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::token::Location;
+
+
+/// Limit on macros expanding to other macros, such that a macro using itself fails instead of
+/// expanding forever.
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// Limit on the characters of expanded source, such that a repetition or macro expanding past
+/// what fits in memory fails instead of aborting.
+const MAX_EXPANDED_SIZE: usize = 1 << 22;
+
+
+/// Where a character of preprocessed source was written: the index of its file in
+/// `SourceMap::files` and its location in that file.
+type Origin = (usize, Location);
+
+
+/// A file read by the preprocessor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    /// The name of the file as given to `preprocess` or in `#include`.
+    pub name: String,
+    pub source: String,
+}
+
+
+/// Where each character of preprocessed source was written in the files it was expanded from,
+/// such that errors and profiles can refer to those files rather than to the expanded source.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// Origin of each character of each line of the preprocessed source.
+    lines: Vec<Vec<Origin>>,
+}
+
+impl SourceMap {
+    /// Map of `source` that was not preprocessed, such that every character is mapped to the same
+    /// location in the file `name`.
+    pub fn plain(name: &str, source: &str) -> Self {
+        let lines = source
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                (1..=line.chars().count())
+                    .map(|column| (0, Location { line: i + 1, column }))
+                    .collect()
+            })
+            .collect();
+        let file = SourceFile { name: name.to_string(), source: source.to_string() };
+        SourceMap { files: vec![file], lines }
+    }
+
+    /// Join sources, each with its map, into one source starting each on a new line, along with
+    /// the map from it back to the files of all of them.
+    pub fn concatenate(parts: Vec<(String, SourceMap)>) -> (String, SourceMap) {
+        let mut joined = SourceMap::default();
+        let mut sources = Vec::new();
+        for (source, source_map) in parts {
+            let offset = joined.files.len();
+            joined.files.extend(source_map.files);
+            joined.lines.extend(source_map.lines.into_iter().map(|line| {
+                line.into_iter().map(|(file, location)| (file + offset, location)).collect()
+            }));
+            sources.push(source);
+        }
+        (sources.join("\n"), joined)
+    }
+
+    /// The files the source was expanded from, starting with the one given to `preprocess`.
+    pub fn files(&self) -> &[SourceFile] { &self.files }
+
+    /// The index in `files` of the file the character at `location` in the preprocessed source
+    /// was written in, and its location there. Characters expanded from a macro are located at
+    /// the use of the macro and characters expanded from a repetition at the repeated character.
+    pub fn origin(&self, location: Location) -> Option<(usize, Location)> {
+        let line = self.lines.get(location.line.checked_sub(1)?)?;
+        line.get(location.column.checked_sub(1)?).copied()
+    }
+}
+
+
+/// A macro defined with `#define`.
+#[derive(Debug, Clone, PartialEq)]
+struct Macro {
+    params: Vec<String>,
+    body: String,
+}
+
+
+/// Expand the directives, macros, and repetitions in `source` into plain source, resolving
+/// includes relative to the directory `dir`. `name` identifies the source in errors, e.g. as its
+/// filename.
+///
+/// - `#include "lib.bf"` on a line of its own is replaced by the expanded contents of `lib.bf`.
+/// - `#define name(a, b) body` on a line of its own defines a macro, with the body continuing on
+///   the following lines up to `#end` if it is empty. Parameters are optional: `#define clear [-]`
+///   defines a macro without any.
+/// - `name(x, y)` anywhere after a definition expands to the body of the macro `name` with each
+///   parameter replaced by the matching argument. Macros without parameters are used as `name()`.
+/// - A command followed by `*` and a number is repeated that many times, e.g. `+*10`. The number
+///   can be a parameter of the enclosing macro.
+///
+/// Lines with directives are replaced by blank lines, such that the commands of the expanded
+/// source stay on the line they were written on as long as nothing is included before them.
+/// Anything else is left as it is, including a `#` that doesn't start a directive. Expanding past
+/// `MAX_EXPANDED_SIZE` characters fails.
+///
+/// Returns the expanded source along with the map from it back to the files it was written in.
+pub fn preprocess(source: &str, name: &str, dir: &Path) -> Result<(String, SourceMap), String> {
+    let mut preprocessor = Preprocessor {
+        macros: HashMap::new(),
+        including: Vec::new(),
+        files: Vec::new(),
+        expanded: Vec::new(),
+    };
+    preprocessor.expand_source(source, name, dir)?;
+    let expanded = repeat(&preprocessor.expanded, &preprocessor.files)?;
+    let mut lines = vec![Vec::new()];
+    for &(c, origin) in &expanded {
+        match (c, lines.last_mut()) {
+            ('\n', _) => lines.push(Vec::new()),
+            (_, Some(line)) => line.push(origin),
+            (_, None) => {},
+        };
+    }
+    let source = expanded.iter().map(|&(c, _)| c).collect();
+    Ok((source, SourceMap { files: preprocessor.files, lines }))
+}
+
+
+/// Macros defined so far, the files being included, outermost first, and the expanded source so
+/// far along with the origin of each of its characters.
+struct Preprocessor {
+    macros: HashMap<String, Macro>,
+    including: Vec<PathBuf>,
+    files: Vec<SourceFile>,
+    expanded: Vec<(char, Origin)>,
+}
+
+impl Preprocessor {
+    fn expand_source(&mut self, source: &str, name: &str, dir: &Path) -> Result<(), String> {
+        let file = match self.files.iter().position(|file| file.name == name) {
+            Some(file) => file,
+            None => {
+                self.files.push(SourceFile { name: name.to_string(), source: source.to_string() });
+                self.files.len() - 1
+            },
+        };
+        let mut lines = source.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let error = |message: String| format!("{}:{}: {}", name, i + 1, message);
+            let directive = line.trim();
+            if let Some(path) = directive.strip_prefix("#include") {
+                let path = path
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|path| path.strip_suffix('"'))
+                    .ok_or_else(|| error("expected a quoted path after #include".to_string()))?;
+                self.include(&dir.join(path)).map_err(error)?;
+            } else if let Some(definition) = directive.strip_prefix("#define") {
+                let (name, mut params, body) = parse_definition(definition).map_err(error)?;
+                let mut body = body.to_string();
+                self.end_line(file, i, line);
+                if body.is_empty() {
+                    loop {
+                        match lines.next() {
+                            Some((j, line)) if line.trim() == "#end" => {
+                                self.end_line(file, j, line);
+                                break;
+                            },
+                            Some((j, line)) => {
+                                body.push_str(line);
+                                body.push('\n');
+                                self.end_line(file, j, line);
+                            },
+                            None => return Err(error(format!("'{}' has no #end", name))),
+                        };
+                    }
+                };
+                params.retain(|param| !param.is_empty());
+                self.macros.insert(name.to_string(), Macro { params, body });
+            } else {
+                let expanded = self.expand(line, 0).map_err(error)?;
+                self.expanded.extend(expanded.into_iter().map(|(c, column)| {
+                    (c, (file, Location { line: i + 1, column }))
+                }));
+                self.end_line(file, i, line);
+            };
+        }
+        Ok(())
+    }
+
+    /// Append the newline ending `line`, the line at index `i` of `file`.
+    fn end_line(&mut self, file: usize, i: usize, line: &str) {
+        let location = Location { line: i + 1, column: line.chars().count() + 1 };
+        self.expanded.push(('\n', (file, location)));
+    }
+
+    /// Expand the file at `path`, failing if it is already being included.
+    fn include(&mut self, path: &Path) -> Result<(), String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("'{}' could not be included ({})", path.display(), e))?;
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.including.contains(&canonical) {
+            return Err(format!("'{}' includes itself", path.display()));
+        };
+        self.including.push(canonical);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let included = self.expand_source(&source, &path.display().to_string(), dir);
+        self.including.pop();
+        included
+    }
+
+    /// Expand the uses of macros in `text`, which is itself the body of a macro if `depth` is not
+    /// zero. Each character of the expansion comes with the column in `text` it was expanded
+    /// from.
+    fn expand(&self, text: &str, depth: usize) -> Result<Vec<(char, usize)>, String> {
+        if depth > MAX_EXPANSION_DEPTH {
+            return Err(format!("macros nested deeper than {} levels", MAX_EXPANSION_DEPTH));
+        };
+        let mut expanded = Vec::new();
+        let mut column = 1;
+        let mut rest = text;
+        while let Some(start) = rest.find(is_identifier_start) {
+            let (before, from) = rest.split_at(start);
+            copy(&mut expanded, before, &mut column);
+            let end = from.find(|c: char| !is_identifier(c)).unwrap_or(from.len());
+            let (identifier, after) = from.split_at(end);
+            rest = after;
+            let (definition, args) = match (self.macros.get(identifier), parse_args(after)) {
+                (Some(definition), Some((args, after))) => {
+                    rest = after;
+                    (definition, args)
+                },
+                _ => {
+                    copy(&mut expanded, identifier, &mut column);
+                    continue;
+                },
+            };
+            // `name()` passes a single empty argument to a macro without parameters
+            let args = if definition.params.is_empty() && args == [""] { Vec::new() } else { args };
+            if args.len() != definition.params.len() {
+                return Err(format!(
+                    "'{}' takes {} arguments but {} were given",
+                    identifier,
+                    definition.params.len(),
+                    args.len(),
+                ));
+            };
+            let bindings: HashMap<&str, &str> =
+                definition.params.iter().map(String::as_str).zip(args).collect();
+            let body = substitute(&definition.body, &bindings);
+            // the whole expansion is located at the start of the use
+            let used_at = column;
+            column += from[..from.len() - rest.len()].chars().count();
+            let body = self.expand(&body, depth + 1)?;
+            expanded.extend(body.into_iter().map(|(c, _)| (c, used_at)));
+            if expanded.len() > MAX_EXPANDED_SIZE {
+                return Err(format!("expands past the limit of {} characters", MAX_EXPANDED_SIZE));
+            };
+        }
+        copy(&mut expanded, rest, &mut column);
+        Ok(expanded)
+    }
+}
+
+
+/// Append each character of `s` to `expanded` along with its column, counting on from `column`.
+fn copy(expanded: &mut Vec<(char, usize)>, s: &str, column: &mut usize) {
+    for c in s.chars() {
+        expanded.push((c, *column));
+        *column += 1;
+    }
+}
+
+
+fn is_identifier_start(c: char) -> bool { c.is_ascii_alphabetic() || c == '_' }
+
+fn is_identifier(c: char) -> bool { c.is_ascii_alphanumeric() || c == '_' }
+
+fn is_name(s: &str) -> bool { s.starts_with(is_identifier_start) && s.chars().all(is_identifier) }
+
+
+/// Split the rest of a `#define` line into the name, parameters, and body of the macro.
+fn parse_definition(definition: &str) -> Result<(&str, Vec<String>, &str), String> {
+    let definition = definition.trim_start();
+    let end = definition.find(|c: char| !is_identifier(c)).unwrap_or(definition.len());
+    let (name, rest) = definition.split_at(end);
+    if !name.starts_with(is_identifier_start) {
+        return Err("expected the name of the macro after #define".to_string());
+    };
+    match parse_args(rest) {
+        Some((params, body)) => {
+            let params: Vec<String> = params.into_iter().map(str::to_string).collect();
+            if let Some(param) = params.iter().find(|param| !param.is_empty() && !is_name(param)) {
+                return Err(format!("'{}' is not a valid parameter name", param));
+            };
+            Ok((name, params, body.trim()))
+        },
+        None if rest.starts_with('(') => Err(format!("parameters of '{}' have no ')'", name)),
+        None => Ok((name, Vec::new(), rest.trim())),
+    }
+}
+
+
+/// Parse a parenthesized list of comma-separated arguments at the start of `s`, returning them
+/// trimmed with the rest of `s`. Parentheses nested within an argument are kept.
+fn parse_args(s: &str) -> Option<(Vec<&str>, &str)> {
+    let inner = s.strip_prefix('(')?;
+    let (mut args, mut depth, mut start) = (Vec::new(), 0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                return Some((args, &inner[i + 1..]));
+            },
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
+            },
+            _ => {},
+        };
+    }
+    None
+}
+
+
+/// Replace each identifier in `body` bound in `bindings` with its value.
+fn substitute(body: &str, bindings: &HashMap<&str, &str>) -> String {
+    let mut substituted = String::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(is_identifier_start) {
+        let (before, from) = rest.split_at(start);
+        let end = from.find(|c: char| !is_identifier(c)).unwrap_or(from.len());
+        let (identifier, after) = from.split_at(end);
+        substituted.push_str(before);
+        substituted.push_str(bindings.get(identifier).copied().unwrap_or(identifier));
+        rest = after;
+    }
+    substituted.push_str(rest);
+    substituted
+}
+
+
+/// Expand repetitions like `+*10`, where a character other than a letter, digit, or whitespace is
+/// followed by `*` and a number, failing with the file and line of the first character taking the
+/// source past `MAX_EXPANDED_SIZE`.
+fn repeat(source: &[(char, Origin)], files: &[SourceFile]) -> Result<Vec<(char, Origin)>, String> {
+    let mut repeated = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let (c, origin) = source[i];
+        i += 1;
+        let digits: String = source
+            .iter()
+            .skip(i + 1)
+            .map(|&(c, _)| c)
+            .take_while(char::is_ascii_digit)
+            .collect();
+        let repeats = !(c.is_alphanumeric() || c.is_whitespace() || c == '*')
+            && source.get(i).is_some_and(|&(next, _)| next == '*')
+            && !digits.is_empty();
+        // a number too large for a `usize` is past the limit anyway
+        let n = if repeats { digits.parse().unwrap_or(usize::MAX) } else { 1 };
+        if repeated.len().saturating_add(n) > MAX_EXPANDED_SIZE {
+            let (file, location) = origin;
+            return Err(format!(
+                "{}:{}: expands past the limit of {} characters",
+                files[file].name,
+                location.line,
+                MAX_EXPANDED_SIZE,
+            ));
+        };
+        repeated.extend(std::iter::repeat_n((c, origin), n));
+        if repeats {
+            i += 1 + digits.len();
+        };
+    }
+    Ok(repeated)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(source: &str) -> Result<String, String> {
+        preprocess(source, "test.bf", Path::new(".")).map(|(expanded, _)| expanded)
+    }
+
+    #[test]
+    fn test_repeat() {
+        assert_eq!(expand("+*3>*2-*0.").unwrap(), "+++>>.\n");
+        // letters, digits, and a lone `*` are left alone
+        assert_eq!(expand("a*2 2*2 +* 1 **2").unwrap(), "a*2 2*2 +* 1 **2\n");
+        // repetitions past the limit fail rather than exhausting memory
+        let limit = format!("expands past the limit of {} characters", MAX_EXPANDED_SIZE);
+        assert_eq!(expand("\n+*99999999999").unwrap_err(), format!("test.bf:2: {}", limit));
+        let message = expand("+*99999999999999999999999").unwrap_err();
+        assert_eq!(message, format!("test.bf:1: {}", limit));
+        let source = format!(">*{}\n<*{}", MAX_EXPANDED_SIZE / 2, MAX_EXPANDED_SIZE / 2);
+        assert_eq!(expand(&source).unwrap_err(), format!("test.bf:2: {}", limit));
+    }
+
+    #[test]
+    fn test_define() {
+        let source = "\
+            #define clear [-]\n\
+            #define move(from, to) from[-to+from]\n\
+            #define add(n)\n\
+            +*n\n\
+            #end\n\
+            add(3) move(<, >) clear() move(add(2), unused)\n";
+        assert_eq!(expand(source).unwrap(), "\n\n\n\n\n+++\n <[->+<] [-] ++\n[-unused+++\n]\n");
+        // identifiers that are not macros, or not followed by arguments, are comments
+        assert_eq!(expand("#define x +\nx y() x(). x").unwrap(), "\nx y() +. x\n");
+    }
+
+    #[test]
+    fn test_source_map() {
+        let (expanded, source_map) =
+            preprocess("#define two(c) c*2\n>two(+).\t+*3", "main", Path::new(".")).unwrap();
+        assert_eq!(expanded, "\n>++.\t+++\n");
+        let origin = |line, column| source_map.origin(Location { line, column });
+        assert_eq!(origin(1, 1), None);
+        // the expansion of `two(+)` is located at its use, and each repetition at its `+`
+        assert_eq!(origin(2, 2), Some((0, Location { line: 2, column: 2 })));
+        assert_eq!(origin(2, 3), Some((0, Location { line: 2, column: 2 })));
+        assert_eq!(origin(2, 4), Some((0, Location { line: 2, column: 8 })));
+        assert_eq!(origin(2, 8), Some((0, Location { line: 2, column: 10 })));
+        assert_eq!(origin(2, 9), None);
+        assert_eq!(origin(3, 1), None);
+        assert_eq!(source_map.files()[0].name, "main");
+    }
+
+    #[test]
+    fn test_define_errors() {
+        assert_eq!(
+            expand("#define m(a) a\n\nm(1, 2)").unwrap_err(),
+            "test.bf:3: 'm' takes 1 arguments but 2 were given",
+        );
+        assert_eq!(expand("#define m\n+").unwrap_err(), "test.bf:1: 'm' has no #end");
+        assert_eq!(expand("#define m() m()\nm()").unwrap_err(), format!(
+            "test.bf:2: macros nested deeper than {} levels",
+            MAX_EXPANSION_DEPTH,
+        ));
+        assert!(expand("#define (x) x").is_err());
+        assert!(expand("#define m(+) +").is_err());
+        // each macro expands to 64 of the last, past the limit without any repetitions
+        let source = format!(
+            "#define a {}\n#define b {}\n#define c {}\nc()",
+            "+".repeat(1024),
+            "a()".repeat(64),
+            "b()".repeat(64),
+        );
+        let message = expand(&source).unwrap_err();
+        assert!(message.starts_with("test.bf:4: expands past the limit"), "{}", message);
+    }
+
+    #[test]
+    fn test_concatenate() {
+        let (expanded, source_map) = preprocess("#define m ++\nm()", "a.bf", Path::new(""))
+            .unwrap();
+        let (source, source_map) = SourceMap::concatenate(vec![
+            (expanded, source_map),
+            ("+\n -".to_string(), SourceMap::plain("b.bf", "+\n -")),
+        ]);
+        // the expanded source ends with a newline of its own
+        assert_eq!(source, "\n++\n\n+\n -");
+        let names: Vec<&str> = source_map.files().iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["a.bf", "b.bf"]);
+        let origin = |line, column| source_map.origin(Location { line, column });
+        assert_eq!(origin(2, 2), Some((0, Location { line: 2, column: 1 })));
+        assert_eq!(origin(3, 1), None);
+        assert_eq!(origin(4, 1), Some((1, Location { line: 1, column: 1 })));
+        assert_eq!(origin(5, 2), Some((1, Location { line: 2, column: 2 })));
+        assert_eq!(origin(6, 1), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "WASI has no temporary directory")]
+    fn test_include() {
+        let dir = std::env::temp_dir().join(format!("bfi-test-preprocess-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib/clear.bf"), "#define clear [-]\n").unwrap();
+        std::fs::write(dir.join("lib/main.bf"), "#include \"clear.bf\"\n+clear()\n").unwrap();
+        std::fs::write(dir.join("loop.bf"), "#include \"loop.bf\"\n").unwrap();
+        assert_eq!(
+            preprocess("#include \"lib/main.bf\"\nclear()", "main", &dir).unwrap().0,
+            "\n+[-]\n[-]\n",
+        );
+        let (_, source_map) = preprocess("#include \"lib/main.bf\"\n", "main", &dir).unwrap();
+        let names: Vec<&str> = source_map.files().iter().map(|file| file.name.as_str()).collect();
+        let lib = dir.join("lib");
+        let (main, clear) = (lib.join("main.bf"), lib.join("clear.bf"));
+        assert_eq!(names, ["main", main.to_str().unwrap(), clear.to_str().unwrap()]);
+        assert_eq!(source_map.origin(Location { line: 2, column: 1 }), Some((1, Location {
+            line: 2,
+            column: 1,
+        })));
+        assert!(preprocess("#include \"loop.bf\"", "main", &dir)
+            .unwrap_err()
+            .ends_with("includes itself"));
+        assert!(preprocess("#include missing.bf", "main", &dir).is_err());
+        assert!(preprocess("#include \"missing.bf\"", "main", &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,474 @@
+//! Execution counts showing where a program spends its time.
+
+use std::cmp::Reverse;
+
+use crate::program::{Instruction, Program};
+
+
+/// Number of rows in each section of `Profile::report`.
+const REPORT_ROWS: usize = 10;
+
+/// Width of the longest bar in the cell access histogram of `Profile::report`.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// 256-color terminal palette used by `Profile::annotate` for commands by increasing execution
+/// count, from blue through to red.
+const HEAT_COLORS: [u8; 6] = [21, 39, 48, 226, 208, 196];
+
+/// 256-color terminal color used by `Profile::annotate` for commands that were never executed.
+const COLD_COLOR: u8 = 240;
+
+
+/// Execution counts collected by an `ExecutionContext`, see `ExecutionContext::with_profiling`.
+///
+/// Counts are kept per instruction of the program as executed, i.e. after optimization.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    instructions: Vec<u64>,
+    cells: Vec<u64>,
+}
+
+
+/// Execution counts of the commands of a file a program was parsed from, see
+/// `Profile::file_counts`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileCounts<'p> {
+    /// Name of the file, `None` for a program that was not preprocessed.
+    pub name: Option<&'p str>,
+    pub source: &'p str,
+
+    /// Count of each character of each line, `None` for characters that are not commands.
+    pub lines: Vec<Vec<Option<u64>>>,
+}
+
+
+/// Execution counts of a single loop of a program, see `Profile::loops`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LoopProfile {
+    /// Index of the `[` starting the loop.
+    pub start: usize,
+
+    /// Index of the `]` ending the loop.
+    pub end: usize,
+
+    /// Number of times the loop was reached.
+    pub entries: u64,
+
+    /// Number of times the body of the loop was executed in total.
+    pub iterations: u64,
+
+    /// Number of instructions executed within the loop, including those of nested loops.
+    pub instructions: u64,
+}
+
+
+impl Profile {
+    /// Count the execution of the instruction at index `program_ptr`, which accesses the cell at
+    /// index `data_ptr` of the tape.
+    pub fn record(&mut self, program_ptr: usize, data_ptr: usize) {
+        increment(&mut self.instructions, program_ptr);
+        increment(&mut self.cells, data_ptr);
+    }
+
+    /// Account for `n` cells added to the start of the tape, moving every existing cell along.
+    pub fn grow_left(&mut self, n: usize) {
+        self.cells.splice(0..0, std::iter::repeat_n(0, n));
+    }
+
+    /// Number of times each instruction was executed, indexed like `Program::instructions`.
+    /// Instructions past the end of the slice were never executed.
+    pub fn instruction_counts(&self) -> &[u64] { &self.instructions }
+
+    /// Number of instructions executed with the data pointer on each cell, indexed like the tape.
+    /// Cells past the end of the slice were never accessed.
+    pub fn cell_accesses(&self) -> &[u64] { &self.cells }
+
+    /// Total number of instructions executed.
+    pub fn total(&self) -> u64 { self.instructions.iter().sum() }
+
+    /// Counts of every loop of `program` that was reached, hottest (by instructions executed)
+    /// first.
+    pub fn loops(&self, program: &Program) -> Vec<LoopProfile> {
+        let count = |i: usize| self.instructions.get(i).copied().unwrap_or(0);
+        let mut loops: Vec<LoopProfile> = program
+            .instructions()
+            .iter()
+            .enumerate()
+            .filter_map(|(start, instruction)| match instruction {
+                Instruction::LoopBeg(Some(end)) if count(start) > 0 => Some(LoopProfile {
+                    start,
+                    end: *end,
+                    entries: count(start),
+                    iterations: count(*end),
+                    instructions: (start..=*end).map(count).sum(),
+                }),
+                _ => None,
+            })
+            .collect();
+        loops.sort_by(|a, b| b.instructions.cmp(&a.instructions).then(a.start.cmp(&b.start)));
+        loops
+    }
+
+    /// Render a report of the hottest loops, the number of instructions executed of each kind,
+    /// and a histogram of the most accessed cells.
+    pub fn report(&self, program: &Program) -> String {
+        let mut lines = vec![format!("{} instructions executed", self.total())];
+
+        lines.push(String::new());
+        lines.push("instruction      count".to_string());
+        let mut kinds: Vec<(&str, u64)> = Vec::new();
+        for (instruction, count) in program.instructions().iter().zip(self.instructions.iter()) {
+            match kinds.iter_mut().find(|(kind, _)| *kind == self::kind(instruction)) {
+                Some((_, total)) => *total += count,
+                None => kinds.push((self::kind(instruction), *count)),
+            };
+        }
+        kinds.retain(|(_, count)| *count > 0);
+        kinds.sort_by_key(|(_, count)| Reverse(*count));
+        lines.extend(kinds.iter().map(|(kind, count)| format!("{:<10} {:>10}", kind, count)));
+
+        lines.push(String::new());
+        lines.push(" index position    entries iterations instructions  location".to_string());
+        lines.extend(self.loops(program).iter().take(REPORT_ROWS).map(|l| {
+            format!(
+                "{:>6} {:>8} {:>10} {:>10} {:>12}  {}",
+                l.start,
+                program.position(l.start),
+                l.entries,
+                l.iterations,
+                l.instructions,
+                program.context(l.start),
+            )
+        }));
+
+        lines.push(String::new());
+        lines.push("  cell   accesses".to_string());
+        let mut hottest: Vec<(usize, u64)> = self.cells
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        hottest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hottest.truncate(REPORT_ROWS);
+        hottest.sort();
+        let max = hottest.iter().map(|(_, count)| *count).max().unwrap_or(1);
+        lines.extend(hottest.iter().map(|(cell, count)| {
+            let bar = "#".repeat(((count * HISTOGRAM_WIDTH as u64).div_ceil(max)) as usize);
+            format!("{:>6} {:>10} {}", cell, count, bar)
+        }));
+        lines.join("\n")
+    }
+
+    /// Number of times each token of the source of `program` was executed, indexed like
+    /// `Program::locations`. A token merged into an instruction by optimization counts as
+    /// executed whenever the instruction is.
+    pub fn token_counts(&self, program: &Program) -> Vec<u64> {
+        let count = |i: usize| self.instructions.get(i).copied().unwrap_or(0);
+        let mut counts = vec![0; program.locations().len()];
+        // instructions replacing a single idiom share a position and with it a range of tokens
+        let (mut next_start, mut next_end) = (counts.len(), counts.len());
+        for i in (0..program.len()).rev() {
+            let start = program.position(i).min(counts.len());
+            let end = if start == next_start { next_end } else { next_start };
+            for token_count in counts[start..end].iter_mut() {
+                *token_count = (*token_count).max(count(i));
+            }
+            next_start = start;
+            next_end = end;
+        }
+        counts
+    }
+
+    /// Render the source of `program` with the execution count of each command.
+    ///
+    /// Each line of source is shown alongside the highest count of any command on it. Below the
+    /// line, each command is marked with the number of digits in its count, or `.` if it was
+    /// never executed. With `color`, the commands are instead colored from blue to red by their
+    /// count using terminal escape codes.
+    ///
+    /// A preprocessed program is shown as the files it was preprocessed from, each headed by its
+    /// name, with the counts of every command expanded from the same character added up.
+    pub fn annotate(&self, program: &Program, color: bool) -> String {
+        let files = self.file_counts(program);
+        let max = files
+            .iter()
+            .flat_map(|file| file.lines.iter().flatten().flatten())
+            .copied()
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![
+            format!("{} instructions executed", self.total()),
+            String::new(),
+            "  line      count".to_string(),
+        ];
+        for file in &files {
+            if let Some(name) = file.name {
+                lines.push(format!("==> {} <==", name));
+            };
+            annotate_lines(&mut lines, file.source, &file.lines, max, color);
+        }
+        lines.join("\n")
+    }
+
+    /// Execution counts of the commands of each file the source of `program` came from, as in
+    /// `token_counts`. A preprocessed program is split back into the files it was preprocessed
+    /// from, with the counts of every command expanded from the same character added up.
+    pub(crate) fn file_counts<'p>(&self, program: &'p Program) -> Vec<FileCounts<'p>> {
+        let files: Vec<(Option<&str>, &str)> = match program.source_map() {
+            Some(source_map) => source_map
+                .files()
+                .iter()
+                .map(|file| (Some(file.name.as_str()), file.source.as_str()))
+                .collect(),
+            None => vec![(None, program.source())],
+        };
+        let mut file_counts: Vec<Vec<Vec<Option<u64>>>> = files
+            .iter()
+            .map(|(_, source)| {
+                source.lines().map(|line| vec![None; line.chars().count()]).collect()
+            })
+            .collect();
+        for (&location, count) in program.locations().iter().zip(self.token_counts(program)) {
+            let origin = match program.source_map() {
+                Some(source_map) => source_map.origin(location),
+                None => Some((0, location)),
+            };
+            let counts = origin.and_then(|(file, location)| {
+                file_counts[file].get_mut(location.line - 1)?.get_mut(location.column - 1)
+            });
+            if let Some(total) = counts {
+                *total = Some(total.unwrap_or(0) + count);
+            };
+        }
+        files
+            .into_iter()
+            .zip(file_counts)
+            .map(|((name, source), lines)| FileCounts { name, source, lines })
+            .collect()
+    }
+}
+
+
+/// Append the lines of `source` to `lines`, each annotated with the execution counts of its
+/// commands in `line_counts`, see `Profile::annotate`.
+fn annotate_lines(
+    lines: &mut Vec<String>,
+    source: &str,
+    line_counts: &[Vec<Option<u64>>],
+    max: u64,
+    color: bool,
+) {
+    for (i, (line, counts)) in source.lines().zip(line_counts.iter()).enumerate() {
+        let line_max = counts.iter().flatten().max();
+        let gutter = format!(
+            "{:>6} {:>10} |",
+            i + 1,
+            line_max.map_or(String::new(), |count| count.to_string()),
+        );
+        let text: String = if color {
+            line.chars().zip(counts.iter()).map(|(c, count)| match count {
+                Some(count) => format!("\x1b[38;5;{}m{}\x1b[0m", heat_color(*count, max), c),
+                None => c.to_string(),
+            }).collect()
+        } else {
+            line.to_string()
+        };
+        lines.push(format!("{} {}", gutter, text).trim_end().to_string());
+        if color || line_max.is_none() {
+            continue;
+        };
+        // keep tabs such that the marks line up regardless of tab width
+        let marks: String = line.chars().zip(counts.iter()).map(|(c, count)| match count {
+            Some(0) => '.',
+            // unwrap is safe as a count has at most 20 digits
+            Some(count) => std::char::from_digit(digits(*count).min(9), 10).unwrap(),
+            None if c == '\t' => '\t',
+            None => ' ',
+        }).collect();
+        lines.push(format!("{:>17} | {}", "", marks).trim_end().to_string());
+    }
+}
+
+
+fn increment(counts: &mut Vec<u64>, i: usize) {
+    if i >= counts.len() {
+        counts.resize(i + 1, 0);
+    };
+    counts[i] += 1;
+}
+
+
+/// Number of decimal digits in `n`.
+fn digits(n: u64) -> u32 { n.checked_ilog10().map_or(1, |log| log + 1) }
+
+
+/// Terminal color of a command executed `count` times, out of at most `max` times for any command.
+/// Colors are assigned by order of magnitude so that the hottest commands stand out.
+fn heat_color(count: u64, max: u64) -> u8 {
+    if count == 0 {
+        return COLD_COLOR;
+    };
+    let levels = HEAT_COLORS.len() as u32 - 1;
+    let level = (digits(count) - 1) * levels / (digits(max) - 1).max(1);
+    HEAT_COLORS[level.min(levels) as usize]
+}
+
+
+/// Name of the kind of an instruction, grouping instructions in `Profile::report` and
+/// `stats::ExecutionStats`.
+pub(crate) fn kind(instruction: &Instruction) -> &'static str {
+    match instruction {
+        Instruction::Add(_) => "add",
+        Instruction::Move(_) => "move",
+        Instruction::SetZero => "zero",
+        Instruction::Scan(_) => "scan",
+        Instruction::MulAdd(_, _) => "muladd",
+        Instruction::PutChar => "output",
+        Instruction::GetChar => "input",
+        Instruction::LoopBeg(_) | Instruction::LoopEnd(_) => "loop",
+        Instruction::DebugDump | Instruction::DebugBreakpoint => "debug",
+        Instruction::ProcBeg(_) | Instruction::ProcEnd(_) | Instruction::ProcCall => "procedure",
+        Instruction::Fork => "fork",
+        Instruction::End
+        | Instruction::Store
+        | Instruction::Load
+        | Instruction::Bitwise(_)
+        | Instruction::Random
+        | Instruction::Clock
+        | Instruction::NextTape
+        | Instruction::PrevTape
+        | Instruction::RowUp
+        | Instruction::RowDown => "extension",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::optimizer;
+
+    #[test]
+    fn test_loops() {
+        let program = Program::parse("++[>++[-]<-]");
+        let mut profile = Profile::default();
+        let executed = [0, 1, 2, 3, 4, 5, 6, 7, 8, 7, 8, 9, 10, 11, 3, 4, 5, 6, 7, 8, 7, 8, 9, 10, 11];
+        for &i in executed.iter() {
+            profile.record(i, 0);
+        }
+        assert_eq!(profile.total(), 25);
+        assert_eq!(profile.instruction_counts()[7], 4);
+        assert_eq!(
+            profile.loops(&program),
+            vec![
+                LoopProfile { start: 2, end: 11, entries: 1, iterations: 2, instructions: 23 },
+                LoopProfile { start: 6, end: 8, entries: 2, iterations: 4, instructions: 10 },
+            ],
+        );
+    }
+
+    #[test]
+    fn test_cell_accesses() {
+        let mut profile = Profile::default();
+        profile.record(0, 0);
+        profile.record(1, 2);
+        profile.grow_left(1);
+        profile.record(2, 0);
+        assert_eq!(profile.cell_accesses(), &[1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_report() {
+        let program = Program::parse("+[>]");
+        let mut profile = Profile::default();
+        for (program_ptr, data_ptr) in [(0, 0), (1, 0), (2, 0), (2, 1), (3, 1)] {
+            profile.record(program_ptr, data_ptr);
+        }
+        let report = profile.report(&program);
+        assert_eq!(
+            report.lines().collect::<Vec<&str>>(),
+            vec![
+                "5 instructions executed",
+                "",
+                "instruction      count",
+                "loop                2",
+                "move                2",
+                "add                 1",
+                "",
+                " index position    entries iterations instructions  location",
+                "     1        1          1          1            4  line 1, col 2",
+                "",
+                "  cell   accesses",
+                "     0          3 ########################################",
+                "     1          2 ###########################",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_token_counts() {
+        let program = optimizer::optimize(&Program::parse("+[->+<]>."));
+        assert_eq!(program.len(), 5);
+        let mut profile = Profile::default();
+        for i in 0..program.len() {
+            profile.record(i, 0);
+        }
+        profile.record(0, 0);
+        assert_eq!(profile.token_counts(&program), vec![2, 1, 1, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(profile.token_counts(&Program::default()), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let program = Program::parse("+ set\n[>+<-]\n\t.\n");
+        let mut profile = Profile::default();
+        let executed = [0, 1, 2, 3, 4, 5, 6, 1];
+        for &i in executed.iter().chain(std::iter::repeat_n(&4, 10)) {
+            profile.record(i, 0);
+        }
+        assert_eq!(
+            profile.annotate(&program, false).lines().collect::<Vec<&str>>(),
+            vec![
+                "18 instructions executed",
+                "",
+                "  line      count",
+                "     1          1 | + set",
+                "                  | 1",
+                "     2         11 | [>+<-]",
+                "                  | 111211",
+                "     3          0 | \t.",
+                "                  | \t.",
+            ],
+        );
+        let colored = profile.annotate(&program, true);
+        assert!(colored.contains("     2         11 | \x1b[38;5;21m[\x1b[0m\x1b[38;5;21m>"));
+        assert!(colored.contains("\x1b[38;5;196m<"));
+        assert!(colored.contains("\x1b[38;5;240m."));
+    }
+
+    #[test]
+    fn test_annotate_preprocessed() {
+        let source = "#define out .\n+*2 out() out()";
+        let (expanded, source_map) =
+            crate::preprocess::preprocess(source, "main.bf", std::path::Path::new("")).unwrap();
+        let program = Program::parse(&expanded).with_source_map(source_map);
+        let mut profile = Profile::default();
+        for i in 0..program.len() {
+            profile.record(i, 0);
+        }
+        assert_eq!(
+            profile.annotate(&program, false).lines().collect::<Vec<&str>>(),
+            vec![
+                "4 instructions executed",
+                "",
+                "  line      count",
+                "==> main.bf <==",
+                "     1            | #define out .",
+                "     2          2 | +*2 out() out()",
+                "                  | 1   1     1",
+            ],
+        );
+    }
+}
@@ -0,0 +1,557 @@
+//! Compiled representation of a BrainF\*ck program ready for execution.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::cell::Cell;
+use crate::dialect::Dialect;
+use crate::error::{BfError, SourceContext};
+#[cfg(feature = "std")]
+use crate::preprocess::{SourceFile, SourceMap};
+use crate::token::{Location, Token};
+
+
+/// A single executable instruction.
+///
+/// Loop instructions carry the index of their matching counterpart so that jumps do not require
+/// scanning the program. The target is `None` when the bracket has no match.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Instruction {
+    /// Add the provided amount to the current cell, wrapping at the bounds of the cell.
+    Add(i32),
+    /// Move the data pointer by the provided number of cells.
+    Move(isize),
+    /// Set the current cell to zero.
+    SetZero,
+    /// Move the data pointer by the provided number of cells until it lands on a zero cell.
+    Scan(isize),
+    /// Add the current cell multiplied by the provided factor to the cell at the provided offset
+    /// from the data pointer. Does nothing if the current cell is zero.
+    MulAdd(isize, i32),
+    PutChar,
+    GetChar,
+    /// Jump past the `LoopEnd` at the provided index if the current cell is zero.
+    LoopBeg(Option<usize>),
+    /// Jump back to the `LoopBeg` at the provided index if the current cell is not zero.
+    LoopEnd(Option<usize>),
+    DebugDump,
+    DebugBreakpoint,
+    /// Define the procedure identified by the current cell as the instructions up to the
+    /// `ProcEnd` at the provided index, and jump past it.
+    ProcBeg(Option<usize>),
+    /// Return from the procedure being executed to the instruction following its call.
+    ProcEnd(Option<usize>),
+    /// Call the procedure identified by the current cell.
+    ProcCall,
+    /// Fork a new thread of execution, see `scheduler::Thread`.
+    Fork,
+    /// End the program, see `dialect::Extension::Ext1`.
+    End,
+    /// Copy the current cell into the storage cell.
+    Store,
+    /// Copy the storage cell into the current cell.
+    Load,
+    /// Replace the current cell with the result of a bitwise operation on it.
+    Bitwise(BitOp),
+    /// Set the current cell to a pseudo-random byte, see `dialect::Extension::Random`.
+    Random,
+    /// Set the current cell to the time since execution began, see `dialect::Extension::Time`.
+    Clock,
+    /// Switch to the next tape, see `dialect::Extension::Multitape`.
+    NextTape,
+    /// Switch to the previous tape.
+    PrevTape,
+    /// Move the data pointer up a row, see `dialect::Extension::Grid`.
+    RowUp,
+    /// Move the data pointer down a row.
+    RowDown,
+}
+
+
+/// Bitwise operations of the Extended Type I extension, see `Instruction::Bitwise`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BitOp {
+    /// Shift right by one bit.
+    ShiftRight,
+    /// Shift left by one bit.
+    ShiftLeft,
+    Not,
+    /// Exclusive or with the storage cell.
+    Xor,
+    /// And with the storage cell.
+    And,
+    /// Or with the storage cell.
+    Or,
+}
+
+impl BitOp {
+    /// Apply the operation to `cell`, with `storage` as the other operand of binary operations.
+    pub fn apply<C: Cell>(self, cell: C, storage: C) -> C {
+        match self {
+            BitOp::ShiftRight => cell >> 1,
+            BitOp::ShiftLeft => cell << 1,
+            BitOp::Not => !cell,
+            BitOp::Xor => cell ^ storage,
+            BitOp::And => cell & storage,
+            BitOp::Or => cell | storage,
+        }
+    }
+
+    /// The token that the operation is compiled from.
+    pub fn token(self) -> Token {
+        match self {
+            BitOp::ShiftRight => Token::ShiftRight,
+            BitOp::ShiftLeft => Token::ShiftLeft,
+            BitOp::Not => Token::Not,
+            BitOp::Xor => Token::Xor,
+            BitOp::And => Token::And,
+            BitOp::Or => Token::Or,
+        }
+    }
+}
+
+impl From<Token> for Instruction {
+    fn from(token: Token) -> Self {
+        match token {
+            Token::PtrInc => Instruction::Move(1),
+            Token::PtrDec => Instruction::Move(-1),
+            Token::ValInc => Instruction::Add(1),
+            Token::ValDec => Instruction::Add(-1),
+            Token::PutChar => Instruction::PutChar,
+            Token::GetChar => Instruction::GetChar,
+            Token::LoopBeg => Instruction::LoopBeg(None),
+            Token::LoopEnd => Instruction::LoopEnd(None),
+            Token::DebugDump => Instruction::DebugDump,
+            Token::DebugBreakpoint => Instruction::DebugBreakpoint,
+            Token::ProcBeg => Instruction::ProcBeg(None),
+            Token::ProcEnd => Instruction::ProcEnd(None),
+            Token::ProcCall => Instruction::ProcCall,
+            Token::Fork => Instruction::Fork,
+            Token::End => Instruction::End,
+            Token::Store => Instruction::Store,
+            Token::Load => Instruction::Load,
+            Token::ShiftRight => Instruction::Bitwise(BitOp::ShiftRight),
+            Token::ShiftLeft => Instruction::Bitwise(BitOp::ShiftLeft),
+            Token::Not => Instruction::Bitwise(BitOp::Not),
+            Token::Xor => Instruction::Bitwise(BitOp::Xor),
+            Token::And => Instruction::Bitwise(BitOp::And),
+            Token::Or => Instruction::Bitwise(BitOp::Or),
+            Token::Random => Instruction::Random,
+            Token::Clock => Instruction::Clock,
+            Token::NextTape => Instruction::NextTape,
+            Token::PrevTape => Instruction::PrevTape,
+            Token::RowUp => Instruction::RowUp,
+            Token::RowDown => Instruction::RowDown,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Add(n) if *n < 0 => write!(f, "-{}", n.unsigned_abs()),
+            Instruction::Add(n) => write!(f, "+{}", n),
+            Instruction::Move(n) if *n < 0 => write!(f, "<{}", n.unsigned_abs()),
+            Instruction::Move(n) => write!(f, ">{}", n),
+            Instruction::SetZero => write!(f, "zero"),
+            Instruction::Scan(n) if *n < 0 => write!(f, "scan <{}", n.unsigned_abs()),
+            Instruction::Scan(n) => write!(f, "scan >{}", n),
+            Instruction::MulAdd(o, k) if *o < 0 => write!(f, "muladd <{} *{}", o.unsigned_abs(), k),
+            Instruction::MulAdd(o, k) => write!(f, "muladd >{} *{}", o, k),
+            Instruction::PutChar => write!(f, "{}", Token::PutChar),
+            Instruction::GetChar => write!(f, "{}", Token::GetChar),
+            Instruction::LoopBeg(Some(i)) => write!(f, "[ -> {}", i),
+            Instruction::LoopBeg(None) => write!(f, "{}", Token::LoopBeg),
+            Instruction::LoopEnd(Some(i)) => write!(f, "] -> {}", i),
+            Instruction::LoopEnd(None) => write!(f, "{}", Token::LoopEnd),
+            Instruction::DebugDump => write!(f, "{}", Token::DebugDump),
+            Instruction::DebugBreakpoint => write!(f, "{}", Token::DebugBreakpoint),
+            Instruction::ProcBeg(Some(i)) => write!(f, "( -> {}", i),
+            Instruction::ProcBeg(None) => write!(f, "{}", Token::ProcBeg),
+            Instruction::ProcEnd(Some(i)) => write!(f, ") -> {}", i),
+            Instruction::ProcEnd(None) => write!(f, "{}", Token::ProcEnd),
+            Instruction::ProcCall => write!(f, "{}", Token::ProcCall),
+            Instruction::Fork => write!(f, "{}", Token::Fork),
+            Instruction::End => write!(f, "{}", Token::End),
+            Instruction::Store => write!(f, "{}", Token::Store),
+            Instruction::Load => write!(f, "{}", Token::Load),
+            Instruction::Bitwise(op) => write!(f, "{}", op.token()),
+            Instruction::Random => write!(f, "{}", Token::Random),
+            Instruction::Clock => write!(f, "{}", Token::Clock),
+            Instruction::NextTape => write!(f, "{}", Token::NextTape),
+            Instruction::PrevTape => write!(f, "{}", Token::PrevTape),
+            Instruction::RowUp => write!(f, "{}", Token::RowUp),
+            Instruction::RowDown => write!(f, "{}", Token::RowDown),
+        }
+    }
+}
+
+
+/// A sequence of `Instruction`s with all loop jump targets resolved.
+///
+/// Each instruction remembers the position of the token it was compiled from such that errors
+/// can be reported against the source even after optimization.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+    positions: Vec<usize>,
+    /// Location in the source of each token, empty if the program was not parsed from source.
+    locations: Vec<Location>,
+    source: String,
+    /// Map from the source back to the files it was preprocessed from, if it was.
+    #[cfg(feature = "std")]
+    source_map: Option<SourceMap>,
+}
+
+impl fmt::Display for Program {
+    /// Render the program as a listing of instructions, one per line, alongside the index of each
+    /// instruction and its position in the source.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            writeln!(f, "{:>6} {:>6}  {}", i, self.position(i), instruction)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl Program {
+    /// Parse a program from source, ignoring any characters that are not valid commands.
+    pub fn parse(s: &str) -> Self {
+        let (tokens, locations): (Vec<Token>, Vec<Location>) =
+            Token::locate_str(s).into_iter().unzip();
+        Self { locations, source: s.to_string(), ..Self::from_tokens(&tokens) }
+    }
+
+    /// Parse a program from source written in `dialect`, see `dialect::Dialect`.
+    pub fn parse_dialect(s: &str, dialect: &dyn Dialect) -> Self {
+        let (tokens, locations): (Vec<Token>, Vec<Location>) =
+            dialect.locate(s).into_iter().unzip();
+        Self { locations, source: s.to_string(), ..Self::from_tokens(&tokens) }
+    }
+
+    /// Compile a sequence of tokens with one instruction per token.
+    pub fn from_tokens(tokens: &[Token]) -> Self {
+        let instructions = tokens.iter().map(|&t| Instruction::from(t)).collect();
+        let program = Self::link(instructions, (0..tokens.len()).collect());
+        log::debug!("parsed {} instructions", program.len());
+        program
+    }
+
+    /// Assemble a program from instructions and the source positions they correspond to,
+    /// matching loop brackets and procedure parentheses in a single pass. Any existing jump
+    /// targets are discarded.
+    pub fn link(mut instructions: Vec<Instruction>, positions: Vec<usize>) -> Self {
+        let mut open_loops: Vec<usize> = Vec::new();
+        let mut open_procedures: Vec<usize> = Vec::new();
+        for i in 0..instructions.len() {
+            match instructions[i] {
+                Instruction::LoopBeg(_) => {
+                    open_loops.push(i);
+                    instructions[i] = Instruction::LoopBeg(None);
+                },
+                Instruction::LoopEnd(_) => match open_loops.pop() {
+                    Some(beg) => {
+                        instructions[beg] = Instruction::LoopBeg(Some(i));
+                        instructions[i] = Instruction::LoopEnd(Some(beg));
+                    },
+                    None => instructions[i] = Instruction::LoopEnd(None),
+                },
+                Instruction::ProcBeg(_) => {
+                    open_procedures.push(i);
+                    instructions[i] = Instruction::ProcBeg(None);
+                },
+                Instruction::ProcEnd(_) => match open_procedures.pop() {
+                    Some(beg) => {
+                        instructions[beg] = Instruction::ProcBeg(Some(i));
+                        instructions[i] = Instruction::ProcEnd(Some(beg));
+                    },
+                    None => instructions[i] = Instruction::ProcEnd(None),
+                },
+                _ => {},
+            };
+        }
+        Self {
+            instructions,
+            positions,
+            locations: Vec::new(),
+            source: String::new(),
+            #[cfg(feature = "std")]
+            source_map: None,
+        }
+    }
+
+    /// Attach the source of `original` to this program, which must have been compiled from the
+    /// same tokens, e.g. by optimizing `original`.
+    pub fn with_source_of(mut self, original: &Program) -> Self {
+        self.locations = original.locations.clone();
+        self.source = original.source.clone();
+        #[cfg(feature = "std")]
+        {
+            self.source_map = original.source_map.clone();
+        }
+        self
+    }
+
+    /// Report errors in this program, which must have been parsed from preprocessed source,
+    /// against the files the source was preprocessed from, see `preprocess::preprocess`.
+    #[cfg(feature = "std")]
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    /// Check that every loop bracket and procedure parenthesis in the program has a match,
+    /// otherwise failing with an error for the unmatched bracket, or `BfError::Multiple` if there
+    /// are several.
+    pub fn verify(&self) -> Result<(), BfError> {
+        let mut errors: Vec<BfError> = self.instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, instruction)| match instruction {
+                Instruction::LoopBeg(None) => Some(BfError::UnmatchedOpenBracket(self.context(i))),
+                Instruction::LoopEnd(None) => Some(BfError::UnmatchedCloseBracket(self.context(i))),
+                Instruction::ProcBeg(None) => Some(BfError::UnmatchedOpenParen(self.context(i))),
+                Instruction::ProcEnd(None) => Some(BfError::UnmatchedCloseParen(self.context(i))),
+                _ => None,
+            })
+            .collect();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(BfError::Multiple(errors)),
+        }
+    }
+
+    /// The compiled instructions of this program.
+    pub fn instructions(&self) -> &[Instruction] { &self.instructions }
+
+    /// Get the instruction at the provided index, if it exists.
+    pub fn get(&self, i: usize) -> Option<&Instruction> { self.instructions.get(i) }
+
+    /// Position in the source program (counted in tokens) of the instruction at index `i`.
+    pub fn position(&self, i: usize) -> usize {
+        self.positions.get(i).copied().unwrap_or_else(|| self.positions.last().map_or(0, |p| p + 1))
+    }
+
+    /// The source the program was parsed from, empty if it was not parsed from source.
+    pub fn source(&self) -> &str { &self.source }
+
+    /// Location in the source of each token, indexed by the positions returned by `position`.
+    /// Empty if the program was not parsed from source.
+    pub fn locations(&self) -> &[Location] { &self.locations }
+
+    /// Map from the source back to the files it was preprocessed from, `None` if it was not.
+    #[cfg(feature = "std")]
+    pub fn source_map(&self) -> Option<&SourceMap> { self.source_map.as_ref() }
+
+    /// Location in the source of the instruction at index `i`, before mapping it back to the file
+    /// it was preprocessed from, see `context`. A program that was not parsed from source is
+    /// treated as a single line of tokens.
+    pub fn location(&self, i: usize) -> Location {
+        let position = self.position(i);
+        match (self.locations.get(position), self.locations.last()) {
+            (Some(&location), _) => location,
+            // one past the end of the program
+            (None, Some(&last)) => Location { column: last.column + 1, ..last },
+            (None, None) => Location { line: 1, column: position + 1 },
+        }
+    }
+
+    /// The file the instruction at index `i` was preprocessed from and its location there, if the
+    /// program was preprocessed.
+    #[cfg(feature = "std")]
+    fn origin(&self, i: usize) -> Option<(&SourceFile, Location)> {
+        let source_map = self.source_map.as_ref()?;
+        let (file, location) = source_map.origin(self.location(i))?;
+        Some((&source_map.files()[file], location))
+    }
+
+    /// The line of source containing the instruction at index `i` with a caret under the
+    /// instruction, or `None` if the program was not parsed from source.
+    pub fn excerpt(&self, i: usize) -> Option<String> { self.context(i).excerpt }
+
+    /// Context for an error caused by the instruction at index `i`, in the file it was written in
+    /// if the program was preprocessed.
+    pub fn context(&self, i: usize) -> SourceContext {
+        #[cfg(feature = "std")]
+        if let Some((file, location)) = self.origin(i) {
+            return SourceContext {
+                file: Some(file.name.clone()),
+                location,
+                excerpt: excerpt(&file.source, location),
+            };
+        }
+        let location = self.location(i);
+        SourceContext { file: None, location, excerpt: excerpt(&self.source, location) }
+    }
+
+    /// Whether the program defines or calls procedures, see `dialect::PBrain`.
+    pub fn has_procedures(&self) -> bool {
+        self.instructions.iter().any(|instruction| matches!(
+            instruction,
+            Instruction::ProcBeg(_) | Instruction::ProcEnd(_) | Instruction::ProcCall,
+        ))
+    }
+
+    /// Whether the program forks threads, see `dialect::BrainFork`.
+    pub fn has_forks(&self) -> bool { self.instructions.contains(&Instruction::Fork) }
+
+    /// Whether the program uses the commands of an extension, see `dialect::Extension`.
+    pub fn has_extensions(&self) -> bool {
+        self.instructions.iter().any(|instruction| matches!(
+            instruction,
+            Instruction::End
+            | Instruction::Store
+            | Instruction::Load
+            | Instruction::Bitwise(_)
+            | Instruction::Random
+            | Instruction::Clock
+            | Instruction::NextTape
+            | Instruction::PrevTape
+            | Instruction::RowUp
+            | Instruction::RowDown,
+        ))
+    }
+
+    pub fn len(&self) -> usize { self.instructions.len() }
+
+    pub fn is_empty(&self) -> bool { self.instructions.is_empty() }
+}
+
+
+/// The line of `source` containing `location` with a caret under it, or `None` if there is no
+/// such line.
+fn excerpt(source: &str, location: Location) -> Option<String> {
+    let line = source.lines().nth(location.line - 1)?;
+    // keep tabs such that the caret lines up regardless of tab width
+    let indent: String = line
+        .chars()
+        .take(location.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let gutter = " ".repeat(location.line.to_string().len());
+    Some(format!("{} | {}\n{} | {}^", location.line, line, gutter, indent))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_jump_targets() {
+        let program = Program::parse("+[>[-]<]");
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::Add(1),
+                Instruction::LoopBeg(Some(7)),
+                Instruction::Move(1),
+                Instruction::LoopBeg(Some(5)),
+                Instruction::Add(-1),
+                Instruction::LoopEnd(Some(3)),
+                Instruction::Move(-1),
+                Instruction::LoopEnd(Some(1)),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // brackets are matched with a stack of their own rather than by recursion, such that
+        // nesting is limited by memory rather than by the call stack
+        let depth = 100_000;
+        let program = Program::parse(&format!("+{}-{}", "[".repeat(depth), "]".repeat(depth)));
+        assert_eq!(program.verify(), Ok(()));
+        assert_eq!(program.get(1), Some(&Instruction::LoopBeg(Some(2 * depth + 1))));
+        assert_eq!(program.get(depth), Some(&Instruction::LoopBeg(Some(depth + 2))));
+        assert_eq!(program.get(depth + 2), Some(&Instruction::LoopEnd(Some(depth))));
+    }
+
+    #[test]
+    fn test_unmatched_brackets() {
+        let program = Program::parse("][");
+        assert_eq!(
+            program.instructions(),
+            &[Instruction::LoopEnd(None), Instruction::LoopBeg(None)],
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(Program::parse("+[>[-]<]").verify(), Ok(()));
+        let expected = "']' at line 1, col 1 has no matching '['\n1 | ] [\n  | ^\n\
+            '[' at line 1, col 3 has no matching ']'\n1 | ] [\n  |   ^";
+        assert_eq!(Program::parse("] [").verify().unwrap_err().to_string(), expected);
+        let error = Program::parse("[").verify().unwrap_err();
+        assert!(matches!(error, BfError::UnmatchedOpenBracket(_)));
+        assert_eq!(error.context().unwrap().location, Location { line: 1, column: 1 });
+    }
+
+    #[test]
+    fn test_display() {
+        let rendered: Vec<String> = Program::parse("[.<]")
+            .instructions()
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(vec!["[ -> 3", ".", "<1", "] -> 0"], rendered);
+    }
+
+    #[test]
+    fn test_location() {
+        let program = Program::parse("+\n\t++ [\n-]");
+        assert_eq!(program.location(3), Location { line: 2, column: 5 });
+        assert_eq!(program.location(5), Location { line: 3, column: 2 });
+        assert_eq!(program.location(6), Location { line: 3, column: 3 });
+        assert_eq!(program.excerpt(3), Some("2 | \t++ [\n  | \t   ^".to_string()));
+        let program = Program::from_tokens(&Token::parse_str("+\n+"));
+        assert_eq!(program.location(1), Location { line: 1, column: 2 });
+        assert_eq!(program.excerpt(1), None);
+    }
+
+    #[test]
+    fn test_source_map() {
+        let source = "#define left(n) <*n\n+ left(2)";
+        let (expanded, source_map) =
+            crate::preprocess::preprocess(source, "main.bf", std::path::Path::new("")).unwrap();
+        let program = Program::parse(&expanded).with_source_map(source_map);
+        assert_eq!(program.location(2), Location { line: 2, column: 4 });
+        let context = crate::optimizer::optimize(&program).context(1);
+        assert_eq!(context.file.as_deref(), Some("main.bf"));
+        assert_eq!(context.location, Location { line: 2, column: 3 });
+        assert_eq!(context.excerpt, Some("2 | + left(2)\n  |   ^".to_string()));
+        assert_eq!(Program::parse(&expanded).context(1).file, None);
+    }
+
+    #[test]
+    fn test_parse_dialect() {
+        let program = Program::parse_dialect("Ook! Ook?\n  Ook? Ook!", &crate::dialect::Ook);
+        assert_eq!(program.instructions(), Program::parse("[]").instructions());
+        assert_eq!(program.excerpt(1), Some("2 |   Ook? Ook!\n  |   ^".to_string()));
+    }
+
+    #[test]
+    fn test_procedures() {
+        let program = Program::parse_dialect("(+[-]):)", &crate::dialect::PBrain);
+        assert_eq!(program.get(0), Some(&Instruction::ProcBeg(Some(5))));
+        assert_eq!(program.get(5), Some(&Instruction::ProcEnd(Some(0))));
+        assert!(program.has_procedures() && !Program::parse("+").has_procedures());
+        assert!(!program.has_forks());
+        let error = program.verify().unwrap_err();
+        assert!(matches!(error, BfError::UnmatchedCloseParen(_)));
+        assert_eq!(error.context().unwrap().location, Location { line: 1, column: 8 });
+    }
+
+    #[test]
+    fn test_listing() {
+        let listing = Program::parse(" + [-]").to_string();
+        assert_eq!(
+            "     0      0  +1\n     1      1  [ -> 3\n     2      2  -1\n     3      3  ] -> 1\n",
+            listing,
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Pseudo-random numbers that are the same for the same seed, see `Xorshift`.
+
+
+/// A xorshift* generator, fast and good enough for program input and games, but not for anything
+/// that must be unpredictable.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    /// A generator producing the same numbers every time for the same `seed`, including zero.
+    pub fn new(seed: u64) -> Self {
+        // a zero state would stay zero, so the state is made odd
+        Xorshift { state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        // scrambled, as the state alone is poorly mixed after a small seed
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number below `n`, which must not be zero.
+    pub fn below(&mut self, n: u64) -> u64 { self.next_u64() % n }
+
+    pub fn next_byte(&mut self) -> u8 { (self.next_u64() >> 56) as u8 }
+}
+
+impl Default for Xorshift {
+    fn default() -> Self { Xorshift::new(0) }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_xorshift() {
+        let bytes = |seed| {
+            let mut rng = Xorshift::new(seed);
+            (0..16).map(|_| rng.next_byte()).collect::<Vec<u8>>()
+        };
+        assert_eq!(bytes(7), bytes(7));
+        assert_ne!(bytes(7), bytes(8));
+        assert!(bytes(0).iter().any(|&b| b != 0));
+        assert!(Xorshift::new(3).below(10) < 10);
+    }
+}
@@ -1,25 +1,118 @@
+#[cfg(not(target_os = "wasi"))]
 extern crate rustyline;
 
 use std::default::Default;
+#[cfg(target_os = "wasi")]
+use std::io::{self, Write};
 use std::iter::Iterator;
+use std::mem;
 
+#[cfg(not(target_os = "wasi"))]
 use rustyline::Editor;
+#[cfg(not(target_os = "wasi"))]
 use rustyline::error::ReadlineError;
 
-use crate::token::Token;
+use crate::cell::Cell;
+use crate::debugger::Breakpoint;
+use crate::expr::Expr;
+use crate::program::Program;
 
 
+/// Number of cells shown on either side of the current cell by the `p` command.
+const PRINT_RADIUS: usize = 8;
+
+
+#[derive(Debug, PartialEq)]
 pub enum ReplResult<T> {
-    Command(Token),
+    Program(Program),
     // Continue,
+    /// Execute the given number of instructions of the program at the breakpoint.
+    Step(usize),
+    /// Step backwards over the given number of instructions, see `journal::Journal`.
+    Undo(usize),
+    /// Print the cells of the tape around the current cell, see `render_tape`.
+    Print,
+    /// Set the cell at an index of the tape to a value, wrapping at the bounds of the cell. Indices
+    /// count from the cell the data pointer started on, such that cells to its left are negative.
+    SetCell(isize, i64),
+    /// Move the data pointer to an index of the tape, counted as for `SetCell`.
+    SetPointer(isize),
+    /// Add a breakpoint, see `debugger::Breakpoint`.
+    Break(Breakpoint),
+    /// List every breakpoint alongside its number, see `debugger::Debugger`.
+    ListBreakpoints,
+    /// Enable (`true`) or disable the breakpoint with the given number.
+    EnableBreakpoint(usize, bool),
+    /// Delete the breakpoint with the given number.
+    DeleteBreakpoint(usize),
+    /// Print the value of an expression over the tape, see `expr::Expr`.
+    Query(Expr),
+    /// Save the tape and pointers to the named file, see `snapshot::Snapshot`.
+    Save(String),
+    /// Replace the tape and pointers with those saved in the named file.
+    Load(String),
     Quit,
     Error(T),
 }
 
 
-pub struct ReplInstance {
+/// Why `LineEditor::readline` did not return a line.
+#[derive(Debug, Clone, PartialEq)]
+enum LineError {
+    /// The end of input, e.g. from Ctrl-D.
+    Eof,
+    /// Ctrl-C, which never interrupts reading on WASI.
+    #[cfg_attr(target_os = "wasi", allow(dead_code))]
+    Interrupted,
+    Failed(String),
+}
+
+
+/// Reads the lines entered in an interactive session, with editing and history at a terminal, or
+/// as plain lines of stdin on WASI, which has no terminal to edit them in.
+struct LineEditor {
+    #[cfg(not(target_os = "wasi"))]
     editor: Editor<()>,
-    queue: Vec<Token>,
+}
+
+impl LineEditor {
+    #[cfg(not(target_os = "wasi"))]
+    fn new() -> Self { LineEditor { editor: Editor::<()>::new() } }
+
+    #[cfg(target_os = "wasi")]
+    fn new() -> Self { LineEditor {} }
+
+    #[cfg(not(target_os = "wasi"))]
+    fn readline(&mut self, prompt: &str) -> Result<String, LineError> {
+        self.editor.readline(prompt).map_err(|e| match e {
+            ReadlineError::Eof => LineError::Eof,
+            ReadlineError::Interrupted => LineError::Interrupted,
+            e => LineError::Failed(e.to_string()),
+        })
+    }
+
+    #[cfg(target_os = "wasi")]
+    fn readline(&mut self, prompt: &str) -> Result<String, LineError> {
+        print!("{}", prompt);
+        io::stdout().flush().map_err(|e| LineError::Failed(e.to_string()))?;
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => Err(LineError::Eof),
+            Ok(_) => Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string()),
+            Err(e) => Err(LineError::Failed(e.to_string())),
+        }
+    }
+
+    #[cfg(not(target_os = "wasi"))]
+    fn add_history_entry(&mut self, line: &str) { self.editor.add_history_entry(line); }
+
+    #[cfg(target_os = "wasi")]
+    fn add_history_entry(&mut self, _line: &str) {}
+}
+
+
+pub struct ReplInstance {
+    editor: LineEditor,
 }
 
 
@@ -30,13 +123,28 @@ impl Default for ReplInstance {
 You have entered an interactive session. All regular commands are available.
 
 Commands:
-    'c' : Continue execution at the command following this breakpoint
-    'q' : Exit interpreter
+    'c'                   : Continue execution at the command following this breakpoint
+    's'                   : Step through the next instruction of the program ('n' also works)
+    's <n>'               : Step through the next n instructions of the program
+    'rs'                  : Step backwards over the last instruction (requires --history)
+    'undo <n>'            : Step backwards over the last n instructions
+    'p'                   : Print the tape around the current cell ('print' also works)
+    'set <index> <value>' : Set the value of a cell on the tape (negative left of the start)
+    'ptr <index>'         : Move the data pointer to a cell on the tape
+    'break <condition>'   : Pause when a condition like 'cell[5] == 42' becomes true
+    'watch <operand>'     : Pause when an operand like 'cell[5]' or 'ptr' changes
+    'info breakpoints'    : List the breakpoints by number, each '%' of the program included
+    'disable <n>'         : Stop breakpoint n from pausing execution until it is enabled
+    'enable <n>'          : Let breakpoint n pause execution again
+    'delete <n>'          : Remove breakpoint n for good
+    '? <expression>'      : Print the value of e.g. 'cell[3] + cell[4]' or 'sum(cell[0..10])'
+    'save <file>'         : Save the tape and position in the program to a file
+    'load <file>'         : Resume from the tape and position saved in a file
+    'q'                   : Exit interpreter
 "
         );
         Self {
-            editor: Editor::<()>::new(),
-            queue: Vec::new(),
+            editor: LineEditor::new(),
         }
     }
 }
@@ -45,23 +153,353 @@ impl Iterator for ReplInstance {
     type Item = ReplResult<String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.queue.is_empty() {
+        loop {
             let input_line = self.editor.readline("bfi $ ");
-            match input_line {
+            return match input_line {
                 // TODO: merge these two arms?
-                Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => Some(ReplResult::Quit),
-                Ok(line) if line == "q" => Some(ReplResult::Quit),
+                Err(LineError::Eof) | Err(LineError::Interrupted) => Some(ReplResult::Quit),
+                Ok(line) if line.trim() == "q" => Some(ReplResult::Quit),
                 // exits cleanly out of the REPL by ending iteration
-                Ok(line) if line == "c" => None,
+                Ok(line) if line.trim() == "c" => None,
                 Ok(line) => {
                     self.editor.add_history_entry(line.as_str());
-                    self.queue.extend(Token::parse_str(line.as_str()).iter());
-                    self.next()
+                    match parse_command(line.as_str()) {
+                        Some(Ok(command)) => Some(command),
+                        Some(Err(e)) => {
+                            println!("{}", e);
+                            continue;
+                        },
+                        None => Some(ReplResult::Program(Program::parse(line.as_str()))),
+                    }
                 },
-                Err(e) => Some(ReplResult::Error(format!("{}", e))),
-            }
-        } else {
-            Some(ReplResult::Command(self.queue.remove(0)))
+                Err(LineError::Failed(e)) => Some(ReplResult::Error(e)),
+            };
+        }
+    }
+}
+
+
+/// Command entered in a standalone interactive session, see `Session`.
+#[derive(Debug, PartialEq)]
+pub enum SessionCommand {
+    /// Program to execute against the tape, spanning as many lines as it took to close its loops.
+    Program(Program),
+    /// Clear the tape and return the data pointer to the first cell.
+    Reset,
+    /// Print the cells of the tape around the current cell, see `render_tape`.
+    Dump,
+    /// Execute the program in the named file against the tape.
+    Load(String),
+    /// Save the tape to the named file, see `snapshot::Snapshot`.
+    Save(String),
+    /// Replace the tape with the one saved in the named file.
+    Restore(String),
+    /// Print the value of an expression over the tape, see `expr::Expr`.
+    Query(Expr),
+    Error(String),
+}
+
+
+static SESSION_HELP: &str = "\
+Commands:
+    ':reset'          : Clear the tape and return to its first cell
+    ':dump'           : Print the tape around the current cell
+    ':load <file>'    : Execute a program file against the tape
+    ':save <file>'    : Save the tape to a file
+    ':restore <file>' : Replace the tape with one saved to a file
+    '? <expression>'  : Print the value of e.g. 'cell[3] + cell[4]' or 'sum(cell[0..10])'
+    ':help'           : Show this message
+    ':quit'           : Exit interpreter (Ctrl-D also works)
+";
+
+
+/// Prompt of a `Session` waiting for a program, unless it is given another.
+pub const DEFAULT_PROMPT: &str = "bfi > ";
+
+
+/// Standalone interactive session, in which every program entered operates on the same tape.
+///
+/// Lines are accumulated until the brackets of the program entered are balanced, such that loops
+/// can be written across multiple lines. Iteration ends when the session is exited.
+pub struct Session {
+    editor: LineEditor,
+    /// Shown when waiting for a program, see `with_prompt`.
+    prompt: String,
+}
+
+impl Session {
+    /// Like `default`, prompting for each program with `prompt` rather than `DEFAULT_PROMPT`.
+    /// The lines continuing a program are prompted with `...` lined up with the end of `prompt`.
+    pub fn with_prompt(prompt: &str) -> Self {
+        Session { prompt: prompt.to_string(), ..Session::default() }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        println!("You have entered an interactive session. Enter a program to execute it.\n");
+        println!("{}", SESSION_HELP);
+        Self {
+            editor: LineEditor::new(),
+            prompt: DEFAULT_PROMPT.to_string(),
         }
     }
 }
+
+impl Iterator for Session {
+    type Item = SessionCommand;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut source = String::new();
+        let width = self.prompt.chars().count().saturating_sub(1);
+        let continuation = format!("{:>1$} ", "...", width);
+        loop {
+            let prompt = if source.is_empty() { &self.prompt } else { &continuation };
+            let line = match self.editor.readline(prompt) {
+                Ok(line) => line,
+                // abandon a partially entered program rather than exiting
+                Err(LineError::Interrupted) if !source.is_empty() => {
+                    source.clear();
+                    continue;
+                },
+                Err(LineError::Eof) | Err(LineError::Interrupted) => return None,
+                Err(LineError::Failed(e)) => return Some(SessionCommand::Error(e)),
+            };
+            if source.is_empty() && line.trim() == ":help" {
+                println!("{}", SESSION_HELP);
+                continue;
+            };
+            if source.is_empty() && line.trim().starts_with(':') {
+                self.editor.add_history_entry(line.as_str());
+                match parse_session_command(line.trim()) {
+                    Ok(Some(command)) => return Some(command),
+                    Ok(None) => return None,
+                    Err(e) => println!("{}", e),
+                };
+                continue;
+            };
+            let expression = line.trim_start().strip_prefix('?');
+            if let Some(expression) = expression.filter(|_| source.is_empty()) {
+                self.editor.add_history_entry(line.as_str());
+                match expression.parse() {
+                    Ok(expr) => return Some(SessionCommand::Query(expr)),
+                    Err(e) => println!("{}", e),
+                };
+                continue;
+            };
+            source.push_str(&line);
+            source.push('\n');
+            if bracket_depth(&source) <= 0 {
+                self.editor.add_history_entry(source.trim_end());
+                return Some(SessionCommand::Program(Program::parse(&source)));
+            };
+        }
+    }
+}
+
+
+/// Parse a line starting with `:` into a session command, or `None` if the session should exit.
+fn parse_session_command(line: &str) -> Result<Option<SessionCommand>, String> {
+    let (command, argument) = match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    };
+    match (command, argument) {
+        (":reset", "") => Ok(Some(SessionCommand::Reset)),
+        (":dump", "") => Ok(Some(SessionCommand::Dump)),
+        (":load", "") => Err("usage: :load <file>".to_string()),
+        (":load", filename) => Ok(Some(SessionCommand::Load(filename.to_string()))),
+        (":save", "") => Err("usage: :save <file>".to_string()),
+        (":save", filename) => Ok(Some(SessionCommand::Save(filename.to_string()))),
+        (":restore", "") => Err("usage: :restore <file>".to_string()),
+        (":restore", filename) => Ok(Some(SessionCommand::Restore(filename.to_string()))),
+        (":quit", "") | (":q", "") => Ok(None),
+        (other, _) => Err(format!("unknown command '{}', see ':help'", other)),
+    }
+}
+
+
+/// Number of loops left open at the end of a program.
+fn bracket_depth(source: &str) -> isize {
+    source.chars().fold(0, |depth, c| match c {
+        '[' => depth + 1,
+        ']' => depth - 1,
+        _ => depth,
+    })
+}
+
+
+/// Parse a line naming one of the debugging commands, e.g. `s 3` or `set 0 65`. Returns `None` if
+/// the line is not a command, i.e. if it should be executed as a program.
+fn parse_command(line: &str) -> Option<Result<ReplResult<String>, String>> {
+    if let Some(expression) = line.trim_start().strip_prefix('?') {
+        return Some(expression.parse().map(ReplResult::Query));
+    };
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["s"] | ["n"] => Ok(ReplResult::Step(1)),
+        ["s", count] => parse_number(count, "step count").map(ReplResult::Step),
+        ["rs"] => Ok(ReplResult::Undo(1)),
+        ["undo", count] => parse_number(count, "step count").map(ReplResult::Undo),
+        ["undo", ..] => Err("usage: undo <n>".to_string()),
+        ["p"] | ["print"] => Ok(ReplResult::Print),
+        ["set", index, value] => parse_number(index, "index").and_then(|index| {
+            parse_number(value, "value").map(|value| ReplResult::SetCell(index, value))
+        }),
+        ["set", ..] => Err("usage: set <index> <value>".to_string()),
+        ["ptr", index] => parse_number(index, "index").map(ReplResult::SetPointer),
+        ["ptr", ..] => Err("usage: ptr <index>".to_string()),
+        ["break", condition @ ..] if !condition.is_empty() => {
+            condition.join(" ").parse().map(ReplResult::Break)
+        },
+        ["watch", operand @ ..] if !operand.is_empty() => {
+            format!("watch {}", operand.join(" ")).parse().map(ReplResult::Break)
+        },
+        ["info", "breakpoints"] | ["info", "b"] => Ok(ReplResult::ListBreakpoints),
+        ["info", ..] => Err("usage: info breakpoints".to_string()),
+        ["enable", number] => {
+            parse_number(number, "breakpoint").map(|n| ReplResult::EnableBreakpoint(n, true))
+        },
+        ["enable", ..] => Err("usage: enable <n>".to_string()),
+        ["disable", number] => {
+            parse_number(number, "breakpoint").map(|n| ReplResult::EnableBreakpoint(n, false))
+        },
+        ["disable", ..] => Err("usage: disable <n>".to_string()),
+        ["delete", number] => parse_number(number, "breakpoint").map(ReplResult::DeleteBreakpoint),
+        ["delete", ..] => Err("usage: delete <n>".to_string()),
+        ["save", filename] => Ok(ReplResult::Save(filename.to_string())),
+        ["save", ..] => Err("usage: save <file>".to_string()),
+        ["load", filename] => Ok(ReplResult::Load(filename.to_string())),
+        ["load", ..] => Err("usage: load <file>".to_string()),
+        _ => return None,
+    };
+    Some(command)
+}
+
+
+fn parse_number<T: std::str::FromStr>(s: &str, name: &str) -> Result<T, String> {
+    s.parse::<T>().map_err(|_| format!("invalid {} '{}'", name, s))
+}
+
+
+/// Render the cells of the tape within `PRINT_RADIUS` of `data_ptr` as columns holding the index,
+/// hexadecimal, decimal, and ASCII value of each cell, with a caret under the current cell.
+/// Indices count from `origin`, the cell the data pointer started on, such that cells to its left
+/// are negative.
+///
+/// The ASCII value is that of the byte written by `.`, or `.` if that byte is not printable.
+pub fn render_tape<C: Cell>(tape: &[C], data_ptr: usize, origin: usize) -> String {
+    let start = data_ptr.saturating_sub(PRINT_RADIUS);
+    let end = tape.len().min(data_ptr + PRINT_RADIUS + 1);
+    let cells = &tape[start..end];
+    let digits = 2 * mem::size_of::<C>();
+    let rows: [(&str, Vec<String>); 4] = [
+        ("index", (start..end).map(|i| (i as isize - origin as isize).to_string()).collect()),
+        ("hex", cells.iter().map(|c| format!("{:0digits$x}", c, digits = digits)).collect()),
+        ("dec", cells.iter().map(|c| c.to_string()).collect()),
+        ("ascii", cells.iter().map(|c| match c.to_byte() {
+            b if b.is_ascii_graphic() => (b as char).to_string(),
+            _ => ".".to_string(),
+        }).collect()),
+    ];
+    let width = rows.iter().flat_map(|(_, row)| row.iter().map(String::len)).max().unwrap_or(0);
+    let mut rendered = String::new();
+    for (label, row) in rows.iter() {
+        rendered.push_str(&format!("{:>5} ", label));
+        for value in row {
+            rendered.push_str(&format!(" {:>width$}", value, width = width));
+        }
+        rendered.push('\n');
+    }
+    // the caret sits under the last character of the right-aligned current cell
+    let caret = 6 + (data_ptr - start + 1) * (width + 1) - 1;
+    rendered.push_str(&format!("{}^", " ".repeat(caret)));
+    rendered
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("s"), Some(Ok(ReplResult::Step(1))));
+        assert_eq!(parse_command("n"), Some(Ok(ReplResult::Step(1))));
+        assert_eq!(parse_command("s 12"), Some(Ok(ReplResult::Step(12))));
+        assert_eq!(parse_command("s -1"), Some(Err("invalid step count '-1'".to_string())));
+        assert_eq!(parse_command("rs"), Some(Ok(ReplResult::Undo(1))));
+        assert_eq!(parse_command("undo 4"), Some(Ok(ReplResult::Undo(4))));
+        assert!(matches!(parse_command("undo"), Some(Err(_))));
+        assert_eq!(parse_command(" print "), Some(Ok(ReplResult::Print)));
+        assert_eq!(parse_command("set 3 -1"), Some(Ok(ReplResult::SetCell(3, -1))));
+        assert!(matches!(parse_command("set 3"), Some(Err(_))));
+        assert_eq!(parse_command("ptr 7"), Some(Ok(ReplResult::SetPointer(7))));
+        assert_eq!(parse_command("ptr -2"), Some(Ok(ReplResult::SetPointer(-2))));
+        assert!(matches!(parse_command("break cell[2] > 1"), Some(Ok(ReplResult::Break(_)))));
+        assert!(matches!(parse_command("watch cell[2]"), Some(Ok(ReplResult::Break(_)))));
+        assert!(matches!(parse_command("break cell[2]"), Some(Err(_))));
+        assert_eq!(parse_command("info breakpoints"), Some(Ok(ReplResult::ListBreakpoints)));
+        assert_eq!(parse_command("disable 2"), Some(Ok(ReplResult::EnableBreakpoint(2, false))));
+        assert_eq!(parse_command("enable 2"), Some(Ok(ReplResult::EnableBreakpoint(2, true))));
+        assert_eq!(parse_command("delete 1"), Some(Ok(ReplResult::DeleteBreakpoint(1))));
+        assert_eq!(parse_command("delete x"), Some(Err("invalid breakpoint 'x'".to_string())));
+        assert!(matches!(parse_command("info"), Some(Err(_))));
+        let filename = "state.txt".to_string();
+        assert_eq!(parse_command("save state.txt"), Some(Ok(ReplResult::Save(filename.clone()))));
+        assert_eq!(parse_command("load state.txt"), Some(Ok(ReplResult::Load(filename))));
+        assert!(matches!(parse_command("load"), Some(Err(_))));
+        assert_eq!(parse_command("? ptr"), Some(Ok(ReplResult::Query(Expr::Ptr))));
+        assert!(matches!(parse_command("?sum(cell[0..3])"), Some(Ok(ReplResult::Query(_)))));
+        assert!(matches!(parse_command("?"), Some(Err(_))));
+        assert_eq!(parse_command("+[-]"), None);
+    }
+
+    #[test]
+    fn test_parse_session_command() {
+        assert_eq!(parse_session_command(":reset"), Ok(Some(SessionCommand::Reset)));
+        assert_eq!(parse_session_command(":dump"), Ok(Some(SessionCommand::Dump)));
+        assert_eq!(
+            parse_session_command(":load  my program.bf"),
+            Ok(Some(SessionCommand::Load("my program.bf".to_string()))),
+        );
+        assert!(parse_session_command(":load").is_err());
+        assert_eq!(
+            parse_session_command(":save tape.txt"),
+            Ok(Some(SessionCommand::Save("tape.txt".to_string()))),
+        );
+        assert_eq!(
+            parse_session_command(":restore tape.txt"),
+            Ok(Some(SessionCommand::Restore("tape.txt".to_string()))),
+        );
+        assert_eq!(parse_session_command(":q"), Ok(None));
+        assert!(parse_session_command(":frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_bracket_depth() {
+        assert_eq!(bracket_depth("+[->[-]"), 1);
+        assert_eq!(bracket_depth("+[-]"), 0);
+        assert_eq!(bracket_depth("]"), -1);
+    }
+
+    #[test]
+    fn test_render_tape() {
+        let rendered = render_tape(&[0u8, 72, 105, 10], 1, 0);
+        assert_eq!(rendered, concat!(
+            "index    0   1   2   3\n",
+            "  hex   00  48  69  0a\n",
+            "  dec    0  72 105  10\n",
+            "ascii    .   H   i   .\n",
+            "             ^",
+        ));
+        let tape: Vec<i32> = (0..20).map(|i| -i).collect();
+        let rendered = render_tape(&tape, 19, 0);
+        assert!(rendered.starts_with("index"));
+        assert!(rendered.contains("       11 "));
+        assert!(rendered.contains(" fffffff5 "));
+        assert!(rendered.ends_with(&format!("{}^", " ".repeat(6 + 9 * 9 - 1))));
+        let rendered = render_tape(&[0u8, 1, 2], 0, 2);
+        assert!(rendered.starts_with("index  -2 -1  0\n"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Threads of execution created by the Brainfork `Y` command, and the order in which they run.
+
+use std::collections::VecDeque;
+
+use crate::cell::Cell;
+
+
+/// The state of a single thread of execution, see `dialect::BrainFork`.
+///
+/// Each thread has a tape of its own, copied from the thread that forked it, while the program
+/// and the I/O context are shared by every thread.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Thread<C: Cell> {
+    pub tape: Vec<C>,
+    pub data_ptr: usize,
+    pub program_ptr: usize,
+
+    /// Return addresses of the procedure calls in progress in the thread.
+    pub call_stack: Vec<usize>,
+}
+
+impl<C: Cell> Thread<C> {
+    /// Number of cells the tape of the thread has room for, counted against the memory limit of
+    /// the program along with the tapes of every other thread.
+    pub fn cells(&self) -> usize { self.tape.capacity() }
+}
+
+
+/// Round-robin scheduler of the threads waiting to run while another executes.
+///
+/// The running thread is held by the `ExecutionContext` itself and swapped for the thread that has
+/// waited longest after every instruction, such that every thread advances at the same rate.
+#[derive(Debug, Clone)]
+pub struct Scheduler<C: Cell> {
+    waiting: VecDeque<Thread<C>>,
+}
+
+impl<C: Cell> Default for Scheduler<C> {
+    fn default() -> Self { Scheduler { waiting: VecDeque::new() } }
+}
+
+impl<C: Cell> Scheduler<C> {
+    /// Queue a newly forked thread to run after every thread already waiting.
+    pub fn spawn(&mut self, thread: Thread<C>) { self.waiting.push_back(thread); }
+
+    /// Number of threads waiting to run.
+    pub fn len(&self) -> usize { self.waiting.len() }
+
+    pub fn is_empty(&self) -> bool { self.waiting.is_empty() }
+
+    /// Number of cells the tapes of the waiting threads have room for, see `Thread::cells`.
+    pub fn cells(&self) -> usize { self.waiting.iter().map(Thread::cells).sum() }
+
+    /// Swap `current` for the thread that has waited longest, or give it back if no other thread
+    /// is waiting.
+    pub fn rotate(&mut self, current: Thread<C>) -> Thread<C> {
+        match self.waiting.pop_front() {
+            Some(next) => {
+                self.waiting.push_back(current);
+                next
+            },
+            None => current,
+        }
+    }
+
+    /// The thread to run once the current thread has finished, if any are left.
+    pub fn take_next(&mut self) -> Option<Thread<C>> { self.waiting.pop_front() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn thread(program_ptr: usize) -> Thread<u8> {
+        Thread { tape: vec![0], data_ptr: 0, program_ptr, call_stack: Vec::new() }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.rotate(thread(0)), thread(0));
+        scheduler.spawn(thread(1));
+        scheduler.spawn(thread(2));
+        assert_eq!(scheduler.rotate(thread(0)), thread(1));
+        assert_eq!(scheduler.rotate(thread(1)), thread(2));
+        assert_eq!(scheduler.len(), 2);
+        assert_eq!(scheduler.take_next(), Some(thread(0)));
+        assert_eq!(scheduler.take_next(), Some(thread(1)));
+        assert_eq!(scheduler.take_next(), None);
+    }
+}
@@ -0,0 +1,437 @@
+//! A small HTTP server running the programs posted to it, see `serve`.
+
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener};
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::cell::Cell;
+use crate::config::{CellSize, InterpreterConfig};
+use crate::dialect::Dialect;
+use crate::error::BfError;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::ioctx::{InMemoryIoCtx, IoCtx};
+use crate::json::{self, Json};
+use crate::program::Program;
+use crate::stats::ExecutionStats;
+
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY_SIZE: usize = 1 << 20;
+
+/// Longest request line or header accepted, in bytes including the line ending.
+const MAX_LINE_SIZE: usize = 8192;
+
+/// Most headers accepted in a request.
+const MAX_HEADERS: usize = 100;
+
+/// Time to wait for the next part of a request before dropping the connection.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// Status code and body of a response.
+type Response = (u16, Json);
+
+
+/// A request as far as the server reads it.
+#[derive(Debug, Clone, PartialEq)]
+struct Request {
+    method: String,
+
+    /// Path of the request target, without the query.
+    path: String,
+
+    body: Vec<u8>,
+}
+
+
+/// Serve the API on `listener` forever, handling up to `jobs` connections at once on threads of
+/// their own and answering `503 Service Unavailable` to any beyond that.
+///
+/// `POST /run` with a JSON object runs its `program`, parsed as `dialect`, on its `input` string
+/// with the semantics of `config`, and responds with the `status` of the run, its `output` as
+/// base64, the `error` if there was one, and the `stats` of the run, like `bfi --json` does.
+/// Every run is limited like `InterpreterConfig::sandboxed`, and a request can lower the limits
+/// with `maxSteps` and `maxMemory`. Requests themselves are limited to `MAX_HEADERS` headers,
+/// `MAX_LINE_SIZE` bytes a line, and a body of `MAX_BODY_SIZE` bytes.
+pub fn serve(
+    listener: &TcpListener,
+    config: &InterpreterConfig,
+    dialect: &dyn Dialect,
+    jobs: usize,
+) -> !
+{
+    let busy = AtomicUsize::new(0);
+    thread::scope(|scope| loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            // e.g. the process is out of file descriptors, which may not last
+            Err(e) => {
+                log::warn!("connection could not be accepted: {}", e);
+                continue;
+            },
+        };
+        // only the accepting thread takes up threads, so none can be taken between these
+        if busy.load(Ordering::Acquire) >= jobs.max(1) {
+            // the request is left unread, as reading it could hold up the accepting thread
+            let refused = write_response(&stream, error(503, "the server is busy"))
+                .and_then(|()| stream.shutdown(Shutdown::Write));
+            if let Err(e) = refused {
+                log::debug!("connection failed: {}", e);
+            };
+            continue;
+        };
+        busy.fetch_add(1, Ordering::AcqRel);
+        let busy = &busy;
+        scope.spawn(move || {
+            let handled = stream.set_read_timeout(Some(READ_TIMEOUT))
+                .and_then(|()| stream.try_clone())
+                .and_then(|input| handle(BufReader::new(input), &stream, config, dialect));
+            // freed before the connection is closed, so that the client can follow up at once
+            busy.fetch_sub(1, Ordering::AcqRel);
+            // a failed connection only fails its own request
+            if let Err(e) = handled {
+                log::debug!("connection failed: {}", e);
+            };
+        });
+    })
+}
+
+
+/// Read a single request from `input` and write the response to `output`.
+fn handle(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    config: &InterpreterConfig,
+    dialect: &dyn Dialect,
+) -> io::Result<()>
+{
+    let response = match read_request(&mut input, &mut output)? {
+        Ok(request) => respond(&request, config, dialect),
+        Err(response) => response,
+    };
+    write_response(output, response)
+}
+
+
+/// Write `response` to `output` as the last on its connection.
+fn write_response(mut output: impl Write, (code, body): Response) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        output,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+            Connection: close\r\n\r\n{}",
+        code,
+        reason(code),
+        body.len(),
+        body,
+    )?;
+    output.flush()
+}
+
+
+/// Read the request line, headers, and body of a request from `input`, or the response rejecting
+/// it. A client expecting `100 Continue` before sending the body is sent it on `output`.
+fn read_request(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<Result<Request, Response>>
+{
+    let line = match read_line(input)? {
+        Some(line) => line,
+        None => return Ok(Err(error(400, "request line too long"))),
+    };
+    let (method, target) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+        [method, target, version] if version.starts_with("HTTP/") => (method, target),
+        _ => return Ok(Err(error(400, "malformed request line"))),
+    };
+    let mut length = 0;
+    let mut expects_continue = false;
+    for i in 0.. {
+        let header = match read_line(input)? {
+            Some(header) if header.is_empty() => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Some(header) => header,
+            None => return Ok(Err(error(431, "header too long"))),
+        };
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        };
+        if i == MAX_HEADERS {
+            return Ok(Err(error(431, "too many headers")));
+        };
+        let (name, value) = match header.split_once(':') {
+            Some((name, value)) => (name.to_ascii_lowercase(), value.trim()),
+            None => return Ok(Err(error(400, "malformed header"))),
+        };
+        match name.as_str() {
+            "content-length" => match value.parse() {
+                Ok(n) => length = n,
+                Err(_) => return Ok(Err(error(400, "malformed Content-Length"))),
+            },
+            "transfer-encoding" => return Ok(Err(error(411, "the body must have a length"))),
+            "expect" => expects_continue = value.eq_ignore_ascii_case("100-continue"),
+            _ => {},
+        };
+    }
+    if length > MAX_BODY_SIZE {
+        let message = format!("the body must be at most {} bytes", MAX_BODY_SIZE);
+        return Ok(Err(error(413, &message)));
+    };
+    if expects_continue {
+        output.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        output.flush()?;
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Ok(Request {
+        method: method.to_string(),
+        // `split` always yields at least once
+        path: target.split('?').next().unwrap().to_string(),
+        body,
+    }))
+}
+
+
+/// Read a line from `input` along with its line ending, which is missing only at the end of
+/// input, or `None` if the line is longer than `MAX_LINE_SIZE`.
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    input.take(MAX_LINE_SIZE as u64).read_line(&mut line)?;
+    Ok(Some(line).filter(|line| line.len() < MAX_LINE_SIZE || line.ends_with('\n')))
+}
+
+
+/// Run the program of `request`, or reject the request.
+fn respond(request: &Request, config: &InterpreterConfig, dialect: &dyn Dialect) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/run") => {},
+        (_, "/run") => return error(405, "programs are run with POST"),
+        (_, path) => return error(404, &format!("no such endpoint '{}'", path)),
+    };
+    let body = match str::from_utf8(&request.body) {
+        Ok(body) => body,
+        Err(_) => return error(400, "the body is not UTF-8"),
+    };
+    let body = match Json::parse(body) {
+        Ok(body) => body,
+        Err(e) => return error(400, &format!("the body is not JSON ({})", e)),
+    };
+    let source = match body.get("program").and_then(Json::as_str) {
+        Some(source) => source,
+        None => return error(400, "'program' must be a string"),
+    };
+    let input = match body.get("input") {
+        None | Some(Json::Null) => "",
+        Some(Json::String(input)) => input,
+        Some(_) => return error(400, "'input' must be a string"),
+    };
+    // a request can lower the limits of the server but never raise them
+    let config = config.clone().sandboxed();
+    let limit = |key: &str, max: u64| match body.get(key) {
+        None | Some(Json::Null) => Ok(max),
+        Some(value) => value.as_u64().map(|n| n.min(max)).ok_or_else(|| {
+            format!("'{}' must be a non-negative integer", key)
+        }),
+    };
+    // unwraps are safe as a sandboxed configuration is always limited
+    let max_steps = limit("maxSteps", config.max_steps.unwrap());
+    let max_memory = limit("maxMemory", config.max_memory.unwrap() as u64);
+    // sandboxed again such that the tape fits within the memory limit of the request
+    let config = match (max_steps, max_memory) {
+        (Ok(max_steps), Ok(max_memory)) => InterpreterConfig {
+            max_steps: Some(max_steps),
+            max_memory: Some(max_memory as usize),
+            ..config
+        }.sandboxed(),
+        (Err(e), _) | (_, Err(e)) => return error(400, &e),
+    };
+
+    let program = Program::parse_dialect(source, dialect);
+    if let Err(e) = program.verify() {
+        return (200, report("parse_error", &[], Some(&e), None));
+    };
+    let input = input.as_bytes();
+    let (status, output, stats) = match config.cell_size {
+        CellSize::U8 => run::<u8>(program, input, config),
+        CellSize::U16 => run::<u16>(program, input, config),
+        CellSize::U32 => run::<u32>(program, input, config),
+        CellSize::I32 => run::<i32>(program, input, config),
+    };
+    (200, report(status.name(), &output, status.error(), stats.as_ref()))
+}
+
+
+/// Execute `program` to completion on `input` with cells of type `C`, returning how it ended,
+/// what it wrote, and its statistics if it began.
+fn run<C: Cell>(
+    program: Program,
+    input: &[u8],
+    config: InterpreterConfig,
+) -> (ExecutionStatus<BfError>, Vec<u8>, Option<ExecutionStats>)
+{
+    let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    if let Err(e) = ictx.borrow_mut().write_input(input) {
+        return (ExecutionStatus::InternalError(e.into()), Vec::new(), None);
+    };
+    let (status, stats) = {
+        let mut ectx = ExecutionContext::<C>::with_program(ictx.borrow_mut(), program)
+            .with_config(config)
+            .without_repl()
+            .with_stats();
+        let status = ectx.execute();
+        (status, ectx.stats().cloned())
+    };
+    let mut output = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Ok(n) = ictx.borrow_mut().read_output(&mut buf) {
+        if n == 0 { break };
+        output.extend_from_slice(&buf[..n]);
+    }
+    (status, output, stats)
+}
+
+
+/// Describe how a run ended like `bfi --json` does, without the exit code.
+fn report(
+    status: &str,
+    output: &[u8],
+    error: Option<&BfError>,
+    stats: Option<&ExecutionStats>,
+) -> Json
+{
+    Json::object(vec![
+        ("status", Json::from(status)),
+        ("output", Json::from(json::base64(output))),
+        ("error", error.map_or(Json::Null, Json::from)),
+        ("stats", stats.map_or(Json::Null, Json::from)),
+    ])
+}
+
+
+/// A response rejecting a request with the status `code` and `message`.
+fn error(code: u16, message: &str) -> Response {
+    (code, Json::object(vec![("error", Json::object(vec![("message", Json::from(message))]))]))
+}
+
+
+/// Reason phrase of the status `code` of a response.
+fn reason(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::TapeMode;
+    use crate::dialect::BrainFuck;
+
+    /// The status code and body of the response to `request`.
+    fn exchange(request: &str, config: &InterpreterConfig) -> (u16, Json) {
+        let mut output = Vec::new();
+        handle(request.as_bytes(), &mut output, config, &BrainFuck).unwrap();
+        let response = String::from_utf8(output).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())), "{}", head);
+        let code = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (code, Json::parse(body).unwrap())
+    }
+
+    fn post(body: &str) -> String {
+        format!("POST /run HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_run() {
+        let config = InterpreterConfig::default();
+        let (code, body) = exchange(&post(r#"{"program": ",+.,+.", "input": "ab"}"#), &config);
+        assert_eq!(code, 200);
+        assert_eq!(body.get("status"), Some(&Json::from("terminated")));
+        assert_eq!(body.get("output"), Some(&Json::from(json::base64(b"bc"))));
+        assert_eq!(body.get("error"), Some(&Json::Null));
+        assert_eq!(body.at(&["stats", "instructions"]).and_then(Json::as_u64), Some(6));
+
+        let (code, body) = exchange(&post(r#"{"program": "+]"}"#), &config);
+        assert_eq!(code, 200);
+        assert_eq!(body.get("status"), Some(&Json::from("parse_error")));
+        assert_eq!(body.at(&["error", "location", "column"]), Some(&Json::from(2usize)));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limited = |program: &str, config: &InterpreterConfig| {
+            let (_, body) = exchange(&post(program), config);
+            body.at(&["error", "message"]).and_then(Json::as_str).map(str::to_string)
+        };
+        let config = InterpreterConfig { max_steps: Some(100), ..InterpreterConfig::default() };
+        // the request lowers the limit of the server, but cannot raise it
+        let message = limited(r#"{"program": "+[]", "maxSteps": 10}"#, &config).unwrap();
+        assert!(message.contains("10 "), "{}", message);
+        let message = limited(r#"{"program": "+[]", "maxSteps": 1000}"#, &config).unwrap();
+        assert!(message.contains("100 "), "{}", message);
+        // breakpoints never open the REPL
+        assert_eq!(limited(r#"{"program": "+%-"}"#, &config), None);
+        // a fixed tape is cut down to the memory limit of the request rather than allocated
+        let config = InterpreterConfig {
+            tape_mode: TapeMode::Fixed,
+            tape_size: usize::MAX,
+            ..InterpreterConfig::default()
+        };
+        let message = limited(r#"{"program": ">>>>", "maxMemory": 4}"#, &config).unwrap();
+        assert!(message.contains("past the end"), "{}", message);
+    }
+
+    #[test]
+    fn test_rejections() {
+        let config = InterpreterConfig::default();
+        let code = |request: &str| exchange(request, &config).0;
+        assert_eq!(code("GET /run HTTP/1.1\r\n\r\n"), 405);
+        assert_eq!(code("POST /other?x=1 HTTP/1.1\r\nContent-Length: 0\r\n\r\n"), 404);
+        assert_eq!(code(&post("{\"program\": 1}")), 400);
+        assert_eq!(code(&post("{\"program\": \"+\", \"maxSteps\": -1}")), 400);
+        assert_eq!(code(&post("not json")), 400);
+        assert_eq!(code("nonsense\r\n\r\n"), 400);
+        assert_eq!(code("POST /run HTTP/1.1\r\nContent-Length: 2000000\r\n\r\n"), 413);
+        // lines and headers past the limits are rejected before the body is read
+        let long = "x".repeat(MAX_LINE_SIZE);
+        assert_eq!(code(&format!("POST /{} HTTP/1.1\r\n\r\n", long)), 400);
+        let with_headers = |headers: &str| post("{}").replace("\r\n\r\n", headers);
+        assert_eq!(code(&with_headers(&format!("\r\nX: {}\r\n\r\n", long))), 431);
+        let headers = "\r\nX: y".repeat(MAX_HEADERS);
+        assert_eq!(code(&with_headers(&format!("{}\r\n\r\n", headers))), 431);
+        // only the missing program is wrong with a request at the limit, Content-Length included
+        let headers = "\r\nX: y".repeat(MAX_HEADERS - 1);
+        assert_eq!(code(&with_headers(&format!("{}\r\n\r\n", headers))), 400);
+
+        let mut output = Vec::new();
+        write_response(&mut output, error(503, "the server is busy")).unwrap();
+        let response = String::from_utf8(output).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{}", response);
+    }
+
+    #[test]
+    fn test_continue() {
+        let request = post(r#"{"program": "+"}"#)
+            .replace("\r\n\r\n", "\r\nExpect: 100-continue\r\n\r\n");
+        let mut output = Vec::new();
+        handle(request.as_bytes(), &mut output, &InterpreterConfig::default(), &BrainFuck).unwrap();
+        let response = String::from_utf8(output).unwrap();
+        let expected = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n";
+        assert!(response.starts_with(expected), "{}", response);
+    }
+}
@@ -0,0 +1,150 @@
+//! Defaults of the `bfi` executable read from its configuration file, see `Settings`.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::InterpreterConfig;
+use crate::dialect::Substitution;
+use crate::header::Header;
+
+
+/// Settings read from a configuration file such that they need not be repeated as options on
+/// every invocation, written in a subset of TOML with one setting per line:
+///
+/// ```text
+/// # ~/.config/bfi/config.toml
+/// cell-size = 16
+/// eof = "zero"
+/// optimize = false
+/// prompt = "bf> "
+/// ```
+///
+/// Settings of the semantics of programs are named and valued as in the header of a program, see
+/// `header::Header`, with values written as strings or bare integers. Options given explicitly,
+/// and the header of the program, take precedence over the file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// Semantics of every program, as if they were given in its header.
+    pub semantics: Header,
+
+    /// Whether to optimize programs before execution, `false` standing in for `--no-optimize`.
+    pub optimize: Option<bool>,
+
+    /// Prompt of the interactive session, see `repl::Session::with_prompt`.
+    pub prompt: Option<String>,
+}
+
+impl Settings {
+    /// Read the settings from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("config file '{}' could not be read ({})", path.display(), e))?
+            .parse()
+            .map_err(|e| format!("config file '{}' is invalid ({})", path.display(), e))
+    }
+
+    /// Apply every setting to `config` except those for which `given` is true of the name of the
+    /// option it stands in for, as `Header::apply` does.
+    pub fn apply(
+        &self,
+        mut config: InterpreterConfig,
+        given: impl Fn(&str) -> bool,
+    ) -> InterpreterConfig {
+        if let Some(optimize) = self.optimize.filter(|_| !given("no-optimize")) {
+            config.optimize = optimize;
+        };
+        self.semantics.apply(config, given)
+    }
+}
+
+impl FromStr for Settings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = Settings::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            };
+            let invalid = |reason: &str| format!("line {}: {}", i + 1, reason);
+            let (name, rest) = line.split_once('=').ok_or_else(|| invalid("expected '='"))?;
+            let (name, rest) = (name.trim(), rest.trim());
+            let (value, rest) = match Substitution::parse_string(rest) {
+                Some((value, rest)) => (value, rest),
+                None if rest.starts_with('"') => return Err(invalid("unterminated string")),
+                None => {
+                    let end = rest.find(|c: char| c.is_whitespace() || c == '#');
+                    let (value, rest) = rest.split_at(end.unwrap_or(rest.len()));
+                    (value.to_string(), rest)
+                },
+            };
+            if !(rest.trim().is_empty() || rest.trim().starts_with('#')) {
+                return Err(invalid(&format!("unexpected '{}'", rest.trim())));
+            };
+            match name {
+                "optimize" => {
+                    let optimize = value.parse().map_err(|_| {
+                        invalid(&format!("invalid value '{}' of setting 'optimize'", value))
+                    })?;
+                    settings.optimize = Some(optimize);
+                },
+                "prompt" => settings.prompt = Some(value),
+                _ => settings.semantics.set(name, &value).map_err(|e| invalid(&e))?,
+            };
+        }
+        Ok(settings)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{CellSize, EofBehavior};
+
+    #[test]
+    fn test_parse() {
+        let settings: Settings = "\
+            # defaults\n\
+            cell-size = 16\n\
+            eof = \"zero\"  # at the end of input\n\
+            \n\
+            optimize = false\n\
+            prompt = \"bf> \"\n"
+            .parse()
+            .unwrap();
+        assert_eq!(settings, Settings {
+            semantics: Header {
+                cell_size: Some(CellSize::U16),
+                eof: Some(EofBehavior::Zero),
+                ..Header::default()
+            },
+            optimize: Some(false),
+            prompt: Some("bf> ".to_string()),
+        });
+        assert_eq!("".parse(), Ok(Settings::default()));
+        assert_eq!(
+            "\ncells = 16".parse::<Settings>(),
+            Err("line 2: unknown setting 'cells'".to_string()),
+        );
+        assert_eq!(
+            "optimize = 1".parse::<Settings>(),
+            Err("line 1: invalid value '1' of setting 'optimize'".to_string()),
+        );
+        assert!("prompt = \"bf> ".parse::<Settings>().is_err());
+        assert!("eof = 0 1".parse::<Settings>().is_err());
+        assert!("eof".parse::<Settings>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let settings: Settings = "optimize = false\ncell-size = 32".parse().unwrap();
+        let config = settings.apply(InterpreterConfig::default(), |_| false);
+        assert!(!config.optimize);
+        assert_eq!(config.cell_size, CellSize::U32);
+        let config = settings.apply(InterpreterConfig::default(), |name| name == "no-optimize");
+        assert!(config.optimize);
+    }
+}
@@ -0,0 +1,169 @@
+//! Saved execution state from which a program can be resumed later.
+
+use std::fmt;
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::cell::Cell;
+use crate::error::BfError;
+
+
+/// First line of every snapshot file, identifying the format.
+static HEADER: &str = "bfi snapshot 1";
+
+
+/// The tape and pointers of an `ExecutionContext`, see `ExecutionContext::snapshot`.
+///
+/// Snapshots are written as plain text with one field per line, e.g.
+///
+/// ```text
+/// bfi snapshot 1
+/// cell-bits 8
+/// program-length 4
+/// program-ptr 2
+/// data-ptr 1
+/// tape 0 3 255
+/// ```
+///
+/// Jump targets are part of the program rather than the state, so the program pointer is all that
+/// is needed to resume execution. It indexes the program as executed, so a snapshot can only be
+/// resumed with the same program and optimization setting that it was taken with. Procedures
+/// defined by a pbrain program are not saved, so it is resumed with none defined, and neither are
+/// the storage cell of `dialect::Extension::Ext1` or the tapes and rows not in use of
+/// `dialect::Extension::Multitape` and `dialect::Extension::Grid`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<C: Cell> {
+    pub tape: Vec<C>,
+    pub data_ptr: usize,
+    pub program_ptr: usize,
+
+    /// Number of instructions in the program, used to reject a snapshot of a different program.
+    pub program_len: usize,
+}
+
+impl<C: Cell> Snapshot<C> {
+    /// Write the snapshot to the file at `path`, replacing any existing file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BfError> {
+        Ok(std::fs::write(path, self.to_string())?)
+    }
+
+    /// Read a snapshot from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BfError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+}
+
+impl<C: Cell> fmt::Display for Snapshot<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "cell-bits {}", 8 * mem::size_of::<C>())?;
+        writeln!(f, "program-length {}", self.program_len)?;
+        writeln!(f, "program-ptr {}", self.program_ptr)?;
+        writeln!(f, "data-ptr {}", self.data_ptr)?;
+        let cells: Vec<String> = self.tape.iter().map(|c| c.to_string()).collect();
+        writeln!(f, "tape {}", cells.join(" "))
+    }
+}
+
+impl<C: Cell> FromStr for Snapshot<C> {
+    type Err = BfError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = BfError::InvalidSnapshot;
+        let mut lines = s.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(invalid("missing header".to_string()));
+        };
+        let mut field = |name: &str| -> Result<String, BfError> {
+            let line = lines.next().unwrap_or("");
+            match line.split_once(' ').map_or((line, ""), |(key, value)| (key, value.trim())) {
+                (key, value) if key == name => Ok(value.to_string()),
+                _ => Err(invalid(format!("expected '{}' but found '{}'", name, line))),
+            }
+        };
+        let number = |name: &str, value: String| {
+            value.parse::<usize>().map_err(|_| invalid(format!("invalid {} '{}'", name, value)))
+        };
+        let cell_bits = number("cell-bits", field("cell-bits")?)?;
+        if cell_bits != 8 * mem::size_of::<C>() {
+            return Err(invalid(format!("snapshot of {}-bit cells", cell_bits)));
+        };
+        let program_len = number("program-length", field("program-length")?)?;
+        let program_ptr = number("program-ptr", field("program-ptr")?)?;
+        let data_ptr = number("data-ptr", field("data-ptr")?)?;
+        let tape = field("tape")?
+            .split_whitespace()
+            .map(|value| match value.parse::<i64>() {
+                // truncating `value` preserves it modulo the width of the cell
+                Ok(value) => Ok(C::default().wrapping_offset(value as i32)),
+                Err(_) => Err(invalid(format!("invalid cell '{}'", value))),
+            })
+            .collect::<Result<Vec<C>, BfError>>()?;
+        if data_ptr >= tape.len() {
+            return Err(invalid(format!("data pointer {} is past the end of the tape", data_ptr)));
+        };
+        if program_ptr > program_len {
+            return Err(invalid(format!("program pointer {} is past the end", program_ptr)));
+        };
+        Ok(Snapshot { tape, data_ptr, program_ptr, program_len })
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = Snapshot {
+            tape: vec![0u8, 3, 255],
+            data_ptr: 1,
+            program_ptr: 2,
+            program_len: 4,
+        };
+        let s = snapshot.to_string();
+        assert_eq!(s, concat!(
+            "bfi snapshot 1\n",
+            "cell-bits 8\n",
+            "program-length 4\n",
+            "program-ptr 2\n",
+            "data-ptr 1\n",
+            "tape 0 3 255\n",
+        ));
+        assert_eq!(s.parse::<Snapshot<u8>>(), Ok(snapshot));
+        let snapshot = Snapshot { tape: vec![-1i32, 7], data_ptr: 1, program_ptr: 0, program_len: 0 };
+        assert_eq!(snapshot.to_string().parse::<Snapshot<i32>>(), Ok(snapshot));
+        let snapshot = Snapshot { tape: vec![u32::MAX], data_ptr: 0, program_ptr: 0, program_len: 0 };
+        assert_eq!(snapshot.to_string().parse::<Snapshot<u32>>(), Ok(snapshot));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let valid = concat!(
+            "bfi snapshot 1\n",
+            "cell-bits 8\n",
+            "program-length 4\n",
+            "program-ptr 2\n",
+            "data-ptr 1\n",
+            "tape 0 3\n",
+        );
+        assert!(valid.parse::<Snapshot<u8>>().is_ok());
+        assert!(valid.parse::<Snapshot<u16>>().is_err());
+        for (from, to) in [
+            ("bfi snapshot 1", "bfi"),
+            ("data-ptr 1", "data-ptr 2"),
+            ("program-ptr 2", "program-ptr 5"),
+            ("tape 0 3", "tape 0 x"),
+            ("program-ptr 2\n", ""),
+        ] {
+            let invalid = valid.replace(from, to);
+            assert!(
+                matches!(invalid.parse::<Snapshot<u8>>(), Err(BfError::InvalidSnapshot(_))),
+                "{} was accepted",
+                invalid,
+            );
+        }
+    }
+}
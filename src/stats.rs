@@ -0,0 +1,126 @@
+//! Summary of how much work an execution did, see `ExecutionStats`.
+
+use std::time::{Duration, Instant};
+
+use crate::json::Json;
+use crate::profile;
+use crate::program::Instruction;
+
+
+/// Statistics collected by an `ExecutionContext`, see `ExecutionContext::with_stats`.
+///
+/// Unlike a `profile::Profile`, instructions are only counted by kind, so the statistics stay the
+/// same size however long the program is.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionStats {
+    /// Total number of instructions executed, counted after optimization.
+    pub instructions: u64,
+
+    /// Number of instructions executed of each kind that was executed at all, e.g. `("loop", 12)`,
+    /// most executed first.
+    pub counts: Vec<(&'static str, u64)>,
+
+    /// Largest number of cells on the tape at once.
+    pub peak_tape_size: usize,
+
+    /// Time from the start of execution until it ended, including optimizing the program.
+    pub elapsed: Duration,
+
+    started: Option<Instant>,
+}
+
+
+impl ExecutionStats {
+    /// Begin timing execution.
+    pub(crate) fn start(&mut self) { self.started = Some(Instant::now()); }
+
+    /// Count the execution of `instruction` with the tape `tape_size` cells long.
+    pub(crate) fn record(&mut self, instruction: &Instruction, tape_size: usize) {
+        self.instructions += 1;
+        let kind = profile::kind(instruction);
+        match self.counts.iter().position(|(k, _)| *k == kind) {
+            Some(mut i) => {
+                self.counts[i].1 += 1;
+                // keep the most executed first, with ties in the order they were first executed
+                while i > 0 && self.counts[i - 1].1 < self.counts[i].1 {
+                    self.counts.swap(i - 1, i);
+                    i -= 1;
+                }
+            },
+            None => self.counts.push((kind, 1)),
+        };
+        self.peak_tape_size = self.peak_tape_size.max(tape_size);
+    }
+
+    /// Stop timing execution, with the tape `tape_size` cells long at its end.
+    pub(crate) fn finish(&mut self, tape_size: usize) {
+        if let Some(started) = self.started.take() {
+            self.elapsed = started.elapsed();
+        };
+        self.peak_tape_size = self.peak_tape_size.max(tape_size);
+    }
+
+    /// Render the statistics as a short table.
+    pub fn report(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "{} instructions executed in {:.3}s",
+                self.instructions,
+                self.elapsed.as_secs_f64(),
+            ),
+            format!("peak tape size: {} cells", self.peak_tape_size),
+            String::new(),
+            "instruction      count".to_string(),
+        ];
+        lines.extend(self.counts.iter().map(|(kind, count)| {
+            format!("{:<10} {:>10}", kind, count)
+        }));
+        lines.join("\n")
+    }
+}
+
+/// The statistics as reported by `bfi --json` and `serve`.
+impl From<&ExecutionStats> for Json {
+    fn from(stats: &ExecutionStats) -> Self {
+        Json::object(vec![
+            ("instructions", Json::Number(stats.instructions as f64)),
+            ("counts", Json::Object(stats.counts.iter().map(|(kind, count)| {
+                (kind.to_string(), Json::Number(*count as f64))
+            }).collect())),
+            ("peakTapeSize", Json::from(stats.peak_tape_size)),
+            ("elapsedSeconds", Json::Number(stats.elapsed.as_secs_f64())),
+        ])
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = ExecutionStats::default();
+        stats.start();
+        for instruction in &[
+            Instruction::Add(1),
+            Instruction::LoopBeg(Some(3)),
+            Instruction::Move(1),
+            Instruction::LoopEnd(Some(1)),
+            Instruction::Move(-1),
+        ] {
+            stats.record(instruction, 2);
+        }
+        stats.finish(3);
+        assert_eq!(stats.instructions, 5);
+        assert_eq!(stats.counts, vec![("loop", 2), ("move", 2), ("add", 1)]);
+        assert_eq!(stats.peak_tape_size, 3);
+        let report = stats.report();
+        assert!(report.starts_with("5 instructions executed in "), "{}", report);
+        assert!(report.ends_with(concat!(
+            "loop                2\n",
+            "move                2\n",
+            "add                 1",
+        )));
+    }
+}
@@ -0,0 +1,255 @@
+//! Storage of the cells of the tape, see `Tape`.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::iter;
+
+use crate::cell::Cell;
+use crate::config::TapeBackend;
+
+
+/// The cells of a tape, indexed from zero at its start, as stored by one of the
+/// `config::TapeBackend`s.
+///
+/// Every cell from the start of the tape to its end exists as far as the interpreter is
+/// concerned, but need not take up memory, see `SparseTape`.
+pub trait Tape<C: Cell>: Debug {
+    /// Number of cells on the tape, including those that were never written.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// The value of the cell at `index`, which must be on the tape.
+    fn get(&self, index: usize) -> C;
+
+    fn set(&mut self, index: usize, value: C);
+
+    /// Add `n` zero cells to the end of the tape.
+    fn grow_right(&mut self, n: usize);
+
+    /// Add `n` zero cells to the start of the tape, moving every cell `n` places to the right.
+    fn grow_left(&mut self, n: usize);
+
+    /// Remove `n` cells from the start of the tape, moving every other cell `n` places to the left.
+    fn shrink_left(&mut self, n: usize);
+
+    /// Remove every cell from `len` onwards.
+    fn truncate(&mut self, len: usize);
+
+    /// Number of cells that the tape has allocated memory for.
+    fn capacity(&self) -> usize;
+
+    /// Allocate memory for at least `len` cells ahead of growing to them, but for no more than
+    /// `max_len`, if the tape allocates memory ahead at all.
+    fn reserve(&mut self, _len: usize, _max_len: usize) {}
+
+    /// Free the memory allocated ahead for cells the tape has not grown to, if any.
+    fn shrink_to_fit(&mut self) {}
+
+    /// Every cell of the tape in order, borrowed if they are stored in order.
+    fn cells(&self) -> Cow<'_, [C]>;
+
+    /// Every cell of the tape as a single slice, if they are stored as one, e.g. for `jit::Jit`.
+    fn as_mut_slice(&mut self) -> Option<&mut [C]> { None }
+
+    fn into_cells(self: Box<Self>) -> Vec<C>;
+}
+
+
+/// A tape of `cells` stored by `backend`.
+pub fn new_tape<C: Cell>(backend: TapeBackend, cells: Vec<C>) -> Box<dyn Tape<C>> {
+    match backend {
+        TapeBackend::Vec => Box::new(cells),
+        TapeBackend::Deque => Box::new(VecDeque::from(cells)),
+        TapeBackend::Sparse => Box::new(SparseTape::from(cells)),
+    }
+}
+
+
+/// Contiguous storage, the fastest to access, and the only storage the JIT can run on. Growing
+/// the tape to the left moves every cell.
+impl<C: Cell> Tape<C> for Vec<C> {
+    fn len(&self) -> usize { Vec::len(self) }
+
+    fn get(&self, index: usize) -> C { self[index] }
+
+    fn set(&mut self, index: usize, value: C) { self[index] = value; }
+
+    fn grow_right(&mut self, n: usize) { self.resize(Vec::len(self) + n, C::default()); }
+
+    fn grow_left(&mut self, n: usize) { self.splice(0..0, iter::repeat_n(C::default(), n)); }
+
+    fn shrink_left(&mut self, n: usize) { self.drain(..n); }
+
+    fn truncate(&mut self, len: usize) { Vec::truncate(self, len); }
+
+    fn capacity(&self) -> usize { Vec::capacity(self) }
+
+    fn reserve(&mut self, len: usize, max_len: usize) {
+        if len > Vec::capacity(self) {
+            // grow geometrically as usual, but never allocate past the limit
+            let capacity = (Vec::capacity(self) * 2).clamp(len, max_len);
+            self.reserve_exact(capacity - Vec::len(self));
+        };
+    }
+
+    fn shrink_to_fit(&mut self) { Vec::shrink_to_fit(self); }
+
+    fn cells(&self) -> Cow<'_, [C]> { Cow::Borrowed(self) }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [C]> { Some(self) }
+
+    fn into_cells(self: Box<Self>) -> Vec<C> { *self }
+}
+
+
+/// Ring buffer storage, which grows to the left as cheaply as to the right.
+impl<C: Cell> Tape<C> for VecDeque<C> {
+    fn len(&self) -> usize { VecDeque::len(self) }
+
+    fn get(&self, index: usize) -> C { self[index] }
+
+    fn set(&mut self, index: usize, value: C) { self[index] = value; }
+
+    fn grow_right(&mut self, n: usize) { self.resize(VecDeque::len(self) + n, C::default()); }
+
+    fn grow_left(&mut self, n: usize) {
+        for _ in 0..n {
+            self.push_front(C::default());
+        }
+    }
+
+    fn shrink_left(&mut self, n: usize) { self.drain(..n); }
+
+    fn truncate(&mut self, len: usize) { VecDeque::truncate(self, len); }
+
+    fn capacity(&self) -> usize { VecDeque::capacity(self) }
+
+    fn shrink_to_fit(&mut self) { VecDeque::shrink_to_fit(self); }
+
+    fn cells(&self) -> Cow<'_, [C]> {
+        match self.as_slices() {
+            (front, []) => Cow::Borrowed(front),
+            (front, back) => Cow::Owned([front, back].concat()),
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [C]> { Some(self.make_contiguous()) }
+
+    fn into_cells(self: Box<Self>) -> Vec<C> { Vec::from(*self) }
+}
+
+
+/// Storage of only the cells that are not zero, for programs that move the data pointer across a
+/// range of cells far larger than they use, at the cost of a lookup for every access.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseTape<C: Cell> {
+    /// Value of each cell that is not zero, keyed by its index relative to `origin`.
+    cells: HashMap<isize, C>,
+
+    /// Index of the cell keyed by zero, which moves as the tape grows to the left.
+    origin: usize,
+
+    len: usize,
+}
+
+impl<C: Cell> SparseTape<C> {
+    fn key(&self, index: usize) -> isize { index as isize - self.origin as isize }
+}
+
+impl<C: Cell> From<Vec<C>> for SparseTape<C> {
+    fn from(cells: Vec<C>) -> Self {
+        let len = cells.len();
+        let cells = cells
+            .into_iter()
+            .enumerate()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(i, value)| (i as isize, value))
+            .collect();
+        SparseTape { cells, origin: 0, len }
+    }
+}
+
+impl<C: Cell> Tape<C> for SparseTape<C> {
+    fn len(&self) -> usize { self.len }
+
+    fn get(&self, index: usize) -> C {
+        self.cells.get(&self.key(index)).copied().unwrap_or_default()
+    }
+
+    fn set(&mut self, index: usize, value: C) {
+        let key = self.key(index);
+        if value.is_zero() {
+            self.cells.remove(&key);
+        } else {
+            self.cells.insert(key, value);
+        };
+    }
+
+    fn grow_right(&mut self, n: usize) { self.len += n; }
+
+    fn grow_left(&mut self, n: usize) {
+        self.origin += n;
+        self.len += n;
+    }
+
+    fn shrink_left(&mut self, n: usize) {
+        let start = self.key(n);
+        self.cells.retain(|&key, _| key >= start);
+        self.origin -= n;
+        self.len -= n;
+    }
+
+    fn truncate(&mut self, len: usize) {
+        let end = self.key(len);
+        self.cells.retain(|&key, _| key < end);
+        self.len = self.len.min(len);
+    }
+
+    fn capacity(&self) -> usize { self.cells.capacity() }
+
+    fn shrink_to_fit(&mut self) { self.cells.shrink_to_fit(); }
+
+    fn cells(&self) -> Cow<'_, [C]> {
+        let mut cells = vec![C::default(); self.len];
+        for (&key, &value) in self.cells.iter() {
+            cells[(key + self.origin as isize) as usize] = value;
+        }
+        Cow::Owned(cells)
+    }
+
+    fn into_cells(self: Box<Self>) -> Vec<C> { self.cells().into_owned() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backends() {
+        for &backend in [TapeBackend::Vec, TapeBackend::Deque, TapeBackend::Sparse].iter() {
+            let mut tape = new_tape(backend, vec![1u8, 0, 2]);
+            tape.grow_left(2);
+            tape.set(0, 3);
+            tape.grow_right(1);
+            assert_eq!(&*tape.cells(), &[3, 0, 1, 0, 2, 0], "{:?}", backend);
+            assert_eq!((tape.len(), tape.get(4)), (6, 2), "{:?}", backend);
+            tape.shrink_left(1);
+            tape.truncate(3);
+            tape.set(1, 0);
+            assert_eq!(tape.into_cells(), vec![0, 0, 0], "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut tape = SparseTape::<u8>::default();
+        tape.grow_right(1 << 40);
+        tape.set(1 << 39, 7);
+        tape.set(3, 1);
+        tape.set(3, 0);
+        assert_eq!((tape.get(1 << 39), tape.cells.len()), (7, 1));
+    }
+}
@@ -1,6 +1,7 @@
 //! (De)serialization tools for BrainF\*ck tokens.
 
-use std::fmt;
+use alloc::vec::Vec;
+use core::fmt;
 
 
 /// All valid `bfi` program commands.
@@ -16,6 +17,39 @@ pub enum Token {
     LoopEnd,
     DebugDump,
     DebugBreakpoint,
+    /// Start of a procedure definition, only decoded by the pbrain dialect.
+    ProcBeg,
+    /// End of a procedure definition, only decoded by the pbrain dialect.
+    ProcEnd,
+    /// Call of a procedure, only decoded by the pbrain dialect.
+    ProcCall,
+    /// Fork into a new thread, only decoded by the Brainfork dialect.
+    Fork,
+    /// End the program, only decoded with the Extended Type I extension, as are the following.
+    End,
+    /// Copy the current cell into the storage cell.
+    Store,
+    /// Copy the storage cell into the current cell.
+    Load,
+    ShiftRight,
+    ShiftLeft,
+    Not,
+    Xor,
+    And,
+    Or,
+    /// Set the current cell to a pseudo-random byte, only decoded with the random extension.
+    Random,
+    /// Set the current cell to the time since execution began, only decoded with the time
+    /// extension.
+    Clock,
+    /// Switch to the next tape, only decoded with the multitape extension, as is the following.
+    NextTape,
+    /// Switch to the previous tape.
+    PrevTape,
+    /// Move the data pointer up a row, only decoded with the grid extension, as is the following.
+    RowUp,
+    /// Move the data pointer down a row.
+    RowDown,
 }
 
 
@@ -33,11 +67,33 @@ impl Token {
             Token::LoopEnd => ']',
             Token::DebugDump => '#',
             Token::DebugBreakpoint => '%',
+            Token::ProcBeg => '(',
+            Token::ProcEnd => ')',
+            Token::ProcCall => ':',
+            Token::Fork => 'Y',
+            Token::End => '@',
+            Token::Store => '$',
+            Token::Load => '!',
+            Token::ShiftRight => '}',
+            Token::ShiftLeft => '{',
+            Token::Not => '~',
+            Token::Xor => '^',
+            Token::And => '&',
+            Token::Or => '|',
+            Token::Random => '?',
+            Token::Clock => ';',
+            Token::NextTape => '/',
+            Token::PrevTape => '\\',
+            Token::RowUp => '^',
+            Token::RowDown => '_',
         }
     }
 
     /// Transform a character into a `Token`, returning the resulting `Token` member if the
     /// character is a valid command, otherwise returning the `Err`-wrapped unsupported character.
+    ///
+    /// Characters of extensions like the pbrain procedures are decoded by their dialect instead,
+    /// see `dialect::PBrain`, `dialect::BrainFork`, and `dialect::Extension`.
     pub fn decode(c: char) -> Result<Token, char> {
         match c {
             '>' => Ok(Token::PtrInc),
@@ -59,6 +115,43 @@ impl Token {
     pub fn parse_str(s: &str) -> Vec<Self> {
         s.chars().filter_map(|c| Token::decode(c).ok()).collect()
     }
+
+    /// Like `parse_str`, additionally returning the location of each `Token` in the source.
+    pub fn locate_str(s: &str) -> Vec<(Self, Location)> { Token::locate_with(s, Token::decode) }
+
+    /// Like `locate_str`, decoding each character with `decode` rather than `Token::decode`.
+    pub fn locate_with(
+        s: &str,
+        decode: impl Fn(char) -> Result<Token, char>,
+    ) -> Vec<(Self, Location)>
+    {
+        let mut location = Location { line: 1, column: 1 };
+        let mut tokens = Vec::new();
+        for c in s.chars() {
+            if let Ok(token) = decode(c) {
+                tokens.push((token, location));
+            };
+            location = match c {
+                '\n' => Location { line: location.line + 1, column: 1 },
+                _ => Location { column: location.column + 1, ..location },
+            };
+        }
+        tokens
+    }
+}
+
+
+/// Location of a character in the source of a program, with lines and columns counted from one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, col {}", self.line, self.column)
+    }
 }
 
 
@@ -89,9 +182,9 @@ mod test {
 
     #[test]
     fn decoding() {
-        for (c, &t) in SYMBOLS.chars().zip(TOKENS.into_iter()) {
+        for (c, &t) in SYMBOLS.chars().zip(TOKENS.iter()) {
             let decoded: Result<Token, char> = Token::decode(c);
-            assert_eq!(decoded.is_ok(), true);
+            assert!(decoded.is_ok());
             assert_eq!(decoded.unwrap(), t);
         }
     }
@@ -100,14 +193,25 @@ mod test {
     fn decoding_comments() {
         let program: &str = "0>something <+\t-.else,[]#\nentirely 💁%";
         let program_decoded: Vec<Token> = Token::parse_str(program);
-        for (&c, &t) in program_decoded.iter().zip(TOKENS.into_iter()) {
+        for (&c, &t) in program_decoded.iter().zip(TOKENS.iter()) {
             assert_eq!(c, t);
         }
     }
 
+    #[test]
+    fn locating() {
+        let located = Token::locate_str("a+\n\n 💁[\r\n]");
+        assert_eq!(located, vec![
+            (Token::ValInc, Location { line: 1, column: 2 }),
+            (Token::LoopBeg, Location { line: 3, column: 3 }),
+            (Token::LoopEnd, Location { line: 4, column: 1 }),
+        ]);
+        assert_eq!("line 3, col 3", located[1].1.to_string());
+    }
+
     #[test]
     fn encoding() {
-        for (c, &t) in SYMBOLS.chars().zip(TOKENS.into_iter()) {
+        for (c, &t) in SYMBOLS.chars().zip(TOKENS.iter()) {
             assert_eq!(Token::encode(t), c);
         }
     }
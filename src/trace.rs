@@ -0,0 +1,112 @@
+//! Instruction traces recording the state of the interpreter as a program executes.
+
+use std::collections::VecDeque;
+use std::io::{self, BufWriter, Write};
+
+use crate::cell::Cell;
+use crate::program::Program;
+
+
+/// Log of every instruction executed by an `ExecutionContext`, see `ExecutionContext::with_tracer`.
+///
+/// Each entry lists the index of the instruction, its position in the source, the instruction
+/// itself, and the data pointer and value of the current cell before the instruction executes.
+pub struct Tracer<'a> {
+    sink: BufWriter<Box<dyn Write + 'a>>,
+
+    /// Number of entries to keep and the most recent entries, if only the last entries are written
+    /// once execution ends rather than every entry as it happens.
+    last: Option<(usize, VecDeque<String>)>,
+}
+
+impl<'a> Tracer<'a> {
+    /// Create a tracer writing every entry to `sink`.
+    pub fn new(sink: impl Write + 'a) -> Self {
+        Tracer { sink: BufWriter::new(Box::new(sink)), last: None }
+    }
+
+    /// Only write the last `n` entries, once execution ends. Useful to find out where a program
+    /// that does not terminate is spending its time without tracing it in full.
+    pub fn keep_last(mut self, n: usize) -> Self {
+        self.last = Some((n, VecDeque::with_capacity(n)));
+        self
+    }
+
+    /// Record the instruction at index `program_ptr` of `program` as it is about to execute.
+    pub fn record<C: Cell>(
+        &mut self,
+        program: &Program,
+        program_ptr: usize,
+        data_ptr: usize,
+        cell: C,
+    ) -> io::Result<()>
+    {
+        // unwrap_or_default covers the end of the program, which is never executed
+        let instruction = program.get(program_ptr).map(|i| i.to_string()).unwrap_or_default();
+        let entry = format!(
+            "{:>6} {:>6}  {:<16} ptr {:<6} cell {}",
+            program_ptr, program.position(program_ptr), instruction, data_ptr, cell,
+        );
+        match &mut self.last {
+            Some((n, entries)) => {
+                if entries.len() == *n {
+                    entries.pop_front();
+                };
+                if *n > 0 {
+                    entries.push_back(entry);
+                };
+                Ok(())
+            },
+            None => writeln!(self.sink, "{}", entry),
+        }
+    }
+
+    /// Write any entries held back by `keep_last` and flush the trace. Called once execution ends.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, entries)) = &mut self.last {
+            for entry in entries.drain(..) {
+                writeln!(self.sink, "{}", entry)?;
+            }
+        };
+        self.sink.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let program = Program::parse("+[-]");
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut tracer = Tracer::new(&mut buffer);
+        tracer.record(&program, 1, 3, 1u8).unwrap();
+        tracer.record(&program, 2, 3, 1u8).unwrap();
+        tracer.finish().unwrap();
+        drop(tracer);
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                "     1      1  [ -> 3           ptr 3      cell 1\n",
+                "     2      2  -1               ptr 3      cell 1\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_keep_last() {
+        let program = Program::parse("+++");
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut tracer = Tracer::new(&mut buffer).keep_last(2);
+        for i in 0..3 {
+            tracer.record(&program, i, 0, i as u8).unwrap();
+        }
+        tracer.finish().unwrap();
+        drop(tracer);
+        let trace = String::from_utf8(buffer).unwrap();
+        let cells: Vec<&str> = trace.lines().map(|line| line.rsplit(' ').next().unwrap()).collect();
+        assert_eq!(cells, vec!["1", "2"]);
+    }
+}
@@ -0,0 +1,507 @@
+//! Full-screen debugger showing the program, tape, and output of a running program, see `debug`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::cell::Cell;
+use crate::difftest;
+use crate::interpreter::{ExecutionContext, ExecutionStatus};
+use crate::program::Program;
+
+/// Number of cells shown to the left of the current cell while following the data pointer.
+const TAPE_CONTEXT: usize = 4;
+
+/// Number of lines of output shown at the bottom of the screen.
+const OUTPUT_LINES: usize = 4;
+
+/// Number of lines taken by everything but the program: the title, the tape with its heading and
+/// index, value, and ASCII rows, the output with its heading, and the key help.
+const FIXED_LINES: usize = 1 + 4 + 1 + OUTPUT_LINES + 1;
+
+/// Width of the line numbers before each line of the program.
+const GUTTER: usize = 5;
+
+/// Time between redraws while the program runs.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+const HELP: &str =
+    " s step  c continue  b breakpoint  j/k move cursor  h/l scroll tape  0 follow  q quit";
+
+const REVERSE: &str = "\x1b[7m";
+const UNDERLINE: &str = "\x1b[4m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+
+/// A key pressed in the debugger.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Other,
+}
+
+impl Key {
+    /// Decode the bytes read from the terminal for a single key press.
+    fn parse(bytes: &[u8]) -> Self {
+        match bytes {
+            b"\x1b[A" => Key::Up,
+            b"\x1b[B" => Key::Down,
+            b"\x1b[C" => Key::Right,
+            b"\x1b[D" => Key::Left,
+            &[byte] if byte.is_ascii() => Key::Char(byte as char),
+            _ => Key::Other,
+        }
+    }
+}
+
+
+/// The terminal switched to raw mode and the alternate screen, restored when dropped.
+struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    fn new() -> io::Result<Self> {
+        // safe as tcgetattr fills in the termios or fails without touching it
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        };
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        };
+        let terminal = Terminal { original };
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(terminal)
+    }
+
+    /// Number of columns and rows of the terminal, 80 by 24 if it can't be determined.
+    fn size(&self) -> (usize, usize) {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+        match unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } {
+            0 if size.ws_col > 0 && size.ws_row > 0 => (size.ws_col as usize, size.ws_row as usize),
+            _ => (80, 24),
+        }
+    }
+
+    /// Wait up to `timeout` for a key to be pressed, or indefinitely if it is `None`. The end of
+    /// input is read as `q`.
+    fn read_key(&self, timeout: Option<Duration>) -> io::Result<Option<Key>> {
+        let mut fd = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+        let timeout = timeout.map_or(-1, |timeout| timeout.as_millis() as libc::c_int);
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            -1 => return Err(io::Error::last_os_error()),
+            0 => return Ok(None),
+            _ => {},
+        };
+        let mut buf = [0u8; 8];
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        match n {
+            -1 => Err(io::Error::last_os_error()),
+            0 => Ok(Some(Key::Char('q'))),
+            n => Ok(Some(Key::parse(&buf[..n as usize]))),
+        }
+    }
+
+    /// Replace the contents of the screen with `lines`.
+    fn draw(&self, lines: &[String]) -> io::Result<()> {
+        let mut screen = String::from("\x1b[H");
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                screen.push_str("\r\n");
+            };
+            screen.push_str(line);
+            screen.push_str("\x1b[K");
+        }
+        screen.push_str("\x1b[J");
+        let mut stdout = io::stdout();
+        stdout.write_all(screen.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[?25h\x1b[?1049l").and_then(|()| stdout.flush());
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+
+/// State of the debugger alongside that of the program.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct View {
+    /// Index of the instruction selected for toggling breakpoints, following the next instruction
+    /// to execute whenever the program runs.
+    cursor: usize,
+
+    /// Indices of the instructions before which execution pauses.
+    breakpoints: BTreeSet<usize>,
+
+    /// Index of the leftmost cell shown, or `None` to follow the data pointer.
+    tape_start: Option<usize>,
+
+    /// Number of instructions executed in the debugger.
+    steps: u64,
+
+    /// Why execution last paused, or what to do next.
+    message: String,
+
+    /// Output of the program so far.
+    output: Vec<u8>,
+}
+
+impl View {
+    /// Add a breakpoint at the cursor, or remove the one already there.
+    fn toggle_breakpoint(&mut self) {
+        if !self.breakpoints.remove(&self.cursor) {
+            self.breakpoints.insert(self.cursor);
+        };
+    }
+}
+
+
+/// Run the program of `ectx` in a full-screen debugger on the terminal, returning its output once
+/// the debugger is quit.
+///
+/// The debugger shows the source of the program with the next instruction highlighted, the cells
+/// of the tape around the data pointer, and the latest lines of output. Execution proceeds one
+/// instruction at a time or continues until a breakpoint, either on an instruction selected in the
+/// debugger, a `%` in the program, or one added with `ExecutionContext::with_breakpoint`.
+///
+/// `ectx` must have been created with an `InMemoryIoCtx` holding all of the input of the program
+/// and `without_repl`, as the debugger takes over the terminal.
+pub fn debug<C: Cell>(ectx: &mut ExecutionContext<C>) -> io::Result<Vec<u8>> {
+    ectx.start();
+    let terminal = Terminal::new()?;
+    let mut view = View {
+        cursor: ectx.program_ptr(),
+        message: "press s to step or c to continue".to_string(),
+        ..View::default()
+    };
+    loop {
+        collect_output(ectx, &mut view)?;
+        let (width, height) = terminal.size();
+        terminal.draw(&render(ectx, &view, width, height))?;
+        match terminal.read_key(None)? {
+            // Ctrl-C is read as a key in raw mode
+            Some(Key::Char('q')) | Some(Key::Char('\x03')) => break,
+            Some(Key::Char('s')) | Some(Key::Char(' ')) => {
+                step(ectx, &mut view);
+            },
+            Some(Key::Char('c')) => run(ectx, &mut view, &terminal)?,
+            Some(Key::Char('b')) => view.toggle_breakpoint(),
+            Some(Key::Up) | Some(Key::Char('k')) => view.cursor = view.cursor.saturating_sub(1),
+            Some(Key::Down) | Some(Key::Char('j')) => {
+                view.cursor = (view.cursor + 1).min(ectx.program().len().saturating_sub(1));
+            },
+            Some(Key::Left) | Some(Key::Char('h')) => {
+                view.tape_start = Some(first_cell(&view, ectx.data_ptr()).saturating_sub(1));
+            },
+            Some(Key::Right) | Some(Key::Char('l')) => {
+                let last = ectx.tape().len().saturating_sub(1);
+                view.tape_start = Some((first_cell(&view, ectx.data_ptr()) + 1).min(last));
+            },
+            Some(Key::Char('0')) => view.tape_start = None,
+            _ => {},
+        };
+    }
+    drop(terminal);
+    collect_output(ectx, &mut view)?;
+    Ok(view.output)
+}
+
+
+/// Execute the next instruction, returning whether execution can continue.
+fn step<C: Cell>(ectx: &mut ExecutionContext<C>, view: &mut View) -> bool {
+    if ectx.status != ExecutionStatus::InProgress {
+        return false;
+    };
+    ectx.step();
+    view.steps += 1;
+    view.cursor = ectx.program_ptr().min(ectx.program().len().saturating_sub(1));
+    view.tape_start = None;
+    let paused = ectx.take_paused();
+    if !paused.is_empty() {
+        view.message = paused.join(", ");
+        return false;
+    };
+    if ectx.status != ExecutionStatus::InProgress {
+        view.message = format!("{}, press q to quit", difftest::describe(&ectx.status));
+        return false;
+    };
+    true
+}
+
+
+/// Execute instructions until a breakpoint, the end of the program, or a key press, redrawing the
+/// screen as the program runs.
+fn run<C: Cell>(
+    ectx: &mut ExecutionContext<C>,
+    view: &mut View,
+    terminal: &Terminal,
+) -> io::Result<()> {
+    view.message = "running, press any key to pause".to_string();
+    let mut drawn = Instant::now();
+    while step(ectx, view) {
+        if view.breakpoints.contains(&ectx.program_ptr()) {
+            view.message = format!("breakpoint at instruction {}", ectx.program_ptr());
+            break;
+        };
+        if drawn.elapsed() >= REDRAW_INTERVAL {
+            collect_output(ectx, view)?;
+            let (width, height) = terminal.size();
+            terminal.draw(&render(ectx, view, width, height))?;
+            if terminal.read_key(Some(Duration::from_millis(0)))?.is_some() {
+                view.message = "paused".to_string();
+                break;
+            };
+            drawn = Instant::now();
+        };
+    }
+    Ok(())
+}
+
+
+/// Move the output written by the program since the last call into `view`.
+fn collect_output<C: Cell>(ectx: &mut ExecutionContext<C>, view: &mut View) -> io::Result<()> {
+    if let Some(ctx) = ectx.io_context() {
+        let mut buf = [0u8; 1024];
+        loop {
+            match ctx.read_output(&mut buf)? {
+                0 => break,
+                n => view.output.extend_from_slice(&buf[..n]),
+            };
+        }
+    };
+    Ok(())
+}
+
+
+/// Index of the leftmost cell shown.
+fn first_cell(view: &View, data_ptr: usize) -> usize {
+    view.tape_start.unwrap_or_else(|| data_ptr.saturating_sub(TAPE_CONTEXT))
+}
+
+
+/// Render the screen as `height` lines of at most `width` visible characters each.
+fn render<C: Cell>(
+    ectx: &ExecutionContext<C>,
+    view: &View,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let state = match ectx.status {
+        ExecutionStatus::NotStarted => "ready",
+        ExecutionStatus::InProgress => "paused",
+        ExecutionStatus::Terminated => "terminated",
+        ExecutionStatus::Cancelled => "cancelled",
+        _ => "failed",
+    };
+    let location = match ectx.next_instruction() {
+        Some(instruction) => {
+            format!("next '{}' at {}", instruction, ectx.program().context(ectx.program_ptr()))
+        },
+        None => "end of program".to_string(),
+    };
+    let title =
+        format!(" bfi | {} | {} steps | {} | {}", state, view.steps, location, view.message);
+    let mut lines = vec![format!("{}{:<width$}{}", REVERSE, fit(&title, width), RESET)];
+    let program_height = height.saturating_sub(FIXED_LINES).max(1);
+    lines.extend(render_program(ectx.program(), view, ectx.program_ptr(), width, program_height));
+    lines.push(heading("tape", width));
+    let tape_start = first_cell(view, ectx.data_ptr());
+    lines.extend(render_tape(&ectx.tape(), ectx.data_ptr(), ectx.origin(), tape_start, width));
+    lines.push(heading("output", width));
+    let output = String::from_utf8_lossy(&view.output);
+    let output_lines: Vec<&str> = output.split('\n').collect();
+    for line in &output_lines[output_lines.len().saturating_sub(OUTPUT_LINES)..] {
+        let line: String = line.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        lines.push(fit(&line, width));
+    }
+    while lines.len() < height.saturating_sub(1) {
+        lines.push(String::new());
+    }
+    lines.push(fit(HELP, width));
+    lines
+}
+
+
+/// The `height` lines of the program around the cursor, with the next instruction highlighted,
+/// the cursor underlined, and breakpoints in red.
+fn render_program(
+    program: &Program,
+    view: &View,
+    program_ptr: usize,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    // a program that was not parsed from source is shown as its listing of instructions
+    let (text, spans): (Vec<String>, Vec<(usize, usize)>) = if program.source().is_empty() {
+        let listing = program.to_string();
+        // the instruction itself follows its index and position, see `Program`'s `Display`
+        let spans = (0..program.len()).map(|i| (i, 15)).collect();
+        (listing.lines().map(str::to_string).collect(), spans)
+    } else {
+        let spans = (0..program.len())
+            .map(|i| program.location(i))
+            .map(|location| (location.line - 1, location.column - 1))
+            .collect();
+        (program.source().lines().map(str::to_string).collect(), spans)
+    };
+    let mut styles: HashMap<(usize, usize), String> = HashMap::new();
+    let mut style = |i: usize, style: &str| {
+        if let Some(&span) = spans.get(i) {
+            styles.entry(span).or_default().push_str(style);
+        };
+    };
+    for &breakpoint in &view.breakpoints {
+        style(breakpoint, RED);
+    }
+    style(view.cursor, UNDERLINE);
+    style(program_ptr, REVERSE);
+
+    let (focus_line, focus_column) = spans.get(view.cursor).copied().unwrap_or((0, 0));
+    let first = focus_line.saturating_sub(height / 2).min(text.len().saturating_sub(height));
+    let shift = (focus_column + GUTTER + 1).saturating_sub(width);
+    let mut lines = Vec::new();
+    for (i, line) in text.iter().enumerate().skip(first).take(height) {
+        let mut rendered = fit(&format!("{:>4} ", i + 1), width);
+        for (column, c) in line.chars().enumerate().skip(shift).take(width.saturating_sub(GUTTER)) {
+            let c = if c.is_control() { ' ' } else { c };
+            match styles.get(&(i, column)) {
+                Some(style) => rendered.push_str(&format!("{}{}{}", style, c, RESET)),
+                None => rendered.push(c),
+            };
+        }
+        lines.push(rendered);
+    }
+    lines.resize(height, String::new());
+    lines
+}
+
+
+/// The index, decimal value, and ASCII value of the cells from `start` that fit in `width`, with
+/// the current cell highlighted. Indices count from `origin`, negative to its left.
+fn render_tape<C: Cell>(
+    tape: &[C],
+    data_ptr: usize,
+    origin: usize,
+    start: usize,
+    width: usize,
+) -> Vec<String> {
+    let mut rows = [String::from("index"), String::from("  dec"), String::from("ascii")];
+    let mut used = rows[0].len();
+    for (i, cell) in tape.iter().enumerate().skip(start) {
+        let ascii = match cell.to_byte() {
+            b if b.is_ascii_graphic() => (b as char).to_string(),
+            _ => ".".to_string(),
+        };
+        let values = [(i as isize - origin as isize).to_string(), cell.to_string(), ascii];
+        let cell_width = values.iter().map(String::len).max().unwrap_or(0) + 1;
+        if used + cell_width > width {
+            break;
+        };
+        used += cell_width;
+        for (row, value) in rows.iter_mut().zip(values.iter()) {
+            let value = format!("{:>width$}", value, width = cell_width);
+            if i == data_ptr {
+                row.push_str(&format!("{}{}{}", REVERSE, value, RESET));
+            } else {
+                row.push_str(&value);
+            };
+        }
+    }
+    rows.to_vec()
+}
+
+
+/// A line of `width` dashes labelled with `label`.
+fn heading(label: &str, width: usize) -> String {
+    fit(&format!("── {} {}", label, "─".repeat(width)), width)
+}
+
+
+/// The first `width` characters of `s`.
+fn fit(s: &str, width: usize) -> String { s.chars().take(width).collect() }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::ioctx::{InMemoryIoCtx, IoCtx};
+    use crate::token::Token;
+
+    /// Remove the terminal escape codes from `s`.
+    fn strip(s: &str) -> String {
+        [REVERSE, UNDERLINE, RED, RESET].iter().fold(s.to_string(), |s, code| s.replace(code, ""))
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(Key::parse(b"s"), Key::Char('s'));
+        assert_eq!(Key::parse(b"\x1b[A"), Key::Up);
+        assert_eq!(Key::parse(b"\x1b[D"), Key::Left);
+        assert_eq!(Key::parse(b"\x1b[15~"), Key::Other);
+    }
+
+    #[test]
+    fn test_render() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), "+++++ +++[>++++++++<-]>+.\n>.")
+            .without_repl();
+        ectx.start();
+        let mut view = View::default();
+        while ectx.program_ptr() < 3 {
+            step(&mut ectx, &mut view);
+        }
+        view.breakpoints.insert(4);
+        collect_output(&mut ectx, &mut view).unwrap();
+        let lines = render(&ectx, &view, 40, 14);
+        assert_eq!(lines.len(), 14);
+        assert_eq!(strip(&lines[0]), " bfi | paused | 3 steps | next '>1' at l");
+        // the loop has been optimized into a multiplication, so the move after it is next
+        assert_eq!(strip(&lines[1]), "   1 +++++ +++[>++++++++<-]>+.");
+        let highlighted = format!("{}{}>{}{}+{}.", UNDERLINE, REVERSE, RESET, RED, RESET);
+        assert!(lines[1].ends_with(&highlighted));
+        assert_eq!(lines[2], "   2 >.");
+        assert_eq!(lines[4], "── tape ────────────────────────────────");
+        assert_eq!(strip(&lines[5]), "index 0  1");
+        assert_eq!(strip(&lines[6]), "  dec 0 64");
+        assert_eq!(lines[6], format!("  dec{} 0{} 64", REVERSE, RESET));
+        assert_eq!(lines[13], HELP[..40]);
+
+        while step(&mut ectx, &mut view) {}
+        collect_output(&mut ectx, &mut view).unwrap();
+        let lines = render(&ectx, &view, 40, 14);
+        assert_eq!(strip(&lines[0]), " bfi | terminated | 9 steps | end of pro");
+        assert_eq!(lines[8], "── output ──────────────────────────────");
+        // control characters are shown as spaces, here the NUL written last
+        assert_eq!(lines[9], "A ");
+        assert_eq!(strip(&lines[6]), "  dec 0 65 0");
+    }
+
+    #[test]
+    fn test_render_listing() {
+        let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+        let program = Program::from_tokens(&Token::parse_str("+>"));
+        let mut ectx = ExecutionContext::<u8>::with_program(ictx.borrow_mut(), program)
+            .without_repl();
+        ectx.start();
+        let lines = render_program(ectx.program(), &View::default(), 0, 40, 2);
+        assert_eq!(strip(&lines[0]), "   1      0      0  +1");
+        assert!(lines[0].contains(&format!("{}{}+{}", UNDERLINE, REVERSE, RESET)));
+    }
+}
@@ -0,0 +1,153 @@
+//! Conversion between cells and UTF-8 text for `config::IoMode::Utf8`.
+//!
+//! Cells wider than a byte hold a whole code point. Byte cells instead hold the UTF-8 encoding of
+//! a character one byte at a time: `Utf8Output` collects the bytes written until they form a
+//! character, and `Utf8Input` hands out the bytes of each character read across several reads.
+
+use std::char::REPLACEMENT_CHARACTER;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+use std::str;
+
+
+/// Bytes written to program output, held until they can be decoded.
+#[derive(Debug, Default)]
+pub struct Utf8Output {
+    pending: Vec<u8>,
+}
+
+impl Utf8Output {
+    /// Add a byte, returning the text completed by it: the character it ends, if any, or U+FFFD
+    /// for each sequence of bytes that cannot be part of a character.
+    pub fn push(&mut self, byte: u8) -> String {
+        self.pending.push(byte);
+        let mut text = String::new();
+        loop {
+            let e = match str::from_utf8(&self.pending) {
+                Ok(s) => {
+                    text.push_str(s);
+                    self.pending.clear();
+                    return text;
+                },
+                Err(e) => e,
+            };
+            let valid = e.valid_up_to();
+            // unwrap is safe as the bytes up to `valid` were just decoded
+            text.push_str(str::from_utf8(&self.pending[..valid]).unwrap());
+            match e.error_len() {
+                // the rest may still be completed by the bytes to come
+                None => {
+                    self.pending.drain(..valid);
+                    return text;
+                },
+                Some(len) => {
+                    text.push(REPLACEMENT_CHARACTER);
+                    self.pending.drain(..valid + len);
+                },
+            };
+        }
+    }
+
+    /// Text for the bytes left over once output ends, i.e. U+FFFD for an incomplete character.
+    pub fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+
+/// Bytes of a character read from program input that have yet to be handed out.
+#[derive(Debug, Default)]
+pub struct Utf8Input {
+    pending: VecDeque<u8>,
+}
+
+impl Utf8Input {
+    /// The next byte of the UTF-8 encoding of the input, reading another character from `input`
+    /// once the bytes of the last one have been handed out. `None` at the end of input.
+    pub fn next_byte(&mut self, input: &mut dyn Read) -> io::Result<Option<u8>> {
+        if self.pending.is_empty() {
+            if let Some(c) = read_char(input)? {
+                let mut buffer = [0; 4];
+                self.pending.extend(c.encode_utf8(&mut buffer).bytes());
+            };
+        };
+        Ok(self.pending.pop_front())
+    }
+}
+
+
+/// Read a single character from `input`, or `None` at the end of input. An invalid or incomplete
+/// sequence of bytes is read as U+FFFD.
+pub fn read_char(input: &mut dyn Read) -> io::Result<Option<char>> {
+    let mut buffer = [0; 4];
+    if input.read(&mut buffer[..1])? == 0 {
+        return Ok(None);
+    };
+    let len = match buffer[0].leading_ones() {
+        0 => 1,
+        n @ 2..=4 => n as usize,
+        // a continuation byte or an invalid one cannot start a character
+        _ => return Ok(Some(REPLACEMENT_CHARACTER)),
+    };
+    for i in 1..len {
+        if input.read(&mut buffer[i..=i])? == 0 {
+            return Ok(Some(REPLACEMENT_CHARACTER));
+        };
+    }
+    Ok(Some(str::from_utf8(&buffer[..len]).map_or(REPLACEMENT_CHARACTER, |s| {
+        // unwrap is safe as the bytes decoded to a single character
+        s.chars().next().unwrap()
+    })))
+}
+
+
+/// The character for a code point held in a cell, or U+FFFD if it is not a valid code point.
+pub fn char_from_i64(n: i64) -> char {
+    u32::try_from(n).ok().and_then(char::from_u32).unwrap_or(REPLACEMENT_CHARACTER)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_output() {
+        let mut output = Utf8Output::default();
+        let text: Vec<String> = "é!".bytes().map(|b| output.push(b)).collect();
+        assert_eq!(text, vec!["", "é", "!"]);
+        assert_eq!(output.push(0xff), "\u{fffd}");
+        assert_eq!(output.push(0xe2), "");
+        assert_eq!(output.push(b'a'), "\u{fffd}a");
+        assert_eq!(output.push(0xe2), "");
+        assert_eq!(output.finish(), "\u{fffd}");
+        assert_eq!(output.finish(), "");
+    }
+
+    #[test]
+    fn test_input() {
+        let mut input: &[u8] = "aé€😀".as_bytes();
+        let chars: Vec<Option<char>> = (0..5).map(|_| read_char(&mut input).unwrap()).collect();
+        assert_eq!(chars, vec![Some('a'), Some('é'), Some('€'), Some('😀'), None]);
+        let mut input: &[u8] = b"\x80\xe2\x82";
+        assert_eq!(read_char(&mut input).unwrap(), Some(REPLACEMENT_CHARACTER));
+        assert_eq!(read_char(&mut input).unwrap(), Some(REPLACEMENT_CHARACTER));
+        assert_eq!(read_char(&mut input).unwrap(), None);
+
+        let mut utf8 = Utf8Input::default();
+        let mut input: &[u8] = "é".as_bytes();
+        assert_eq!(utf8.next_byte(&mut input).unwrap(), Some(0xc3));
+        assert_eq!(utf8.next_byte(&mut input).unwrap(), Some(0xa9));
+        assert_eq!(utf8.next_byte(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn test_char_from_i64() {
+        assert_eq!(char_from_i64(0x20ac), '€');
+        assert_eq!(char_from_i64(-1), REPLACEMENT_CHARACTER);
+        assert_eq!(char_from_i64(0xd800), REPLACEMENT_CHARACTER);
+    }
+}
@@ -0,0 +1,251 @@
+//! Heatmap of the tape over the course of an execution, see `Timeline`.
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use crate::cell::Cell;
+
+
+/// Most rows kept by a `Timeline`, beyond which it keeps every other row and samples half as
+/// often.
+const MAX_ROWS: usize = 512;
+
+/// Most cells shown in each row of `Timeline::to_svg`, counted from the leftmost cell shown.
+const MAX_COLUMNS: usize = 256;
+
+/// Size in pixels that `Timeline::to_svg` aims for the heatmap itself to fill.
+const TARGET_WIDTH: usize = 1024;
+const TARGET_HEIGHT: usize = 768;
+
+/// Space in pixels around the heatmap of `Timeline::to_svg` for its labels.
+const MARGIN: usize = 40;
+
+
+/// The tape at a single point in time, without the zero cells at either end.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    /// Number of instructions executed before the row was recorded.
+    step: u64,
+
+    /// Index of the first of `cells`, relative to the first cell of the tape at the start of
+    /// execution, such that cells keep their index as the tape grows to the left.
+    first: isize,
+    cells: Vec<i64>,
+    data_ptr: isize,
+}
+
+
+/// Samples of the tape taken as an `ExecutionContext` executes, see
+/// `ExecutionContext::with_timeline`.
+///
+/// The tape is sampled every `interval` instructions, which doubles whenever there are too many
+/// rows to keep, such that a timeline stays the same size however long the program runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    rows: Vec<Row>,
+    interval: u64,
+    steps: u64,
+
+    /// Number of cells the tape has grown by to the left.
+    grown_left: usize,
+}
+
+impl Default for Timeline {
+    fn default() -> Self { Timeline { rows: Vec::new(), interval: 1, steps: 0, grown_left: 0 } }
+}
+
+impl Timeline {
+    /// Count an instruction about to execute on `tape`, sampling the tape if it is due.
+    pub(crate) fn record<C: Cell>(&mut self, tape: &[C], data_ptr: usize) {
+        if self.steps.is_multiple_of(self.interval) {
+            self.sample(tape, data_ptr);
+            if self.rows.len() > MAX_ROWS {
+                self.interval *= 2;
+                let interval = self.interval;
+                self.rows.retain(|row| row.step.is_multiple_of(interval));
+            };
+        };
+        self.steps += 1;
+    }
+
+    /// Shift the cells sampled so far to make room for `n` new cells at the start of the tape.
+    pub(crate) fn grow_left(&mut self, n: usize) { self.grown_left += n; }
+
+    /// Sample the tape once execution has ended, unless it was just sampled.
+    pub(crate) fn finish<C: Cell>(&mut self, tape: &[C], data_ptr: usize) {
+        if self.rows.last().is_none_or(|row| row.step != self.steps) {
+            self.sample(tape, data_ptr);
+        };
+    }
+
+    fn sample<C: Cell>(&mut self, tape: &[C], data_ptr: usize) {
+        let nonzero = |cell: &C| !cell.is_zero();
+        let start = tape.iter().position(nonzero).map_or(data_ptr, |i| i.min(data_ptr));
+        let end = tape.iter().rposition(nonzero).map_or(data_ptr, |i| i.max(data_ptr)) + 1;
+        let end = end.min(tape.len());
+        let origin = self.grown_left as isize;
+        self.rows.push(Row {
+            step: self.steps,
+            first: start as isize - origin,
+            cells: tape[start..end].iter().map(|cell| cell.to_i64()).collect(),
+            data_ptr: data_ptr as isize - origin,
+        });
+    }
+
+    /// Number of rows sampled.
+    pub fn len(&self) -> usize { self.rows.len() }
+
+    pub fn is_empty(&self) -> bool { self.rows.is_empty() }
+
+    /// Render the timeline as an SVG image: time runs down and cells run across, each colored from
+    /// blue for small values to red for the largest, with zero cells left dark and the position
+    /// of the data pointer traced in white.
+    pub fn to_svg(&self) -> String {
+        let first = self.rows.iter().map(|row| row.first.min(row.data_ptr)).min().unwrap_or(0);
+        let last = self.rows
+            .iter()
+            .map(|row| (row.first + row.cells.len() as isize).max(row.data_ptr + 1))
+            .max()
+            .unwrap_or(1);
+        let columns = ((last - first) as usize).clamp(1, MAX_COLUMNS);
+        let rows = self.rows.len().max(1);
+        let cell_width = (TARGET_WIDTH / columns).clamp(2, 16);
+        let row_height = (TARGET_HEIGHT / rows).clamp(1, 16);
+        let (width, height) = (columns * cell_width, rows * row_height);
+        let max = self.rows
+            .iter()
+            .flat_map(|row| row.cells.iter())
+            .map(|value| value.unsigned_abs())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut svg = String::new();
+        // writing to a string cannot fail, so the results are ignored throughout
+        let _ = writeln!(
+            svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" "#,
+                r#"font-family="monospace" font-size="12">"#,
+            ),
+            width + 2 * MARGIN,
+            height + 2 * MARGIN,
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let _ = writeln!(svg, r##"<g transform="translate({0},{0})">"##, MARGIN);
+        let _ = writeln!(svg, r##"<rect width="{}" height="{}" fill="#111"/>"##, width, height);
+        for (y, row) in self.rows.iter().enumerate() {
+            // runs of cells with the same value are drawn as a single rectangle
+            let mut x = 0;
+            while x < columns {
+                let value = row.value(first + x as isize);
+                let run = (x..columns)
+                    .take_while(|&x| row.value(first + x as isize) == value)
+                    .count();
+                if value != 0 {
+                    let hue = 240 - 240 * value.unsigned_abs() / max;
+                    let _ = writeln!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="hsl({},90%,55%)"/>"#,
+                        x * cell_width,
+                        y * row_height,
+                        run * cell_width,
+                        row_height,
+                        hue,
+                    );
+                };
+                x += run;
+            }
+        }
+        let points: Vec<String> = self.rows.iter().enumerate()
+            .filter(|(_, row)| row.data_ptr >= first && row.data_ptr < first + columns as isize)
+            .map(|(y, row)| format!(
+                "{},{}",
+                (row.data_ptr - first) as usize * cell_width + cell_width / 2,
+                y * row_height + row_height / 2,
+            ))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="white" stroke-width="1" opacity="0.6"/>"#,
+            points.join(" "),
+        );
+        let _ = writeln!(svg, "</g>");
+        let _ = writeln!(svg, r#"<text x="{}" y="{}">cell {}</text>"#, MARGIN, MARGIN - 8, first);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="end">cell {}</text>"#,
+            MARGIN + width,
+            MARGIN - 8,
+            first + columns as isize - 1,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}">step {}</text>"#,
+            MARGIN,
+            MARGIN + height + 16,
+            self.rows.last().map_or(0, |row| row.step),
+        );
+        let _ = writeln!(svg, "</svg>");
+        svg
+    }
+}
+
+impl Row {
+    /// Value of the cell at `index`, zero for cells outside of the row.
+    fn value(&self, index: isize) -> i64 {
+        let i = usize::try_from(index - self.first).ok();
+        i.and_then(|i| self.cells.get(i)).copied().unwrap_or(0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut timeline = Timeline::default();
+        timeline.record(&[0u8, 1, 0], 0);
+        timeline.record(&[0u8, 1, 2, 0], 3);
+        timeline.grow_left(2);
+        timeline.record(&[5u8, 0, 0, 1, 2], 0);
+        timeline.finish(&[5u8, 0, 0, 1, 2], 0);
+        assert_eq!(timeline.rows, vec![
+            Row { step: 0, first: 0, cells: vec![0, 1], data_ptr: 0 },
+            Row { step: 1, first: 1, cells: vec![1, 2, 0], data_ptr: 3 },
+            Row { step: 2, first: -2, cells: vec![5, 0, 0, 1, 2], data_ptr: -2 },
+            Row { step: 3, first: -2, cells: vec![5, 0, 0, 1, 2], data_ptr: -2 },
+        ]);
+        assert_eq!(timeline.rows[1].value(2), 2);
+        assert_eq!(timeline.rows[1].value(-1), 0);
+    }
+
+    #[test]
+    fn test_downsampling() {
+        let mut timeline = Timeline::default();
+        for step in 0..10 * MAX_ROWS {
+            timeline.record(&[step as u32], 0);
+        }
+        assert!(timeline.len() <= MAX_ROWS && timeline.len() > MAX_ROWS / 2);
+        let interval = timeline.interval;
+        assert!(timeline.rows.iter().enumerate().all(|(i, row)| row.step == i as u64 * interval));
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut timeline = Timeline::default();
+        timeline.record(&[0u8, 0], 1);
+        timeline.record(&[3u8, 3], 1);
+        timeline.finish(&[3u8, 6], 1);
+        let svg = timeline.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // the run of two threes is a single rectangle
+        assert_eq!(svg.matches("fill=\"hsl(").count(), 3);
+        assert!(svg.contains("width=\"32\" height=\"16\" fill=\"hsl(120,90%,55%)\""));
+        assert!(svg.contains("fill=\"hsl(0,90%,55%)\""));
+        assert!(svg.contains("<text x=\"40\" y=\"32\">cell 0</text>"));
+    }
+}
@@ -0,0 +1,42 @@
+//! Interface for running programs from JavaScript once compiled to WebAssembly, e.g. in a browser
+//! playground, see `run_program` and `Playground`.
+//!
+//! Apart from `Playground::output_bytes`, only strings, integers, and byte vectors cross the
+//! interface, all of which wasm-bindgen can pass to and from JavaScript as they are.
+
+use crate::config::InterpreterConfig;
+use crate::difftest;
+use crate::interpreter::ExecutionStatus;
+use crate::program::Program;
+
+pub use crate::playground::Playground;
+
+
+/// Run `source` to completion with `input`, returning its output, or a description of the error
+/// along with the output written before it.
+///
+/// Execution is not interrupted, so a page should use `Playground` for programs that may not end.
+pub fn run_program(source: &str, input: &str) -> Result<String, String> {
+    let program = Program::parse(source);
+    let outcome = difftest::run::<u8>(&program, input.as_bytes(), InterpreterConfig::default());
+    let output = String::from_utf8_lossy(&outcome.output).into_owned();
+    match outcome.status {
+        ExecutionStatus::Terminated => Ok(output),
+        status => Err(format!("{}{}", output, difftest::describe(&status))),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run_program() {
+        assert_eq!(run_program(",.,.,.,.", "echo"), Ok("echo".to_string()));
+        assert_eq!(
+            run_program("+[.", ""),
+            Err("failed ('[' at line 1, col 2 has no matching ']'\n1 | +[.\n  |  ^)".to_string()),
+        );
+    }
+}
@@ -2,13 +2,19 @@ use std::env;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::Duration;
 
 struct TestCase<'a> {
     executable: Box<PathBuf>,
     args: Vec<&'a str>,
     stdin: Option<&'a str>,
+    stdin_open: bool,
+    env: Vec<(&'a str, &'a str)>,
+    interrupt_after: Option<Duration>,
     expected_stdout: Option<&'a str>,
+    expected_stdout_contents: Vec<&'a str>,
     expected_stderr: Option<&'a str>,
+    expected_stderr_contents: Vec<&'a str>,
     expected_retcode: i32,
 }
 
@@ -26,8 +32,13 @@ impl<'a> TestCase<'a> {
             executable: Box::new(root.join("bfi")),
             args: Vec::new(),
             stdin: None,
+            stdin_open: false,
+            env: Vec::new(),
+            interrupt_after: None,
             expected_stdout: None,
+            expected_stdout_contents: Vec::new(),
             expected_stderr: None,
+            expected_stderr_contents: Vec::new(),
             expected_retcode: 0,
         }
     }
@@ -42,16 +53,45 @@ impl<'a> TestCase<'a> {
         self
     }
 
+    /// Keep stdin open until the process exits rather than ending its input after `with_input`.
+    fn with_open_input(&mut self) -> &mut Self {
+        self.stdin_open = true;
+        self
+    }
+
+    /// Set an environment variable of the process, which otherwise gets no `RUST_LOG` and reads no
+    /// configuration file.
+    fn with_env(&mut self, key: &'a str, value: &'a str) -> &mut Self {
+        self.env.push((key, value));
+        self
+    }
+
+    /// Send the process SIGINT, as Ctrl-C does, once it has run for `duration`.
+    fn with_interrupt_after(&mut self, duration: Duration) -> &mut Self {
+        self.interrupt_after = Some(duration);
+        self
+    }
+
     fn expect_stdout(&mut self, stdout: &'a str) -> &mut Self {
         self.expected_stdout = Some(stdout);
         self
     }
 
+    fn expect_stdout_containing(&mut self, stdout: &'a str) -> &mut Self {
+        self.expected_stdout_contents.push(stdout);
+        self
+    }
+
     fn expect_stderr(&mut self, stderr: &'a str) -> &mut Self {
         self.expected_stderr = Some(stderr);
         self
     }
 
+    fn expect_stderr_containing(&mut self, stderr: &'a str) -> &mut Self {
+        self.expected_stderr_contents.push(stderr);
+        self
+    }
+
     fn expect_retcode(&mut self, retcode: i32) -> &mut Self {
         self.expected_retcode = retcode;
         self
@@ -60,6 +100,9 @@ impl<'a> TestCase<'a> {
     fn execute(&self) {
         let mut child_proc = Command::new(&*self.executable)  // reref the deref
             .args(&self.args)
+            .env_remove("RUST_LOG")
+            .env("BFI_CONFIG", "")
+            .envs(self.env.iter().copied())
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
@@ -67,12 +110,24 @@ impl<'a> TestCase<'a> {
             .expect("failed to execute");
 
         if let Some(s) = self.stdin {
-            child_proc.stdin.as_mut()
+            let written = child_proc.stdin.as_mut()
                 .expect("failed to open stdin")
-                .write_all(s.as_bytes())
-                .expect("failed to write to stdin");
+                .write_all(s.as_bytes());
+            // the process may legitimately exit before reading its input, e.g. on a missing file
+            if let Err(e) = written {
+                assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe, "failed to write to stdin");
+            };
         };
+        let stdin = if self.stdin_open { child_proc.stdin.take() } else { None };
+        #[cfg(unix)]
+        {
+            if let Some(duration) = self.interrupt_after {
+                std::thread::sleep(duration);
+                unsafe { libc::kill(child_proc.id() as libc::pid_t, libc::SIGINT) };
+            };
+        }
         let child_output = child_proc.wait_with_output().expect("failed to read stdout");
+        drop(stdin);
 
         let retcode = child_output.status.code().unwrap();
         assert_eq!(self.expected_retcode, retcode);
@@ -82,10 +137,20 @@ impl<'a> TestCase<'a> {
             assert_eq!(s, stdout_str);
         };
 
+        let stdout_str = std::str::from_utf8(&child_output.stdout).unwrap();
+        for s in &self.expected_stdout_contents {
+            assert!(stdout_str.contains(s), "{:?} not in {:?}", s, stdout_str);
+        }
+
         if let Some(s) = self.expected_stderr {
             let stderr_str = std::str::from_utf8(&child_output.stderr).unwrap();
             assert_eq!(s, stderr_str);
         };
+
+        let stderr_str = std::str::from_utf8(&child_output.stderr).unwrap();
+        for s in &self.expected_stderr_contents {
+            assert!(stderr_str.contains(s), "{:?} not in {:?}", s, stderr_str);
+        }
     }
 }
 
@@ -169,7 +234,7 @@ fn sierpinski(n: u64) -> Vec<String> {
         vec!["*".to_string()]
     } else {
         let prev = sierpinski(n - 1);
-        let prev_width = prev.iter().rev().nth(0).unwrap().len();
+        let prev_width = prev.last().unwrap().len();
         let next_width = prev_width * 2 + 1;
         let mut next: Vec<String> = Vec::new();
         for (i, cur) in prev.iter().enumerate() {
@@ -199,7 +264,2015 @@ fn test_missing_file() {
         .with_arg("--file")
         .with_arg("does_not_exist.bf")
         .with_input("anything")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_usage_error() {
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("spherical")
+        .with_arg("+")
+        .expect_stdout("")
+        .expect_retcode(2)
+        .execute();
+    TestCase::new()
+        .with_arg("--help")
+        .expect_stdout_containing("EXIT STATUS:")
+        .expect_retcode(0)
+        .execute();
+}
+
+
+#[test]
+fn test_eval() {
+    TestCase::new()
+        .with_arg("-e")
+        .with_arg(",[.[-],]")
+        .with_input("evaluated")
+        .expect_stdout("evaluated")
+        .execute();
+    // a program can start with '-' without being taken for an option
+    TestCase::new()
+        .with_arg("-e")
+        .with_arg("-.")
+        .with_arg("--cell-size")
+        .with_arg("i32")
+        .with_arg("--io")
+        .with_arg("numeric")
+        .expect_stdout("-1\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg("--")
+        .with_arg("-.")
+        .expect_stdout("255\n")
+        .execute();
+}
+
+#[test]
+fn test_unbalanced_brackets() {
+    TestCase::new()
+        .with_arg("-e")
+        .with_arg("+++.\n[>]]")
+        .expect_stdout("")
+        .expect_stderr("bfi: ']' at line 2, col 4 has no matching '['\n\
+            2 | [>]]\n  |    ^\n")
+        .expect_retcode(3)
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("-")
+        .with_input("[[]")
+        .expect_stdout("")
+        .expect_stderr("bfi: '[' at line 1, col 1 has no matching ']'\n1 | [[]\n  | ^\n")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_limits() {
+    TestCase::new()
+        .with_arg("--max-steps")
+        .with_arg("100")
+        .with_arg("-e")
+        .with_arg("+.[]")
+        .expect_stdout("\x01")
+        .expect_stderr("bfi: exited with error: exceeded the limit of 100 steps\n")
+        .expect_retcode(4)
+        .execute();
+    TestCase::new()
+        .with_arg("--timeout")
+        .with_arg("0.1")
+        .with_arg("-e")
+        .with_arg("+[]")
+        .expect_stderr("bfi: exited with error: exceeded the timeout of 0.1s\n")
+        .expect_retcode(4)
+        .execute();
+    TestCase::new()
+        .with_arg("--max-memory")
+        .with_arg("1K")
+        .with_arg("-e")
+        .with_arg("+[>+]")
+        .expect_stderr("bfi: exited with error: tape exceeded the memory limit of 1024 bytes\n")
+        .expect_retcode(4)
+        .execute();
+}
+
+#[test]
+fn test_trace() {
+    TestCase::new()
+        .with_arg("--trace")
+        .with_arg("-e")
+        .with_arg("+[-]")
+        .expect_stderr(concat!(
+            "     0      0  +1               ptr 0      cell 0\n",
+            "     1      1  zero             ptr 0      cell 1\n",
+        ))
+        .execute();
+    TestCase::new()
+        .with_arg("--trace-last")
+        .with_arg("1")
+        .with_arg("--max-steps")
+        .with_arg("10")
+        .with_arg("--no-optimize")
+        .with_arg("-e")
+        .with_arg("+[>+<]")
+        .expect_stderr(concat!(
+            "     5      5  ] -> 1           ptr 0      cell 1\n",
+            "bfi: exited with error: exceeded the limit of 10 steps\n",
+        ))
+        .expect_retcode(4)
+        .execute();
+}
+
+#[test]
+fn test_profile() {
+    TestCase::new()
+        .with_arg("--profile")
+        .with_arg("-e")
+        .with_arg("+++[>+<-]")
+        .expect_stderr(concat!(
+            "bfi: profile: 3 instructions executed\n",
+            "\n",
+            "instruction      count\n",
+            "add                 1\n",
+            "muladd              1\n",
+            "zero                1\n",
+            "\n",
+            " index position    entries iterations instructions  location\n",
+            "\n",
+            "  cell   accesses\n",
+            "     0          3 ########################################\n",
+        ))
+        .execute();
+}
+
+
+#[test]
+fn test_stats() {
+    TestCase::new()
+        .with_arg("--stats")
+        .with_arg("-e")
+        .with_arg("+++[>+<-]>.")
+        .expect_stdout("\u{3}")
+        .expect_stderr_containing("bfi: stats: 5 instructions executed in ")
+        .expect_stderr_containing("peak tape size: 2 cells\n")
+        .expect_stderr_containing("muladd              1\n")
+        .execute();
+}
+
+#[test]
+fn test_json() {
+    TestCase::new()
+        .with_arg("--json")
+        .with_arg("-e")
+        .with_arg("++++++++[>++++++++<-]>+.+.")
+        .expect_stdout_containing(r#"{"status":"terminated","exitCode":0,"output":"QUI=","#)
+        .expect_stdout_containing(r#""error":null,"#)
+        .expect_stdout_containing(r#""peakTapeSize":2,"#)
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("--json")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("-e")
+        .with_arg("+.<")
+        .expect_stdout_containing(r#""status":"program_error","exitCode":1,"output":"AQ==""#)
+        .expect_stdout_containing(r#""location":{"line":1,"column":3,"file":null}"#)
+        .expect_stderr("")
+        .expect_retcode(1)
+        .execute();
+    TestCase::new()
+        .with_arg("--json")
+        .with_arg("-e")
+        .with_arg("+[")
+        .expect_stdout_containing(r#""status":"parse_error","exitCode":3,"#)
+        .expect_stdout_containing(r#""stats":null}"#)
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_expect() {
+    TestCase::new()
+        .with_arg("--expect")
+        .with_arg("AB")
+        .with_arg("-e")
+        .with_arg("++++++++[>++++++++<-]>+.+.")
+        .expect_stdout("AB")
+        .expect_stderr("")
+        .execute();
+    let expected = env::temp_dir().join(format!("bfi-test-expect-{}.txt", std::process::id()));
+    std::fs::write(&expected, "A\nC\n").unwrap();
+    TestCase::new()
+        .with_arg("--expect")
+        .with_arg(expected.to_str().unwrap())
+        .with_arg("-e")
+        .with_arg("++++++++[>++++++++<-]>+.>++++++++++.<+.>.")
+        .expect_stdout("A\nB\n")
+        .expect_stderr(concat!(
+            "bfi: output differs from the expected output\n",
+            "--- expected\n",
+            "+++ output\n",
+            "@@ -1,2 +1,2 @@\n",
+            " A\n",
+            "-C\n",
+            "+B\n",
+        ))
+        .expect_retcode(6)
+        .execute();
+    std::fs::remove_file(&expected).unwrap();
+    // the failure of the program itself takes precedence
+    TestCase::new()
+        .with_arg("--expect")
+        .with_arg("")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("-e")
+        .with_arg("+.<")
+        .expect_stderr_containing("bfi: exited with error: ")
+        .expect_retcode(1)
+        .execute();
+}
+
+#[test]
+fn test_profile_annotate() {
+    TestCase::new()
+        .with_arg("profile")
+        .with_arg("--annotate")
+        .with_arg("-")
+        .with_input("++ two\n[-]\n")
+        .expect_stderr(concat!(
+            "bfi: profile: 2 instructions executed\n",
+            "\n",
+            "  line      count\n",
+            "     1          1 | ++ two\n",
+            "                  | 11\n",
+            "     2          1 | [-]\n",
+            "                  | 111\n",
+        ))
+        .execute();
+    TestCase::new()
+        .with_arg("profile")
+        .with_arg("--color")
+        .with_arg("-")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_cov() {
+    let lcov = env::temp_dir().join(format!("bfi-test-cov-{}.info", std::process::id()));
+    TestCase::new()
+        .with_arg("cov")
+        .with_arg("--lcov")
+        .with_arg(lcov.to_str().unwrap())
+        .with_arg("-")
+        .with_input("+[-]\n\n[dead>]\n")
+        .expect_stderr(concat!(
+            "bfi: coverage: 5 of 7 commands executed (71.4%)\n",
+            "\n",
+            "  line      count\n",
+            "     1          1 | +[-]\n",
+            "     2            |\n",
+            "     3          1 | [dead>]\n",
+            "                  |      ^^\n",
+        ))
+        .execute();
+    let contents = std::fs::read_to_string(&lcov).unwrap();
+    std::fs::remove_file(&lcov).unwrap();
+    assert_eq!(contents, "TN:\nSF:-\nDA:1,1\nDA:3,1\nLF:2\nLH:2\nend_of_record\n");
+}
+
+#[test]
+fn test_run_subcommand_missing_file() {
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("does_not_exist.bf")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_run_subcommand_stdin() {
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("-")
+        .with_input("++++++++[>++++++++<-]>+.")
+        .expect_stdout("A")
+        .execute();
+}
+
+#[test]
+fn test_file_stdin() {
+    TestCase::new()
+        .with_arg("--file")
+        .with_arg("-")
+        .with_input("++++++++[>++++++++<-]>++.")
+        .expect_stdout("B")
+        .execute();
+}
+
+#[test]
+fn test_script() {
+    TestCase::new()
+        .with_arg("-")
+        .with_input("#!/usr/bin/env bfi\n++++++++[>++++++++<-]>+++.")
+        .expect_stdout("C")
+        .expect_stderr("")
+        .execute();
+    let script = env::temp_dir().join(format!("bfi-test-script-{}.bf", std::process::id()));
+    std::fs::write(&script, "#!/usr/bin/env bfi\n++++++++[>++++++++<-]>++++.\n]").unwrap();
+    TestCase::new()
+        .with_arg(script.to_str().unwrap())
+        .expect_stderr("bfi: ']' at line 3, col 1 has no matching '['\n3 | ]\n  | ^\n")
+        .expect_retcode(3)
+        .execute();
+    std::fs::write(&script, "#!/usr/bin/env bfi\n++++++++[>++++++++<-]>++++.").unwrap();
+    TestCase::new()
+        .with_arg(script.to_str().unwrap())
+        .expect_stdout("D")
+        .expect_stderr("")
+        .execute();
+    std::fs::remove_file(&script).unwrap();
+}
+
+#[test]
+fn test_shared_tape() {
+    let dir = env::temp_dir();
+    let setup = dir.join(format!("bfi-test-shared-tape-{}-setup.bf", std::process::id()));
+    let print = dir.join(format!("bfi-test-shared-tape-{}-print.bf", std::process::id()));
+    std::fs::write(&setup, ">+++++[<+++++++++++++>-]").unwrap();
+    std::fs::write(&print, "<.\n>]").unwrap();
+    let (setup, print) = (setup.to_str().unwrap(), print.to_str().unwrap());
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--shared-tape")
+        .with_arg(setup)
+        .with_arg(print)
+        .expect_stderr_containing("']' at line 2, col 2 in ")
+        .expect_retcode(3)
+        .execute();
+    std::fs::write(print, "<.\n>").unwrap();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--shared-tape")
+        .with_arg(setup)
+        .with_arg(print)
+        .with_arg(print)
+        .expect_stdout("AA")
+        .execute();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg(setup)
+        .with_arg(print)
+        .expect_stderr("bfi: running several program files requires --shared-tape\n")
+        .expect_retcode(2)
+        .execute();
+    std::fs::remove_file(setup).unwrap();
+    std::fs::remove_file(print).unwrap();
+}
+
+#[test]
+fn test_input_string() {
+    TestCase::new()
+        .with_arg("--input-string")
+        .with_arg("from argument")
+        .with_arg(",[.[-],]")
+        .with_input("from stdin")
+        .expect_stdout("from argument")
+        .execute();
+}
+
+#[test]
+fn test_input_file() {
+    let input = env::temp_dir().join(format!("bfi-test-input-{}.txt", std::process::id()));
+    std::fs::write(&input, "from file").unwrap();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--input-file")
+        .with_arg(input.to_str().unwrap())
+        .with_arg("-")
+        .with_input(",[.[-],]")
+        .expect_stdout("from file")
+        .execute();
+    std::fs::remove_file(&input).unwrap();
+    TestCase::new()
+        .with_arg("--input-file")
+        .with_arg("does_not_exist.txt")
+        .with_arg(",.")
+        .expect_stdout("")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_eof() {
+    // prints 'G' unless the cell is 255 after reading past the end of input
+    let program = ",+[[-]+++++++[->++++++++++<]>+.[-]]";
+    TestCase::new()
+        .with_arg(program)
+        .expect_stdout("G")
+        .execute();
+    TestCase::new()
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg(program)
+        .expect_stdout("G")
+        .execute();
+    TestCase::new()
+        .with_arg("--eof")
+        .with_arg("255")
+        .with_arg(program)
+        .expect_stdout("")
+        .execute();
+}
+
+#[test]
+fn test_io_numeric() {
+    TestCase::new()
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg(",>,[-<+>]<.")
+        .with_input("12 30\n")
+        .expect_stdout("42\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg(",")
+        .with_input("twelve")
+        .expect_stderr_containing("read 'twelve', which is not an integer")
+        .expect_retcode(1)
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg("-")
+        .with_input(",.")
+        .expect_stderr_containing("numeric I/O is not supported by compiled programs")
+        .expect_retcode(5)
+        .execute();
+}
+
+#[test]
+fn test_io_utf8() {
+    // wider cells hold a whole code point, 'è' coming right before 'é'
+    TestCase::new()
+        .with_arg("--io")
+        .with_arg("utf8")
+        .with_arg("--cell-size")
+        .with_arg("16")
+        .with_arg(",-.")
+        .with_input("é")
+        .expect_stdout("è")
+        .execute();
+    // byte cells see each byte of a character, and an invalid one is written out as U+FFFD
+    TestCase::new()
+        .with_arg("--io")
+        .with_arg("utf8")
+        .with_arg(",.,.>-.")
+        .with_input("é")
+        .expect_stdout("é\u{fffd}")
+        .execute();
+}
+
+#[test]
+fn test_break() {
+    TestCase::new()
+        .with_arg("--break")
+        .with_arg("cell[1] == 6")
+        .with_arg("--break")
+        .with_arg("watch ptr")
+        .with_arg("+++[->++<]>+.")
+        .with_input("c\np\nc\n")
+        .expect_stdout_containing("breakpoint 1: cell[1] == 6\n")
+        .expect_stdout_containing("watchpoint 2: ptr changed from 0 to 1\n")
+        .expect_stdout_containing("  dec   0  6\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--break")
+        .with_arg("cell[1] = 6")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_manage_breakpoints() {
+    // each '%' is numbered in the order it appears, ahead of the breakpoints added later
+    TestCase::new()
+        .with_arg("+%+%+%.")
+        .with_input("disable 2\ndelete 3\nwatch cell\ninfo breakpoints\ndisable 4\nc\n")
+        .expect_stdout_containing("breakpoint 1 at line 1, col 2\n")
+        .expect_stdout_containing("added breakpoint 4\n")
+        .expect_stdout_containing(concat!(
+            "num  enabled  breakpoint\n",
+            "1    yes      % at line 1, col 2\n",
+            "2    no       % at line 1, col 4\n",
+            "4    yes      watch cell\n",
+        ))
+        .expect_stdout_containing("\u{3}")
+        .execute();
+    TestCase::new()
+        .with_arg("+%+%.")
+        .with_input("disable 2\nenable 2\ndelete 5\nc\nc\n")
+        .expect_stdout_containing("no breakpoint 5\n")
+        .expect_stdout_containing("breakpoint 2 at line 1, col 4\n")
+        .execute();
+}
+
+#[test]
+fn test_query() {
+    TestCase::new()
+        .with_arg("+++>++++%.")
+        .with_input("? cell[0] * cell\n? ptr\n? sum(cell[0..10]) - max(cell[0..2])\n? cell[\nc\n")
+        .expect_stdout_containing("12\n1\n3\nexpression ended early\n")
+        .expect_stdout_containing("\u{4}")
+        .execute();
+    TestCase::new()
+        .with_input("+++>++\n? cell[ptr - 1] / cell\n? min(cell[0..0])\n")
+        .expect_stdout_containing("1\n")
+        .expect_stdout_containing("range 0..0 is empty\n")
+        .execute();
+}
+
+#[test]
+fn test_breakpoint_action() {
+    TestCase::new()
+        .with_arg("--breakpoint-action")
+        .with_arg("trace")
+        .with_arg("+>++%<.")
+        .expect_stdout("\u{1}")
+        .expect_stderr("breakpoint at line 1, col 5: data pointer 1, cell 2\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--breakpoint-action")
+        .with_arg("dump")
+        .with_arg("+>++%<.")
+        .expect_stdout("\u{1}")
+        .expect_stderr_containing("breakpoint at line 1, col 5\n")
+        .expect_stderr_containing("  dec   1  2\n")
+        .execute();
+    // cells to the left of where the data pointer started have negative indices
+    TestCase::new()
+        .with_arg("--breakpoint-action")
+        .with_arg("trace")
+        .with_arg("<<+%")
+        .expect_stderr("breakpoint at line 1, col 4: data pointer -2, cell 1\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--breakpoint-action")
+        .with_arg("dump")
+        .with_arg("<<+%")
+        .expect_stderr_containing("index  -2 -1  0\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--breakpoint-action")
+        .with_arg("ignore")
+        .with_arg("+>++%<.")
+        .expect_stdout("\u{1}")
+        .expect_stderr("")
+        .execute();
+}
+
+#[test]
+fn test_session() {
+    TestCase::new()
+        .with_input(concat!(
+            "++++++++[\n>++++++++<-]>+.\n:dump\n",
+            "<[\n:reset\n]\n:reset\n:dump\n",
+            ":bogus\n+++++++[>++++++++++<-]>.\n",
+        ))
+        .expect_stdout_containing("A")
+        .expect_stdout_containing("  dec   0 65\n")
+        .expect_stdout_containing("  dec   0\n")
+        .expect_stdout_containing("unknown command ':bogus'")
+        .expect_stdout_containing("F")
+        .execute();
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_input("<\n+.\n")
+        .expect_stdout_containing("error: data pointer moved past the start of the tape")
+        .expect_stdout_containing("\u{1}")
+        .execute();
+}
+
+#[test]
+fn test_tape_mode_fixed() {
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("<")
         .expect_retcode(1)
         .execute();
 }
 
+#[test]
+fn test_tape_size() {
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("--tape-size")
+        .with_arg("3")
+        .with_arg(">>>")
+        .expect_stderr_containing("past the end of the tape")
+        .expect_retcode(1)
+        .execute();
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("circular")
+        .with_arg("--tape-size")
+        .with_arg("2")
+        .with_arg("+>>.")
+        .expect_stdout("\u{1}")
+        .execute();
+    // a growable tape only allocates the cells up front
+    TestCase::new()
+        .with_arg("--tape-size")
+        .with_arg("100000")
+        .with_arg("<++++++++[>++++++++<-]>+.")
+        .expect_stdout("A")
+        .execute();
+    TestCase::new()
+        .with_arg("--tape-size")
+        .with_arg("0")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_bounds() {
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("--bounds")
+        .with_arg("wrap")
+        .with_arg("<++++++++[>++++++++<-]>+.")
+        .expect_stdout("A")
+        .execute();
+    // stays on the first cell rather than moving to a cell of its own
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("--bounds")
+        .with_arg("clamp")
+        .with_arg("+<+.")
+        .expect_stdout("\u{2}")
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("--bounds")
+        .with_arg("clamp")
+        .with_arg("-")
+        .with_input("+")
+        .expect_stderr_containing("clamping at the ends of the tape is not supported")
+        .expect_retcode(5)
+        .execute();
+}
+
+
+#[test]
+fn test_overflow() {
+    TestCase::new()
+        .with_arg("--overflow")
+        .with_arg("saturate")
+        .with_arg(">-+.")
+        .expect_stdout("\u{1}")
+        .execute();
+    TestCase::new()
+        .with_arg("--overflow")
+        .with_arg("error")
+        .with_arg("+.--")
+        .expect_stdout("\u{1}")
+        .expect_stderr(concat!(
+            "bfi: exited with error: cell overflowed its bounds at line 1, col 4\n",
+            "1 | +.--\n",
+            "  |    ^\n",
+        ))
+        .expect_retcode(1)
+        .execute();
+    TestCase::new()
+        .with_arg("--overflow")
+        .with_arg("saturate")
+        .with_arg("--cell-size")
+        .with_arg("i32")
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg(",+.")
+        .with_input("2147483647")
+        .expect_stdout("2147483647\n")
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--overflow")
+        .with_arg("error")
+        .with_arg("-")
+        .with_input("+")
+        .expect_stderr_containing("cells that do not wrap on overflow are not supported")
+        .expect_retcode(5)
+        .execute();
+}
+
+#[test]
+fn test_tape_mode_circular() {
+    TestCase::new()
+        .with_arg("--tape-mode")
+        .with_arg("circular")
+        .with_arg("<++++++++[>++++++++<-]>+.")
+        .expect_stdout("A")
+        .execute();
+}
+
+#[test]
+fn test_cell_size() {
+    // sets a cell to 256, which is only nonzero with cells wider than a byte
+    let program = "++++++++++++++++[>++++++++++++++++<-]>[[-]<+++++++++[>+++++++<-]>+.[-]]";
+    TestCase::new()
+        .with_arg(program)
+        .expect_stdout("")
+        .execute();
+    TestCase::new()
+        .with_arg("--cell-size")
+        .with_arg("u16")
+        .with_arg(program)
+        .expect_stdout("@")
+        .execute();
+}
+
+#[test]
+fn test_no_optimize() {
+    TestCase::new()
+        .with_arg("--no-optimize")
+        .with_arg("+[-->-[>>+>-----<<]<--<---]>-.>>>+.>>..+++[.>]<<<<.+++.------.<<-.>>>>+.")
+        .expect_stdout("Hello, World!")
+        .execute();
+}
+
+#[test]
+fn test_dump_ir() {
+    TestCase::new()
+        .with_arg("--dump-ir")
+        .with_arg("+++[->++<]>.")
+        .expect_stdout("\u{6}")
+        .expect_stderr(concat!(
+            "     0      0  +3\n",
+            "     1      3  muladd >1 *2\n",
+            "     2      3  zero\n",
+            "     3     10  >1\n",
+            "     4     11  .\n",
+            "bfi: optimizations: collapsed runs: 2, set zero: 0, scans: 0, multiplication loops: 1\n",
+        ))
+        .execute();
+}
+
+#[test]
+fn test_compile_c() {
+    let output = env::temp_dir().join(format!("bfi-test-compile-{}.c", std::process::id()));
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--target")
+        .with_arg("c")
+        .with_arg("-o")
+        .with_arg(output.to_str().unwrap())
+        .with_arg("-")
+        .with_input("+++[->++<]>.")
+        .expect_stdout("")
+        .expect_stderr("")
+        .execute();
+    let compiled = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert!(compiled.contains("int main(void) {"));
+    assert!(compiled.contains("tape[ptr] += value * 2u;"));
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("does_not_exist.bf")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_optimize() {
+    TestCase::new()
+        .with_arg("optimize")
+        .with_arg("-")
+        .with_input("a comment\n+++++ -- >><<< [+] ,[>+<-]>.")
+        .expect_stdout("+++<[-],[->+<]>.\n")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--target")
+        .with_arg("bf")
+        .with_arg("--cell-size")
+        .with_arg("16")
+        .with_arg("-")
+        .with_input(&format!("{}.", "-".repeat(10)))
+        .expect_stdout("----------.\n")
+        .execute();
+    TestCase::new()
+        .with_arg("optimize")
+        .with_arg("-")
+        .with_input("]")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_compile_bytecode() {
+    let output = env::temp_dir().join(format!("bfi-test-compile-{}.bfc", std::process::id()));
+    let filename = output.to_str().unwrap();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--target")
+        .with_arg("bytecode")
+        .with_arg("--cell-size")
+        .with_arg("u16")
+        .with_arg("-o")
+        .with_arg(filename)
+        .with_arg("-")
+        .with_input("++++++++++++++++[>++++++++++++++++<-]>[>++++++++[<++++++++>-]<+.[-]]")
+        .expect_stdout("")
+        .expect_stderr("")
+        .execute();
+    // the cell size comes from the bytecode, so 256 does not wrap to zero and 321 prints 'A'
+    TestCase::new()
+        .with_arg("run")
+        .with_arg(filename)
+        .expect_stdout("A")
+        .expect_retcode(0)
+        .execute();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--cell-size")
+        .with_arg("u8")
+        .with_arg(filename)
+        .expect_retcode(2)
+        .execute();
+    let mut truncated = std::fs::read(&output).unwrap();
+    truncated.pop();
+    std::fs::write(&output, truncated).unwrap();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg(filename)
+        .expect_stderr("bfi: invalid bytecode: unexpected end at byte 30\n")
+        .expect_retcode(3)
+        .execute();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_disasm() {
+    let output = env::temp_dir().join(format!("bfi-test-disasm-{}.bfc", std::process::id()));
+    let filename = output.to_str().unwrap();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--target")
+        .with_arg("bytecode")
+        .with_arg("-o")
+        .with_arg(filename)
+        .with_arg("-")
+        .with_input(",[->++<]>.")
+        .expect_retcode(0)
+        .execute();
+    TestCase::new()
+        .with_arg("disasm")
+        .with_arg(filename)
+        .expect_stdout(concat!(
+            "; bytecode version 1, u8 cells, 5 instructions\n",
+            "     0  0x0007  ,\n",
+            "     1  0x0008  muladd >1 *2\n",
+            "     2  0x000b  zero\n",
+            "     3  0x000c  >1\n",
+            "     4  0x000e  .\n",
+        ))
+        .expect_retcode(0)
+        .execute();
+    std::fs::remove_file(&output).unwrap();
+    TestCase::new()
+        .with_arg("disasm")
+        .with_arg("-")
+        .with_input("+.")
+        .expect_stderr("bfi: file '-' is not bytecode, see 'bfi compile --target bytecode'\n")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_fmt() {
+    TestCase::new()
+        .with_arg("fmt")
+        .with_arg("-")
+        .with_input("cat\n\n\n  ,[>+++[-<++>]<.,]")
+        .expect_stdout("cat\n\n,[\n    >+++[-<++>]<.,\n]\n")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("fmt")
+        .with_arg("--strip-comments")
+        .with_arg("--width")
+        .with_arg("4")
+        .with_arg("-")
+        .with_input("cat: ,[.,]")
+        .expect_stdout(",[\n    .\n    ,\n]\n")
+        .execute();
+    TestCase::new()
+        .with_arg("fmt")
+        .with_arg("--check")
+        .with_arg("-")
+        .with_input(",[.,]\n")
+        .expect_stdout("")
+        .expect_retcode(0)
+        .execute();
+    TestCase::new()
+        .with_arg("fmt")
+        .with_arg("--check")
+        .with_arg("-")
+        .with_input(",[.,]")
+        .expect_stderr("bfi: file '-' is not formatted\n")
+        .expect_retcode(6)
+        .execute();
+    TestCase::new()
+        .with_arg("fmt")
+        .with_arg("-")
+        .with_input("[[]")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_minify() {
+    TestCase::new()
+        .with_arg("minify")
+        .with_arg("-")
+        .with_input("[a comment loop]\n+++ three\n[->++<]>+-. print six\n[-]")
+        .expect_stdout("+++[->++<]>.[-]\n")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("minify")
+        .with_arg("-")
+        .with_input("]")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_score() {
+    TestCase::new()
+        .with_arg("score")
+        .with_arg("-")
+        .with_input("[c]\n+++ three\n[->++<]>+-. six\n")
+        .expect_stdout("commands: 16\nbytes: 30\nnon-command characters: 14\n")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("score")
+        .with_arg("--minify")
+        .with_arg("-")
+        .with_input("[c]\n+++ three\n[->++<]>+-. six\n")
+        .expect_stdout("commands: 12\nbytes: 12\nnon-command characters: 0\n")
+        .execute();
+    TestCase::new()
+        .with_arg("score")
+        .with_arg("--minify")
+        .with_arg("-")
+        .with_input("[")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_lint() {
+    TestCase::new()
+        .with_arg("lint")
+        .with_arg("-")
+        .with_input("[comment] ,[.,]")
+        .expect_stdout("")
+        .expect_stderr("")
+        .expect_retcode(0)
+        .execute();
+    TestCase::new()
+        .with_arg("lint")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("-")
+        .with_input("+[]<")
+        .expect_stderr(concat!(
+            "bfi: warning: loop at line 1, col 2 never ends once entered, as it changes neither ",
+            "the current cell nor the data pointer\n",
+            "1 | +[]<\n",
+            "  |  ^\n",
+            "bfi: warning: move at line 1, col 4 goes past the start of the tape\n",
+            "1 | +[]<\n",
+            "  |    ^\n",
+        ))
+        .expect_retcode(7)
+        .execute();
+}
+
+#[test]
+fn test_generate() {
+    let output = env::temp_dir().join(format!("bfi-test-generate-{}.bf", std::process::id()));
+    let filename = output.to_str().unwrap();
+    TestCase::new()
+        .with_arg("generate")
+        .with_arg("--text")
+        .with_arg("Hello, World!")
+        .with_arg("-o")
+        .with_arg(filename)
+        .expect_stdout("")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg(filename)
+        .expect_stdout("Hello, World!")
+        .execute();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_preprocess() {
+    let dir = env::temp_dir().join(format!("bfi-test-preprocess-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lib.bf"), "#define letter(n) +*n[>+*8<-]>+.[-]<\n").unwrap();
+    std::fs::write(dir.join("main.bf"), "#include \"lib.bf\"\nletter(9) letter(8)\n").unwrap();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--preprocess")
+        .with_arg(dir.join("main.bf").to_str().unwrap())
+        .expect_stdout("IA")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("--preprocess")
+        .with_arg("-e")
+        .with_arg("unknown(1)+*33.")
+        .expect_stdout("!")
+        .execute();
+    TestCase::new()
+        .with_arg("--preprocess")
+        .with_arg("-e")
+        .with_arg("#include \"missing.bf\"")
+        .expect_retcode(3)
+        .execute();
+    TestCase::new()
+        .with_arg("--preprocess")
+        .with_arg("-e")
+        .with_arg("+*99999999999")
+        .expect_stderr("bfi: <program>:1: expands past the limit of 4194304 characters\n")
+        .expect_retcode(3)
+        .execute();
+    // errors refer to the file and line the failing command was written on
+    std::fs::write(dir.join("left.bf"), "#define left()\n+\n<<\n#end\n").unwrap();
+    std::fs::write(dir.join("fail.bf"), "#include \"left.bf\"\n>left()\n").unwrap();
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--preprocess")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg(dir.join("fail.bf").to_str().unwrap())
+        .expect_stderr(&format!(
+            "bfi: exited with error: data pointer moved past the start of the tape at line 2, \
+                col 2 in {}\n2 | >left()\n  |  ^\n",
+            dir.join("fail.bf").display(),
+        ))
+        .expect_retcode(1)
+        .execute();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_batch() {
+    let dir = env::temp_dir().join(format!("bfi-test-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, contents) in &[
+        ("cat.bf", ",[.,]"),
+        ("cat.in", "meow"),
+        ("cat.out", "meow"),
+        ("hang.b", "+[]"),
+        ("manifest", "# program input expected\ncat.bf - cat.out\n"),
+    ] {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    TestCase::new()
+        .with_arg("batch")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg("--max-steps")
+        .with_arg("100")
+        .with_arg("--jobs")
+        .with_arg("2")
+        .with_arg(dir.to_str().unwrap())
+        .expect_stdout(&format!(
+            "pass {}\nFAIL {}: stopped (exceeded the limit of 100 steps)\n1 passed, 1 failed\n",
+            dir.join("cat.bf").display(),
+            dir.join("hang.b").display(),
+        ))
+        .expect_retcode(6)
+        .execute();
+    TestCase::new()
+        .with_arg("batch")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg(dir.join("manifest").to_str().unwrap())
+        .expect_stdout("FAIL cat.bf: output: 0 bytes but 4 expected, first differing at byte 0\n\
+            0 passed, 1 failed\n")
+        .expect_retcode(6)
+        .execute();
+    std::fs::remove_dir_all(&dir).unwrap();
+    TestCase::new()
+        .with_arg("batch")
+        .with_arg(dir.to_str().unwrap())
+        .expect_stderr_containing("could not be read")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_equiv() {
+    let dir = env::temp_dir().join(format!("bfi-test-equiv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, contents) in &[
+        ("cat.bf", ",[.,]"),
+        ("golfed.bf", "cat ,[.,]"),
+        ("inc.b", ",[+.,]"),
+    ] {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    TestCase::new()
+        .with_arg("equiv")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg(&path("cat.bf"))
+        .with_arg(&path("golfed.bf"))
+        .expect_stdout("no differences on 100 inputs\n")
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("equiv")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg("--inputs")
+        .with_arg(&path("cat.bf"))
+        .with_arg(&path("cat.bf"))
+        .with_arg(&path("inc.b"))
+        .expect_stdout(concat!(
+            "programs differ on input 0 \",[.,]\", ",
+            "output: 5 bytes but 5 for the second program, first differing at byte 0\n",
+        ))
+        .expect_retcode(6)
+        .execute();
+    TestCase::new()
+        .with_arg("equiv")
+        .with_arg("--max-steps")
+        .with_arg("100")
+        .with_arg(&path("cat.bf"))
+        .with_arg(&path("golfed.bf"))
+        .expect_stdout_containing("99 of them not compared in full")
+        .execute();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_difftest() {
+    TestCase::new()
+        .with_arg("difftest")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg("--input-string")
+        .with_arg("ab")
+        .with_arg("-")
+        .with_input(",[>+++[-<++>]<.,]>>><<<")
+        .expect_stdout(concat!(
+            "reference: terminated, 2 bytes of output\n",
+            "optimized: terminated, 2 bytes of output\n",
+            "no differences\n",
+        ))
+        .expect_stderr("")
+        .execute();
+    TestCase::new()
+        .with_arg("difftest")
+        .with_arg("--max-steps")
+        .with_arg("10")
+        .with_arg("-")
+        .with_input("+[]")
+        .expect_stdout(concat!(
+            "reference: stopped (exceeded the limit of 10 steps), 0 bytes of output\n",
+            "optimized: stopped (exceeded the limit of 10 steps), 0 bytes of output\n",
+            "not compared, as execution was stopped by a limit\n",
+        ))
+        .expect_retcode(4)
+        .execute();
+}
+
+
+#[test]
+fn test_snapshot() {
+    let snapshot = env::temp_dir().join(format!("bfi-test-snapshot-{}.txt", std::process::id()));
+    TestCase::new()
+        .with_arg("--max-steps")
+        .with_arg("3")
+        .with_arg("--snapshot-on-exit")
+        .with_arg(snapshot.to_str().unwrap())
+        .with_arg("-e")
+        .with_arg("+++.>++.")
+        .expect_stdout("\u{3}")
+        .expect_stderr("bfi: exited with error: exceeded the limit of 3 steps\n")
+        .expect_retcode(4)
+        .execute();
+    TestCase::new()
+        .with_arg("--restore")
+        .with_arg(snapshot.to_str().unwrap())
+        .with_arg("-e")
+        .with_arg("+++.>++.")
+        .expect_stdout("\u{2}")
+        .execute();
+    TestCase::new()
+        .with_arg("--restore")
+        .with_arg(snapshot.to_str().unwrap())
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_stderr(concat!(
+            "bfi: exited with error: invalid snapshot: ",
+            "taken of a program of 5 instructions rather than 1\n",
+        ))
+        .expect_retcode(1)
+        .execute();
+    std::fs::remove_file(&snapshot).unwrap();
+    TestCase::new()
+        .with_arg("--restore")
+        .with_arg("does_not_exist.txt")
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_visualize() {
+    let svg = env::temp_dir().join(format!("bfi-test-visualize-{}.svg", std::process::id()));
+    TestCase::new()
+        .with_arg("--visualize")
+        .with_arg(svg.to_str().unwrap())
+        .with_arg("-e")
+        .with_arg("++++[>+++<-]>.")
+        .expect_stdout("\u{c}")
+        .expect_stderr("")
+        .execute();
+    let contents = std::fs::read_to_string(&svg).unwrap();
+    std::fs::remove_file(&svg).unwrap();
+    assert!(contents.starts_with("<svg "));
+    assert!(contents.contains("<polyline "));
+    TestCase::new()
+        .with_arg("--visualize")
+        .with_arg("does_not_exist/out.svg")
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_stderr_containing(
+            "bfi: visualization could not be saved to 'does_not_exist/out.svg'",
+        )
+        .expect_retcode(5)
+        .execute();
+}
+
+#[test]
+fn test_delay() {
+    TestCase::new()
+        .with_arg("--delay")
+        .with_arg("10")
+        .with_arg("--delay-output-only")
+        .with_arg("-e")
+        .with_arg("+.+.")
+        .expect_stdout("\u{1}\u{2}")
+        .execute();
+    TestCase::new()
+        .with_arg("--delay-output-only")
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_animate() {
+    TestCase::new()
+        .with_arg("--animate")
+        .with_arg("--speed")
+        .with_arg("1000")
+        .with_arg("-e")
+        .with_arg(",+.")
+        .with_input("a")
+        .expect_stdout("b")
+        .expect_stderr_containing(" bfi | terminated | 4 steps\x1b[K\n")
+        .expect_stderr_containing("  out b\x1b[K\n")
+        .execute();
+    TestCase::new()
+        .with_arg("--speed")
+        .with_arg("0")
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_logging() {
+    // steps run by the JIT are not counted
+    TestCase::new()
+        .with_env("RUST_LOG", "debug")
+        .with_arg("--no-jit")
+        .with_arg("-e")
+        .with_arg("+++.")
+        .expect_stdout("\u{3}")
+        .expect_stderr_containing("bfi: DEBUG bfi::program: parsed 4 instructions\n")
+        .expect_stderr_containing("bfi: DEBUG bfi::optimizer: optimized 4 instructions into 2")
+        .expect_stderr_containing("bfi: DEBUG bfi::interpreter: execution terminated after 2 steps")
+        .execute();
+    TestCase::new()
+        .with_env("RUST_LOG", "info,bfi::interpreter=trace")
+        .with_arg("-e")
+        .with_arg(">+")
+        .expect_stderr(concat!(
+            "bfi: DEBUG bfi::interpreter: executing 2 instructions\n",
+            "bfi: TRACE bfi::interpreter: instruction 0 '>1' with the data pointer at 0\n",
+            "bfi: TRACE bfi::interpreter: instruction 1 '+1' with the data pointer at 1\n",
+            "bfi: DEBUG bfi::interpreter: execution terminated after 2 steps\n",
+        ))
+        .execute();
+    TestCase::new()
+        .with_env("RUST_LOG", "loud")
+        .with_arg("-e")
+        .with_arg("+")
+        .expect_stderr("bfi: ignoring invalid RUST_LOG directive 'loud'\n")
+        .execute();
+}
+
+#[test]
+fn test_record_and_replay_input() {
+    let recording = env::temp_dir().join(format!("bfi-test-recording-{}.txt", std::process::id()));
+    TestCase::new()
+        .with_arg("--record-input")
+        .with_arg(recording.to_str().unwrap())
+        .with_arg(",.,.")
+        .with_input("abc")
+        .expect_stdout("ab")
+        .execute();
+    assert_eq!(std::fs::read_to_string(&recording).unwrap(), "ab");
+    TestCase::new()
+        .with_arg("--replay")
+        .with_arg(recording.to_str().unwrap())
+        .with_arg(",.,.,.")
+        .with_input("ignored")
+        .expect_stdout("abb")
+        .execute();
+    std::fs::remove_file(&recording).unwrap();
+    TestCase::new()
+        .with_arg("--replay")
+        .with_arg("does_not_exist.txt")
+        .with_arg(",.")
+        .expect_stderr(concat!(
+            "bfi: replay file 'does_not_exist.txt' could not be read ",
+            "(No such file or directory (os error 2))\n",
+        ))
+        .expect_retcode(2)
+        .execute();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupt() {
+    TestCase::new()
+        .with_interrupt_after(Duration::from_millis(200))
+        .with_arg("--stats")
+        .with_arg("-e")
+        .with_arg("+.[>+<]")
+        .expect_stdout("\u{1}")
+        .expect_stderr_containing("bfi: stats: ")
+        .expect_stderr_containing("bfi: interrupted\n")
+        .expect_retcode(130)
+        .execute();
+    // without --stats a build with the jit feature compiles the loop, which is interrupted alike
+    TestCase::new()
+        .with_interrupt_after(Duration::from_millis(200))
+        .with_arg("-e")
+        .with_arg("+.[>+<]")
+        .expect_stdout("\u{1}")
+        .expect_stderr("bfi: interrupted\n")
+        .expect_retcode(130)
+        .execute();
+    // the REPL reads its commands from stdin as it is not a terminal
+    TestCase::new()
+        .with_interrupt_after(Duration::from_millis(200))
+        .with_arg("--on-interrupt")
+        .with_arg("repl")
+        .with_arg("-e")
+        .with_arg("+.[>+<]")
+        .with_input("p\nq\n")
+        .expect_stdout_containing("\u{1}interrupted\n")
+        // the width of the columns depends on how far the second cell has counted
+        .expect_stdout_containing("  hex ")
+        .expect_stdout_containing(" 01 ")
+        .expect_stderr("")
+        .execute();
+}
+
+#[test]
+fn test_raw_input() {
+    // stdin is read as it is when it is not a terminal
+    TestCase::new()
+        .with_arg("--raw-input")
+        .with_arg(",.,.")
+        .with_input("ab\n")
+        .expect_stdout("ab")
+        .execute();
+    TestCase::new()
+        .with_arg("--raw-input")
+        .with_arg("--input-string")
+        .with_arg("ab")
+        .with_arg(",.")
+        .expect_stderr_containing("cannot be used with")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_input_timeout() {
+    // stdin stays open, so the second ',' gives up waiting rather than reaching the end of input
+    TestCase::new()
+        .with_arg("--input-timeout")
+        .with_arg("0.05")
+        .with_arg("--eof")
+        .with_arg("0")
+        .with_arg(",.,+.")
+        .with_input("a")
+        .with_open_input()
+        .expect_stdout("a\u{1}")
+        .execute();
+}
+
+#[test]
+fn test_output_file() {
+    let output = env::temp_dir().join(format!("bfi-test-output-{}.txt", std::process::id()));
+    TestCase::new()
+        .with_arg("--output")
+        .with_arg(output.to_str().unwrap())
+        .with_arg(",.,.")
+        .with_input("abc")
+        .expect_stdout("")
+        .execute();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "ab");
+    TestCase::new()
+        .with_arg("--output")
+        .with_arg(output.to_str().unwrap())
+        .with_arg("--append")
+        .with_arg(",.")
+        .with_input("c")
+        .expect_stdout("")
+        .execute();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "abc");
+    TestCase::new()
+        .with_arg("-o")
+        .with_arg(output.to_str().unwrap())
+        .with_arg(",.")
+        .with_input("d")
+        .execute();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "d");
+    std::fs::remove_file(&output).unwrap();
+    TestCase::new()
+        .with_arg("--append")
+        .with_arg(",.")
+        .expect_stderr_containing("--output")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_sandbox() {
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--max-steps")
+        .with_arg("100")
+        .with_arg("+[]")
+        .expect_stderr("bfi: exited with error: exceeded the limit of 100 steps\n")
+        .expect_retcode(4)
+        .execute();
+    // the debugging commands are comments, so neither dumps the state nor opens the REPL
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("+#%.")
+        .expect_stdout("\u{1}")
+        .expect_stderr("")
+        .execute();
+    // a fixed tape is cut down to the cells that fit in the memory limit rather than allocated
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--max-memory")
+        .with_arg("4")
+        .with_arg("--tape-mode")
+        .with_arg("fixed")
+        .with_arg("--tape-size")
+        .with_arg("100000000000")
+        .with_arg("+>>>>")
+        .expect_stderr_containing("data pointer moved past the end of the tape")
+        .expect_retcode(1)
+        .execute();
+    // nor can the header of the program lift the memory limit
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg(";! tape-mode=fixed tape=100000000000\n+.")
+        .expect_stdout("\u{1}")
+        .execute();
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--max-memory")
+        .with_arg("4")
+        .with_arg(";! tape-mode=fixed tape=100000000000\n+>>>>")
+        .expect_stderr_containing("data pointer moved past the end of the tape")
+        .expect_retcode(1)
+        .execute();
+    // every row of the grid and every thread counts against the same memory limit
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--max-memory")
+        .with_arg("64K")
+        .with_arg("--extensions")
+        .with_arg("grid")
+        .with_arg("+[_+]")
+        .expect_stderr("bfi: exited with error: tape exceeded the memory limit of 65536 bytes\n")
+        .expect_retcode(4)
+        .execute();
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--max-memory")
+        .with_arg("64K")
+        .with_arg("--dialect")
+        .with_arg("brainfork")
+        .with_arg("+[Y]")
+        .expect_stderr("bfi: exited with error: tape exceeded the memory limit of 65536 bytes\n")
+        .expect_retcode(4)
+        .execute();
+    TestCase::new()
+        .with_arg("--sandbox")
+        .expect_stderr("bfi: --sandbox requires a program, as it never opens the REPL\n")
+        .expect_retcode(2)
+        .execute();
+    TestCase::new()
+        .with_arg("--sandbox")
+        .with_arg("--preprocess")
+        .with_arg("+")
+        .expect_stderr_containing("cannot be used with")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_header() {
+    // prints 'A' only if 256 fits in a cell
+    let program = format!("{}[>{}.<[-]]", "+".repeat(256), "+".repeat(65));
+    let with_header = format!(";! cell-size=16 io=bytes\n;! eof=zero\n{}>+,[.[-]]", program);
+    TestCase::new()
+        .with_arg(&with_header)
+        .expect_stdout("A")
+        .execute();
+    // options given explicitly take precedence over the header
+    TestCase::new()
+        .with_arg("--cell-size")
+        .with_arg("8")
+        .with_arg("--eof")
+        .with_arg("unchanged")
+        .with_arg(&with_header)
+        .expect_stdout("\u{1}")
+        .execute();
+    TestCase::new()
+        .with_arg(";! cells=16\n+")
+        .expect_stderr("bfi: program header is invalid (unknown setting 'cells')\n")
+        .expect_retcode(3)
+        .execute();
+}
+
+#[test]
+fn test_config_file() {
+    let config = env::temp_dir().join(format!("bfi-test-config-{}.toml", std::process::id()));
+    std::fs::write(&config, "# defaults\ncell-size = 16\neof = \"zero\"\n").unwrap();
+    // prints 'A' only if 256 fits in a cell, then the next cell unless ',' zeroes it
+    let program = format!("{}[>{}.<[-]]>+,[.[-]]", "+".repeat(256), "+".repeat(65));
+    TestCase::new()
+        .with_env("BFI_CONFIG", config.to_str().unwrap())
+        .with_arg(&program)
+        .expect_stdout("A")
+        .execute();
+    // options given explicitly and the header of the program take precedence over the file
+    let with_header = format!(";! eof=unchanged\n{}", program);
+    TestCase::new()
+        .with_env("BFI_CONFIG", config.to_str().unwrap())
+        .with_arg("--cell-size")
+        .with_arg("8")
+        .with_arg(&with_header)
+        .expect_stdout("\u{1}")
+        .execute();
+    std::fs::write(&config, "cells = 16\n").unwrap();
+    let message = format!(
+        "bfi: config file '{}' is invalid (line 1: unknown setting 'cells')\n",
+        config.display(),
+    );
+    TestCase::new()
+        .with_env("BFI_CONFIG", config.to_str().unwrap())
+        .with_arg("+")
+        .expect_stderr(&message)
+        .expect_retcode(2)
+        .execute();
+    std::fs::remove_file(&config).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_descriptors() {
+    // the descriptors of stdin and stderr stand in for ones redirected by a sandbox
+    TestCase::new()
+        .with_arg("--fd-in")
+        .with_arg("0")
+        .with_arg("--fd-out")
+        .with_arg("2")
+        .with_arg(",+.")
+        .with_input("a")
+        .expect_stdout("")
+        .expect_stderr("b")
+        .execute();
+    TestCase::new()
+        .with_arg("--fd-in")
+        .with_arg("99")
+        .with_arg(",.")
+        .expect_stderr_containing("file descriptor 99 could not be used")
+        .expect_retcode(2)
+        .execute();
+    TestCase::new()
+        .with_arg("--fd-out")
+        .with_arg("1")
+        .with_arg("--output")
+        .with_arg("out.txt")
+        .with_arg(",.")
+        .expect_stderr_containing("cannot be used with")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_dialect() {
+    let ook: Vec<&str> = "+++[>++++++++++<-]>+++.".chars().map(|c| match c {
+        '>' => "Ook. Ook?",
+        '<' => "Ook? Ook.",
+        '+' => "Ook. Ook.",
+        '-' => "Ook! Ook!",
+        '.' => "Ook! Ook.",
+        '[' => "Ook! Ook?",
+        ']' => "Ook? Ook!",
+        _ => unreachable!(),
+    }).collect();
+    let source = ook.join("\n");
+    TestCase::new()
+        .with_arg("run")
+        .with_arg("--dialect")
+        .with_arg("ook")
+        .with_arg("-")
+        .with_input(&source)
+        .expect_stdout("!")
+        .execute();
+    TestCase::new()
+        .with_arg("--dialect")
+        .with_arg("ook")
+        .with_arg("Ook! Ook?")
+        .expect_stderr("bfi: '[' at line 1, col 1 has no matching ']'\n1 | Ook! Ook?\n  | ^\n")
+        .expect_retcode(3)
+        .execute();
+}
+
+
+#[test]
+fn test_strict() {
+    TestCase::new()
+        .with_arg("--strict")
+        .with_arg("+++. # 100% done")
+        .expect_stdout("\u{3}")
+        .expect_stderr("")
+        .execute();
+}
+
+
+#[test]
+fn test_charset() {
+    let charset = env::temp_dir().join(format!("bfi-test-charset-{}.toml", std::process::id()));
+    let program = format!("{}Blub!", "Blub.".repeat(65));
+    std::fs::write(&charset, "# Blub\n\"+\" = \"Blub.\"\n\".\" = \"Blub!\"\n").unwrap();
+    TestCase::new()
+        .with_arg("--charset")
+        .with_arg(charset.to_str().unwrap())
+        .with_arg(&program)
+        .expect_stdout("A")
+        .execute();
+    std::fs::write(&charset, "\"+\" = Blub.\n").unwrap();
+    let expected = format!(
+        "bfi: charset '{}' is invalid (line 1: expected a quoted string)\n",
+        charset.display(),
+    );
+    TestCase::new()
+        .with_arg("--charset")
+        .with_arg(charset.to_str().unwrap())
+        .with_arg("Blub.")
+        .expect_stderr(&expected)
+        .expect_retcode(2)
+        .execute();
+    std::fs::remove_file(&charset).unwrap();
+}
+
+
+#[test]
+fn test_extensions() {
+    // store 'A', print it, and end before the rest of the program
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("ext1")
+        .with_arg("++++++++[>++++++++<-]>+$[-]!.@.")
+        .expect_stdout("A")
+        .execute();
+    TestCase::new()
+        .with_arg("--dialect")
+        .with_arg("ook")
+        .with_arg("--extensions")
+        .with_arg("ext1")
+        .with_arg("Ook. Ook.")
+        .expect_stderr("bfi: the ook dialect cannot be extended\n")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_random() {
+    // the same seed gives the same bytes on every run
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("random")
+        .with_arg("--seed")
+        .with_arg("42")
+        .with_arg("--io")
+        .with_arg("numeric")
+        .with_arg("?.?.?.")
+        .expect_stdout("38\n24\n136\n")
+        .execute();
+    // without the extension '?' is a comment
+    TestCase::new()
+        .with_arg("+?.")
+        .expect_stdout("\u{1}")
+        .execute();
+}
+
+
+#[test]
+fn test_multitape() {
+    // 'A' is built on the second tape while the first keeps counting
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("multitape")
+        .with_arg("--tapes")
+        .with_arg("3")
+        .with_arg("+/++++++++[>++++++++<-]>+\\+./.")
+        .expect_stdout("\u{2}A")
+        .execute();
+    TestCase::new()
+        .with_arg("--tapes")
+        .with_arg("0")
+        .with_arg("+")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_tape_backend() {
+    // travels far to the left and right of the start, leaving a few cells in between
+    for backend in &["vec", "deque", "sparse"] {
+        TestCase::new()
+            .with_arg("--tape-backend")
+            .with_arg(backend)
+            .with_arg(concat!(
+                "++++++++[<<<<<<<<+>>>>>>>>>>>>>>>>++++++++<<<<<<<<-]",
+                ">>>>>>>>+.<<<<<<<<<<<<<<<<.",
+            ))
+            .expect_stdout("A\u{8}")
+            .execute();
+    }
+}
+
+
+#[test]
+fn test_grid() {
+    // 'A' is built in the second row while the cell above the counter counts the iterations
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("grid")
+        .with_arg("_++++++++[^+_>++++++++<-]>+^>.<_.")
+        .expect_stdout("\u{0}A")
+        .execute();
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("ext1,grid")
+        .with_arg("+")
+        .expect_stderr("bfi: the ext1 and grid extensions both use '^'\n")
+        .expect_retcode(2)
+        .execute();
+}
+
+
+#[test]
+fn test_clock() {
+    // wait for the clock to tick past the time stored at the start, then print 'A'
+    TestCase::new()
+        .with_arg("--extensions")
+        .with_arg("ext1,time")
+        .with_arg(";$>+[>;^[[-]<->]<]++++++++[>++++++++<-]>+.")
+        .expect_stdout("A")
+        .execute();
+}
+
+
+#[test]
+fn test_brainfork() {
+    // the original thread prints 'a' two cells to the left, the new thread 'b', and goes first
+    TestCase::new()
+        .with_arg("--dialect")
+        .with_arg("brainfork")
+        .with_arg("++++++++[>++++++++++++>++++++++++++<<-]>+>++>Y<<.")
+        .expect_stdout("ba")
+        .execute();
+    TestCase::new()
+        .with_arg("compile")
+        .with_arg("--dialect")
+        .with_arg("brainfork")
+        .with_arg("-")
+        .with_input("Y")
+        .expect_stderr("bfi: compilation failed (forks are not supported by compiled programs)\n")
+        .expect_retcode(5)
+        .execute();
+}
+
+
+#[test]
+fn test_pbrain() {
+    // procedure 1 increments and prints the next cell, called twice with it holding 64
+    TestCase::new()
+        .with_arg("--dialect")
+        .with_arg("pbrain")
+        .with_arg("+(>+.<)>>++++++++[<++++++++>-]<<::")
+        .expect_stdout("AB")
+        .execute();
+    TestCase::new()
+        .with_arg("--dialect")
+        .with_arg("pbrain")
+        .with_arg("--max-call-depth")
+        .with_arg("3")
+        .with_arg("+(:):")
+        .expect_stderr("bfi: exited with error: exceeded the limit of 3 nested procedure calls\n")
+        .expect_retcode(4)
+        .execute();
+}
+
+
+#[test]
+fn test_lsp() {
+    let messages = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":
+            {"uri":"file:///a.b","languageId":"bf","version":1,"text":"+[-]]"}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ];
+    let input: String = messages
+        .iter()
+        .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+        .collect();
+    TestCase::new()
+        .with_arg("lsp")
+        .with_input(&input)
+        .expect_stdout_containing(r#""documentFormattingProvider":true"#)
+        .expect_stdout_containing(r#""message":"']' at line 1, col 5 has no matching '['""#)
+        .expect_stdout_containing(r#"{"jsonrpc":"2.0","id":2,"result":null}"#)
+        .expect_stderr("")
+        .execute();
+    // exiting without a shutdown is a failure
+    TestCase::new()
+        .with_arg("lsp")
+        .with_input("Content-Length: 33\r\n\r\n{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}")
+        .expect_retcode(1)
+        .execute();
+}
+
+
+#[test]
+fn test_dap() {
+    let program = env::temp_dir().join(format!("bfi-test-dap-{}.bf", std::process::id()));
+    std::fs::write(&program, ",+.").unwrap();
+    let launch = format!(
+        r#"{{"seq":2,"type":"request","command":"launch","arguments":
+            {{"program":"{}","input":"a"}}}}"#,
+        program.display(),
+    );
+    let messages = [
+        r#"{"seq":1,"type":"request","command":"initialize","arguments":{"adapterID":"bfi"}}"#,
+        &launch,
+        r#"{"seq":3,"type":"request","command":"configurationDone","arguments":{}}"#,
+        r#"{"seq":4,"type":"request","command":"disconnect","arguments":{}}"#,
+    ];
+    let input: String = messages
+        .iter()
+        .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+        .collect();
+    TestCase::new()
+        .with_arg("dap")
+        .with_input(&input)
+        .expect_stdout_containing(r#""supportsConfigurationDoneRequest":true"#)
+        .expect_stdout_containing(r#""event":"output","body":{"category":"stdout","output":"b"}"#)
+        .expect_stdout_containing(r#""event":"exited","body":{"exitCode":0}"#)
+        .expect_stdout_containing(r#""command":"disconnect","success":true"#)
+        .expect_stderr("")
+        .execute();
+    std::fs::remove_file(&program).unwrap();
+}
+
+
+#[test]
+fn test_completions() {
+    for shell in ["bash", "zsh", "fish"] {
+        TestCase::new()
+            .with_arg("completions")
+            .with_arg(shell)
+            .expect_stdout_containing("serve")
+            .expect_stdout_containing("cell-size")
+            .expect_stderr("")
+            .execute();
+    }
+    TestCase::new()
+        .with_arg("completions")
+        .with_arg("sh")
+        .expect_stderr_containing("isn't a valid value")
+        .expect_retcode(2)
+        .execute();
+}
+
+#[test]
+fn test_serve() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    let mut child_proc = Command::new(&*TestCase::new().executable)
+        .args(["serve", "--port", "0", "--max-steps", "100", "--jobs", "1"])
+        .env("BFI_CONFIG", "")
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to execute");
+    let mut line = String::new();
+    BufReader::new(child_proc.stderr.take().unwrap()).read_line(&mut line).unwrap();
+    let address = line.trim_end().strip_prefix("bfi: serving on http://").unwrap().to_string();
+    let post = |body: &str| {
+        let mut stream = TcpStream::connect(&address).unwrap();
+        write!(stream, "POST /run HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let responses = [post(r#"{"program": ",+.", "input": "a"}"#), post(r#"{"program": "+[]"}"#)];
+    // the only job is taken by a connection yet to send its request
+    let idle = TcpStream::connect(&address).unwrap();
+    let mut busy = String::new();
+    TcpStream::connect(&address).unwrap().read_to_string(&mut busy).unwrap();
+    drop(idle);
+    child_proc.kill().and_then(|()| child_proc.wait()).unwrap();
+    assert!(responses[0].starts_with("HTTP/1.1 200 OK\r\n"), "{}", responses[0]);
+    // "Yg==" is "b" as base64
+    assert!(responses[0].contains(r#"{"status":"terminated","output":"Yg==","error":null"#));
+    assert!(responses[1].contains("exceeded the limit of 100 steps"), "{}", responses[1]);
+    assert!(busy.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{}", busy);
+
+    TestCase::new()
+        .with_arg("serve")
+        .with_arg("--bind")
+        .with_arg("not an address")
+        .expect_stderr_containing("bfi: serve: cannot listen on not an address:8080")
+        .expect_retcode(5)
+        .execute();
+}
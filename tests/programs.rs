@@ -0,0 +1,126 @@
+//! Runs each program of the corpus in `examples/` with known input and checks its output.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use bfi::ioctx::{IoCtx, ReadWriteIoCtx};
+use bfi::{Cell, CellSize, EofBehavior, ExecutionContext, ExecutionStatus, InterpreterConfig};
+
+
+/// Output stream that remains readable once the I/O context holding it has been boxed.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(buf) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+
+fn read_example(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples").join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {:?}: {}", path, e))
+}
+
+
+/// Run `program` to completion with `input`, returning its output.
+fn run<C: Cell>(program: &str, input: &[u8], config: InterpreterConfig) -> Vec<u8> {
+    let output = SharedOutput::default();
+    let streams = ReadWriteIoCtx::new(Cursor::new(input.to_vec()), output.clone());
+    let ictx = RefCell::new(Box::new(streams) as Box<dyn IoCtx>);
+    let status = ExecutionContext::<C>::with_cells(ictx.borrow_mut(), program)
+        .with_config(config)
+        .execute();
+    assert_eq!(status, ExecutionStatus::Terminated);
+    let output = output.0.borrow().clone();
+    output
+}
+
+
+/// Like `run`, checking that the output is the same with and without optimization.
+fn run_both<C: Cell>(program: &str, input: &[u8], config: InterpreterConfig) -> Vec<u8> {
+    let unoptimized = InterpreterConfig { optimize: false, ..config.clone() };
+    let output = run::<C>(program, input, config);
+    assert_eq!(run::<C>(program, input, unoptimized), output, "optimized output differs");
+    output
+}
+
+
+fn eof_zero() -> InterpreterConfig {
+    InterpreterConfig { eof: EofBehavior::Zero, ..InterpreterConfig::default() }
+}
+
+
+#[test]
+fn test_hello() {
+    let output = run_both::<u8>(&read_example("hello.b"), b"", InterpreterConfig::default());
+    assert_eq!(output, b"Hello World!\n");
+}
+
+#[test]
+fn test_rot13() {
+    let program = read_example("rot13.b");
+    let input = b"Hello, World! The quick brown fox jumps over the lazy dog. @[`{ 0123\n";
+    let expected = b"Uryyb, Jbeyq! Gur dhvpx oebja sbk whzcf bire gur ynml qbt. @[`{ 0123\n";
+    assert_eq!(run_both::<u8>(&program, input, InterpreterConfig::default()), expected.to_vec());
+    assert_eq!(run_both::<u8>(&program, expected, eof_zero()), input.to_vec());
+    assert_eq!(run_both::<u8>(&program, b"", InterpreterConfig::default()), b"");
+}
+
+#[test]
+fn test_hanoi() {
+    fn solve(disks: u8, from: char, to: char, via: char, moves: &mut String) {
+        if disks > 0 {
+            solve(disks - 1, from, via, to, moves);
+            moves.push_str(&format!("Move disk {} from {} to {}\n", disks, from, to));
+            solve(disks - 1, via, to, from, moves);
+        };
+    }
+    let mut expected = String::new();
+    solve(10, 'A', 'C', 'B', &mut expected);
+    let output = run_both::<u8>(&read_example("hanoi.b"), b"", InterpreterConfig::default());
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+}
+
+#[test]
+fn test_mandelbrot() {
+    // the picture worked out directly from the fixed point arithmetic of the program, where `u`
+    // and `v` are the point at a scale of sixteen offset by 32 and escape once past 0..=64
+    let palette = b" .,:;-=+*o#%&@";
+    let mut expected = String::new();
+    for cy in (-20..=20).step_by(2) {
+        for cx in -32..8 {
+            let (mut u, mut v, mut shade) = (32, 32, palette[0]);
+            for &next in &palette[1..] {
+                let (h, k) = ((u + 1) / 2 - 16, (v + 1) / 2 - 16);
+                u = (h * h - k * k + 256) / 4 + cx - 32;
+                v = (h * k + 256) / 2 + cy - 96;
+                if !(0..=64).contains(&u) || !(0..=64).contains(&v) {
+                    break;
+                };
+                shade = next;
+            }
+            expected.push(shade as char);
+        }
+        expected.push('\n');
+    }
+    assert_eq!(read_example("mandelbrot.out"), expected);
+    // far too slow to interpret without optimization in a debug build
+    let config = InterpreterConfig { cell_size: CellSize::U16, ..InterpreterConfig::default() };
+    let output = run::<u16>(&read_example("mandelbrot.b"), b"", config);
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+}
+
+#[test]
+fn test_selfinterpreter() {
+    let interpreter = read_example("selfinterpreter.b");
+    let hello = read_example("hello.b") + "!";
+    assert_eq!(run_both::<u8>(&interpreter, hello.as_bytes(), eof_zero()), b"Hello World!\n");
+    let echo = b",[.,]!echoed";
+    assert_eq!(run_both::<u8>(&interpreter, echo, eof_zero()), b"echoed");
+    let nested = b"++++[>++++[>++++>++++<<-]<-]>>+.>++.!";
+    assert_eq!(run_both::<u8>(&interpreter, nested, eof_zero()), b"AB");
+}
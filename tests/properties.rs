@@ -0,0 +1,167 @@
+//! Property tests running randomly generated programs through the interpreter, each reproducible
+//! from the seed reported when it fails. See `fuzz/` for the equivalent fuzz target.
+
+use std::cell::RefCell;
+use std::mem;
+
+use bfi::ioctx::{InMemoryIoCtx, IoCtx};
+use bfi::{BfError, EofBehavior, ExecutionContext, ExecutionStatus, InterpreterConfig, TapeMode};
+
+
+/// Number of programs generated for each property.
+const CASES: u64 = 500;
+
+
+/// Xorshift pseudo-random number generator, good enough to generate programs.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self { Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1) }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniformly distributed number in `0..n`.
+    fn below(&mut self, n: usize) -> usize { (self.next() % n as u64) as usize }
+
+    fn choose<T: Copy>(&mut self, items: &[T]) -> T { items[self.below(items.len())] }
+}
+
+
+/// Random program with balanced brackets, nesting loops up to `depth` deep.
+fn balanced_program(rng: &mut Rng, depth: usize) -> String {
+    let mut program = String::new();
+    for _ in 0..rng.below(24) {
+        match rng.below(10) {
+            0 if depth > 0 => {
+                program.push('[');
+                program.push_str(&balanced_program(rng, depth - 1));
+                program.push(']');
+            },
+            _ => program.push(rng.choose(&['+', '-', '<', '>', '.', ',', '+', '>'])),
+        };
+    }
+    program
+}
+
+
+/// Random program whose brackets may not be balanced, with some comment characters.
+fn any_program(rng: &mut Rng) -> String {
+    (0..rng.below(48)).map(|_| rng.choose(&['+', '-', '<', '>', '.', ',', '[', ']', 'x'])).collect()
+}
+
+
+fn random_input(rng: &mut Rng) -> Vec<u8> {
+    (0..rng.below(8)).map(|_| rng.next() as u8).collect()
+}
+
+
+fn random_config(rng: &mut Rng) -> InterpreterConfig {
+    InterpreterConfig {
+        tape_mode: rng.choose(&[TapeMode::Fixed, TapeMode::Growable, TapeMode::Circular]),
+        tape_size: 1 + rng.below(16),
+        eof: rng.choose(&[EofBehavior::Unchanged, EofBehavior::Zero, EofBehavior::MinusOne]),
+        max_steps: Some(10_000),
+        max_memory: Some(64),
+        ..InterpreterConfig::default()
+    }
+}
+
+
+/// Final state of an execution, with the tape trimmed of the zeros at either end.
+#[derive(Debug)]
+struct Outcome {
+    status: ExecutionStatus<BfError>,
+    output: Vec<u8>,
+    tape: Vec<u8>,
+    tape_len: usize,
+}
+
+fn run(program: &str, input: &[u8], config: InterpreterConfig) -> Outcome {
+    let ictx = RefCell::new(Box::new(InMemoryIoCtx::default()) as Box<dyn IoCtx>);
+    ictx.borrow_mut().write_input(input).unwrap();
+    let (status, tape) = {
+        let mut ectx = ExecutionContext::new(ictx.borrow_mut(), program).with_config(config);
+        let status = ectx.execute();
+        (status, ectx.tape().to_vec())
+    };
+    let mut output = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Ok(n) = ictx.borrow_mut().read_output(&mut buf) {
+        if n == 0 { break };
+        output.extend_from_slice(&buf[..n]);
+    }
+    let start = tape.iter().position(|&c| c != 0).unwrap_or(tape.len());
+    let end = tape.iter().rposition(|&c| c != 0).map_or(start, |i| i + 1);
+    Outcome { status, output, tape: tape[start..end].to_vec(), tape_len: tape.len() }
+}
+
+
+#[test]
+fn test_no_internal_errors() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let program = any_program(&mut rng);
+        let input = random_input(&mut rng);
+        let config = random_config(&mut rng);
+        let outcome = run(&program, &input, config);
+        assert!(
+            !matches!(outcome.status, ExecutionStatus::InternalError(_)),
+            "seed {}: {:?} failed with {:?}", seed, program, outcome.status,
+        );
+    }
+}
+
+#[test]
+fn test_tape_limits() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let program = balanced_program(&mut rng, 3);
+        let input = random_input(&mut rng);
+        let config = random_config(&mut rng);
+        let limit = match config.tape_mode {
+            TapeMode::Growable => config.max_memory.unwrap() / mem::size_of::<u8>(),
+            TapeMode::Fixed | TapeMode::Circular => config.tape_size,
+        };
+        let outcome = run(&program, &input, config);
+        assert!(
+            outcome.tape_len <= limit,
+            "seed {}: {:?} grew the tape to {} cells", seed, program, outcome.tape_len,
+        );
+    }
+}
+
+#[test]
+fn test_optimizer_preserves_behavior() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let program = balanced_program(&mut rng, 3);
+        let input = random_input(&mut rng);
+        let mut config = InterpreterConfig { optimize: false, ..random_config(&mut rng) };
+        // combined moves skip over the positions in between, so may not run off of a fixed tape
+        if config.tape_mode == TapeMode::Fixed {
+            config.tape_mode = TapeMode::Circular;
+        };
+        let optimized = InterpreterConfig { optimize: true, ..config.clone() };
+        let expected = run(&program, &input, config);
+        let actual = run(&program, &input, optimized);
+        // optimized programs take fewer steps, so only programs finishing either way compare
+        if matches!(expected.status, ExecutionStatus::LimitExceeded(_))
+            || matches!(actual.status, ExecutionStatus::LimitExceeded(_))
+        {
+            continue;
+        };
+        assert_eq!(
+            mem::discriminant(&expected.status),
+            mem::discriminant(&actual.status),
+            "seed {}: {:?} ended with {:?} once optimized, not {:?}",
+            seed, program, actual.status, expected.status,
+        );
+        assert_eq!(expected.output, actual.output, "seed {}: output of {:?}", seed, program);
+        assert_eq!(expected.tape, actual.tape, "seed {}: tape of {:?}", seed, program);
+    }
+}